@@ -7,8 +7,10 @@ A framework for simple climate models built it Rust.
 import importlib.metadata
 import warnings
 
-from ._lib import TwoLayerComponentBuilder  # noqa
+from ._lib import TwoLayerComponentBuilder, diagnose_forcing, run_two_layer  # noqa
 from ._lib import __version__ as _lib_version
+from .core import load_scenario  # noqa
+from .runner import run, run_ensemble  # noqa
 
 __version__ = importlib.metadata.version("rscm")
 
@@ -1,8 +1,55 @@
 # This .pyi file and the other .pyi files within this module are handwritten.
 # See https://github.com/PyO3/pyo3/issues/2454 for more information about the ongoing
 # implementation of the automatic generation of .pyi files from rust
-from .core import ComponentBuilder
+from typing import Any
+
+from .components import TwoLayerComponentBuilder as TwoLayerComponentBuilder
+from .core import Timeseries, TimeseriesCollection
 
 __version__: str
 
-class TwoLayerComponentBuilder(ComponentBuilder): ...
+def run_two_layer(
+    erf: Timeseries, parameters: dict[str, Any]
+) -> TimeseriesCollection:
+    """
+    Emulate the temperature response to a prescribed ERF pathway
+
+    A model containing only a two-layer component is built and run on the
+    time axis of `erf`.
+
+    Parameters
+    ----------
+    erf
+        Effective Radiative Forcing timeseries [W / m^2]
+    parameters
+        Parameters for the two-layer component
+
+    Returns
+    -------
+        State of the model after the run,
+        including the "Surface Temperature" timeseries
+    """
+
+def diagnose_forcing(
+    temperature: Timeseries, parameters: dict[str, Any]
+) -> TimeseriesCollection:
+    """
+    Diagnose the ERF implied by a prescribed surface temperature
+
+    The two-layer equations are inverted to find the forcing required to
+    produce the change in surface temperature over each step.
+    Both layers are assumed to start with a temperature of zero.
+
+    Parameters
+    ----------
+    temperature
+        Surface temperature timeseries [K]
+    parameters
+        Parameters for the two-layer component
+
+    Returns
+    -------
+        Collection containing the prescribed "Surface Temperature" and the
+        diagnosed "Effective Radiative Forcing" and "Deep Ocean Temperature".
+        The final value of the ERF is NaN.
+    """
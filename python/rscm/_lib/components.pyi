@@ -1,4 +1,322 @@
 from rscm._lib.core import ComponentBuilder
 
-class CarbonCycleBuilder(ComponentBuilder): ...
-class CO2ERFBuilder(ComponentBuilder): ...
+class BlendedTemperatureBuilder(ComponentBuilder):
+    """Builder for the blended surface air and sea surface temperature diagnostic"""
+
+    def __init__(
+        self,
+        *,
+        land_fraction: float,
+        sea_ice_fraction: float,
+        sst_ratio: float,
+        coverage: float,
+    ):
+        """
+        Parameters
+        ----------
+        land_fraction
+            Fraction of the surface which is land
+        sea_ice_fraction
+            Fraction of the ocean covered by sea ice, where air temperatures are used
+        sst_ratio
+            Change in sea surface temperature relative to the change in air
+            temperature over the ice-free ocean
+        coverage
+            Fraction of the blended warming retained after masking to the coverage
+            of the observational product
+        """
+
+class CarbonCycleBuilder(ComponentBuilder):
+    """Builder for the single-box carbon cycle component"""
+
+    def __init__(self, *, tau: float, conc_pi: float, alpha_temperature: float):
+        """
+        Parameters
+        ----------
+        tau
+            Timescale of the box's response [yr]
+        conc_pi
+            Pre-industrial atmospheric CO2 concentration [ppm]
+        alpha_temperature
+            Sensitivity of lifetime to changes in global-mean temperature [1 / K]
+        """
+
+class CH4ERFBuilder(ComponentBuilder):
+    """Builder for the CH4 effective radiative forcing component"""
+
+    def __init__(  # noqa: PLR0913
+        self,
+        *,
+        a3: float = ...,
+        b3: float = ...,
+        d3: float = ...,
+        conc_pi: float = ...,
+        adjustment: float = ...,
+    ):
+        """
+        Any parameters which aren't provided use the values from Meinshausen et al.
+        (2020) and the AR6 tropospheric adjustment.
+
+        Parameters
+        ----------
+        a3
+            Dependence of the forcing on the CH4 concentration [W / m^2 / ppb]
+        b3
+            Dependence of the forcing on the N2O concentration [W / m^2 / ppb]
+        d3
+            Constant term [W / m^2 / ppb^0.5]
+        conc_pi
+            Pre-industrial atmospheric CH4 concentration [ppb]
+        adjustment
+            Ratio of the effective radiative forcing to the
+            stratospherically-adjusted forcing
+        """
+
+class CO2ERFBuilder(ComponentBuilder):
+    """Builder for the CO2 effective radiative forcing component"""
+
+    def __init__(self, *, erf_2xco2: float, conc_pi: float):
+        """
+        Parameters
+        ----------
+        erf_2xco2
+            ERF due to a doubling of atmospheric CO2 concentrations [W / m^2]
+        conc_pi
+            Pre-industrial atmospheric CO2 concentration [ppm]
+        """
+
+class FaIRCarbonCycleBuilder(ComponentBuilder):
+    """Builder for the FaIR-style multi-box carbon cycle component"""
+
+    def __init__(  # noqa: PLR0913
+        self,
+        *,
+        partition: list[float] = ...,
+        tau: list[float] = ...,
+        r0: float = ...,
+        r_uptake: float = ...,
+        r_temperature: float = ...,
+        r_atmosphere: float = ...,
+        iirf_max: float = ...,
+        iirf_horizon: float = ...,
+        conc_pi: float = ...,
+    ):
+        """
+        Any parameters which aren't provided use the values from FaIR v1.3
+        (with a pre-industrial CO2 concentration of 278 ppm),
+        e.g. `r0` is 35.0 yr.
+
+        Parameters
+        ----------
+        partition
+            Fraction of emissions which enter each of the 4 boxes (should sum to 1)
+        tau
+            Unperturbed decay timescale of each of the 4 boxes [yr]
+        r0
+            Pre-industrial time-integrated airborne fraction [yr]
+        r_uptake
+            Sensitivity of the time-integrated airborne fraction to cumulative
+            uptake of carbon by the land and ocean [yr / GtC]
+        r_temperature
+            Sensitivity of the time-integrated airborne fraction to
+            global-mean temperature [yr / K]
+        r_atmosphere
+            Sensitivity of the time-integrated airborne fraction to the excess
+            atmospheric burden [yr / GtC]
+        iirf_max
+            Maximum value of the time-integrated airborne fraction [yr]
+        iirf_horizon
+            Time horizon over which the airborne fraction is integrated [yr]
+        conc_pi
+            Pre-industrial atmospheric CO2 concentration [ppm]
+        """
+
+class GreensFunctionTemperatureBuilder(ComponentBuilder):
+    """Builder for the Green's function temperature emulator"""
+
+    def __init__(self, *, q: list[float], d: list[float]):
+        """
+        Parameters
+        ----------
+        q
+            Equilibrium temperature response of each mode to a unit ERF [K / (W / m^2)]
+        d
+            Response timescale of each mode [yr]. Must be the same length as `q`
+        """
+
+class InternalVariabilityBuilder(ComponentBuilder):
+    """Builder for the stochastic internal variability component"""
+
+    def __init__(self, *, variance: float, autocorrelation: float):
+        """
+        AR(1) variability is added to "Surface Temperature" and output as
+        "Surface Temperature|With Variability".
+        The random numbers are reproducible for a given seed,
+        see `ModelBuilder.with_rng_seed`.
+
+        Parameters
+        ----------
+        variance
+            Variance of the variability [K^2]
+        autocorrelation
+            Correlation between the variability of consecutive years
+        """
+
+class N2OERFBuilder(ComponentBuilder):
+    """Builder for the N2O effective radiative forcing component"""
+
+    def __init__(  # noqa: PLR0913
+        self,
+        *,
+        a2: float = ...,
+        b2: float = ...,
+        c2: float = ...,
+        d2: float = ...,
+        conc_pi: float = ...,
+        adjustment: float = ...,
+    ):
+        """
+        Any parameters which aren't provided use the values from Meinshausen et al.
+        (2020) and the AR6 tropospheric adjustment.
+
+        Parameters
+        ----------
+        a2
+            Dependence of the forcing on the CO2 concentration
+            [W / m^2 / ppm^0.5 / ppb^0.5]
+        b2
+            Dependence of the forcing on the N2O concentration [W / m^2 / ppb]
+        c2
+            Dependence of the forcing on the CH4 concentration [W / m^2 / ppb]
+        d2
+            Constant term [W / m^2 / ppb^0.5]
+        conc_pi
+            Pre-industrial atmospheric N2O concentration [ppb]
+        adjustment
+            Ratio of the effective radiative forcing to the
+            stratospherically-adjusted forcing
+        """
+
+class OceanCarbonCycleBuilder(ComponentBuilder):
+    """Builder for the mixed-layer ocean carbon cycle component"""
+
+    def __init__(  # noqa: PLR0913
+        self,
+        *,
+        conc_pi: float = ...,
+        gas_exchange_rate: float = ...,
+        mixed_layer_depth: float = ...,
+        ocean_area: float = ...,
+        dic_pi: float = ...,
+        revelle_factor_pi: float = ...,
+        revelle_factor_sensitivity: float = ...,
+        temperature_sensitivity: float = ...,
+        mixing_timescale: float = ...,
+    ):
+        """
+        Any parameters which aren't provided use the defaults, which are based on
+        Joos et al. (2001).
+
+        Parameters
+        ----------
+        conc_pi
+            Pre-industrial atmospheric CO2 concentration [ppm]
+        gas_exchange_rate
+            Rate of air-sea gas exchange per unit difference in partial pressure
+            [GtC / yr / ppm]
+        mixed_layer_depth
+            Depth of the mixed layer [m]
+        ocean_area
+            Surface area of the ocean [m^2]
+        dic_pi
+            Pre-industrial dissolved inorganic carbon in the mixed layer
+            [micromol / kg]
+        revelle_factor_pi
+            Pre-industrial Revelle (buffer) factor
+        revelle_factor_sensitivity
+            Increase in the Revelle factor with dissolved inorganic carbon
+            [kg / micromol]
+        temperature_sensitivity
+            Sensitivity of the partial pressure to warming [1 / K]
+        mixing_timescale
+            Timescale of the export of dissolved inorganic carbon into the deep
+            ocean [yr]
+        """
+
+class OceanSurfacePartialPressureBuilder(ComponentBuilder):
+    """Builder for the ocean surface partial pressure component"""
+
+    def __init__(
+        self,
+        *,
+        ospp_preindustrial: float,
+        sensitivity_ospp_to_temperature: float,
+        sea_surface_temperature_preindustrial: float,
+        delta_ospp_offsets: list[float],
+        delta_ospp_coefficients: list[float],
+    ):
+        """
+        Parameters
+        ----------
+        ospp_preindustrial
+            Pre-industrial ocean surface partial pressure [ppm]
+        sensitivity_ospp_to_temperature
+            Sensitivity of the ocean surface's partial pressure to changes in
+            sea surface temperature relative to pre-industrial [1 / delta_degC]
+        sea_surface_temperature_preindustrial
+            Pre-industrial sea surface temperature [degC]
+        delta_ospp_offsets
+            Offsets used when calculating the change in ocean surface
+            partial pressure (length 5) [ppm]
+        delta_ospp_coefficients
+            Coefficients (applied to pre-industrial sea surface temperatures)
+            used when calculating the change in ocean surface partial
+            pressure (length 5) [ppm / delta_degC]
+        """
+
+class PatternScalingBuilder(ComponentBuilder):
+    """Builder for the pattern scaling regional temperature component"""
+
+    def __init__(self, *, regions: list[str], patterns: list[float]):
+        """
+        Regional temperatures are output as the labelled variable
+        "Surface Temperature|Regional", e.g. "Surface Temperature|Regional|Land".
+
+        Parameters
+        ----------
+        regions
+            Name of each region, used as the labels of the regional temperature
+        patterns
+            Change in the temperature of each region per unit change in
+            global-mean temperature [K / K]
+        """
+
+class TwoLayerComponentBuilder(ComponentBuilder):
+    """Builder for the two-layer energy balance component"""
+
+    def __init__(  # noqa: PLR0913
+        self,
+        *,
+        lambda0: float,
+        a: float,
+        efficacy: float,
+        eta: float,
+        heat_capacity_surface: float,
+        heat_capacity_deep: float,
+    ):
+        """
+        Parameters
+        ----------
+        lambda0
+            Climate feedback parameter at zero warming [W / m^2 / K]
+        a
+            Change in the feedback parameter with warming [W / m^2 / K^2]
+        efficacy
+            Efficacy of deep-ocean heat uptake [dimensionless]
+        eta
+            Heat transfer coefficient between the layers [W / m^2 / K]
+        heat_capacity_surface
+            Heat capacity of the surface layer [W yr / m^2 / K]
+        heat_capacity_deep
+            Heat capacity of the deep layer [W yr / m^2 / K]
+        """
@@ -1,5 +1,7 @@
+import os
+from collections.abc import Callable
 from enum import Enum, auto
-from typing import Protocol, Self, TypeVar
+from typing import Any, Protocol, Self, TypeVar
 
 import numpy as np
 from numpy.typing import NDArray
@@ -10,29 +12,216 @@ T = TypeVar("T")
 Arr = NDArray[np.float64]
 F = np.float64 | float
 
+class Calendar(Enum):
+    """Calendar used to convert decimal years into dates"""
+
+    ProlepticGregorian = auto()
+    NoLeap = auto()
+
 class TimeAxis:
     @staticmethod
-    def from_values(values: Arr) -> TimeAxis: ...
+    def from_values(values: Arr | Any) -> TimeAxis:
+        """
+        Time axis with a time step starting at each value
+
+        The last time step has the same length as the previous step.
+        Dates, e.g. a pandas DatetimeIndex or numpy datetime64 array,
+        are converted into decimal years.
+
+        Raises
+        ------
+        ValueError
+            The values aren't strictly increasing
+        RuntimeError
+            Fewer than 2 values are provided
+        """
+    @staticmethod
+    def from_bounds(values: Arr) -> TimeAxis:
+        """
+        Time axis with time steps between consecutive bounds
+
+        Raises
+        ------
+        ValueError
+            The bounds aren't strictly increasing
+        RuntimeError
+            Fewer than 2 bounds are provided
+        """
+    @staticmethod
+    def piecewise(periods: list[tuple[F, F, F]]) -> TimeAxis:
+        """
+        Time axis made of contiguous periods, each with its own time step
+
+        Each period must be a whole number of time steps.
+
+        Parameters
+        ----------
+        periods
+            The start, end and time step of each period,
+            e.g. `[(1750.0, 1850.0, 10.0), (1850.0, 2100.0, 1.0)]`
+
+        Returns
+        -------
+            Time axis from the start of the first period to the end of the last
+
+        Raises
+        ------
+        RuntimeError
+            The periods aren't contiguous, don't have a positive length and step
+            or aren't a whole number of time steps
+        """
+    @staticmethod
+    def monthly(
+        start_year: int,
+        end_year: int,
+        calendar: Calendar = Calendar.ProlepticGregorian,
+    ) -> TimeAxis:
+        """
+        Time axis with a time step for each month
+
+        Parameters
+        ----------
+        start_year
+            First year of the time axis
+        end_year
+            Last year of the time axis (inclusive)
+        calendar
+            Calendar used to convert dates into decimal years
+
+        Returns
+        -------
+            Time axis from the start of `start_year` to the end of `end_year`
+        """
     @staticmethod
-    def from_bounds(values: Arr) -> TimeAxis: ...
+    def daily(
+        start_year: int,
+        end_year: int,
+        calendar: Calendar = Calendar.ProlepticGregorian,
+    ) -> TimeAxis:
+        """
+        Time axis with a time step for each day
+
+        Parameters
+        ----------
+        start_year
+            First year of the time axis
+        end_year
+            Last year of the time axis (inclusive)
+        calendar
+            Calendar used to convert dates into decimal years
+
+        Returns
+        -------
+            Time axis from the start of `start_year` to the end of `end_year`
+        """
     def values(self): ...
     def bounds(self): ...
     def __len__(self) -> int: ...
     def at(self, index: int) -> F: ...
     def at_bounds(self, index: int) -> tuple[F, F]: ...
+    def labels(
+        self,
+        calendar: Calendar = Calendar.ProlepticGregorian,
+        include_day: bool = False,
+    ) -> list[str]:
+        """
+        ISO 8601 labels for the start of each time step
+
+        The labels can be converted to numpy datetimes using
+        `np.asarray(labels, dtype="datetime64[M]")`.
+
+        Parameters
+        ----------
+        calendar
+            Calendar used to convert decimal years into dates
+        include_day
+            If True, labels include the day of the month (e.g. "2000-07-02"),
+            otherwise only the year and month are included (e.g. "2000-07")
+
+        Returns
+        -------
+            A label for each time step
+        """
 
 class InterpolationStrategy(Enum):
     Linear = auto()
     Next = auto()
     Previous = auto()
 
+class ExtrapolationPolicy:
+    """
+    Policy for determining values outside the time domain of a timeseries
+
+    The policy is applied the same way for each interpolation strategy.
+    """
+
+    @staticmethod
+    def error() -> ExtrapolationPolicy:
+        """Raise an error if a value outside the time domain is requested"""
+    @staticmethod
+    def extrapolate() -> ExtrapolationPolicy:
+        """Extrapolate using the interpolation strategy"""
+    @staticmethod
+    def hold_nearest() -> ExtrapolationPolicy:
+        """Use the value at the nearest end of the time domain"""
+    @staticmethod
+    def clamp_to_range(min: F, max: F) -> ExtrapolationPolicy:
+        """Extrapolate using the interpolation strategy and clip the result to [min, max]"""
+
 class Timeseries:
     def __init__(
         self, values: Arr, time_axis: TimeAxis, units: str, interpolation_strategy
     ) -> Timeseries: ...
-    def with_interpolation_strategy(self, interpolation_strategy) -> Timeseries: ...
+    def with_interpolation_strategy(
+        self,
+        interpolation_strategy: InterpolationStrategy,
+        extrapolation: ExtrapolationPolicy | None = None,
+    ) -> Timeseries: ...
+    @staticmethod
+    def from_csv(csv: str) -> Timeseries:
+        """
+        Read a timeseries from CSV
+
+        The CSV contains a `time,value` header followed by one row per time step.
+        Optional `# units: ...`, `# interpolation: ...` and `# end: ...` comment lines
+        before the header set the units, interpolation strategy
+        and the end of the final time step.
+        Empty and `NaN` values are read as missing values.
+
+        Raises
+        ------
+        RuntimeError
+            The CSV couldn't be parsed
+        """
+    def to_csv(self) -> str:
+        """
+        Write the timeseries as CSV
+
+        The units, interpolation strategy and end of the final time step are included
+        as comment lines so that `Timeseries.from_csv` reproduces the timeseries.
+        """
     def __len__(self) -> int: ...
-    def set(self, index: int, value: float): ...
+    def set(self, index: int, value: float):
+        """
+        Set the value at an index
+
+        Raises
+        ------
+        IndexError
+            The index is out of bounds
+        """
+    def __setitem__(self, key: int | slice, value: float | Arr) -> None:
+        """
+        Set a value using an index or contiguous values using a slice
+
+        A slice can be assigned either an array of the same length or a single value.
+        """
+    def assign_from(self, other: Timeseries) -> None:
+        """
+        Copy the values of another timeseries with the same time axis
+
+        The values are converted into the units of this timeseries.
+        """
     def values(self) -> Arr: ...
     @property
     def latest(self) -> int: ...
@@ -42,6 +231,13 @@ class Timeseries:
     def time_axis(self) -> TimeAxis: ...
     def latest_value(self) -> F | None: ...
     def at(self, index: int) -> F: ...
+    def summary(self) -> str:
+        """
+        Single line description of the timeseries
+
+        Includes the units, the range of the time axis,
+        the first and last values and the number of NaN values.
+        """
     def at_time(self, time: F) -> F:
         """
         Interpolates a value for a given time using the current interpolation strategy.
@@ -63,17 +259,185 @@ class Timeseries:
         Interpolated value
 
         """
+    def slice(self, start: F, end: F) -> Timeseries:
+        """
+        Get the subset of the timeseries within a time window
+
+        Parameters
+        ----------
+        start
+            Start of the window (inclusive)
+        end
+            End of the window (inclusive)
+
+        Raises
+        ------
+        RuntimeError
+            No values are within the window
+
+        Returns
+        -------
+        A copy of the values within the window with a truncated time axis
+        """
+    def convert_unit(self, unit: str, contexts: list[str] = []) -> Timeseries:  # noqa: B006
+        """
+        Convert the values of the timeseries into different units
+
+        Parameters
+        ----------
+        unit
+            Target units
+        contexts
+            Unit contexts which enable conversions between dimensions,
+            e.g. "CO2" for conversions between GtC and GtCO2
+
+        Raises
+        ------
+        RuntimeError
+            The units can't be converted
+        """
+    def disaggregate_into(
+        self,
+        time_axis: TimeAxis,
+        strategy: DisaggregationStrategy = DisaggregationStrategy.Interpolate,
+    ) -> Timeseries:
+        """
+        Convert the timeseries onto a time axis with shorter time steps
+
+        Parameters
+        ----------
+        time_axis
+            New time axis
+        strategy
+            How the value of each time step is distributed between the new time steps
+        """
+    def regrid(
+        self,
+        time_axis: TimeAxis,
+        method: RegridMethod = RegridMethod.Interpolate,
+    ) -> Timeseries:
+        """
+        Convert the timeseries onto a new time axis
+
+        The new time axis can have longer or shorter time steps than the timeseries.
+
+        Parameters
+        ----------
+        time_axis
+            New time axis
+        method
+            How the values of the new time steps are calculated
+
+        Raises
+        ------
+        RuntimeError
+            The timeseries doesn't cover the new time axis
+        """
+    def integrate(self) -> Timeseries:
+        """
+        Cumulative integral of the timeseries over time
+
+        The integral of each time step is exact for the interpolation strategy
+        of the timeseries.
+        Rates are per year so the units are multiplied by "yr",
+        e.g. "GtC / yr" becomes "GtC".
+
+        Returns
+        -------
+        Integral from the start of the time axis to the start of each time step
+        """
+    def differentiate(self) -> Timeseries:
+        """
+        Rate of change of the timeseries over each time step
+
+        The units are divided by "yr", e.g. "GtC" becomes "GtC / yr".
+
+        Raises
+        ------
+        RuntimeError
+            The end of the final time step requires extrapolation
+            which isn't allowed by the interpolation strategy
+
+        Returns
+        -------
+        Rates which are constant over each time step
+        """
 
 class VariableType(Enum):
     Exogenous = auto()
     Endogenous = auto()
 
+class Precision(Enum):
+    """Precision used to store values in an archive"""
+
+    Float64 = auto()
+    """Store values exactly"""
+    Float32 = auto()
+    """Round values to single precision"""
+
+class Compression(Enum):
+    """Compression applied to an archive"""
+
+    Uncompressed = auto()
+    """Write plain TOML"""
+    Zstd = auto()
+    """Compress the TOML using zstd"""
+
 class TimeseriesCollection:
     def __init__(self) -> TimeseriesCollection: ...
+    def summary(self) -> str:
+        """
+        Description of the collection with a line per timeseries
+        """
+    def _repr_html_(self) -> str:
+        """
+        HTML table of the timeseries for notebooks
+
+        Includes the units, time range, first and last values
+        and a compact plot of the values of each timeseries.
+        """
+    def to_pandas(self) -> Any:
+        """
+        Convert to a pandas DataFrame with a row per timeseries
+
+        The index has "variable" and "unit" levels and the columns are the times.
+        Requires pandas to be installed.
+        """
     def add_timeseries(
-        self, name: str, timeseries: Timeseries, variable_type: VariableType
-    ): ...
-    def get_timeseries_by_name(self, name: str) -> Timeseries | None:
+        self,
+        name: str,
+        timeseries: Timeseries,
+        variable_type: VariableType,
+        run: tuple[str, str] | None = None,
+        overwrite: bool = False,
+    ):
+        """
+        Add a timeseries to the collection
+
+        A clone of the timeseries is stored in the collection.
+
+        Parameters
+        ----------
+        name
+            Name of the timeseries
+        timeseries
+            Timeseries to add
+        variable_type
+            Type of variable
+        run
+            (run_id, scenario) of the run that the timeseries belongs to
+        overwrite
+            If True, replace any existing timeseries with the same name and run
+
+        Raises
+        ------
+        ValueError
+            A timeseries with the same name and run already exists
+            and `overwrite` is False
+        """
+    def get_timeseries_by_name(
+        self, name: str, run: tuple[str, str] | None = None
+    ) -> Timeseries | None:
         """
         Get a timeseries from the collection by name
 
@@ -84,13 +448,82 @@ class TimeseriesCollection:
         ----------
         name
             Name of the timeseries to query
+        run
+            (run_id, scenario) of the run to query.
+
+            If not provided, the timeseries without a run is preferred.
 
         Returns
         -------
         A clone of the timeseries or None if the collection doesn't contain
         a timeseries by that name.
         """
+    def with_run(self, run_id: str, scenario: str) -> TimeseriesCollection:
+        """
+        Create a copy of the collection where the timeseries belong to a run
+
+        Timeseries that already belong to a run are unchanged.
+        """
+    def merge(self, other: TimeseriesCollection, overwrite: bool = False):
+        """
+        Copy the timeseries from another collection into this collection
+
+        Raises
+        ------
+        ValueError
+            A timeseries with the same name and run already exists
+            and `overwrite` is False
+        """
+    def runs(self) -> list[tuple[str, str]]:
+        """Get the unique (run_id, scenario) pairs in the collection"""
     def names(self) -> list[str]: ...
+    def filter(self, pattern: str) -> TimeseriesCollection:
+        """
+        Select the timeseries whose names match a pattern
+
+        Parameters
+        ----------
+        pattern
+            Glob pattern, e.g. "Emissions|*".
+
+            `*` matches any sequence of characters, including "|",
+            and `?` matches any single character.
+
+        Returns
+        -------
+        New collection containing clones of the matching timeseries for every run
+        """
+    def remove(self, name: str):
+        """
+        Remove the timeseries with a given name for every run
+
+        Raises
+        ------
+        KeyError
+            The collection doesn't contain a timeseries with the name
+        """
+    def rename(self, name: str, new_name: str):
+        """
+        Rename the timeseries with a given name for every run
+
+        Raises
+        ------
+        KeyError
+            The collection doesn't contain a timeseries named `name`
+        ValueError
+            A timeseries named `new_name` already exists
+        """
+    def metadata(
+        self,
+    ) -> list[tuple[str, str, VariableType, tuple[str, str] | None]]:
+        """
+        Describe each timeseries without its values
+
+        Returns
+        -------
+        (name, units, variable_type, run) of each timeseries,
+        where run is the (run_id, scenario) or None
+        """
     def timeseries(self) -> list[Timeseries]:
         """
         Get a list of timeseries stored in the collection.
@@ -102,48 +535,266 @@ class TimeseriesCollection:
         -------
         List of timeseries
         """
+    def convert_units(
+        self,
+        units: dict[str, str],
+        contexts: list[str] = [],  # noqa: B006
+    ) -> TimeseriesCollection:
+        """
+        Convert variables into preferred units
+
+        Parameters
+        ----------
+        units
+            Units to convert each variable into, by name.
+
+            Variables which aren't included are unchanged.
+        contexts
+            Unit contexts which enable conversions between dimensions,
+            e.g. "CO2" for conversions between GtC and GtCO2
+
+        Raises
+        ------
+        RuntimeError
+            A variable can't be converted into the requested units
+
+        Returns
+        -------
+            Collection with the converted timeseries
+        """
+    def save(
+        self,
+        precision: Precision = Precision.Float64,
+        units: dict[str, str] = {},  # noqa: B006
+        contexts: list[str] = [],  # noqa: B006
+        compression: Compression = Compression.Uncompressed,
+    ) -> bytes:
+        """
+        Write the collection as an archive
+
+        The precision and compression used to store the values are recorded in the
+        archive metadata.
+
+        Parameters
+        ----------
+        precision
+            Precision used to store values.
+
+            `Precision.Float32` rounds values to single precision
+            which reduces the size of large archives.
+        units
+            Units to write each variable in, by name (see `convert_units`)
+        contexts
+            Unit contexts used when converting variables into `units`
+        compression
+            Compression applied to the archive
+
+        Raises
+        ------
+        RuntimeError
+            A variable can't be converted into the requested units
+        """
+    @staticmethod
+    def load(archive: bytes) -> TimeseriesCollection:
+        """
+        Read a collection written by `save`
+
+        Values written at `Precision.Float32` are read as single precision floats.
+
+        Raises
+        ------
+        RuntimeError
+            The archive is invalid or was written using a different format version
+        """
 
 class RequirementType(Enum):
     Input = auto()
     Output = auto()
     InputAndOutput = auto()
 
-class RequirementDefinition:
-    name: str
-    units: str
-    requirement_type: RequirementType
+class NanPolicy(Enum):
+    """
+    How NaN values produced by components are handled
 
-    def __init__(self, name: str, units: str, requirement_type: RequirementType): ...
+    The same policy applies to values outside of the bounds declared by the component
+    which produces them.
+    """
 
-class Component(Protocol):
-    """A component of the model that can be solved"""
+    Allow = auto()
+    Warn = auto()
+    Error = auto()
 
-    def definitions(self) -> list[RequirementDefinition]: ...
-    def solve(
-        self, t_current: float, t_next: float, input_state: dict[str, float]
-    ) -> dict[str, float]: ...
+class WarningKind(Enum):
+    """Category of a warning recorded while building or running a model"""
 
-class RustComponent(Component):
+    MissingExogenousData = auto()
+    IncompatibleUnits = auto()
+    NanValues = auto()
+    OutOfBounds = auto()
+    ComponentFailed = auto()
+    RunAborted = auto()
+    LossyResampling = auto()
+    MassBalanceViolation = auto()
+    OutputFailed = auto()
+
+class ComponentError(RuntimeError):
     """
-    Component that has been defined in Rust
+    A component failed to solve a step
+
+    Raised by `Model.try_step` and `Model.try_run`.
     """
 
-class CustomComponent(Protocol):
+    component: str
+    """Name of the component which failed"""
+    time_index: int
+    """Index of the time step which failed"""
+    time: float
+    """Start of the time step which failed"""
+
+class BuildError(ValueError):
     """
-    Interface required for registering Python-based component
+    A model couldn't be built because of problems with its inputs
 
-    See Also
-    --------
-    UserDefinedComponent
+    Raised by `ModelBuilder.try_build`.
     """
 
-    def definitions(self) -> list[RequirementDefinition]: ...
-    def solve(
-        self, t_current: float, t_next: float, input_state: dict[str, float]
+    problems: list[str]
+    """Description of each problem, e.g. a missing exogenous variable"""
+
+class RSCMWarning(UserWarning):
+    """Base class of model warnings"""
+
+class MissingExogenousDataWarning(RSCMWarning):
+    """Exogenous data required by a component wasn't provided"""
+
+class IncompatibleUnitsWarning(RSCMWarning):
+    """Exogenous data has units which can't be converted"""
+
+class NanValuesWarning(RSCMWarning):
+    """A component produced NaN values"""
+
+class ComponentFailedWarning(RSCMWarning):
+    """A component failed to solve a step"""
+
+class RunAbortedWarning(RSCMWarning):
+    """A run was stopped before reaching the end of the time axis"""
+
+class LossyResamplingWarning(RSCMWarning):
+    """Exogenous data has time steps which don't align with the model's time steps"""
+
+class MassBalanceViolationWarning(RSCMWarning):
+    """A conserved quantity didn't balance after a step"""
+
+class OutOfBoundsWarning(RSCMWarning):
+    """A component produced values outside of their declared bounds"""
+
+class OutputFailedWarning(RSCMWarning):
+    """Values couldn't be written to an output sink"""
+
+class DisaggregationStrategy(Enum):
+    """How values are converted onto a time axis with shorter time steps"""
+
+    Interpolate = auto()
+    """Interpolate using the timeseries' interpolation strategy"""
+    Repeat = auto()
+    """Repeat the value of the step containing the start of each new step"""
+    Split = auto()
+    """Split the value of each step between the new steps in proportion to their length"""
+
+class RegridMethod(Enum):
+    """How values are converted onto a new time axis"""
+
+    Interpolate = auto()
+    """Interpolate the value at the start of each new step"""
+    Conservative = auto()
+    """Average the values over each new step, preserving the integral over time"""
+
+class RequirementDefinition:
+    name: str
+    units: str
+    requirement_type: RequirementType
+    default: float | None
+    """
+    Value used if no component or exogenous data provides this input
+
+    If None, the input must be provided.
+    """
+    disaggregation: DisaggregationStrategy
+    """
+    How exogenous data is converted if it has longer time steps than the model
+    """
+    labels: list[str]
+    """
+    Labels of a labelled variable, e.g. regions
+
+    Each label is stored as a separate timeseries named `"{name}|{label}"`.
+    Empty for scalar variables.
+    """
+    min: float | None
+    """
+    Smallest physically meaningful value, e.g. 0 for a concentration
+
+    Outputs outside of their bounds are handled according to the model's `NanPolicy`.
+    """
+    max: float | None
+    """Largest physically meaningful value, e.g. 1 for a fraction"""
+
+    def __init__(  # noqa: PLR0913
+        self,
+        name: str,
+        units: str,
+        requirement_type: RequirementType,
+        default: float | None = None,
+        disaggregation: DisaggregationStrategy = DisaggregationStrategy.Interpolate,
+        labels: list[str] = [],  # noqa: B006
+        min: float | None = None,  # noqa: A002
+        max: float | None = None,  # noqa: A002
+    ): ...
+
+class Component(Protocol):
+    """A component of the model that can be solved"""
+
+    def definitions(self) -> list[RequirementDefinition]: ...
+    def solve(
+        self, t_current: float, t_next: float, input_state: dict[str, float]
+    ) -> dict[str, float]: ...
+    def initialise(self, t0: float) -> dict[str, float]:
+        """
+        Initial values of the state variables solved by this component
+
+        These are used when a model is built unless initial values are provided
+        using `ModelBuilder.with_initial_values`.
+        """
+
+class RustComponent(Component):
+    """
+    Component that has been defined in Rust
+    """
+
+class CustomComponent(Protocol):
+    """
+    Interface required for registering Python-based component
+
+    Components may also define an `initialise(t0)` method which returns
+    the initial values of their state variables at `t0`.
+
+    See Also
+    --------
+    UserDefinedComponent
+    """
+
+    def definitions(self) -> list[RequirementDefinition]: ...
+    def solve(
+        self, t_current: float, t_next: float, input_state: dict[str, float]
     ) -> dict[str, float]: ...
 
 class ComponentBuilder(Protocol):
-    """A component of the model that can be solved"""
+    """
+    A component of the model that can be solved
+
+    Builders can be created from keyword arguments or from a dictionary
+    using `from_parameters`.
+    """
 
     @classmethod
     def from_parameters(cls: type[T], parameters: dict[str, F]) -> T:
@@ -154,6 +805,29 @@ class ComponentBuilder(Protocol):
         -------
         Builder that can create a Component
         """
+    @classmethod
+    def from_run(cls: type[T], path: str | os.PathLike, component_name: str) -> T:
+        """
+        Create a builder object using the parameters from a previous run
+
+        Parameters
+        ----------
+        path
+            Path to a model serialised using `Model.to_toml`
+        component_name
+            Name given to the component when it was added to the model,
+            otherwise the name of the component's type, e.g. "CO2ERF"
+
+        Raises
+        ------
+        RuntimeError
+            If the run can't be read or doesn't contain exactly one
+            component with this name
+
+        Returns
+        -------
+        Builder that can create a Component identical to the one in the run
+        """
     def build(self) -> RustComponent:
         """
         Create a concrete component
@@ -183,15 +857,275 @@ class ModelBuilder:
 
     def __init__(self): ...
     def with_time_axis(self, time_axis: TimeAxis) -> Self: ...
-    def with_py_component(self, component: PythonComponent) -> Self: ...
-    def with_rust_component(self, component: RustComponent) -> Self: ...
+    def with_py_component(
+        self,
+        component: PythonComponent,
+        group: str | None = None,
+        name: str | None = None,
+        variables: dict[str, str] | None = None,
+        namespace: str | None = None,
+    ) -> Self: ...
+    def with_rust_component(
+        self,
+        component: RustComponent,
+        group: str | None = None,
+        name: str | None = None,
+        variables: dict[str, str] | None = None,
+        namespace: str | None = None,
+    ) -> Self:
+        """
+        Add a component that is defined in rust
+
+        Parameters
+        ----------
+        component
+            Component to add
+        group
+            Name of the group the component belongs to.
+            Components in disabled groups are not included in the model.
+        name
+            Name used to refer to the component in errors, warnings, graphs
+            and provenance.
+            Defaults to the name of the component's type.
+            Names must be unique within a model.
+        variables
+            Names of the component's variables in the model, by the name used
+            by the component, e.g. `{"Carbon Uptake": "Carbon Uptake|Land"}`.
+            Used to add multiple instances of the same type of component,
+            as each variable can only be solved by one component.
+        namespace
+            Prefix added to the names of all of the component's outputs,
+            e.g. "Land" renames "Carbon Uptake" to "Land|Carbon Uptake".
+            Inputs are only renamed if they are in `variables`.
+            The component is named after the namespace unless `name` is provided.
+        """
+    def with_group_enabled(self, group: str, enabled: bool) -> Self:
+        """
+        Enable or disable a named group of components
+
+        Groups are enabled by default.
+        Components in disabled groups are not included when the model is built.
+        """
+    def with_group_toggles_toml(self, config: str) -> Self:
+        """
+        Enable or disable groups of components from a TOML configuration
+
+        Parameters
+        ----------
+        config
+            TOML containing a `groups` table mapping group names to a boolean,
+            e.g. `[groups]\naerosols = false`
+        """
+    def with_stability_options(
+        self,
+        max_relative_change: float,
+        absolute_tolerance: float = 0.0,
+        max_refinements: int = 4,
+    ) -> Self:
+        """
+        Detect numerical instability and retry unstable steps
+
+        An output is unstable if it isn't finite or if it changes by more than
+        `absolute_tolerance + max_relative_change * abs(previous value)` over a step.
+        Unstable steps are retried with the internal solver step of the component
+        halved, up to `max_refinements` times, before the step fails.
+
+        Steps which required refinement are available via `Model.refinements`.
+        """
+    def with_observer(self, callback: Callable[[dict[str, Any]], None]) -> Self:
+        """
+        Call `callback` with each event of the model
+
+        Events are dictionaries with a `kind` of "BuildStarted",
+        "StepCompleted", "ComponentFailed" or "RunFinished"
+        along with the details of the event, e.g. the `time` of a step.
+        Exceptions raised by the callback are logged and ignored.
+        """
+    def with_output_variables(self, patterns: list[str]) -> Self:
+        """
+        Only store the variables which match one of a set of glob patterns
+
+        `*` matches any characters, including the `|` separating namespaces,
+        e.g. "Effective Radiative Forcing|*".
+        Other variables are still solved, but their values are discarded
+        to reduce the memory used by large ensembles.
+        Exogenous data and variables required by a component are always stored.
+        """
+    def with_sensitivity(self, parameter: str) -> Self:
+        """
+        Calculate the sensitivity of every variable to a component parameter
+
+        Derivatives are propagated through the run using dual numbers.
+        Only components implemented in Rust which support dual numbers
+        can be differentiated. Variables which depend on other components
+        have NaN sensitivities.
+
+        The sensitivities are available from `Model.sensitivity`.
+        """
+    def with_rng_seed(self, seed: int) -> Self:
+        """
+        Seed the random number streams of the components
+
+        Each component draws from a stream derived from the seed,
+        the ensemble member and the name of the component,
+        so stochastic components are reproducible across runs
+        and adding or removing components doesn't change the random numbers
+        of the others.
+        """
+    def with_ensemble_member(self, member: int) -> Self:
+        """
+        Set the index of the model in an ensemble
+
+        Each member of an ensemble with the same seed draws different random numbers.
+        """
+    def with_scenario(self, scenario: str) -> Self:
+        """
+        Set the name of the scenario recorded in the model's metadata
+        """
+    def with_parameters(self, parameters: dict[str, float]) -> Self:
+        """
+        Record the values of the parameters used to build the model in its metadata
+
+        The parameters are only recorded, they aren't applied to the components.
+        """
+    def with_version(self, name: str, version: str) -> Self:
+        """
+        Record the version of a package used to build the model in its metadata
+
+        The version of rscm-core is always recorded.
+        """
+    def with_options(  # noqa: PLR0913
+        self,
+        nan_policy: NanPolicy = NanPolicy.Allow,
+        extrapolation: ExtrapolationPolicy | None = None,
+        solver_step: float = 0.1,
+        threads: int = 1,
+        strict: bool = False,
+        unit_contexts: list[str] = [],  # noqa: B006
+        parallel_components: bool = False,
+    ) -> Self:
+        """
+        Set the options which apply to the whole model
+
+        Any stability options, run limits, sensitivities and random number seeds
+        which have already been set are kept.
+
+        Parameters
+        ----------
+        nan_policy
+            How NaN values produced by components are handled
+        extrapolation
+            Extrapolation policy of the timeseries created by the model.
+            Defaults to `ExtrapolationPolicy.extrapolate()`
+        solver_step
+            Default step size for solvers internal to components [yr]
+        threads
+            Maximum number of threads used when running independent models,
+            e.g. for attribution, or solving independent components
+        strict
+            Fail when building a model with missing exogenous data
+            instead of printing a warning
+        unit_contexts
+            Unit contexts which may be used when converting values passed
+            between components
+        parallel_components
+            Solve components which don't depend on each other concurrently
+            using up to `threads` threads.
+            The results are identical to solving the components one at a time.
+        """
+    def with_run_limits(
+        self,
+        max_wall_time: float | None = None,
+        max_steps: int | None = None,
+    ) -> Self:
+        """
+        Abort runs which exceed a time or step budget
+
+        Parameters
+        ----------
+        max_wall_time
+            Maximum wall-clock time of a run in seconds
+        max_steps
+            Maximum number of time steps in a run
+        """
     def with_initial_values(self, input_state: dict[str, F]) -> Self: ...
-    def with_exogenous_variable(self, name: str, timeseries: Timeseries) -> Self: ...
+    def with_exogenous_variable(
+        self,
+        name: str,
+        values: Timeseries | Arr | Any,
+        times: Arr | None = None,
+        unit: str | None = None,
+    ) -> Self:
+        """
+        Add exogenous data for a variable
+
+        Parameters
+        ----------
+        name
+            Name of the variable
+        values
+            Data for the variable. One of:
+
+            - a Timeseries
+            - an array of values, in which case `times` must be provided
+            - a pandas Series with a time index
+            - a pandas DataFrame with a time index
+              and either a column named `name` or a single column
+            - an scmdata run containing a single timeseries
+
+            Dates in a time index are converted into decimal years.
+        times
+            Start of the time step of each value if `values` is an array
+        unit
+            Units of an array or pandas object, which must be provided for these.
+
+            A Timeseries or scmdata run is converted into these units.
+
+        Raises
+        ------
+        TypeError
+            `values` is an array and `times` isn't provided,
+            or `unit` isn't provided for an array or pandas object
+        ValueError
+            The data can't be converted into a timeseries
+        """
     def with_exogenous_collection(self, timeseries: TimeseriesCollection) -> Self: ...
+    def run_attribution(
+        self, target: str, groups: dict[str, list[str]]
+    ) -> tuple[Timeseries, dict[str, Timeseries]]:
+        """
+        Attribute changes in a variable to groups of forcings
+
+        The model is run with all forcings and then re-run once per group
+        with the exogenous variables in that group set to zero.
+        The contribution of a group is the difference between these runs.
+
+        Parameters
+        ----------
+        target
+            Name of the variable to attribute, e.g. "Surface Temperature"
+        groups
+            Exogenous variables to zero out for each forcing group,
+            e.g. `{"aerosols": ["Effective Radiative Forcing|Aerosols"]}`
+
+        Raises
+        ------
+        RuntimeError
+            If a variable in a group is not an exogenous variable of the model
+
+        Returns
+        -------
+            The target variable from the run with all forcings and
+            the contribution of each forcing group
+        """
     def build(self) -> Model:
         """
         Build a concrete model from the provided information.
 
+        Any warnings, such as missing exogenous data, are emitted using the
+        `warnings` module using a subclass of `RSCMWarning`.
+        Use a warnings filter to turn specific classes of warning into errors.
+
         Raises
         ------
         Exception
@@ -204,6 +1138,21 @@ class ModelBuilder:
         -------
         Concrete model that can be solved
         """
+    def try_build(self) -> Model:
+        """
+        Build a concrete model, checking for problems with its inputs
+
+        Raises
+        ------
+        BuildError
+            If any exogenous variables are missing, state variables don't have
+            an initial value or variables have units which can't be converted.
+            All the problems are listed in `BuildError.problems`.
+
+        Returns
+        -------
+        Concrete model that can be solved
+        """
 
 class Model:
     """
@@ -225,12 +1174,258 @@ class Model:
     the carbon cycle model.
     """
 
+    def summary(self) -> str:
+        """
+        Single line description of the model
+
+        Includes the current time, the range of the time axis
+        and the number of components, variables and warnings.
+        """
+    def _repr_html_(self) -> str:
+        """HTML description of the model and its variables for notebooks"""
     def current_time(self) -> F: ...
     def current_time_bounds(self) -> (F, F): ...
     def step(self): ...
-    def run(self): ...
+    def run(self):
+        """
+        Run the model until the end of the time axis
+
+        Any warnings recorded during the run are emitted using the `warnings`
+        module using a subclass of `RSCMWarning`.
+
+        Raises
+        ------
+        TimeoutError
+            The run exceeded a limit set by `ModelBuilder.with_run_limits`
+        """
+    def try_step(self):
+        """
+        Step the model forward one time step
+
+        Unlike `step`, a failure of any component raises an error.
+        The time index is updated even if a component fails.
+
+        Raises
+        ------
+        ComponentError
+            A component failed to solve the step
+        """
+    def try_run(self):
+        """
+        Run the model until the end of the time axis, stopping at the first failed step
+
+        Raises
+        ------
+        ComponentError
+            A component failed to solve a step
+        TimeoutError
+            The run exceeded a limit set by `ModelBuilder.with_run_limits`
+        """
+    def warnings(self) -> list[tuple[WarningKind, str]]:
+        """
+        Warnings recorded while building and running the model
+
+        Returns
+        -------
+            List of (kind, message)
+        """
     def as_dot(self) -> str: ...
+    def as_mermaid(self) -> str:
+        """
+        Mermaid flowchart of the components and the variables passed between them
+
+        Edges are labelled with the name and units of each variable.
+        This can be rendered in notebooks and documentation which support Mermaid.
+        """
+    def as_json(self) -> str:
+        """
+        JSON description of the components and the variables passed between them
+
+        Each node has the name, type and parameters of a component
+        and each edge has the name and units of a variable.
+        """
+    def to_component(self) -> RustComponent:
+        """
+        Create a component which solves the components of this model
+
+        This allows a model to be embedded as a single component of a larger model
+        using `ModelBuilder.with_rust_component`.
+        Exogenous variables without data become inputs of the component,
+        variables carried between time steps become its state
+        and all other variables solved by the components become its outputs.
+
+        Returns
+        -------
+            Component which can be registered with another model
+        """
+    def estimate_cost(self, members: int = 1, threads: int = 1) -> CostEstimate:
+        """
+        Estimate the runtime and memory use of the remainder of the run
+
+        These are order-of-magnitude estimates to help size ensembles.
+
+        Parameters
+        ----------
+        members
+            Number of runs in the ensemble
+        threads
+            Number of runs which are run in parallel
+        """
+    def execution_order(self) -> list[str]:
+        """
+        Names of the components in the order they are solved each step
+
+        Components are solved after any components which provide their inputs.
+        """
+    def component_names(self) -> list[str]:
+        """
+        Names of the components in the model
+
+        Components are named when added to the `ModelBuilder`,
+        otherwise the name of their type is used.
+        """
     def finished(self) -> bool: ...
+    def sensitivity(self, name: str) -> Timeseries | None:
+        """
+        Derivative of a variable with respect to the parameter set using
+        `ModelBuilder.with_sensitivity`
+
+        Parameters
+        ----------
+        name
+            Name of the variable
+
+        Returns
+        -------
+        Derivative in the units of the variable per unit of the parameter,
+        or None if sensitivities weren't requested or the variable doesn't exist
+        """
+    def update_exogenous(self, collection: TimeseriesCollection) -> int:
+        """
+        Replace exogenous timeseries, only recomputing the affected steps
+
+        The model is rewound to the first step which uses modified inputs
+        and the endogenous values after this step are cleared.
+        Results before this step are reused by the next call to `run`.
+
+        Parameters
+        ----------
+        collection
+            Timeseries which replace existing exogenous variables
+
+        Returns
+        -------
+            Time index that the model will resume from
+        """
+    def replace_component(
+        self,
+        component_name: str,
+        component: RustComponent | PythonComponent,
+        variables: dict[str, str] | None = None,
+        namespace: str | None = None,
+    ) -> None:
+        """
+        Replace a component without rebuilding the model
+
+        Used to modify the parameters of a component between runs,
+        e.g. when calibrating, while reusing the model's timeseries.
+        The model is rewound to the start of the run
+        and the endogenous values after the initial values are cleared.
+
+        Parameters
+        ----------
+        component_name
+            Name given to the component when it was added to the model,
+            otherwise the name of the component's type, e.g. "CO2ERF"
+        component
+            New component.
+            Must have the same variables, units and requirement types
+            as the component it replaces
+        variables
+            Names of the component's variables in the model,
+            as used when the original component was added
+        namespace
+            Prefix added to the names of the component's outputs,
+            as used when the original component was added
+        """
+    def regrid(
+        self, time_axis: TimeAxis, exogenous: TimeseriesCollection | None = None
+    ) -> None:
+        """
+        Continue the run on a different time axis
+
+        A model can be spun up using long time steps and then continued
+        using shorter time steps, e.g. annual then monthly.
+        The endogenous values up to the current time are interpolated onto
+        the new time axis, so the state at the current time is carried over.
+
+        Parameters
+        ----------
+        time_axis
+            New time axis. Must contain the current time
+        exogenous
+            Data which replace existing exogenous variables.
+            Other exogenous variables are resampled from the original time axis
+        """
+    def metadata(self) -> dict[str, Any]:
+        """
+        Scenario, parameters, versions and timing of the run
+
+        The metadata is serialised with the model by `to_toml`.
+
+        Returns
+        -------
+            Dictionary with the "scenario", "parameters", "versions" (name to version),
+            "git_hash" that rscm was built from, the time the run "started"
+            (seconds since the Unix epoch) and the "wall_time" of the run in seconds.
+            Items which aren't known are omitted.
+        """
+    def refinements(self) -> list[tuple[int, str, int]]:
+        """
+        Steps which required refinement to be solved stably
+
+        Returns
+        -------
+            List of (time index, component name, number of refinements)
+        """
+    def save_rng_streams(self) -> str:
+        """
+        Serialise the random number streams of each component
+
+        The streams are also included in `to_toml`,
+        but can be saved and restored independently of the rest of the model's state.
+
+        Returns
+        -------
+            TOML representation of the streams
+        """
+    def restore_rng_streams(self, state: str):
+        """
+        Restore random number streams saved using `save_rng_streams`
+
+        Used to replay exactly the same random numbers from a checkpoint.
+        """
+    def add_observer(self, callback: Callable[[dict[str, Any]], None]) -> None:
+        """
+        Call `callback` with each subsequent event of the model
+
+        See `ModelBuilder.with_observer`
+        """
+    def reseed_rng_streams(self, seed: int, component_name: str | None = None):
+        """
+        Reseed the random number streams
+
+        Used to branch a run from a checkpoint with fresh noise.
+
+        Parameters
+        ----------
+        seed
+            New seed
+        component_name
+            Name given to the component when it was added to the model,
+            otherwise the name of the component's type, e.g. "CO2ERF".
+            If None, the streams of all components are reseeded.
+        """
     def timeseries(self) -> TimeseriesCollection:
         """
         Get the timeseries associated with the model.
@@ -242,6 +1437,43 @@ class Model:
         -------
         Clone of the timeseries held by the model
         """
+    def to_pandas(self) -> Any:
+        """
+        Convert the timeseries held by the model to a pandas DataFrame
+
+        See `TimeseriesCollection.to_pandas`.
+        """
+
+    STATE_FORMAT_VERSION: int
+    """Version of the format written by `Model.save_state`"""
+
+    def save_state(self) -> str:
+        """
+        Save the state of the model so that the run can be resumed later
+
+        The state can be saved at any time index. Resuming a restored model
+        produces identical results to an uninterrupted run.
+
+        Returns
+        -------
+        TOML string containing the model and `STATE_FORMAT_VERSION`
+        """
+
+    @classmethod
+    def load_state(cls: type[T], state: str) -> T:
+        """
+        Restore a model from a state written by `Model.save_state`
+
+        Parameters
+        ----------
+        state
+            Saved state of a model
+
+        Raises
+        ------
+        RuntimeError
+            The state was written using a different format version
+        """
 
     def to_toml(self) -> str:
         """
@@ -270,3 +1502,216 @@ class Model:
         -------
         New model object with the state as defined in the TOML string.
         """
+
+class Skill:
+    """Skill scores of a model relative to a reference over a window"""
+
+    n: int
+    """Number of time points that were compared"""
+    rmse: float
+    """Root-mean-square error"""
+    bias: float
+    """Mean of the model minus the reference"""
+    correlation: float
+    """Pearson correlation coefficient"""
+    trend_difference: float
+    """Linear trend of the model minus that of the reference"""
+
+class ScoreCardEntry:
+    """Skill of a single variable over a window"""
+
+    variable: str
+    start: float
+    end: float
+    skill: Skill
+
+def score_card(
+    model: TimeseriesCollection,
+    references: TimeseriesCollection,
+    windows: list[tuple[float, float]],
+) -> list[ScoreCardEntry]:
+    """
+    Compare model output against reference datasets
+
+    Each timeseries in `references` is compared against the timeseries with the
+    same name in `model` at the reference time points within each window.
+    Both ends of a window are inclusive.
+
+    Raises
+    ------
+    RuntimeError
+        If a reference variable is missing from `model`, the units differ or
+        a window contains no valid values
+
+    Returns
+    -------
+        Skill of each variable for each window
+    """
+
+class CostEstimate:
+    """Estimated cost of running a model"""
+
+    steps: int
+    """Number of time steps which remain to be run"""
+    component_solves: int
+    """Number of times a component is solved"""
+    seconds: float
+    """Approximate runtime in seconds"""
+    bytes: int
+    """Approximate memory use in bytes"""
+
+class OptimisationResult:
+    """Result of an optimisation"""
+
+    parameters: dict[str, float]
+    """Best parameter set found"""
+    value: float
+    """Value of the cost at `parameters`"""
+    iterations: int
+    converged: bool
+    """Whether a convergence criterion was met before the maximum number of iterations"""
+
+def calibrate(  # noqa: PLR0913
+    initial: dict[str, float],
+    bounds: dict[str, tuple[float, float]],
+    build: Callable[[dict[str, float]], ModelBuilder],
+    cost: Callable[[TimeseriesCollection], float],
+    max_iterations: int = 500,
+    initial_step: float = 0.1,
+    tolerance: float = 1e-8,
+    parameter_tolerance: float = 1e-6,
+) -> OptimisationResult:
+    """
+    Find the parameters which minimise a cost using the Nelder-Mead simplex method
+
+    Parameter sets for which the model fails to run are assigned an infinite cost.
+
+    Parameters
+    ----------
+    initial
+        Starting parameter set. Parameters which aren't in `bounds` are held fixed
+    bounds
+        Range of each calibrated parameter as `(min, max)`
+    build
+        Creates a model builder for a parameter set
+    cost
+        Misfit between the output of a run and observations. Lower values are better
+    max_iterations
+        Maximum number of iterations
+    initial_step
+        Size of the initial simplex relative to the range of each parameter
+    tolerance
+        Stop once the costs at the vertices of the simplex differ by less than this
+        value and the simplex is smaller than `parameter_tolerance`
+    parameter_tolerance
+        Largest distance between the vertices of the simplex, relative to the range
+        of each parameter, at which the search can stop
+
+    Raises
+    ------
+    RuntimeError
+        If a parameter in `bounds` is missing from `initial` or a range is invalid
+
+    Returns
+    -------
+        Best parameter set found
+    """
+
+def register_unit(unit: str, dimension: str, scale: float) -> None:
+    """
+    Register a unit in the global unit registry
+
+    Parameters
+    ----------
+    unit
+        Name of the unit. Whitespace is ignored
+    dimension
+        Dimension of the unit, e.g. "[carbon]"
+    scale
+        Size of the unit relative to the base unit of the dimension
+    """
+
+def register_context(context: str, from_: str, to: str, factor: float) -> None:
+    """
+    Register a conversion between two dimensions within a unit context
+
+    One base unit of `from_` is equivalent to `factor` base units of `to`.
+    The reverse conversion is also available.
+    """
+
+def conversion_factor(from_: str, to: str, contexts: list[str] = []) -> float:  # noqa: B006
+    """
+    Factor to convert a value from `from_` units to `to` units
+
+    Raises
+    ------
+    RuntimeError
+        The units can't be converted
+    """
+
+class SamplingMethod(Enum):
+    """Method used to sample a parameter space"""
+
+    Random = auto()
+    LatinHypercube = auto()
+    Sobol = auto()
+    Halton = auto()
+
+def load_scenario(
+    path: str | os.PathLike, scenario: str | None = None, region: str | None = None
+) -> TimeseriesCollection:
+    """
+    Read the timeseries of a scenario from a CSV file in the IAMC format
+
+    The file has `Model`, `Scenario`, `Region`, `Variable` and `Unit` columns
+    and a column for each year.
+    Each row is read as an exogenous timeseries named after its variable,
+    which is linearly interpolated between the years.
+
+    Parameters
+    ----------
+    path
+        Path to the CSV file
+    scenario
+        Only read rows for this scenario
+    region
+        Only read rows for this region
+
+    Raises
+    ------
+    RuntimeError
+        The file can't be read or a variable is defined in more than one
+        of the selected rows
+
+    Returns
+    -------
+        Timeseries of each variable
+    """
+
+def sample(method: SamplingMethod, n: int, dimensions: int, seed: int = 0) -> Arr:
+    """
+    Sample points in the unit hypercube
+
+    The same seed always produces the same points.
+    Sobol and Halton sequences are scrambled using the seed.
+
+    Parameters
+    ----------
+    method
+        Sampling method
+    n
+        Number of points
+    dimensions
+        Number of coordinates of each point
+    seed
+        Seed of the random number generator
+
+    Raises
+    ------
+    RuntimeError
+        The method doesn't support the number of dimensions
+
+    Returns
+    -------
+        Array with shape (n, dimensions) with values in [0, 1)
+    """
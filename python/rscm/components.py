@@ -2,9 +2,32 @@
 RSCM Components defined in Rust
 """
 
-from rscm._lib.components import CarbonCycleBuilder, CO2ERFBuilder
+from rscm._lib.components import (
+    BlendedTemperatureBuilder,
+    CarbonCycleBuilder,
+    CH4ERFBuilder,
+    CO2ERFBuilder,
+    FaIRCarbonCycleBuilder,
+    GreensFunctionTemperatureBuilder,
+    InternalVariabilityBuilder,
+    N2OERFBuilder,
+    OceanCarbonCycleBuilder,
+    OceanSurfacePartialPressureBuilder,
+    PatternScalingBuilder,
+    TwoLayerComponentBuilder,
+)
 
 __all__ = [
+    "BlendedTemperatureBuilder",
+    "CH4ERFBuilder",
     "CO2ERFBuilder",
     "CarbonCycleBuilder",
+    "FaIRCarbonCycleBuilder",
+    "GreensFunctionTemperatureBuilder",
+    "InternalVariabilityBuilder",
+    "N2OERFBuilder",
+    "OceanCarbonCycleBuilder",
+    "OceanSurfacePartialPressureBuilder",
+    "PatternScalingBuilder",
+    "TwoLayerComponentBuilder",
 ]
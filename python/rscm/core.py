@@ -3,27 +3,91 @@ Core classes and functions for Rust Simple Climate Models (RSCMs)
 """
 
 from rscm._lib.core import (
+    BuildError,
+    Calendar,
+    ComponentError,
+    Compression,
+    CostEstimate,
+    ComponentFailedWarning,
+    DisaggregationStrategy,
+    ExtrapolationPolicy,
+    IncompatibleUnitsWarning,
     InterpolationStrategy,
+    LossyResamplingWarning,
+    MassBalanceViolationWarning,
+    MissingExogenousDataWarning,
     Model,
     ModelBuilder,
+    NanPolicy,
+    NanValuesWarning,
+    OptimisationResult,
+    OutOfBoundsWarning,
+    OutputFailedWarning,
+    Precision,
     PythonComponent,
+    RegridMethod,
     RequirementDefinition,
     RequirementType,
+    RSCMWarning,
+    RunAbortedWarning,
+    SamplingMethod,
+    ScoreCardEntry,
+    Skill,
     TimeAxis,
     Timeseries,
     TimeseriesCollection,
     VariableType,
+    WarningKind,
+    calibrate,
+    conversion_factor,
+    load_scenario,
+    register_context,
+    register_unit,
+    sample,
+    score_card,
 )
 
 __all__ = [
+    "BuildError",
+    "Calendar",
+    "ComponentError",
+    "Compression",
+    "CostEstimate",
+    "ComponentFailedWarning",
+    "DisaggregationStrategy",
+    "ExtrapolationPolicy",
+    "IncompatibleUnitsWarning",
     "InterpolationStrategy",
+    "LossyResamplingWarning",
+    "MassBalanceViolationWarning",
+    "MissingExogenousDataWarning",
+    "NanValuesWarning",
+    "RSCMWarning",
+    "RunAbortedWarning",
+    "RegridMethod",
     "RequirementDefinition",
     "RequirementType",
     "Model",
     "ModelBuilder",
+    "NanPolicy",
+    "OptimisationResult",
+    "OutOfBoundsWarning",
+    "OutputFailedWarning",
+    "Precision",
     "TimeAxis",
     "Timeseries",
     "TimeseriesCollection",
     "PythonComponent",
+    "SamplingMethod",
+    "ScoreCardEntry",
+    "Skill",
     "VariableType",
+    "WarningKind",
+    "calibrate",
+    "conversion_factor",
+    "load_scenario",
+    "register_context",
+    "register_unit",
+    "sample",
+    "score_card",
 ]
@@ -0,0 +1,395 @@
+"""
+High-level interface for running a model in a single call
+
+A model is described by a configuration containing the time axis,
+the components and any initial values.
+Exogenous data and results can be provided as pandas or xarray objects.
+
+```toml
+[time_axis]
+start = 1850.0
+end = 2100.0
+step = 1.0
+
+[components.temperature]
+type = "TwoLayerComponent"
+
+[components.temperature.parameters]
+lambda0 = 1.0
+a = 0.0
+efficacy = 1.0
+eta = 0.7
+heat_capacity_surface = 8.0
+heat_capacity_deep = 100.0
+
+[initial_values]
+"Surface Temperature" = 0.0
+```
+
+An optional top-level `output_variables` list of glob patterns,
+e.g. `["Surface Temperature", "Effective Radiative Forcing|*"]`,
+limits which variables are stored during the run.
+"""
+
+from __future__ import annotations
+
+import json
+import os
+from typing import Any
+
+import numpy as np
+
+from rscm.components import (
+    BlendedTemperatureBuilder,
+    CarbonCycleBuilder,
+    CH4ERFBuilder,
+    CO2ERFBuilder,
+    FaIRCarbonCycleBuilder,
+    GreensFunctionTemperatureBuilder,
+    InternalVariabilityBuilder,
+    N2OERFBuilder,
+    OceanCarbonCycleBuilder,
+    OceanSurfacePartialPressureBuilder,
+    PatternScalingBuilder,
+    TwoLayerComponentBuilder,
+)
+from rscm.core import (
+    InterpolationStrategy,
+    ModelBuilder,
+    TimeAxis,
+    Timeseries,
+    TimeseriesCollection,
+    VariableType,
+)
+
+COMPONENTS: dict[str, Any] = {
+    "BlendedTemperature": BlendedTemperatureBuilder,
+    "CarbonCycle": CarbonCycleBuilder,
+    "CH4ERF": CH4ERFBuilder,
+    "CO2ERF": CO2ERFBuilder,
+    "FaIRCarbonCycle": FaIRCarbonCycleBuilder,
+    "GreensFunctionTemperature": GreensFunctionTemperatureBuilder,
+    "InternalVariability": InternalVariabilityBuilder,
+    "N2OERF": N2OERFBuilder,
+    "OceanCarbonCycle": OceanCarbonCycleBuilder,
+    "OceanSurfacePartialPressure": OceanSurfacePartialPressureBuilder,
+    "PatternScaling": PatternScalingBuilder,
+    "TwoLayerComponent": TwoLayerComponentBuilder,
+}
+"""Builders of the components which can be used in a configuration, by type"""
+
+
+def register_component(name: str, builder: Any) -> None:
+    """
+    Make a component available to configurations
+
+    Parameters
+    ----------
+    name
+        Name used as the `type` of the component in configurations
+    builder
+        Component builder with a `from_parameters` class method
+    """
+    COMPONENTS[name] = builder
+
+
+def _load_config(config: dict[str, Any] | str | os.PathLike) -> dict[str, Any]:
+    if isinstance(config, dict):
+        return config
+
+    path = os.fspath(config)
+    with open(path, "rb") as fh:
+        if path.endswith(".json"):
+            return json.load(fh)
+
+        try:
+            import tomllib
+        except ImportError as e:  # pragma: no cover
+            raise ImportError(
+                "Reading TOML configuration requires Python 3.11 or later"
+            ) from e
+        return tomllib.load(fh)
+
+
+def _time_axis(config: dict[str, Any]) -> TimeAxis:
+    if "values" in config:
+        return TimeAxis.from_values(np.asarray(config["values"], dtype=float))
+    if "bounds" in config:
+        return TimeAxis.from_bounds(np.asarray(config["bounds"], dtype=float))
+
+    step = config.get("step", 1.0)
+    return TimeAxis.from_values(
+        np.arange(config["start"], config["end"] + step / 2, step, dtype=float)
+    )
+
+
+def _timeseries(times: Any, values: Any, units: str) -> Timeseries:
+    return Timeseries(
+        np.asarray(values, dtype=float),
+        TimeAxis.from_values(times),
+        units,
+        InterpolationStrategy.Linear,
+    )
+
+
+def _from_pandas(data: Any) -> dict[str, Timeseries]:
+    names = data.index.names
+    if "variable" not in names:
+        raise ValueError("The index of exogenous data must include a 'variable' level")
+
+    exogenous = {}
+    for key, row in data.iterrows():
+        labels = dict(zip(names, key if isinstance(key, tuple) else (key,)))
+        exogenous[labels["variable"]] = _timeseries(
+            row.index, row.to_numpy(), labels.get("unit", "")
+        )
+    return exogenous
+
+
+def _from_xarray(data: Any) -> dict[str, Timeseries]:
+    return {
+        name: _timeseries(
+            array["time"].to_numpy(), array.to_numpy(), array.attrs.get("units", "")
+        )
+        for name, array in data.data_vars.items()
+    }
+
+
+def _exogenous_collection(exogenous: Any) -> TimeseriesCollection:
+    if isinstance(exogenous, TimeseriesCollection):
+        return exogenous
+
+    if isinstance(exogenous, dict):
+        timeseries = exogenous
+    elif hasattr(exogenous, "data_vars"):
+        timeseries = _from_xarray(exogenous)
+    elif hasattr(exogenous, "iterrows"):
+        timeseries = _from_pandas(exogenous)
+    else:
+        raise TypeError(f"Unsupported exogenous data: {type(exogenous)}")
+
+    collection = TimeseriesCollection()
+    for name, ts in timeseries.items():
+        collection.add_timeseries(name, ts, VariableType.Exogenous)
+    return collection
+
+
+def _to_pandas(collection: TimeseriesCollection) -> Any:
+    import pandas as pd
+
+    rows = {
+        (name, ts.units): pd.Series(ts.values(), index=ts.time_axis.values())
+        for name, ts in zip(collection.names(), collection.timeseries())
+    }
+    result = pd.DataFrame(rows).T
+    result.index.names = ["variable", "unit"]
+    result.columns.name = "time"
+    return result
+
+
+def _to_xarray(collection: TimeseriesCollection) -> Any:
+    import xarray as xr
+
+    return xr.Dataset(
+        {
+            name: xr.DataArray(
+                ts.values(),
+                coords={"time": ts.time_axis.values()},
+                dims=["time"],
+                attrs={"units": ts.units},
+            )
+            for name, ts in zip(collection.names(), collection.timeseries())
+        }
+    )
+
+
+def run(
+    config: dict[str, Any] | str | os.PathLike,
+    exogenous: Any = None,
+    output: str = "pandas",
+    units: dict[str, str] | None = None,
+    unit_contexts: list[str] | None = None,
+) -> Any:
+    """
+    Build and run a model
+
+    Parameters
+    ----------
+    config
+        Configuration of the model or the path to a TOML or JSON configuration file.
+
+        The configuration contains:
+
+        * `time_axis`: either `start`, `end` and `step` (defaults to 1.0),
+          the `values` of the time axis or its `bounds`
+        * `components`: a table for each component containing the `type`
+          of the component (see `COMPONENTS`), its `parameters`,
+          optionally the `group` that it belongs to
+          and the `variables` to rename, mapping the names used by the component
+          to the names used in the model, or a `namespace` to prefix its outputs with
+        * `initial_values`: optional initial values of variables
+    exogenous
+        Exogenous data used by the model.
+
+        Either a `TimeseriesCollection`, a dictionary of `Timeseries`,
+        a pandas `DataFrame` with one row per variable,
+        an index containing `variable` and optionally `unit` levels
+        and a column for each time,
+        or an xarray `Dataset` with a `time` dimension and a data variable
+        per variable with a `units` attribute.
+    output
+        Format of the results, one of "pandas", "xarray" or "collection"
+    units
+        Units to return variables in, by name.
+
+        Other variables are returned in the units used by the model.
+    unit_contexts
+        Unit contexts used when converting variables into `units`,
+        e.g. "CO2" for conversions between GtC and GtCO2
+
+    Raises
+    ------
+    ValueError
+        The configuration contains an unknown component type
+        or an unknown output format is requested
+    RuntimeError
+        A variable can't be converted into the requested units
+
+    Returns
+    -------
+        State of the model after the run in the requested format.
+
+        The pandas format matches the format of the exogenous data.
+    """
+    if output not in ("pandas", "xarray", "collection"):
+        raise ValueError(f"Unknown output format: {output}")
+
+    config = _load_config(config)
+
+    builder = ModelBuilder()
+    builder.with_time_axis(_time_axis(config["time_axis"]))
+    for name, component in config.get("components", {}).items():
+        kind = component["type"]
+        if kind not in COMPONENTS:
+            raise ValueError(f"Unknown type '{kind}' for component '{name}'")
+        parameters = component.get("parameters", {})
+        built = COMPONENTS[kind].from_parameters(parameters).build()
+        builder.with_rust_component(
+            built,
+            component.get("group"),
+            name,
+            component.get("variables"),
+            component.get("namespace"),
+        )
+
+    if config.get("output_variables"):
+        builder.with_output_variables(config["output_variables"])
+    if config.get("initial_values"):
+        builder.with_initial_values(config["initial_values"])
+    if exogenous is not None:
+        builder.with_exogenous_collection(_exogenous_collection(exogenous))
+
+    model = builder.build()
+    model.run()
+    collection = model.timeseries()
+    if units:
+        collection = collection.convert_units(units, unit_contexts or [])
+
+    if output == "pandas":
+        return _to_pandas(collection)
+    if output == "xarray":
+        return _to_xarray(collection)
+    return collection
+
+
+def _parameter_table(parameters: Any) -> list[dict[str, float]]:
+    if isinstance(parameters, (str, os.PathLike)):
+        import pandas as pd
+
+        path = os.fspath(parameters)
+        if path.endswith(".parquet"):
+            parameters = pd.read_parquet(path)
+        elif path.endswith(".csv"):
+            parameters = pd.read_csv(path)
+        else:
+            raise ValueError(f"Unsupported parameter table format: {path}")
+
+    if hasattr(parameters, "to_dict"):
+        return parameters.to_dict(orient="records")
+    return list(parameters)
+
+
+def _member_config(config: dict[str, Any], member: dict[str, float]) -> dict[str, Any]:
+    components = {
+        name: dict(component, parameters=dict(component.get("parameters", {})))
+        for name, component in config.get("components", {}).items()
+    }
+    for column, value in member.items():
+        component, _, parameter = column.rpartition(".")
+        if component not in components:
+            raise ValueError(
+                f"Parameter column '{column}' doesn't match a component, "
+                "expected component.parameter"
+            )
+        components[component]["parameters"][parameter] = float(value)
+    return dict(config, components=components)
+
+
+def run_ensemble(
+    config: dict[str, Any] | str | os.PathLike,
+    parameters: Any,
+    exogenous: Any = None,
+    output: str = "pandas",
+    units: dict[str, str] | None = None,
+    unit_contexts: list[str] | None = None,
+) -> list[Any]:
+    """
+    Run a model for each member of a table of pre-sampled parameters
+
+    This allows parameter ensembles sampled or calibrated by other tools
+    to be reused directly.
+
+    Parameters
+    ----------
+    config
+        Configuration of the model shared by all members (see `run`)
+    parameters
+        Parameters of each member.
+
+        Either a path to a CSV or Parquet file, a pandas `DataFrame`
+        or a list of dictionaries, with a row per member.
+        Each column is named `component.parameter`, e.g. `temperature.lambda0`,
+        and overrides the parameter of the component in `config`.
+        Reading files requires pandas.
+    exogenous
+        Exogenous data used by every member (see `run`)
+    output
+        Format of the results of each member (see `run`)
+    units
+        Units to return variables in, by name
+    unit_contexts
+        Unit contexts used when converting variables into `units`
+
+    Raises
+    ------
+    ValueError
+        A column doesn't match a component in the configuration
+
+    Returns
+    -------
+        Results of each member in the order of the table
+    """
+    config = _load_config(config)
+    if exogenous is not None:
+        exogenous = _exogenous_collection(exogenous)
+
+    return [
+        run(
+            _member_config(config, member),
+            exogenous=exogenous,
+            output=output,
+            units=units,
+            unit_contexts=unit_contexts,
+        )
+        for member in _parameter_table(parameters)
+    ]
@@ -0,0 +1,102 @@
+//! A coupled carbon cycle and climate model driven by CO_2 emissions
+//!
+//! CO_2 emissions are converted to concentrations by the FaIR carbon cycle,
+//! to effective radiative forcing (ERF) using the logarithmic CO_2 forcing relationship
+//! and to warming using a two-layer energy balance model.
+//! The carbon cycle responds to the warming of the previous time step.
+//!
+//! The model is run from 1750 to 2100 using historical emissions followed by SSP2-4.5.
+//!
+//! Run using `cargo run --example coupled_model`.
+use numpy::array;
+use numpy::ndarray::Array;
+use rscm_components::{
+    CO2ERFParameters, FaIRCarbonCycleComponent, FaIRCarbonCycleParameters, TwoLayerComponent,
+    TwoLayerComponentParameters, CO2ERF,
+};
+use rscm_core::interpolate::strategies::{InterpolationStrategy, LinearSplineStrategy};
+use rscm_core::model::{Model, ModelBuilder};
+use rscm_core::remap::RemappedComponent;
+use rscm_core::timeseries::{FloatValue, TimeAxis, Timeseries};
+use std::sync::Arc;
+
+/// Anthropogenic CO_2 emissions from fossil fuels, industry and land-use change
+///
+/// Approximate values rounded from the Global Carbon Budget up to 2015
+/// and the harmonised SSP2-4.5 emissions used in RCMIP (Nicholls et al. 2020) afterwards.
+/// unit: GtC / yr
+pub fn co2_emissions() -> Timeseries<FloatValue> {
+    Timeseries::new(
+        array![
+            0.2, 0.3, 0.6, 1.5, 2.1, 2.7, 3.9, 5.3, 6.5, 7.6, 8.1, 10.2, 10.9, 11.9, 12.0, 11.7,
+            11.0, 9.5, 7.2, 4.9, 2.6
+        ],
+        Arc::new(TimeAxis::from_values(array![
+            1750.0, 1800.0, 1850.0, 1900.0, 1920.0, 1950.0, 1960.0, 1970.0, 1980.0, 1990.0, 2000.0,
+            2010.0, 2015.0, 2030.0, 2040.0, 2050.0, 2060.0, 2070.0, 2080.0, 2090.0, 2100.0
+        ])),
+        "GtC / yr".to_string(),
+        InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+    )
+}
+
+/// Build the coupled model on an annual time axis from 1750 to 2100
+pub fn build_model() -> Model {
+    // Solved using the surface temperature at the start of each step
+    let carbon_cycle = FaIRCarbonCycleComponent::from_parameters(FaIRCarbonCycleParameters {
+        conc_pi: 278.0,
+        ..Default::default()
+    });
+    // AR6 assessment of the ERF from a doubling of CO_2
+    let co2_erf = CO2ERF::from_parameters(CO2ERFParameters {
+        erf_2xco2: 3.93,
+        conc_pi: 278.0,
+    });
+    // CMIP5 multi-model mean from Geoffroy et al. (2013)
+    let climate = TwoLayerComponent::from_parameters(TwoLayerComponentParameters {
+        lambda0: 1.18,
+        a: 0.0,
+        efficacy: 1.0,
+        eta: 0.67,
+        heat_capacity_surface: 8.2,
+        heat_capacity_deep: 109.0,
+    });
+
+    // CO_2 is the only forcing agent
+    let mut climate = RemappedComponent::new(Arc::new(climate));
+    climate.with_variable(
+        "Effective Radiative Forcing",
+        "Effective Radiative Forcing|CO2",
+    );
+
+    ModelBuilder::new()
+        .with_component(Arc::new(carbon_cycle))
+        .with_component(Arc::new(co2_erf))
+        .with_component(Arc::new(climate))
+        .with_time_axis(TimeAxis::from_values(Array::range(1750.0, 2101.0, 1.0)))
+        .with_exogenous_variable("Emissions|CO2|Anthropogenic", co2_emissions())
+        .build()
+}
+
+fn main() {
+    let mut model = build_model();
+    model.run();
+
+    let timeseries = model.timeseries();
+    let concentration = timeseries
+        .get_timeseries_by_name("Atmospheric Concentration|CO2")
+        .unwrap();
+    let temperature = timeseries
+        .get_timeseries_by_name("Surface Temperature")
+        .unwrap();
+
+    println!("{}", model.summary());
+    for year in [1850.0, 1900.0, 1950.0, 2000.0, 2020.0, 2050.0, 2100.0] {
+        println!(
+            "{}: {:.1} ppm, {:.2} K",
+            year,
+            concentration.at_time(year).unwrap(),
+            temperature.at_time(year).unwrap()
+        );
+    }
+}
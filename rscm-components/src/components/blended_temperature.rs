@@ -0,0 +1,159 @@
+/// Blending of air temperatures to match observational products
+use rscm_core::component::{
+    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+};
+use rscm_core::errors::RSCMResult;
+use rscm_core::timeseries::{FloatValue, Time};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Parameters for the blended temperature diagnostic
+///
+/// Typical values are `land_fraction = 0.29`, `sea_ice_fraction = 0.04` and
+/// `sst_ratio = 0.93`, which gives a blended warming about 5% lower than the air temperature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlendedTemperatureParameters {
+    /// Fraction of the surface which is land
+    /// unit: dimensionless
+    pub land_fraction: FloatValue,
+    /// Fraction of the ocean covered by sea ice, where air temperatures are used
+    /// unit: dimensionless
+    pub sea_ice_fraction: FloatValue,
+    /// Change in sea surface temperature relative to the change in air temperature
+    /// over the ice-free ocean
+    /// unit: dimensionless
+    pub sst_ratio: FloatValue,
+    /// Fraction of the blended warming retained after masking to the coverage of the
+    /// observational product, e.g. 1 for a product with complete coverage
+    /// unit: dimensionless
+    pub coverage: FloatValue,
+}
+
+/// Blended surface air and sea surface temperature from surface air temperature
+///
+/// Observational products such as HadCRUT combine air temperatures over land and sea ice
+/// with sea surface temperatures (SSTs) over the ice-free ocean
+/// and only include regions with observations.
+/// Comparing these products directly with modelled air temperatures overestimates the warming,
+/// so this diagnostic estimates the equivalent of the observational product:
+///
+/// ```math
+/// T_{blended} = c \left[ f_l T + (1 - f_l) \left( f_i T + (1 - f_i) r T \right) \right]
+/// ```
+///
+/// where `T` is the air temperature change, `f_l` the land fraction, `f_i` the sea ice fraction,
+/// `r` the SST ratio and `c` the coverage factor.
+/// The SST change, `r T`, is also output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlendedTemperature {
+    parameters: BlendedTemperatureParameters,
+}
+
+impl BlendedTemperature {
+    /// Create a new component
+    ///
+    /// Panics if the land or sea ice fractions aren't between 0 and 1.
+    pub fn from_parameters(parameters: BlendedTemperatureParameters) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&parameters.land_fraction),
+            "Land fraction must be between 0 and 1"
+        );
+        assert!(
+            (0.0..=1.0).contains(&parameters.sea_ice_fraction),
+            "Sea ice fraction must be between 0 and 1"
+        );
+        Self { parameters }
+    }
+
+    /// Blended temperature change and SST change from an air temperature change
+    pub fn blend(&self, temperature: FloatValue) -> (FloatValue, FloatValue) {
+        let p = &self.parameters;
+        let sst = p.sst_ratio * temperature;
+        let ocean = p.sea_ice_fraction * temperature + (1.0 - p.sea_ice_fraction) * sst;
+        let blended =
+            p.coverage * (p.land_fraction * temperature + (1.0 - p.land_fraction) * ocean);
+        (blended, sst)
+    }
+}
+
+#[typetag::serde]
+impl Component for BlendedTemperature {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new("Surface Temperature", "K", RequirementType::Input),
+            RequirementDefinition::new("Surface Temperature|Blended", "K", RequirementType::Output),
+            RequirementDefinition::new("Sea Surface Temperature", "K", RequirementType::Output),
+        ]
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let (blended, sst) = self.blend(*input_state.get("Surface Temperature"));
+
+        let output = HashMap::from([
+            ("Surface Temperature|Blended".to_string(), blended),
+            ("Sea Surface Temperature".to_string(), sst),
+        ]);
+        Ok(OutputState::from_hashmap_and_verify(
+            output,
+            self.output_names(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn parameters() -> BlendedTemperatureParameters {
+        BlendedTemperatureParameters {
+            land_fraction: 0.29,
+            sea_ice_fraction: 0.04,
+            sst_ratio: 0.93,
+            coverage: 1.0,
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_fraction() {
+        BlendedTemperature::from_parameters(BlendedTemperatureParameters {
+            land_fraction: 1.2,
+            ..parameters()
+        });
+    }
+
+    #[test]
+    fn blend() {
+        let component = BlendedTemperature::from_parameters(parameters());
+        let input_state =
+            InputState::from_vectors(vec![1.0], vec!["Surface Temperature".to_string()]);
+
+        let output = component.solve(2000.0, 2001.0, &input_state).unwrap();
+
+        let ocean = 0.04 + 0.96 * 0.93;
+        assert_relative_eq!(
+            *output.get("Surface Temperature|Blended"),
+            0.29 + 0.71 * ocean
+        );
+        assert_relative_eq!(*output.get("Sea Surface Temperature"), 0.93);
+
+        // Without any masking or SST difference the air temperature is unchanged
+        let component = BlendedTemperature::from_parameters(BlendedTemperatureParameters {
+            sst_ratio: 1.0,
+            ..parameters()
+        });
+        assert_relative_eq!(component.blend(1.5).0, 1.5);
+
+        let component = BlendedTemperature::from_parameters(BlendedTemperatureParameters {
+            coverage: 0.9,
+            ..parameters()
+        });
+        assert_relative_eq!(component.blend(1.0).0, 0.9 * (0.29 + 0.71 * ocean));
+    }
+}
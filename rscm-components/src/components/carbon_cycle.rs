@@ -2,12 +2,12 @@ use crate::constants::GTC_PER_PPM;
 use ode_solvers::Vector3;
 use rscm_core::component::{
     Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+    TimestepContext,
 };
 use rscm_core::errors::RSCMResult;
-use rscm_core::ivp::{get_last_step, IVPBuilder, IVP};
-use rscm_core::timeseries::{FloatValue, Time};
+use rscm_core::ivp::{IVPBuilder, StatefulIVP, IVP};
+use rscm_core::timeseries::{FloatValue, Time, TimeUnit};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::sync::Arc;
 type ModelState = Vector3<FloatValue>;
 
@@ -50,6 +50,19 @@ impl CarbonCycleComponent {
             solver_options,
         }
     }
+
+    fn solve_with_step_size(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &InputState,
+        step_size: FloatValue,
+        time_unit: TimeUnit,
+    ) -> RSCMResult<OutputState> {
+        IVPBuilder::from_input_state(Arc::new(self.to_owned()), input_state.clone())
+            .with_time_unit(time_unit)
+            .solve_step(t_current, t_next, step_size)
+    }
 }
 
 #[typetag::serde]
@@ -66,7 +79,8 @@ impl Component for CarbonCycleComponent {
                 "Atmospheric Concentration|CO2",
                 "ppm",
                 RequirementType::InputAndOutput,
-            ),
+            )
+            .with_bounds(Some(0.0), None),
             RequirementDefinition::new(
                 "Cumulative Emissions|CO2",
                 "Gt C",
@@ -80,37 +94,50 @@ impl Component for CarbonCycleComponent {
         ]
     }
 
+    /// The atmosphere starts at the pre-industrial concentration with nothing emitted
+    fn initialise(&self, _t0: Time) -> OutputState {
+        OutputState::from_vectors(
+            vec![self.parameters.conc_pi, 0.0, 0.0],
+            vec![
+                "Atmospheric Concentration|CO2".to_string(),
+                "Cumulative Emissions|CO2".to_string(),
+                "Cumulative Land Uptake".to_string(),
+            ],
+        )
+    }
+
     fn solve(
         &self,
         t_current: Time,
         t_next: Time,
         input_state: &InputState,
     ) -> RSCMResult<OutputState> {
-        let y0 = ModelState::new(
-            *input_state.get("Atmospheric Concentration|CO2"),
-            *input_state.get("Cumulative Land Uptake"),
-            *input_state.get("Cumulative Emissions|CO2"),
-        );
-
-        let solver = IVPBuilder::new(Arc::new(self.to_owned()), input_state.clone(), y0);
-
-        let mut solver = solver.to_rk4(t_current, t_next, self.solver_options.step_size);
-        solver.integrate().expect("Failed solving");
-
-        let results = get_last_step(solver.results(), t_next);
-
-        let mut output = HashMap::new();
-        output.insert("Atmospheric Concentration|CO2".to_string(), results[0]);
-        output.insert("Cumulative Land Uptake".to_string(), results[1]);
-        output.insert("Cumulative Emissions|CO2".to_string(), results[2]);
+        self.solve_with_step_size(
+            t_current,
+            t_next,
+            input_state,
+            self.solver_options.step_size,
+            TimeUnit::Year,
+        )
+    }
 
-        Ok(OutputState::from_hashmap_and_verify(
-            output,
-            self.output_names(),
-        ))
+    fn solve_with_context(
+        &self,
+        context: &mut TimestepContext,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        self.solve_with_step_size(
+            context.t_current(),
+            context.t_next(),
+            input_state,
+            context.solver_step_size(self.solver_options.step_size),
+            context.time_unit(),
+        )
     }
 }
 
+impl StatefulIVP<ModelState> for CarbonCycleComponent {}
+
 impl IVP<Time, ModelState> for CarbonCycleComponent {
     fn calculate_dy_dt(
         &self,
@@ -129,7 +156,7 @@ impl IVP<Time, ModelState> for CarbonCycleComponent {
         let uptake = (conc - self.parameters.conc_pi) / lifetime; // ppm / yr
 
         dy_dt[0] = emissions / GTC_PER_PPM - uptake; // ppm / yr
-        dy_dt[1] = uptake * GTC_PER_PPM; // GtC / yr
-        dy_dt[2] = *emissions // GtC / yr
+        dy_dt[1] = *emissions; // GtC / yr
+        dy_dt[2] = uptake * GTC_PER_PPM; // GtC / yr
     }
 }
@@ -0,0 +1,148 @@
+/// Methane effective radiative forcing
+use rscm_core::component::{
+    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+};
+use rscm_core::errors::RSCMResult;
+use rscm_core::timeseries::{FloatValue, Time};
+use serde::{Deserialize, Serialize};
+
+/// Parameters for the methane ERF component
+///
+/// The default values are the coefficients from Meinshausen et al. (2020),
+/// the 1750 concentration used in that paper and the tropospheric adjustment from AR6.
+/// Any parameters missing when deserialising use these defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CH4ERFParameters {
+    /// Dependence of the forcing on the CH_4 concentration
+    /// unit: W / m^2 / ppb
+    pub a3: FloatValue,
+    /// Dependence of the forcing on the N_2O concentration (band overlap)
+    /// unit: W / m^2 / ppb
+    pub b3: FloatValue,
+    /// Constant term
+    /// unit: W / m^2 / ppb^0.5
+    pub d3: FloatValue,
+    /// Pre-industrial atmospheric CH_4 concentration
+    /// unit: ppb
+    pub conc_pi: FloatValue,
+    /// Ratio of the effective radiative forcing to the stratospherically-adjusted forcing
+    /// unit: dimensionless
+    pub adjustment: FloatValue,
+}
+
+impl Default for CH4ERFParameters {
+    fn default() -> Self {
+        Self {
+            a3: -8.9603e-5,
+            b3: -1.2462e-4,
+            d3: 0.045194,
+            conc_pi: 731.41,
+            adjustment: 0.86,
+        }
+    }
+}
+
+/// Methane effective radiative forcing (ERF) calculations
+///
+/// Uses the simplified expression from Meinshausen et al. (2020),
+/// which includes the overlap between the CH_4 and N_2O absorption bands.
+///
+/// # Notes
+///
+/// ```math
+///     \text{ERF} = f (a_3 \sqrt{M} + b_3 \sqrt{N} + d_3) (\sqrt{M} - \sqrt{M_{pi}})
+/// ```
+///
+/// where $M$ and $N$ are the CH_4 and N_2O concentrations and $f$ is the adjustment.
+///
+/// See [`meinshausen_et_al_2020`].
+///
+/// [`meinshausen_et_al_2020`]: https://doi.org/10.5194/gmd-13-3571-2020
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CH4ERF {
+    parameters: CH4ERFParameters,
+}
+
+impl CH4ERF {
+    pub fn from_parameters(parameters: CH4ERFParameters) -> Self {
+        Self { parameters }
+    }
+}
+
+#[typetag::serde]
+impl Component for CH4ERF {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new(
+                "Atmospheric Concentration|CH4",
+                "ppb",
+                RequirementType::Input,
+            ),
+            RequirementDefinition::new(
+                "Atmospheric Concentration|N2O",
+                "ppb",
+                RequirementType::Input,
+            ),
+            RequirementDefinition::new(
+                "Effective Radiative Forcing|CH4",
+                "W / m^2",
+                RequirementType::Output,
+            ),
+        ]
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let p = &self.parameters;
+        let sqrt_ch4 = input_state.get("Atmospheric Concentration|CH4").sqrt();
+        let sqrt_n2o = input_state.get("Atmospheric Concentration|N2O").sqrt();
+
+        let erf = p.adjustment
+            * (p.a3 * sqrt_ch4 + p.b3 * sqrt_n2o + p.d3)
+            * (sqrt_ch4 - p.conc_pi.sqrt());
+
+        Ok(OutputState::from_vectors(vec![erf], self.output_names()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn erf(component: &CH4ERF, ch4: FloatValue, n2o: FloatValue) -> FloatValue {
+        let input_state = InputState::from_vectors(
+            vec![ch4, n2o],
+            vec![
+                "Atmospheric Concentration|CH4".to_string(),
+                "Atmospheric Concentration|N2O".to_string(),
+            ],
+        );
+        *component
+            .solve(2019.0, 2020.0, &input_state)
+            .unwrap()
+            .get("Effective Radiative Forcing|CH4")
+    }
+
+    #[test]
+    fn present_day() {
+        let component = CH4ERF::from_parameters(CH4ERFParameters::default());
+
+        assert_eq!(erf(&component, 731.41, 273.87), 0.0);
+        // Close to the AR6 assessment of 0.54 W / m^2 for 2019
+        assert_relative_eq!(erf(&component, 1866.3, 332.1), 0.54, max_relative = 0.02);
+    }
+
+    #[test]
+    fn band_overlap() {
+        let component = CH4ERF::from_parameters(CH4ERFParameters::default());
+
+        // More N2O absorbs more of the shared band, reducing the CH4 forcing
+        assert!(erf(&component, 1866.3, 400.0) < erf(&component, 1866.3, 332.1));
+    }
+}
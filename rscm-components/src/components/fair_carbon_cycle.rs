@@ -0,0 +1,365 @@
+/// Multi-box atmospheric CO_2 component following FaIR
+use crate::constants::GTC_PER_PPM;
+use rscm_core::component::{
+    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+    TimestepContext,
+};
+use rscm_core::errors::RSCMResult;
+use rscm_core::timeseries::{FloatValue, Time};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Number of atmospheric boxes
+pub const N_BOXES: usize = 4;
+
+/// Parameters for the FaIR carbon cycle component
+///
+/// The default values are those used in FaIR v1.3,
+/// with a pre-industrial concentration of 278 ppm.
+/// Any parameters missing when deserialising use these defaults.
+///
+/// The values used in FaIR v1.3 are:
+///
+/// | Parameter     | Value                              |
+/// |---------------|------------------------------------|
+/// | `partition`   | [0.2173, 0.2240, 0.2824, 0.2763]   |
+/// | `tau`         | [1.0e6, 394.4, 36.54, 4.304]       |
+/// | `r0`          | 35.0                               |
+/// | `r_uptake`    | 0.019                              |
+/// | `r_temperature` | 4.165                            |
+/// | `r_atmosphere`  | 0.0                              |
+/// | `iirf_max`    | 97.0                               |
+/// | `iirf_horizon`| 100.0                              |
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FaIRCarbonCycleParameters {
+    /// Fraction of emissions which enter each box
+    ///
+    /// Should sum to 1
+    /// unit: dimensionless
+    pub partition: [FloatValue; N_BOXES],
+    /// Unperturbed decay timescale of each box
+    /// unit: yr
+    pub tau: [FloatValue; N_BOXES],
+    /// Pre-industrial time-integrated airborne fraction
+    /// unit: yr
+    pub r0: FloatValue,
+    /// Sensitivity of the time-integrated airborne fraction to cumulative uptake of carbon
+    /// by the land and ocean
+    /// unit: yr / GtC
+    pub r_uptake: FloatValue,
+    /// Sensitivity of the time-integrated airborne fraction to global-mean temperature
+    /// unit: yr / K
+    pub r_temperature: FloatValue,
+    /// Sensitivity of the time-integrated airborne fraction to the excess atmospheric burden
+    /// unit: yr / GtC
+    pub r_atmosphere: FloatValue,
+    /// Maximum value of the time-integrated airborne fraction
+    ///
+    /// Must be less than `iirf_horizon`
+    /// unit: yr
+    pub iirf_max: FloatValue,
+    /// Time horizon over which the airborne fraction is integrated
+    /// unit: yr
+    pub iirf_horizon: FloatValue,
+    /// Pre-industrial atmospheric CO_2 concentration
+    /// unit: ppm
+    pub conc_pi: FloatValue,
+}
+
+impl Default for FaIRCarbonCycleParameters {
+    fn default() -> Self {
+        Self {
+            partition: [0.2173, 0.2240, 0.2824, 0.2763],
+            tau: [1.0e6, 394.4, 36.54, 4.304],
+            r0: 35.0,
+            r_uptake: 0.019,
+            r_temperature: 4.165,
+            r_atmosphere: 0.0,
+            iirf_max: 97.0,
+            iirf_horizon: 100.0,
+            conc_pi: 278.0,
+        }
+    }
+}
+
+/// Multi-box impulse-response model of atmospheric CO_2 with saturating uptake
+///
+/// Emissions are partitioned into four boxes which decay with different timescales.
+/// The timescales of all boxes are scaled by a state-dependent factor, $\alpha$,
+/// which is chosen so that the time-integrated impulse response function over
+/// $H$ years (iIRF) matches a linear function of cumulative uptake,
+/// temperature and the atmospheric burden.
+/// This captures the weakening of carbon sinks as they take up carbon and as the climate warms.
+///
+/// The box burdens and cumulative emissions are state variables.
+/// If no initial values are provided, these default to zero (i.e. pre-industrial).
+///
+/// # Notes
+///
+/// ```math
+///     \text{iIRF} = \min(r_0 + r_u G_u + r_T T + r_a G_a, \text{iIRF}_{max}) \\
+///
+///     \text{iIRF} = \sum_i \alpha a_i \tau_i (1 - \exp(-H / (\alpha \tau_i))) \\
+///
+///     R_i(t + \Delta t) = R_i(t) \exp(-\Delta t / (\alpha \tau_i))
+///         + a_i E \alpha \tau_i (1 - \exp(-\Delta t / (\alpha \tau_i))) \\
+///
+///     C = C_{pi} + \sum_i R_i / k
+/// ```
+///
+/// where $G_u$ is the cumulative uptake by the land and ocean,
+/// $G_a$ is the excess atmospheric burden, $E$ are the emissions over the step
+/// and $k$ is the conversion from GtC to ppm.
+///
+/// See [`millar_et_al_2017`] and [`smith_et_al_2018`].
+///
+/// [`millar_et_al_2017`]: https://doi.org/10.5194/acp-17-7213-2017
+/// [`smith_et_al_2018`]: https://doi.org/10.5194/gmd-11-2273-2018
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FaIRCarbonCycleComponent {
+    parameters: FaIRCarbonCycleParameters,
+}
+
+impl FaIRCarbonCycleComponent {
+    pub fn from_parameters(parameters: FaIRCarbonCycleParameters) -> Self {
+        Self { parameters }
+    }
+
+    /// Name of the variable holding the excess carbon in a box
+    pub fn box_name(index: usize) -> String {
+        format!("Atmospheric CO2 Pool|Box {}", index + 1)
+    }
+
+    /// Time-integrated airborne fraction for a given scaling of the box timescales
+    fn iirf(&self, alpha: FloatValue) -> FloatValue {
+        let horizon = self.parameters.iirf_horizon;
+        self.parameters
+            .partition
+            .iter()
+            .zip(self.parameters.tau.iter())
+            .map(|(a, tau)| alpha * a * tau * (1.0 - (-horizon / (alpha * tau)).exp()))
+            .sum()
+    }
+
+    /// Find the scaling of the box timescales which reproduces a target iIRF
+    ///
+    /// The iIRF increases monotonically with alpha so a bisection in log-space is used.
+    fn calculate_alpha(&self, iirf_target: FloatValue) -> FloatValue {
+        let mut lower: FloatValue = 1e-10_f64.ln();
+        let mut upper: FloatValue = 1e4_f64.ln();
+
+        for _ in 0..100 {
+            let mid = 0.5 * (lower + upper);
+            if self.iirf(mid.exp()) < iirf_target {
+                lower = mid;
+            } else {
+                upper = mid;
+            }
+        }
+
+        (0.5 * (lower + upper)).exp()
+    }
+
+    /// Solve a time step of length `dt`
+    ///
+    /// unit: yr
+    fn solve_step(&self, dt: Time, input_state: &InputState) -> RSCMResult<OutputState> {
+        let emissions = *input_state.get("Emissions|CO2|Anthropogenic");
+        let temperature = *input_state.get("Surface Temperature");
+        let cumulative_emissions = *input_state.get("Cumulative Emissions|CO2");
+        let pools: Vec<FloatValue> = (0..N_BOXES)
+            .map(|i| *input_state.get(&Self::box_name(i)))
+            .collect();
+
+        let burden: FloatValue = pools.iter().sum();
+        let cumulative_uptake = cumulative_emissions - burden;
+
+        let iirf = (self.parameters.r0
+            + self.parameters.r_uptake * cumulative_uptake
+            + self.parameters.r_temperature * temperature
+            + self.parameters.r_atmosphere * burden)
+            .min(self.parameters.iirf_max);
+        let alpha = self.calculate_alpha(iirf);
+
+        let mut output = HashMap::new();
+        let mut burden_next = 0.0;
+        (0..N_BOXES).for_each(|i| {
+            let timescale = alpha * self.parameters.tau[i];
+            let decay = (-dt / timescale).exp();
+            let pool = pools[i] * decay
+                + self.parameters.partition[i] * emissions * timescale * (1.0 - decay);
+
+            burden_next += pool;
+            output.insert(Self::box_name(i), pool);
+        });
+
+        output.insert(
+            "Atmospheric Concentration|CO2".to_string(),
+            self.parameters.conc_pi + burden_next / GTC_PER_PPM,
+        );
+        output.insert(
+            "Cumulative Emissions|CO2".to_string(),
+            cumulative_emissions + emissions * dt,
+        );
+        output.insert("Carbon Cycle|Timescale Scaling".to_string(), alpha);
+
+        Ok(OutputState::from_hashmap_and_verify(
+            output,
+            self.output_names(),
+        ))
+    }
+}
+
+#[typetag::serde]
+impl Component for FaIRCarbonCycleComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        let mut definitions = vec![
+            RequirementDefinition::new(
+                "Emissions|CO2|Anthropogenic",
+                "GtC / yr",
+                RequirementType::Input,
+            ),
+            RequirementDefinition::new("Surface Temperature", "K", RequirementType::Input),
+            RequirementDefinition::new(
+                "Cumulative Emissions|CO2",
+                "Gt C",
+                RequirementType::InputAndOutput,
+            )
+            .with_default(0.0),
+            RequirementDefinition::new(
+                "Atmospheric Concentration|CO2",
+                "ppm",
+                RequirementType::Output,
+            )
+            .with_bounds(Some(0.0), None),
+            RequirementDefinition::new(
+                "Carbon Cycle|Timescale Scaling",
+                "dimensionless",
+                RequirementType::Output,
+            ),
+        ];
+        definitions.extend((0..N_BOXES).map(|i| {
+            RequirementDefinition::new(&Self::box_name(i), "Gt C", RequirementType::InputAndOutput)
+                .with_default(0.0)
+        }));
+        definitions
+    }
+
+    fn solve(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        self.solve_step(t_next - t_current, input_state)
+    }
+
+    fn solve_with_context(
+        &self,
+        context: &mut TimestepContext,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        self.solve_step(context.step_in_years(), input_state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn parameters() -> FaIRCarbonCycleParameters {
+        FaIRCarbonCycleParameters::default()
+    }
+
+    fn input_state(
+        emissions: FloatValue,
+        temperature: FloatValue,
+        cumulative_emissions: FloatValue,
+        pools: [FloatValue; N_BOXES],
+    ) -> InputState {
+        let mut names = vec![
+            "Emissions|CO2|Anthropogenic".to_string(),
+            "Surface Temperature".to_string(),
+            "Cumulative Emissions|CO2".to_string(),
+        ];
+        names.extend((0..N_BOXES).map(FaIRCarbonCycleComponent::box_name));
+        let mut values = vec![emissions, temperature, cumulative_emissions];
+        values.extend(pools);
+
+        InputState::from_vectors(values, names)
+    }
+
+    #[test]
+    fn alpha_reproduces_iirf() {
+        let component = FaIRCarbonCycleComponent::from_parameters(parameters());
+
+        [10.0, 32.4, 60.0, 97.0].iter().for_each(|target| {
+            let alpha = component.calculate_alpha(*target);
+            assert_relative_eq!(component.iirf(alpha), *target, max_relative = 1e-8);
+        });
+    }
+
+    #[test]
+    fn pulse_decays() {
+        let component = FaIRCarbonCycleComponent::from_parameters(parameters());
+
+        // A single year of emissions starting from pre-industrial
+        let output = component
+            .solve(2000.0, 2001.0, &input_state(10.0, 0.0, 0.0, [0.0; N_BOXES]))
+            .unwrap();
+
+        let burden: FloatValue = (0..N_BOXES)
+            .map(|i| *output.get(&FaIRCarbonCycleComponent::box_name(i)))
+            .sum();
+        assert!(burden > 0.0 && burden < 10.0);
+        assert_relative_eq!(
+            *output.get("Atmospheric Concentration|CO2"),
+            278.0 + burden / GTC_PER_PPM
+        );
+        assert_eq!(*output.get("Cumulative Emissions|CO2"), 10.0);
+    }
+
+    #[test]
+    fn uptake_saturates() {
+        let component = FaIRCarbonCycleComponent::from_parameters(parameters());
+        let pools = [100.0, 50.0, 30.0, 10.0];
+        let retained = |temperature: FloatValue, cumulative_emissions: FloatValue| {
+            let output = component
+                .solve(
+                    2000.0,
+                    2001.0,
+                    &input_state(0.0, temperature, cumulative_emissions, pools),
+                )
+                .unwrap();
+            (
+                *output.get("Atmospheric Concentration|CO2"),
+                *output.get("Carbon Cycle|Timescale Scaling"),
+            )
+        };
+
+        let (conc_reference, alpha_reference) = retained(0.0, 400.0);
+        // Warming and additional cumulative uptake both weaken the sinks
+        let (conc_warm, alpha_warm) = retained(2.0, 400.0);
+        let (conc_uptake, alpha_uptake) = retained(0.0, 800.0);
+
+        assert!(alpha_warm > alpha_reference);
+        assert!(alpha_uptake > alpha_reference);
+        assert!(conc_warm > conc_reference);
+        assert!(conc_uptake > conc_reference);
+    }
+
+    #[test]
+    fn partial_parameters_use_defaults() {
+        let parameters: FaIRCarbonCycleParameters =
+            serde_json::from_str(r#"{"r_temperature": 0.0, "conc_pi": 284.3}"#).unwrap();
+
+        assert_eq!(parameters.r_temperature, 0.0);
+        assert_eq!(parameters.conc_pi, 284.3);
+        assert_eq!(parameters.tau, FaIRCarbonCycleParameters::default().tau);
+
+        // FaIR v1.3 rather than Millar et al. (2017), which uses 32.4 yr
+        assert_eq!(FaIRCarbonCycleParameters::default().r0, 35.0);
+    }
+}
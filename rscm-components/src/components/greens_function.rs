@@ -0,0 +1,181 @@
+/// Temperature emulator using an analytic Green's function
+use rscm_core::component::{
+    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+    TimestepContext,
+};
+use rscm_core::convolution::{convolve, exponential_kernel};
+use rscm_core::errors::RSCMResult;
+use rscm_core::timeseries::{FloatValue, Time};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Parameters for the Green's function temperature emulator
+///
+/// The response to a unit step in ERF is `sum(q[i] * (1 - exp(-t / d[i])))`.
+/// Typically two or three modes are used,
+/// for example the AR5 impulse response uses `q = [0.33, 0.41]` and `d = [8.4, 409.5]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GreensFunctionTemperatureParameters {
+    /// Equilibrium temperature response of each mode to a unit ERF
+    /// unit: K / (W / m^2)
+    pub q: Vec<FloatValue>,
+    /// Response timescale of each mode
+    /// unit: yr
+    pub d: Vec<FloatValue>,
+}
+
+/// Surface temperature from ERF using a sum of exponential response functions
+///
+/// This is a fast emulator of the ODE-based two-layer model.
+/// The temperature of each mode is carried as state so that the model only needs the current ERF,
+/// which is exactly equivalent to convolving the ERF (assumed constant over each step) with the
+/// response function (see [`GreensFunctionTemperature::response`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GreensFunctionTemperature {
+    parameters: GreensFunctionTemperatureParameters,
+}
+
+impl GreensFunctionTemperature {
+    /// Create a new component
+    ///
+    /// Panics if `q` and `d` have different lengths.
+    pub fn from_parameters(parameters: GreensFunctionTemperatureParameters) -> Self {
+        assert_eq!(
+            parameters.q.len(),
+            parameters.d.len(),
+            "Expected the same number of amplitudes and timescales"
+        );
+        Self { parameters }
+    }
+
+    /// Name of the variable containing the temperature of a mode
+    pub fn mode_name(index: usize) -> String {
+        format!("Surface Temperature|Mode {}", index + 1)
+    }
+
+    /// Temperature response to an ERF timeseries with a constant step
+    ///
+    /// `erf[n]` is the ERF during step `n`
+    /// and the result contains the temperature at the end of each step, starting from zero.
+    pub fn response(&self, erf: &[FloatValue], step: Time) -> Vec<FloatValue> {
+        let kernel = exponential_kernel(&self.parameters.q, &self.parameters.d, step, erf.len());
+        convolve(erf, &kernel)
+    }
+
+    /// Solve a time step of length `dt`
+    ///
+    /// unit: yr
+    fn solve_step(&self, dt: Time, input_state: &InputState) -> RSCMResult<OutputState> {
+        let erf = *input_state.get("Effective Radiative Forcing");
+
+        let mut output = HashMap::new();
+        let mut temperature = 0.0;
+        self.parameters
+            .q
+            .iter()
+            .zip(&self.parameters.d)
+            .enumerate()
+            .for_each(|(i, (q, d))| {
+                let decay = (-dt / d).exp();
+                let mode = input_state.get(&Self::mode_name(i)) * decay + q * erf * (1.0 - decay);
+
+                temperature += mode;
+                output.insert(Self::mode_name(i), mode);
+            });
+        output.insert("Surface Temperature".to_string(), temperature);
+
+        Ok(OutputState::from_hashmap_and_verify(
+            output,
+            self.output_names(),
+        ))
+    }
+}
+
+#[typetag::serde]
+impl Component for GreensFunctionTemperature {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        let mut definitions = vec![
+            RequirementDefinition::new(
+                "Effective Radiative Forcing",
+                "W/m^2",
+                RequirementType::Input,
+            ),
+            RequirementDefinition::new("Surface Temperature", "K", RequirementType::Output),
+        ];
+        definitions.extend((0..self.parameters.q.len()).map(|i| {
+            RequirementDefinition::new(&Self::mode_name(i), "K", RequirementType::InputAndOutput)
+                .with_default(0.0)
+        }));
+        definitions
+    }
+
+    fn solve(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        self.solve_step(t_next - t_current, input_state)
+    }
+
+    fn solve_with_context(
+        &self,
+        context: &mut TimestepContext,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        self.solve_step(context.step_in_years(), input_state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn parameters() -> GreensFunctionTemperatureParameters {
+        GreensFunctionTemperatureParameters {
+            q: vec![0.33, 0.41],
+            d: vec![8.4, 409.5],
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_modes() {
+        GreensFunctionTemperature::from_parameters(GreensFunctionTemperatureParameters {
+            q: vec![0.33, 0.41],
+            d: vec![8.4],
+        });
+    }
+
+    #[test]
+    fn step_response() {
+        let component = GreensFunctionTemperature::from_parameters(parameters());
+        let input_state = InputState::from_vectors(
+            vec![1.0, 0.0, 0.0],
+            vec![
+                "Effective Radiative Forcing".to_string(),
+                GreensFunctionTemperature::mode_name(0),
+                GreensFunctionTemperature::mode_name(1),
+            ],
+        );
+
+        let output = component.solve(2000.0, 2010.0, &input_state).unwrap();
+
+        let expected =
+            0.33 * (1.0 - (-10.0 / 8.4_f64).exp()) + 0.41 * (1.0 - (-10.0 / 409.5_f64).exp());
+        assert_relative_eq!(*output.get("Surface Temperature"), expected);
+    }
+
+    #[test]
+    fn response_matches_equilibrium() {
+        let component = GreensFunctionTemperature::from_parameters(parameters());
+
+        let response = component.response(&vec![3.7; 10000], 1.0);
+        assert_relative_eq!(
+            *response.last().unwrap(),
+            3.7 * (0.33 + 0.41),
+            max_relative = 1e-6
+        );
+    }
+}
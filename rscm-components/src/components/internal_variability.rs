@@ -0,0 +1,239 @@
+/// Stochastic internal variability of surface temperature
+use rand::Rng;
+use rscm_core::component::{
+    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+    TimestepContext,
+};
+use rscm_core::distributions::normal_ppf;
+use rscm_core::errors::{RSCMError, RSCMResult};
+use rscm_core::timeseries::{FloatValue, Time};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Parameters for the internal variability component
+///
+/// Observed annual-mean global temperatures have a standard deviation of about 0.1 K
+/// (a variance of 0.01 K^2) about the forced response
+/// and an autocorrelation of about 0.5 between consecutive years.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InternalVariabilityParameters {
+    /// Variance of the variability
+    /// unit: K^2
+    pub variance: FloatValue,
+    /// Correlation between the variability of consecutive years
+    /// unit: dimensionless
+    pub autocorrelation: FloatValue,
+}
+
+/// Internal variability added to the forced surface temperature using an AR(1) process
+///
+/// The variability evolves as
+///
+/// ```math
+/// \epsilon_{t + \Delta t} = \phi^{\Delta t} \epsilon_t + \sigma \sqrt{1 - \phi^{2 \Delta t}} z
+/// ```
+///
+/// where $\phi$ is the autocorrelation, $\sigma^2$ the variance, $\Delta t$ the length of the
+/// time step in years and $z$ is drawn from a standard normal distribution.
+/// The variance and the correlation between values a year apart therefore don't depend
+/// on the length of the time step.
+///
+/// The variability is carried as state, starting from zero,
+/// and output as "Surface Temperature|Internal Variability".
+/// "Surface Temperature|With Variability" is the sum of the forced "Surface Temperature"
+/// and the variability.
+///
+/// Random numbers are drawn from the component's random number stream,
+/// so the variability is reproducible for a given seed
+/// (see [`rscm_core::model::ModelBuilder::with_rng_seed`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InternalVariability {
+    parameters: InternalVariabilityParameters,
+}
+
+impl InternalVariability {
+    /// Create a new component
+    ///
+    /// Panics if the variance is negative or the autocorrelation isn't between 0 and 1.
+    pub fn from_parameters(parameters: InternalVariabilityParameters) -> Self {
+        assert!(parameters.variance >= 0.0, "Variance must not be negative");
+        assert!(
+            (0.0..1.0).contains(&parameters.autocorrelation),
+            "Autocorrelation must be between 0 and 1"
+        );
+        Self { parameters }
+    }
+
+    /// Variability at the end of a time step from the variability at the start
+    ///
+    /// `z` is a sample from a standard normal distribution.
+    pub fn evolve(
+        &self,
+        variability: FloatValue,
+        step_in_years: Time,
+        z: FloatValue,
+    ) -> FloatValue {
+        let p = &self.parameters;
+        let correlation = p.autocorrelation.powf(step_in_years);
+        correlation * variability + (p.variance * (1.0 - correlation.powi(2))).sqrt() * z
+    }
+}
+
+#[typetag::serde]
+impl Component for InternalVariability {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new("Surface Temperature", "K", RequirementType::Input),
+            RequirementDefinition::new(
+                "Surface Temperature|Internal Variability",
+                "K",
+                RequirementType::InputAndOutput,
+            )
+            .with_default(0.0),
+            RequirementDefinition::new(
+                "Surface Temperature|With Variability",
+                "K",
+                RequirementType::Output,
+            ),
+        ]
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        _input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        Err(RSCMError::Error(
+            "InternalVariability requires the random numbers of a TimestepContext".to_string(),
+        ))
+    }
+
+    fn solve_with_context(
+        &self,
+        context: &mut TimestepContext,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let z = normal_ppf(context.rng().gen_range(FloatValue::EPSILON..1.0));
+        let variability = self.evolve(
+            *input_state.get("Surface Temperature|Internal Variability"),
+            context.step_in_years(),
+            z,
+        );
+
+        let output = HashMap::from([
+            (
+                "Surface Temperature|Internal Variability".to_string(),
+                variability,
+            ),
+            (
+                "Surface Temperature|With Variability".to_string(),
+                input_state.get("Surface Temperature") + variability,
+            ),
+        ]);
+        Ok(OutputState::from_hashmap_and_verify(
+            output,
+            self.output_names(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use numpy::array;
+    use numpy::ndarray::Array;
+    use rscm_core::interpolate::strategies::{InterpolationStrategy, PreviousStrategy};
+    use rscm_core::model::{Model, ModelBuilder};
+    use rscm_core::timeseries::{TimeAxis, Timeseries};
+    use std::sync::Arc;
+
+    fn parameters() -> InternalVariabilityParameters {
+        InternalVariabilityParameters {
+            variance: 0.01,
+            autocorrelation: 0.5,
+        }
+    }
+
+    fn build(time_axis: TimeAxis) -> Model {
+        ModelBuilder::new()
+            .with_component(Arc::new(InternalVariability::from_parameters(parameters())))
+            .with_time_axis(time_axis)
+            .with_exogenous_variable(
+                "Surface Temperature",
+                Timeseries::new(
+                    array![1.0],
+                    Arc::new(TimeAxis::from_bounds(array![0.0, 1.0e4])),
+                    "K".to_string(),
+                    InterpolationStrategy::from(PreviousStrategy::new(true)),
+                ),
+            )
+            .build()
+    }
+
+    fn variability(model: &Model) -> Vec<FloatValue> {
+        model
+            .timeseries()
+            .get_timeseries_by_name("Surface Temperature|Internal Variability")
+            .unwrap()
+            .values()
+            .to_vec()
+    }
+
+    #[test]
+    fn statistics() {
+        let mut model = build(TimeAxis::from_values(Array::range(0.0, 5000.0, 1.0)));
+        model.run();
+
+        let values = variability(&model);
+        let n = values.len() as FloatValue;
+        let mean = values.iter().sum::<FloatValue>() / n;
+        let variance = values
+            .iter()
+            .map(|v| (v - mean).powi(2))
+            .sum::<FloatValue>()
+            / n;
+        let lag1 = values
+            .iter()
+            .zip(values.iter().skip(1))
+            .map(|(a, b)| (a - mean) * (b - mean))
+            .sum::<FloatValue>()
+            / n
+            / variance;
+
+        assert!(mean.abs() < 0.01, "{}", mean);
+        assert!((variance - 0.01).abs() < 0.001, "{}", variance);
+        assert!((lag1 - 0.5).abs() < 0.05, "{}", lag1);
+
+        // The variability is added to the forced temperature
+        let total = model
+            .timeseries()
+            .get_timeseries_by_name("Surface Temperature|With Variability")
+            .unwrap();
+        assert_eq!(total.at(10).unwrap(), 1.0 + values[10]);
+    }
+
+    #[test]
+    fn reproducible() {
+        let time_axis = TimeAxis::from_values(Array::range(2000.0, 2050.0, 1.0));
+        let mut first = build(time_axis.clone());
+        first.run();
+        let mut second = build(time_axis.clone());
+        second.run();
+        assert_eq!(variability(&first), variability(&second));
+
+        let mut reseeded = build(time_axis);
+        reseeded.reseed_rng_streams(1);
+        reseeded.run();
+        assert_ne!(variability(&first), variability(&reseeded));
+    }
+
+    #[test]
+    fn time_step() {
+        let component = InternalVariability::from_parameters(parameters());
+
+        // Two half-year steps have the same correlation as a single annual step
+        let half = component.evolve(component.evolve(1.0, 0.5, 0.0), 0.5, 0.0);
+        assert!((half - component.evolve(1.0, 1.0, 0.0)).abs() < 1e-12);
+    }
+}
@@ -1,6 +1,22 @@
+mod blended_temperature;
 mod carbon_cycle;
+mod ch4_erf;
 mod co2_erf;
+mod fair_carbon_cycle;
+mod greens_function;
+mod internal_variability;
+mod n2o_erf;
 pub mod ocean_carbon_cycle;
+mod pattern_scaling;
+mod two_layer;
 
+pub use blended_temperature::{BlendedTemperature, BlendedTemperatureParameters};
 pub use carbon_cycle::{CarbonCycleComponent, CarbonCycleParameters, SolverOptions};
+pub use ch4_erf::{CH4ERFParameters, CH4ERF};
 pub use co2_erf::{CO2ERFParameters, CO2ERF};
+pub use fair_carbon_cycle::{FaIRCarbonCycleComponent, FaIRCarbonCycleParameters};
+pub use greens_function::{GreensFunctionTemperature, GreensFunctionTemperatureParameters};
+pub use internal_variability::{InternalVariability, InternalVariabilityParameters};
+pub use n2o_erf::{N2OERFParameters, N2OERF};
+pub use pattern_scaling::{PatternScaling, PatternScalingParameters};
+pub use two_layer::{TwoLayerComponent, TwoLayerComponentParameters};
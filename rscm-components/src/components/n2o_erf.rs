@@ -0,0 +1,166 @@
+/// Nitrous oxide effective radiative forcing
+use rscm_core::component::{
+    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+};
+use rscm_core::errors::RSCMResult;
+use rscm_core::timeseries::{FloatValue, Time};
+use serde::{Deserialize, Serialize};
+
+/// Parameters for the nitrous oxide ERF component
+///
+/// The default values are the coefficients from Meinshausen et al. (2020),
+/// the 1750 concentration used in that paper and the tropospheric adjustment from AR6.
+/// Any parameters missing when deserialising use these defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct N2OERFParameters {
+    /// Dependence of the forcing on the CO_2 concentration (band overlap)
+    /// unit: W / m^2 / ppm^0.5 / ppb^0.5
+    pub a2: FloatValue,
+    /// Dependence of the forcing on the N_2O concentration
+    /// unit: W / m^2 / ppb
+    pub b2: FloatValue,
+    /// Dependence of the forcing on the CH_4 concentration (band overlap)
+    /// unit: W / m^2 / ppb
+    pub c2: FloatValue,
+    /// Constant term
+    /// unit: W / m^2 / ppb^0.5
+    pub d2: FloatValue,
+    /// Pre-industrial atmospheric N_2O concentration
+    /// unit: ppb
+    pub conc_pi: FloatValue,
+    /// Ratio of the effective radiative forcing to the stratospherically-adjusted forcing
+    /// unit: dimensionless
+    pub adjustment: FloatValue,
+}
+
+impl Default for N2OERFParameters {
+    fn default() -> Self {
+        Self {
+            a2: -3.4197e-4,
+            b2: 2.5455e-4,
+            c2: -2.4357e-4,
+            d2: 0.12173,
+            conc_pi: 273.87,
+            adjustment: 1.07,
+        }
+    }
+}
+
+/// Nitrous oxide effective radiative forcing (ERF) calculations
+///
+/// Uses the simplified expression from Meinshausen et al. (2020),
+/// which includes the overlap of the N_2O absorption bands with those of CO_2 and CH_4.
+///
+/// # Notes
+///
+/// ```math
+///     \text{ERF} = f (a_2 \sqrt{C} + b_2 \sqrt{N} + c_2 \sqrt{M} + d_2) (\sqrt{N} - \sqrt{N_{pi}})
+/// ```
+///
+/// where $C$, $M$ and $N$ are the CO_2, CH_4 and N_2O concentrations
+/// and $f$ is the adjustment.
+///
+/// See [`meinshausen_et_al_2020`].
+///
+/// [`meinshausen_et_al_2020`]: https://doi.org/10.5194/gmd-13-3571-2020
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct N2OERF {
+    parameters: N2OERFParameters,
+}
+
+impl N2OERF {
+    pub fn from_parameters(parameters: N2OERFParameters) -> Self {
+        Self { parameters }
+    }
+}
+
+#[typetag::serde]
+impl Component for N2OERF {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new(
+                "Atmospheric Concentration|CO2",
+                "ppm",
+                RequirementType::Input,
+            ),
+            RequirementDefinition::new(
+                "Atmospheric Concentration|CH4",
+                "ppb",
+                RequirementType::Input,
+            ),
+            RequirementDefinition::new(
+                "Atmospheric Concentration|N2O",
+                "ppb",
+                RequirementType::Input,
+            ),
+            RequirementDefinition::new(
+                "Effective Radiative Forcing|N2O",
+                "W / m^2",
+                RequirementType::Output,
+            ),
+        ]
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let p = &self.parameters;
+        let sqrt_co2 = input_state.get("Atmospheric Concentration|CO2").sqrt();
+        let sqrt_ch4 = input_state.get("Atmospheric Concentration|CH4").sqrt();
+        let sqrt_n2o = input_state.get("Atmospheric Concentration|N2O").sqrt();
+
+        let erf = p.adjustment
+            * (p.a2 * sqrt_co2 + p.b2 * sqrt_n2o + p.c2 * sqrt_ch4 + p.d2)
+            * (sqrt_n2o - p.conc_pi.sqrt());
+
+        Ok(OutputState::from_vectors(vec![erf], self.output_names()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn erf(component: &N2OERF, co2: FloatValue, ch4: FloatValue, n2o: FloatValue) -> FloatValue {
+        let input_state = InputState::from_vectors(
+            vec![co2, ch4, n2o],
+            vec![
+                "Atmospheric Concentration|CO2".to_string(),
+                "Atmospheric Concentration|CH4".to_string(),
+                "Atmospheric Concentration|N2O".to_string(),
+            ],
+        );
+        *component
+            .solve(2019.0, 2020.0, &input_state)
+            .unwrap()
+            .get("Effective Radiative Forcing|N2O")
+    }
+
+    #[test]
+    fn present_day() {
+        let component = N2OERF::from_parameters(N2OERFParameters::default());
+
+        assert_eq!(erf(&component, 410.1, 1866.3, 273.87), 0.0);
+        // AR6 assessed 0.21 W / m^2 for 2019 relative to a lower 1750 concentration
+        assert_relative_eq!(
+            erf(&component, 410.1, 1866.3, 332.1),
+            0.21,
+            max_relative = 0.1
+        );
+    }
+
+    #[test]
+    fn band_overlap() {
+        let component = N2OERF::from_parameters(N2OERFParameters::default());
+        let reference = erf(&component, 410.1, 1866.3, 332.1);
+
+        // More CO2 or CH4 absorbs more of the shared bands, reducing the N2O forcing
+        assert!(erf(&component, 600.0, 1866.3, 332.1) < reference);
+        assert!(erf(&component, 410.1, 2500.0, 332.1) < reference);
+    }
+}
@@ -0,0 +1,365 @@
+/// Mixed-layer ocean carbon uptake
+use ode_solvers::Vector2;
+use rscm_core::component::{
+    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+    TimestepContext,
+};
+use rscm_core::errors::RSCMResult;
+use rscm_core::ivp::{IVPBuilder, StatefulIVP, IVP};
+use rscm_core::timeseries::{FloatValue, Time, TimeUnit};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+type ModelState = Vector2<FloatValue>;
+
+/// Step size used when integrating a time step
+///
+/// unit: yr
+const STEP_SIZE: Time = 0.1;
+
+/// Molar mass of carbon
+///
+/// unit: g / mol
+const MOLAR_MASS_CARBON: FloatValue = 12.011;
+
+/// Density of seawater
+///
+/// unit: kg / m^3
+const SEAWATER_DENSITY: FloatValue = 1026.0;
+
+/// Parameters for the mixed-layer ocean carbon cycle component
+///
+/// The defaults use the mixed-layer geometry, gas exchange rate and temperature sensitivity
+/// of Joos et al. (2001).
+/// The mixing timescale is chosen so that the ocean takes up around 2.5 GtC / yr
+/// under historical CO_2 concentrations.
+/// Any parameters missing when deserialising use these defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OceanCarbonCycleParameters {
+    /// Pre-industrial atmospheric CO_2 concentration,
+    /// which is in equilibrium with the ocean surface
+    /// unit: ppm
+    pub conc_pi: FloatValue,
+    /// Rate of air-sea gas exchange per unit difference in partial pressure
+    /// unit: GtC / yr / ppm
+    pub gas_exchange_rate: FloatValue,
+    /// Depth of the mixed layer
+    /// unit: m
+    pub mixed_layer_depth: FloatValue,
+    /// Surface area of the ocean
+    /// unit: m^2
+    pub ocean_area: FloatValue,
+    /// Pre-industrial dissolved inorganic carbon in the mixed layer
+    /// unit: micromol / kg
+    pub dic_pi: FloatValue,
+    /// Pre-industrial Revelle (buffer) factor
+    /// unit: dimensionless
+    pub revelle_factor_pi: FloatValue,
+    /// Increase in the Revelle factor with dissolved inorganic carbon
+    /// unit: kg / micromol
+    pub revelle_factor_sensitivity: FloatValue,
+    /// Sensitivity of the partial pressure to warming, due to the decrease in solubility
+    /// unit: 1 / K
+    pub temperature_sensitivity: FloatValue,
+    /// Timescale of the export of dissolved inorganic carbon into the deep ocean
+    /// unit: yr
+    pub mixing_timescale: FloatValue,
+}
+
+impl Default for OceanCarbonCycleParameters {
+    fn default() -> Self {
+        Self {
+            conc_pi: 278.0,
+            gas_exchange_rate: 2.13 / 9.06,
+            mixed_layer_depth: 75.0,
+            ocean_area: 3.5375e14,
+            dic_pi: 2000.0,
+            revelle_factor_pi: 9.0,
+            revelle_factor_sensitivity: 0.03,
+            temperature_sensitivity: 0.0423,
+            mixing_timescale: 10.0,
+        }
+    }
+}
+
+/// Uptake of CO_2 by a well-mixed ocean surface layer
+///
+/// CO_2 is exchanged between the atmosphere and the mixed layer in proportion to the
+/// difference in their partial pressures.
+/// The partial pressure of the mixed layer increases with its dissolved inorganic carbon
+/// according to the Revelle factor, which itself increases as carbon is taken up,
+/// and with warming as CO_2 becomes less soluble.
+/// Carbon is exported from the mixed layer into the deep ocean with a single timescale.
+///
+/// The change in dissolved inorganic carbon and the total carbon taken up by the ocean
+/// are state variables which start from zero (i.e. pre-industrial).
+///
+/// # Notes
+///
+/// ```math
+///     F = k (C_{atm} - p\text{CO}_{2_o}) \\
+///
+///     \frac{\delta p\text{CO}_{2_o}}{p\text{CO}_{2_o}}
+///         = R \frac{\delta \text{DIC}}{\text{DIC}_{pi}}, \quad R = R_{pi} + r \Delta \text{DIC} \\
+///
+///     p\text{CO}_{2_o} = C_{pi} \exp \left(
+///         \frac{R_{pi} \Delta \text{DIC} + r \Delta \text{DIC}^2 / 2}{\text{DIC}_{pi}}
+///         + \alpha_T \Delta T
+///     \right) \\
+///
+///     \frac{d \Delta \text{DIC}}{dt} = c F - \frac{\Delta \text{DIC}}{\tau}
+/// ```
+///
+/// where $c$ converts GtC into a concentration in the mixed layer
+/// and $\Delta T$ is the change in surface temperature.
+///
+/// See [`joos_et_al_2001`] and [`takahashi_et_al_1993`].
+///
+/// [`joos_et_al_2001`]: https://doi.org/10.1029/2000GB001375
+/// [`takahashi_et_al_1993`]: https://doi.org/10.1029/93GB02263
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OceanCarbonCycle {
+    parameters: OceanCarbonCycleParameters,
+}
+
+impl OceanCarbonCycle {
+    pub fn from_parameters(parameters: OceanCarbonCycleParameters) -> Self {
+        Self { parameters }
+    }
+
+    /// Change in the dissolved inorganic carbon of the mixed layer per GtC taken up
+    ///
+    /// unit: micromol / kg / GtC
+    fn dic_per_gtc(&self) -> FloatValue {
+        let mass =
+            self.parameters.mixed_layer_depth * self.parameters.ocean_area * SEAWATER_DENSITY;
+        1e15 / MOLAR_MASS_CARBON * 1e6 / mass
+    }
+
+    /// Partial pressure of CO_2 in the mixed layer
+    ///
+    /// unit: ppm
+    fn partial_pressure(&self, delta_dic: FloatValue, delta_temperature: FloatValue) -> FloatValue {
+        let p = &self.parameters;
+        let buffer = (p.revelle_factor_pi * delta_dic
+            + 0.5 * p.revelle_factor_sensitivity * delta_dic.powi(2))
+            / p.dic_pi;
+        p.conc_pi * (buffer + p.temperature_sensitivity * delta_temperature).exp()
+    }
+
+    /// Flux of carbon from the atmosphere into the ocean
+    ///
+    /// unit: GtC / yr
+    fn flux(&self, input_state: &InputState, delta_dic: FloatValue) -> FloatValue {
+        let concentration = *input_state.get("Atmospheric Concentration|CO2");
+        let temperature = *input_state.get("Surface Temperature");
+        self.parameters.gas_exchange_rate
+            * (concentration - self.partial_pressure(delta_dic, temperature))
+    }
+
+    fn solve_with_step_size(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &InputState,
+        step_size: Time,
+        time_unit: TimeUnit,
+    ) -> RSCMResult<OutputState> {
+        let mut output_state =
+            IVPBuilder::from_input_state(Arc::new(self.to_owned()), input_state.clone())
+                .with_time_unit(time_unit)
+                .solve_step(t_current, t_next, step_size)?;
+
+        // The mean flux over the step is consistent with the change in the ocean carbon pool
+        let uptake = output_state.get("Carbon|Ocean") - input_state.get("Carbon|Ocean");
+        let dt = (t_next - t_current) * time_unit.in_years();
+        let partial_pressure = self.partial_pressure(
+            *output_state.get("Dissolved Inorganic Carbon"),
+            *input_state.get("Surface Temperature"),
+        );
+        output_state.merge(OutputState::from_vectors(
+            vec![uptake / dt, partial_pressure],
+            vec![
+                "Carbon Flux|Ocean".to_string(),
+                "Ocean Surface Partial Pressure|CO2".to_string(),
+            ],
+        ));
+
+        Ok(output_state)
+    }
+}
+
+#[typetag::serde]
+impl Component for OceanCarbonCycle {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new(
+                "Atmospheric Concentration|CO2",
+                "ppm",
+                RequirementType::Input,
+            ),
+            RequirementDefinition::new("Surface Temperature", "K", RequirementType::Input),
+            RequirementDefinition::new(
+                "Dissolved Inorganic Carbon",
+                "micromol / kg",
+                RequirementType::InputAndOutput,
+            )
+            .with_default(0.0),
+            RequirementDefinition::new("Carbon|Ocean", "GtC", RequirementType::InputAndOutput)
+                .with_default(0.0),
+            RequirementDefinition::new("Carbon Flux|Ocean", "GtC / yr", RequirementType::Output),
+            RequirementDefinition::new(
+                "Ocean Surface Partial Pressure|CO2",
+                "ppm",
+                RequirementType::Output,
+            )
+            .with_bounds(Some(0.0), None),
+        ]
+    }
+
+    fn solve(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        self.solve_with_step_size(t_current, t_next, input_state, STEP_SIZE, TimeUnit::Year)
+    }
+
+    fn solve_with_context(
+        &self,
+        context: &mut TimestepContext,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        self.solve_with_step_size(
+            context.t_current(),
+            context.t_next(),
+            input_state,
+            context.solver_step_size(STEP_SIZE),
+            context.time_unit(),
+        )
+    }
+}
+
+impl StatefulIVP<ModelState> for OceanCarbonCycle {}
+
+impl IVP<Time, ModelState> for OceanCarbonCycle {
+    fn calculate_dy_dt(
+        &self,
+        _t: Time,
+        input_state: &InputState,
+        y: &ModelState,
+        dy_dt: &mut ModelState,
+    ) {
+        let delta_dic = y[0];
+        let flux = self.flux(input_state, delta_dic);
+
+        dy_dt[0] = self.dic_per_gtc() * flux - delta_dic / self.parameters.mixing_timescale;
+        dy_dt[1] = flux;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use numpy::ndarray::Array;
+    use rscm_core::model::ModelBuilder;
+    use rscm_core::timeseries::{TimeAxis, Timeseries};
+
+    fn input_state(
+        concentration: FloatValue,
+        temperature: FloatValue,
+        delta_dic: FloatValue,
+        carbon: FloatValue,
+    ) -> InputState {
+        InputState::from_vectors(
+            vec![concentration, temperature, delta_dic, carbon],
+            vec![
+                "Atmospheric Concentration|CO2".to_string(),
+                "Surface Temperature".to_string(),
+                "Dissolved Inorganic Carbon".to_string(),
+                "Carbon|Ocean".to_string(),
+            ],
+        )
+    }
+
+    #[test]
+    fn equilibrium() {
+        let component = OceanCarbonCycle::from_parameters(OceanCarbonCycleParameters::default());
+
+        let output = component
+            .solve(2000.0, 2001.0, &input_state(278.0, 0.0, 0.0, 0.0))
+            .unwrap();
+        assert_eq!(*output.get("Carbon Flux|Ocean"), 0.0);
+        assert_eq!(*output.get("Ocean Surface Partial Pressure|CO2"), 278.0);
+    }
+
+    #[test]
+    fn feedbacks() {
+        let component = OceanCarbonCycle::from_parameters(OceanCarbonCycleParameters::default());
+        let flux = |temperature: FloatValue, delta_dic: FloatValue| {
+            *component
+                .solve(
+                    2000.0,
+                    2001.0,
+                    &input_state(400.0, temperature, delta_dic, 0.0),
+                )
+                .unwrap()
+                .get("Carbon Flux|Ocean")
+        };
+
+        let reference = flux(0.0, 20.0);
+        assert!(reference > 0.0);
+        // Warming reduces solubility and a fuller mixed layer is more strongly buffered
+        assert!(flux(2.0, 20.0) < reference);
+        assert!(flux(0.0, 40.0) < reference);
+
+        // The Revelle factor increases the partial pressure more than proportionally
+        let p = OceanCarbonCycleParameters::default();
+        let increase =
+            |delta_dic: FloatValue| component.partial_pressure(delta_dic, 0.0) / p.conc_pi - 1.0;
+        assert_relative_eq!(
+            increase(1.0),
+            p.revelle_factor_pi / p.dic_pi,
+            max_relative = 0.01
+        );
+        assert!(increase(100.0) > 100.0 * increase(1.0));
+    }
+
+    #[test]
+    fn historical_uptake() {
+        let time = Array::range(1850.0, 2021.0, 1.0);
+        // Approximately exponential growth from 285 ppm in 1850 to 410 ppm in 2020
+        let concentration = time.mapv(|t: Time| 278.0 + 7.0 * ((t - 1850.0) / 58.0).exp());
+
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(time.clone()))
+            .with_component(Arc::new(OceanCarbonCycle::from_parameters(
+                OceanCarbonCycleParameters::default(),
+            )))
+            .with_exogenous_variable(
+                "Atmospheric Concentration|CO2",
+                Timeseries::from_values(concentration, time.clone()),
+            )
+            .with_exogenous_variable(
+                "Surface Temperature",
+                Timeseries::from_values(time.mapv(|t: Time| (t - 1850.0) / 170.0), time),
+            )
+            .build();
+        model.run();
+
+        let last = |name: &str| {
+            model
+                .timeseries()
+                .get_timeseries_by_name(name)
+                .unwrap()
+                .latest_value()
+                .unwrap()
+        };
+        let flux = last("Carbon Flux|Ocean");
+        // Global Carbon Budget estimates of recent ocean uptake are 2.5 +/- 0.6 GtC / yr
+        assert!((1.9..=3.1).contains(&flux));
+    }
+}
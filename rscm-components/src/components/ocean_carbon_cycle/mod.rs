@@ -1,5 +1,7 @@
+mod mixed_layer;
 mod ocean_surface_partial_pressure;
 
+pub use mixed_layer::{OceanCarbonCycle, OceanCarbonCycleParameters};
 pub use ocean_surface_partial_pressure::{
     OceanSurfacePartialPressure, OceanSurfacePartialPressureParameters,
 };
@@ -0,0 +1,182 @@
+/// Regional temperatures from global-mean temperature using pattern scaling
+use rscm_core::component::{
+    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+};
+use rscm_core::errors::RSCMResult;
+use rscm_core::timeseries::{FloatValue, Time};
+use serde::{Deserialize, Serialize};
+
+/// Parameters for the pattern scaling component
+///
+/// Patterns are typically derived by regressing the regional warming of a climate model
+/// against its global-mean warming, e.g. land regions warm faster than the global mean
+/// so have patterns greater than 1.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternScalingParameters {
+    /// Name of each region, used as the labels of the regional temperature
+    pub regions: Vec<String>,
+    /// Change in the temperature of each region per unit change in global-mean temperature
+    /// unit: K / K
+    pub patterns: Vec<FloatValue>,
+}
+
+/// Regional surface temperatures from the global-mean surface temperature
+///
+/// The temperature change of each region is assumed to scale linearly with the
+/// global-mean temperature change:
+///
+/// ```math
+/// T_r = p_r T
+/// ```
+///
+/// where `T` is the global-mean temperature change and `p_r` the pattern of region `r`.
+/// The regional temperatures are output as "Surface Temperature|Regional",
+/// a labelled variable with a label for each region,
+/// e.g. "Surface Temperature|Regional|Land".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternScaling {
+    parameters: PatternScalingParameters,
+}
+
+impl PatternScaling {
+    /// Create a new component
+    ///
+    /// Panics if there isn't a pattern for each region or if a region is repeated.
+    pub fn from_parameters(parameters: PatternScalingParameters) -> Self {
+        assert_eq!(
+            parameters.regions.len(),
+            parameters.patterns.len(),
+            "Expected a pattern for each region"
+        );
+        parameters
+            .regions
+            .iter()
+            .enumerate()
+            .for_each(|(i, region)| {
+                assert!(
+                    !parameters.regions[..i].contains(region),
+                    "Region '{}' is repeated",
+                    region
+                );
+            });
+        Self { parameters }
+    }
+
+    /// Temperature of each region from the global-mean temperature
+    pub fn scale(&self, temperature: FloatValue) -> Vec<FloatValue> {
+        self.parameters
+            .patterns
+            .iter()
+            .map(|pattern| pattern * temperature)
+            .collect()
+    }
+}
+
+#[typetag::serde]
+impl Component for PatternScaling {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        let regions: Vec<&str> = self.parameters.regions.iter().map(String::as_str).collect();
+        vec![
+            RequirementDefinition::new("Surface Temperature", "K", RequirementType::Input),
+            RequirementDefinition::new(
+                "Surface Temperature|Regional",
+                "K",
+                RequirementType::Output,
+            )
+            .with_labels(&regions),
+        ]
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let regional = self.scale(*input_state.get("Surface Temperature"));
+
+        Ok(OutputState::from_vectors(regional, self.output_names()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use numpy::array;
+    use numpy::ndarray::Array;
+    use rscm_core::interpolate::strategies::{InterpolationStrategy, PreviousStrategy};
+    use rscm_core::labelled::LabelledTimeseries;
+    use rscm_core::model::ModelBuilder;
+    use rscm_core::timeseries::{TimeAxis, Timeseries};
+    use std::sync::Arc;
+
+    fn parameters() -> PatternScalingParameters {
+        PatternScalingParameters {
+            regions: vec![
+                "Land".to_string(),
+                "Ocean".to_string(),
+                "Arctic".to_string(),
+            ],
+            patterns: vec![1.4, 0.85, 2.5],
+        }
+    }
+
+    #[test]
+    fn regional_temperature() {
+        let mut model = ModelBuilder::new()
+            .with_component(Arc::new(PatternScaling::from_parameters(parameters())))
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0)))
+            .with_exogenous_variable(
+                "Surface Temperature",
+                Timeseries::new(
+                    array![1.0, 1.2, 1.4],
+                    Arc::new(TimeAxis::from_values(array![2020.0, 2021.0, 2022.0])),
+                    "K".to_string(),
+                    InterpolationStrategy::from(PreviousStrategy::new(true)),
+                ),
+            )
+            .build();
+        model.run();
+
+        let regional = LabelledTimeseries::from_collection(
+            "Surface Temperature|Regional",
+            &parameters().regions,
+            model.timeseries(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(regional.labels(), parameters().regions);
+        // Outputs are solved at the end of each step
+        let values = regional.values();
+        assert_eq!(
+            values.row(2).to_vec(),
+            vec![1.4 * 1.2, 0.85 * 1.2, 2.5 * 1.2]
+        );
+        assert_eq!(
+            model
+                .timeseries()
+                .get_timeseries_by_name("Surface Temperature|Regional|Arctic")
+                .unwrap()
+                .at(1),
+            Some(2.5)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected a pattern for each region")]
+    fn missing_pattern() {
+        PatternScaling::from_parameters(PatternScalingParameters {
+            patterns: vec![1.4],
+            ..parameters()
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Region 'Land' is repeated")]
+    fn repeated_region() {
+        PatternScaling::from_parameters(PatternScalingParameters {
+            regions: vec!["Land".to_string(), "Land".to_string(), "Arctic".to_string()],
+            ..parameters()
+        });
+    }
+}
@@ -0,0 +1,346 @@
+/// Two-layer ocean heat uptake model
+use nalgebra::{dmatrix, dvector, DMatrix};
+use ode_solvers::Vector3;
+use rscm_core::component::{
+    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+    TimestepContext,
+};
+use rscm_core::errors::RSCMResult;
+use rscm_core::ivp::{IVPBuilder, LinearIVP, LinearStepper, StatefulIVP, IVP};
+use rscm_core::timeseries::{FloatValue, Time, TimeUnit};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+type ModelState = Vector3<FloatValue>;
+
+/// Step size used when integrating a time step
+///
+/// unit: yr
+const STEP_SIZE: Time = 0.1;
+
+/// Parameters for the two-layer model
+///
+/// See Held et al. (2010) and Geoffroy et al. (2013).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TwoLayerComponentParameters {
+    /// Climate feedback parameter at zero warming
+    /// unit: W / m^2 / K
+    pub lambda0: FloatValue,
+    /// Change in the climate feedback parameter with warming
+    /// unit: W / m^2 / K^2
+    pub a: FloatValue,
+    /// Efficacy of the deep ocean heat uptake
+    /// unit: dimensionless
+    pub efficacy: FloatValue,
+    /// Heat exchange coefficient between the surface and deep layers
+    /// unit: W / m^2 / K
+    pub eta: FloatValue,
+    /// Heat capacity of the surface layer
+    /// unit: W yr / m^2 / K
+    pub heat_capacity_surface: FloatValue,
+    /// Heat capacity of the deep ocean layer
+    /// unit: W yr / m^2 / K
+    pub heat_capacity_deep: FloatValue,
+}
+
+/// Surface and deep ocean temperatures from ERF using a two-layer energy balance model
+///
+/// The temperature of each layer and the ocean heat content are carried as state,
+/// starting from zero, so each step continues from the end of the previous step.
+/// The ocean heat content is the heat taken up by both layers since the start of the run.
+///
+/// If the feedback parameter doesn't change with warming (`a` is zero) the model is linear
+/// and each step is solved exactly using a [`LinearStepper`],
+/// otherwise the model is integrated numerically.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoLayerComponent {
+    parameters: TwoLayerComponentParameters,
+    #[serde(skip)]
+    stepper: LinearStepper,
+}
+
+impl TwoLayerComponent {
+    pub fn from_parameters(parameters: TwoLayerComponentParameters) -> Self {
+        Self {
+            parameters,
+            stepper: LinearStepper::new(),
+        }
+    }
+
+    fn is_linear(&self) -> bool {
+        self.parameters.a == 0.0
+    }
+
+    fn solve_with_step_size(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &InputState,
+        step_size: Time,
+        time_unit: TimeUnit,
+    ) -> RSCMResult<OutputState> {
+        if self.is_linear() {
+            return Ok(self.solve_linear(input_state, (t_next - t_current) * time_unit.in_years()));
+        }
+        IVPBuilder::from_input_state(Arc::new(self.to_owned()), input_state.clone())
+            .with_time_unit(time_unit)
+            .solve_step(t_current, t_next, step_size)
+    }
+
+    /// Advance the linear model exactly by `step` years
+    fn solve_linear(&self, input_state: &InputState, step: Time) -> OutputState {
+        let y = dvector![
+            *input_state.get("Surface Temperature"),
+            *input_state.get("Deep Ocean Temperature"),
+            *input_state.get("Ocean Heat Content")
+        ];
+        let u = dvector![*input_state.get("Effective Radiative Forcing")];
+
+        let y_next = self.stepper.step(self, &y, &u, step);
+        OutputState::from_vectors(
+            vec![y_next[0], y_next[1], y_next[2]],
+            vec![
+                "Surface Temperature".to_string(),
+                "Deep Ocean Temperature".to_string(),
+                "Ocean Heat Content".to_string(),
+            ],
+        )
+    }
+}
+
+/// The model without a state-dependent feedback (see [`TwoLayerComponent`])
+///
+/// The state is the temperature of the surface and deep layers and the ocean heat content,
+/// and the input is the ERF.
+impl LinearIVP for TwoLayerComponent {
+    fn state_matrix(&self) -> DMatrix<FloatValue> {
+        let p = &self.parameters;
+        let surface = [
+            -(p.lambda0 + p.efficacy * p.eta) / p.heat_capacity_surface,
+            p.efficacy * p.eta / p.heat_capacity_surface,
+        ];
+        let deep = [p.eta / p.heat_capacity_deep, -p.eta / p.heat_capacity_deep];
+        // The heat content changes by the heat taken up by both layers
+        let heat_content = [
+            p.heat_capacity_surface * surface[0] + p.heat_capacity_deep * deep[0],
+            p.heat_capacity_surface * surface[1] + p.heat_capacity_deep * deep[1],
+        ];
+        dmatrix![
+            surface[0], surface[1], 0.0;
+            deep[0], deep[1], 0.0;
+            heat_content[0], heat_content[1], 0.0
+        ]
+    }
+
+    fn input_matrix(&self) -> DMatrix<FloatValue> {
+        dmatrix![1.0 / self.parameters.heat_capacity_surface; 0.0; 1.0]
+    }
+}
+
+#[typetag::serde]
+impl Component for TwoLayerComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new(
+                "Effective Radiative Forcing",
+                "W/m^2",
+                RequirementType::Input,
+            ),
+            RequirementDefinition::new("Surface Temperature", "K", RequirementType::InputAndOutput)
+                .with_default(0.0),
+            RequirementDefinition::new(
+                "Deep Ocean Temperature",
+                "K",
+                RequirementType::InputAndOutput,
+            )
+            .with_default(0.0),
+            RequirementDefinition::new(
+                "Ocean Heat Content",
+                "W yr / m^2",
+                RequirementType::InputAndOutput,
+            )
+            .with_default(0.0),
+        ]
+    }
+
+    fn solve(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        self.solve_with_step_size(t_current, t_next, input_state, STEP_SIZE, TimeUnit::Year)
+    }
+
+    fn solve_with_context(
+        &self,
+        context: &mut TimestepContext,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        self.solve_with_step_size(
+            context.t_current(),
+            context.t_next(),
+            input_state,
+            context.solver_step_size(STEP_SIZE),
+            context.time_unit(),
+        )
+    }
+}
+
+impl StatefulIVP<ModelState> for TwoLayerComponent {}
+
+impl IVP<Time, ModelState> for TwoLayerComponent {
+    fn calculate_dy_dt(
+        &self,
+        _t: Time,
+        input_state: &InputState,
+        y: &ModelState,
+        dy_dt: &mut ModelState,
+    ) {
+        let temperature_surface = y[0];
+        let temperature_deep = y[1];
+        let erf = input_state.get("Effective Radiative Forcing");
+
+        let temperature_difference = temperature_surface - temperature_deep;
+
+        let lambda_eff = self.parameters.lambda0 - self.parameters.a * temperature_surface;
+        let heat_exchange_surface =
+            self.parameters.efficacy * self.parameters.eta * temperature_difference;
+        let dtemperature_surface_dt =
+            (erf - lambda_eff * temperature_surface - heat_exchange_surface)
+                / self.parameters.heat_capacity_surface;
+
+        let heat_exchange_deep = self.parameters.eta * temperature_difference;
+        let dtemperature_deep_dt = heat_exchange_deep / self.parameters.heat_capacity_deep;
+
+        dy_dt[0] = dtemperature_surface_dt;
+        dy_dt[1] = dtemperature_deep_dt;
+        dy_dt[2] = self.parameters.heat_capacity_surface * dtemperature_surface_dt
+            + self.parameters.heat_capacity_deep * dtemperature_deep_dt;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use numpy::array;
+    use numpy::ndarray::Array;
+    use rscm_core::model::ModelBuilder;
+    use rscm_core::timeseries::{TimeAxis, Timeseries};
+
+    fn parameters() -> TwoLayerComponentParameters {
+        TwoLayerComponentParameters {
+            lambda0: 0.5,
+            a: 0.01,
+            efficacy: 0.5,
+            eta: 0.5,
+            heat_capacity_surface: 1.0,
+            heat_capacity_deep: 10.0,
+        }
+    }
+
+    fn state(values: [FloatValue; 4]) -> InputState {
+        InputState::from_vectors(
+            values.to_vec(),
+            vec![
+                "Effective Radiative Forcing".to_string(),
+                "Surface Temperature".to_string(),
+                "Deep Ocean Temperature".to_string(),
+                "Ocean Heat Content".to_string(),
+            ],
+        )
+    }
+
+    #[test]
+    fn continues_from_state() {
+        let component = TwoLayerComponent::from_parameters(parameters());
+
+        let first = component
+            .solve(2000.0, 2001.0, &state([1.0, 0.0, 0.0, 0.0]))
+            .unwrap();
+        let temperature = *first.get("Surface Temperature");
+        assert!(temperature > 0.0);
+
+        // Starting from the end of the first step warms further
+        let second = component
+            .solve(
+                2001.0,
+                2002.0,
+                &state([
+                    1.0,
+                    temperature,
+                    *first.get("Deep Ocean Temperature"),
+                    *first.get("Ocean Heat Content"),
+                ]),
+            )
+            .unwrap();
+        assert!(*second.get("Surface Temperature") > temperature);
+        assert!(*second.get("Ocean Heat Content") > *first.get("Ocean Heat Content"));
+    }
+
+    #[test]
+    fn linear_matches_numerical_solution() {
+        let linear = TwoLayerComponent::from_parameters(TwoLayerComponentParameters {
+            a: 0.0,
+            ..parameters()
+        });
+        assert!(linear.is_linear());
+
+        let input_state = state([3.7, 0.5, 0.1, 2.0]);
+        let exact = linear.solve(2000.0, 2005.0, &input_state).unwrap();
+        let numerical = IVPBuilder::from_input_state(Arc::new(linear.clone()), input_state.clone())
+            .solve_step(2000.0, 2005.0, 0.001)
+            .unwrap();
+
+        for name in [
+            "Surface Temperature",
+            "Deep Ocean Temperature",
+            "Ocean Heat Content",
+        ] {
+            assert_relative_eq!(*exact.get(name), *numerical.get(name), max_relative = 1e-8);
+        }
+        // A single propagator is reused for each step of the same length
+        linear.solve(2005.0, 2010.0, &input_state).unwrap();
+        assert_eq!(linear.stepper.n_cached(), 1);
+    }
+
+    #[test]
+    fn equilibrium() {
+        let p = parameters();
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(1850.0, 2350.0, 1.0)))
+            .with_component(Arc::new(TwoLayerComponent::from_parameters(p.clone())))
+            .with_exogenous_variable(
+                "Effective Radiative Forcing",
+                Timeseries::from_values(array![1.0, 1.0], array![1850.0, 2350.0]),
+            )
+            .build();
+        model.run();
+
+        let value = |name: &str| {
+            model
+                .timeseries()
+                .get_timeseries_by_name(name)
+                .unwrap()
+                .values()
+                .last()
+                .copied()
+                .unwrap()
+        };
+        let temperature_surface = value("Surface Temperature");
+        let temperature_deep = value("Deep Ocean Temperature");
+
+        // At equilibrium ERF = (lambda0 - a T) T and both layers have the same temperature
+        let expected = (p.lambda0 - (p.lambda0.powi(2) - 4.0 * p.a).sqrt()) / (2.0 * p.a);
+        assert_relative_eq!(temperature_surface, expected, max_relative = 1e-4);
+        assert_relative_eq!(temperature_deep, expected, max_relative = 1e-4);
+
+        // All of the heat taken up is stored in the two layers
+        assert_relative_eq!(
+            value("Ocean Heat Content"),
+            p.heat_capacity_surface * temperature_surface + p.heat_capacity_deep * temperature_deep,
+            max_relative = 1e-8
+        );
+    }
+}
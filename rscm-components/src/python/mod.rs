@@ -4,18 +4,99 @@ use pyo3::{pymodule, Bound, PyResult};
 use rscm_core::create_component_builder;
 use rscm_core::python::PyRustComponent;
 
+use crate::components::ocean_carbon_cycle::{
+    OceanCarbonCycle, OceanCarbonCycleParameters, OceanSurfacePartialPressure,
+    OceanSurfacePartialPressureParameters,
+};
 use crate::components::*;
 
-create_component_builder!(CO2ERFBuilder, CO2ERF, CO2ERFParameters);
 create_component_builder!(
+    /// Builder for the blended surface air and sea surface temperature diagnostic
+    BlendedTemperatureBuilder,
+    BlendedTemperature,
+    BlendedTemperatureParameters
+);
+create_component_builder!(
+    /// Builder for the CH4 effective radiative forcing component
+    CH4ERFBuilder,
+    CH4ERF,
+    CH4ERFParameters
+);
+create_component_builder!(
+    /// Builder for the CO2 effective radiative forcing component
+    CO2ERFBuilder,
+    CO2ERF,
+    CO2ERFParameters
+);
+create_component_builder!(
+    /// Builder for the single-box carbon cycle component
     CarbonCycleBuilder,
     CarbonCycleComponent,
     CarbonCycleParameters
 );
+create_component_builder!(
+    /// Builder for the FaIR-style multi-box carbon cycle component
+    FaIRCarbonCycleBuilder,
+    FaIRCarbonCycleComponent,
+    FaIRCarbonCycleParameters
+);
+create_component_builder!(
+    /// Builder for the Green's function temperature emulator
+    GreensFunctionTemperatureBuilder,
+    GreensFunctionTemperature,
+    GreensFunctionTemperatureParameters
+);
+create_component_builder!(
+    /// Builder for the stochastic internal variability component
+    InternalVariabilityBuilder,
+    InternalVariability,
+    InternalVariabilityParameters
+);
+create_component_builder!(
+    /// Builder for the N2O effective radiative forcing component
+    N2OERFBuilder,
+    N2OERF,
+    N2OERFParameters
+);
+create_component_builder!(
+    /// Builder for the mixed-layer ocean carbon cycle component
+    OceanCarbonCycleBuilder,
+    OceanCarbonCycle,
+    OceanCarbonCycleParameters
+);
+create_component_builder!(
+    /// Builder for the ocean surface partial pressure component
+    OceanSurfacePartialPressureBuilder,
+    OceanSurfacePartialPressure,
+    OceanSurfacePartialPressureParameters
+);
+create_component_builder!(
+    /// Builder for the pattern scaling regional temperature component
+    PatternScalingBuilder,
+    PatternScaling,
+    PatternScalingParameters
+);
+
+create_component_builder!(
+    /// Builder for the two-layer energy balance component
+    TwoLayerComponentBuilder,
+    TwoLayerComponent,
+    TwoLayerComponentParameters
+);
 
 #[pymodule]
 pub fn components(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<BlendedTemperatureBuilder>()?;
+    m.add_class::<CH4ERFBuilder>()?;
     m.add_class::<CO2ERFBuilder>()?;
     m.add_class::<CarbonCycleBuilder>()?;
+    m.add_class::<FaIRCarbonCycleBuilder>()?;
+    m.add_class::<GreensFunctionTemperatureBuilder>()?;
+    m.add_class::<InternalVariabilityBuilder>()?;
+    m.add_class::<N2OERFBuilder>()?;
+    m.add_class::<OceanCarbonCycleBuilder>()?;
+    m.add_class::<OceanSurfacePartialPressureBuilder>()?;
+    m.add_class::<PatternScalingBuilder>()?;
+    m.add_class::<TwoLayerComponentBuilder>()?;
     Ok(())
 }
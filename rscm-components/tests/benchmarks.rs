@@ -0,0 +1,66 @@
+//! Headline results of the coupled model example compared to published assessments
+//!
+//! CO_2 is the only forcing agent in the example, so the warming is compared to assessments
+//! of the warming caused by CO_2 rather than the observed or projected total warming.
+use numpy::ndarray::Array;
+use rscm_core::timeseries::{FloatValue, Time, Timeseries};
+
+/// The coupled model from the public example
+#[path = "../examples/coupled_model.rs"]
+#[allow(dead_code)]
+mod coupled_model;
+
+use coupled_model::build_model;
+
+/// Mean of the annual values from `start` to `end` inclusive
+fn period_mean(timeseries: &Timeseries<FloatValue>, start: Time, end: Time) -> FloatValue {
+    let years = Array::range(start, end + 1.0, 1.0);
+    years
+        .iter()
+        .map(|year| timeseries.at_time(*year).unwrap())
+        .sum::<FloatValue>()
+        / years.len() as FloatValue
+}
+
+#[test]
+fn test_coupled_model_benchmarks() {
+    let mut model = build_model();
+    model.run();
+    assert!(model.finished());
+    assert!(model.warnings().is_empty());
+
+    let concentration = model
+        .timeseries()
+        .get_timeseries_by_name("Atmospheric Concentration|CO2")
+        .unwrap();
+    let temperature = model
+        .timeseries()
+        .get_timeseries_by_name("Surface Temperature")
+        .unwrap();
+    let cumulative_emissions = model
+        .timeseries()
+        .get_timeseries_by_name("Emissions|CO2|Anthropogenic")
+        .unwrap()
+        .integrate();
+
+    // Observed global-mean concentrations were 369.7 ppm in 2000 and 414.2 ppm in 2020
+    let conc_2000 = concentration.at_time(2000.0).unwrap();
+    let conc_2020 = concentration.at_time(2020.0).unwrap();
+    assert!((360.0..380.0).contains(&conc_2000), "{}", conc_2000);
+    assert!((404.0..424.0).contains(&conc_2020), "{}", conc_2020);
+
+    // Within 10% of the 603 ppm prescribed for 2100 in the concentration-driven
+    // SSP2-4.5 scenario (Meinshausen et al. 2020)
+    let conc_2100 = concentration.at_time(2100.0).unwrap();
+    assert!((543.0..663.0).contains(&conc_2100), "{}", conc_2100);
+
+    // The warming since the start of the run in 1750 per 1000 GtC of cumulative emissions
+    // is within the AR6 likely range of the transient climate response to cumulative
+    // emissions of CO_2 (TCRE) of 1.0-2.3 K per 1000 GtC (Canadell et al. 2021),
+    // both historically and while emissions remain positive under SSP2-4.5
+    for (start, end) in [(2011.0, 2020.0), (2081.0, 2100.0)] {
+        let tcre = 1000.0 * period_mean(temperature, start, end)
+            / period_mean(&cumulative_emissions, start, end);
+        assert!((1.0..2.3).contains(&tcre), "{}-{}: {}", start, end, tcre);
+    }
+}
@@ -1,12 +1,15 @@
+use approx::assert_relative_eq;
 use numpy::array;
 use numpy::ndarray::Array;
 use rscm_components::{
-    CO2ERFParameters, CarbonCycleComponent, CarbonCycleParameters, SolverOptions, CO2ERF,
+    CO2ERFParameters, CarbonCycleComponent, CarbonCycleParameters, FaIRCarbonCycleComponent,
+    FaIRCarbonCycleParameters, GreensFunctionTemperature, GreensFunctionTemperatureParameters,
+    SolverOptions, CO2ERF,
 };
 use rscm_core::component::InputState;
 use rscm_core::interpolate::strategies::{InterpolationStrategy, NextStrategy, PreviousStrategy};
 use rscm_core::model::ModelBuilder;
-use rscm_core::timeseries::{FloatValue, Time, TimeAxis, Timeseries};
+use rscm_core::timeseries::{FloatValue, Time, TimeAxis, TimeUnit, Timeseries};
 use std::sync::Arc;
 
 #[test]
@@ -191,8 +194,212 @@ fn test_coupled_model() {
         ]
     );
 
-    println!("{:?}", model.as_dot());
+    println!("{}", model.as_dot());
 
     // Run the model
     model.run()
 }
+
+#[test]
+fn test_fair_carbon_cycle() {
+    let time_axis = TimeAxis::from_values(Array::range(1850.0, 2100.0, 1.0));
+    let emissions = Timeseries::new(
+        array![10.0],
+        Arc::new(TimeAxis::from_bounds(array![1850.0, 2100.0])),
+        "GtC / yr".to_string(),
+        InterpolationStrategy::from(PreviousStrategy::new(true)),
+    );
+    let temperature = Timeseries::new(
+        array![0.0],
+        Arc::new(TimeAxis::from_bounds(array![1850.0, 2100.0])),
+        "K".to_string(),
+        InterpolationStrategy::from(PreviousStrategy::new(true)),
+    );
+
+    // The carbon pools and cumulative emissions start at zero
+    let mut model = ModelBuilder::new()
+        .with_component(Arc::new(FaIRCarbonCycleComponent::from_parameters(
+            FaIRCarbonCycleParameters {
+                partition: [0.2173, 0.2240, 0.2824, 0.2763],
+                tau: [1.0e6, 394.4, 36.54, 4.304],
+                r0: 32.4,
+                r_uptake: 0.019,
+                r_temperature: 4.165,
+                r_atmosphere: 0.0,
+                iirf_max: 97.0,
+                iirf_horizon: 100.0,
+                conc_pi: 278.0,
+            },
+        )))
+        .with_time_axis(time_axis)
+        .with_exogenous_variable("Emissions|CO2|Anthropogenic", emissions)
+        .with_exogenous_variable("Surface Temperature", temperature)
+        .build();
+
+    model.run();
+
+    let conc = model
+        .timeseries()
+        .get_timeseries_by_name("Atmospheric Concentration|CO2")
+        .unwrap();
+    let alpha = model
+        .timeseries()
+        .get_timeseries_by_name("Carbon Cycle|Timescale Scaling")
+        .unwrap();
+    let cumulative_emissions = model
+        .timeseries()
+        .get_timeseries_by_name("Cumulative Emissions|CO2")
+        .unwrap();
+
+    assert_eq!(cumulative_emissions.at_time(2099.0).unwrap(), 2490.0);
+    // Concentrations increase under constant emissions
+    let values = conc.values();
+    assert!(values
+        .iter()
+        .skip(1)
+        .zip(values.iter().skip(2))
+        .all(|(a, b)| b > a));
+    // Sinks saturate as carbon is taken up
+    assert!(alpha.at_time(2099.0).unwrap() > alpha.at_time(1852.0).unwrap());
+}
+
+#[test]
+fn test_greens_function_temperature() {
+    let time_axis = TimeAxis::from_values(Array::range(1850.0, 2000.0, 1.0));
+    let erf_values = Array::range(0.0, 150.0, 1.0).mapv(|i: FloatValue| 0.02 * i + (0.3 * i).sin());
+    let erf = Timeseries::new(
+        erf_values.clone(),
+        Arc::new(time_axis.clone()),
+        "W/m^2".to_string(),
+        InterpolationStrategy::from(PreviousStrategy::new(true)),
+    );
+    let component =
+        GreensFunctionTemperature::from_parameters(GreensFunctionTemperatureParameters {
+            q: vec![0.33, 0.41],
+            d: vec![8.4, 409.5],
+        });
+
+    let mut model = ModelBuilder::new()
+        .with_component(Arc::new(component.clone()))
+        .with_time_axis(time_axis)
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+        .build();
+    model.run();
+
+    let temperature = model
+        .timeseries()
+        .get_timeseries_by_name("Surface Temperature")
+        .unwrap();
+
+    // Stepping the model is equivalent to convolving the ERF with the response function
+    let expected = component.response(erf_values.as_slice().unwrap(), 1.0);
+    temperature
+        .values()
+        .iter()
+        .skip(1)
+        .zip(expected.iter())
+        .for_each(|(value, expected)| {
+            assert_relative_eq!(*value, *expected, max_relative = 1e-10, epsilon = 1e-12)
+        });
+}
+
+#[test]
+fn test_time_units() {
+    let run = |time_axis: TimeAxis| {
+        let erf = Timeseries::new(
+            array![1.0, 1.0],
+            Arc::new(TimeAxis::from_values(array![1850.0, 1900.0])),
+            "W/m^2".to_string(),
+            InterpolationStrategy::from(PreviousStrategy::new(true)),
+        );
+        let emissions = Timeseries::new(
+            array![10.0, 10.0],
+            Arc::new(TimeAxis::from_values(array![1850.0, 1900.0])),
+            "GtC / yr".to_string(),
+            InterpolationStrategy::from(PreviousStrategy::new(true)),
+        );
+        let mut model = ModelBuilder::new()
+            .with_component(Arc::new(GreensFunctionTemperature::from_parameters(
+                GreensFunctionTemperatureParameters {
+                    q: vec![0.33, 0.41],
+                    d: vec![8.4, 409.5],
+                },
+            )))
+            .with_component(Arc::new(
+                CarbonCycleComponent::from_parameters(CarbonCycleParameters {
+                    tau: 20.3,
+                    conc_pi: 280.0,
+                    alpha_temperature: 0.0,
+                })
+                .with_solver_options(SolverOptions { step_size: 0.1 }),
+            ))
+            // The carbon cycle is initialised at its pre-industrial concentration
+            .with_time_axis(time_axis)
+            .with_exogenous_variable("Effective Radiative Forcing", erf)
+            .with_exogenous_variable("Emissions|CO2|Anthropogenic", emissions)
+            .build();
+        model.run();
+        model.timeseries().clone()
+    };
+
+    let years = TimeAxis::from_values(Array::range(1850.0, 1860.0, 1.0));
+    let seconds = years.to_unit(TimeUnit::Second);
+    let in_years = run(years);
+    let in_seconds = run(seconds);
+
+    // Rates are per year whatever the unit of the time axis
+    for name in ["Atmospheric Concentration|CO2", "Surface Temperature"] {
+        let expected = in_years.get_timeseries_by_name(name).unwrap();
+        let value = in_seconds.get_timeseries_by_name(name).unwrap();
+        assert_relative_eq!(
+            value.at(9).unwrap(),
+            expected.at(9).unwrap(),
+            max_relative = 1e-6
+        );
+    }
+}
+
+#[test]
+fn test_variable_time_step() {
+    let run = |time_axis: TimeAxis| {
+        let erf = Timeseries::new(
+            array![1.0, 1.0],
+            Arc::new(TimeAxis::from_values(array![1750.0, 1900.0])),
+            "W/m^2".to_string(),
+            InterpolationStrategy::from(PreviousStrategy::new(true)),
+        );
+        let mut model = ModelBuilder::new()
+            .with_component(Arc::new(GreensFunctionTemperature::from_parameters(
+                GreensFunctionTemperatureParameters {
+                    q: vec![0.33, 0.41],
+                    d: vec![8.4, 409.5],
+                },
+            )))
+            .with_time_axis(time_axis)
+            .with_exogenous_variable("Effective Radiative Forcing", erf)
+            .build();
+        model.run();
+        model
+            .timeseries()
+            .get_timeseries_by_name("Surface Temperature")
+            .unwrap()
+            .clone()
+    };
+
+    let annual = run(TimeAxis::from_values(Array::range(1750.0, 1900.0, 1.0)));
+    // Spin up using decadal time steps
+    let piecewise = run(TimeAxis::piecewise(&[
+        (1750.0, 1850.0, 10.0),
+        (1850.0, 1900.0, 1.0),
+    ]));
+    assert_eq!(piecewise.len(), 10 + 50);
+
+    // The response to a constant forcing doesn't depend on the time step
+    for year in [1800.0, 1850.0, 1851.0, 1899.0] {
+        assert_relative_eq!(
+            piecewise.at_time(year).unwrap(),
+            annual.at_time(year).unwrap(),
+            max_relative = 1e-10
+        );
+    }
+}
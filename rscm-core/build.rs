@@ -0,0 +1,21 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Record the git commit that rscm is built from, if it is built from a git repository
+fn main() {
+    let output = Command::new("git").args(["rev-parse", "HEAD"]).output();
+    if let Some(output) = output.ok().filter(|output| output.status.success()) {
+        println!(
+            "cargo:rustc-env=RSCM_GIT_HASH={}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+    }
+
+    // Only rebuild when the checked out commit changes
+    println!("cargo:rerun-if-changed=build.rs");
+    for path in ["../.git/HEAD", "../.git/refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+}
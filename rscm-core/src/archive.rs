@@ -0,0 +1,493 @@
+//! Saving collections and ensembles of results
+//!
+//! Results are written as TOML with a `[metadata]` table describing how the values were stored.
+//! Large ensembles can be written at reduced [`Precision`] and with [`Compression`]
+//! to keep archives manageable.
+//! TOML doesn't have a single precision type, so single precision values are written as the
+//! shortest decimal which identifies the float, taking at most 9 significant digits,
+//! and are read back as that float.
+use crate::ensemble::EnsembleResult;
+use crate::errors::{RSCMError, RSCMResult};
+use crate::timeseries::FloatValue;
+use crate::timeseries_collection::{TimeseriesCollection, TimeseriesItem};
+use pyo3::pyclass;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Bytes at the start of a zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Version of the archive format written by this version of rscm
+///
+/// Archives written with a different format version can't be loaded.
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// Precision used to store values
+#[pyclass]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Precision {
+    /// Store values exactly
+    #[default]
+    Float64,
+    /// Round values to single precision
+    Float32,
+}
+
+impl Precision {
+    /// Round a value to the precision
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rscm_core::archive::Precision;
+    ///
+    /// assert_eq!(Precision::Float32.round(0.1), 0.1_f32 as f64);
+    /// assert_eq!(Precision::Float32.round(1.0 / 3.0), (1.0_f32 / 3.0) as f64);
+    /// assert_eq!(Precision::Float64.round(1.0 / 3.0), 1.0 / 3.0);
+    /// ```
+    pub fn round(&self, value: FloatValue) -> FloatValue {
+        match self {
+            Precision::Float64 => value,
+            Precision::Float32 => value as f32 as FloatValue,
+        }
+    }
+
+    /// Value which is written as the shortest decimal identifying the value at this precision
+    fn shortest(&self, value: FloatValue) -> FloatValue {
+        match self {
+            Precision::Float64 => value,
+            Precision::Float32 => {
+                let single = value as f32;
+                match single.is_finite() {
+                    true => single.to_string().parse().unwrap(),
+                    false => single as FloatValue,
+                }
+            }
+        }
+    }
+}
+
+/// Compression applied to archives
+#[pyclass]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compression {
+    /// Write plain TOML
+    #[default]
+    Uncompressed,
+    /// Compress the TOML using zstd
+    Zstd,
+}
+
+/// Options for writing archives
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WriteOptions {
+    /// Precision used to store values
+    pub precision: Precision,
+    /// Compression applied to the archive
+    pub compression: Compression,
+    /// Units to write variables in, by name
+    ///
+    /// See [`TimeseriesCollection::convert_units`]
+    pub units: BTreeMap<String, String>,
+    /// Unit contexts which may be used when converting variables into `units`
+    pub unit_contexts: Vec<String>,
+}
+
+impl WriteOptions {
+    /// Write a variable in the given units
+    pub fn with_unit(&mut self, variable: &str, unit: &str) -> &mut Self {
+        self.units.insert(variable.to_string(), unit.to_string());
+        self
+    }
+}
+
+/// Description of how an archive was written
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveMetadata {
+    pub format_version: u32,
+    pub precision: Precision,
+    #[serde(default)]
+    pub compression: Compression,
+}
+
+#[derive(Serialize)]
+struct Archive<'a, T> {
+    metadata: ArchiveMetadata,
+    contents: &'a T,
+}
+
+/// Convert a collection into the preferred units and the values which are written for the
+/// requested precision
+fn prepare_collection(
+    collection: &TimeseriesCollection,
+    options: &WriteOptions,
+) -> RSCMResult<TimeseriesCollection> {
+    let converted = collection.convert_units(&options.units, &options.unit_contexts)?;
+    Ok(map_values(&converted, |value| {
+        options.precision.shortest(value)
+    }))
+}
+
+fn map_values(
+    collection: &TimeseriesCollection,
+    f: impl Fn(FloatValue) -> FloatValue,
+) -> TimeseriesCollection {
+    let mut mapped = TimeseriesCollection::new();
+    collection.iter().for_each(|item| {
+        let mut timeseries = item.timeseries.clone();
+        (0..timeseries.len()).for_each(|i| {
+            let value = timeseries.at(i).unwrap();
+            timeseries.set(i, f(value));
+        });
+        mapped
+            .add_item(TimeseriesItem {
+                timeseries,
+                ..item.clone()
+            })
+            .expect("Items are unique within a collection");
+    });
+    mapped
+}
+
+fn save<T: Serialize>(contents: &T, options: &WriteOptions) -> RSCMResult<Vec<u8>> {
+    let archive = toml::to_string(&Archive {
+        metadata: ArchiveMetadata {
+            format_version: ARCHIVE_FORMAT_VERSION,
+            precision: options.precision,
+            compression: options.compression,
+        },
+        contents,
+    })
+    .map_err(|e| RSCMError::Error(format!("Could not write archive: {}", e)))?;
+
+    match options.compression {
+        Compression::Uncompressed => Ok(archive.into_bytes()),
+        Compression::Zstd => zstd::encode_all(archive.as_bytes(), 0)
+            .map_err(|e| RSCMError::Error(format!("Could not compress archive: {}", e))),
+    }
+}
+
+/// Decompress an archive if required
+fn decode(archive: &[u8]) -> RSCMResult<(String, Compression)> {
+    let (bytes, compression) = match archive.starts_with(&ZSTD_MAGIC) {
+        true => (
+            zstd::decode_all(archive)
+                .map_err(|e| RSCMError::Error(format!("Invalid compressed archive: {}", e)))?,
+            Compression::Zstd,
+        ),
+        false => (archive.to_vec(), Compression::Uncompressed),
+    };
+    let text = String::from_utf8(bytes)
+        .map_err(|e| RSCMError::Error(format!("Invalid archive: {}", e)))?;
+    Ok((text, compression))
+}
+
+fn load<T: DeserializeOwned>(archive: &[u8]) -> RSCMResult<(T, ArchiveMetadata)> {
+    let (archive, compression) = decode(archive)?;
+    let metadata = parse_metadata(&archive, compression)?;
+
+    let contents = toml::from_str::<toml::Table>(&archive)
+        .map_err(|e| RSCMError::Error(format!("Invalid archive: {}", e)))?
+        .remove("contents")
+        .ok_or_else(|| RSCMError::Error("Archive doesn't contain any contents".to_string()))?
+        .try_into()
+        .map_err(|e| RSCMError::Error(format!("Invalid archive: {}", e)))?;
+    Ok((contents, metadata))
+}
+
+/// Read the metadata of an archive
+///
+/// Returns an error if the archive was written using a different format version.
+pub fn metadata(archive: &[u8]) -> RSCMResult<ArchiveMetadata> {
+    let (archive, compression) = decode(archive)?;
+    parse_metadata(&archive, compression)
+}
+
+fn parse_metadata(archive: &str, compression: Compression) -> RSCMResult<ArchiveMetadata> {
+    #[derive(Deserialize)]
+    struct Header {
+        metadata: ArchiveMetadata,
+    }
+
+    let metadata = toml::from_str::<Header>(archive)
+        .map_err(|e| RSCMError::Error(format!("Invalid archive metadata: {}", e)))?
+        .metadata;
+    if metadata.format_version != ARCHIVE_FORMAT_VERSION {
+        return Err(RSCMError::Error(format!(
+            "Unsupported archive format version {}, expected {}",
+            metadata.format_version, ARCHIVE_FORMAT_VERSION
+        )));
+    }
+    if metadata.compression != compression {
+        return Err(RSCMError::Error(format!(
+            "Archive metadata records {:?} compression but the archive is {:?}",
+            metadata.compression, compression
+        )));
+    }
+    Ok(metadata)
+}
+
+/// Read the stored values of a collection at the precision they were written
+fn restore_collection(
+    collection: TimeseriesCollection,
+    precision: Precision,
+) -> TimeseriesCollection {
+    match precision {
+        Precision::Float64 => collection,
+        Precision::Float32 => map_values(&collection, |value| precision.round(value)),
+    }
+}
+
+/// Write a collection of timeseries as an archive
+///
+/// Returns an error if a variable can't be converted into the units requested in `options`.
+pub fn save_collection(
+    collection: &TimeseriesCollection,
+    options: &WriteOptions,
+) -> RSCMResult<Vec<u8>> {
+    save(&prepare_collection(collection, options)?, options)
+}
+
+/// Read a collection of timeseries written by [`save_collection`]
+///
+/// Values written at [`Precision::Float32`] are read as single precision floats.
+pub fn load_collection(archive: &[u8]) -> RSCMResult<TimeseriesCollection> {
+    let (collection, metadata) = load(archive)?;
+    Ok(restore_collection(collection, metadata.precision))
+}
+
+/// Write the results of an ensemble as an archive
+///
+/// Failed members are included along with the results of the successful members.
+/// Returns an error if a variable can't be converted into the units requested in `options`.
+pub fn save_ensemble(ensemble: &EnsembleResult, options: &WriteOptions) -> RSCMResult<Vec<u8>> {
+    let prepared = EnsembleResult {
+        results: ensemble
+            .results
+            .iter()
+            .map(|(index, collection)| Ok((*index, prepare_collection(collection, options)?)))
+            .collect::<RSCMResult<Vec<_>>>()?,
+        failures: ensemble.failures.clone(),
+    };
+    save(&prepared, options)
+}
+
+/// Read the results of an ensemble written by [`save_ensemble`]
+///
+/// Values written at [`Precision::Float32`] are read as single precision floats.
+pub fn load_ensemble(archive: &[u8]) -> RSCMResult<EnsembleResult> {
+    let (ensemble, metadata): (EnsembleResult, _) = load(archive)?;
+    Ok(EnsembleResult {
+        results: ensemble
+            .results
+            .into_iter()
+            .map(|(index, collection)| (index, restore_collection(collection, metadata.precision)))
+            .collect(),
+        failures: ensemble.failures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::MemberFailure;
+    use crate::interpolate::strategies::LinearSplineStrategy;
+    use crate::timeseries::{TimeAxis, Timeseries};
+    use crate::timeseries_collection::{RunKey, VariableType};
+    use numpy::array;
+    use numpy::ndarray::Array;
+    use std::collections::BTreeMap;
+    use std::iter::zip;
+    use std::sync::Arc;
+
+    fn collection() -> TimeseriesCollection {
+        let mut collection = TimeseriesCollection::new();
+        collection
+            .add_timeseries(
+                "Surface Temperature".to_string(),
+                Timeseries::from_values(
+                    array![1.0 / 3.0, 2.0e-12, FloatValue::NAN, 1e300],
+                    array![2000.0, 2001.0, 2002.0, 2003.0],
+                ),
+                VariableType::Endogenous,
+            )
+            .unwrap();
+        collection.with_run(RunKey::new("member", "ssp245"))
+    }
+
+    fn values(collection: &TimeseriesCollection) -> Vec<FloatValue> {
+        collection
+            .get_timeseries_by_name("Surface Temperature")
+            .unwrap()
+            .values()
+            .to_vec()
+    }
+
+    fn same(a: &[FloatValue], b: &[FloatValue]) -> bool {
+        a.len() == b.len()
+            && zip(a, b).all(|(x, y)| x.to_bits() == y.to_bits() || (x.is_nan() && y.is_nan()))
+    }
+
+    /// Values of the test collection read at single precision
+    fn single_values() -> Vec<FloatValue> {
+        [1.0_f32 / 3.0, 2.0e-12, f32::NAN, f32::INFINITY]
+            .iter()
+            .map(|value| *value as FloatValue)
+            .collect()
+    }
+
+    #[test]
+    fn collection_round_trip() {
+        let collection = collection();
+
+        let archive = save_collection(&collection, &WriteOptions::default()).unwrap();
+        assert_eq!(
+            metadata(&archive).unwrap(),
+            ArchiveMetadata {
+                format_version: ARCHIVE_FORMAT_VERSION,
+                precision: Precision::Float64,
+                compression: Compression::Uncompressed,
+            }
+        );
+        let loaded = load_collection(&archive).unwrap();
+        assert!(same(&values(&loaded), &values(&collection)));
+        assert_eq!(loaded.runs(), collection.runs());
+    }
+
+    #[test]
+    fn single_precision_round_trip() {
+        let collection = collection();
+        let archive = save_collection(&collection, &WriteOptions::default()).unwrap();
+
+        let options = WriteOptions {
+            precision: Precision::Float32,
+            ..Default::default()
+        };
+        let reduced = save_collection(&collection, &options).unwrap();
+        assert!(reduced.len() < archive.len());
+        assert_eq!(metadata(&reduced).unwrap().precision, Precision::Float32);
+        // Values are written as the shortest decimal identifying the single precision float
+        assert!(String::from_utf8(reduced.clone())
+            .unwrap()
+            .contains("0.33333334,"));
+
+        // The values read are the single precision floats
+        let loaded = values(&load_collection(&reduced).unwrap());
+        assert!(same(&loaded, &single_values()));
+        assert!(same(
+            &loaded,
+            &values(&collection)
+                .iter()
+                .map(|value| Precision::Float32.round(*value))
+                .collect::<Vec<_>>()
+        ));
+    }
+
+    #[test]
+    fn compressed_round_trip() {
+        let mut collection = collection();
+        collection
+            .add_timeseries(
+                "Constant".to_string(),
+                Timeseries::from_values(
+                    Array::from_elem(1000, 1.5),
+                    Array::range(0.0, 1000.0, 1.0),
+                ),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+        let archive = save_collection(&collection, &WriteOptions::default()).unwrap();
+
+        let options = WriteOptions {
+            compression: Compression::Zstd,
+            ..Default::default()
+        };
+        let compressed = save_collection(&collection, &options).unwrap();
+        assert!(compressed.starts_with(&ZSTD_MAGIC));
+        assert!(compressed.len() < archive.len() / 4);
+        assert_eq!(
+            metadata(&compressed).unwrap().compression,
+            Compression::Zstd
+        );
+
+        let loaded = load_collection(&compressed).unwrap();
+        assert!(same(&values(&loaded), &values(&collection)));
+        let constant = loaded.get_timeseries_by_name("Constant").unwrap();
+        assert!(constant.values().iter().all(|value| *value == 1.5));
+
+        // The recorded compression must match the archive
+        let mislabelled = String::from_utf8(archive)
+            .unwrap()
+            .replace("compression = \"Uncompressed\"", "compression = \"Zstd\"");
+        let err = load_collection(mislabelled.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("records Zstd compression"));
+    }
+
+    #[test]
+    fn ensemble_round_trip() {
+        let ensemble = EnsembleResult {
+            results: vec![(0, collection()), (2, collection())],
+            failures: vec![MemberFailure {
+                index: 1,
+                parameters: toml::Value::try_from(BTreeMap::from([("k", 1e200)])).unwrap(),
+                error: "Unstable".to_string(),
+                retried: false,
+            }],
+        };
+        let options = WriteOptions {
+            precision: Precision::Float32,
+            compression: Compression::Zstd,
+            ..Default::default()
+        };
+
+        let loaded = load_ensemble(&save_ensemble(&ensemble, &options).unwrap()).unwrap();
+        assert_eq!(loaded.failures, ensemble.failures);
+        assert_eq!(
+            loaded.results.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+        assert!(same(&values(&loaded.results[1].1), &single_values()));
+    }
+
+    #[test]
+    fn preferred_units() {
+        let mut collection = TimeseriesCollection::new();
+        collection
+            .add_timeseries(
+                "Surface Temperature".to_string(),
+                Timeseries::new(
+                    array![0.5, 1.0],
+                    Arc::new(TimeAxis::from_values(array![2000.0, 2001.0])),
+                    "K".to_string(),
+                    LinearSplineStrategy::new(true).into(),
+                ),
+                VariableType::Endogenous,
+            )
+            .unwrap();
+
+        let mut options = WriteOptions::default();
+        options.with_unit("Surface Temperature", "mK");
+        let loaded = load_collection(&save_collection(&collection, &options).unwrap()).unwrap();
+        let temperature = loaded
+            .get_timeseries_by_name("Surface Temperature")
+            .unwrap();
+        assert_eq!(temperature.units(), "mK");
+        assert_eq!(temperature.values().to_vec(), vec![500.0, 1000.0]);
+
+        options.with_unit("Surface Temperature", "W / m^2");
+        assert!(save_collection(&collection, &options).is_err());
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let archive =
+            String::from_utf8(save_collection(&collection(), &WriteOptions::default()).unwrap())
+                .unwrap()
+                .replace("format_version = 1", "format_version = 99");
+        let err = load_collection(archive.as_bytes()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unsupported archive format version 99"));
+    }
+}
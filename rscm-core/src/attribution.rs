@@ -0,0 +1,228 @@
+//! Attribution of changes in a model variable to groups of forcings
+//!
+//! The model is first run with all forcings.
+//! It is then re-run once per forcing group with the variables in that group set to zero.
+//! The contribution of a group is the difference between the run with all forcings
+//! and the run where the group has been zeroed out.
+//!
+//! Note that the contributions aren't guaranteed to sum to the total if the model is non-linear.
+use crate::errors::{RSCMError, RSCMResult};
+use crate::interpolate::strategies::{InterpolationStrategy, PreviousStrategy};
+use crate::model::ModelBuilder;
+use crate::timeseries::{FloatValue, Timeseries};
+use crate::timeseries_collection::{TimeseriesCollection, VariableType};
+use numpy::ndarray::Array;
+use std::collections::HashMap;
+
+/// A named set of exogenous variables which are zeroed out together
+///
+/// For example, a "GHG" group may contain the forcing from CO2, CH4 and N2O.
+#[derive(Debug, Clone)]
+pub struct ForcingGroup {
+    pub name: String,
+    pub variables: Vec<String>,
+}
+
+impl ForcingGroup {
+    pub fn new(name: &str, variables: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            variables: variables.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+}
+
+/// Results from an attribution run
+#[derive(Debug, Clone)]
+pub struct AttributionResult {
+    /// The target variable from the run with all forcings
+    pub total: Timeseries<FloatValue>,
+    /// The contribution of each forcing group to the target variable
+    pub contributions: HashMap<String, Timeseries<FloatValue>>,
+    /// The state of the model from the run with all forcings
+    pub collection: TimeseriesCollection,
+}
+
+/// Run a model with all forcings and then with each forcing group zeroed out
+///
+/// Each variable in `groups` must be an exogenous variable of the model built by `builder`.
+/// `target` is the variable to attribute, for example "Surface Temperature".
+pub fn run_attribution(
+    builder: &ModelBuilder,
+    target: &str,
+    groups: &[ForcingGroup],
+) -> RSCMResult<AttributionResult> {
+    let mut model = builder.build();
+    model.run();
+    let collection = model.timeseries().clone();
+    let total = get_target(&collection, target)?;
+
+    let mut counterfactual_builders = vec![];
+    for group in groups {
+        let mut counterfactual_builder = builder.clone();
+        for name in group.variables.iter() {
+            let item = collection
+                .get_by_name(name)
+                .ok_or_else(|| RSCMError::TimeseriesNotFound(format!("'{}'", name)))?;
+            if item.variable_type != VariableType::Exogenous {
+                return Err(RSCMError::Error(format!(
+                    "Cannot zero out '{}' in forcing group '{}' as it is not exogenous",
+                    name, group.name
+                )));
+            }
+
+            let zeros = Timeseries::new(
+                Array::zeros(builder.time_axis.len()),
+                builder.time_axis.clone(),
+                item.timeseries.units().to_string(),
+                InterpolationStrategy::from(PreviousStrategy::new(true)),
+            );
+            counterfactual_builder.with_exogenous_variable(name, zeros);
+        }
+        counterfactual_builders.push(counterfactual_builder);
+    }
+
+    // The counterfactual runs are independent so can be run in parallel
+    let threads = builder.options().threads.max(1);
+    let mut counterfactuals = vec![];
+    for chunk in counterfactual_builders.chunks(threads) {
+        let results: Vec<RSCMResult<Timeseries<FloatValue>>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|counterfactual_builder| {
+                    scope.spawn(move || {
+                        let mut counterfactual = counterfactual_builder.build();
+                        counterfactual.run();
+                        get_target(counterfactual.timeseries(), target)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Counterfactual run panicked"))
+                .collect()
+        });
+        counterfactuals.extend(results);
+    }
+
+    let mut contributions = HashMap::new();
+    for (group, counterfactual) in groups.iter().zip(counterfactuals) {
+        let counterfactual = counterfactual?;
+        let contribution = Timeseries::new(
+            &total.values() - &counterfactual.values(),
+            total.time_axis(),
+            total.units().to_string(),
+            InterpolationStrategy::from(PreviousStrategy::new(true)),
+        );
+        contributions.insert(group.name.clone(), contribution);
+    }
+
+    Ok(AttributionResult {
+        total,
+        contributions,
+        collection,
+    })
+}
+
+fn get_target(
+    collection: &TimeseriesCollection,
+    target: &str,
+) -> RSCMResult<Timeseries<FloatValue>> {
+    collection
+        .get_timeseries_by_name(target)
+        .cloned()
+        .ok_or_else(|| RSCMError::TimeseriesNotFound(format!("'{}'", target)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_components::TestOptionalInputComponent;
+    use crate::model::ModelOptions;
+    use crate::timeseries::TimeAxis;
+    use is_close::is_close;
+    use numpy::array;
+    use std::iter::zip;
+    use std::sync::Arc;
+
+    fn builder() -> ModelBuilder {
+        let mut builder = ModelBuilder::new();
+        builder
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_component(Arc::new(TestOptionalInputComponent {}))
+            .with_exogenous_variable(
+                "Effective Radiative Forcing|Aerosols",
+                Timeseries::from_values(array![-0.5, -1.0], array![2020.0, 2025.0]),
+            );
+        builder
+    }
+
+    #[test]
+    fn attribution() {
+        let result = run_attribution(
+            &builder(),
+            "Effective Radiative Forcing|Total",
+            &[ForcingGroup::new(
+                "aerosols",
+                &["Effective Radiative Forcing|Aerosols"],
+            )],
+        )
+        .unwrap();
+
+        let contribution = &result.contributions["aerosols"];
+        assert_eq!(contribution.len(), 5);
+        // Forcing at 2023 is used to calculate the total at 2024
+        assert!(is_close!(contribution.at_time(2024.0).unwrap(), -0.8));
+        // Without aerosols the total is zero
+        zip(contribution.values().iter(), result.total.values().iter())
+            .skip(1)
+            .for_each(|(c, t)| assert!(is_close!(*c, *t)));
+    }
+
+    #[test]
+    fn attribution_threads() {
+        let groups = [
+            ForcingGroup::new("aerosols", &["Effective Radiative Forcing|Aerosols"]),
+            ForcingGroup::new("all", &["Effective Radiative Forcing|Aerosols"]),
+            ForcingGroup::new("none", &[]),
+        ];
+        let mut builder = builder();
+        builder.with_options(ModelOptions {
+            threads: 2,
+            ..Default::default()
+        });
+
+        let result =
+            run_attribution(&builder, "Effective Radiative Forcing|Total", &groups).unwrap();
+
+        assert_eq!(result.contributions.len(), 3);
+        zip(
+            result.contributions["aerosols"].values().iter(),
+            result.contributions["all"].values().iter(),
+        )
+        .skip(1)
+        .for_each(|(a, b)| assert_eq!(a, b));
+        assert!(result.contributions["none"]
+            .values()
+            .iter()
+            .skip(1)
+            .all(|v| *v == 0.0));
+    }
+
+    #[test]
+    fn attribution_endogenous() {
+        let result = run_attribution(
+            &builder(),
+            "Effective Radiative Forcing|Total",
+            &[ForcingGroup::new(
+                "total",
+                &["Effective Radiative Forcing|Total"],
+            )],
+        );
+
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Cannot zero out 'Effective Radiative Forcing|Total' in forcing group 'total' as it is not exogenous"
+        );
+    }
+}
@@ -0,0 +1,435 @@
+//! Conversion of decimal years into calendar dates
+//!
+//! Time axes use decimal years, but downstream tools generally expect date-like coordinates.
+//! A decimal year `t` refers to the point `t - floor(t)` of the way through the year `floor(t)`
+//! so the date depends on the number of days in the year and hence the [`Calendar`].
+use crate::errors::{RSCMError, RSCMResult};
+use crate::timeseries::{Time, TimeAxis};
+use pyo3::pyclass;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Tolerance used when converting fractions of a year into days
+///
+/// This ensures that decimal years calculated from the start of a day aren't rounded into the
+/// previous day due to floating point error.
+const DAY_TOLERANCE: Time = 1e-6;
+
+/// Calendar used to convert decimal years into dates
+#[pyclass]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Calendar {
+    /// Gregorian calendar with leap years, extended to dates before 1582
+    #[default]
+    ProlepticGregorian,
+    /// Calendar without leap years where every year has 365 days
+    NoLeap,
+}
+
+impl Calendar {
+    /// Create a calendar from its CF-conventions name, e.g. "365_day"
+    pub fn from_name(name: &str) -> RSCMResult<Self> {
+        match name {
+            "proleptic_gregorian" | "gregorian" | "standard" => Ok(Self::ProlepticGregorian),
+            "noleap" | "365_day" => Ok(Self::NoLeap),
+            _ => Err(RSCMError::Error(format!("Unknown calendar '{}'", name))),
+        }
+    }
+
+    fn is_leap_year(&self, year: i32) -> bool {
+        match self {
+            Self::ProlepticGregorian => (year % 4 == 0 && year % 100 != 0) || year % 400 == 0,
+            Self::NoLeap => false,
+        }
+    }
+
+    pub fn days_in_year(&self, year: i32) -> u32 {
+        match self.is_leap_year(year) {
+            true => 366,
+            false => 365,
+        }
+    }
+
+    pub fn days_in_month(&self, year: i32, month: u32) -> u32 {
+        match month {
+            2 if self.is_leap_year(year) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+}
+
+/// A calendar date
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    /// Month of the year, starting from 1
+    pub month: u32,
+    /// Day of the month, starting from 1
+    pub day: u32,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Self {
+        Self { year, month, day }
+    }
+
+    /// Date containing a decimal year
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rscm_core::calendar::{Calendar, Date};
+    ///
+    /// assert_eq!(Date::from_decimal_year(2000.5, Calendar::ProlepticGregorian), Date::new(2000, 7, 2));
+    /// assert_eq!(Date::from_decimal_year(2001.5, Calendar::NoLeap), Date::new(2001, 7, 2));
+    /// ```
+    pub fn from_decimal_year(time: Time, calendar: Calendar) -> Self {
+        let year = time.floor() as i32;
+        let days = calendar.days_in_year(year);
+        let mut day_of_year =
+            (((time - time.floor()) * days as Time + DAY_TOLERANCE).floor() as u32).min(days - 1);
+
+        let mut month = 1;
+        while day_of_year >= calendar.days_in_month(year, month) {
+            day_of_year -= calendar.days_in_month(year, month);
+            month += 1;
+        }
+        Self::new(year, month, day_of_year + 1)
+    }
+
+    /// Decimal year at the start of the date
+    pub fn to_decimal_year(&self, calendar: Calendar) -> Time {
+        let day_of_year: u32 = (1..self.month)
+            .map(|month| calendar.days_in_month(self.year, month))
+            .sum::<u32>()
+            + self.day
+            - 1;
+        self.year as Time + day_of_year as Time / calendar.days_in_year(self.year) as Time
+    }
+
+    /// First day of the following month
+    pub fn next_month(&self) -> Self {
+        match self.month {
+            12 => Self::new(self.year + 1, 1, 1),
+            month => Self::new(self.year, month + 1, 1),
+        }
+    }
+
+    /// The following day
+    pub fn next_day(&self, calendar: Calendar) -> Self {
+        match self.day < calendar.days_in_month(self.year, self.month) {
+            true => Self::new(self.year, self.month, self.day + 1),
+            false => self.next_month(),
+        }
+    }
+
+    /// ISO 8601 representation of the year and month, e.g. "2000-07"
+    pub fn year_month(&self) -> String {
+        format!("{:04}-{:02}", self.year, self.month)
+    }
+
+    /// Check that the date exists in a calendar, e.g. 29 February doesn't exist in
+    /// [`Calendar::NoLeap`]
+    pub fn is_valid(&self, calendar: Calendar) -> bool {
+        (1..=12).contains(&self.month)
+            && (1..=calendar.days_in_month(self.year, self.month)).contains(&self.day)
+    }
+
+    /// Date in `calendar` with the same year, month and day as a chrono date
+    ///
+    /// Returns an error if the date doesn't exist in `calendar`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use chrono::NaiveDate;
+    /// use rscm_core::calendar::{Calendar, Date};
+    ///
+    /// let leap_day = NaiveDate::from_ymd_opt(2000, 2, 29).unwrap();
+    /// assert_eq!(
+    ///     Date::from_naive_date(leap_day, Calendar::ProlepticGregorian).unwrap(),
+    ///     Date::new(2000, 2, 29)
+    /// );
+    /// assert!(Date::from_naive_date(leap_day, Calendar::NoLeap).is_err());
+    /// ```
+    #[cfg(feature = "chrono")]
+    pub fn from_naive_date(date: chrono::NaiveDate, calendar: Calendar) -> RSCMResult<Self> {
+        use chrono::Datelike;
+
+        let converted = Self::new(date.year(), date.month(), date.day());
+        match converted.is_valid(calendar) {
+            true => Ok(converted),
+            false => Err(RSCMError::Error(format!(
+                "{} doesn't exist in the {:?} calendar",
+                converted, calendar
+            ))),
+        }
+    }
+}
+
+/// Convert a chrono date, which uses the proleptic Gregorian calendar
+///
+/// See [`Date::from_naive_date`] to convert into a date in another calendar.
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::NaiveDate> for Date {
+    type Error = RSCMError;
+
+    fn try_from(date: chrono::NaiveDate) -> Result<Self, Self::Error> {
+        Self::from_naive_date(date, Calendar::ProlepticGregorian)
+    }
+}
+
+/// Convert into a chrono date with the same year, month and day
+///
+/// Every date in the supported calendars exists in the proleptic Gregorian calendar used by
+/// chrono.
+///
+/// # Panics
+///
+/// Panics if the date isn't valid in the proleptic Gregorian calendar (see [`Date::is_valid`])
+/// or is outside the range of years supported by chrono.
+#[cfg(feature = "chrono")]
+impl From<Date> for chrono::NaiveDate {
+    fn from(date: Date) -> Self {
+        chrono::NaiveDate::from_ymd_opt(date.year, date.month, date.day)
+            .unwrap_or_else(|| panic!("{} can't be represented by chrono::NaiveDate", date))
+    }
+}
+
+/// ISO 8601 representation of the date, e.g. "2000-07-02"
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{:02}", self.year_month(), self.day)
+    }
+}
+
+impl TimeAxis {
+    /// Time axis with a time step starting at each date
+    ///
+    /// The last date is the end of the last time step.
+    /// Panics if there are fewer than two dates.
+    pub fn from_dates(dates: &[Date], calendar: Calendar) -> Self {
+        Self::from_bounds(
+            dates
+                .iter()
+                .map(|date| date.to_decimal_year(calendar))
+                .collect(),
+        )
+    }
+
+    /// Time axis with a time step for each month from the start of `start_year`
+    /// to the end of `end_year`
+    ///
+    /// Each time step starts on the first day of a month,
+    /// so the length of the steps in decimal years depends on the number of days in each month.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rscm_core::calendar::Calendar;
+    /// use rscm_core::timeseries::TimeAxis;
+    ///
+    /// let time_axis = TimeAxis::monthly(2000, 2001, Calendar::NoLeap);
+    /// assert_eq!(time_axis.len(), 24);
+    /// assert_eq!(time_axis.labels(Calendar::NoLeap, false)[13], "2001-02");
+    /// ```
+    pub fn monthly(start_year: i32, end_year: i32, calendar: Calendar) -> Self {
+        Self::from_dates(
+            &dates_until(Date::new(start_year, 1, 1), end_year, Date::next_month),
+            calendar,
+        )
+    }
+
+    /// Time axis with a time step for each day from the start of `start_year`
+    /// to the end of `end_year`
+    pub fn daily(start_year: i32, end_year: i32, calendar: Calendar) -> Self {
+        Self::from_dates(
+            &dates_until(Date::new(start_year, 1, 1), end_year, |date| {
+                date.next_day(calendar)
+            }),
+            calendar,
+        )
+    }
+
+    /// Dates of the start of each time step
+    pub fn dates(&self, calendar: Calendar) -> Vec<Date> {
+        self.values()
+            .iter()
+            .map(|time| Date::from_decimal_year(*time, calendar))
+            .collect()
+    }
+
+    /// ISO 8601 labels for the start of each time step
+    ///
+    /// Labels contain the year and month (e.g. "2000-07") unless `include_day` is true.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use rscm_core::calendar::Calendar;
+    /// use rscm_core::timeseries::TimeAxis;
+    ///
+    /// let time_axis = TimeAxis::from_values(array![2000.0, 2000.5, 2001.0]);
+    /// assert_eq!(time_axis.labels(Calendar::NoLeap, false), vec!["2000-01", "2000-07", "2001-01"]);
+    /// ```
+    pub fn labels(&self, calendar: Calendar, include_day: bool) -> Vec<String> {
+        self.dates(calendar)
+            .iter()
+            .map(|date| match include_day {
+                true => date.to_string(),
+                false => date.year_month(),
+            })
+            .collect()
+    }
+}
+
+/// Dates from `start` up to and including the start of the year after `end_year`
+fn dates_until(start: Date, end_year: i32, next: impl Fn(&Date) -> Date) -> Vec<Date> {
+    let end = Date::new(end_year + 1, 1, 1);
+    std::iter::successors(Some(start), |date| Some(next(date)))
+        .take_while(|date| *date <= end)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use is_close::is_close;
+    use numpy::ndarray::Array;
+
+    #[test]
+    fn month_starts_roundtrip() {
+        for calendar in [Calendar::ProlepticGregorian, Calendar::NoLeap] {
+            for year in [1900, 2000, 2023, 2024] {
+                for month in 1..=12 {
+                    let date = Date::new(year, month, 1);
+                    let time = date.to_decimal_year(calendar);
+                    assert_eq!(Date::from_decimal_year(time, calendar), date);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn calendars() {
+        // 2000 is a leap year, 1900 isn't
+        let time = 2000.0 + 59.0 / 366.0;
+        assert_eq!(
+            Date::from_decimal_year(time, Calendar::ProlepticGregorian).to_string(),
+            "2000-02-29"
+        );
+        assert_eq!(
+            Date::from_decimal_year(time, Calendar::NoLeap).to_string(),
+            "2000-02-28"
+        );
+        assert_eq!(Calendar::ProlepticGregorian.days_in_year(1900), 365);
+        assert_eq!(Calendar::from_name("365_day").unwrap(), Calendar::NoLeap);
+        assert!(Calendar::from_name("360_day").is_err());
+    }
+
+    #[test]
+    fn monthly() {
+        let time_axis = TimeAxis::monthly(2023, 2024, Calendar::ProlepticGregorian);
+        assert_eq!(time_axis.len(), 24);
+        assert_eq!(*time_axis.first(), 2023.0);
+        assert_eq!(time_axis.at_bounds(23).unwrap().1, 2025.0);
+
+        // February is longer in a leap year
+        let february = |index: usize| {
+            let (start, end) = time_axis.at_bounds(index).unwrap();
+            end - start
+        };
+        assert!(is_close!(february(1), 28.0 / 365.0));
+        assert!(is_close!(february(13), 29.0 / 366.0));
+
+        let dates = time_axis.dates(Calendar::ProlepticGregorian);
+        assert_eq!(dates[13], Date::new(2024, 2, 1));
+        assert_eq!(
+            TimeAxis::from_dates(
+                &[Date::new(2024, 2, 1), Date::new(2024, 3, 1)],
+                Calendar::ProlepticGregorian
+            )
+            .at_bounds(0),
+            time_axis.at_bounds(13)
+        );
+    }
+
+    #[test]
+    fn daily() {
+        assert_eq!(
+            TimeAxis::daily(2024, 2024, Calendar::ProlepticGregorian).len(),
+            366
+        );
+        assert_eq!(TimeAxis::daily(2024, 2024, Calendar::NoLeap).len(), 365);
+
+        let time_axis = TimeAxis::daily(2023, 2024, Calendar::ProlepticGregorian);
+        assert_eq!(time_axis.len(), 365 + 366);
+        assert_eq!(
+            time_axis.labels(Calendar::ProlepticGregorian, true)[365 + 59],
+            "2024-02-29"
+        );
+        assert_eq!(
+            Date::new(2023, 12, 31).next_day(Calendar::NoLeap),
+            Date::new(2024, 1, 1)
+        );
+    }
+
+    #[test]
+    fn is_valid() {
+        assert!(Date::new(2024, 2, 29).is_valid(Calendar::ProlepticGregorian));
+        assert!(!Date::new(2024, 2, 29).is_valid(Calendar::NoLeap));
+        assert!(!Date::new(2023, 4, 31).is_valid(Calendar::ProlepticGregorian));
+        assert!(!Date::new(2023, 13, 1).is_valid(Calendar::NoLeap));
+        assert!(!Date::new(2023, 1, 0).is_valid(Calendar::NoLeap));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_conversions() {
+        use chrono::NaiveDate;
+
+        let naive = |year, month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+
+        for date in [
+            Date::new(1850, 1, 1),
+            Date::new(2000, 2, 29),
+            Date::new(2023, 12, 31),
+        ] {
+            let converted = NaiveDate::from(date);
+            assert_eq!(converted, naive(date.year, date.month, date.day));
+            assert_eq!(Date::try_from(converted).unwrap(), date);
+        }
+
+        // Every date in a year without leap days
+        let time_axis = TimeAxis::daily(2024, 2024, Calendar::NoLeap);
+        for date in time_axis.dates(Calendar::NoLeap) {
+            assert_eq!(
+                Date::from_naive_date(NaiveDate::from(date), Calendar::NoLeap).unwrap(),
+                date
+            );
+        }
+
+        let err = Date::from_naive_date(naive(2024, 2, 29), Calendar::NoLeap).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "2024-02-29 doesn't exist in the NoLeap calendar"
+        );
+        assert_eq!(
+            Date::try_from(naive(2024, 2, 29)).unwrap(),
+            Date::new(2024, 2, 29)
+        );
+    }
+
+    #[test]
+    fn labels() {
+        let time_axis = TimeAxis::from_values(Array::range(1850.0, 1853.0, 1.0));
+
+        assert_eq!(
+            time_axis.labels(Calendar::ProlepticGregorian, true),
+            vec!["1850-01-01", "1851-01-01", "1852-01-01"]
+        );
+    }
+}
@@ -0,0 +1,558 @@
+//! Calibration against several observational targets
+//!
+//! A [`Target`] compares a model variable against a reference over a window
+//! using the root-mean-square error from [`crate::metrics::skill`].
+//! [`MultiObjectiveCalibration`] evaluates parameter sets against all targets simultaneously.
+//! The targets can either be combined into a single score using their weights
+//! ([`MultiObjectiveCalibration::weighted_search`])
+//! or kept separate to find the trade-off between them
+//! ([`MultiObjectiveCalibration::pareto_search`]).
+//!
+//! The Pareto search is a simplified NSGA-II (Deb et al., 2002) which uses
+//! simulated binary crossover and polynomial mutation.
+//!
+//! Parameter sets for which the model fails to run are assigned infinite objectives
+//! so that they are dominated by every successful parameter set.
+//!
+//! See [`crate::optimise`] to fit the parameters to a single cost using the Nelder-Mead method.
+use crate::component::ModelRng;
+use crate::ensemble::{run_ensemble, FailurePolicy};
+use crate::errors::{RSCMError, RSCMResult};
+use crate::metrics::skill;
+use crate::model::ModelBuilder;
+use crate::sampling::{scale_to_ranges, LatinHypercubeSampler, Sampler};
+use crate::timeseries::{FloatValue, Time, Timeseries};
+use crate::timeseries_collection::TimeseriesCollection;
+use rand::Rng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// Values of each calibrated parameter
+pub type ParameterSet = BTreeMap<String, FloatValue>;
+
+/// A parameter which is varied during calibration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationParameter {
+    pub name: String,
+    pub min: FloatValue,
+    pub max: FloatValue,
+}
+
+/// An observational target
+#[derive(Debug, Clone)]
+pub struct Target {
+    /// Name of the model variable
+    pub variable: String,
+    /// Reference values to compare against
+    pub reference: Timeseries<FloatValue>,
+    /// Start of the comparison window (inclusive)
+    pub start: Time,
+    /// End of the comparison window (inclusive)
+    pub end: Time,
+    /// Weight used when combining targets into a single score
+    ///
+    /// Targets in different units should be weighted so that their errors are comparable,
+    /// for example by the inverse of the observational uncertainty.
+    pub weight: FloatValue,
+}
+
+impl Target {
+    pub fn new(variable: &str, reference: Timeseries<FloatValue>, start: Time, end: Time) -> Self {
+        Self {
+            variable: variable.to_string(),
+            reference,
+            start,
+            end,
+            weight: 1.0,
+        }
+    }
+
+    pub fn with_weight(mut self, weight: FloatValue) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Root-mean-square error of the model output relative to the reference
+    pub fn objective(&self, output: &TimeseriesCollection) -> RSCMResult<FloatValue> {
+        let timeseries = output
+            .get_timeseries_by_name(&self.variable)
+            .ok_or_else(|| RSCMError::TimeseriesNotFound(format!("'{}'", self.variable)))?;
+        Ok(skill(timeseries, &self.reference, self.start, self.end)?.rmse)
+    }
+}
+
+/// A parameter set and its objective for each target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub parameters: ParameterSet,
+    /// Objective for each target in the order that the targets were added
+    ///
+    /// Lower values are better.
+    pub objectives: Vec<FloatValue>,
+}
+
+impl Candidate {
+    /// Whether this candidate is at least as good as `other` for every objective
+    /// and better for at least one
+    pub fn dominates(&self, other: &Candidate) -> bool {
+        let pairs = || self.objectives.iter().zip(other.objectives.iter());
+        pairs().all(|(a, b)| a <= b) && pairs().any(|(a, b)| a < b)
+    }
+
+    /// Weighted sum of the objectives
+    pub fn weighted(&self, weights: &[FloatValue]) -> FloatValue {
+        self.objectives
+            .iter()
+            .zip(weights)
+            .map(|(objective, weight)| objective * weight)
+            .sum()
+    }
+}
+
+/// Candidates which are not dominated by any other candidate
+pub fn pareto_front(candidates: &[Candidate]) -> Vec<Candidate> {
+    candidates
+        .iter()
+        .filter(|c| !candidates.iter().any(|other| other.dominates(c)))
+        .cloned()
+        .collect()
+}
+
+/// Options for [`MultiObjectiveCalibration::pareto_search`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParetoSearchOptions {
+    /// Number of parameter sets in each generation
+    pub population: usize,
+    /// Number of generations after the initial population
+    pub generations: usize,
+    pub seed: u64,
+    /// Distribution index of the simulated binary crossover
+    ///
+    /// Larger values produce offspring closer to their parents.
+    pub crossover_index: FloatValue,
+    /// Distribution index of the polynomial mutation
+    pub mutation_index: FloatValue,
+}
+
+impl Default for ParetoSearchOptions {
+    fn default() -> Self {
+        Self {
+            population: 40,
+            generations: 20,
+            seed: 0,
+            crossover_index: 15.0,
+            mutation_index: 20.0,
+        }
+    }
+}
+
+/// Calibration of a set of parameters against several targets
+#[derive(Debug, Clone, Default)]
+pub struct MultiObjectiveCalibration {
+    pub parameters: Vec<CalibrationParameter>,
+    pub targets: Vec<Target>,
+}
+
+impl MultiObjectiveCalibration {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_parameter(&mut self, name: &str, min: FloatValue, max: FloatValue) -> &mut Self {
+        self.parameters.push(CalibrationParameter {
+            name: name.to_string(),
+            min,
+            max,
+        });
+        self
+    }
+
+    pub fn with_target(&mut self, target: Target) -> &mut Self {
+        self.targets.push(target);
+        self
+    }
+
+    /// Weight of each target
+    pub fn weights(&self) -> Vec<FloatValue> {
+        self.targets.iter().map(|t| t.weight).collect()
+    }
+
+    fn validate(&self) -> RSCMResult<()> {
+        if self.parameters.is_empty() || self.targets.is_empty() {
+            return Err(RSCMError::Error(
+                "At least one parameter and one target are required".to_string(),
+            ));
+        }
+        if let Some(p) = self
+            .parameters
+            .iter()
+            .find(|p| p.min.partial_cmp(&p.max) != Some(Ordering::Less))
+        {
+            return Err(RSCMError::Error(format!(
+                "Invalid range [{}, {}] for parameter '{}'",
+                p.min, p.max, p.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Convert a point in the unit hypercube into a parameter set
+    fn parameter_set(&self, point: &[FloatValue]) -> ParameterSet {
+        let ranges: Vec<_> = self.parameters.iter().map(|p| (p.min, p.max)).collect();
+        self.parameters
+            .iter()
+            .map(|p| p.name.clone())
+            .zip(scale_to_ranges(point, &ranges))
+            .collect()
+    }
+
+    /// Run the model for each point in the unit hypercube and evaluate the targets
+    fn evaluate_points<F>(
+        &self,
+        points: &[Vec<FloatValue>],
+        build: &F,
+    ) -> RSCMResult<Vec<Candidate>>
+    where
+        F: Fn(&ParameterSet) -> ModelBuilder,
+    {
+        let parameter_sets: Vec<_> = points.iter().map(|p| self.parameter_set(p)).collect();
+        self.evaluate(&parameter_sets, build)
+    }
+
+    /// Run the model for each parameter set and evaluate the targets
+    ///
+    /// `build` creates a model builder for a parameter set.
+    pub fn evaluate<F>(
+        &self,
+        parameter_sets: &[ParameterSet],
+        build: &F,
+    ) -> RSCMResult<Vec<Candidate>>
+    where
+        F: Fn(&ParameterSet) -> ModelBuilder,
+    {
+        let ensemble = run_ensemble(parameter_sets, build, &FailurePolicy::SkipAndRecord)?;
+
+        let mut candidates: Vec<Candidate> = parameter_sets
+            .iter()
+            .map(|parameters| Candidate {
+                parameters: parameters.clone(),
+                objectives: vec![FloatValue::INFINITY; self.targets.len()],
+            })
+            .collect();
+        for (index, output) in ensemble.results.iter() {
+            candidates[*index].objectives = self
+                .targets
+                .iter()
+                .map(|target| target.objective(output))
+                .collect::<RSCMResult<_>>()?;
+        }
+        Ok(candidates)
+    }
+
+    /// Evaluate `n` parameter sets from `sampler` and rank them by the weighted sum of
+    /// their objectives
+    ///
+    /// The best candidate is first.
+    pub fn weighted_search<F>(
+        &self,
+        n: usize,
+        sampler: &mut dyn Sampler,
+        build: F,
+    ) -> RSCMResult<Vec<Candidate>>
+    where
+        F: Fn(&ParameterSet) -> ModelBuilder,
+    {
+        self.validate()?;
+        let points = sampler.sample(n, self.parameters.len())?;
+        let mut candidates = self.evaluate_points(&points, &build)?;
+
+        let weights = self.weights();
+        candidates.sort_by(|a, b| {
+            a.weighted(&weights)
+                .partial_cmp(&b.weighted(&weights))
+                .unwrap_or(Ordering::Equal)
+        });
+        Ok(candidates)
+    }
+
+    /// Search for the trade-off front between the targets
+    ///
+    /// Returns the non-dominated candidates of the final generation.
+    pub fn pareto_search<F>(
+        &self,
+        options: &ParetoSearchOptions,
+        build: F,
+    ) -> RSCMResult<Vec<Candidate>>
+    where
+        F: Fn(&ParameterSet) -> ModelBuilder,
+    {
+        self.validate()?;
+        if options.population < 2 {
+            return Err(RSCMError::Error(
+                "The population must contain at least two members".to_string(),
+            ));
+        }
+        let dimensions = self.parameters.len();
+        let mut rng = ModelRng::seed_from_u64(options.seed);
+
+        let mut points =
+            LatinHypercubeSampler::new(options.seed).sample(options.population, dimensions)?;
+        let mut candidates = self.evaluate_points(&points, &build)?;
+
+        for _ in 0..options.generations {
+            let (ranks, crowding) = rank_and_crowding(&candidates);
+            let better = |a: usize, b: usize| {
+                (ranks[a], -crowding[a]).partial_cmp(&(ranks[b], -crowding[b]))
+                    == Some(Ordering::Less)
+            };
+            let tournament = |rng: &mut ModelRng| {
+                let a = rng.gen_range(0..points.len());
+                let b = rng.gen_range(0..points.len());
+                if better(b, a) {
+                    b
+                } else {
+                    a
+                }
+            };
+
+            let mut offspring = vec![];
+            while offspring.len() < options.population {
+                let first = &points[tournament(&mut rng)];
+                let second = &points[tournament(&mut rng)];
+                let (mut a, mut b) =
+                    simulated_binary_crossover(first, second, options.crossover_index, &mut rng);
+                polynomial_mutation(&mut a, options.mutation_index, &mut rng);
+                polynomial_mutation(&mut b, options.mutation_index, &mut rng);
+                offspring.push(a);
+                offspring.push(b);
+            }
+            offspring.truncate(options.population);
+            let offspring_candidates = self.evaluate_points(&offspring, &build)?;
+
+            // Select the next generation from the parents and offspring
+            points.extend(offspring);
+            candidates.extend(offspring_candidates);
+            let (ranks, crowding) = rank_and_crowding(&candidates);
+            let mut order: Vec<usize> = (0..candidates.len()).collect();
+            order.sort_by(|&a, &b| {
+                ranks[a].cmp(&ranks[b]).then(
+                    crowding[b]
+                        .partial_cmp(&crowding[a])
+                        .unwrap_or(Ordering::Equal),
+                )
+            });
+            order.truncate(options.population);
+            points = order.iter().map(|&i| points[i].clone()).collect();
+            candidates = order.iter().map(|&i| candidates[i].clone()).collect();
+        }
+
+        Ok(pareto_front(&candidates))
+    }
+}
+
+/// Non-domination rank and crowding distance of each candidate
+///
+/// Rank 0 is the Pareto front.
+/// The crowding distance is calculated within each rank and is infinite for the
+/// extremes of each objective.
+fn rank_and_crowding(candidates: &[Candidate]) -> (Vec<usize>, Vec<FloatValue>) {
+    let mut ranks = vec![usize::MAX; candidates.len()];
+    let mut crowding = vec![0.0; candidates.len()];
+
+    let mut rank = 0;
+    let mut remaining: Vec<usize> = (0..candidates.len()).collect();
+    while !remaining.is_empty() {
+        let (front, rest): (Vec<usize>, Vec<usize>) = remaining.iter().partition(|&&i| {
+            !remaining
+                .iter()
+                .any(|&j| candidates[j].dominates(&candidates[i]))
+        });
+
+        let n_objectives = candidates[front[0]].objectives.len();
+        for objective in 0..n_objectives {
+            let value = |i: usize| candidates[i].objectives[objective];
+            let mut sorted = front.clone();
+            sorted.sort_by(|&a, &b| value(a).partial_cmp(&value(b)).unwrap_or(Ordering::Equal));
+
+            let first = sorted[0];
+            let last = sorted[sorted.len() - 1];
+            crowding[first] = FloatValue::INFINITY;
+            crowding[last] = FloatValue::INFINITY;
+
+            let range = value(last) - value(first);
+            if range > 0.0 && range.is_finite() {
+                for window in sorted.windows(3) {
+                    crowding[window[1]] += (value(window[2]) - value(window[0])) / range;
+                }
+            }
+        }
+
+        front.iter().for_each(|&i| ranks[i] = rank);
+        rank += 1;
+        remaining = rest;
+    }
+
+    (ranks, crowding)
+}
+
+/// Simulated binary crossover of two points in the unit hypercube
+fn simulated_binary_crossover(
+    first: &[FloatValue],
+    second: &[FloatValue],
+    index: FloatValue,
+    rng: &mut ModelRng,
+) -> (Vec<FloatValue>, Vec<FloatValue>) {
+    first
+        .iter()
+        .zip(second)
+        .map(|(&a, &b)| {
+            if rng.gen_bool(0.5) {
+                return (a, b);
+            }
+            let u: FloatValue = rng.gen();
+            let beta = if u <= 0.5 {
+                (2.0 * u).powf(1.0 / (index + 1.0))
+            } else {
+                (1.0 / (2.0 * (1.0 - u))).powf(1.0 / (index + 1.0))
+            };
+            (
+                (0.5 * ((1.0 + beta) * a + (1.0 - beta) * b)).clamp(0.0, 1.0),
+                (0.5 * ((1.0 - beta) * a + (1.0 + beta) * b)).clamp(0.0, 1.0),
+            )
+        })
+        .unzip()
+}
+
+/// Polynomial mutation of a point in the unit hypercube
+///
+/// Each coordinate is mutated with a probability of one over the number of dimensions.
+fn polynomial_mutation(point: &mut [FloatValue], index: FloatValue, rng: &mut ModelRng) {
+    let probability = 1.0 / point.len() as f64;
+    for x in point.iter_mut() {
+        if !rng.gen_bool(probability) {
+            continue;
+        }
+        let u: FloatValue = rng.gen();
+        let delta = if u < 0.5 {
+            (2.0 * u).powf(1.0 / (index + 1.0)) - 1.0
+        } else {
+            1.0 - (2.0 * (1.0 - u)).powf(1.0 / (index + 1.0))
+        };
+        *x = (*x + delta).clamp(0.0, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_components::build_test_model as build;
+    use crate::interpolate::strategies::{InterpolationStrategy, LinearSplineStrategy};
+    use crate::sampling::HaltonSampler;
+    use crate::timeseries::TimeAxis;
+    use numpy::array;
+    use std::sync::Arc;
+
+    fn reference(value: FloatValue) -> Timeseries<FloatValue> {
+        Timeseries::new(
+            array![value, value, value],
+            Arc::new(TimeAxis::from_values(array![2021.0, 2022.0, 2023.0])),
+            "ppm".to_string(),
+            InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+        )
+    }
+
+    /// Two targets which are matched by p = 0.5 and p = 1.5 respectively
+    fn calibration() -> MultiObjectiveCalibration {
+        let mut calibration = MultiObjectiveCalibration::new();
+        calibration
+            .with_parameter("p", 0.0, 2.0)
+            .with_target(Target::new(
+                "Concentrations|CO2",
+                reference(0.65),
+                2021.0,
+                2023.0,
+            ))
+            .with_target(
+                Target::new("Concentrations|CO2", reference(1.95), 2021.0, 2023.0).with_weight(0.0),
+            );
+        calibration
+    }
+
+    #[test]
+    fn dominance() {
+        let candidate = |objectives: Vec<FloatValue>| Candidate {
+            parameters: ParameterSet::new(),
+            objectives,
+        };
+        let candidates = vec![
+            candidate(vec![1.0, 2.0]),
+            candidate(vec![2.0, 1.0]),
+            candidate(vec![2.0, 2.0]),
+            candidate(vec![1.0, 2.0]),
+        ];
+
+        assert!(candidates[0].dominates(&candidates[2]));
+        assert!(!candidates[0].dominates(&candidates[1]));
+        assert!(!candidates[0].dominates(&candidates[3]));
+        assert_eq!(
+            pareto_front(&candidates),
+            vec![
+                candidates[0].clone(),
+                candidates[1].clone(),
+                candidates[3].clone()
+            ]
+        );
+        assert_eq!(candidates[0].weighted(&[1.0, 0.5]), 2.0);
+    }
+
+    #[test]
+    fn weighted_search() {
+        let candidates = calibration()
+            .weighted_search(32, &mut HaltonSampler::new(), build)
+            .unwrap();
+
+        assert_eq!(candidates.len(), 32);
+        assert!((candidates[0].parameters["p"] - 0.5).abs() < 0.05);
+        assert!(candidates[0].objectives[0] < 0.1);
+    }
+
+    #[test]
+    fn pareto_search() {
+        let options = ParetoSearchOptions {
+            population: 16,
+            generations: 20,
+            ..Default::default()
+        };
+        let front = calibration().pareto_search(&options, build).unwrap();
+
+        assert!(!front.is_empty());
+        for candidate in front.iter() {
+            let p = candidate.parameters["p"];
+            assert!((0.45..=1.55).contains(&p), "p={} is not on the front", p);
+        }
+        assert!(front.iter().any(|c| c.parameters["p"] < 0.6));
+        assert!(front.iter().any(|c| c.parameters["p"] > 1.4));
+    }
+
+    #[test]
+    fn invalid_calibration() {
+        let mut calibration = MultiObjectiveCalibration::new();
+        assert!(calibration
+            .weighted_search(4, &mut HaltonSampler::new(), build)
+            .is_err());
+
+        calibration
+            .with_parameter("p", 1.0, 0.0)
+            .with_target(Target::new(
+                "Concentrations|CO2",
+                reference(1.0),
+                2021.0,
+                2023.0,
+            ));
+        assert!(calibration
+            .pareto_search(&ParetoSearchOptions::default(), build)
+            .is_err());
+    }
+}
@@ -1,7 +1,13 @@
-use crate::errors::RSCMResult;
-use crate::timeseries::{FloatValue, Time};
+use crate::cost::CostHint;
+use crate::dual::DualState;
+use crate::errors::{RSCMError, RSCMResult};
+use crate::model::{ModelOptions, DEFAULT_MODEL_OPTIONS};
+use crate::timeseries::{DisaggregationStrategy, FloatValue, Time, TimeAxis, TimeUnit};
 use crate::timeseries_collection::{TimeseriesCollection, VariableType};
+use indexmap::IndexMap;
 use pyo3::pyclass;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Debug;
@@ -12,34 +18,43 @@ use std::iter::zip;
 /// A state is a collection of values
 /// that can be used to represent the state of a system at a given time.
 ///
-/// This is very similar to a Hashmap (with likely worse performance),
-/// but provides strong type separation.
+/// This is very similar to a Hashmap, but provides strong type separation.
 pub trait State<T> {
-    fn get(&self, name: &str) -> &T;
+    /// Get the value of a variable, returning an error if the variable isn't present
+    fn try_get(&self, name: &str) -> RSCMResult<&T>;
+
+    /// Get the value of a variable
+    ///
+    /// Panics if the variable isn't present.
+    fn get(&self, name: &str) -> &T {
+        self.try_get(name).unwrap_or_else(|err| panic!("{}", err))
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Values of a set of variables
+///
+/// Values are looked up by name in constant time
+/// and are iterated over in the order in which they were first added.
+#[derive(Debug, Clone, Default)]
 pub struct InputState {
-    state: Vec<(String, FloatValue)>,
+    state: IndexMap<String, FloatValue>,
 }
 
 impl InputState {
     pub fn from_vectors(values: Vec<FloatValue>, names: Vec<String>) -> Self {
         assert_eq!(values.len(), names.len());
-        let state: Vec<(String, FloatValue)> = zip(names, values).collect();
+        let state = zip(names, values).collect();
         Self { state }
     }
 
     pub fn empty() -> Self {
-        Self { state: vec![] }
+        Self::default()
     }
 
     pub fn from_hashmap(items: HashMap<String, FloatValue>) -> Self {
-        let mut state = vec![];
-        items.into_iter().for_each(|(name, value)| {
-            state.push((name, value));
-        });
-        Self { state }
+        Self {
+            state: items.into_iter().collect(),
+        }
     }
 
     pub fn from_hashmap_and_verify(
@@ -56,26 +71,34 @@ impl InputState {
         Self::from_hashmap(items)
     }
 
+    /// Get the value of a label of a labelled variable
+    ///
+    /// Panics if the label isn't present, in the same way as [`State::get`].
+    pub fn get_labelled(&self, name: &str, label: &str) -> &FloatValue {
+        self.get(&labelled_name(name, label))
+    }
+
+    /// Get the values of each label of a labelled variable
+    pub fn get_labels(&self, name: &str, labels: &[String]) -> Vec<FloatValue> {
+        labels
+            .iter()
+            .map(|label| *self.get_labelled(name, label))
+            .collect()
+    }
+
     pub fn has(&self, name: &str) -> bool {
-        self.state.iter().any(|(n, _)| *n == name)
+        self.state.contains_key(name)
     }
 
     /// Merge state into this state
     ///
     /// Overrides any existing values with the same name
     pub fn merge(&mut self, state: InputState) -> &mut Self {
-        state.into_iter().for_each(|(key, value)| {
-            let existing = self.state.iter_mut().find(|(n, _)| *n == key);
-
-            match existing {
-                Some(item) => *item = (key, value),
-                None => self.state.push((key, value)),
-            }
-        });
+        self.state.extend(state.state);
         self
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &(String, FloatValue)> {
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &FloatValue)> {
         self.state.iter()
     }
 
@@ -85,18 +108,20 @@ impl InputState {
     }
 }
 impl State<FloatValue> for InputState {
-    fn get(&self, name: &str) -> &FloatValue {
-        let found = self.state.iter().find(|(n, _)| *n == name).map(|(_, v)| v);
-        match found {
-            Some(val) => val,
-            None => panic!("No state named {} found in {:?}", name, self),
-        }
+    fn try_get(&self, name: &str) -> RSCMResult<&FloatValue> {
+        self.state.get(name).ok_or_else(|| {
+            RSCMError::Error(format!(
+                "No state named {} found in {:?}",
+                name,
+                self.state.keys().collect::<Vec<_>>()
+            ))
+        })
     }
 }
 
 impl IntoIterator for InputState {
     type Item = (String, FloatValue);
-    type IntoIter = std::vec::IntoIter<Self::Item>;
+    type IntoIter = indexmap::map::IntoIter<String, FloatValue>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.state.into_iter()
@@ -115,7 +140,7 @@ pub enum RequirementType {
 }
 
 #[pyclass]
-#[derive(Debug, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct RequirementDefinition {
     #[pyo3(get, set)]
     pub name: String,
@@ -123,6 +148,49 @@ pub struct RequirementDefinition {
     pub unit: String,
     #[pyo3(get, set)]
     pub requirement_type: RequirementType,
+    /// Value to use if no component or exogenous data provides this input
+    ///
+    /// The value is expressed in `unit`.
+    /// If `None`, the input is required.
+    #[pyo3(get, set)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<FloatValue>,
+    /// How exogenous data is converted if it has longer time steps than the model
+    #[pyo3(get, set)]
+    #[serde(default, skip_serializing_if = "is_default_disaggregation")]
+    pub disaggregation: DisaggregationStrategy,
+    /// Labels of the second dimension of a labelled variable, e.g. regions or gases
+    ///
+    /// A labelled variable is stored as a timeseries for each label (see [`labelled_name`]).
+    /// Empty for scalar variables.
+    #[pyo3(get, set)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Smallest physically meaningful value, e.g. 0 for a concentration
+    ///
+    /// The value is expressed in `unit`.
+    /// Outputs are checked against the bounds declared by the component which produces them
+    /// according to the model's [`crate::model::NanPolicy`].
+    #[pyo3(get, set)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<FloatValue>,
+    /// Largest physically meaningful value, e.g. 1 for a fraction
+    ///
+    /// The value is expressed in `unit`.
+    #[pyo3(get, set)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<FloatValue>,
+}
+
+/// Name of the timeseries which stores a label of a labelled variable
+///
+/// For example, the "World" label of "Emissions|CO2" is stored as "Emissions|CO2|World".
+pub fn labelled_name(name: &str, label: &str) -> String {
+    format!("{}|{}", name, label)
+}
+
+fn is_default_disaggregation(strategy: &DisaggregationStrategy) -> bool {
+    *strategy == DisaggregationStrategy::default()
 }
 
 impl RequirementDefinition {
@@ -131,8 +199,303 @@ impl RequirementDefinition {
             name: name.to_string(),
             unit: unit.to_string(),
             requirement_type,
+            default: None,
+            disaggregation: DisaggregationStrategy::default(),
+            labels: vec![],
+            min: None,
+            max: None,
+        }
+    }
+
+    /// Make the variable labelled, e.g. with a value for each region
+    pub fn with_labels(mut self, labels: &[&str]) -> Self {
+        self.labels = labels.iter().map(|label| label.to_string()).collect();
+        self
+    }
+
+    /// Definition of each label of a labelled variable
+    ///
+    /// A scalar variable is returned unchanged.
+    pub fn expand(&self) -> Vec<RequirementDefinition> {
+        match self.labels.is_empty() {
+            true => vec![self.clone()],
+            false => self
+                .labels
+                .iter()
+                .map(|label| RequirementDefinition {
+                    name: labelled_name(&self.name, label),
+                    labels: vec![],
+                    ..self.clone()
+                })
+                .collect(),
         }
     }
+
+    /// Create an optional input
+    ///
+    /// If the input isn't provided by another component or as exogenous data,
+    /// the model builder will use a constant timeseries of `default`.
+    /// For example, a forcing term that defaults to 0 W/m^2.
+    pub fn optional(name: &str, unit: &str, default: FloatValue) -> Self {
+        Self::new(name, unit, RequirementType::Input).with_default(default)
+    }
+
+    /// Set the value that is used if the input is not provided
+    pub fn with_default(mut self, default: FloatValue) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Set how exogenous data with longer time steps than the model is converted
+    ///
+    /// For example, annual emissions totals required by a model with monthly time steps
+    /// should use [`DisaggregationStrategy::Split`].
+    pub fn with_disaggregation(mut self, strategy: DisaggregationStrategy) -> Self {
+        self.disaggregation = strategy;
+        self
+    }
+
+    /// Set the range of physically meaningful values
+    ///
+    /// Either bound may be `None`, e.g. `with_bounds(Some(0.0), None)` for a concentration.
+    pub fn with_bounds(mut self, min: Option<FloatValue>, max: Option<FloatValue>) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    pub fn is_optional(&self) -> bool {
+        self.default.is_some()
+    }
+
+    /// Whether a value is within the bounds of the variable
+    ///
+    /// NaN values are considered to be within bounds.
+    pub fn is_within_bounds(&self, value: FloatValue) -> bool {
+        value.is_nan()
+            || (self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max))
+    }
+}
+
+/// Random number generator used by models and components
+///
+/// A seedable generator is used so that model runs are reproducible.
+pub type ModelRng = ChaCha8Rng;
+
+/// An independent stream of random numbers
+///
+/// Each component in a model draws from its own stream so that the random numbers
+/// used by one component don't depend on what other components consume.
+/// The position within the stream is serialised so that a model can be
+/// restored from a checkpoint and continue with exactly the same random numbers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "RngStreamState", try_from = "RngStreamState")]
+pub struct RngStream(ModelRng);
+
+impl RngStream {
+    /// Create stream number `stream` of the generator seeded with `seed`
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut rng = ModelRng::seed_from_u64(seed);
+        rng.set_stream(stream);
+        Self(rng)
+    }
+
+    /// Stream of a named component in a member of an ensemble
+    ///
+    /// The seed of the generator is derived from `seed` and `member`,
+    /// and the stream number from `component_name`, using SplitMix64.
+    /// Each component of each member therefore draws independent random numbers which don't
+    /// depend on the other components in the model or the order they were registered.
+    pub fn for_component(seed: u64, member: u64, component_name: &str) -> Self {
+        let stream = component_name
+            .bytes()
+            .fold(splitmix64(0), |hash, byte| splitmix64(hash ^ byte as u64));
+        Self::new(splitmix64(seed ^ splitmix64(member)), stream)
+    }
+
+    /// Restart the stream from a new seed
+    ///
+    /// The stream number is preserved.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed, self.0.get_stream());
+    }
+
+    pub fn rng(&mut self) -> &mut ModelRng {
+        &mut self.0
+    }
+}
+
+/// Mix a value using the SplitMix64 generator
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Serialised form of a [`RngStream`]
+///
+/// The seed and word position are stored as strings
+/// as they don't fit into the integer types supported by all formats (e.g. TOML).
+#[derive(Serialize, Deserialize)]
+struct RngStreamState {
+    seed: String,
+    stream: u64,
+    word_pos: String,
+}
+
+impl From<RngStream> for RngStreamState {
+    fn from(value: RngStream) -> Self {
+        Self {
+            seed: value
+                .0
+                .get_seed()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+            stream: value.0.get_stream(),
+            word_pos: value.0.get_word_pos().to_string(),
+        }
+    }
+}
+
+impl TryFrom<RngStreamState> for RngStream {
+    type Error = RSCMError;
+
+    fn try_from(value: RngStreamState) -> Result<Self, Self::Error> {
+        let invalid = |field: &str| RSCMError::Error(format!("Invalid RNG stream {}", field));
+
+        let mut seed = <ModelRng as SeedableRng>::Seed::default();
+        if value.seed.len() != 2 * seed.len() {
+            return Err(invalid("seed"));
+        }
+        for (i, byte) in seed.iter_mut().enumerate() {
+            *byte = value
+                .seed
+                .get(2 * i..2 * i + 2)
+                .and_then(|s| u8::from_str_radix(s, 16).ok())
+                .ok_or_else(|| invalid("seed"))?;
+        }
+
+        let mut rng = ModelRng::from_seed(seed);
+        rng.set_stream(value.stream);
+        rng.set_word_pos(value.word_pos.parse().map_err(|_| invalid("word_pos"))?);
+        Ok(Self(rng))
+    }
+}
+
+/// Information about the time step that is being solved
+///
+/// The context is created by the model for each time step and passed to
+/// [`Component::solve_with_context`].
+/// New cross-cutting information can be added here without changing the `Component` trait.
+pub struct TimestepContext<'a> {
+    time_index: usize,
+    time_axis: &'a TimeAxis,
+    rng: &'a mut ModelRng,
+    scratch: &'a mut Vec<FloatValue>,
+    refinement: u32,
+    options: &'a ModelOptions,
+}
+
+impl<'a> TimestepContext<'a> {
+    pub fn new(
+        time_index: usize,
+        time_axis: &'a TimeAxis,
+        rng: &'a mut ModelRng,
+        scratch: &'a mut Vec<FloatValue>,
+    ) -> Self {
+        assert!(time_index < time_axis.len());
+        Self {
+            time_index,
+            time_axis,
+            rng,
+            scratch,
+            refinement: 0,
+            options: &DEFAULT_MODEL_OPTIONS,
+        }
+    }
+
+    /// Use the options of a model
+    pub fn with_options(mut self, options: &'a ModelOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Options of the model being solved
+    ///
+    /// [`ModelOptions::default`] is used if the component isn't being solved as part of a model.
+    pub fn options(&self) -> &ModelOptions {
+        self.options
+    }
+
+    /// Request that internal solvers use a smaller step
+    ///
+    /// Used by the model when retrying a step that was numerically unstable.
+    pub fn with_refinement(mut self, refinement: u32) -> Self {
+        self.refinement = refinement;
+        self
+    }
+
+    /// Number of times that the step has been refined
+    pub fn refinement(&self) -> u32 {
+        self.refinement
+    }
+
+    /// Step size that internal solvers should use
+    ///
+    /// The step size is halved for each refinement.
+    pub fn solver_step_size(&self, step_size: Time) -> Time {
+        step_size / 2.0_f64.powi(self.refinement as i32)
+    }
+
+    /// Index of the time step being solved
+    pub fn time_index(&self) -> usize {
+        self.time_index
+    }
+
+    /// The time axis on which the model is solved
+    pub fn time_axis(&self) -> &TimeAxis {
+        self.time_axis
+    }
+
+    /// Unit of the times of the time axis
+    ///
+    /// Internal solvers must convert rates, which are per year, into this unit
+    /// (see [`crate::ivp::IVPBuilder::with_time_unit`]).
+    pub fn time_unit(&self) -> TimeUnit {
+        self.time_axis.unit()
+    }
+
+    /// Length of the time step in years
+    pub fn step_in_years(&self) -> Time {
+        (self.t_next() - self.t_current()) * self.time_unit().in_years()
+    }
+
+    /// Time at the start of the time step
+    pub fn t_current(&self) -> Time {
+        self.time_axis.at_bounds(self.time_index).unwrap().0
+    }
+
+    /// Time at the end of the time step
+    pub fn t_next(&self) -> Time {
+        self.time_axis.at_bounds(self.time_index).unwrap().1
+    }
+
+    /// Random number stream of the component being solved
+    pub fn rng(&mut self) -> &mut ModelRng {
+        self.rng
+    }
+
+    /// Get a zeroed buffer of `len` values for temporary calculations
+    ///
+    /// The underlying allocation is reused between components and time steps.
+    /// The contents are not preserved between calls.
+    pub fn scratch(&mut self, len: usize) -> &mut [FloatValue] {
+        self.scratch.clear();
+        self.scratch.resize(len, 0.0);
+        self.scratch.as_mut_slice()
+    }
 }
 
 /// Component of a reduced complexity climate model
@@ -158,9 +521,17 @@ impl RequirementDefinition {
 pub trait Component: Debug + Send + Sync {
     fn definitions(&self) -> Vec<RequirementDefinition>;
 
+    /// Definitions of the timeseries used by this component
+    ///
+    /// Labelled variables are expanded into a definition for each label
+    /// (see [`RequirementDefinition::expand`]).
+    fn variables(&self) -> Vec<RequirementDefinition> {
+        self.definitions().iter().flat_map(|d| d.expand()).collect()
+    }
+
     /// Variables that are required to solve this component
     fn inputs(&self) -> Vec<RequirementDefinition> {
-        self.definitions()
+        self.variables()
             .iter()
             .filter(|d| {
                 (d.requirement_type == RequirementType::Input)
@@ -180,7 +551,7 @@ pub trait Component: Debug + Send + Sync {
     /// These names can contain '|' to namespace variables to avoid collisions,
     /// for example, 'Emissions|CO2' and 'Atmospheric Concentrations|CO2'
     fn outputs(&self) -> Vec<RequirementDefinition> {
-        self.definitions()
+        self.variables()
             .iter()
             .filter(|d| {
                 (d.requirement_type == RequirementType::Output)
@@ -220,6 +591,19 @@ pub trait Component: Debug + Send + Sync {
         InputState::from_hashmap_and_verify(state, self.input_names())
     }
 
+    /// Initial values of the state variables solved by this component
+    ///
+    /// This is called when a model is built so that components can calculate
+    /// self-consistent initial values at `t0`, e.g. a pre-industrial equilibrium,
+    /// for their `RequirementType::InputAndOutput` variables.
+    /// Values provided using [`crate::model::ModelBuilder::with_initial_values`] take precedence,
+    /// and variables without an initial value fall back to the default of their definition.
+    ///
+    /// Returns an empty state by default.
+    fn initialise(&self, _t0: Time) -> OutputState {
+        OutputState::empty()
+    }
+
     /// Solve the component until `t_next`
     ///
     /// The result should contain values for the current time step for all output variables
@@ -229,6 +613,51 @@ pub trait Component: Debug + Send + Sync {
         t_next: Time,
         input_state: &InputState,
     ) -> RSCMResult<OutputState>;
+
+    /// Solve the component for the time step described by `context`
+    ///
+    /// This is what is called by a model.
+    /// By default, this calls [`Component::solve`] with the bounds of the current time step.
+    /// Components which need additional information about the time step
+    /// (e.g. the time index or random numbers) should override this method.
+    fn solve_with_context(
+        &self,
+        context: &mut TimestepContext,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        self.solve(context.t_current(), context.t_next(), input_state)
+    }
+
+    /// Solve the component using dual numbers to propagate derivatives
+    ///
+    /// The derivative of each input with respect to `parameter` is supplied in `input_state`.
+    /// Parameters of the component should be created using [`crate::dual::Dual::parameter`]
+    /// so that the derivative with respect to the component's own parameters is included.
+    /// The result should contain the value and derivative of each output.
+    ///
+    /// This is only called by models which calculate sensitivities
+    /// (see [`crate::model::ModelBuilder::with_sensitivity`]).
+    /// Returns `None` by default, as only components which are analytic functions of
+    /// their inputs and parameters can be differentiated.
+    fn solve_dual(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        _input_state: &DualState,
+        _parameter: &str,
+    ) -> Option<RSCMResult<DualState>> {
+        None
+    }
+
+    /// Approximate cost of solving the component
+    ///
+    /// Used by [`crate::model::Model::estimate_cost`] to predict the runtime and memory use
+    /// of a model before it is run.
+    /// Components which are much more expensive than a few arithmetic operations per step,
+    /// e.g. those which integrate ODEs with many internal steps, should override this.
+    fn cost_hint(&self) -> CostHint {
+        CostHint::default()
+    }
 }
 
 #[cfg(test)]
@@ -245,4 +674,135 @@ mod tests {
 
         assert_eq!(*output_state.get("Concentrations|CO2"), 2.0 * 1.3);
     }
+
+    #[test]
+    fn input_state() {
+        let mut state = InputState::from_vectors(
+            vec![1.0, 2.0],
+            vec![
+                "Surface Temperature".to_string(),
+                "Emissions|CO2".to_string(),
+            ],
+        );
+        assert_eq!(*state.get("Emissions|CO2"), 2.0);
+        assert!(state.try_get("Emissions|CH4").is_err());
+
+        state.merge(InputState::from_vectors(
+            vec![3.0, 4.0],
+            vec![
+                "Emissions|CH4".to_string(),
+                "Surface Temperature".to_string(),
+            ],
+        ));
+        assert_eq!(*state.try_get("Surface Temperature").unwrap(), 4.0);
+        assert_eq!(
+            state
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            ["Surface Temperature", "Emissions|CO2", "Emissions|CH4"]
+        );
+    }
+
+    /// Compare looking up variables in an `InputState` with the linear search it replaced
+    ///
+    /// Run with `cargo test --release -p rscm-core input_state_lookup_timing -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn input_state_lookup_timing() {
+        use std::hint::black_box;
+        use std::time::Instant;
+
+        let repeats = 100_000;
+        for n in [2, 10, 50, 200] {
+            let names: Vec<String> = (0..n).map(|i| format!("Variable|{}", i)).collect();
+            let values: Vec<FloatValue> = (0..n).map(|i| i as FloatValue).collect();
+            let linear: Vec<(String, FloatValue)> = zip(names.clone(), values.clone()).collect();
+            let state = InputState::from_vectors(values, names.clone());
+
+            let start = Instant::now();
+            for _ in 0..repeats {
+                for name in &names {
+                    black_box(linear.iter().find(|(other, _)| other == name).unwrap().1);
+                }
+            }
+            let linear_time = start.elapsed();
+
+            let start = Instant::now();
+            for _ in 0..repeats {
+                for name in &names {
+                    black_box(*state.get(name));
+                }
+            }
+            let indexed_time = start.elapsed();
+
+            let per_lookup =
+                |elapsed: std::time::Duration| elapsed.as_nanos() as f64 / (repeats * n) as f64;
+            println!(
+                "{} variables: linear search {:.1} ns, IndexMap {:.1} ns per lookup",
+                n,
+                per_lookup(linear_time),
+                per_lookup(indexed_time)
+            );
+        }
+    }
+
+    #[test]
+    fn rng_stream_roundtrip() {
+        use rand::Rng;
+
+        let mut stream = RngStream::new(42, 3);
+        let _: f64 = stream.rng().gen();
+
+        let mut restored: RngStream =
+            serde_json::from_str(&serde_json::to_string(&stream).unwrap()).unwrap();
+        assert_eq!(restored, stream);
+        assert_eq!(
+            restored.rng().gen::<u64>(),
+            stream.clone().rng().gen::<u64>()
+        );
+
+        let mut reseeded = stream.clone();
+        reseeded.reseed(43);
+        assert_ne!(reseeded.rng().gen::<u64>(), stream.rng().gen::<u64>());
+    }
+
+    #[test]
+    fn solve_with_context() {
+        use numpy::array;
+        use rand::{Rng, SeedableRng};
+
+        let component = TestComponent::from_parameters(TestComponentParameters { p: 2.0 });
+        let time_axis = TimeAxis::from_values(array![2020.0, 2021.0, 2022.0]);
+        let mut rng = ModelRng::seed_from_u64(0);
+        let mut scratch = vec![];
+
+        let mut context = TimestepContext::new(1, &time_axis, &mut rng, &mut scratch);
+        assert_eq!(context.t_current(), 2021.0);
+        assert_eq!(context.t_next(), 2022.0);
+        assert_eq!(context.scratch(3), [0.0, 0.0, 0.0]);
+        assert_eq!(context.solver_step_size(0.1), 0.1);
+        let _: f64 = context.rng().gen();
+
+        let input_state = component.extract_state(&TimeseriesCollection::new(), 2021.0);
+        let output_state = component
+            .solve_with_context(&mut context, &input_state)
+            .unwrap();
+        assert_eq!(*output_state.get("Concentrations|CO2"), 2.0 * 1.3);
+    }
+
+    #[test]
+    fn bounds() {
+        let definition = RequirementDefinition::new("Fraction", "1", RequirementType::Output)
+            .with_bounds(Some(0.0), Some(1.0));
+
+        assert!(definition.is_within_bounds(0.0));
+        assert!(definition.is_within_bounds(1.0));
+        assert!(!definition.is_within_bounds(-0.1));
+        assert!(!definition.is_within_bounds(1.1));
+        assert!(definition.is_within_bounds(FloatValue::NAN));
+
+        let unbounded = RequirementDefinition::new("Temperature", "K", RequirementType::Output);
+        assert!(unbounded.is_within_bounds(FloatValue::NEG_INFINITY));
+    }
 }
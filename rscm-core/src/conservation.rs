@@ -0,0 +1,278 @@
+//! Checks that conserved quantities balance
+//!
+//! A [`MassBalanceCheck`] declares that a weighted sum of variables is zero at every time,
+//! e.g. the cumulative carbon emitted minus the carbon taken up by the atmosphere, land and ocean.
+//! Checks are attached to a model using [`crate::model::ModelBuilder::with_mass_balance_check`]
+//! and are evaluated after each step, which catches coupling bugs where components
+//! lose or double count a quantity.
+//! Violations are collected in a [`MassBalanceReport`] (see [`crate::model::Model::mass_balance`]).
+//!
+//! Each term is converted to the units of the first term before summing.
+use crate::errors::{RSCMError, RSCMResult};
+use crate::timeseries::{FloatValue, Time};
+use crate::timeseries_collection::TimeseriesCollection;
+use crate::units;
+use serde::{Deserialize, Serialize};
+
+/// A variable contributing to a balance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceTerm {
+    pub variable: String,
+    /// Weight of the variable in the sum
+    ///
+    /// Typically 1 for sources of the quantity and -1 for the stores that it ends up in.
+    pub coefficient: FloatValue,
+}
+
+/// A quantity which must be conserved
+///
+/// The check passes if `|residual| <= absolute_tolerance + relative_tolerance * scale`,
+/// where the residual is the weighted sum of the terms
+/// and the scale is the largest absolute value of the weighted terms.
+/// Time points where any term is NaN aren't checked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MassBalanceCheck {
+    pub name: String,
+    pub terms: Vec<BalanceTerm>,
+    pub absolute_tolerance: FloatValue,
+    pub relative_tolerance: FloatValue,
+}
+
+/// A time at which a [`MassBalanceCheck`] didn't balance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MassBalanceViolation {
+    /// Name of the check
+    pub check: String,
+    pub time: Time,
+    /// Weighted sum of the terms
+    ///
+    /// unit: units of the first term of the check
+    pub residual: FloatValue,
+    /// Largest residual which would have balanced
+    pub tolerance: FloatValue,
+}
+
+/// The result of evaluating mass balance checks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MassBalanceReport {
+    /// Number of time points at which a check was evaluated
+    pub checked: usize,
+    pub violations: Vec<MassBalanceViolation>,
+}
+
+impl MassBalanceReport {
+    /// Check if there weren't any violations
+    pub fn is_balanced(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl MassBalanceCheck {
+    /// Create a check without any terms
+    ///
+    /// The tolerances default to an absolute tolerance of zero and a relative tolerance of 1e-9.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            terms: vec![],
+            absolute_tolerance: 0.0,
+            relative_tolerance: 1e-9,
+        }
+    }
+
+    /// Add a variable which adds to the quantity
+    pub fn with_source(&mut self, variable: &str) -> &mut Self {
+        self.with_term(variable, 1.0)
+    }
+
+    /// Add a variable which the quantity ends up in
+    pub fn with_sink(&mut self, variable: &str) -> &mut Self {
+        self.with_term(variable, -1.0)
+    }
+
+    /// Add a variable with an arbitrary weight
+    pub fn with_term(&mut self, variable: &str, coefficient: FloatValue) -> &mut Self {
+        self.terms.push(BalanceTerm {
+            variable: variable.to_string(),
+            coefficient,
+        });
+        self
+    }
+
+    pub fn with_tolerance(
+        &mut self,
+        absolute_tolerance: FloatValue,
+        relative_tolerance: FloatValue,
+    ) -> &mut Self {
+        self.absolute_tolerance = absolute_tolerance;
+        self.relative_tolerance = relative_tolerance;
+        self
+    }
+
+    /// Factor which converts each term into the units of the first term
+    ///
+    /// Returns an error if a variable isn't in `collection` or its units can't be converted.
+    pub fn conversion_factors<S: AsRef<str>>(
+        &self,
+        collection: &TimeseriesCollection,
+        contexts: &[S],
+    ) -> RSCMResult<Vec<FloatValue>> {
+        let timeseries = self
+            .terms
+            .iter()
+            .map(|term| {
+                collection
+                    .get_timeseries_by_name(&term.variable)
+                    .ok_or_else(|| {
+                        RSCMError::TimeseriesNotFound(format!(
+                            "'{}' in mass balance check '{}'",
+                            term.variable, self.name
+                        ))
+                    })
+            })
+            .collect::<RSCMResult<Vec<_>>>()?;
+
+        match timeseries.first() {
+            Some(first) => timeseries
+                .iter()
+                .map(|ts| units::conversion_factor(ts.units(), first.units(), contexts))
+                .collect(),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Evaluate the check at a single time index
+    ///
+    /// Returns `None` if the check balances or any of the terms are NaN.
+    pub fn evaluate<S: AsRef<str>>(
+        &self,
+        collection: &TimeseriesCollection,
+        time_index: usize,
+        contexts: &[S],
+    ) -> RSCMResult<Option<MassBalanceViolation>> {
+        let factors = self.conversion_factors(collection, contexts)?;
+
+        let mut values = vec![];
+        for (term, factor) in self.terms.iter().zip(factors) {
+            let timeseries = collection.get_timeseries_by_name(&term.variable).unwrap();
+            let value = timeseries.at(time_index).ok_or_else(|| {
+                RSCMError::Error(format!("Time index {} is out of bounds", time_index))
+            })?;
+            values.push(term.coefficient * factor * value);
+        }
+        if values.iter().any(|v| v.is_nan()) {
+            return Ok(None);
+        }
+
+        let residual: FloatValue = values.iter().sum();
+        let scale = values
+            .iter()
+            .fold(0.0, |acc: FloatValue, v| acc.max(v.abs()));
+        let tolerance = self.absolute_tolerance + self.relative_tolerance * scale;
+
+        Ok(match residual.abs() <= tolerance {
+            true => None,
+            false => {
+                let time_axis = collection
+                    .get_timeseries_by_name(&self.terms[0].variable)
+                    .unwrap()
+                    .time_axis();
+                Some(MassBalanceViolation {
+                    check: self.name.clone(),
+                    time: time_axis.at(time_index).unwrap(),
+                    residual,
+                    tolerance,
+                })
+            }
+        })
+    }
+
+    /// Evaluate the check at every time point of a collection
+    pub fn check<S: AsRef<str>>(
+        &self,
+        collection: &TimeseriesCollection,
+        contexts: &[S],
+    ) -> RSCMResult<MassBalanceReport> {
+        let n = match self.terms.first() {
+            Some(term) => collection
+                .get_timeseries_by_name(&term.variable)
+                .map_or(0, |ts| ts.len()),
+            None => 0,
+        };
+
+        let mut report = MassBalanceReport::default();
+        for time_index in 0..n {
+            report.checked += 1;
+            if let Some(violation) = self.evaluate(collection, time_index, contexts)? {
+                report.violations.push(violation);
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolate::strategies::LinearSplineStrategy;
+    use crate::timeseries::{TimeAxis, Timeseries};
+    use crate::timeseries_collection::VariableType;
+    use numpy::array;
+    use numpy::ndarray::Array1;
+    use std::sync::Arc;
+
+    const NO_CONTEXT: [&str; 0] = [];
+
+    fn collection() -> TimeseriesCollection {
+        let mut collection = TimeseriesCollection::new();
+        let mut add = |name: &str, values: Array1<FloatValue>, units: &str| {
+            let ts = Timeseries::new(
+                values,
+                Arc::new(TimeAxis::from_values(array![2000.0, 2001.0, 2002.0])),
+                units.to_string(),
+                LinearSplineStrategy::new(true).into(),
+            );
+            collection
+                .add_timeseries(name.to_string(), ts, VariableType::Endogenous)
+                .unwrap();
+        };
+        add("Cumulative Emissions", array![0.0, 10.0, 20.0], "GtC");
+        add("Atmosphere", array![0.0, 5000.0, 10000.0], "MtC");
+        add("Ocean", array![0.0, 5.0, 9.0], "GtC");
+        add("Land", array![FloatValue::NAN, 0.0, 0.0], "GtC");
+        collection
+    }
+
+    #[test]
+    fn balance() {
+        let mut check = MassBalanceCheck::new("Carbon");
+        check
+            .with_source("Cumulative Emissions")
+            .with_sink("Atmosphere")
+            .with_sink("Ocean")
+            .with_sink("Land");
+
+        let report = check.check(&collection(), &NO_CONTEXT).unwrap();
+        assert_eq!(report.checked, 3);
+        assert!(!report.is_balanced());
+        // The first time point isn't checked as it contains NaN
+        assert_eq!(
+            report.violations,
+            vec![MassBalanceViolation {
+                check: "Carbon".to_string(),
+                time: 2002.0,
+                residual: 1.0,
+                tolerance: 20.0 * 1e-9,
+            }]
+        );
+
+        check.with_tolerance(1.0, 0.0);
+        assert!(check
+            .check(&collection(), &NO_CONTEXT)
+            .unwrap()
+            .is_balanced());
+
+        check.with_sink("Permafrost");
+        assert!(check.check(&collection(), &NO_CONTEXT).is_err());
+    }
+}
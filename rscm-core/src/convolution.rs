@@ -0,0 +1,92 @@
+//! Convolution of timeseries with response functions
+//!
+//! The response of a linear system to a forcing can be calculated by convolving the
+//! forcing with the response of the system to a unit impulse (a Green's function).
+//! This avoids solving the underlying differential equations.
+use crate::timeseries::{FloatValue, Time};
+
+/// Causal discrete convolution of `signal` with `kernel`
+///
+/// `output[n] = sum(kernel[n - k] * signal[k] for k in 0..=n)`
+///
+/// The output has the same length as `signal`.
+/// Values of the kernel beyond its length are treated as zero.
+///
+/// ```
+/// use rscm_core::convolution::convolve;
+///
+/// assert_eq!(convolve(&[1.0, 2.0, 3.0], &[1.0, 0.5]), [1.0, 2.5, 4.0]);
+/// ```
+pub fn convolve(signal: &[FloatValue], kernel: &[FloatValue]) -> Vec<FloatValue> {
+    (0..signal.len())
+        .map(|n| {
+            signal[..=n]
+                .iter()
+                .rev()
+                .zip(kernel)
+                .map(|(s, k)| s * k)
+                .sum()
+        })
+        .collect()
+}
+
+/// Kernel for a sum of exponential responses to forcing that is constant over each step
+///
+/// Each mode `i` relaxes towards `amplitudes[i] * forcing` with an e-folding time of
+/// `timescales[i]`.
+/// Element `j` of the kernel is the response at the end of step `j`
+/// to a unit forcing applied only during step 0.
+///
+/// Panics if `amplitudes` and `timescales` have different lengths.
+pub fn exponential_kernel(
+    amplitudes: &[FloatValue],
+    timescales: &[Time],
+    step: Time,
+    len: usize,
+) -> Vec<FloatValue> {
+    assert_eq!(amplitudes.len(), timescales.len());
+
+    (0..len)
+        .map(|j| {
+            amplitudes
+                .iter()
+                .zip(timescales)
+                .map(|(q, d)| {
+                    let decay = (-step / d).exp();
+                    q * (1.0 - decay) * decay.powi(j as i32)
+                })
+                .sum()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use is_close::is_close;
+
+    #[test]
+    fn constant_forcing_equilibrates() {
+        let amplitudes = [0.5, 0.7];
+        let timescales = [4.0, 200.0];
+        let kernel = exponential_kernel(&amplitudes, &timescales, 1.0, 5000);
+
+        let response = convolve(&vec![2.0; 5000], &kernel);
+        assert!(is_close!(
+            *response.last().unwrap(),
+            2.0 * (0.5 + 0.7),
+            rel_tol = 1e-6
+        ));
+        // The fast mode dominates the initial response
+        assert!(is_close!(
+            response[0],
+            2.0 * (0.5 * (1.0 - (-0.25f64).exp()) + 0.7 * (1.0 - (-0.005f64).exp()))
+        ));
+    }
+
+    #[test]
+    fn short_kernel() {
+        assert_eq!(convolve(&[1.0, 1.0, 1.0], &[1.0]), [1.0, 1.0, 1.0]);
+        assert!(convolve(&[], &[1.0]).is_empty());
+    }
+}
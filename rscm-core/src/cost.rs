@@ -0,0 +1,113 @@
+//! Estimates of the cost of running a model
+//!
+//! Large ensembles can take a long time to run or exhaust the available memory.
+//! [`crate::model::Model::estimate_cost`] predicts the approximate runtime and memory use of
+//! a model before it is run, using the length of the time axis, the number of variables
+//! and a [`CostHint`] from each component (see [`crate::component::Component::cost_hint`]).
+//! The estimate for a single run can be scaled to an ensemble using [`CostEstimate::ensemble`].
+//!
+//! These are order-of-magnitude estimates to help size an ensemble for the available hardware,
+//! rather than accurate predictions.
+use crate::timeseries::FloatValue;
+use pyo3::pyclass;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// Time taken to solve a typical component step, in seconds
+pub const DEFAULT_SECONDS_PER_STEP: f64 = 1e-6;
+
+/// Approximate cost of a component
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CostHint {
+    /// Time taken to solve a single time step, in seconds
+    pub seconds_per_step: f64,
+    /// Memory used by the component in addition to its outputs, in bytes
+    pub bytes: usize,
+}
+
+impl Default for CostHint {
+    fn default() -> Self {
+        Self {
+            seconds_per_step: DEFAULT_SECONDS_PER_STEP,
+            bytes: 0,
+        }
+    }
+}
+
+/// Estimated cost of running a model
+#[pyclass(get_all)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CostEstimate {
+    /// Number of time steps which remain to be run
+    pub steps: usize,
+    /// Number of times a component is solved
+    pub component_solves: usize,
+    /// Approximate runtime, in seconds
+    pub seconds: f64,
+    /// Approximate memory used by the model, in bytes
+    pub bytes: usize,
+}
+
+impl CostEstimate {
+    /// Estimate from the number of steps, the cost of each component and the number of values
+    /// which are stored
+    pub fn new(steps: usize, hints: &[CostHint], values: usize) -> Self {
+        Self {
+            steps,
+            component_solves: steps * hints.len(),
+            seconds: steps as f64 * hints.iter().map(|h| h.seconds_per_step).sum::<f64>(),
+            bytes: values * std::mem::size_of::<FloatValue>()
+                + hints.iter().map(|h| h.bytes).sum::<usize>(),
+        }
+    }
+
+    /// Cost of an ensemble of `members` runs
+    ///
+    /// The runtime is shared between `threads` and the results of every member are kept.
+    pub fn ensemble(&self, members: usize, threads: usize) -> Self {
+        Self {
+            steps: self.steps * members,
+            component_solves: self.component_solves * members,
+            seconds: self.seconds * members.div_ceil(threads.max(1)) as f64,
+            bytes: self.bytes * members,
+        }
+    }
+}
+
+impl Display for CostEstimate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} component solves, ~{:.3} s, ~{:.1} MB",
+            self.component_solves,
+            self.seconds,
+            self.bytes as f64 / 1e6
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate() {
+        let hints = [
+            CostHint::default(),
+            CostHint {
+                seconds_per_step: 1e-3,
+                bytes: 1000,
+            },
+        ];
+        let estimate = CostEstimate::new(100, &hints, 500);
+        assert_eq!(estimate.component_solves, 200);
+        assert!((estimate.seconds - 100.0 * (1e-6 + 1e-3)).abs() < 1e-12);
+        assert_eq!(estimate.bytes, 500 * 8 + 1000);
+
+        let ensemble = estimate.ensemble(10, 4);
+        assert_eq!(ensemble.component_solves, 2000);
+        // 10 members on 4 threads takes 3 rounds
+        assert!((ensemble.seconds - 3.0 * estimate.seconds).abs() < 1e-12);
+        assert_eq!(ensemble.bytes, 10 * estimate.bytes);
+    }
+}
@@ -0,0 +1,230 @@
+//! Descriptions of the component graph of a model
+//!
+//! [`ModelDiagnostics`] describes the components of a model and the variables which are passed
+//! between them.
+//! The graph can be exported as Graphviz Dot, as a Mermaid flowchart for rendering in notebooks
+//! and documentation, or as JSON for use by other tools.
+use crate::component::RequirementType;
+use crate::model::Model;
+use petgraph::dot::{Config, Dot};
+use petgraph::visit::EdgeRef;
+use serde::Serialize;
+
+/// A component in the graph of a model
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphNode {
+    /// Index of the component in the graph
+    pub index: usize,
+    /// Name of the component within the model
+    pub name: String,
+    /// Name of the component's type, e.g. "CO2ERF"
+    pub component_type: String,
+    /// Parameters of the component, if the component has any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<serde_json::Value>,
+}
+
+/// A variable passed from one component to another
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphEdge {
+    /// Index of the component which solves the variable
+    pub from: usize,
+    /// Index of the component which uses the variable
+    pub to: usize,
+    pub variable: String,
+    pub unit: String,
+}
+
+#[derive(Serialize)]
+struct GraphDescription {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+/// Diagnostic views of the component graph of a model
+///
+/// Created using [`Model::diagnostics`].
+/// The Mermaid and JSON descriptions exclude the placeholder component
+/// which the graph is traversed from.
+pub struct ModelDiagnostics<'a> {
+    model: &'a Model,
+}
+
+impl<'a> ModelDiagnostics<'a> {
+    pub(crate) fn new(model: &'a Model) -> Self {
+        Self { model }
+    }
+
+    /// The components of the model
+    pub fn nodes(&self) -> Vec<GraphNode> {
+        let (graph, initial_node) = self.model.graph();
+        graph
+            .node_indices()
+            .filter(|nx| *nx != initial_node)
+            .map(|nx| {
+                let component = &graph[nx];
+                GraphNode {
+                    index: nx.index(),
+                    name: self.model.component_name(nx).to_string(),
+                    component_type: component.typetag_name().to_string(),
+                    parameters: serde_json::to_value(component)
+                        .ok()
+                        .and_then(|value| parameters(&value)),
+                }
+            })
+            .collect()
+    }
+
+    /// The variables passed between the components of the model
+    pub fn edges(&self) -> Vec<GraphEdge> {
+        let (graph, _) = self.model.graph();
+        graph
+            .edge_references()
+            .filter(|edge| edge.weight().requirement_type != RequirementType::EmptyLink)
+            .map(|edge| GraphEdge {
+                from: edge.source().index(),
+                to: edge.target().index(),
+                variable: edge.weight().name.clone(),
+                unit: edge.weight().unit.clone(),
+            })
+            .collect()
+    }
+
+    /// Graphviz Dot description of the component graph
+    pub fn as_dot(&self) -> String {
+        let (graph, _) = self.model.graph();
+        let node_label = |_, (nx, _)| format!("label = {:?}", self.model.component_name(nx));
+        let dot = Dot::with_attr_getters(
+            graph,
+            &[Config::NodeNoLabel, Config::EdgeNoLabel],
+            &|_, er| format!("label = {:?}", er.weight().name),
+            &node_label,
+        );
+        format!("{:?}", dot)
+    }
+
+    /// Mermaid flowchart of the component graph
+    ///
+    /// Edges are labelled with the name and unit of the variable.
+    pub fn as_mermaid(&self) -> String {
+        let mut lines = vec!["flowchart LR".to_string()];
+        self.nodes().iter().for_each(|node| {
+            lines.push(format!(
+                "    n{}[\"{}\"]",
+                node.index,
+                mermaid_escape(&node.name)
+            ))
+        });
+        self.edges().iter().for_each(|edge| {
+            lines.push(format!(
+                "    n{} -->|\"{} [{}]\"| n{}",
+                edge.from,
+                mermaid_escape(&edge.variable),
+                mermaid_escape(&edge.unit),
+                edge.to
+            ))
+        });
+        lines.join("\n") + "\n"
+    }
+
+    /// JSON description of the components and the variables passed between them
+    pub fn as_json(&self) -> String {
+        let description = GraphDescription {
+            nodes: self.nodes(),
+            edges: self.edges(),
+        };
+        serde_json::to_string_pretty(&description).expect("Graph can be serialised")
+    }
+}
+
+/// Parameters of a serialised component
+///
+/// Components which wrap another component (e.g. remapped components) use the parameters of
+/// the wrapped component.
+fn parameters(value: &serde_json::Value) -> Option<serde_json::Value> {
+    match value.get("component") {
+        Some(component) => parameters(component),
+        None => value.get("parameters").cloned(),
+    }
+}
+
+/// Escape text for use within a quoted Mermaid label
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::example_components::{TestComponent, TestComponentParameters, TestSumComponent};
+    use crate::interpolate::strategies::{InterpolationStrategy, PreviousStrategy};
+    use crate::model::ModelBuilder;
+    use crate::timeseries::{TimeAxis, Timeseries};
+    use ndarray::{array, Array};
+    use std::sync::Arc;
+
+    #[test]
+    fn diagnostics() {
+        let model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_named_component(
+                "co2",
+                Arc::new(TestComponent::from_parameters(TestComponentParameters {
+                    p: 0.5,
+                })),
+            )
+            .with_named_component(
+                "a",
+                Arc::new(TestSumComponent {
+                    inputs: vec![],
+                    output: "X".to_string(),
+                }),
+            )
+            .with_named_component(
+                "b",
+                Arc::new(TestSumComponent {
+                    inputs: vec!["X".to_string()],
+                    output: "Y".to_string(),
+                }),
+            )
+            .with_exogenous_variable(
+                "Emissions|CO2",
+                Timeseries::new(
+                    array![1.0, 1.0],
+                    Arc::new(TimeAxis::from_values(array![2000.0, 2100.0])),
+                    "GtCO2".to_string(),
+                    InterpolationStrategy::from(PreviousStrategy::new(true)),
+                ),
+            )
+            .build();
+        let diagnostics = model.diagnostics();
+
+        let nodes = diagnostics.nodes();
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes[0].name, "co2");
+        assert_eq!(nodes[0].component_type, "TestComponent");
+        assert_eq!(nodes[0].parameters, Some(serde_json::json!({"p": 0.5})));
+        assert_eq!(nodes[1].parameters, None);
+
+        let edges = diagnostics.edges();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].variable, "X");
+        assert_eq!(edges[0].unit, "dimensionless");
+
+        assert_eq!(
+            diagnostics.as_mermaid(),
+            r#"flowchart LR
+    n1["co2"]
+    n2["a"]
+    n3["b"]
+    n2 -->|"X [dimensionless]"| n3
+"#
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&diagnostics.as_json()).unwrap();
+        assert_eq!(json["nodes"][1]["name"], "a");
+        assert_eq!(json["edges"][0]["from"], 2);
+        assert_eq!(json["edges"][0]["to"], 3);
+
+        assert_eq!(diagnostics.as_dot(), model.as_dot());
+    }
+}
@@ -0,0 +1,671 @@
+//! Probability distributions of model parameters
+//!
+//! A [`Prior`] is a [`Distribution`] which may be truncated to a range of values.
+//! A [`JointDistribution`] combines named priors
+//! with an optional Gaussian copula which describes the correlation between parameters.
+//!
+//! Distributions are defined using their inverse CDFs so that points from a
+//! [`Sampler`] in the unit hypercube can be transformed into parameter values.
+//! Quasi-random samplers therefore retain their even coverage of the parameter space.
+//!
+//! Joint distributions can be read from a configuration file:
+//!
+//! ```rust
+//! use rscm_core::distributions::JointDistribution;
+//!
+//! let joint: JointDistribution = toml::from_str(r#"
+//! [[parameters]]
+//! name = "lambda0"
+//! kind = "lognormal"
+//! mu = 0.0
+//! sigma = 0.25
+//!
+//! [[parameters]]
+//! name = "efficacy"
+//! kind = "normal"
+//! mean = 1.0
+//! std = 0.2
+//! lower = 0.5
+//!
+//! [[correlations]]
+//! first = "lambda0"
+//! second = "efficacy"
+//! coefficient = 0.5
+//! "#).unwrap();
+//! joint.validate().unwrap();
+//! ```
+use crate::errors::{RSCMError, RSCMResult};
+use crate::sampling::Sampler;
+use crate::timeseries::FloatValue;
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{PI, SQRT_2};
+
+/// Complementary error function
+///
+/// Uses a Chebyshev approximation with a fractional error below 1.2e-7
+/// (Press et al., Numerical Recipes).
+fn erfc(x: FloatValue) -> FloatValue {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let ans = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    match x >= 0.0 {
+        true => ans,
+        false => 2.0 - ans,
+    }
+}
+
+/// CDF of the standard normal distribution
+pub fn normal_cdf(x: FloatValue) -> FloatValue {
+    0.5 * erfc(-x / SQRT_2)
+}
+
+/// Inverse CDF of the standard normal distribution
+///
+/// Uses the rational approximation of Acklam (2003) with a relative error below 1.2e-9.
+pub fn normal_ppf(p: FloatValue) -> FloatValue {
+    const A: [FloatValue; 6] = [
+        -3.969683028665376e+01,
+        2.209460984245205e+02,
+        -2.759285104469687e+02,
+        1.38357751867269e+02,
+        -3.066479806614716e+01,
+        2.506628277459239e+00,
+    ];
+    const B: [FloatValue; 5] = [
+        -5.447609879822406e+01,
+        1.615858368580409e+02,
+        -1.556989798598866e+02,
+        6.680131188771972e+01,
+        -1.328068155288572e+01,
+    ];
+    const C: [FloatValue; 6] = [
+        -7.784894002430293e-03,
+        -3.223964580411365e-01,
+        -2.400758277161838e+00,
+        -2.549732539343734e+00,
+        4.374664141464968e+00,
+        2.938163982698783e+00,
+    ];
+    const D: [FloatValue; 4] = [
+        7.784695709041462e-03,
+        3.224671290700398e-01,
+        2.445134137142996e+00,
+        3.754408661907416e+00,
+    ];
+    const P_LOW: FloatValue = 0.02425;
+
+    if p <= 0.0 {
+        return FloatValue::NEG_INFINITY;
+    }
+    if p >= 1.0 {
+        return FloatValue::INFINITY;
+    }
+
+    let tail = |q: FloatValue| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - P_LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// A univariate probability distribution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Distribution {
+    Normal {
+        mean: FloatValue,
+        std: FloatValue,
+    },
+    /// Distribution whose logarithm is normally distributed with mean `mu` and standard
+    /// deviation `sigma`
+    LogNormal {
+        mu: FloatValue,
+        sigma: FloatValue,
+    },
+    Uniform {
+        min: FloatValue,
+        max: FloatValue,
+    },
+    Triangular {
+        min: FloatValue,
+        mode: FloatValue,
+        max: FloatValue,
+    },
+}
+
+impl Distribution {
+    /// Check that the parameters of the distribution are valid
+    pub fn validate(&self) -> RSCMResult<()> {
+        let valid = match *self {
+            Self::Normal { std, .. } => std > 0.0,
+            Self::LogNormal { sigma, .. } => sigma > 0.0,
+            Self::Uniform { min, max } => min < max,
+            Self::Triangular { min, mode, max } => min < max && (min..=max).contains(&mode),
+        };
+        match valid {
+            true => Ok(()),
+            false => Err(RSCMError::Error(format!(
+                "Invalid parameters for distribution: {:?}",
+                self
+            ))),
+        }
+    }
+
+    /// Cumulative distribution function
+    pub fn cdf(&self, x: FloatValue) -> FloatValue {
+        match *self {
+            Self::Normal { mean, std } => normal_cdf((x - mean) / std),
+            Self::LogNormal { mu, sigma } => match x > 0.0 {
+                true => normal_cdf((x.ln() - mu) / sigma),
+                false => 0.0,
+            },
+            Self::Uniform { min, max } => ((x - min) / (max - min)).clamp(0.0, 1.0),
+            Self::Triangular { min, mode, max } => {
+                if x <= min {
+                    0.0
+                } else if x <= mode {
+                    (x - min).powi(2) / ((max - min) * (mode - min))
+                } else if x < max {
+                    1.0 - (max - x).powi(2) / ((max - min) * (max - mode))
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+
+    /// Inverse of the cumulative distribution function
+    pub fn ppf(&self, p: FloatValue) -> FloatValue {
+        match *self {
+            Self::Normal { mean, std } => mean + std * normal_ppf(p),
+            Self::LogNormal { mu, sigma } => (mu + sigma * normal_ppf(p)).exp(),
+            Self::Uniform { min, max } => min + p * (max - min),
+            Self::Triangular { min, mode, max } => {
+                let split = (mode - min) / (max - min);
+                match p < split {
+                    true => min + (p * (max - min) * (mode - min)).sqrt(),
+                    false => max - ((1.0 - p) * (max - min) * (max - mode)).sqrt(),
+                }
+            }
+        }
+    }
+
+    /// Natural logarithm of the probability density function
+    pub fn ln_pdf(&self, x: FloatValue) -> FloatValue {
+        match *self {
+            Self::Normal { mean, std } => {
+                let z = (x - mean) / std;
+                -0.5 * z * z - std.ln() - 0.5 * (2.0 * PI).ln()
+            }
+            Self::LogNormal { mu, sigma } => match x > 0.0 {
+                true => {
+                    let z = (x.ln() - mu) / sigma;
+                    -0.5 * z * z - (x * sigma).ln() - 0.5 * (2.0 * PI).ln()
+                }
+                false => FloatValue::NEG_INFINITY,
+            },
+            Self::Uniform { min, max } => match (min..=max).contains(&x) {
+                true => -(max - min).ln(),
+                false => FloatValue::NEG_INFINITY,
+            },
+            Self::Triangular { min, mode, max } => {
+                let density = if x < min || x > max {
+                    0.0
+                } else if x < mode {
+                    2.0 * (x - min) / ((max - min) * (mode - min))
+                } else if x > mode {
+                    2.0 * (max - x) / ((max - min) * (max - mode))
+                } else {
+                    2.0 / (max - min)
+                };
+                density.ln()
+            }
+        }
+    }
+}
+
+/// A distribution which may be truncated to `[lower, upper]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Prior {
+    #[serde(flatten)]
+    pub distribution: Distribution,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lower: Option<FloatValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upper: Option<FloatValue>,
+}
+
+impl From<Distribution> for Prior {
+    fn from(distribution: Distribution) -> Self {
+        Self {
+            distribution,
+            lower: None,
+            upper: None,
+        }
+    }
+}
+
+impl Prior {
+    /// Truncate the distribution to `[lower, upper]`
+    pub fn truncated(distribution: Distribution, lower: FloatValue, upper: FloatValue) -> Self {
+        Self {
+            distribution,
+            lower: Some(lower),
+            upper: Some(upper),
+        }
+    }
+
+    /// Values of the untruncated CDF at the truncation bounds
+    fn cdf_bounds(&self) -> (FloatValue, FloatValue) {
+        (
+            self.lower.map_or(0.0, |x| self.distribution.cdf(x)),
+            self.upper.map_or(1.0, |x| self.distribution.cdf(x)),
+        )
+    }
+
+    /// Check that the distribution is valid and that the truncated range has a non-zero probability
+    pub fn validate(&self) -> RSCMResult<()> {
+        self.distribution.validate()?;
+        let (lower, upper) = self.cdf_bounds();
+        match lower < upper {
+            true => Ok(()),
+            false => Err(RSCMError::Error(format!(
+                "Truncation bounds [{:?}, {:?}] contain no probability",
+                self.lower, self.upper
+            ))),
+        }
+    }
+
+    pub fn cdf(&self, x: FloatValue) -> FloatValue {
+        let (lower, upper) = self.cdf_bounds();
+        ((self.distribution.cdf(x) - lower) / (upper - lower)).clamp(0.0, 1.0)
+    }
+
+    pub fn ppf(&self, p: FloatValue) -> FloatValue {
+        let (lower, upper) = self.cdf_bounds();
+        let x = self.distribution.ppf(lower + p * (upper - lower));
+        // Guard against values outside the bounds due to rounding
+        x.clamp(
+            self.lower.unwrap_or(FloatValue::NEG_INFINITY),
+            self.upper.unwrap_or(FloatValue::INFINITY),
+        )
+    }
+
+    pub fn ln_pdf(&self, x: FloatValue) -> FloatValue {
+        if self.lower.is_some_and(|lower| x < lower) || self.upper.is_some_and(|upper| x > upper) {
+            return FloatValue::NEG_INFINITY;
+        }
+        let (lower, upper) = self.cdf_bounds();
+        self.distribution.ln_pdf(x) - (upper - lower).ln()
+    }
+}
+
+/// Prior of a named parameter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParameterPrior {
+    pub name: String,
+    #[serde(flatten)]
+    pub prior: Prior,
+}
+
+/// Correlation between two parameters in the Gaussian copula
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Correlation {
+    pub first: String,
+    pub second: String,
+    pub coefficient: FloatValue,
+}
+
+/// Distribution of a set of parameters
+///
+/// Parameters are independent unless correlations are defined.
+/// Correlations are applied using a Gaussian copula, so each parameter retains its prior
+/// as its marginal distribution while the ranks of the parameters are correlated.
+/// The coefficients are the correlations between the normal scores of the parameters.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct JointDistribution {
+    pub parameters: Vec<ParameterPrior>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub correlations: Vec<Correlation>,
+}
+
+impl JointDistribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_parameter(&mut self, name: &str, prior: Prior) -> &mut Self {
+        self.parameters.push(ParameterPrior {
+            name: name.to_string(),
+            prior,
+        });
+        self
+    }
+
+    pub fn with_correlation(
+        &mut self,
+        first: &str,
+        second: &str,
+        coefficient: FloatValue,
+    ) -> &mut Self {
+        self.correlations.push(Correlation {
+            first: first.to_string(),
+            second: second.to_string(),
+            coefficient,
+        });
+        self
+    }
+
+    /// Names of the parameters in the order used by [`JointDistribution::transform`]
+    pub fn names(&self) -> Vec<&str> {
+        self.parameters.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    fn index(&self, name: &str) -> RSCMResult<usize> {
+        self.parameters
+            .iter()
+            .position(|p| p.name == name)
+            .ok_or_else(|| RSCMError::Error(format!("Unknown parameter '{}'", name)))
+    }
+
+    /// Correlation matrix of the Gaussian copula
+    fn correlation_matrix(&self) -> RSCMResult<DMatrix<FloatValue>> {
+        let mut matrix = DMatrix::identity(self.parameters.len(), self.parameters.len());
+        for correlation in self.correlations.iter() {
+            let (i, j) = (
+                self.index(&correlation.first)?,
+                self.index(&correlation.second)?,
+            );
+            if i == j || correlation.coefficient.abs() >= 1.0 {
+                return Err(RSCMError::Error(format!(
+                    "Invalid correlation between {} and {}",
+                    correlation.first, correlation.second
+                )));
+            }
+            matrix[(i, j)] = correlation.coefficient;
+            matrix[(j, i)] = correlation.coefficient;
+        }
+        Ok(matrix)
+    }
+
+    /// Lower triangular Cholesky factor of the correlation matrix
+    fn cholesky(&self) -> RSCMResult<DMatrix<FloatValue>> {
+        self.correlation_matrix()?
+            .cholesky()
+            .map(|c| c.l())
+            .ok_or_else(|| {
+                RSCMError::Error("Correlation matrix isn't positive definite".to_string())
+            })
+    }
+
+    /// Check that the priors and correlations are valid
+    pub fn validate(&self) -> RSCMResult<()> {
+        self.parameters
+            .iter()
+            .try_for_each(|p| p.prior.validate())?;
+        self.cholesky().map(|_| ())
+    }
+
+    /// Transform a point in the unit hypercube into a set of parameter values
+    pub fn transform(&self, point: &[FloatValue]) -> RSCMResult<Vec<FloatValue>> {
+        self.transform_with(point, &self.cholesky()?)
+    }
+
+    fn transform_with(
+        &self,
+        point: &[FloatValue],
+        cholesky: &DMatrix<FloatValue>,
+    ) -> RSCMResult<Vec<FloatValue>> {
+        if point.len() != self.parameters.len() {
+            return Err(RSCMError::Error(format!(
+                "Expected a point with {} dimensions, got {}",
+                self.parameters.len(),
+                point.len()
+            )));
+        }
+        let normal_scores =
+            DVector::from_iterator(point.len(), point.iter().map(|u| normal_ppf(*u)));
+        let correlated = cholesky * normal_scores;
+
+        Ok(self
+            .parameters
+            .iter()
+            .zip(correlated.iter())
+            .map(|(p, z)| p.prior.ppf(normal_cdf(*z)))
+            .collect())
+    }
+
+    /// Sample `n` sets of parameter values using a [`Sampler`]
+    pub fn sample(&self, n: usize, sampler: &mut dyn Sampler) -> RSCMResult<Vec<Vec<FloatValue>>> {
+        self.validate()?;
+        let cholesky = self.cholesky()?;
+        sampler
+            .sample(n, self.parameters.len())?
+            .iter()
+            .map(|point| self.transform_with(point, &cholesky))
+            .collect()
+    }
+
+    /// Natural logarithm of the joint probability density of a set of parameter values
+    pub fn ln_pdf(&self, values: &[FloatValue]) -> RSCMResult<FloatValue> {
+        let marginals: FloatValue = self
+            .parameters
+            .iter()
+            .zip(values)
+            .map(|(p, x)| p.prior.ln_pdf(*x))
+            .sum();
+        if self.correlations.is_empty() || !marginals.is_finite() {
+            return Ok(marginals);
+        }
+
+        // Density of the Gaussian copula
+        let correlation = self.correlation_matrix()?;
+        let cholesky = correlation.clone().cholesky().ok_or_else(|| {
+            RSCMError::Error("Correlation matrix isn't positive definite".to_string())
+        })?;
+        let z = DVector::from_iterator(
+            values.len(),
+            self.parameters
+                .iter()
+                .zip(values)
+                .map(|(p, x)| normal_ppf(p.prior.cdf(*x))),
+        );
+        let ln_det = 2.0
+            * cholesky
+                .l()
+                .diagonal()
+                .iter()
+                .map(|d| d.ln())
+                .sum::<FloatValue>();
+        let quadratic = (z.transpose()
+            * (cholesky.inverse() - DMatrix::identity(z.len(), z.len()))
+            * &z)[(0, 0)];
+
+        Ok(marginals - 0.5 * ln_det - 0.5 * quadratic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampling::{LatinHypercubeSampler, SobolSampler};
+    use is_close::is_close;
+
+    #[test]
+    fn standard_normal() {
+        assert!(is_close!(normal_ppf(0.975), 1.959963985, abs_tol = 1e-8));
+        assert!(is_close!(normal_cdf(1.959963985), 0.975, abs_tol = 1e-7));
+        for p in [1e-6, 0.01, 0.3, 0.5, 0.9, 0.999] {
+            assert!(is_close!(normal_cdf(normal_ppf(p)), p, rel_tol = 1e-6));
+        }
+    }
+
+    #[test]
+    fn inverse_cdfs() {
+        let distributions = [
+            Distribution::Normal {
+                mean: 1.0,
+                std: 2.0,
+            },
+            Distribution::LogNormal {
+                mu: 0.5,
+                sigma: 0.3,
+            },
+            Distribution::Uniform {
+                min: -1.0,
+                max: 3.0,
+            },
+            Distribution::Triangular {
+                min: 0.0,
+                mode: 1.0,
+                max: 4.0,
+            },
+        ];
+        for distribution in distributions {
+            distribution.validate().unwrap();
+            for p in [0.05, 0.25, 0.5, 0.75, 0.95] {
+                let x = distribution.ppf(p);
+                assert!(is_close!(distribution.cdf(x), p, abs_tol = 1e-6));
+            }
+        }
+
+        assert!(Distribution::Triangular {
+            min: 0.0,
+            mode: 5.0,
+            max: 4.0
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn truncation() {
+        let prior = Prior::truncated(
+            Distribution::Normal {
+                mean: 0.0,
+                std: 1.0,
+            },
+            0.0,
+            FloatValue::INFINITY,
+        );
+        prior.validate().unwrap();
+
+        // Half-normal distribution
+        assert!(is_close!(prior.ppf(0.0), 0.0, abs_tol = 1e-6));
+        assert!(is_close!(
+            prior.ppf(0.95),
+            normal_ppf(0.975),
+            abs_tol = 1e-6
+        ));
+        assert_eq!(prior.ln_pdf(-1.0), FloatValue::NEG_INFINITY);
+        assert!(is_close!(
+            prior.ln_pdf(0.0),
+            (2.0 / (2.0 * PI).sqrt()).ln(),
+            abs_tol = 1e-6
+        ));
+
+        let empty = Prior::truncated(Distribution::Uniform { min: 0.0, max: 1.0 }, 2.0, 3.0);
+        assert!(empty.validate().is_err());
+    }
+
+    fn correlation(samples: &[Vec<FloatValue>]) -> FloatValue {
+        let n = samples.len() as FloatValue;
+        let mean = |i: usize| samples.iter().map(|s| s[i]).sum::<FloatValue>() / n;
+        let (mx, my) = (mean(0), mean(1));
+        let cov: FloatValue = samples.iter().map(|s| (s[0] - mx) * (s[1] - my)).sum();
+        let var = |i: usize, m: FloatValue| {
+            samples
+                .iter()
+                .map(|s| (s[i] - m).powi(2))
+                .sum::<FloatValue>()
+        };
+        cov / (var(0, mx) * var(1, my)).sqrt()
+    }
+
+    #[test]
+    fn correlated_sampling() {
+        let mut joint = JointDistribution::new();
+        joint
+            .with_parameter(
+                "a",
+                Distribution::Normal {
+                    mean: 0.0,
+                    std: 1.0,
+                }
+                .into(),
+            )
+            .with_parameter(
+                "b",
+                Distribution::Normal {
+                    mean: 10.0,
+                    std: 2.0,
+                }
+                .into(),
+            );
+
+        let independent = joint
+            .sample(2000, &mut LatinHypercubeSampler::new(0))
+            .unwrap();
+        assert!(correlation(&independent).abs() < 0.1);
+
+        joint.with_correlation("a", "b", 0.8);
+        let correlated = joint.sample(2000, &mut SobolSampler::with_seed(0)).unwrap();
+        assert!(is_close!(correlation(&correlated), 0.8, abs_tol = 0.05));
+
+        // The marginal distributions are unchanged
+        let mean_b = correlated.iter().map(|s| s[1]).sum::<FloatValue>() / 2000.0;
+        assert!(is_close!(mean_b, 10.0, abs_tol = 0.05));
+
+        // The copula density is largest when the parameters are similarly ranked
+        assert!(joint.ln_pdf(&[1.0, 12.0]).unwrap() > joint.ln_pdf(&[1.0, 8.0]).unwrap());
+
+        joint.with_correlation("a", "c", 0.1);
+        assert!(joint.validate().is_err());
+    }
+
+    #[test]
+    fn serialisation() {
+        let mut joint = JointDistribution::new();
+        joint
+            .with_parameter(
+                "lambda0",
+                Prior::truncated(
+                    Distribution::Triangular {
+                        min: 0.5,
+                        mode: 1.0,
+                        max: 2.0,
+                    },
+                    0.6,
+                    1.8,
+                ),
+            )
+            .with_parameter("eta", Distribution::Uniform { min: 0.1, max: 1.0 }.into())
+            .with_correlation("lambda0", "eta", -0.3);
+
+        let serialised = toml::to_string(&joint).unwrap();
+        assert!(serialised.contains("kind = \"triangular\""));
+        assert_eq!(
+            toml::from_str::<JointDistribution>(&serialised).unwrap(),
+            joint
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Forward-mode automatic differentiation using dual numbers
+//!
+//! A [`Dual`] number carries a value and its derivative with respect to a single parameter.
+//! Arithmetic on dual numbers applies the chain rule, so evaluating a function using dual
+//! numbers calculates its derivative exactly (to floating point precision) alongside its value.
+//!
+//! Components which are analytic functions of their inputs and parameters can implement
+//! [`crate::component::Component::solve_dual`].
+//! A model built with [`crate::model::ModelBuilder::with_sensitivity`] then propagates the
+//! derivative of every variable with respect to the parameter through each time step,
+//! giving the sensitivity of all outputs from a single run.
+use crate::timeseries::FloatValue;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A value and its derivative with respect to a parameter
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Dual {
+    pub value: FloatValue,
+    pub derivative: FloatValue,
+}
+
+/// Input or output state of a component in dual numbers
+pub type DualState = HashMap<String, Dual>;
+
+impl Dual {
+    pub fn new(value: FloatValue, derivative: FloatValue) -> Self {
+        Self { value, derivative }
+    }
+
+    /// A value which doesn't depend on the parameter
+    pub fn constant(value: FloatValue) -> Self {
+        Self::new(value, 0.0)
+    }
+
+    /// A parameter of a component
+    ///
+    /// The derivative is one if `name` is the parameter being differentiated with respect to
+    /// and zero otherwise.
+    pub fn parameter(value: FloatValue, name: &str, parameter: &str) -> Self {
+        Self::new(value, if name == parameter { 1.0 } else { 0.0 })
+    }
+
+    pub fn exp(self) -> Self {
+        let value = self.value.exp();
+        Self::new(value, self.derivative * value)
+    }
+
+    pub fn ln(self) -> Self {
+        Self::new(self.value.ln(), self.derivative / self.value)
+    }
+
+    pub fn sqrt(self) -> Self {
+        let value = self.value.sqrt();
+        Self::new(value, self.derivative / (2.0 * value))
+    }
+
+    pub fn powi(self, n: i32) -> Self {
+        Self::new(
+            self.value.powi(n),
+            self.derivative * n as FloatValue * self.value.powi(n - 1),
+        )
+    }
+
+    pub fn powf(self, n: FloatValue) -> Self {
+        Self::new(
+            self.value.powf(n),
+            self.derivative * n * self.value.powf(n - 1.0),
+        )
+    }
+}
+
+impl From<FloatValue> for Dual {
+    fn from(value: FloatValue) -> Self {
+        Self::constant(value)
+    }
+}
+
+impl Neg for Dual {
+    type Output = Dual;
+
+    fn neg(self) -> Dual {
+        Dual::new(-self.value, -self.derivative)
+    }
+}
+
+impl Add for Dual {
+    type Output = Dual;
+
+    fn add(self, other: Dual) -> Dual {
+        Dual::new(self.value + other.value, self.derivative + other.derivative)
+    }
+}
+
+impl Sub for Dual {
+    type Output = Dual;
+
+    fn sub(self, other: Dual) -> Dual {
+        Dual::new(self.value - other.value, self.derivative - other.derivative)
+    }
+}
+
+impl Mul for Dual {
+    type Output = Dual;
+
+    fn mul(self, other: Dual) -> Dual {
+        Dual::new(
+            self.value * other.value,
+            self.derivative * other.value + self.value * other.derivative,
+        )
+    }
+}
+
+impl Div for Dual {
+    type Output = Dual;
+
+    fn div(self, other: Dual) -> Dual {
+        Dual::new(
+            self.value / other.value,
+            (self.derivative * other.value - self.value * other.derivative)
+                / (other.value * other.value),
+        )
+    }
+}
+
+/// Arithmetic between dual numbers and constants
+macro_rules! impl_constant_ops {
+    ($($trait:ident, $method:ident);*) => {
+        $(
+            impl $trait<FloatValue> for Dual {
+                type Output = Dual;
+
+                fn $method(self, other: FloatValue) -> Dual {
+                    self.$method(Dual::constant(other))
+                }
+            }
+
+            impl $trait<Dual> for FloatValue {
+                type Output = Dual;
+
+                fn $method(self, other: Dual) -> Dual {
+                    Dual::constant(self).$method(other)
+                }
+            }
+        )*
+    };
+}
+
+impl_constant_ops!(Add, add; Sub, sub; Mul, mul; Div, div);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use is_close::is_close;
+
+    #[test]
+    fn chain_rule() {
+        let x = Dual::new(2.0, 1.0);
+
+        // d/dx x^2 exp(-x / 4) = (2x - x^2 / 4) exp(-x / 4)
+        let y = x.powi(2) * (-x / 4.0).exp();
+        assert!(is_close!(y.value, 4.0 * (-0.5 as FloatValue).exp()));
+        assert!(is_close!(
+            y.derivative,
+            (4.0 - 1.0) * (-0.5 as FloatValue).exp()
+        ));
+
+        // d/dx ln(x) / sqrt(x) = (1 - ln(x) / 2) / x^1.5
+        let y = x.ln() / x.sqrt();
+        assert!(is_close!(
+            y.derivative,
+            (1.0 - (2.0 as FloatValue).ln() / 2.0) / (2.0 as FloatValue).powf(1.5)
+        ));
+
+        let y = 1.0 - 3.0 * x + x.powf(0.5);
+        assert!(is_close!(
+            y.derivative,
+            -3.0 + 0.5 / (2.0 as FloatValue).sqrt()
+        ));
+    }
+
+    #[test]
+    fn parameters() {
+        assert_eq!(Dual::parameter(3.0, "tau", "tau"), Dual::new(3.0, 1.0));
+        assert_eq!(Dual::parameter(3.0, "tau", "lambda"), Dual::new(3.0, 0.0));
+        assert_eq!(Dual::from(3.0), Dual::constant(3.0));
+    }
+}
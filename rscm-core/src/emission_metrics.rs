@@ -0,0 +1,360 @@
+//! Aggregation of multi-gas emissions into CO2-equivalent emissions
+//!
+//! Emissions of each gas are weighted by an emission metric and summed.
+//! The conventional metrics (GWP100 and GWP20) weight the emissions at each time
+//! by the global warming potential of the gas.
+//! GWP* (Smith et al. 2021, <https://doi.org/10.1038/s41612-021-00169-8>) instead represents
+//! short-lived gases by a combination of their current emissions and the change in emissions,
+//! which better represents their effect on temperature.
+//!
+//! The default metric values are from IPCC AR6 WG1 Chapter 7
+//! and can be replaced using [`CO2Equivalent::with_gas`].
+//! Emissions are converted using the unit registry (see [`crate::units`]),
+//! so the units of each gas must be convertible to `t{gas} / yr`.
+use crate::errors::{RSCMError, RSCMResult};
+use crate::interpolate::strategies::LinearSplineStrategy;
+use crate::timeseries::{FloatValue, Time, Timeseries};
+use crate::timeseries_collection::TimeseriesCollection;
+use crate::units;
+use numpy::ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+/// Parameters of the GWP* metric
+///
+/// The CO2-equivalent emissions of a short-lived gas are
+/// `GWP100 * (current * E(t) - previous * E(t - interval))`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GwpStarParameters {
+    /// Weight of the current emissions
+    pub current: FloatValue,
+    /// Weight of the emissions `interval` years earlier
+    pub previous: FloatValue,
+    /// unit: yr
+    pub interval: Time,
+}
+
+impl Default for GwpStarParameters {
+    /// Values from Smith et al. (2021) which include the response of the carbon cycle
+    fn default() -> Self {
+        Self {
+            current: 4.53,
+            previous: 4.25,
+            interval: 20.0,
+        }
+    }
+}
+
+/// Metric used to weight the emissions of each gas
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EmissionMetric {
+    /// Global warming potential over a 100 year time horizon
+    GWP100,
+    /// Global warming potential over a 20 year time horizon
+    GWP20,
+    /// GWP100 for long-lived gases and GWP* for short-lived gases
+    GWPStar(GwpStarParameters),
+}
+
+impl Display for EmissionMetric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EmissionMetric::GWP100 => write!(f, "GWP100"),
+            EmissionMetric::GWP20 => write!(f, "GWP20"),
+            EmissionMetric::GWPStar(_) => write!(f, "GWP*"),
+        }
+    }
+}
+
+/// Metric values of a gas
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GasMetrics {
+    pub gwp100: FloatValue,
+    pub gwp20: FloatValue,
+    /// Short-lived gases use the GWP* formulation when using [`EmissionMetric::GWPStar`]
+    pub short_lived: bool,
+}
+
+/// Calculates CO2-equivalent emissions using an emission metric
+///
+/// # Examples
+///
+/// ```rust
+/// use numpy::array;
+/// use rscm_core::emission_metrics::{CO2Equivalent, EmissionMetric};
+/// use rscm_core::timeseries::{TimeAxis, Timeseries};
+/// use rscm_core::interpolate::strategies::PreviousStrategy;
+/// use std::sync::Arc;
+///
+/// let methane = Timeseries::new(
+///     array![100.0, 100.0],
+///     Arc::new(TimeAxis::from_values(array![2000.0, 2001.0])),
+///     "MtCH4 / yr".to_string(),
+///     PreviousStrategy::new(true).into(),
+/// );
+///
+/// let co2e = CO2Equivalent::new(EmissionMetric::GWP100)
+///     .aggregate(&[("CH4", &methane)])
+///     .unwrap();
+///
+/// assert_eq!(co2e.units(), "GtCO2 / yr");
+/// assert!((co2e.at(0).unwrap() - 2.79).abs() < 1e-12);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CO2Equivalent {
+    metric: EmissionMetric,
+    gases: BTreeMap<String, GasMetrics>,
+    units: String,
+}
+
+impl CO2Equivalent {
+    /// Create a calculator using the default metric values
+    ///
+    /// CO2-equivalent emissions are in GtCO2 / yr by default.
+    pub fn new(metric: EmissionMetric) -> Self {
+        let gases = [
+            ("CO2", 1.0, 1.0, false),
+            ("CH4", 27.9, 81.2, true),
+            ("N2O", 273.0, 273.0, false),
+        ]
+        .into_iter()
+        .map(|(gas, gwp100, gwp20, short_lived)| {
+            (
+                gas.to_string(),
+                GasMetrics {
+                    gwp100,
+                    gwp20,
+                    short_lived,
+                },
+            )
+        })
+        .collect();
+
+        Self {
+            metric,
+            gases,
+            units: "GtCO2 / yr".to_string(),
+        }
+    }
+
+    /// Add a gas or replace the metric values of a gas
+    pub fn with_gas(&mut self, gas: &str, metrics: GasMetrics) -> &mut Self {
+        self.gases.insert(gas.to_string(), metrics);
+        self
+    }
+
+    /// Set the units of the CO2-equivalent emissions
+    pub fn with_units(&mut self, units: &str) -> &mut Self {
+        self.units = units.to_string();
+        self
+    }
+
+    pub fn metric(&self) -> &EmissionMetric {
+        &self.metric
+    }
+
+    /// Name of the variable containing the CO2-equivalent emissions, e.g. "Emissions|CO2e|GWP100"
+    pub fn variable_name(&self) -> String {
+        format!("Emissions|CO2e|{}", self.metric)
+    }
+
+    /// CO2-equivalent emissions of a gas in tCO2 / yr at a given time
+    fn gas_co2e(
+        &self,
+        metrics: &GasMetrics,
+        emissions: &Timeseries<FloatValue>,
+        factor: FloatValue,
+        time: Time,
+    ) -> FloatValue {
+        let at = |t: Time| {
+            emissions
+                .at_time(t)
+                .map_or(FloatValue::NAN, |value| value * factor)
+        };
+
+        match &self.metric {
+            EmissionMetric::GWP100 => metrics.gwp100 * at(time),
+            EmissionMetric::GWP20 => metrics.gwp20 * at(time),
+            EmissionMetric::GWPStar(parameters) if metrics.short_lived => {
+                metrics.gwp100
+                    * (parameters.current * at(time)
+                        - parameters.previous * at(time - parameters.interval))
+            }
+            EmissionMetric::GWPStar(_) => metrics.gwp100 * at(time),
+        }
+    }
+
+    /// Aggregate the emissions of several gases
+    ///
+    /// The result uses the time axis of the first timeseries
+    /// and the other timeseries are interpolated onto it.
+    /// Values which can't be interpolated, such as the emissions before the start of a
+    /// timeseries required by GWP*, are NaN.
+    ///
+    /// Returns an error if no emissions are provided, a gas doesn't have metric values
+    /// or the units of a gas can't be converted.
+    pub fn aggregate(
+        &self,
+        emissions: &[(&str, &Timeseries<FloatValue>)],
+    ) -> RSCMResult<Timeseries<FloatValue>> {
+        let (_, first) = emissions
+            .first()
+            .ok_or_else(|| RSCMError::Error("No emissions to aggregate".to_string()))?;
+        let time_axis = first.time_axis();
+
+        let mut values = Array1::zeros(time_axis.len());
+        for (gas, timeseries) in emissions {
+            let metrics = self
+                .gases
+                .get(*gas)
+                .ok_or_else(|| RSCMError::Error(format!("No metric values for {}", gas)))?;
+            let factor =
+                units::conversion_factor(timeseries.units(), &format!("t{} / yr", gas), &["CO2"])?;
+
+            values
+                .iter_mut()
+                .zip(time_axis.values())
+                .for_each(|(value, time)| {
+                    *value += self.gas_co2e(metrics, timeseries, factor, *time)
+                });
+        }
+
+        let factor = units::conversion_factor("tCO2 / yr", &self.units, &["CO2"])?;
+        Ok(Timeseries::new(
+            values * factor,
+            time_axis,
+            self.units.clone(),
+            LinearSplineStrategy::new(true).into(),
+        ))
+    }
+
+    /// Aggregate the emissions in a collection
+    ///
+    /// Emissions of each gas with metric values are read from the "Emissions|{gas}" variable.
+    /// Gases which aren't in the collection are skipped.
+    pub fn aggregate_collection(
+        &self,
+        collection: &TimeseriesCollection,
+    ) -> RSCMResult<Timeseries<FloatValue>> {
+        let emissions: Vec<(&str, &Timeseries<FloatValue>)> = self
+            .gases
+            .keys()
+            .filter_map(|gas| {
+                collection
+                    .get_timeseries_by_name(&format!("Emissions|{}", gas))
+                    .map(|timeseries| (gas.as_str(), timeseries))
+            })
+            .collect();
+        self.aggregate(&emissions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeseries::TimeAxis;
+    use crate::timeseries_collection::VariableType;
+    use is_close::is_close;
+    use numpy::array;
+    use numpy::ndarray::Array;
+    use std::sync::Arc;
+
+    fn emissions(values: Array1<FloatValue>, units: &str) -> Timeseries<FloatValue> {
+        let n = values.len() as FloatValue;
+        Timeseries::new(
+            values,
+            Arc::new(TimeAxis::from_values(Array::linspace(
+                2000.0,
+                2000.0 + 10.0 * (n - 1.0),
+                n as usize,
+            ))),
+            units.to_string(),
+            LinearSplineStrategy::new(true).into(),
+        )
+    }
+
+    fn collection() -> TimeseriesCollection {
+        let mut collection = TimeseriesCollection::new();
+        [
+            (
+                "Emissions|CO2",
+                emissions(array![1.0, 1.0, 1.0, 1.0], "GtC / yr"),
+            ),
+            (
+                "Emissions|CH4",
+                emissions(array![100.0, 200.0, 200.0, 200.0], "MtCH4 / yr"),
+            ),
+            (
+                "Emissions|N2O",
+                emissions(array![10.0, 10.0, 10.0, 10.0], "MtN2O / yr"),
+            ),
+        ]
+        .into_iter()
+        .for_each(|(name, ts)| {
+            collection
+                .add_timeseries(name.to_string(), ts, VariableType::Exogenous)
+                .unwrap()
+        });
+        collection
+    }
+
+    #[test]
+    fn gwp() {
+        let co2 = 44.009 / 12.011;
+
+        let co2e = CO2Equivalent::new(EmissionMetric::GWP100)
+            .aggregate_collection(&collection())
+            .unwrap();
+        assert_eq!(co2e.units(), "GtCO2 / yr");
+        assert!(is_close!(co2e.at(0).unwrap(), co2 + 2.79 + 2.73));
+        assert!(is_close!(co2e.at(2).unwrap(), co2 + 5.58 + 2.73));
+
+        let co2e = CO2Equivalent::new(EmissionMetric::GWP20)
+            .with_units("MtCO2 / yr")
+            .aggregate_collection(&collection())
+            .unwrap();
+        assert!(is_close!(co2e.at(0).unwrap(), 1e3 * (co2 + 8.12 + 2.73)));
+    }
+
+    #[test]
+    fn gwp_star() {
+        let co2 = 44.009 / 12.011;
+        let mut calculator = CO2Equivalent::new(EmissionMetric::GWPStar(Default::default()));
+        let co2e = calculator.aggregate_collection(&collection()).unwrap();
+        assert_eq!(calculator.variable_name(), "Emissions|CO2e|GWP*");
+
+        // The emissions 20 years earlier are extrapolated
+        let extrapolated = 2.79 * (4.53 * 2.0 - 4.25 * 0.0);
+        assert!(is_close!(co2e.at(1).unwrap(), co2 + extrapolated + 2.73));
+        // Increasing emissions of short-lived gases have a larger effect than constant emissions
+        let rising = 2.79 * (4.53 * 2.0 - 4.25 * 1.0);
+        let constant = 2.79 * (4.53 * 2.0 - 4.25 * 2.0);
+        assert!(is_close!(co2e.at(2).unwrap(), co2 + rising + 2.73));
+        assert!(is_close!(co2e.at(3).unwrap(), co2 + constant + 2.73));
+
+        // Custom metric values
+        calculator.with_gas(
+            "CH4",
+            GasMetrics {
+                gwp100: 30.0,
+                gwp20: 80.0,
+                short_lived: false,
+            },
+        );
+        let co2e = calculator.aggregate_collection(&collection()).unwrap();
+        assert!(is_close!(co2e.at(1).unwrap(), co2 + 6.0 + 2.73));
+    }
+
+    #[test]
+    fn invalid() {
+        let calculator = CO2Equivalent::new(EmissionMetric::GWP100);
+        assert!(calculator.aggregate(&[]).is_err());
+        assert!(calculator
+            .aggregate(&[("SF6", &emissions(array![1.0, 1.0], "ktSF6 / yr"))])
+            .is_err());
+        assert!(calculator
+            .aggregate(&[("CH4", &emissions(array![1.0, 1.0], "K"))])
+            .is_err());
+    }
+}
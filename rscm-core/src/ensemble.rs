@@ -0,0 +1,510 @@
+//! Running ensembles of models
+//!
+//! Each member of an ensemble is defined by a set of parameters which are used to
+//! build a model.
+//! In large Monte Carlo ensembles some parameter combinations will fail to solve.
+//! A [`FailurePolicy`] controls how these failures are handled,
+//! so that a single failure doesn't need to stop the whole ensemble.
+use crate::errors::{RSCMError, RSCMResult};
+use crate::html;
+use crate::iamc::split_columns;
+use crate::model::{ModelBuilder, StabilityOptions};
+use crate::timeseries::FloatValue;
+use crate::timeseries_collection::TimeseriesCollection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+
+/// How to handle ensemble members which fail
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FailurePolicy {
+    /// Stop the ensemble at the first failure
+    AbortAll,
+    /// Skip failed members and record them in the failure report
+    SkipAndRecord,
+    /// Retry failed members with numerical instability detection enabled
+    ///
+    /// Members which still fail are skipped and recorded in the failure report.
+    RetryWithRefinement(StabilityOptions),
+}
+
+/// An ensemble member that failed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberFailure {
+    /// Index of the member in the ensemble
+    pub index: usize,
+    /// Parameters of the member
+    pub parameters: toml::Value,
+    /// Description of the failure
+    pub error: String,
+    /// Whether the member was retried with refinement
+    pub retried: bool,
+}
+
+/// Results from running an ensemble
+///
+/// See [`crate::archive::save_ensemble`] for writing the results to disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnsembleResult {
+    /// State of each successful member after the run, along with the member's index
+    pub results: Vec<(usize, TimeseriesCollection)>,
+    /// Members which failed
+    pub failures: Vec<MemberFailure>,
+}
+
+impl EnsembleResult {
+    /// Summary of the failed members and their parameters
+    pub fn failure_report(&self) -> String {
+        let mut report = format!(
+            "{} of {} members failed\n",
+            self.failures.len(),
+            self.failures.len() + self.results.len()
+        );
+        self.failures.iter().for_each(|failure| {
+            report.push_str(&format!(
+                "member {}: {} (retried={}) parameters={}\n",
+                failure.index, failure.error, failure.retried, failure.parameters
+            ));
+        });
+        report
+    }
+
+    /// Statistics of the latest valid value of each variable across the successful members
+    ///
+    /// Variables are sorted by name.
+    /// Members without a valid value for a variable are excluded from its statistics.
+    pub fn statistics(&self) -> Vec<VariableStatistics> {
+        let mut finals: BTreeMap<&str, (&str, Vec<FloatValue>)> = BTreeMap::new();
+        self.results.iter().for_each(|(_, collection)| {
+            collection.iter().for_each(|item| {
+                let entry = finals
+                    .entry(&item.name)
+                    .or_insert_with(|| (item.timeseries.units(), vec![]));
+                if let Some(value) = item.timeseries.latest_value().filter(|v| !v.is_nan()) {
+                    entry.1.push(value);
+                }
+            });
+        });
+
+        finals
+            .into_iter()
+            .filter(|(_, (_, values))| !values.is_empty())
+            .map(|(name, (units, mut values))| {
+                values.sort_by(|a, b| a.total_cmp(b));
+                let n = values.len();
+                VariableStatistics {
+                    name: name.to_string(),
+                    units: units.to_string(),
+                    members: n,
+                    min: values[0],
+                    median: match n % 2 {
+                        0 => (values[n / 2 - 1] + values[n / 2]) / 2.0,
+                        _ => values[n / 2],
+                    },
+                    max: values[n - 1],
+                }
+            })
+            .collect()
+    }
+
+    /// HTML description of the ensemble
+    ///
+    /// Includes the [`EnsembleResult::statistics`] of each variable and the failed members.
+    pub fn to_html(&self) -> String {
+        let statistics: Vec<Vec<String>> = self
+            .statistics()
+            .into_iter()
+            .map(|s| {
+                vec![
+                    s.name,
+                    s.units,
+                    s.members.to_string(),
+                    html::number(s.min),
+                    html::number(s.median),
+                    html::number(s.max),
+                ]
+            })
+            .collect();
+        let mut output = format!(
+            "<div><strong>EnsembleResult</strong> {} of {} members succeeded{}",
+            self.results.len(),
+            self.results.len() + self.failures.len(),
+            html::table(
+                &["Variable", "Units", "Members", "Min", "Median", "Max"],
+                &statistics
+            )
+        );
+
+        if !self.failures.is_empty() {
+            let failures: Vec<Vec<String>> = self
+                .failures
+                .iter()
+                .map(|f| {
+                    vec![
+                        f.index.to_string(),
+                        f.error.clone(),
+                        f.retried.to_string(),
+                        f.parameters.to_string(),
+                    ]
+                })
+                .collect();
+            output.push_str(&html::table(
+                &["Failed member", "Error", "Retried", "Parameters"],
+                &failures,
+            ));
+        }
+        output.push_str("</div>");
+        output
+    }
+}
+
+/// Spread of the final value of a variable across the members of an ensemble
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableStatistics {
+    pub name: String,
+    pub units: String,
+    /// Number of members with a valid final value
+    pub members: usize,
+    pub min: FloatValue,
+    pub median: FloatValue,
+    pub max: FloatValue,
+}
+
+/// Build and run a single model
+///
+/// Panics within components are converted into errors.
+fn run_member(builder: &ModelBuilder) -> RSCMResult<TimeseriesCollection> {
+    catch_unwind(AssertUnwindSafe(|| {
+        let mut model = builder.build();
+        model.try_run()?;
+        Ok(model.timeseries().clone())
+    }))
+    .unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_else(|| "Unknown panic".to_string());
+        Err(RSCMError::Error(format!("Panicked: {}", message)))
+    })
+}
+
+/// Parameters of an ensemble member, keyed by component name and then parameter name
+pub type MemberParameters = BTreeMap<String, BTreeMap<String, FloatValue>>;
+
+/// Read a table of pre-sampled parameters with a row per ensemble member
+///
+/// Each column is named `component.parameter`, e.g. `temperature.lambda0`,
+/// so that ensembles calibrated with other tools can be reused directly.
+/// The resulting members can be passed to [`run_ensemble`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rscm_core::ensemble::read_parameter_table;
+///
+/// let csv = "temperature.lambda0,temperature.eta\n1.1,0.7\n0.9,0.6\n";
+/// let members = read_parameter_table(csv).unwrap();
+/// assert_eq!(members.len(), 2);
+/// assert_eq!(members[1]["temperature"]["eta"], 0.6);
+/// ```
+pub fn read_parameter_table(csv: &str) -> RSCMResult<Vec<MemberParameters>> {
+    let mut lines = csv
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| RSCMError::Error("Empty parameter table".to_string()))?;
+    let columns = split_columns(header)
+        .into_iter()
+        .map(|column| {
+            column
+                .rsplit_once('.')
+                .map(|(component, parameter)| (component.to_string(), parameter.to_string()))
+                .ok_or_else(|| {
+                    RSCMError::Error(format!(
+                        "Column {:?} should be named component.parameter",
+                        column
+                    ))
+                })
+        })
+        .collect::<RSCMResult<Vec<_>>>()?;
+
+    lines
+        .map(|(index, line)| {
+            let values = split_columns(line);
+            if values.len() != columns.len() {
+                return Err(RSCMError::Error(format!(
+                    "Expected {} columns on line {}, found {}",
+                    columns.len(),
+                    index + 1,
+                    values.len()
+                )));
+            }
+            let mut member = MemberParameters::new();
+            for ((component, parameter), value) in columns.iter().zip(values) {
+                let value = value.parse::<FloatValue>().map_err(|_| {
+                    RSCMError::Error(format!(
+                        "Could not parse {:?} as a number on line {}",
+                        value,
+                        index + 1
+                    ))
+                })?;
+                member
+                    .entry(component.clone())
+                    .or_default()
+                    .insert(parameter.clone(), value);
+            }
+            Ok(member)
+        })
+        .collect()
+}
+
+/// Read a table of pre-sampled parameters from a file
+///
+/// Only CSV files are currently supported (see [`read_parameter_table`]).
+pub fn load_parameter_table(path: impl AsRef<Path>) -> RSCMResult<Vec<MemberParameters>> {
+    let path = path.as_ref();
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if !extension.eq_ignore_ascii_case("csv") {
+        return Err(RSCMError::Error(format!(
+            "Unsupported parameter table format for {}, expected a CSV file",
+            path.display()
+        )));
+    }
+    let csv = std::fs::read_to_string(path).map_err(|e| {
+        RSCMError::Error(format!(
+            "Could not read parameter table {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    read_parameter_table(&csv)
+}
+
+/// Run a model for each set of parameters in `members`
+///
+/// `build` creates the model builder for a set of parameters.
+/// Failures are handled according to `policy`.
+/// With [`FailurePolicy::AbortAll`], an error is returned for the first failure.
+/// Each model is given the index of its member (see [`ModelBuilder::with_ensemble_member`])
+/// so that stochastic components draw different random numbers in each member.
+pub fn run_ensemble<P, F>(
+    members: &[P],
+    build: F,
+    policy: &FailurePolicy,
+) -> RSCMResult<EnsembleResult>
+where
+    P: Serialize,
+    F: Fn(&P) -> ModelBuilder,
+{
+    let mut results = vec![];
+    let mut failures = vec![];
+
+    for (index, parameters) in members.iter().enumerate() {
+        let mut builder = build(parameters);
+        builder.with_ensemble_member(index as u64);
+
+        let (result, retried) = match (run_member(&builder), policy) {
+            (Ok(collection), _) => (Ok(collection), false),
+            (Err(err), FailurePolicy::AbortAll) => {
+                return Err(RSCMError::Error(format!(
+                    "Ensemble member {} failed: {}",
+                    index, err
+                )))
+            }
+            (Err(err), FailurePolicy::SkipAndRecord) => (Err(err), false),
+            (Err(_), FailurePolicy::RetryWithRefinement(options)) => {
+                let mut builder = builder.clone();
+                builder.with_stability_options(options.clone());
+                (run_member(&builder), true)
+            }
+        };
+
+        match result {
+            Ok(collection) => results.push((index, collection)),
+            Err(err) => failures.push(MemberFailure {
+                index,
+                parameters: toml::Value::try_from(parameters).map_err(|e| {
+                    RSCMError::Error(format!("Could not serialise parameters: {}", e))
+                })?,
+                error: err.to_string(),
+                retried,
+            }),
+        }
+    }
+
+    Ok(EnsembleResult { results, failures })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_components::{TestNoiseComponent, TestStiffComponent};
+    use crate::timeseries::TimeAxis;
+    use numpy::ndarray::Array;
+    use std::sync::Arc;
+
+    #[derive(Serialize)]
+    struct Parameters {
+        k: f64,
+    }
+
+    fn build(parameters: &Parameters) -> ModelBuilder {
+        let mut builder = ModelBuilder::new();
+        builder
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0)))
+            .with_component(Arc::new(TestStiffComponent { k: parameters.k }))
+            .with_stability_options(StabilityOptions {
+                max_relative_change: 2.0,
+                absolute_tolerance: 0.0,
+                max_refinements: 0,
+            });
+        builder
+    }
+
+    // Explicit Euler is unstable when k > 20 with the default solver step
+    fn members() -> Vec<Parameters> {
+        vec![
+            Parameters { k: 1.0 },
+            Parameters { k: 1e200 },
+            Parameters { k: 5.0 },
+        ]
+    }
+
+    #[test]
+    fn parameter_table() {
+        let csv = "stiff.k,other.x\n1.0,0.0\n1e200,0.0\n5.0,0.0\n";
+        let members = read_parameter_table(csv).unwrap();
+        assert_eq!(members.len(), 3);
+        assert_eq!(members[2]["stiff"]["k"], 5.0);
+
+        let res = run_ensemble(
+            &members,
+            |member| {
+                build(&Parameters {
+                    k: member["stiff"]["k"],
+                })
+            },
+            &FailurePolicy::SkipAndRecord,
+        )
+        .unwrap();
+        assert_eq!(res.failures[0].index, 1);
+        assert_eq!(
+            res.failures[0].parameters["stiff"]["k"].as_float(),
+            Some(1e200)
+        );
+
+        let res = read_parameter_table("k\n1.0\n");
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Column \"k\" should be named component.parameter"
+        );
+        assert!(read_parameter_table("stiff.k\nfast\n").is_err());
+        assert!(load_parameter_table("parameters.parquet").is_err());
+    }
+
+    #[test]
+    fn abort_all() {
+        let res = run_ensemble(&members(), build, &FailurePolicy::AbortAll);
+        assert!(res
+            .err()
+            .unwrap()
+            .to_string()
+            .starts_with("Ensemble member 1 failed"));
+    }
+
+    #[test]
+    fn skip_and_record() {
+        let res = run_ensemble(&members(), build, &FailurePolicy::SkipAndRecord).unwrap();
+
+        assert_eq!(
+            res.results.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            [0, 2]
+        );
+        assert_eq!(res.failures.len(), 1);
+        assert_eq!(res.failures[0].index, 1);
+        assert!(!res.failures[0].retried);
+        assert_eq!(res.failures[0].parameters["k"].as_float(), Some(1e200));
+        assert!(res.failure_report().starts_with("1 of 3 members failed"));
+    }
+
+    #[test]
+    fn statistics() {
+        let members = vec![
+            Parameters { k: 1.0 },
+            Parameters { k: 1e200 },
+            Parameters { k: 5.0 },
+            Parameters { k: 3.0 },
+        ];
+        let res = run_ensemble(&members, build, &FailurePolicy::SkipAndRecord).unwrap();
+
+        let statistics = res.statistics();
+        assert_eq!(statistics.len(), 1);
+        let state = &statistics[0];
+        assert_eq!((state.name.as_str(), state.members), ("State", 3));
+        let finals: Vec<FloatValue> = res
+            .results
+            .iter()
+            .map(|(_, c)| {
+                c.get_timeseries_by_name("State")
+                    .unwrap()
+                    .latest_value()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(state.median, finals[2]);
+        assert!(state.min <= state.median && state.median <= state.max);
+
+        let html = res.to_html();
+        assert!(html.contains("3 of 4 members succeeded"));
+        assert!(html.contains("<td>State</td><td>dimensionless</td><td>3</td>"));
+        assert!(html.contains("<th>Failed member</th>"));
+    }
+
+    #[test]
+    fn member_noise() {
+        let build = |_: &Parameters| {
+            let mut builder = ModelBuilder::new();
+            builder
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0)))
+                .with_component(Arc::new(TestNoiseComponent {
+                    name: "Noise".to_string(),
+                }))
+                .with_rng_seed(7);
+            builder
+        };
+        let noise = |res: &EnsembleResult, member: usize| {
+            res.results[member]
+                .1
+                .get_timeseries_by_name("Noise")
+                .unwrap()
+                .latest_value()
+                .unwrap()
+        };
+
+        // Each member draws different random numbers, but repeated ensembles are identical
+        let first = run_ensemble(&members(), build, &FailurePolicy::AbortAll).unwrap();
+        let second = run_ensemble(&members(), build, &FailurePolicy::AbortAll).unwrap();
+        assert_ne!(noise(&first, 0), noise(&first, 1));
+        assert_eq!(noise(&first, 1), noise(&second, 1));
+    }
+
+    #[test]
+    fn retry_with_refinement() {
+        let members = vec![Parameters { k: 30.0 }, Parameters { k: 1e200 }];
+        let policy = FailurePolicy::RetryWithRefinement(StabilityOptions {
+            max_relative_change: 2.0,
+            absolute_tolerance: 0.0,
+            max_refinements: 3,
+        });
+        let res = run_ensemble(&members, build, &policy).unwrap();
+
+        assert_eq!(res.results.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [0]);
+        assert_eq!(res.failures.len(), 1);
+        assert!(res.failures[0].retried);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::timeseries::Time;
 use thiserror::Error;
 
 /// Error type for invalid operations.
@@ -6,9 +7,76 @@ pub enum RSCMError {
     #[error("{0}")]
     Error(String),
     #[error("Extrapolation is not allowed. Target={0}, {1} interpolation range={2}")]
-    ExtrapolationNotAllowed(f32, String, f32),
+    ExtrapolationNotAllowed(Time, String, Time),
     #[error("Wrong input units. Expected {0}, got {1}")]
     WrongUnits(String, String),
+    #[error("Time values must be strictly increasing, got {0} followed by {1}")]
+    NonMonotonicTime(Time, Time),
+    #[error("Index {0} is out of bounds for a length of {1}")]
+    IndexOutOfBounds(usize, usize),
+    #[error("Timeseries {0} already exists")]
+    TimeseriesAlreadyExists(String),
+    #[error("Timeseries {0} not found")]
+    TimeseriesNotFound(String),
+    #[error("Run limit exceeded: {0}")]
+    LimitExceeded(String),
+    #[error("{component} failed to solve at {time} (time index {time_index}): {source}")]
+    ComponentFailed {
+        component: String,
+        time_index: usize,
+        time: Time,
+        source: Box<RSCMError>,
+    },
+}
+
+/// A problem which prevents a model from being built
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum BuildProblem {
+    /// A variable required by a component isn't solved by any component or provided as data
+    #[error("No exogenous data for {0}")]
+    MissingExogenousData(String),
+    /// A state variable has no initial value
+    #[error("No initial value for {0}")]
+    Uninitialised(String),
+    /// Exogenous data has units which can't be converted to the units of the variable
+    #[error("Exogenous data for {variable}: {message}")]
+    IncompatibleUnits { variable: String, message: String },
+    /// Components use units for a variable which can't be converted into each other
+    #[error("Inconsistent units for {variable}: {message}")]
+    InconsistentUnits { variable: String, message: String },
+    /// Exogenous data can't be interpolated onto the model's time axis,
+    /// e.g. if the data don't cover the time axis and extrapolation isn't allowed
+    #[error(
+        "Exogenous data for {variable} can't be interpolated onto the model's time axis: {message}"
+    )]
+    InvalidExogenousData { variable: String, message: String },
+    /// More than one component was registered with the same name
+    #[error("Multiple components named '{0}'")]
+    DuplicateComponentName(String),
+    /// Components depend upon each other, so there is no order in which they can be solved
+    #[error("Component graph contains a cycle")]
+    CyclicGraph,
+    /// A mass balance check uses variables which aren't in the model
+    /// or which have units that can't be converted
+    #[error("Invalid mass balance check {check}: {message}")]
+    InvalidMassBalanceCheck { check: String, message: String },
+}
+
+/// Error returned by [`crate::model::ModelBuilder::try_build`]
+///
+/// All the problems found while building the model are reported together.
+#[derive(Error, Debug)]
+#[error("Model can't be built: {}", describe_problems(.problems))]
+pub struct BuildError {
+    pub problems: Vec<BuildProblem>,
+}
+
+fn describe_problems(problems: &[BuildProblem]) -> String {
+    problems
+        .iter()
+        .map(BuildProblem::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 /// Convenience type for `Result<T, EosError>`.
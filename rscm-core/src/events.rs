@@ -0,0 +1,109 @@
+//! Events describing the lifecycle of a model
+//!
+//! A [`ModelEvent`] is emitted as a model is built, as each step is completed,
+//! when a component fails and when the run reaches the end of its time axis.
+//! Each event is logged using the `log` crate at the debug level
+//! and passed to any [`ModelObserver`]s registered using
+//! [`crate::model::ModelBuilder::with_observer`] or [`crate::model::Model::add_observer`],
+//! so logging, progress reporting and caching can all consume the same events.
+//!
+//! [`EventRecorder`] keeps a copy of each event that it receives.
+use crate::timeseries::Time;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::sync::{Arc, Mutex};
+
+/// An event in the lifecycle of a model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum ModelEvent {
+    /// A model is being built
+    BuildStarted {
+        /// Names of the enabled components
+        components: Vec<String>,
+    },
+    /// A time step has been solved
+    StepCompleted {
+        /// Index of the time at the end of the step
+        time_index: usize,
+        /// Time at the end of the step
+        time: Time,
+    },
+    /// A component failed to solve a time step
+    ComponentFailed {
+        /// Name of the component
+        component: String,
+        /// Index of the time at the start of the step
+        time_index: usize,
+        /// Time at the start of the step
+        time: Time,
+        message: String,
+    },
+    /// The model has reached the end of its time axis
+    RunFinished {
+        /// Last time of the time axis
+        time: Time,
+    },
+}
+
+impl Display for ModelEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelEvent::BuildStarted { components } => {
+                write!(f, "Building model with components {:?}", components)
+            }
+            ModelEvent::StepCompleted { time_index, time } => {
+                write!(f, "Completed step to {} (index {})", time, time_index)
+            }
+            ModelEvent::ComponentFailed {
+                component,
+                time,
+                message,
+                ..
+            } => write!(f, "{} failed to solve at {}: {}", component, time, message),
+            ModelEvent::RunFinished { time } => write!(f, "Run finished at {}", time),
+        }
+    }
+}
+
+/// Receives the events of a model
+///
+/// Observers are shared between a builder and the models that it builds,
+/// so any state must use interior mutability.
+/// Observers aren't serialised with the model.
+pub trait ModelObserver: Debug + Send + Sync {
+    fn notify(&self, event: &ModelEvent);
+}
+
+/// Log an event and pass it to each observer
+pub(crate) fn emit(observers: &[Arc<dyn ModelObserver>], event: ModelEvent) {
+    log::debug!("{}", event);
+    observers
+        .iter()
+        .for_each(|observer| observer.notify(&event));
+}
+
+/// Keeps a copy of each event
+///
+/// Clones share the same events.
+#[derive(Debug, Clone, Default)]
+pub struct EventRecorder {
+    events: Arc<Mutex<Vec<ModelEvent>>>,
+}
+
+impl EventRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The events received so far
+    pub fn events(&self) -> Vec<ModelEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl ModelObserver for EventRecorder {
+    fn notify(&self, event: &ModelEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
@@ -1,12 +1,40 @@
 #![allow(dead_code)]
 
+use crate::calibration::ParameterSet;
 use crate::component::{
     Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+    TimestepContext,
 };
-use crate::errors::RSCMResult;
-use crate::timeseries::{FloatValue, Time};
+use crate::dual::{Dual, DualState};
+use crate::errors::{RSCMError, RSCMResult};
+use crate::ivp::{LinearIVP, LinearStepper};
+use crate::model::ModelBuilder;
+use crate::timeseries::{DisaggregationStrategy, FloatValue, Time, TimeAxis, Timeseries};
 use crate::timeseries_collection::TimeseriesCollection;
+use nalgebra::{dmatrix, dvector, DMatrix};
+use numpy::array;
+use numpy::ndarray::Array;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Model containing a [`TestComponent`] with constant emissions from 2020 to 2024
+///
+/// The parameter `p` of the component is taken from `parameters`,
+/// for use as the builder of calibration and sampling tests.
+pub(crate) fn build_test_model(parameters: &ParameterSet) -> ModelBuilder {
+    let mut builder = ModelBuilder::new();
+    builder
+        .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+        .with_component(Arc::new(TestComponent::from_parameters(
+            TestComponentParameters { p: parameters["p"] },
+        )))
+        .with_exogenous_variable(
+            "Emissions|CO2",
+            Timeseries::from_values(array![10.0, 10.0], array![2000.0, 2100.0]),
+        );
+    builder
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct TestComponentParameters {
@@ -29,7 +57,8 @@ impl Component for TestComponent {
     fn definitions(&self) -> Vec<RequirementDefinition> {
         vec![
             RequirementDefinition::new("Emissions|CO2", "GtCO2", RequirementType::Input),
-            RequirementDefinition::new("Concentrations|CO2", "ppm", RequirementType::Output),
+            RequirementDefinition::new("Concentrations|CO2", "ppm", RequirementType::Output)
+                .with_bounds(Some(0.0), None),
         ]
     }
 
@@ -51,4 +80,422 @@ impl Component for TestComponent {
             self.output_names(),
         ))
     }
+
+    fn solve_dual(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        input_state: &DualState,
+        parameter: &str,
+    ) -> Option<RSCMResult<DualState>> {
+        let p = Dual::parameter(self.parameters.p, "p", parameter);
+        Some(Ok(DualState::from([(
+            "Concentrations|CO2".to_string(),
+            input_state["Emissions|CO2"] * p,
+        )])))
+    }
+}
+
+/// Component with an optional input
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TestOptionalInputComponent {}
+
+#[typetag::serde]
+impl Component for TestOptionalInputComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::optional("Effective Radiative Forcing|Aerosols", "W/m^2", 0.0),
+            RequirementDefinition::new(
+                "Effective Radiative Forcing|Total",
+                "W/m^2",
+                RequirementType::Output,
+            ),
+        ]
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let erf = input_state.get("Effective Radiative Forcing|Aerosols");
+
+        Ok(OutputState::from_vectors(vec![*erf], self.output_names()))
+    }
+}
+
+/// Component which uses different units to [`TestOptionalInputComponent`]
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TestUnitsComponent {}
+
+#[typetag::serde]
+impl Component for TestUnitsComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new(
+                "Effective Radiative Forcing|Total",
+                "mW / m^2",
+                RequirementType::Input,
+            ),
+            RequirementDefinition::new(
+                "Effective Radiative Forcing|Doubled",
+                "mW / m^2",
+                RequirementType::Output,
+            ),
+        ]
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let erf = input_state.get("Effective Radiative Forcing|Total");
+
+        Ok(OutputState::from_vectors(
+            vec![2.0 * erf],
+            self.output_names(),
+        ))
+    }
+}
+
+/// Component which accumulates emissions that are totals over each time step
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TestCumulativeComponent {}
+
+#[typetag::serde]
+impl Component for TestCumulativeComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new("Emissions|CO2", "GtC", RequirementType::Input)
+                .with_disaggregation(DisaggregationStrategy::Split),
+            RequirementDefinition::new(
+                "Cumulative Emissions|CO2",
+                "GtC",
+                RequirementType::InputAndOutput,
+            )
+            .with_default(0.0),
+        ]
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let cumulative =
+            input_state.get("Cumulative Emissions|CO2") + input_state.get("Emissions|CO2");
+        Ok(OutputState::from_vectors(
+            vec![cumulative],
+            self.output_names(),
+        ))
+    }
+}
+
+/// Component which accumulates emissions and partitions them between the atmosphere and ocean
+///
+/// A fraction `leak` of the emissions isn't stored anywhere.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TestCarbonPoolsComponent {
+    pub leak: FloatValue,
+}
+
+#[typetag::serde]
+impl Component for TestCarbonPoolsComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new("Emissions|CO2", "GtC", RequirementType::Input)
+                .with_disaggregation(DisaggregationStrategy::Split),
+            RequirementDefinition::new(
+                "Cumulative Emissions|CO2",
+                "GtC",
+                RequirementType::InputAndOutput,
+            )
+            .with_default(0.0),
+            RequirementDefinition::new("Carbon|Atmosphere", "GtC", RequirementType::InputAndOutput)
+                .with_default(0.0),
+            RequirementDefinition::new("Carbon|Ocean", "MtC", RequirementType::InputAndOutput)
+                .with_default(0.0),
+        ]
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let emissions = input_state.get("Emissions|CO2");
+        let stored = emissions * (1.0 - self.leak);
+        Ok(OutputState::from_vectors(
+            vec![
+                input_state.get("Cumulative Emissions|CO2") + emissions,
+                input_state.get("Carbon|Atmosphere") + 0.6 * stored,
+                input_state.get("Carbon|Ocean") + 0.4 * stored * 1000.0,
+            ],
+            self.output_names(),
+        ))
+    }
+}
+
+/// Component which accumulates concentrations into a stock that decays exponentially
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TestDecayComponent {
+    pub tau: FloatValue,
+}
+
+#[typetag::serde]
+impl Component for TestDecayComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new("Concentrations|CO2", "ppm", RequirementType::Input),
+            RequirementDefinition::new("Stock", "ppm yr", RequirementType::InputAndOutput)
+                .with_default(0.0),
+        ]
+    }
+
+    fn solve(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let dt = t_next - t_current;
+        let stock = input_state.get("Stock") * (-dt / self.tau).exp()
+            + input_state.get("Concentrations|CO2") * dt;
+        Ok(OutputState::from_vectors(vec![stock], self.output_names()))
+    }
+
+    fn solve_dual(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &DualState,
+        parameter: &str,
+    ) -> Option<RSCMResult<DualState>> {
+        let dt = t_next - t_current;
+        let tau = Dual::parameter(self.tau, "tau", parameter);
+        let stock =
+            input_state["Stock"] * (-dt / tau).exp() + input_state["Concentrations|CO2"] * dt;
+        Some(Ok(DualState::from([("Stock".to_string(), stock)])))
+    }
+}
+
+/// Component whose state relaxes towards an equilibrium level
+///
+/// The state is initialised at the equilibrium.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TestEquilibriumComponent {
+    pub equilibrium: FloatValue,
+}
+
+#[typetag::serde]
+impl Component for TestEquilibriumComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![RequirementDefinition::new(
+            "Level",
+            "m",
+            RequirementType::InputAndOutput,
+        )]
+    }
+
+    fn initialise(&self, _t0: Time) -> OutputState {
+        OutputState::from_vectors(vec![self.equilibrium], self.output_names())
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let level = input_state.get("Level");
+        Ok(OutputState::from_vectors(
+            vec![level + 0.5 * (self.equilibrium - level)],
+            self.output_names(),
+        ))
+    }
+}
+
+/// Component that is unstable unless the internal solver step is refined
+///
+/// Solves `dy/dt = -k y` using an explicit Euler scheme with the model's solver step.
+/// With the default step of 0.1, this is unstable for `k > 20`.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TestStiffComponent {
+    pub k: FloatValue,
+}
+
+#[typetag::serde]
+impl Component for TestStiffComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new("State", "dimensionless", RequirementType::InputAndOutput)
+                .with_default(1.0),
+        ]
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        _input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        Err(RSCMError::Error(
+            "TestStiffComponent requires the solver step of a TimestepContext".to_string(),
+        ))
+    }
+
+    fn solve_with_context(
+        &self,
+        context: &mut TimestepContext,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let step_size = context.solver_step_size(context.options().solver_step);
+        let n_steps = ((context.t_next() - context.t_current()) / step_size).round() as i32;
+
+        let y = input_state.get("State") * (1.0 - self.k * step_size).powi(n_steps);
+        Ok(OutputState::from_vectors(vec![y], self.output_names()))
+    }
+}
+
+/// Component which produces random noise
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TestNoiseComponent {
+    pub name: String,
+}
+
+#[typetag::serde]
+impl Component for TestNoiseComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![RequirementDefinition::new(
+            &self.name,
+            "dimensionless",
+            RequirementType::Output,
+        )]
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        _input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        Err(RSCMError::Error(
+            "TestNoiseComponent requires the random numbers of a TimestepContext".to_string(),
+        ))
+    }
+
+    fn solve_with_context(
+        &self,
+        context: &mut TimestepContext,
+        _input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let noise: FloatValue = context.rng().gen();
+        Ok(OutputState::from_vectors(vec![noise], self.output_names()))
+    }
+}
+
+/// Component which outputs one more than the sum of its inputs
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TestSumComponent {
+    pub inputs: Vec<String>,
+    pub output: String,
+}
+
+#[typetag::serde]
+impl Component for TestSumComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        let mut definitions: Vec<RequirementDefinition> = self
+            .inputs
+            .iter()
+            .map(|name| RequirementDefinition::new(name, "dimensionless", RequirementType::Input))
+            .collect();
+        definitions.push(RequirementDefinition::new(
+            &self.output,
+            "dimensionless",
+            RequirementType::Output,
+        ));
+        definitions
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let total: FloatValue = input_state.iter().map(|(_, value)| value).sum();
+        Ok(OutputState::from_vectors(
+            vec![total + 1.0],
+            self.output_names(),
+        ))
+    }
+}
+
+/// Linear two-layer energy balance model which is stepped exactly
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TestLinearComponent {
+    pub lambda: FloatValue,
+    pub eta: FloatValue,
+    pub heat_capacity_surface: FloatValue,
+    pub heat_capacity_deep: FloatValue,
+    #[serde(skip)]
+    pub stepper: LinearStepper,
+}
+
+impl LinearIVP for TestLinearComponent {
+    fn state_matrix(&self) -> DMatrix<FloatValue> {
+        dmatrix![
+            -(self.lambda + self.eta) / self.heat_capacity_surface, self.eta / self.heat_capacity_surface;
+            self.eta / self.heat_capacity_deep, -self.eta / self.heat_capacity_deep
+        ]
+    }
+
+    fn input_matrix(&self) -> DMatrix<FloatValue> {
+        dmatrix![1.0 / self.heat_capacity_surface; 0.0]
+    }
+}
+
+#[typetag::serde]
+impl Component for TestLinearComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new(
+                "Effective Radiative Forcing",
+                "W/m^2",
+                RequirementType::Input,
+            ),
+            RequirementDefinition::new("Surface Temperature", "K", RequirementType::InputAndOutput)
+                .with_default(0.0),
+            RequirementDefinition::new(
+                "Deep Ocean Temperature",
+                "K",
+                RequirementType::InputAndOutput,
+            )
+            .with_default(0.0),
+        ]
+    }
+
+    fn solve(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let y = dvector![
+            *input_state.get("Surface Temperature"),
+            *input_state.get("Deep Ocean Temperature")
+        ];
+        let u = dvector![*input_state.get("Effective Radiative Forcing")];
+
+        let y_next = self.stepper.step(self, &y, &u, t_next - t_current);
+        Ok(OutputState::from_vectors(
+            vec![y_next[0], y_next[1]],
+            self.output_names(),
+        ))
+    }
 }
@@ -0,0 +1,321 @@
+//! Threshold exceedance and overshoot statistics of ensembles
+//!
+//! Projections are often summarised by when warming first exceeds a threshold such as 1.5 K,
+//! how high it peaks and how long it stays above the threshold before returning below it.
+//! [`member_exceedance`] calculates these for a single timeseries and
+//! [`exceedance_statistics`] combines them across the members of an ensemble.
+//!
+//! The aggregate statistics are returned as sorted samples ([`Distribution`]),
+//! which can be plotted directly as histograms or empirical CDFs.
+//! All of the types are serialisable.
+//!
+//! A value exceeds a threshold if it is strictly greater than the threshold.
+//! NaN values are ignored.
+use crate::ensemble::EnsembleResult;
+use crate::errors::{RSCMError, RSCMResult};
+use crate::timeseries::{FloatValue, Time, Timeseries};
+use serde::{Deserialize, Serialize};
+
+/// Exceedance of a single threshold by a timeseries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdExceedance {
+    pub threshold: FloatValue,
+    /// Time of the first value above the threshold
+    pub first_exceedance: Option<Time>,
+    /// Time of the first value at or below the threshold after the last exceedance
+    ///
+    /// `None` if the threshold was never exceeded or the timeseries ends above it.
+    pub return_time: Option<Time>,
+    /// Total length of the time steps with values above the threshold
+    pub overshoot_duration: Time,
+    /// Peak value minus the threshold, or zero if the threshold wasn't exceeded
+    pub overshoot_magnitude: FloatValue,
+}
+
+/// Exceedance statistics of a single ensemble member
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberExceedance {
+    /// Index of the member in the ensemble
+    pub index: usize,
+    pub peak: FloatValue,
+    pub peak_time: Time,
+    /// Exceedance of each threshold in the order they were requested
+    pub thresholds: Vec<ThresholdExceedance>,
+}
+
+/// Sorted samples of a quantity across an ensemble
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Distribution {
+    /// Values in ascending order
+    pub values: Vec<FloatValue>,
+}
+
+impl Distribution {
+    /// Create a distribution from unsorted values
+    ///
+    /// NaN values are dropped.
+    pub fn new(values: impl IntoIterator<Item = FloatValue>) -> Self {
+        let mut values: Vec<FloatValue> = values.into_iter().filter(|v| !v.is_nan()).collect();
+        values.sort_by(|a, b| a.total_cmp(b));
+        Self { values }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Quantile using linear interpolation between the samples
+    ///
+    /// Returns `None` if the distribution is empty or `q` isn't between 0 and 1.
+    pub fn quantile(&self, q: FloatValue) -> Option<FloatValue> {
+        if self.values.is_empty() || !(0.0..=1.0).contains(&q) {
+            return None;
+        }
+        let position = q * (self.values.len() - 1) as FloatValue;
+        let lower = position.floor() as usize;
+        let upper = position.ceil() as usize;
+        let weight = position - lower as FloatValue;
+        Some(self.values[lower] + weight * (self.values[upper] - self.values[lower]))
+    }
+
+    pub fn median(&self) -> Option<FloatValue> {
+        self.quantile(0.5)
+    }
+}
+
+/// Exceedance of a threshold across an ensemble
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdStatistics {
+    pub threshold: FloatValue,
+    /// Fraction of members which exceeded the threshold
+    pub probability: FloatValue,
+    /// Fraction of the members which exceeded the threshold that returned below it
+    ///
+    /// NaN if no members exceeded the threshold.
+    pub return_probability: FloatValue,
+    /// Time of first exceedance of the members which exceeded the threshold
+    pub first_exceedance: Distribution,
+    /// Overshoot duration of the members which exceeded the threshold
+    pub overshoot_duration: Distribution,
+    /// Overshoot magnitude of the members which exceeded the threshold
+    pub overshoot_magnitude: Distribution,
+}
+
+/// Exceedance statistics of a variable across an ensemble
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExceedanceStatistics {
+    pub variable: String,
+    pub units: String,
+    /// Statistics of each member with at least one valid value
+    pub members: Vec<MemberExceedance>,
+    pub peak: Distribution,
+    pub peak_time: Distribution,
+    /// Statistics of each threshold in the order they were requested
+    pub thresholds: Vec<ThresholdStatistics>,
+}
+
+/// Exceedance statistics of a single timeseries
+///
+/// Returns `None` if the timeseries doesn't contain any valid values.
+pub fn member_exceedance(
+    index: usize,
+    timeseries: &Timeseries<FloatValue>,
+    thresholds: &[FloatValue],
+) -> Option<MemberExceedance> {
+    let time_axis = timeseries.time_axis();
+    // (time, step length, value) of each valid value
+    let points: Vec<(Time, Time, FloatValue)> = timeseries
+        .values()
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| !value.is_nan())
+        .map(|(i, value)| {
+            let (start, end) = time_axis.at_bounds(i).unwrap();
+            (start, end - start, *value)
+        })
+        .collect();
+
+    let (peak_time, _, peak) = *points.iter().reduce(|best, point| match point.2 > best.2 {
+        true => point,
+        false => best,
+    })?;
+
+    let thresholds = thresholds
+        .iter()
+        .map(|threshold| {
+            let above = |point: &&(Time, Time, FloatValue)| point.2 > *threshold;
+            let last_exceedance = points.iter().rposition(|p| above(&p));
+            ThresholdExceedance {
+                threshold: *threshold,
+                first_exceedance: points.iter().find(above).map(|p| p.0),
+                return_time: last_exceedance.and_then(|i| points.get(i + 1)).map(|p| p.0),
+                overshoot_duration: points.iter().filter(above).map(|p| p.1).sum(),
+                overshoot_magnitude: (peak - threshold).max(0.0),
+            }
+        })
+        .collect();
+
+    Some(MemberExceedance {
+        index,
+        peak,
+        peak_time,
+        thresholds,
+    })
+}
+
+/// Exceedance statistics of a variable across the successful members of an ensemble
+///
+/// Members without any valid values of the variable are excluded.
+/// Returns an error if the variable is missing from any member or the units differ
+/// between members.
+pub fn exceedance_statistics(
+    ensemble: &EnsembleResult,
+    variable: &str,
+    thresholds: &[FloatValue],
+) -> RSCMResult<ExceedanceStatistics> {
+    let mut units: Option<&str> = None;
+    let mut members = vec![];
+    for (index, collection) in ensemble.results.iter() {
+        let timeseries = collection.get_timeseries_by_name(variable).ok_or_else(|| {
+            RSCMError::TimeseriesNotFound(format!("'{}' in member {}", variable, index))
+        })?;
+        match units {
+            Some(units) if units != timeseries.units() => {
+                return Err(RSCMError::WrongUnits(
+                    units.to_string(),
+                    timeseries.units().to_string(),
+                ))
+            }
+            _ => units = Some(timeseries.units()),
+        }
+        members.extend(member_exceedance(*index, timeseries, thresholds));
+    }
+
+    let n = members.len() as FloatValue;
+    let thresholds = thresholds
+        .iter()
+        .enumerate()
+        .map(|(i, threshold)| {
+            let exceeded: Vec<&ThresholdExceedance> = members
+                .iter()
+                .map(|m| &m.thresholds[i])
+                .filter(|t| t.first_exceedance.is_some())
+                .collect();
+            let returned = exceeded.iter().filter(|t| t.return_time.is_some()).count();
+            ThresholdStatistics {
+                threshold: *threshold,
+                probability: exceeded.len() as FloatValue / n,
+                return_probability: returned as FloatValue / exceeded.len() as FloatValue,
+                first_exceedance: Distribution::new(
+                    exceeded.iter().filter_map(|t| t.first_exceedance),
+                ),
+                overshoot_duration: Distribution::new(
+                    exceeded.iter().map(|t| t.overshoot_duration),
+                ),
+                overshoot_magnitude: Distribution::new(
+                    exceeded.iter().map(|t| t.overshoot_magnitude),
+                ),
+            }
+        })
+        .collect();
+
+    Ok(ExceedanceStatistics {
+        variable: variable.to_string(),
+        units: units.unwrap_or_default().to_string(),
+        peak: Distribution::new(members.iter().map(|m| m.peak)),
+        peak_time: Distribution::new(members.iter().map(|m| m.peak_time)),
+        members,
+        thresholds,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeseries_collection::{TimeseriesCollection, VariableType};
+    use numpy::array;
+    use numpy::ndarray::Array1;
+
+    fn temperature(values: Array1<FloatValue>) -> Timeseries<FloatValue> {
+        let n = values.len();
+        Timeseries::from_values(values, Array1::range(2020.0, 2020.0 + n as Time, 1.0))
+    }
+
+    #[test]
+    fn member() {
+        let ts = temperature(array![1.2, 1.6, 1.9, FloatValue::NAN, 1.7, 1.4, 1.3]);
+        let member = member_exceedance(3, &ts, &[1.5, 2.0, 1.0]).unwrap();
+
+        assert_eq!(
+            (member.index, member.peak, member.peak_time),
+            (3, 1.9, 2022.0)
+        );
+        assert_eq!(
+            member.thresholds[0],
+            ThresholdExceedance {
+                threshold: 1.5,
+                first_exceedance: Some(2021.0),
+                return_time: Some(2025.0),
+                overshoot_duration: 3.0,
+                overshoot_magnitude: 1.9 - 1.5,
+            }
+        );
+        assert_eq!(member.thresholds[1].first_exceedance, None);
+        assert_eq!(member.thresholds[1].overshoot_magnitude, 0.0);
+        // Never returns below the threshold
+        assert_eq!(member.thresholds[2].return_time, None);
+        assert_eq!(member.thresholds[2].overshoot_duration, 6.0);
+
+        let empty = temperature(array![FloatValue::NAN, FloatValue::NAN]);
+        assert!(member_exceedance(0, &empty, &[1.5]).is_none());
+    }
+
+    #[test]
+    fn distribution() {
+        let distribution = Distribution::new([3.0, FloatValue::NAN, 1.0, 2.0, 4.0]);
+        assert_eq!(distribution.values, vec![1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(distribution.median(), Some(2.5));
+        assert_eq!(distribution.quantile(0.0), Some(1.0));
+        assert_eq!(distribution.quantile(1.0 / 3.0), Some(2.0));
+        assert_eq!(distribution.quantile(1.5), None);
+        assert_eq!(Distribution::default().median(), None);
+    }
+
+    #[test]
+    fn ensemble() {
+        let member = |values: Array1<FloatValue>| {
+            let mut collection = TimeseriesCollection::new();
+            collection
+                .add_timeseries(
+                    "Surface Temperature".to_string(),
+                    temperature(values),
+                    VariableType::Endogenous,
+                )
+                .unwrap();
+            collection
+        };
+        let ensemble = EnsembleResult {
+            results: vec![
+                (0, member(array![1.0, 1.6, 1.4])),
+                (2, member(array![1.0, 1.2, 1.3])),
+                (3, member(array![1.6, 1.8, 2.0])),
+            ],
+            failures: vec![],
+        };
+
+        let statistics = exceedance_statistics(&ensemble, "Surface Temperature", &[1.5]).unwrap();
+        assert_eq!(statistics.members.len(), 3);
+        assert_eq!(statistics.peak.values, vec![1.3, 1.6, 2.0]);
+        let threshold = &statistics.thresholds[0];
+        assert_eq!(threshold.probability, 2.0 / 3.0);
+        assert_eq!(threshold.return_probability, 0.5);
+        assert_eq!(threshold.first_exceedance.values, vec![2020.0, 2021.0]);
+        assert_eq!(threshold.overshoot_duration.values, vec![1.0, 3.0]);
+
+        assert!(exceedance_statistics(&ensemble, "Concentrations|CO2", &[1.5]).is_err());
+    }
+}
@@ -0,0 +1,570 @@
+//! Experiments composed of multiple dependent runs
+//!
+//! An [`Experiment`] is a set of named stages where each stage may depend on the results of
+//! other stages, for example spin-up → historical → a set of scenarios → diagnostics.
+//! Stages are executed once all of their dependencies have completed
+//! and independent stages are executed in parallel.
+//!
+//! The result of each stage is a [`StageResult`] which contains the state of the stage's model
+//! as a checkpoint.
+//! If a checkpoint directory is set, results are written to disk and stages which already
+//! have a checkpoint are not recomputed when the experiment is rerun.
+//!
+//! The stages of an experiment can be described using TOML (see [`ExperimentConfig`]),
+//! where the `kind` of each stage refers to a function registered by the caller.
+use crate::errors::{RSCMError, RSCMResult};
+use crate::model::Model;
+use crate::timeseries_collection::TimeseriesCollection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Result of a stage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageResult {
+    /// State of the model at the end of the stage, as written by [`Model::save_state`]
+    ///
+    /// Dependent stages can restore the model using [`StageResult::restore`].
+    pub checkpoint: Option<String>,
+    /// Timeseries produced by the stage
+    pub timeseries: TimeseriesCollection,
+}
+
+impl StageResult {
+    /// Create a result from the state of a model
+    pub fn from_model(model: &Model) -> RSCMResult<Self> {
+        Ok(Self {
+            checkpoint: Some(model.save_state()?),
+            timeseries: model.timeseries().clone(),
+        })
+    }
+
+    /// Create a result which only contains timeseries, e.g. for diagnostics
+    pub fn from_timeseries(timeseries: TimeseriesCollection) -> Self {
+        Self {
+            checkpoint: None,
+            timeseries,
+        }
+    }
+
+    /// Restore the model from the checkpoint
+    pub fn restore(&self) -> RSCMResult<Model> {
+        let checkpoint = self
+            .checkpoint
+            .as_ref()
+            .ok_or_else(|| RSCMError::Error("Stage result has no checkpoint".to_string()))?;
+        Model::load_state(checkpoint)
+    }
+}
+
+/// Information available to a stage when it is executed
+pub struct StageContext<'a> {
+    /// Name of the stage
+    pub name: &'a str,
+    /// Parameters of the stage from the experiment's configuration
+    pub parameters: &'a toml::Value,
+    dependencies: Vec<(&'a str, &'a StageResult)>,
+}
+
+impl StageContext<'_> {
+    /// Result of a stage that this stage depends on
+    pub fn dependency(&self, name: &str) -> RSCMResult<&StageResult> {
+        self.dependencies
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, result)| *result)
+            .ok_or_else(|| {
+                RSCMError::Error(format!(
+                    "Stage '{}' doesn't depend on '{}'",
+                    self.name, name
+                ))
+            })
+    }
+
+    /// Results of all of the dependencies in the order they were declared
+    pub fn dependencies(&self) -> impl Iterator<Item = &(&str, &StageResult)> {
+        self.dependencies.iter()
+    }
+}
+
+/// Function which executes a stage
+pub type StageFn = Arc<dyn Fn(&StageContext) -> RSCMResult<StageResult> + Send + Sync>;
+
+/// Configuration of a single stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageConfig {
+    pub name: String,
+    /// Name of the registered [`StageFn`] which executes the stage
+    pub kind: String,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Parameters which are passed to the stage via [`StageContext::parameters`]
+    #[serde(default = "empty_table")]
+    pub parameters: toml::Value,
+}
+
+fn empty_table() -> toml::Value {
+    toml::Value::Table(Default::default())
+}
+
+/// Configuration of an experiment
+///
+/// ```rust
+/// use rscm_core::experiment::ExperimentConfig;
+///
+/// let config = ExperimentConfig::from_toml(r#"
+/// threads = 2
+///
+/// [[stages]]
+/// name = "historical"
+/// kind = "historical"
+///
+/// [[stages]]
+/// name = "ssp126"
+/// kind = "scenario"
+/// depends_on = ["historical"]
+/// parameters = { scenario = "ssp126" }
+/// "#).unwrap();
+/// assert_eq!(config.stages.len(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    pub stages: Vec<StageConfig>,
+    /// Maximum number of stages which are executed at the same time
+    #[serde(default = "default_threads")]
+    pub threads: usize,
+    /// Directory where the results of each stage are stored
+    #[serde(default)]
+    pub checkpoint_dir: Option<PathBuf>,
+}
+
+fn default_threads() -> usize {
+    1
+}
+
+impl ExperimentConfig {
+    pub fn from_toml(config: &str) -> RSCMResult<Self> {
+        toml::from_str(config)
+            .map_err(|e| RSCMError::Error(format!("Invalid experiment configuration: {}", e)))
+    }
+}
+
+struct Stage {
+    config: StageConfig,
+    run: StageFn,
+}
+
+/// A set of stages with dependencies between them
+pub struct Experiment {
+    stages: Vec<Stage>,
+    threads: usize,
+    checkpoint_dir: Option<PathBuf>,
+}
+
+impl Default for Experiment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Experiment {
+    pub fn new() -> Self {
+        Self {
+            stages: vec![],
+            threads: 1,
+            checkpoint_dir: None,
+        }
+    }
+
+    /// Create an experiment from a configuration
+    ///
+    /// `kinds` maps the `kind` of each stage to the function which executes it.
+    pub fn from_config(
+        config: &ExperimentConfig,
+        kinds: &HashMap<String, StageFn>,
+    ) -> RSCMResult<Self> {
+        let mut experiment = Self::new();
+        experiment.with_threads(config.threads);
+        if let Some(dir) = &config.checkpoint_dir {
+            experiment.with_checkpoint_dir(dir);
+        }
+        for stage in config.stages.iter() {
+            let run = kinds.get(&stage.kind).ok_or_else(|| {
+                RSCMError::Error(format!(
+                    "Unknown kind '{}' for stage '{}'",
+                    stage.kind, stage.name
+                ))
+            })?;
+            experiment.add_stage(stage.clone(), run.clone())?;
+        }
+        Ok(experiment)
+    }
+
+    /// Add a stage
+    ///
+    /// Returns an error if a stage with the same name already exists.
+    pub fn add_stage(&mut self, config: StageConfig, run: StageFn) -> RSCMResult<&mut Self> {
+        if self.stages.iter().any(|s| s.config.name == config.name) {
+            return Err(RSCMError::Error(format!(
+                "Stage '{}' already exists",
+                config.name
+            )));
+        }
+        self.stages.push(Stage { config, run });
+        Ok(self)
+    }
+
+    /// Add a stage without any parameters
+    pub fn with_stage<F>(
+        &mut self,
+        name: &str,
+        depends_on: &[&str],
+        run: F,
+    ) -> RSCMResult<&mut Self>
+    where
+        F: Fn(&StageContext) -> RSCMResult<StageResult> + Send + Sync + 'static,
+    {
+        self.add_stage(
+            StageConfig {
+                name: name.to_string(),
+                kind: name.to_string(),
+                depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+                parameters: empty_table(),
+            },
+            Arc::new(run),
+        )
+    }
+
+    /// Set the maximum number of stages which are executed at the same time
+    pub fn with_threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Store the result of each stage in `dir`
+    ///
+    /// Stages which already have a result in `dir` are loaded instead of being executed.
+    pub fn with_checkpoint_dir(&mut self, dir: impl AsRef<Path>) -> &mut Self {
+        self.checkpoint_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    fn checkpoint_path(&self, name: &str) -> Option<PathBuf> {
+        self.checkpoint_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.toml", name)))
+    }
+
+    /// Load the result of a stage from the checkpoint directory if it exists
+    fn load_checkpoint(&self, name: &str) -> RSCMResult<Option<StageResult>> {
+        match self.checkpoint_path(name) {
+            Some(path) if path.exists() => {
+                let serialised = std::fs::read_to_string(&path).map_err(|e| {
+                    RSCMError::Error(format!("Could not read {}: {}", path.display(), e))
+                })?;
+                toml::from_str(&serialised).map(Some).map_err(|e| {
+                    RSCMError::Error(format!("Invalid checkpoint {}: {}", path.display(), e))
+                })
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn save_checkpoint(&self, name: &str, result: &StageResult) -> RSCMResult<()> {
+        if let Some(path) = self.checkpoint_path(name) {
+            let serialised = toml::to_string(result)
+                .map_err(|e| RSCMError::Error(format!("Could not serialise {}: {}", name, e)))?;
+            std::fs::create_dir_all(path.parent().unwrap())
+                .and_then(|_| std::fs::write(&path, serialised))
+                .map_err(|e| {
+                    RSCMError::Error(format!("Could not write {}: {}", path.display(), e))
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Check that every dependency exists and that there are no cycles
+    ///
+    /// Returns the stages grouped into waves where each wave only depends on earlier waves.
+    fn waves(&self) -> RSCMResult<Vec<Vec<usize>>> {
+        let names: Vec<&str> = self.stages.iter().map(|s| s.config.name.as_str()).collect();
+        for stage in self.stages.iter() {
+            if let Some(missing) = stage
+                .config
+                .depends_on
+                .iter()
+                .find(|d| !names.contains(&d.as_str()))
+            {
+                return Err(RSCMError::Error(format!(
+                    "Stage '{}' depends on unknown stage '{}'",
+                    stage.config.name, missing
+                )));
+            }
+        }
+
+        let mut done: Vec<&str> = vec![];
+        let mut waves = vec![];
+        while done.len() < self.stages.len() {
+            let wave: Vec<usize> = self
+                .stages
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| !done.contains(&s.config.name.as_str()))
+                .filter(|(_, s)| {
+                    s.config
+                        .depends_on
+                        .iter()
+                        .all(|d| done.contains(&d.as_str()))
+                })
+                .map(|(i, _)| i)
+                .collect();
+            if wave.is_empty() {
+                return Err(RSCMError::Error(
+                    "Experiment contains a cycle of dependencies".to_string(),
+                ));
+            }
+            done.extend(wave.iter().map(|i| names[*i]));
+            waves.push(wave);
+        }
+        Ok(waves)
+    }
+
+    fn run_stage(
+        &self,
+        stage: &Stage,
+        results: &HashMap<String, StageResult>,
+    ) -> RSCMResult<StageResult> {
+        if let Some(result) = self.load_checkpoint(&stage.config.name)? {
+            return Ok(result);
+        }
+
+        let context = StageContext {
+            name: &stage.config.name,
+            parameters: &stage.config.parameters,
+            dependencies: stage
+                .config
+                .depends_on
+                .iter()
+                .map(|d| (d.as_str(), &results[d]))
+                .collect(),
+        };
+        let result = (stage.run)(&context).map_err(|e| {
+            RSCMError::Error(format!("Stage '{}' failed: {}", stage.config.name, e))
+        })?;
+        self.save_checkpoint(&stage.config.name, &result)?;
+        Ok(result)
+    }
+
+    /// Execute every stage
+    ///
+    /// Returns the result of each stage, keyed by the name of the stage.
+    /// Execution stops after the first failed stage.
+    pub fn run(&self) -> RSCMResult<HashMap<String, StageResult>> {
+        let mut results: HashMap<String, StageResult> = HashMap::new();
+        let threads = self.threads.max(1);
+
+        for wave in self.waves()? {
+            for chunk in wave.chunks(threads) {
+                let chunk_results: Vec<RSCMResult<StageResult>> = std::thread::scope(|scope| {
+                    let results = &results;
+                    let handles: Vec<_> = chunk
+                        .iter()
+                        .map(|i| scope.spawn(move || self.run_stage(&self.stages[*i], results)))
+                        .collect();
+                    handles
+                        .into_iter()
+                        .map(|handle| handle.join().expect("Stage panicked"))
+                        .collect()
+                });
+                for (i, result) in chunk.iter().zip(chunk_results) {
+                    results.insert(self.stages[*i].config.name.clone(), result?);
+                }
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::InputState;
+    use crate::example_components::TestStiffComponent;
+    use crate::model::ModelBuilder;
+    use crate::timeseries::TimeAxis;
+    use crate::timeseries_collection::VariableType;
+    use is_close::is_close;
+    use numpy::ndarray::Array;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Run a model starting from the final state of a previous stage
+    fn run(k: f64, start: f64, initial: Option<&StageResult>) -> RSCMResult<StageResult> {
+        let mut builder = ModelBuilder::new();
+        builder
+            .with_time_axis(TimeAxis::from_values(Array::range(start, start + 3.0, 1.0)))
+            .with_component(Arc::new(TestStiffComponent { k }));
+        if let Some(initial) = initial {
+            let state = initial.timeseries.get_timeseries_by_name("State").unwrap();
+            builder.with_initial_values(InputState::from_vectors(
+                vec![state.latest_value().unwrap()],
+                vec!["State".to_string()],
+            ));
+        }
+        let mut model = builder.build();
+        model.try_run()?;
+        StageResult::from_model(&model)
+    }
+
+    fn final_state(result: &StageResult) -> f64 {
+        result
+            .timeseries
+            .get_timeseries_by_name("State")
+            .unwrap()
+            .latest_value()
+            .unwrap()
+    }
+
+    fn kinds(counter: Arc<AtomicUsize>) -> HashMap<String, StageFn> {
+        let mut kinds: HashMap<String, StageFn> = HashMap::new();
+        let spinup_counter = counter.clone();
+        kinds.insert(
+            "spinup".to_string(),
+            Arc::new(move |_| {
+                spinup_counter.fetch_add(1, Ordering::SeqCst);
+                run(1.0, 1750.0, None)
+            }),
+        );
+        kinds.insert(
+            "historical".to_string(),
+            Arc::new(|ctx| run(1.0, 1850.0, Some(ctx.dependency("spinup")?))),
+        );
+        kinds.insert(
+            "scenario".to_string(),
+            Arc::new(|ctx| {
+                let k = ctx.parameters["k"].as_float().unwrap();
+                run(k, 2020.0, Some(ctx.dependency("historical")?))
+            }),
+        );
+        kinds.insert(
+            "diagnostics".to_string(),
+            Arc::new(|ctx| {
+                let mut collection = TimeseriesCollection::new();
+                for (name, result) in ctx.dependencies() {
+                    let ts = result
+                        .timeseries
+                        .get_timeseries_by_name("State")
+                        .unwrap()
+                        .clone();
+                    collection.upsert_timeseries(name.to_string(), ts, VariableType::Endogenous);
+                }
+                Ok(StageResult::from_timeseries(collection))
+            }),
+        );
+        kinds
+    }
+
+    const CONFIG: &str = r#"
+threads = 2
+
+[[stages]]
+name = "spinup"
+kind = "spinup"
+
+[[stages]]
+name = "diagnostics"
+kind = "diagnostics"
+depends_on = ["low", "high"]
+
+[[stages]]
+name = "historical"
+kind = "historical"
+depends_on = ["spinup"]
+
+[[stages]]
+name = "low"
+kind = "scenario"
+depends_on = ["historical"]
+parameters = { k = 0.1 }
+
+[[stages]]
+name = "high"
+kind = "scenario"
+depends_on = ["historical"]
+parameters = { k = 0.5 }
+"#;
+
+    #[test]
+    fn run_experiment() {
+        let config = ExperimentConfig::from_toml(CONFIG).unwrap();
+        let experiment =
+            Experiment::from_config(&config, &kinds(Arc::new(AtomicUsize::new(0)))).unwrap();
+
+        let results = experiment.run().unwrap();
+        assert_eq!(results.len(), 5);
+
+        // Each stage continues from the state at the end of its dependency
+        let historical = final_state(&results["historical"]);
+        assert!(is_close!(historical, 0.9_f64.powi(40)));
+        assert!(is_close!(
+            final_state(&results["low"]),
+            historical * 0.99_f64.powi(20)
+        ));
+        assert!(is_close!(
+            final_state(&results["high"]),
+            historical * 0.95_f64.powi(20)
+        ));
+
+        let diagnostics = &results["diagnostics"].timeseries;
+        assert!(diagnostics.get_timeseries_by_name("low").is_some());
+        assert!(results["diagnostics"].checkpoint.is_none());
+        assert_eq!(results["low"].restore().unwrap().current_time(), 2022.0);
+    }
+
+    #[test]
+    fn reuse_checkpoints() {
+        let dir = std::env::temp_dir().join("rscm-experiment-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let mut config = ExperimentConfig::from_toml(CONFIG).unwrap();
+        config.checkpoint_dir = Some(dir.clone());
+        let experiment = Experiment::from_config(&config, &kinds(counter.clone())).unwrap();
+
+        let first = experiment.run().unwrap();
+        assert!(dir.join("historical.toml").exists());
+        let second = experiment.run().unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        assert_eq!(final_state(&first["high"]), final_state(&second["high"]));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_dependencies() {
+        let mut experiment = Experiment::new();
+        experiment
+            .with_stage("a", &["b"], |_| {
+                Ok(StageResult::from_timeseries(TimeseriesCollection::new()))
+            })
+            .unwrap()
+            .with_stage("b", &["a"], |_| {
+                Ok(StageResult::from_timeseries(TimeseriesCollection::new()))
+            })
+            .unwrap();
+        assert_eq!(
+            experiment.run().err().unwrap().to_string(),
+            "Experiment contains a cycle of dependencies"
+        );
+
+        experiment
+            .with_stage("c", &["d"], |_| {
+                Ok(StageResult::from_timeseries(TimeseriesCollection::new()))
+            })
+            .unwrap();
+        assert_eq!(
+            experiment.run().err().unwrap().to_string(),
+            "Stage 'c' depends on unknown stage 'd'"
+        );
+    }
+}
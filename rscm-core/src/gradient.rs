@@ -0,0 +1,470 @@
+//! Gradient-based calibration using finite-difference sensitivities
+//!
+//! [`finite_difference_gradient`] estimates the gradient of a scalar objective with respect
+//! to a set of parameters by running the model with each parameter perturbed in turn.
+//! The perturbed runs are independent so are run in parallel using up to
+//! [`crate::model::ModelOptions::threads`] threads.
+//! Each run uses the same random number streams (common random numbers),
+//! so stochastic components don't add noise to the differences.
+//!
+//! [`minimise`] uses these gradients with a bounded limited-memory BFGS (L-BFGS)
+//! optimiser.
+//! This typically needs far fewer model runs than derivative-free methods
+//! when the objective is a smooth function of the parameters.
+use crate::calibration::{CalibrationParameter, ParameterSet};
+use crate::errors::{RSCMError, RSCMResult};
+use crate::model::ModelBuilder;
+use crate::timeseries::FloatValue;
+use crate::timeseries_collection::TimeseriesCollection;
+use pyo3::pyclass;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Options for calculating finite-difference gradients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GradientOptions {
+    /// Size of the perturbation relative to the magnitude of each parameter
+    ///
+    /// Parameters with a magnitude less than one are perturbed by this absolute amount.
+    pub relative_step: FloatValue,
+    /// Use central differences, which are more accurate but require twice as many runs
+    pub central: bool,
+    /// Seed used to reseed the random number streams of every run
+    ///
+    /// If `None`, the default streams of the model are used.
+    pub seed: Option<u64>,
+}
+
+impl GradientOptions {
+    /// Size of the perturbation applied to a value
+    pub fn step_size(&self, value: FloatValue) -> FloatValue {
+        self.relative_step * value.abs().max(1.0)
+    }
+}
+
+impl Default for GradientOptions {
+    fn default() -> Self {
+        Self {
+            relative_step: 1e-4,
+            central: true,
+            seed: None,
+        }
+    }
+}
+
+/// Value and gradient of an objective
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Gradient {
+    /// Value of the objective at the unperturbed parameters
+    pub value: FloatValue,
+    /// Partial derivative with respect to each parameter in the order they were requested
+    pub gradient: Vec<FloatValue>,
+}
+
+/// Run each builder and evaluate `f` on its output, using parallel threads where possible
+///
+/// If `seed` is provided, the random number streams of every run are reseeded with it.
+pub(crate) fn run_perturbed<T, O>(
+    builders: &[ModelBuilder],
+    f: &O,
+    seed: Option<u64>,
+) -> RSCMResult<Vec<T>>
+where
+    T: Send,
+    O: Fn(&TimeseriesCollection) -> RSCMResult<T> + Sync,
+{
+    let threads = builders
+        .first()
+        .map_or(1, |builder| builder.options().threads.max(1));
+
+    let mut values = vec![];
+    for chunk in builders.chunks(threads) {
+        let results: Vec<RSCMResult<T>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|builder| {
+                    scope.spawn(move || {
+                        let mut model = builder.build();
+                        if let Some(seed) = seed {
+                            model.reseed_rng_streams(seed);
+                        }
+                        model.try_run()?;
+                        f(model.timeseries())
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Perturbed run panicked"))
+                .collect()
+        });
+        values.extend(results);
+    }
+    values.into_iter().collect()
+}
+
+/// Estimate the gradient of a scalar objective with respect to `names`
+///
+/// `build` creates a model builder for a parameter set and `objective` calculates
+/// the objective from the output of a run.
+/// Returns an error if any parameter in `names` is missing from `parameters`
+/// or if any of the runs fail.
+pub fn finite_difference_gradient<F, O>(
+    parameters: &ParameterSet,
+    names: &[String],
+    build: F,
+    objective: O,
+    options: &GradientOptions,
+) -> RSCMResult<Gradient>
+where
+    F: Fn(&ParameterSet) -> ModelBuilder,
+    O: Fn(&TimeseriesCollection) -> RSCMResult<FloatValue> + Sync,
+{
+    let mut steps = vec![];
+    let mut builders = vec![build(parameters)];
+    for name in names {
+        let value = *parameters
+            .get(name)
+            .ok_or_else(|| RSCMError::Error(format!("Unknown parameter '{}'", name)))?;
+        let step = options.step_size(value);
+        steps.push(step);
+
+        let mut perturbed = parameters.clone();
+        perturbed.insert(name.clone(), value + step);
+        builders.push(build(&perturbed));
+        if options.central {
+            perturbed.insert(name.clone(), value - step);
+            builders.push(build(&perturbed));
+        }
+    }
+
+    let values = run_perturbed(&builders, &objective, options.seed)?;
+    let value = values[0];
+    let gradient = steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            if options.central {
+                (values[1 + 2 * i] - values[2 + 2 * i]) / (2.0 * step)
+            } else {
+                (values[1 + i] - value) / step
+            }
+        })
+        .collect();
+
+    Ok(Gradient { value, gradient })
+}
+
+/// Options for [`minimise`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptimisationOptions {
+    /// Number of previous steps used to approximate the inverse Hessian
+    pub memory: usize,
+    pub max_iterations: usize,
+    /// Stop once the norm of the projected gradient is below this value
+    pub gradient_tolerance: FloatValue,
+    /// Stop once the objective improves by less than this relative amount
+    pub relative_tolerance: FloatValue,
+    pub gradient: GradientOptions,
+}
+
+impl Default for OptimisationOptions {
+    fn default() -> Self {
+        Self {
+            memory: 10,
+            max_iterations: 100,
+            gradient_tolerance: 1e-6,
+            relative_tolerance: 1e-10,
+            gradient: GradientOptions::default(),
+        }
+    }
+}
+
+/// Result of [`minimise`]
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptimisationResult {
+    /// Best parameter set found
+    pub parameters: ParameterSet,
+    /// Value of the objective at `parameters`
+    pub value: FloatValue,
+    pub iterations: usize,
+    /// Whether a convergence criterion was met before the maximum number of iterations
+    pub converged: bool,
+}
+
+fn dot(a: &[FloatValue], b: &[FloatValue]) -> FloatValue {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Minimise a scalar objective using L-BFGS with finite-difference gradients
+///
+/// Each parameter is constrained to lie within its `[min, max]` range
+/// by projecting trial points back onto the bounds.
+/// Parameters which aren't in `bounds` are held fixed at their `initial` values.
+pub fn minimise<F, O>(
+    initial: &ParameterSet,
+    bounds: &[CalibrationParameter],
+    build: F,
+    objective: O,
+    options: &OptimisationOptions,
+) -> RSCMResult<OptimisationResult>
+where
+    F: Fn(&ParameterSet) -> ModelBuilder,
+    O: Fn(&TimeseriesCollection) -> RSCMResult<FloatValue> + Sync,
+{
+    let names: Vec<String> = bounds.iter().map(|p| p.name.clone()).collect();
+    let project = |x: &[FloatValue]| -> Vec<FloatValue> {
+        x.iter()
+            .zip(bounds)
+            .map(|(x, p)| x.clamp(p.min, p.max))
+            .collect()
+    };
+    let to_parameters = |x: &[FloatValue]| {
+        let mut parameters = initial.clone();
+        names.iter().zip(x).for_each(|(name, value)| {
+            parameters.insert(name.clone(), *value);
+        });
+        parameters
+    };
+    let evaluate = |x: &[FloatValue]| {
+        finite_difference_gradient(
+            &to_parameters(x),
+            &names,
+            &build,
+            &objective,
+            &options.gradient,
+        )
+    };
+    // Gradient with the components that point out of the bounds removed
+    let projected_gradient = |x: &[FloatValue], gradient: &[FloatValue]| -> Vec<FloatValue> {
+        x.iter()
+            .zip(gradient)
+            .zip(bounds)
+            .map(|((x, g), p)| {
+                if (*x <= p.min && *g > 0.0) || (*x >= p.max && *g < 0.0) {
+                    0.0
+                } else {
+                    *g
+                }
+            })
+            .collect()
+    };
+
+    let mut x = project(
+        &names
+            .iter()
+            .map(|name| {
+                initial
+                    .get(name)
+                    .copied()
+                    .ok_or_else(|| RSCMError::Error(format!("Unknown parameter '{}'", name)))
+            })
+            .collect::<RSCMResult<Vec<_>>>()?,
+    );
+    let mut current = evaluate(&x)?;
+    let mut history: VecDeque<(Vec<FloatValue>, Vec<FloatValue>)> = VecDeque::new();
+
+    for iteration in 0..options.max_iterations {
+        let gradient = projected_gradient(&x, &current.gradient);
+        if dot(&gradient, &gradient).sqrt() < options.gradient_tolerance {
+            return Ok(OptimisationResult {
+                parameters: to_parameters(&x),
+                value: current.value,
+                iterations: iteration,
+                converged: true,
+            });
+        }
+
+        // Two-loop recursion to approximate the inverse Hessian applied to the gradient
+        let mut direction = gradient.clone();
+        let mut alphas = vec![];
+        for (s, y) in history.iter().rev() {
+            let alpha = dot(s, &direction) / dot(y, s);
+            direction
+                .iter_mut()
+                .zip(y)
+                .for_each(|(d, y)| *d -= alpha * y);
+            alphas.push(alpha);
+        }
+        if let Some((s, y)) = history.back() {
+            let scale = dot(s, y) / dot(y, y);
+            direction.iter_mut().for_each(|d| *d *= scale);
+        }
+        for ((s, y), alpha) in history.iter().zip(alphas.iter().rev()) {
+            let beta = dot(y, &direction) / dot(y, s);
+            direction
+                .iter_mut()
+                .zip(s)
+                .for_each(|(d, s)| *d += (alpha - beta) * s);
+        }
+        direction.iter_mut().for_each(|d| *d = -*d);
+        // Fall back to steepest descent if the direction isn't a descent direction
+        if dot(&direction, &gradient) >= 0.0 {
+            direction = gradient.iter().map(|g| -g).collect();
+            history.clear();
+        }
+
+        // Backtracking line search satisfying the Armijo condition
+        let mut step = 1.0;
+        let next = loop {
+            let trial: Vec<FloatValue> = project(
+                &x.iter()
+                    .zip(&direction)
+                    .map(|(x, d)| x + step * d)
+                    .collect::<Vec<_>>(),
+            );
+            let displacement: Vec<FloatValue> = trial.iter().zip(&x).map(|(t, x)| t - x).collect();
+            let candidate = evaluate(&trial)?;
+            if candidate.value <= current.value + 1e-4 * dot(&gradient, &displacement) {
+                break Some((trial, candidate));
+            }
+            step /= 2.0;
+            if step < 1e-10 {
+                break None;
+            }
+        };
+
+        let Some((trial, candidate)) = next else {
+            // No further progress can be made along the descent direction
+            return Ok(OptimisationResult {
+                parameters: to_parameters(&x),
+                value: current.value,
+                iterations: iteration,
+                converged: true,
+            });
+        };
+
+        let s: Vec<FloatValue> = trial.iter().zip(&x).map(|(t, x)| t - x).collect();
+        let y: Vec<FloatValue> = candidate
+            .gradient
+            .iter()
+            .zip(&current.gradient)
+            .map(|(a, b)| a - b)
+            .collect();
+        if dot(&s, &y) > 1e-12 {
+            history.push_back((s, y));
+            if history.len() > options.memory {
+                history.pop_front();
+            }
+        }
+
+        let improvement = current.value - candidate.value;
+        x = trial;
+        current = candidate;
+        if improvement.abs() <= options.relative_tolerance * current.value.abs().max(1e-12) {
+            return Ok(OptimisationResult {
+                parameters: to_parameters(&x),
+                value: current.value,
+                iterations: iteration + 1,
+                converged: true,
+            });
+        }
+    }
+
+    Ok(OptimisationResult {
+        parameters: to_parameters(&x),
+        value: current.value,
+        iterations: options.max_iterations,
+        converged: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_components::build_test_model as build;
+    use is_close::is_close;
+
+    /// Squared error of the concentration relative to 0.65 ppm, which is minimised by p = 0.5
+    fn objective(output: &TimeseriesCollection) -> RSCMResult<FloatValue> {
+        let concentration = output
+            .get_timeseries_by_name("Concentrations|CO2")
+            .unwrap()
+            .at(2)
+            .unwrap();
+        Ok((concentration - 0.65).powi(2))
+    }
+
+    fn parameters(p: FloatValue) -> ParameterSet {
+        ParameterSet::from([("p".to_string(), p)])
+    }
+
+    #[test]
+    fn gradient() {
+        // d/dp (1.3p - 0.65)^2 = 2.6 (1.3p - 0.65)
+        let expected = 2.6 * (1.3 - 0.65);
+
+        let central = finite_difference_gradient(
+            &parameters(1.0),
+            &["p".to_string()],
+            build,
+            objective,
+            &GradientOptions::default(),
+        )
+        .unwrap();
+        assert!(is_close!(central.value, 0.65 * 0.65));
+        assert!(is_close!(central.gradient[0], expected, rel_tol = 1e-8));
+
+        let forward = finite_difference_gradient(
+            &parameters(1.0),
+            &["p".to_string()],
+            build,
+            objective,
+            &GradientOptions {
+                central: false,
+                seed: Some(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(is_close!(forward.gradient[0], expected, rel_tol = 1e-3));
+
+        assert!(finite_difference_gradient(
+            &parameters(1.0),
+            &["q".to_string()],
+            build,
+            objective,
+            &GradientOptions::default(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn minimisation() {
+        let bounds = [CalibrationParameter {
+            name: "p".to_string(),
+            min: 0.0,
+            max: 2.0,
+        }];
+
+        let result = minimise(
+            &parameters(1.8),
+            &bounds,
+            build,
+            objective,
+            &OptimisationOptions::default(),
+        )
+        .unwrap();
+        assert!(result.converged);
+        assert!(is_close!(result.parameters["p"], 0.5, abs_tol = 1e-4));
+        assert!(result.value < 1e-8);
+
+        // The minimum is outside of the bounds
+        let bounds = [CalibrationParameter {
+            name: "p".to_string(),
+            min: 1.0,
+            max: 2.0,
+        }];
+        let result = minimise(
+            &parameters(1.8),
+            &bounds,
+            build,
+            objective,
+            &OptimisationOptions::default(),
+        )
+        .unwrap();
+        assert!(result.converged);
+        assert_eq!(result.parameters["p"], 1.0);
+    }
+}
@@ -0,0 +1,71 @@
+//! Helpers for rendering HTML summaries
+//!
+//! These are used to display objects in notebooks via `_repr_html_`.
+use crate::timeseries::FloatValue;
+
+/// Escape text for inclusion in HTML
+pub(crate) fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&#39;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// Format a number compactly
+///
+/// Very large and very small values use scientific notation.
+pub(crate) fn number(value: FloatValue) -> String {
+    let magnitude = value.abs();
+    if value.is_finite() && magnitude != 0.0 && !(1e-3..1e5).contains(&magnitude) {
+        format!("{:.3e}", value)
+    } else {
+        format!("{:.4}", value)
+    }
+}
+
+/// A table with a header row
+///
+/// Cells are escaped.
+pub(crate) fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let cells = |tag: &str, row: &[String]| -> String {
+        row.iter()
+            .map(|cell| format!("<{tag}>{}</{tag}>", escape(cell)))
+            .collect()
+    };
+    let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+
+    let mut html = format!(
+        "<table><thead><tr>{}</tr></thead><tbody>",
+        cells("th", &headers)
+    );
+    rows.iter()
+        .for_each(|row| html.push_str(&format!("<tr>{}</tr>", cells("td", row))));
+    html.push_str("</tbody></table>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_table() {
+        assert_eq!(
+            table(
+                &["Name", "Value"],
+                &[vec!["<CO2>".to_string(), number(1.5)]]
+            ),
+            "<table><thead><tr><th>Name</th><th>Value</th></tr></thead>\
+             <tbody><tr><td>&lt;CO2&gt;</td><td>1.5000</td></tr></tbody></table>"
+        );
+        assert_eq!(number(1.5e-7), "1.500e-7");
+        assert_eq!(number(0.0), "0.0000");
+        assert_eq!(number(FloatValue::NAN), "NaN");
+    }
+}
@@ -0,0 +1,219 @@
+//! Read emissions scenarios in the IAMC format
+//!
+//! Scenarios from integrated assessment models are commonly distributed as "wide" CSV files
+//! in the format used by the Integrated Assessment Modeling Consortium (IAMC),
+//! e.g. the SSP database:
+//!
+//! ```text
+//! Model,Scenario,Region,Variable,Unit,2015,2020,2030
+//! MESSAGE,SSP2-4.5,World,Emissions|CO2,Mt CO2/yr,39152.7,40647.8,43476.1
+//! ```
+//!
+//! Each row is read into a [`Timeseries`] named after the variable,
+//! which can be passed directly to a [`crate::model::ModelBuilder`] as exogenous data.
+//! The columns are matched case-insensitively and any extra metadata columns are ignored.
+//! Missing values are read as NaN.
+use crate::errors::{RSCMError, RSCMResult};
+use crate::interpolate::strategies::{InterpolationStrategy, LinearSplineStrategy};
+use crate::timeseries::{FloatValue, Time, TimeAxis, Timeseries};
+use crate::timeseries_collection::{TimeseriesCollection, VariableType};
+use numpy::ndarray::Array1;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Split a line of a CSV file into columns
+///
+/// Columns may be quoted to include commas.
+pub(crate) fn split_columns(line: &str) -> Vec<String> {
+    let mut columns = vec![];
+    let mut column = String::new();
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => columns.push(std::mem::take(&mut column).trim().to_string()),
+            _ => column.push(c),
+        }
+    }
+    columns.push(column.trim().to_string());
+    columns
+}
+
+/// Read the timeseries of a scenario from a CSV in the IAMC format
+///
+/// If the file contains multiple scenarios or regions,
+/// `scenario` and `region` select which rows are read.
+/// An error is returned if a variable is present in more than one of the selected rows,
+/// as the scenario or region would be ambiguous.
+/// The timeseries are linearly interpolated between the years.
+///
+/// # Examples
+///
+/// ```rust
+/// use rscm_core::iamc::from_iamc_csv;
+///
+/// let csv = "Model,Scenario,Region,Variable,Unit,2015,2020\n\
+///     MESSAGE,SSP2-4.5,World,Emissions|CO2,Mt CO2/yr,39152.7,40647.8\n";
+/// let collection = from_iamc_csv(csv, None, Some("World")).unwrap();
+///
+/// let emissions = collection.get_timeseries_by_name("Emissions|CO2").unwrap();
+/// assert_eq!(emissions.units(), "Mt CO2/yr");
+/// assert_eq!(emissions.at_time(2020.0).unwrap(), 40647.8);
+/// ```
+pub fn from_iamc_csv(
+    csv: &str,
+    scenario: Option<&str>,
+    region: Option<&str>,
+) -> RSCMResult<TimeseriesCollection> {
+    let mut lines = csv
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| RSCMError::Error("Empty IAMC file".to_string()))?;
+    let header = split_columns(header);
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|c| c.eq_ignore_ascii_case(name))
+            .ok_or_else(|| RSCMError::Error(format!("Missing {} column", name)))
+    };
+    let scenario_column = column("scenario")?;
+    let region_column = column("region")?;
+    let variable_column = column("variable")?;
+    let unit_column = column("unit")?;
+
+    // Any column which is a number is a year
+    let (year_columns, years): (Vec<usize>, Vec<Time>) = header
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| c.parse::<Time>().ok().map(|year| (i, year)))
+        .unzip();
+    if years.len() < 2 {
+        return Err(RSCMError::Error(
+            "IAMC file must contain at least two years".to_string(),
+        ));
+    }
+    let time_axis = Arc::new(TimeAxis::from_values(Array1::from_vec(years)));
+
+    let mut collection = TimeseriesCollection::new();
+    for (index, line) in lines {
+        let line_number = index + 1;
+        let columns = split_columns(line);
+        if columns.len() != header.len() {
+            return Err(RSCMError::Error(format!(
+                "Expected {} columns on line {}, found {}",
+                header.len(),
+                line_number,
+                columns.len()
+            )));
+        }
+        let selected = |filter: Option<&str>, column: usize| match filter {
+            Some(filter) => columns[column] == filter,
+            None => true,
+        };
+        if !selected(scenario, scenario_column) || !selected(region, region_column) {
+            continue;
+        }
+
+        let values = year_columns
+            .iter()
+            .map(|i| match columns[*i].as_str() {
+                "" => Ok(FloatValue::NAN),
+                value => value.parse::<FloatValue>().map_err(|_| {
+                    RSCMError::Error(format!(
+                        "Could not parse {:?} as a number on line {}",
+                        value, line_number
+                    ))
+                }),
+            })
+            .collect::<RSCMResult<Array1<FloatValue>>>()?;
+
+        let name = columns[variable_column].clone();
+        let timeseries = Timeseries::new(
+            values,
+            time_axis.clone(),
+            columns[unit_column].clone(),
+            InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+        );
+        collection
+            .add_timeseries(name.clone(), timeseries, VariableType::Exogenous)
+            .map_err(|_| {
+                RSCMError::Error(format!(
+                    "{} is defined for more than one scenario or region, select one",
+                    name
+                ))
+            })?;
+    }
+    Ok(collection)
+}
+
+/// Read the timeseries of a scenario from a file
+///
+/// Only CSV files in the IAMC format are currently supported (see [`from_iamc_csv`]).
+pub fn load_scenario(
+    path: impl AsRef<Path>,
+    scenario: Option<&str>,
+    region: Option<&str>,
+) -> RSCMResult<TimeseriesCollection> {
+    let path = path.as_ref();
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if !extension.eq_ignore_ascii_case("csv") {
+        return Err(RSCMError::Error(format!(
+            "Unsupported scenario format for {}, expected a CSV file",
+            path.display()
+        )));
+    }
+    let csv = std::fs::read_to_string(path).map_err(|e| {
+        RSCMError::Error(format!("Could not read scenario {}: {}", path.display(), e))
+    })?;
+    from_iamc_csv(&csv, scenario, region)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV: &str = "\
+Model,Scenario,Region,Variable,Unit,2015,2020,2030
+MESSAGE,SSP2-4.5,World,Emissions|CO2,Mt CO2/yr,39152.7,40647.8,43476.1
+MESSAGE,SSP2-4.5,World,Emissions|CH4,Mt CH4/yr,388.3,,379.0
+MESSAGE,SSP2-4.5,\"R5.2ASIA, excluding Japan\",Emissions|CO2,Mt CO2/yr,1.0,2.0,3.0
+";
+
+    #[test]
+    fn read() {
+        let collection = from_iamc_csv(CSV, Some("SSP2-4.5"), Some("World")).unwrap();
+
+        let co2 = collection.get_timeseries_by_name("Emissions|CO2").unwrap();
+        assert_eq!(co2.units(), "Mt CO2/yr");
+        assert_eq!(co2.len(), 3);
+        assert_eq!(co2.at_time(2025.0).unwrap(), (40647.8 + 43476.1) / 2.0);
+
+        let ch4 = collection.get_timeseries_by_name("Emissions|CH4").unwrap();
+        assert!(ch4.at(1).unwrap().is_nan());
+
+        let asia = from_iamc_csv(CSV, None, Some("R5.2ASIA, excluding Japan")).unwrap();
+        assert_eq!(asia.iter().count(), 1);
+    }
+
+    #[test]
+    fn ambiguous() {
+        let res = from_iamc_csv(CSV, None, None);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Emissions|CO2 is defined for more than one scenario or region, select one"
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let res = from_iamc_csv("Model,Scenario,Region,Unit,2015,2020\n", None, None);
+        assert_eq!(res.err().unwrap().to_string(), "Missing variable column");
+
+        let res = load_scenario("scenario.nc", None, None);
+        assert!(res.is_err());
+    }
+}
@@ -18,11 +18,15 @@ use crate::errors::RSCMResult;
 use num::Float;
 use numpy::ndarray::{ArrayBase, Data};
 use numpy::Ix1;
-use strategies::{Interp1DStrategy, InterpolationStrategy};
+use strategies::{Interp1DStrategy, InterpolationStrategy, SegmentCache};
 
 pub mod strategies;
 
 /// Interpolator
+///
+/// The interpolator remembers the segment used for the previous target.
+/// Interpolating targets in increasing order (as is done during a model run)
+/// reuses this segment where possible instead of searching the entire time axis.
 pub struct Interp1d<At, Ay>
 where
     At: Data,
@@ -32,6 +36,7 @@ where
     // TODO: Expand to support shape (t, ...)
     y: ArrayBase<Ay, Ix1>,
     strategy: InterpolationStrategy,
+    cache: SegmentCache,
 }
 
 impl<At, Ay> Interp1d<At, Ay>
@@ -46,7 +51,12 @@ where
         y: ArrayBase<Ay, Ix1>,
         strategy: InterpolationStrategy,
     ) -> Self {
-        Self { time, y, strategy }
+        Self {
+            time,
+            y,
+            strategy,
+            cache: SegmentCache::new(),
+        }
     }
     pub fn with_strategy(&mut self, strategy: InterpolationStrategy) -> &mut Self {
         self.strategy = strategy;
@@ -54,7 +64,8 @@ where
     }
 
     pub fn interpolate(&self, time_target: At::Elem) -> RSCMResult<Ay::Elem> {
-        self.strategy.interpolate(&self.time, &self.y, time_target)
+        self.strategy
+            .interpolate_cached(&self.time, &self.y, time_target, &self.cache)
     }
 }
 
@@ -62,6 +73,7 @@ where
 mod tests {
     use super::*;
     use crate::interpolate::strategies::next::NextStrategy;
+    use crate::interpolate::strategies::LinearSplineStrategy;
     use numpy::array;
     use numpy::ndarray::Array;
 
@@ -82,6 +94,27 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn interpolate_sequential() {
+        let data = Array::range(0.0, 100.0, 1.0);
+        let years = Array::range(2000.0, 2100.0, 1.0);
+
+        let interpolator = Interp1d::new(
+            years.view(),
+            data.view(),
+            InterpolationStrategy::from(LinearSplineStrategy::new(false)),
+        );
+
+        // Walk forwards through the axis, then jump backwards to check the cache is not stale
+        Array::range(2000.0, 2098.0, 0.25)
+            .iter()
+            .chain([2010.5, 2000.0].iter())
+            .for_each(|t| {
+                let result = interpolator.interpolate(*t).unwrap();
+                assert_eq!(result, t - 2000.0);
+            });
+    }
+
     #[test]
     fn interpolate_with_view() {
         let data = array![1.0, 1.5, 2.0];
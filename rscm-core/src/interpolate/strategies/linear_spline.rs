@@ -1,5 +1,7 @@
 use crate::errors::RSCMResult;
-use crate::interpolate::strategies::{find_segment, Interp1DStrategy, SegmentOptions};
+use crate::interpolate::strategies::{
+    find_segment, ExtrapolationPolicy, Interp1DStrategy, SegmentCache, SegmentOptions,
+};
 use num::Float;
 use numpy::ndarray::{s, ArrayBase, Data};
 use numpy::Ix1;
@@ -14,12 +16,22 @@ use std::cmp::min;
 /// The resulting curve is therefore only zero-order continuous.
 #[derive(Clone)]
 pub struct LinearSplineStrategy {
-    extrapolate: bool,
+    extrapolation: ExtrapolationPolicy,
 }
 
 impl LinearSplineStrategy {
+    /// Create a new strategy which either errors or extrapolates outside the time domain
     pub fn new(extrapolate: bool) -> Self {
-        Self { extrapolate }
+        Self::with_extrapolation(ExtrapolationPolicy::from(extrapolate))
+    }
+
+    /// Create a new strategy using a specific extrapolation policy
+    pub fn with_extrapolation(extrapolation: ExtrapolationPolicy) -> Self {
+        Self { extrapolation }
+    }
+
+    pub fn extrapolation(&self) -> &ExtrapolationPolicy {
+        &self.extrapolation
     }
 }
 
@@ -30,23 +42,22 @@ where
     Ay: Data,
     Ay::Elem: Float + From<At::Elem>,
 {
-    fn interpolate(
+    fn interpolate_cached(
         &self,
         time: &ArrayBase<At, Ix1>,
         y: &ArrayBase<Ay, Ix1>,
         time_target: At::Elem,
+        cache: &SegmentCache,
     ) -> RSCMResult<Ay::Elem> {
         let segment_info = find_segment(
             time_target,
             // Trim off the last bound as it isn't needed for linear extrapolation
             &time.slice(s![..time.len() - 1]),
-            self.extrapolate,
+            &self.extrapolation,
+            cache,
         );
 
-        let (segment_options, end_segment_idx) = match segment_info {
-            Ok(info) => info,
-            Err(e) => return Err(e),
-        };
+        let (segment_options, end_segment_idx) = segment_info?;
         // Clip the index to exclude the last bound
         let end_segment_idx = min(end_segment_idx, y.len() - 1);
 
@@ -94,7 +105,8 @@ where
 
         let m = (y2 - y1) / (time2 - time1);
 
-        Ok(m * (time_target - time1) + y1)
+        let res = m * (time_target - time1) + y1;
+        Ok(self.extrapolation.apply(res, &segment_options))
     }
 }
 
@@ -115,7 +127,7 @@ mod tests {
 
         let strategy = LinearSplineStrategy::new(false);
 
-        zip(target.into_iter(), exps.into_iter()).for_each(|(t, e)| {
+        zip(target, exps).for_each(|(t, e)| {
             println!("target={}, expected={}", t, e);
             assert!(is_close!(strategy.interpolate(&time, &y, t).unwrap(), e));
         })
@@ -137,7 +149,14 @@ mod tests {
 
             let err = res.err().unwrap();
             assert!(err.to_string().starts_with("Extrapolation is not allowed"))
-        })
+        });
+
+        // Times are reported without losing precision
+        let err = strategy.interpolate(&time, &y, 1850.000123456789).err();
+        assert_eq!(
+            err.unwrap().to_string(),
+            "Extrapolation is not allowed. Target=1850.000123456789, end of interpolation range=0"
+        );
     }
 
     #[test]
@@ -150,7 +169,7 @@ mod tests {
 
         let strategy = LinearSplineStrategy::new(true);
 
-        zip(target.into_iter(), exps.into_iter()).for_each(|(t, e)| {
+        zip(target, exps).for_each(|(t, e)| {
             let res = strategy.interpolate(&time, &y, t).unwrap();
             println!("target={}, expected={}, found={}", t, e, res);
             assert!(is_close!(res, e));
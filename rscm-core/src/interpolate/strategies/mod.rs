@@ -1,17 +1,20 @@
 pub mod linear_spline;
 pub mod next;
+pub mod piecewise;
 pub mod previous;
 
 use crate::errors::{RSCMError, RSCMResult};
 use is_close::is_close;
 pub use linear_spline::LinearSplineStrategy;
 pub use next::NextStrategy;
-use num::{Float, ToPrimitive};
+use num::{Float, NumCast, ToPrimitive};
 use numpy::ndarray::{ArrayBase, Data};
 use numpy::Ix1;
+pub use piecewise::PiecewiseStrategy;
 pub use previous::PreviousStrategy;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[derive(PartialEq)]
 pub(crate) enum SegmentOptions {
@@ -21,16 +24,121 @@ pub(crate) enum SegmentOptions {
     OnBoundary,
 }
 
+/// How values outside the time domain of the data are determined
+///
+/// The policy is independent of the interpolation strategy and is applied the same way
+/// by each strategy.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ExtrapolationPolicy {
+    /// Return an error if a value outside the time domain is requested
+    Error,
+    /// Extrapolate using the interpolation strategy
+    Extrapolate,
+    /// Use the value at the nearest end of the time domain
+    HoldNearest,
+    /// Extrapolate using the interpolation strategy and then clip the result to `[min, max]`
+    ///
+    /// Values within the time domain are not clipped.
+    ClampToRange(f64, f64),
+}
+
+impl ExtrapolationPolicy {
+    /// Whether values outside the time domain can be calculated
+    pub fn allows_extrapolation(&self) -> bool {
+        !matches!(self, ExtrapolationPolicy::Error)
+    }
+
+    /// Apply any limits to a value calculated by a strategy
+    pub(crate) fn apply<T: Float>(&self, value: T, segment_options: &SegmentOptions) -> T {
+        let is_extrapolated = matches!(
+            segment_options,
+            SegmentOptions::ExtrapolateBackward | SegmentOptions::ExtrapolateForward
+        );
+
+        match self {
+            ExtrapolationPolicy::ClampToRange(min, max) if is_extrapolated => {
+                let min = <T as NumCast>::from(*min).unwrap();
+                let max = <T as NumCast>::from(*max).unwrap();
+                value.max(min).min(max)
+            }
+            _ => value,
+        }
+    }
+}
+
+impl From<bool> for ExtrapolationPolicy {
+    /// Convert the legacy `extrapolate` flag into a policy
+    fn from(extrapolate: bool) -> Self {
+        match extrapolate {
+            true => ExtrapolationPolicy::Extrapolate,
+            false => ExtrapolationPolicy::Error,
+        }
+    }
+}
+
+/// Cache of the most recently located segment
+///
+/// Model runs query values with monotonically increasing times so the next target
+/// is very likely to be in the same segment as the previous target, or the one after it.
+/// Checking these segments first avoids a binary search over the full time axis.
+///
+/// An atomic is used (rather than a `Cell`) so that types holding a cache remain `Sync`.
+#[derive(Debug)]
+pub struct SegmentCache {
+    last_index: AtomicUsize,
+}
+
+impl SegmentCache {
+    const EMPTY: usize = usize::MAX;
+
+    pub fn new() -> Self {
+        Self {
+            last_index: AtomicUsize::new(Self::EMPTY),
+        }
+    }
+
+    fn get(&self) -> Option<usize> {
+        match self.last_index.load(Ordering::Relaxed) {
+            Self::EMPTY => None,
+            idx => Some(idx),
+        }
+    }
+
+    fn set(&self, index: usize) {
+        self.last_index.store(index, Ordering::Relaxed)
+    }
+
+    /// Forget the previously located segment
+    pub fn clear(&self) {
+        self.set(Self::EMPTY)
+    }
+}
+
+impl Default for SegmentCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for SegmentCache {
+    fn clone(&self) -> Self {
+        Self {
+            last_index: AtomicUsize::new(self.last_index.load(Ordering::Relaxed)),
+        }
+    }
+}
+
 fn find_segment<T>(
     target: T::Elem,
     time_bounds: &ArrayBase<T, Ix1>,
-    extrapolate: bool,
+    extrapolation: &ExtrapolationPolicy,
+    cache: &SegmentCache,
 ) -> RSCMResult<(SegmentOptions, usize)>
 where
     T: Data,
     T::Elem: Float,
 {
-    let end_segment_idx = find_segment_index(&target, time_bounds);
+    let end_segment_idx = find_segment_index_cached(&target, time_bounds, cache);
 
     let needs_extrap_forward = end_segment_idx == time_bounds.len();
     let needs_extrap_backward = !needs_extrap_forward & (end_segment_idx == 0);
@@ -42,21 +150,28 @@ where
 
     let needs_extrap = needs_extrap_backward | needs_extrap_forward;
 
-    if needs_extrap & (!extrapolate) {
+    if needs_extrap & (!extrapolation.allows_extrapolation()) {
         if needs_extrap_backward {
             return Err(RSCMError::ExtrapolationNotAllowed(
-                target.to_f32().unwrap(),
+                target.to_f64().unwrap(),
                 "start of".to_string(),
-                time_bounds[0].to_f32().unwrap(),
+                time_bounds[0].to_f64().unwrap(),
             ));
         } else {
             return Err(RSCMError::ExtrapolationNotAllowed(
-                target.to_f32().unwrap(),
+                target.to_f64().unwrap(),
                 "end of".to_string(),
-                time_bounds[time_bounds.len() - 1].to_f32().unwrap(),
+                time_bounds[time_bounds.len() - 1].to_f64().unwrap(),
             ));
         }
     }
+    if needs_extrap & (*extrapolation == ExtrapolationPolicy::HoldNearest) {
+        // Treat the target as if it was on the nearest boundary
+        return match needs_extrap_backward {
+            true => Ok((SegmentOptions::OnBoundary, 0)),
+            false => Ok((SegmentOptions::OnBoundary, time_bounds.len() - 1)),
+        };
+    }
     if needs_extrap_backward {
         Ok((SegmentOptions::ExtrapolateBackward, 0))
     } else if needs_extrap_forward {
@@ -80,6 +195,45 @@ where
     result.unwrap_or_else(|res| res)
 }
 
+/// Check if `target` falls in the segment ending at `index`
+///
+/// This matches the index that would be returned by a binary search,
+/// i.e. `time_bounds[index - 1] < target <= time_bounds[index]`.
+fn is_in_segment<T>(target: &T::Elem, time_bounds: &ArrayBase<T, Ix1>, index: usize) -> bool
+where
+    T: Data,
+    T::Elem: PartialOrd,
+{
+    if index > time_bounds.len() {
+        return false;
+    }
+    let after_start = index == 0 || time_bounds[index - 1] < *target;
+    let before_end = index == time_bounds.len() || *target <= time_bounds[index];
+
+    after_start && before_end
+}
+
+fn find_segment_index_cached<T>(
+    target: &T::Elem,
+    time_bounds: &ArrayBase<T, Ix1>,
+    cache: &SegmentCache,
+) -> usize
+where
+    T: Data,
+    T::Elem: PartialOrd,
+{
+    // Check the previously used segment and the one after it before falling back to a search
+    let hit = cache.get().and_then(|last| {
+        [last, last + 1]
+            .into_iter()
+            .find(|&idx| is_in_segment(target, time_bounds, idx))
+    });
+
+    let index = hit.unwrap_or_else(|| find_segment_index(target, time_bounds));
+    cache.set(index);
+    index
+}
+
 /// Strategy for interpolating a set of 1D values along a time axis
 ///
 /// A simple climate model often needs to perform mathematical operations on a timeseries
@@ -92,12 +246,26 @@ where
     Ay: Data,
 {
     /// Interpolate the value at a given time
-    /// This is used internally by [crate::interpolate::Interp1d].
     fn interpolate(
         &self,
         time: &ArrayBase<At, Ix1>,
         y: &ArrayBase<Ay, Ix1>,
         time_target: At::Elem,
+    ) -> RSCMResult<Ay::Elem> {
+        self.interpolate_cached(time, y, time_target, &SegmentCache::new())
+    }
+
+    /// Interpolate the value at a given time reusing a previously located segment
+    ///
+    /// `cache` is updated with the segment containing `time_target`.
+    /// The same cache must only be used with the same `time` array.
+    /// This is used internally by [crate::interpolate::Interp1d].
+    fn interpolate_cached(
+        &self,
+        time: &ArrayBase<At, Ix1>,
+        y: &ArrayBase<Ay, Ix1>,
+        time_target: At::Elem,
+        cache: &SegmentCache,
     ) -> RSCMResult<Ay::Elem>;
 }
 
@@ -106,6 +274,7 @@ pub enum InterpolationStrategy {
     Linear(LinearSplineStrategy),
     Next(NextStrategy),
     Previous(PreviousStrategy),
+    Piecewise(PiecewiseStrategy),
 }
 
 impl<At, Ay> Interp1DStrategy<At, Ay> for InterpolationStrategy
@@ -115,16 +284,74 @@ where
     Ay: Data,
     Ay::Elem: Float + From<At::Elem>,
 {
-    fn interpolate(
+    fn interpolate_cached(
         &self,
         time: &ArrayBase<At, Ix1>,
         y: &ArrayBase<Ay, Ix1>,
         time_target: At::Elem,
+        cache: &SegmentCache,
     ) -> RSCMResult<Ay::Elem> {
         match self {
-            InterpolationStrategy::Linear(strat) => strat.interpolate(time, y, time_target),
-            InterpolationStrategy::Next(strat) => strat.interpolate(time, y, time_target),
-            InterpolationStrategy::Previous(strat) => strat.interpolate(time, y, time_target),
+            InterpolationStrategy::Linear(strat) => {
+                strat.interpolate_cached(time, y, time_target, cache)
+            }
+            InterpolationStrategy::Next(strat) => {
+                strat.interpolate_cached(time, y, time_target, cache)
+            }
+            InterpolationStrategy::Previous(strat) => {
+                strat.interpolate_cached(time, y, time_target, cache)
+            }
+            InterpolationStrategy::Piecewise(strat) => {
+                strat.interpolate_cached(time, y, time_target, cache)
+            }
+        }
+    }
+}
+
+impl InterpolationStrategy {
+    /// The extrapolation policy used by the strategy
+    pub fn extrapolation(&self) -> &ExtrapolationPolicy {
+        match self {
+            InterpolationStrategy::Linear(strat) => strat.extrapolation(),
+            InterpolationStrategy::Next(strat) => strat.extrapolation(),
+            InterpolationStrategy::Previous(strat) => strat.extrapolation(),
+            InterpolationStrategy::Piecewise(strat) => strat.extrapolation(),
+        }
+    }
+
+    /// Replace the extrapolation policy while keeping the same strategy
+    pub fn with_extrapolation(self, extrapolation: ExtrapolationPolicy) -> Self {
+        match self {
+            InterpolationStrategy::Linear(_) => {
+                LinearSplineStrategy::with_extrapolation(extrapolation).into()
+            }
+            InterpolationStrategy::Next(_) => {
+                NextStrategy::with_extrapolation(extrapolation).into()
+            }
+            InterpolationStrategy::Previous(_) => {
+                PreviousStrategy::with_extrapolation(extrapolation).into()
+            }
+            InterpolationStrategy::Piecewise(strat) => {
+                strat.with_extrapolation(extrapolation).into()
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            InterpolationStrategy::Linear(_) => "Linear",
+            InterpolationStrategy::Next(_) => "Next",
+            InterpolationStrategy::Previous(_) => "Previous",
+            InterpolationStrategy::Piecewise(_) => "Piecewise",
+        }
+    }
+
+    fn from_name(name: &str, extrapolation: ExtrapolationPolicy) -> Option<Self> {
+        match name {
+            "Linear" => Some(LinearSplineStrategy::with_extrapolation(extrapolation).into()),
+            "Next" => Some(NextStrategy::with_extrapolation(extrapolation).into()),
+            "Previous" => Some(PreviousStrategy::with_extrapolation(extrapolation).into()),
+            _ => None,
         }
     }
 }
@@ -147,12 +374,37 @@ impl From<PreviousStrategy> for InterpolationStrategy {
     }
 }
 
+impl From<PiecewiseStrategy> for InterpolationStrategy {
+    fn from(value: PiecewiseStrategy) -> Self {
+        InterpolationStrategy::Piecewise(value)
+    }
+}
+
 impl Debug for InterpolationStrategy {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_tuple("InterpolationStrategy").finish()
     }
 }
 
+/// Serialised form of an [`InterpolationStrategy`]
+///
+/// Strategies using the default extrapolation policy are serialised as just the name of
+/// the strategy, e.g. `"Linear"`.
+/// A [`PiecewiseStrategy`] is serialised as its breakpoints and the strategy for each window.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SerialisedStrategy {
+    Name(String),
+    WithExtrapolation {
+        strategy: String,
+        extrapolation: ExtrapolationPolicy,
+    },
+    Piecewise {
+        breakpoints: Vec<f64>,
+        strategies: Vec<InterpolationStrategy>,
+    },
+}
+
 impl Serialize for InterpolationStrategy {
     fn serialize<S>(
         &self,
@@ -161,11 +413,23 @@ impl Serialize for InterpolationStrategy {
     where
         S: serde::Serializer,
     {
-        match self {
-            InterpolationStrategy::Linear(_) => serializer.serialize_str("Linear"),
-            InterpolationStrategy::Next(_) => serializer.serialize_str("Next"),
-            InterpolationStrategy::Previous(_) => serializer.serialize_str("Previous"),
+        if let InterpolationStrategy::Piecewise(strat) = self {
+            return SerialisedStrategy::Piecewise {
+                breakpoints: strat.breakpoints().to_vec(),
+                strategies: strat.strategies().to_vec(),
+            }
+            .serialize(serializer);
         }
+
+        let strategy = self.name().to_string();
+        let serialised = match self.extrapolation() {
+            ExtrapolationPolicy::Extrapolate => SerialisedStrategy::Name(strategy),
+            extrapolation => SerialisedStrategy::WithExtrapolation {
+                strategy,
+                extrapolation: extrapolation.clone(),
+            },
+        };
+        serialised.serialize(serializer)
     }
 }
 impl<'de> Deserialize<'de> for InterpolationStrategy {
@@ -173,14 +437,130 @@ impl<'de> Deserialize<'de> for InterpolationStrategy {
     where
         D: serde::Deserializer<'de>,
     {
-        let s = String::deserialize(deserializer)?;
-        match s.as_str() {
-            "Linear" => Ok(InterpolationStrategy::Linear(LinearSplineStrategy::new(
-                true,
-            ))),
-            "Next" => Ok(InterpolationStrategy::Next(NextStrategy::new(true))),
-            "Previous" => Ok(InterpolationStrategy::Previous(PreviousStrategy::new(true))),
-            _ => Err(serde::de::Error::custom(format!("Unknown strategy: {}", s))),
-        }
+        let (name, extrapolation) = match SerialisedStrategy::deserialize(deserializer)? {
+            SerialisedStrategy::Name(name) => (name, ExtrapolationPolicy::Extrapolate),
+            SerialisedStrategy::WithExtrapolation {
+                strategy,
+                extrapolation,
+            } => (strategy, extrapolation),
+            SerialisedStrategy::Piecewise {
+                breakpoints,
+                strategies,
+            } => {
+                return PiecewiseStrategy::new(breakpoints, strategies)
+                    .map(InterpolationStrategy::from)
+                    .map_err(serde::de::Error::custom)
+            }
+        };
+        InterpolationStrategy::from_name(&name, extrapolation)
+            .ok_or_else(|| serde::de::Error::custom(format!("Unknown strategy: {}", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use numpy::array;
+
+    #[test]
+    fn cached_segment_matches_search() {
+        let time = array![0.0, 1.0, 2.0, 3.0, 4.0];
+        let cache = SegmentCache::new();
+
+        // Sequential, repeated, backwards and out of range targets
+        let targets = [-1.0, 0.0, 0.5, 1.0, 1.0, 1.5, 3.5, 0.2, 4.0, 5.0, 2.0];
+
+        targets.into_iter().for_each(|t| {
+            let expected = find_segment_index(&t, &time);
+            assert_eq!(find_segment_index_cached(&t, &time, &cache), expected);
+            assert_eq!(cache.get(), Some(expected));
+        });
+
+        cache.clear();
+        assert_eq!(cache.get(), None);
+    }
+
+    #[test]
+    fn extrapolation_policies() {
+        let time = array![0.0, 1.0, 2.0, 3.0];
+        let y = array![1.0, 2.0, 4.0];
+
+        let check = |extrapolation: ExtrapolationPolicy, expected: [f64; 3]| {
+            [
+                InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+                InterpolationStrategy::from(PreviousStrategy::new(true)),
+                InterpolationStrategy::from(NextStrategy::new(true)),
+            ]
+            .into_iter()
+            .map(|strategy| strategy.with_extrapolation(extrapolation.clone()))
+            .zip(expected)
+            .for_each(|(strategy, exp)| {
+                // Inside the domain the policy has no effect
+                assert_eq!(strategy.interpolate(&time, &y, 1.0).unwrap(), 2.0);
+                let res = strategy.interpolate(&time, &y, 5.0).unwrap();
+                println!("{:?} expected={} found={}", extrapolation, exp, res);
+                assert_eq!(res, exp);
+            })
+        };
+
+        check(ExtrapolationPolicy::Extrapolate, [10.0, 4.0, 4.0]);
+        check(ExtrapolationPolicy::HoldNearest, [4.0, 4.0, 4.0]);
+        check(ExtrapolationPolicy::ClampToRange(0.0, 5.0), [5.0, 4.0, 4.0]);
+
+        let strategy = InterpolationStrategy::from(LinearSplineStrategy::new(true))
+            .with_extrapolation(ExtrapolationPolicy::HoldNearest);
+        assert_eq!(strategy.interpolate(&time, &y, -2.0).unwrap(), 1.0);
+
+        let strategy = strategy.with_extrapolation(ExtrapolationPolicy::Error);
+        assert!(strategy.interpolate(&time, &y, -2.0).is_err());
+    }
+
+    #[test]
+    fn serialise_extrapolation_policy() {
+        let strategy = InterpolationStrategy::from(PreviousStrategy::with_extrapolation(
+            ExtrapolationPolicy::ClampToRange(0.0, 1.0),
+        ));
+
+        let serialised = serde_json::to_string(&strategy).unwrap();
+        assert_eq!(
+            serialised,
+            r#"{"strategy":"Previous","extrapolation":{"ClampToRange":[0.0,1.0]}}"#
+        );
+        let deserialised: InterpolationStrategy = serde_json::from_str(&serialised).unwrap();
+        assert_eq!(
+            *deserialised.extrapolation(),
+            ExtrapolationPolicy::ClampToRange(0.0, 1.0)
+        );
+
+        let deserialised: InterpolationStrategy = serde_json::from_str(r#""Linear""#).unwrap();
+        assert_eq!(
+            *deserialised.extrapolation(),
+            ExtrapolationPolicy::Extrapolate
+        );
+    }
+
+    #[test]
+    fn serialise_piecewise() {
+        let strategy = InterpolationStrategy::from(
+            PiecewiseStrategy::new(
+                vec![2015.0],
+                vec![
+                    PreviousStrategy::new(true).into(),
+                    LinearSplineStrategy::new(false).into(),
+                ],
+            )
+            .unwrap(),
+        );
+
+        let serialised = serde_json::to_string(&strategy).unwrap();
+        assert_eq!(
+            serialised,
+            r#"{"breakpoints":[2015.0],"strategies":["Previous",{"strategy":"Linear","extrapolation":"Error"}]}"#
+        );
+        let deserialised: InterpolationStrategy = serde_json::from_str(&serialised).unwrap();
+        assert_eq!(serde_json::to_string(&deserialised).unwrap(), serialised);
+
+        let invalid = r#"{"breakpoints":[2015.0],"strategies":["Previous"]}"#;
+        assert!(serde_json::from_str::<InterpolationStrategy>(invalid).is_err());
     }
 }
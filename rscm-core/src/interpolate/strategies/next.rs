@@ -1,5 +1,7 @@
 use crate::errors::RSCMResult;
-use crate::interpolate::strategies::{find_segment, Interp1DStrategy, SegmentOptions};
+use crate::interpolate::strategies::{
+    find_segment, ExtrapolationPolicy, Interp1DStrategy, SegmentCache, SegmentOptions,
+};
 use num::Float;
 use numpy::ndarray::{ArrayBase, Data};
 use numpy::Ix1;
@@ -37,12 +39,22 @@ use std::cmp::min;
 /// it isn't actually used in the interpolation domain at all.
 #[derive(Clone)]
 pub struct NextStrategy {
-    extrapolate: bool,
+    extrapolation: ExtrapolationPolicy,
 }
 
 impl NextStrategy {
+    /// Create a new strategy which either errors or extrapolates outside the time domain
     pub fn new(extrapolate: bool) -> Self {
-        Self { extrapolate }
+        Self::with_extrapolation(ExtrapolationPolicy::from(extrapolate))
+    }
+
+    /// Create a new strategy using a specific extrapolation policy
+    pub fn with_extrapolation(extrapolation: ExtrapolationPolicy) -> Self {
+        Self { extrapolation }
+    }
+
+    pub fn extrapolation(&self) -> &ExtrapolationPolicy {
+        &self.extrapolation
     }
 }
 
@@ -53,18 +65,16 @@ where
     Ay: Data,
     Ay::Elem: Float,
 {
-    fn interpolate(
+    fn interpolate_cached(
         &self,
         time: &ArrayBase<At, Ix1>,
         y: &ArrayBase<Ay, Ix1>,
         time_target: At::Elem,
+        cache: &SegmentCache,
     ) -> RSCMResult<Ay::Elem> {
-        let segment_info = find_segment(time_target, time, self.extrapolate);
+        let segment_info = find_segment(time_target, time, &self.extrapolation, cache);
 
-        let (segment_options, end_segment_idx) = match segment_info {
-            Ok(info) => info,
-            Err(e) => return Err(e),
-        };
+        let (segment_options, end_segment_idx) = segment_info?;
         // Clip the index to exclude the last bound
         let end_segment_idx = min(end_segment_idx, y.len() - 1);
 
@@ -79,7 +89,7 @@ where
             SegmentOptions::InSegment | SegmentOptions::OnBoundary => y[end_segment_idx],
         };
 
-        Ok(res)
+        Ok(self.extrapolation.apply(res, &segment_options))
     }
 }
 
@@ -100,7 +110,7 @@ mod tests {
 
         let strategy = NextStrategy::new(false);
 
-        zip(target.into_iter(), exps.into_iter()).for_each(|(t, e)| {
+        zip(target, exps).for_each(|(t, e)| {
             println!("target={}, expected={}", t, e);
             assert!(is_close!(strategy.interpolate(&time, &y, t).unwrap(), e));
         })
@@ -135,7 +145,7 @@ mod tests {
 
         let strategy = NextStrategy::new(true);
 
-        zip(target.into_iter(), exps.into_iter()).for_each(|(t, e)| {
+        zip(target, exps).for_each(|(t, e)| {
             let value = strategy.interpolate(&time, &y, t).unwrap();
             println!("target={}, expected={} found={}", t, e, value);
             assert!(is_close!(value, e));
@@ -0,0 +1,199 @@
+use crate::errors::{RSCMError, RSCMResult};
+use crate::interpolate::strategies::{
+    ExtrapolationPolicy, Interp1DStrategy, InterpolationStrategy, SegmentCache,
+};
+use num::{Float, ToPrimitive};
+use numpy::ndarray::{ArrayBase, Data};
+use numpy::Ix1;
+
+/// Use different interpolation strategies over different time windows
+///
+/// The time domain is split into windows at each of the `breakpoints`.
+/// `strategies[0]` is used before `breakpoints[0]`,
+/// `strategies[i]` is used from `breakpoints[i - 1]` up to (but excluding) `breakpoints[i]`
+/// and the last strategy is used from the last breakpoint onwards.
+///
+/// This is useful when splicing observations and scenario data into a single timeseries,
+/// e.g. previous-value interpolation of historical data before 2015 and linear
+/// interpolation of the scenario data beyond.
+///
+/// Each strategy interpolates using all of the data,
+/// so the values either side of a breakpoint may be discontinuous.
+/// Targets outside the time domain of the data are handled by the extrapolation policy
+/// of the strategy for the window containing the target.
+#[derive(Clone, Debug)]
+pub struct PiecewiseStrategy {
+    breakpoints: Vec<f64>,
+    strategies: Vec<InterpolationStrategy>,
+}
+
+impl PiecewiseStrategy {
+    /// Create a new strategy from the times separating each window and the strategy
+    /// used within each window
+    ///
+    /// There must be one more strategy than breakpoints and the breakpoints must be
+    /// strictly increasing.
+    pub fn new(breakpoints: Vec<f64>, strategies: Vec<InterpolationStrategy>) -> RSCMResult<Self> {
+        if strategies.len() != breakpoints.len() + 1 {
+            return Err(RSCMError::Error(format!(
+                "Expected {} strategies for {} breakpoints, found {}",
+                breakpoints.len() + 1,
+                breakpoints.len(),
+                strategies.len()
+            )));
+        }
+        if !breakpoints.windows(2).all(|w| w[0] < w[1]) {
+            return Err(RSCMError::Error(format!(
+                "Breakpoints must be strictly increasing: {:?}",
+                breakpoints
+            )));
+        }
+
+        Ok(Self {
+            breakpoints,
+            strategies,
+        })
+    }
+
+    pub fn breakpoints(&self) -> &[f64] {
+        &self.breakpoints
+    }
+
+    pub fn strategies(&self) -> &[InterpolationStrategy] {
+        &self.strategies
+    }
+
+    /// The extrapolation policy of the last window
+    ///
+    /// This is the policy used when extrapolating beyond the end of the data.
+    pub fn extrapolation(&self) -> &ExtrapolationPolicy {
+        self.strategies.last().unwrap().extrapolation()
+    }
+
+    /// Replace the extrapolation policy of every window
+    pub fn with_extrapolation(self, extrapolation: ExtrapolationPolicy) -> Self {
+        Self {
+            breakpoints: self.breakpoints,
+            strategies: self
+                .strategies
+                .into_iter()
+                .map(|strategy| strategy.with_extrapolation(extrapolation.clone()))
+                .collect(),
+        }
+    }
+
+    /// The strategy for the window containing `time`
+    fn strategy_at(&self, time: f64) -> &InterpolationStrategy {
+        let window = self.breakpoints.partition_point(|&b| b <= time);
+        &self.strategies[window]
+    }
+}
+
+impl<At, Ay> Interp1DStrategy<At, Ay> for PiecewiseStrategy
+where
+    At: Data,
+    At::Elem: Float,
+    Ay: Data,
+    Ay::Elem: Float + From<At::Elem>,
+{
+    fn interpolate_cached(
+        &self,
+        time: &ArrayBase<At, Ix1>,
+        y: &ArrayBase<Ay, Ix1>,
+        time_target: At::Elem,
+        cache: &SegmentCache,
+    ) -> RSCMResult<Ay::Elem> {
+        // Each strategy locates segments in the same time array so the cache can be shared
+        self.strategy_at(time_target.to_f64().unwrap())
+            .interpolate_cached(time, y, time_target, cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolate::strategies::{LinearSplineStrategy, PreviousStrategy};
+    use is_close::is_close;
+    use numpy::array;
+    use std::iter::zip;
+
+    fn historical_then_linear() -> PiecewiseStrategy {
+        PiecewiseStrategy::new(
+            vec![2015.0],
+            vec![
+                PreviousStrategy::new(true).into(),
+                LinearSplineStrategy::new(true).into(),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_piecewise() {
+        let time = array![2000.0, 2010.0, 2015.0, 2020.0, 2030.0];
+        let y = array![0.0, 10.0, 20.0, 30.0, 50.0];
+
+        let strategy = historical_then_linear();
+
+        let target = vec![2000.0, 2005.0, 2012.0, 2015.0, 2017.5, 2025.0, 2040.0];
+        let exps = vec![0.0, 0.0, 10.0, 20.0, 25.0, 40.0, 70.0];
+
+        zip(target, exps).for_each(|(t, e)| {
+            let res = strategy.interpolate(&time, &y, t).unwrap();
+            println!("target={}, expected={} found={}", t, e, res);
+            assert!(is_close!(res, e));
+        });
+    }
+
+    #[test]
+    fn test_piecewise_cache() {
+        let time = array![2000.0, 2010.0, 2015.0, 2020.0, 2030.0];
+        let y = array![0.0, 10.0, 20.0, 30.0, 50.0];
+
+        let strategy = historical_then_linear();
+        let cache = SegmentCache::new();
+
+        // Crossing the breakpoint in either direction gives the same result as a fresh search
+        [2005.0, 2014.0, 2016.0, 2025.0, 2012.0, 2017.5]
+            .into_iter()
+            .for_each(|t| {
+                assert_eq!(
+                    strategy.interpolate_cached(&time, &y, t, &cache).unwrap(),
+                    strategy.interpolate(&time, &y, t).unwrap()
+                );
+            });
+    }
+
+    #[test]
+    fn test_piecewise_extrapolation() {
+        let time = array![2000.0, 2010.0, 2020.0];
+        let y = array![0.0, 10.0, 20.0];
+
+        let strategy = historical_then_linear();
+        assert!(is_close!(
+            strategy.interpolate(&time, &y, 1990.0).unwrap(),
+            0.0
+        ));
+        assert!(is_close!(
+            strategy.interpolate(&time, &y, 2030.0).unwrap(),
+            30.0
+        ));
+
+        let strategy = strategy.with_extrapolation(ExtrapolationPolicy::Error);
+        assert!(strategy.interpolate(&time, &y, 1990.0).is_err());
+        assert!(strategy.interpolate(&time, &y, 2030.0).is_err());
+        assert_eq!(*strategy.extrapolation(), ExtrapolationPolicy::Error);
+    }
+
+    #[test]
+    fn test_piecewise_invalid() {
+        let linear = || InterpolationStrategy::from(LinearSplineStrategy::new(true));
+
+        assert!(PiecewiseStrategy::new(vec![2015.0], vec![linear()]).is_err());
+        assert!(
+            PiecewiseStrategy::new(vec![2015.0, 2010.0], vec![linear(), linear(), linear()])
+                .is_err()
+        );
+        assert!(PiecewiseStrategy::new(vec![], vec![linear()]).is_ok());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::timeseries::{FloatValue, Time};
+use nalgebra::{DMatrix, DVector};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A linear initial value problem with constant coefficients
+///
+/// $$ \frac{dy}{dt} = A y + B u $$
+///
+/// where $u$ are inputs which are constant over a time step.
+/// These systems can be advanced exactly using [`LinearStepper`] rather than
+/// integrating numerically.
+pub trait LinearIVP {
+    /// State matrix $A$ with shape (n_states, n_states)
+    fn state_matrix(&self) -> DMatrix<FloatValue>;
+    /// Input matrix $B$ with shape (n_states, n_inputs)
+    fn input_matrix(&self) -> DMatrix<FloatValue>;
+}
+
+/// Exact solution operators of a linear system over a step
+#[derive(Debug)]
+struct Propagator {
+    /// $e^{A \Delta t}$
+    transition: DMatrix<FloatValue>,
+    /// $\int_0^{\Delta t} e^{A s} ds \, B$
+    input: DMatrix<FloatValue>,
+}
+
+impl Propagator {
+    /// Calculate the propagator from the exponential of the augmented matrix
+    ///
+    /// $$ \exp\left(\begin{bmatrix} A & B \\ 0 & 0 \end{bmatrix} \Delta t\right) =
+    /// \begin{bmatrix} e^{A \Delta t} & \int_0^{\Delta t} e^{A s} ds \, B \\ 0 & I \end{bmatrix} $$
+    fn new(a: &DMatrix<FloatValue>, b: &DMatrix<FloatValue>, step: Time) -> Self {
+        let n_states = a.nrows();
+        let n_inputs = b.ncols();
+        assert_eq!(a.ncols(), n_states, "A must be square");
+        assert_eq!(b.nrows(), n_states, "B must have a row for each state");
+
+        let mut augmented = DMatrix::zeros(n_states + n_inputs, n_states + n_inputs);
+        augmented
+            .view_mut((0, 0), (n_states, n_states))
+            .copy_from(&(a * step));
+        augmented
+            .view_mut((0, n_states), (n_states, n_inputs))
+            .copy_from(&(b * step));
+
+        let exp = augmented.exp();
+        Self {
+            transition: exp.view((0, 0), (n_states, n_states)).into_owned(),
+            input: exp.view((0, n_states), (n_states, n_inputs)).into_owned(),
+        }
+    }
+}
+
+/// Advances a [`LinearIVP`] exactly using the matrix exponential
+///
+/// The matrix exponential is only calculated once for each unique step size
+/// and then reused, so each step is reduced to a pair of matrix-vector products.
+/// The cache assumes that the system matrices don't change,
+/// so a stepper should only be used with a single system.
+///
+/// Components can store a stepper alongside their parameters.
+/// The cache isn't serialised.
+#[derive(Debug, Default)]
+pub struct LinearStepper {
+    propagators: Mutex<HashMap<u64, Arc<Propagator>>>,
+}
+
+impl Clone for LinearStepper {
+    fn clone(&self) -> Self {
+        Self {
+            propagators: Mutex::new(self.propagators.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl LinearStepper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn propagator<S: LinearIVP>(&self, system: &S, step: Time) -> Arc<Propagator> {
+        self.propagators
+            .lock()
+            .unwrap()
+            .entry(step.to_bits())
+            .or_insert_with(|| {
+                Arc::new(Propagator::new(
+                    &system.state_matrix(),
+                    &system.input_matrix(),
+                    step,
+                ))
+            })
+            .clone()
+    }
+
+    /// Advance `y` by `step` with constant inputs `u`
+    pub fn step<S: LinearIVP>(
+        &self,
+        system: &S,
+        y: &DVector<FloatValue>,
+        u: &DVector<FloatValue>,
+        step: Time,
+    ) -> DVector<FloatValue> {
+        let propagator = self.propagator(system, step);
+        &propagator.transition * y + &propagator.input * u
+    }
+
+    /// Number of unique step sizes that have been cached
+    pub fn n_cached(&self) -> usize {
+        self.propagators.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::{Component, InputState, State};
+    use crate::example_components::TestLinearComponent;
+    use crate::ivp::{get_last_step, IVPBuilder, IVP};
+    use crate::testing::ComponentTester;
+    use crate::timeseries::TimeAxis;
+    use is_close::is_close;
+    use nalgebra::{dvector, Vector2};
+    use numpy::array;
+
+    /// dy/dt = -y / tau + u
+    struct Decay {
+        tau: FloatValue,
+    }
+
+    impl LinearIVP for Decay {
+        fn state_matrix(&self) -> DMatrix<FloatValue> {
+            DMatrix::from_element(1, 1, -1.0 / self.tau)
+        }
+        fn input_matrix(&self) -> DMatrix<FloatValue> {
+            DMatrix::from_element(1, 1, 1.0)
+        }
+    }
+
+    #[test]
+    fn exact_decay() {
+        let system = Decay { tau: 4.0 };
+        let stepper = LinearStepper::new();
+
+        let y = stepper.step(&system, &dvector![2.0], &dvector![0.5], 3.0);
+
+        let decay = (-3.0 / 4.0_f64).exp();
+        let expected = 2.0 * decay + 0.5 * 4.0 * (1.0 - decay);
+        assert!(is_close!(y[0], expected));
+    }
+
+    #[test]
+    fn propagators_are_cached() {
+        let system = Decay { tau: 4.0 };
+        let stepper = LinearStepper::new();
+
+        let mut y = dvector![0.0];
+        for _ in 0..10 {
+            y = stepper.step(&system, &y, &dvector![1.0], 1.0);
+        }
+        stepper.step(&system, &y, &dvector![1.0], 0.5);
+
+        assert_eq!(stepper.n_cached(), 2);
+        // Steps of 1 are equivalent to a single step of 10
+        let single = LinearStepper::new().step(&system, &dvector![0.0], &dvector![1.0], 10.0);
+        assert!(is_close!(y[0], single[0]));
+    }
+
+    fn component() -> TestLinearComponent {
+        TestLinearComponent {
+            lambda: 1.2,
+            eta: 0.7,
+            heat_capacity_surface: 8.0,
+            heat_capacity_deep: 100.0,
+            stepper: LinearStepper::new(),
+        }
+    }
+
+    impl IVP<Time, Vector2<FloatValue>> for TestLinearComponent {
+        fn calculate_dy_dt(
+            &self,
+            _t: Time,
+            input_state: &InputState,
+            y: &Vector2<FloatValue>,
+            dy_dt: &mut Vector2<FloatValue>,
+        ) {
+            let y = DVector::from_column_slice(y.as_slice());
+            let u = dvector![*input_state.get("Effective Radiative Forcing")];
+            let result = self.state_matrix() * y + self.input_matrix() * u;
+            dy_dt.copy_from_slice(result.as_slice());
+        }
+    }
+
+    #[test]
+    fn matches_numerical_solution() {
+        let component = component();
+        let input_state = InputState::from_vectors(
+            vec![3.7, 0.0, 0.0],
+            vec![
+                "Effective Radiative Forcing".to_string(),
+                "Surface Temperature".to_string(),
+                "Deep Ocean Temperature".to_string(),
+            ],
+        );
+
+        let output = component.solve(2000.0, 2005.0, &input_state).unwrap();
+
+        let mut solver = IVPBuilder::new(Arc::new(component), input_state, Vector2::new(0.0, 0.0))
+            .to_rk4(2000.0, 2005.0, 0.001);
+        solver.integrate().unwrap();
+        let expected = get_last_step(solver.results(), 2005.0);
+
+        assert!(is_close!(*output.get("Surface Temperature"), expected[0]));
+        assert!(is_close!(
+            *output.get("Deep Ocean Temperature"),
+            expected[1]
+        ));
+    }
+
+    #[test]
+    fn irregular_time_axis() {
+        let outputs = ComponentTester::new(
+            component(),
+            TimeAxis::from_values(array![2000.0, 2001.0, 2002.0, 2004.0, 2006.0, 2007.0]),
+        )
+        .with_input(
+            "Effective Radiative Forcing",
+            array![3.7, 3.7, 3.7, 3.7, 3.7, 3.7],
+        )
+        .run()
+        .unwrap();
+
+        // Constant forcing gives the same result as a single step over the whole period
+        let single =
+            component()
+                .stepper
+                .step(&component(), &dvector![0.0, 0.0], &dvector![3.7], 7.0);
+        let temperature = outputs
+            .get_timeseries_by_name("Surface Temperature")
+            .unwrap();
+        assert!(is_close!(temperature.at(5).unwrap(), single[0]));
+    }
+}
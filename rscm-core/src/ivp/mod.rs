@@ -1,11 +1,16 @@
-use crate::component::InputState;
-use crate::timeseries::Time;
+use crate::component::{Component, InputState, OutputState, RequirementType, State};
+use crate::errors::{RSCMError, RSCMResult};
+use crate::timeseries::{Time, TimeUnit};
 use nalgebra::allocator::Allocator;
-use nalgebra::{DefaultAllocator, Dim};
+use nalgebra::{Const, DefaultAllocator, Dim};
 use ode_solvers::dop_shared::{FloatNumber, SolverResult};
 use ode_solvers::*;
 use std::sync::Arc;
 
+mod linear;
+
+pub use linear::{LinearIVP, LinearStepper};
+
 const T_THRESHOLD: Time = 5e-3;
 
 pub fn get_last_step<V>(results: &SolverResult<Time, V>, t_expected: Time) -> &V {
@@ -26,7 +31,36 @@ pub trait IVP<T, S> {
     fn calculate_dy_dt(&self, t: T, input_state: &InputState, y: &S, dy_dt: &mut S);
 }
 
+/// An initial value problem whose state is carried between time steps
+///
+/// Each element of the state vector is stored in the model as a
+/// [`RequirementType::InputAndOutput`] variable,
+/// so each time step continues from the state at the end of the previous step
+/// and the state is included in checkpoints.
+/// The initial state is given by the defaults of the variables
+/// (see [`crate::component::RequirementDefinition::with_default`]).
+///
+/// [`IVPBuilder::from_input_state`] creates a solver starting from the current state
+/// and [`IVPBuilder::solve_step`] returns the state at the end of the step.
+pub trait StatefulIVP<S>: Component + IVP<Time, S> {
+    /// Names of the variables containing each element of the state vector
+    ///
+    /// By default, these are the component's [`RequirementType::InputAndOutput`] variables
+    /// in the order they are defined.
+    fn state_names(&self) -> Vec<String> {
+        self.definitions()
+            .into_iter()
+            .filter(|definition| definition.requirement_type == RequirementType::InputAndOutput)
+            .map(|definition| definition.name)
+            .collect()
+    }
+}
+
 /// Builds a solver for an initial value problem
+///
+/// [`IVP::calculate_dy_dt`] returns rates per year.
+/// If the problem is solved on a time axis which uses a different [`TimeUnit`],
+/// the rates and the solver's step size are converted (see [`IVPBuilder::with_time_unit`]).
 #[derive(Clone)]
 pub struct IVPBuilder<C, S> {
     /// Model component to be solving
@@ -35,6 +69,8 @@ pub struct IVPBuilder<C, S> {
     /// Initial
     y0: S,
     input_state: InputState,
+    /// Length of one unit of time of the solver in years
+    years_per_unit: Time,
 }
 
 impl<T, D: Dim, C> System<T, OVector<T, D>> for IVPBuilder<C, OVector<T, D>>
@@ -45,7 +81,10 @@ where
     DefaultAllocator: Allocator<T, D>,
 {
     fn system(&self, t: T, y: &OVector<T, D>, dy: &mut OVector<T, D>) {
-        self.component.calculate_dy_dt(t, &self.input_state, y, dy)
+        self.component.calculate_dy_dt(t, &self.input_state, y, dy);
+        if self.years_per_unit != 1.0 {
+            *dy *= T::from_f64(self.years_per_unit).unwrap();
+        }
     }
 }
 
@@ -61,9 +100,21 @@ where
             component,
             y0,
             input_state,
+            years_per_unit: 1.0,
         }
     }
 
+    /// Solve on a time axis with times in `unit`
+    ///
+    /// Usually the unit of the model's time axis (see [`crate::component::TimestepContext::time_unit`]).
+    pub fn with_time_unit(mut self, unit: TimeUnit) -> Self {
+        self.years_per_unit = unit.in_years();
+        self
+    }
+
+    /// Create a fourth order Runge-Kutta solver between `t0` and `t1`
+    ///
+    /// `t0` and `t1` are in the unit of the time axis, while `step` is in years.
     #[allow(clippy::type_complexity)]
     pub fn to_rk4(
         self,
@@ -72,6 +123,111 @@ where
         step: T,
     ) -> Rk4<T, OVector<T, D>, IVPBuilder<C, OVector<T, D>>> {
         let y0 = self.y0.clone();
+        let step = step / T::from_f64(self.years_per_unit).unwrap();
         Rk4::new(self, t0, y0, t1, step)
     }
 }
+
+impl<D: Dim, C> IVPBuilder<C, OVector<Time, D>>
+where
+    C: StatefulIVP<OVector<Time, D>>,
+    OVector<Time, D>: std::ops::Mul<Time, Output = OVector<Time, D>>,
+    DefaultAllocator: Allocator<Time, D>,
+{
+    /// Start from the values of the state variables in `input_state`
+    ///
+    /// Panics if the number of state variables doesn't match the size of the state vector.
+    pub fn from_input_state(component: Arc<C>, input_state: InputState) -> Self {
+        let names = component.state_names();
+        let y0 = OVector::<Time, D>::from_iterator_generic(
+            D::from_usize(names.len()),
+            Const::<1>,
+            names.iter().map(|name| *input_state.get(name)),
+        );
+        Self::new(component, input_state, y0)
+    }
+
+    /// Integrate from `t0` to `t1` using a fourth order Runge-Kutta solver
+    ///
+    /// `step` is in years (see [`IVPBuilder::to_rk4`]).
+    /// Returns the values of the state variables at `t1`.
+    pub fn solve_step(self, t0: Time, t1: Time, step: Time) -> RSCMResult<OutputState> {
+        let names = self.component.state_names();
+        let mut solver = self.to_rk4(t0, t1, step);
+        solver
+            .integrate()
+            .map_err(|e| RSCMError::Error(format!("Failed solving: {:?}", e)))?;
+
+        let y = get_last_step(solver.results(), t1);
+        Ok(OutputState::from_vectors(
+            y.iter().copied().collect(),
+            names,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::RequirementDefinition;
+    use crate::model::ModelBuilder;
+    use crate::timeseries::{FloatValue, TimeAxis};
+    use nalgebra::Vector1;
+    use numpy::ndarray::Array;
+    use serde::{Deserialize, Serialize};
+
+    /// dy/dt = -y / tau
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Decay {
+        tau: FloatValue,
+    }
+
+    impl IVP<Time, Vector1<FloatValue>> for Decay {
+        fn calculate_dy_dt(
+            &self,
+            _t: Time,
+            _input_state: &InputState,
+            y: &Vector1<FloatValue>,
+            dy_dt: &mut Vector1<FloatValue>,
+        ) {
+            dy_dt[0] = -y[0] / self.tau;
+        }
+    }
+
+    impl StatefulIVP<Vector1<FloatValue>> for Decay {}
+
+    #[typetag::serde]
+    impl Component for Decay {
+        fn definitions(&self) -> Vec<RequirementDefinition> {
+            vec![
+                RequirementDefinition::new("y", "", RequirementType::InputAndOutput)
+                    .with_default(1.0),
+            ]
+        }
+
+        fn solve(
+            &self,
+            t_current: Time,
+            t_next: Time,
+            input_state: &InputState,
+        ) -> RSCMResult<OutputState> {
+            IVPBuilder::from_input_state(Arc::new(self.clone()), input_state.clone())
+                .solve_step(t_current, t_next, 0.01)
+        }
+    }
+
+    #[test]
+    fn state_carried_between_steps() {
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2000.0, 2011.0, 1.0)))
+            .with_component(Arc::new(Decay { tau: 5.0 }))
+            .build();
+        model.run();
+
+        let y = model.timeseries().get_timeseries_by_name("y").unwrap();
+        assert_eq!(y.at(0), Some(1.0));
+        // Each step continues from the end of the previous step
+        let expected = (-10.0 / 5.0 as FloatValue).exp();
+        assert!((y.at(10).unwrap() - expected).abs() < 1e-9);
+    }
+}
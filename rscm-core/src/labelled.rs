@@ -0,0 +1,261 @@
+//! Timeseries with a second labelled dimension
+//!
+//! Many variables have a value for each of a set of labels,
+//! such as the emissions of each region or the concentrations of each gas.
+//! A [`LabelledTimeseries`] stores these values as a 2D array with a row per time step
+//! and a column per label.
+//!
+//! Within a model, each label is stored as a separate [`Timeseries`]
+//! named using [`labelled_name`], e.g. "Emissions|CO2|World".
+//! Components declare labelled variables using [`RequirementDefinition::with_labels`]
+//! and access the values using [`InputState::get_labelled`].
+//!
+//! [`RequirementDefinition::with_labels`]: crate::component::RequirementDefinition::with_labels
+//! [`InputState::get_labelled`]: crate::component::InputState::get_labelled
+use crate::component::labelled_name;
+use crate::errors::{RSCMError, RSCMResult};
+use crate::interpolate::strategies::InterpolationStrategy;
+use crate::timeseries::{FloatValue, TimeAxis, Timeseries};
+use crate::timeseries_collection::{TimeseriesCollection, VariableType};
+use numpy::ndarray::{Array2, ArrayView2, Axis};
+use std::sync::Arc;
+
+/// A timeseries with a value for each of a set of labels at each time
+#[derive(Debug, Clone)]
+pub struct LabelledTimeseries {
+    /// Values with a row per time step and a column per label
+    values: Array2<FloatValue>,
+    labels: Vec<String>,
+    time_axis: Arc<TimeAxis>,
+    units: String,
+    interpolation_strategy: InterpolationStrategy,
+}
+
+impl LabelledTimeseries {
+    /// Create a labelled timeseries
+    ///
+    /// Panics if `values` doesn't have a row for each time and a column for each label.
+    pub fn new(
+        values: Array2<FloatValue>,
+        labels: Vec<String>,
+        time_axis: Arc<TimeAxis>,
+        units: &str,
+        interpolation_strategy: InterpolationStrategy,
+    ) -> Self {
+        assert_eq!(values.dim(), (time_axis.len(), labels.len()));
+        Self {
+            values,
+            labels,
+            time_axis,
+            units: units.to_string(),
+            interpolation_strategy,
+        }
+    }
+
+    /// Combine a timeseries for each label
+    ///
+    /// The timeseries must share a time axis and units.
+    /// The interpolation strategy of the first timeseries is used.
+    pub fn from_timeseries(
+        labels: Vec<String>,
+        timeseries: &[&Timeseries<FloatValue>],
+    ) -> RSCMResult<Self> {
+        let first = timeseries
+            .first()
+            .ok_or_else(|| RSCMError::Error("No timeseries to combine".to_string()))?;
+        if labels.len() != timeseries.len() {
+            return Err(RSCMError::Error(format!(
+                "Expected {} timeseries, got {}",
+                labels.len(),
+                timeseries.len()
+            )));
+        }
+        if let Some(other) = timeseries.iter().find(|ts| ts.units() != first.units()) {
+            return Err(RSCMError::WrongUnits(
+                first.units().to_string(),
+                other.units().to_string(),
+            ));
+        }
+        if timeseries
+            .iter()
+            .any(|ts| ts.time_axis().bounds() != first.time_axis().bounds())
+        {
+            return Err(RSCMError::Error(
+                "Labelled timeseries must share a time axis".to_string(),
+            ));
+        }
+
+        let mut values = Array2::zeros((first.len(), labels.len()));
+        timeseries.iter().enumerate().for_each(|(i, ts)| {
+            values.column_mut(i).assign(&ts.values());
+        });
+        Ok(Self {
+            values,
+            labels,
+            time_axis: first.time_axis(),
+            units: first.units().to_string(),
+            interpolation_strategy: first.interpolation_strategy().clone(),
+        })
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Values with a row per time step and a column per label
+    pub fn values(&self) -> ArrayView2<'_, FloatValue> {
+        self.values.view()
+    }
+
+    pub fn units(&self) -> &str {
+        &self.units
+    }
+
+    pub fn time_axis(&self) -> Arc<TimeAxis> {
+        self.time_axis.clone()
+    }
+
+    /// The timeseries of a single label
+    pub fn timeseries(&self, label: &str) -> Option<Timeseries<FloatValue>> {
+        let index = self.labels.iter().position(|l| l == label)?;
+        Some(Timeseries::new(
+            self.values.index_axis(Axis(1), index).to_owned(),
+            self.time_axis.clone(),
+            self.units.clone(),
+            self.interpolation_strategy.clone(),
+        ))
+    }
+
+    /// Add the timeseries of each label to a collection
+    ///
+    /// The timeseries are named using [`labelled_name`].
+    pub fn add_to_collection(
+        &self,
+        name: &str,
+        collection: &mut TimeseriesCollection,
+        variable_type: VariableType,
+    ) {
+        self.labels.iter().for_each(|label| {
+            collection.upsert_timeseries(
+                labelled_name(name, label),
+                self.timeseries(label).unwrap(),
+                variable_type,
+            );
+        });
+    }
+
+    /// Read the timeseries of each label from a collection
+    ///
+    /// Returns `None` if any of the labels are missing.
+    pub fn from_collection(
+        name: &str,
+        labels: &[String],
+        collection: &TimeseriesCollection,
+    ) -> Option<RSCMResult<Self>> {
+        let timeseries = labels
+            .iter()
+            .map(|label| collection.get_timeseries_by_name(&labelled_name(name, label)))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self::from_timeseries(labels.to_vec(), &timeseries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::{
+        Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+    };
+    use crate::interpolate::strategies::PreviousStrategy;
+    use crate::model::ModelBuilder;
+    use crate::timeseries::Time;
+    use numpy::array;
+    use numpy::ndarray::Array;
+    use serde::{Deserialize, Serialize};
+
+    fn regions() -> Vec<String> {
+        vec!["World".to_string(), "Asia".to_string()]
+    }
+
+    fn emissions() -> LabelledTimeseries {
+        LabelledTimeseries::new(
+            array![[10.0, 4.0], [11.0, 5.0], [12.0, 6.0]],
+            regions(),
+            Arc::new(TimeAxis::from_values(array![2020.0, 2021.0, 2022.0])),
+            "GtC / yr",
+            InterpolationStrategy::from(PreviousStrategy::new(true)),
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        let emissions = emissions();
+        let asia = emissions.timeseries("Asia").unwrap();
+        assert_eq!(asia.values(), array![4.0, 5.0, 6.0]);
+        assert!(emissions.timeseries("Europe").is_none());
+
+        let mut collection = TimeseriesCollection::new();
+        emissions.add_to_collection("Emissions|CO2", &mut collection, VariableType::Exogenous);
+        assert!(collection
+            .get_timeseries_by_name("Emissions|CO2|World")
+            .is_some());
+
+        let read = LabelledTimeseries::from_collection("Emissions|CO2", &regions(), &collection)
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.values(), emissions.values());
+        assert_eq!(read.units(), "GtC / yr");
+    }
+
+    /// Component which sums a regional variable
+    #[derive(Debug, Serialize, Deserialize)]
+    struct RegionalSum {}
+
+    #[typetag::serde]
+    impl Component for RegionalSum {
+        fn definitions(&self) -> Vec<RequirementDefinition> {
+            vec![
+                RequirementDefinition::new("Emissions|CO2", "GtC / yr", RequirementType::Input)
+                    .with_labels(&["World", "Asia"]),
+                RequirementDefinition::new(
+                    "Emissions|CO2|Total",
+                    "GtC / yr",
+                    RequirementType::Output,
+                ),
+            ]
+        }
+
+        fn solve(
+            &self,
+            _t_current: Time,
+            _t_next: Time,
+            input_state: &InputState,
+        ) -> RSCMResult<OutputState> {
+            let total: FloatValue = input_state
+                .get_labels("Emissions|CO2", &regions())
+                .iter()
+                .sum();
+            assert_eq!(
+                *input_state.get_labelled("Emissions|CO2", "Asia"),
+                *input_state.get("Emissions|CO2|Asia")
+            );
+            Ok(OutputState::from_vectors(vec![total], self.output_names()))
+        }
+    }
+
+    #[test]
+    fn labelled_input() {
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2022.0, 1.0)))
+            .with_component(Arc::new(RegionalSum {}))
+            .with_labelled_exogenous_variable("Emissions|CO2", emissions())
+            .build();
+        model.run();
+
+        let total = model
+            .timeseries()
+            .get_timeseries_by_name("Emissions|CO2|Total")
+            .unwrap();
+        assert_eq!(total.at(1), Some(14.0));
+    }
+}
@@ -1,10 +1,45 @@
+pub mod archive;
+pub mod attribution;
+pub mod calendar;
+pub mod calibration;
 pub mod component;
+pub mod conservation;
+pub mod convolution;
+pub mod cost;
+pub mod diagnostics;
+pub mod distributions;
+pub mod dual;
+pub mod emission_metrics;
+pub mod ensemble;
+pub mod events;
 mod example_components;
+pub mod exceedance;
+pub mod experiment;
+pub mod gradient;
+mod html;
+pub mod iamc;
 pub mod interpolate;
 pub mod ivp;
+pub mod labelled;
+pub mod linearisation;
+pub mod mcmc;
+pub mod metadata;
+pub mod metrics;
 pub mod model;
+pub mod optimise;
+pub mod output;
+pub mod prelude;
+pub mod provenance;
 pub mod python;
+pub mod remap;
+pub mod run_database;
+pub mod sampling;
+pub mod scenarios;
+pub mod sparse_timeseries;
+pub mod submodel;
+pub mod testing;
 pub mod timeseries;
 pub mod timeseries_collection;
+pub mod units;
 
 pub mod errors;
@@ -0,0 +1,372 @@
+//! Linearisation of a model around a reference run
+//!
+//! [`linearise`] calculates the Jacobians of the model's outputs with respect to a set of
+//! parameters and the initial values of state variables using finite differences.
+//! The outputs are stacked into a single vector, ordered by variable and then by time.
+//!
+//! The resulting [`Linearisation`] is a tangent-linear approximation of the full model
+//! which can be used where running the model many times would be too expensive,
+//! such as the observation operator of a Kalman filter
+//! or to rapidly propagate parameter uncertainty.
+use crate::calibration::ParameterSet;
+use crate::component::InputState;
+use crate::errors::{RSCMError, RSCMResult};
+use crate::gradient::{run_perturbed, GradientOptions};
+use crate::model::ModelBuilder;
+use crate::timeseries::{FloatValue, Time};
+use crate::timeseries_collection::TimeseriesCollection;
+use nalgebra::{DMatrix, DVector};
+use serde_json::json;
+
+/// Tangent-linear approximation of a model around a reference run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Linearisation {
+    /// Names of the output variables
+    pub outputs: Vec<String>,
+    /// Time axis of the model
+    pub times: Vec<Time>,
+    /// Names of the parameters which were perturbed
+    pub parameters: Vec<String>,
+    /// Names of the state variables whose initial values were perturbed
+    pub initial_state: Vec<String>,
+    /// Stacked outputs of the reference run
+    pub reference: DVector<FloatValue>,
+    /// Derivative of each output with respect to each parameter
+    pub parameter_jacobian: DMatrix<FloatValue>,
+    /// Derivative of each output with respect to the initial value of each state variable
+    pub state_jacobian: DMatrix<FloatValue>,
+}
+
+impl Linearisation {
+    /// Index in the stacked outputs of a variable at a time index
+    pub fn index(&self, output: &str, time_index: usize) -> Option<usize> {
+        let position = self.outputs.iter().position(|o| o == output)?;
+        (time_index < self.times.len()).then_some(position * self.times.len() + time_index)
+    }
+
+    /// Combined Jacobian with the parameters followed by the initial state
+    pub fn jacobian(&self) -> DMatrix<FloatValue> {
+        let mut jacobian = DMatrix::zeros(
+            self.reference.len(),
+            self.parameters.len() + self.initial_state.len(),
+        );
+        jacobian
+            .columns_mut(0, self.parameters.len())
+            .copy_from(&self.parameter_jacobian);
+        jacobian
+            .columns_mut(self.parameters.len(), self.initial_state.len())
+            .copy_from(&self.state_jacobian);
+        jacobian
+    }
+
+    /// Approximate the stacked outputs after perturbing the parameters and initial state
+    ///
+    /// `perturbation` contains the change in each parameter followed by the change in the
+    /// initial value of each state variable.
+    pub fn predict(&self, perturbation: &[FloatValue]) -> RSCMResult<DVector<FloatValue>> {
+        let perturbation = DVector::from_column_slice(perturbation);
+        if perturbation.len() != self.parameters.len() + self.initial_state.len() {
+            return Err(RSCMError::Error(format!(
+                "Expected a perturbation of length {}, got {}",
+                self.parameters.len() + self.initial_state.len(),
+                perturbation.len()
+            )));
+        }
+        Ok(&self.reference + self.jacobian() * perturbation)
+    }
+
+    /// Covariance of the stacked outputs given the covariance of the parameters
+    /// and initial state
+    ///
+    /// The rows and columns of `covariance` are ordered in the same way as [`Linearisation::jacobian`].
+    pub fn propagate_covariance(
+        &self,
+        covariance: &DMatrix<FloatValue>,
+    ) -> RSCMResult<DMatrix<FloatValue>> {
+        let jacobian = self.jacobian();
+        if covariance.shape() != (jacobian.ncols(), jacobian.ncols()) {
+            return Err(RSCMError::Error(format!(
+                "Expected a {0}x{0} covariance matrix, got {1:?}",
+                jacobian.ncols(),
+                covariance.shape()
+            )));
+        }
+        Ok(&jacobian * covariance * jacobian.transpose())
+    }
+
+    /// Export the linearisation as JSON
+    ///
+    /// Matrices are written as a list of rows.
+    /// NaN values, such as outputs before they are first solved, are written as `null`.
+    pub fn to_json(&self) -> RSCMResult<String> {
+        let rows = |matrix: &DMatrix<FloatValue>| -> Vec<Vec<FloatValue>> {
+            matrix
+                .row_iter()
+                .map(|row| row.iter().copied().collect())
+                .collect()
+        };
+        serde_json::to_string(&json!({
+            "outputs": self.outputs,
+            "times": self.times,
+            "parameters": self.parameters,
+            "initial_state": self.initial_state,
+            "reference": self.reference.as_slice(),
+            "parameter_jacobian": rows(&self.parameter_jacobian),
+            "state_jacobian": rows(&self.state_jacobian),
+        }))
+        .map_err(|e| RSCMError::Error(format!("Failed to serialise linearisation: {}", e)))
+    }
+}
+
+/// Linearise a model around the run using `parameters`
+///
+/// `build` creates a model builder for a parameter set.
+/// Each parameter in `parameter_names` and the initial value of each variable in
+/// `initial_state` is perturbed in turn and the perturbed runs are run in parallel.
+/// The initial values of the reference run are those of the model built from `parameters`.
+pub fn linearise<F>(
+    parameters: &ParameterSet,
+    parameter_names: &[String],
+    initial_state: &[String],
+    outputs: &[String],
+    build: F,
+    options: &GradientOptions,
+) -> RSCMResult<Linearisation>
+where
+    F: Fn(&ParameterSet) -> ModelBuilder,
+{
+    let reference_builder = build(parameters);
+    let reference_model = reference_builder.build();
+    let times = reference_builder.time_axis.values().to_vec();
+
+    let mut steps = vec![];
+    let mut builders = vec![reference_builder.clone()];
+    let mut perturb = |builders: &mut Vec<ModelBuilder>,
+                       step: FloatValue,
+                       f: &dyn Fn(FloatValue) -> ModelBuilder| {
+        steps.push(step);
+        builders.push(f(step));
+        if options.central {
+            builders.push(f(-step));
+        }
+    };
+
+    for name in parameter_names {
+        let value = *parameters
+            .get(name)
+            .ok_or_else(|| RSCMError::Error(format!("Unknown parameter '{}'", name)))?;
+        perturb(&mut builders, options.step_size(value), &|delta| {
+            let mut perturbed = parameters.clone();
+            perturbed.insert(name.clone(), value + delta);
+            build(&perturbed)
+        });
+    }
+    for name in initial_state {
+        let value = reference_model
+            .timeseries()
+            .get_timeseries_by_name(name)
+            .and_then(|ts| ts.at(0))
+            .filter(|v| !v.is_nan())
+            .ok_or_else(|| RSCMError::Error(format!("'{}' doesn't have an initial value", name)))?;
+        perturb(&mut builders, options.step_size(value), &|delta| {
+            let mut builder = reference_builder.clone();
+            builder.with_initial_values(InputState::from_vectors(
+                vec![value + delta],
+                vec![name.clone()],
+            ));
+            builder
+        });
+    }
+
+    let stack = |collection: &TimeseriesCollection| -> RSCMResult<Vec<FloatValue>> {
+        let mut values = vec![];
+        for name in outputs {
+            let timeseries = collection
+                .get_timeseries_by_name(name)
+                .ok_or_else(|| RSCMError::TimeseriesNotFound(format!("'{}'", name)))?;
+            values.extend(timeseries.values().iter());
+        }
+        Ok(values)
+    };
+    let runs = run_perturbed(&builders, &stack, options.seed)?;
+
+    let reference = DVector::from_vec(runs[0].clone());
+    let columns: Vec<DVector<FloatValue>> = steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            if options.central {
+                let upper = DVector::from_column_slice(&runs[1 + 2 * i]);
+                let lower = DVector::from_column_slice(&runs[2 + 2 * i]);
+                (upper - lower) / (2.0 * step)
+            } else {
+                (DVector::from_column_slice(&runs[1 + i]) - &reference) / *step
+            }
+        })
+        .collect();
+    let matrix = |columns: &[DVector<FloatValue>]| {
+        if columns.is_empty() {
+            DMatrix::zeros(reference.len(), 0)
+        } else {
+            DMatrix::from_columns(columns)
+        }
+    };
+
+    Ok(Linearisation {
+        outputs: outputs.to_vec(),
+        times,
+        parameters: parameter_names.to_vec(),
+        initial_state: initial_state.to_vec(),
+        parameter_jacobian: matrix(&columns[..parameter_names.len()]),
+        state_jacobian: matrix(&columns[parameter_names.len()..]),
+        reference,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_components::{TestComponent, TestComponentParameters, TestDecayComponent};
+    use crate::timeseries::{TimeAxis, Timeseries};
+    use is_close::is_close;
+    use numpy::array;
+    use numpy::ndarray::Array;
+    use std::sync::Arc;
+
+    fn build(parameters: &ParameterSet) -> ModelBuilder {
+        let mut builder = ModelBuilder::new();
+        builder
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2026.0, 1.0)))
+            .with_component(Arc::new(TestComponent::from_parameters(
+                TestComponentParameters { p: parameters["p"] },
+            )))
+            .with_component(Arc::new(TestDecayComponent {
+                tau: parameters["tau"],
+            }))
+            .with_exogenous_variable(
+                "Emissions|CO2",
+                Timeseries::from_values(array![10.0, 10.0], array![2000.0, 2100.0]),
+            )
+            .with_initial_values(InputState::from_vectors(
+                vec![0.0, 2.0],
+                vec!["Concentrations|CO2".to_string(), "Stock".to_string()],
+            ));
+        builder
+    }
+
+    fn linearisation() -> Linearisation {
+        let parameters = ParameterSet::from([("p".to_string(), 0.5), ("tau".to_string(), 4.0)]);
+        linearise(
+            &parameters,
+            &["p".to_string(), "tau".to_string()],
+            &["Stock".to_string()],
+            &["Stock".to_string(), "Concentrations|CO2".to_string()],
+            build,
+            &GradientOptions::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn jacobians() {
+        let linearisation = linearisation();
+        assert_eq!(linearisation.reference.len(), 12);
+        assert_eq!(linearisation.parameter_jacobian.shape(), (12, 2));
+        assert_eq!(linearisation.state_jacobian.shape(), (12, 1));
+
+        // The stock decays by exp(-1 / tau) each year
+        for n in 0..6 {
+            let expected = (-(n as FloatValue) / 4.0).exp();
+            let index = linearisation.index("Stock", n).unwrap();
+            assert!(is_close!(
+                linearisation.state_jacobian[(index, 0)],
+                expected,
+                rel_tol = 1e-6
+            ));
+        }
+
+        // The concentration only depends on p
+        let index = linearisation.index("Concentrations|CO2", 3).unwrap();
+        assert!(is_close!(
+            linearisation.parameter_jacobian[(index, 0)],
+            1.3,
+            rel_tol = 1e-6
+        ));
+        assert!(is_close!(
+            linearisation.parameter_jacobian[(index, 1)],
+            0.0,
+            abs_tol = 1e-6
+        ));
+        assert!(linearisation.index("Concentrations|CO2", 6).is_none());
+        assert!(linearisation.index("Unknown", 0).is_none());
+    }
+
+    #[test]
+    fn matches_dual_numbers() {
+        let linearisation = linearisation();
+        let parameters = ParameterSet::from([("p".to_string(), 0.5), ("tau".to_string(), 4.0)]);
+
+        for (column, parameter) in ["p", "tau"].iter().enumerate() {
+            let mut model = build(&parameters).with_sensitivity(parameter).build();
+            model.run();
+            let sensitivity = model.sensitivity("Stock").unwrap();
+            for n in 0..6 {
+                assert!(is_close!(
+                    linearisation.parameter_jacobian[(n, column)],
+                    sensitivity.values()[n],
+                    rel_tol = 1e-6,
+                    abs_tol = 1e-8
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn prediction() {
+        let linearisation = linearisation();
+
+        // The stock is linear in its initial value so the prediction is exact
+        let predicted = linearisation.predict(&[0.0, 0.0, 1.0]).unwrap();
+        let mut builder = build(&ParameterSet::from([
+            ("p".to_string(), 0.5),
+            ("tau".to_string(), 4.0),
+        ]));
+        builder.with_initial_values(InputState::from_vectors(
+            vec![3.0],
+            vec!["Stock".to_string()],
+        ));
+        let mut model = builder.build();
+        model.run();
+        let stock = model.timeseries().get_timeseries_by_name("Stock").unwrap();
+        for n in 0..6 {
+            assert!(is_close!(predicted[n], stock.values()[n], rel_tol = 1e-6));
+        }
+        assert!(linearisation.predict(&[0.0]).is_err());
+
+        let covariance = linearisation
+            .propagate_covariance(&DMatrix::from_diagonal_element(3, 3, 0.01))
+            .unwrap();
+        assert_eq!(covariance.shape(), (12, 12));
+        assert!(is_close!(
+            covariance[(0, 0)],
+            0.01 * linearisation.state_jacobian[(0, 0)].powi(2),
+            abs_tol = 1e-9
+        ));
+        assert!(linearisation
+            .propagate_covariance(&DMatrix::identity(2, 2))
+            .is_err());
+    }
+
+    #[test]
+    fn export() {
+        let exported: serde_json::Value =
+            serde_json::from_str(&linearisation().to_json().unwrap()).unwrap();
+
+        assert_eq!(exported["parameters"], json!(["p", "tau"]));
+        assert_eq!(exported["state_jacobian"].as_array().unwrap().len(), 12);
+        assert_eq!(
+            exported["parameter_jacobian"][0].as_array().unwrap().len(),
+            2
+        );
+    }
+}
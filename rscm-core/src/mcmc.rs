@@ -0,0 +1,849 @@
+//! MCMC calibrations and using their output
+//!
+//! [`adaptive_metropolis`] samples the posterior distribution of a set of parameters
+//! given their prior distribution and the likelihood of observational constraints.
+//! [`sample_posterior`] uses a [`CostFunction`] of model output as the negative log-likelihood.
+//!
+//! [`Chains`] stores the draws from one or more MCMC chains.
+//! Chains are serialisable so that the output of a calibration can be stored
+//! and later used to make projections.
+//!
+//! [`posterior_predictive`] thins the chains and runs the model for each retained draw
+//! using the exogenous data of a scenario.
+//! The resulting ensemble represents the posterior predictive distribution of the projection.
+//!
+//! Convergence of the chains can be checked using [`Chains::diagnostics`],
+//! which calculates the split-$\hat{R}$ and effective sample size of each parameter
+//! following Vehtari et al. (2021).
+//! [`Chains::to_arviz_json`] exports the chains in the JSON format read by `arviz.from_json`
+//! so that standard Bayesian tooling can be used to inspect them.
+use crate::calibration::ParameterSet;
+use crate::component::ModelRng;
+use crate::distributions::{normal_ppf, JointDistribution};
+use crate::ensemble::{run_ensemble, EnsembleResult, FailurePolicy};
+use crate::errors::{RSCMError, RSCMResult};
+use crate::model::ModelBuilder;
+use crate::optimise::{run_cost, CostFunction};
+use crate::sampling::RandomSampler;
+use crate::timeseries::FloatValue;
+use crate::timeseries_collection::{RunKey, TimeseriesCollection};
+use nalgebra::{DMatrix, DVector};
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// Draws from a set of MCMC chains
+///
+/// Each draw contains a value for each parameter in the same order as `parameters`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chains {
+    /// Names of the parameters
+    pub parameters: Vec<String>,
+    /// Draws of each chain indexed by `[chain][draw][parameter]`
+    pub draws: Vec<Vec<Vec<FloatValue>>>,
+}
+
+impl Chains {
+    pub fn new(parameters: Vec<String>) -> Self {
+        Self {
+            parameters,
+            draws: vec![],
+        }
+    }
+
+    /// Add a chain
+    ///
+    /// Returns an error if any draw doesn't have a value for each parameter.
+    pub fn add_chain(&mut self, draws: Vec<Vec<FloatValue>>) -> RSCMResult<&mut Self> {
+        if let Some(draw) = draws.iter().find(|d| d.len() != self.parameters.len()) {
+            return Err(RSCMError::Error(format!(
+                "Expected {} parameter values per draw, got {}",
+                self.parameters.len(),
+                draw.len()
+            )));
+        }
+        self.draws.push(draws);
+        Ok(self)
+    }
+
+    pub fn n_chains(&self) -> usize {
+        self.draws.len()
+    }
+
+    /// Values of a parameter in each chain
+    pub fn parameter(&self, name: &str) -> RSCMResult<Vec<Vec<FloatValue>>> {
+        let index = self
+            .parameters
+            .iter()
+            .position(|p| p == name)
+            .ok_or_else(|| RSCMError::Error(format!("Unknown parameter '{}'", name)))?;
+        Ok(self
+            .draws
+            .iter()
+            .map(|chain| chain.iter().map(|draw| draw[index]).collect())
+            .collect())
+    }
+
+    /// Discard the first `burn_in` draws of each chain and then keep every `thin`th draw
+    pub fn thin(&self, burn_in: usize, thin: usize) -> RSCMResult<Vec<PosteriorDraw>> {
+        if thin == 0 {
+            return Err(RSCMError::Error(
+                "Thinning interval must be at least 1".to_string(),
+            ));
+        }
+        Ok(self
+            .draws
+            .iter()
+            .enumerate()
+            .flat_map(|(chain, draws)| {
+                draws
+                    .iter()
+                    .enumerate()
+                    .skip(burn_in)
+                    .step_by(thin)
+                    .map(move |(draw, values)| PosteriorDraw {
+                        chain,
+                        draw,
+                        parameters: self
+                            .parameters
+                            .iter()
+                            .cloned()
+                            .zip(values.clone())
+                            .collect(),
+                    })
+            })
+            .collect())
+    }
+
+    /// Convergence diagnostics of each parameter
+    ///
+    /// Each chain is split in half before calculating the diagnostics
+    /// so that non-stationary chains are detected.
+    /// All chains must have the same number of draws and at least four draws are required.
+    pub fn diagnostics(&self) -> RSCMResult<Vec<ParameterDiagnostics>> {
+        let n_draws = self.draws.first().map_or(0, |chain| chain.len());
+        if n_draws < 4 {
+            return Err(RSCMError::Error(
+                "At least four draws are required for diagnostics".to_string(),
+            ));
+        }
+        if self.draws.iter().any(|chain| chain.len() != n_draws) {
+            return Err(RSCMError::Error(
+                "All chains must have the same number of draws".to_string(),
+            ));
+        }
+
+        self.parameters
+            .iter()
+            .map(|name| {
+                let chains = self.parameter(name)?;
+                let values: Vec<FloatValue> = chains.iter().flatten().copied().collect();
+                let mean = values.iter().sum::<FloatValue>() / values.len() as FloatValue;
+                let variance = values
+                    .iter()
+                    .map(|v| (v - mean).powi(2))
+                    .sum::<FloatValue>()
+                    / (values.len() - 1) as FloatValue;
+
+                let split = split_chains(&chains);
+                Ok(ParameterDiagnostics {
+                    name: name.clone(),
+                    mean,
+                    std: variance.sqrt(),
+                    r_hat: r_hat(&split),
+                    ess: effective_sample_size(&split),
+                })
+            })
+            .collect()
+    }
+
+    /// Export the chains as JSON which can be read using `arviz.from_json`
+    ///
+    /// Each parameter is stored in the `posterior` group with dimensions `(chain, draw)`.
+    pub fn to_arviz_json(&self) -> RSCMResult<String> {
+        let posterior = self
+            .parameters
+            .iter()
+            .map(|name| Ok((name.clone(), json!(self.parameter(name)?))))
+            .collect::<RSCMResult<serde_json::Map<_, _>>>()?;
+        serde_json::to_string(&json!({
+            "posterior": posterior,
+            "attrs": {"inference_library": "rscm"},
+        }))
+        .map_err(|e| RSCMError::Error(format!("Failed to serialise chains: {}", e)))
+    }
+}
+
+/// Convergence diagnostics of a parameter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParameterDiagnostics {
+    pub name: String,
+    /// Mean over all draws
+    pub mean: FloatValue,
+    /// Standard deviation over all draws
+    pub std: FloatValue,
+    /// Potential scale reduction factor
+    ///
+    /// Values close to 1 indicate that the chains have converged.
+    /// Values greater than 1.01 suggest that more draws are required.
+    pub r_hat: FloatValue,
+    /// Effective number of independent draws
+    pub ess: FloatValue,
+}
+
+/// Split each chain into two halves, dropping the middle draw of chains with an odd length
+fn split_chains(chains: &[Vec<FloatValue>]) -> Vec<Vec<FloatValue>> {
+    chains
+        .iter()
+        .flat_map(|chain| {
+            let half = chain.len() / 2;
+            [chain[..half].to_vec(), chain[chain.len() - half..].to_vec()]
+        })
+        .collect()
+}
+
+fn mean(values: &[FloatValue]) -> FloatValue {
+    values.iter().sum::<FloatValue>() / values.len() as FloatValue
+}
+
+/// Mean of the within-chain variances and the pooled estimate of the posterior variance
+fn variances(chains: &[Vec<FloatValue>]) -> (FloatValue, FloatValue) {
+    let m = chains.len() as FloatValue;
+    let n = chains[0].len() as FloatValue;
+    let means: Vec<FloatValue> = chains.iter().map(|c| mean(c)).collect();
+    let overall_mean = mean(&means);
+
+    let within = chains
+        .iter()
+        .zip(means.iter())
+        .map(|(c, chain_mean)| {
+            c.iter()
+                .map(|v| (v - chain_mean).powi(2))
+                .sum::<FloatValue>()
+                / (n - 1.0)
+        })
+        .sum::<FloatValue>()
+        / m;
+    let between = means
+        .iter()
+        .map(|chain_mean| (chain_mean - overall_mean).powi(2))
+        .sum::<FloatValue>()
+        / (m - 1.0);
+
+    (within, (n - 1.0) / n * within + between)
+}
+
+fn r_hat(chains: &[Vec<FloatValue>]) -> FloatValue {
+    let (within, pooled) = variances(chains);
+    if within == 0.0 {
+        return FloatValue::NAN;
+    }
+    (pooled / within).sqrt()
+}
+
+/// Autocovariance of a chain at a lag, normalised by the length of the chain
+fn autocovariance(chain: &[FloatValue], lag: usize) -> FloatValue {
+    let chain_mean = mean(chain);
+    chain
+        .iter()
+        .zip(chain[lag..].iter())
+        .map(|(a, b)| (a - chain_mean) * (b - chain_mean))
+        .sum::<FloatValue>()
+        / chain.len() as FloatValue
+}
+
+/// Effective sample size using Geyer's initial monotone sequence estimator
+fn effective_sample_size(chains: &[Vec<FloatValue>]) -> FloatValue {
+    let m = chains.len() as FloatValue;
+    let n = chains[0].len();
+    let (_, pooled) = variances(chains);
+    if pooled == 0.0 {
+        return FloatValue::NAN;
+    }
+
+    // Within-chain variance using the same normalisation as the autocovariances
+    let within = chains
+        .iter()
+        .map(|c| autocovariance(c, 0))
+        .sum::<FloatValue>()
+        / m;
+    let autocorrelation = |lag: usize| {
+        let acov = chains
+            .iter()
+            .map(|c| autocovariance(c, lag))
+            .sum::<FloatValue>()
+            / m;
+        1.0 - (within - acov) / pooled
+    };
+
+    // Sum pairs of autocorrelations while they remain positive, forcing them to be monotone
+    let mut sum = 0.0;
+    let mut previous = FloatValue::INFINITY;
+    for lag in (0..n - 1).step_by(2) {
+        let pair = (autocorrelation(lag) + autocorrelation(lag + 1)).min(previous);
+        if pair <= 0.0 {
+            break;
+        }
+        sum += pair;
+        previous = pair;
+    }
+    let tau = (2.0 * sum - 1.0).max(1.0 / (m * n as FloatValue).log10());
+    m * n as FloatValue / tau
+}
+
+/// A single draw from a chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PosteriorDraw {
+    /// Index of the chain
+    pub chain: usize,
+    /// Index of the draw within the chain
+    pub draw: usize,
+    /// Value of each parameter
+    pub parameters: BTreeMap<String, FloatValue>,
+}
+
+impl PosteriorDraw {
+    /// Value of a parameter
+    pub fn get(&self, name: &str) -> RSCMResult<FloatValue> {
+        self.parameters
+            .get(name)
+            .copied()
+            .ok_or_else(|| RSCMError::Error(format!("Unknown parameter '{}'", name)))
+    }
+
+    /// Key which identifies the run of this draw for a scenario
+    pub fn run_key(&self, scenario: &str) -> RunKey {
+        RunKey::new(&format!("chain{}-draw{}", self.chain, self.draw), scenario)
+    }
+}
+
+/// Ensemble of runs using draws from the posterior
+#[derive(Debug, Clone)]
+pub struct PosteriorPredictive {
+    /// Draws used for each member of the ensemble
+    pub draws: Vec<PosteriorDraw>,
+    /// Results of the ensemble
+    ///
+    /// The index of each member is the index of its draw in `draws`.
+    pub ensemble: EnsembleResult,
+}
+
+impl PosteriorPredictive {
+    /// Combine the results of the successful runs into a single collection
+    ///
+    /// Each run is identified using [`PosteriorDraw::run_key`].
+    pub fn collection(&self, scenario: &str) -> RSCMResult<TimeseriesCollection> {
+        let mut collection = TimeseriesCollection::new();
+        for (index, result) in self.ensemble.results.iter() {
+            collection.merge(
+                result
+                    .clone()
+                    .with_run(self.draws[*index].run_key(scenario)),
+            )?;
+        }
+        Ok(collection)
+    }
+}
+
+/// Run the model for thinned draws from the posterior using the exogenous data of a scenario
+///
+/// `build` creates a model builder for a draw.
+/// The timeseries in `scenario` are added to the builder, replacing any exogenous data
+/// with the same name.
+/// Failed runs are handled according to `policy`.
+pub fn posterior_predictive<F>(
+    chains: &Chains,
+    burn_in: usize,
+    thin: usize,
+    scenario: &TimeseriesCollection,
+    build: F,
+    policy: &FailurePolicy,
+) -> RSCMResult<PosteriorPredictive>
+where
+    F: Fn(&PosteriorDraw) -> ModelBuilder,
+{
+    let draws = chains.thin(burn_in, thin)?;
+    let ensemble = run_ensemble(
+        &draws,
+        |draw| {
+            let mut builder = build(draw);
+            builder.with_exogenous_collection(scenario.clone());
+            builder
+        },
+        policy,
+    )?;
+    Ok(PosteriorPredictive { draws, ensemble })
+}
+
+/// Options for [`adaptive_metropolis`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdaptiveMetropolisOptions {
+    /// Number of independent chains
+    ///
+    /// At least two chains are required to calculate [`Chains::diagnostics`].
+    pub chains: usize,
+    /// Number of draws in each chain, including any burn-in
+    pub draws: usize,
+    /// Number of draws before the proposal is adapted to the covariance of the chain
+    pub adaptation_start: usize,
+    /// Standard deviation of the initial proposal relative to that of the prior
+    pub initial_scale: FloatValue,
+    /// Maximum number of draws from the prior used to find a valid starting point for each chain
+    pub max_initial_attempts: usize,
+    pub seed: u64,
+}
+
+impl Default for AdaptiveMetropolisOptions {
+    fn default() -> Self {
+        Self {
+            chains: 4,
+            draws: 2000,
+            adaptation_start: 200,
+            initial_scale: 0.1,
+            max_initial_attempts: 100,
+            seed: 0,
+        }
+    }
+}
+
+/// Output of an MCMC sampler
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SamplerResult {
+    pub chains: Chains,
+    /// Fraction of the proposals which were accepted in each chain
+    ///
+    /// Rates of around 0.2 to 0.5 indicate that the proposal is well tuned.
+    pub acceptance_rates: Vec<FloatValue>,
+}
+
+/// Running mean and covariance of the draws of a chain
+struct RunningCovariance {
+    n: FloatValue,
+    mean: DVector<FloatValue>,
+    /// Sum of the outer products of the deviations from the mean
+    scatter: DMatrix<FloatValue>,
+}
+
+impl RunningCovariance {
+    fn new(dimensions: usize) -> Self {
+        Self {
+            n: 0.0,
+            mean: DVector::zeros(dimensions),
+            scatter: DMatrix::zeros(dimensions, dimensions),
+        }
+    }
+
+    fn push(&mut self, x: &DVector<FloatValue>) {
+        self.n += 1.0;
+        let delta = x - &self.mean;
+        self.mean += &delta / self.n;
+        self.scatter += &delta * (x - &self.mean).transpose();
+    }
+
+    fn covariance(&self) -> DMatrix<FloatValue> {
+        &self.scatter / (self.n - 1.0)
+    }
+}
+
+/// Sample the posterior distribution of a set of parameters using the adaptive Metropolis
+/// algorithm (Haario et al., 2001)
+///
+/// The posterior is the product of the `prior` and the likelihood,
+/// where `ln_likelihood` calculates the natural logarithm of the likelihood of a parameter set.
+/// Proposals outside of the support of the prior are rejected without evaluating the likelihood.
+///
+/// Each chain starts from a random draw from the prior.
+/// The proposal is a multivariate normal with a covariance of `initial_scale^2` times
+/// the covariance of the prior until `adaptation_start` draws have been made,
+/// after which it is `2.38^2 / d` times the covariance of the previous draws of the chain,
+/// where `d` is the number of parameters.
+/// Every draw is retained, so burn-in should be discarded using [`Chains::thin`].
+pub fn adaptive_metropolis<F>(
+    prior: &JointDistribution,
+    mut ln_likelihood: F,
+    options: &AdaptiveMetropolisOptions,
+) -> RSCMResult<SamplerResult>
+where
+    F: FnMut(&ParameterSet) -> RSCMResult<FloatValue>,
+{
+    prior.validate()?;
+    let names: Vec<String> = prior.names().iter().map(|n| n.to_string()).collect();
+    let d = names.len();
+    if d == 0 || options.chains == 0 || options.draws == 0 {
+        return Err(RSCMError::Error(
+            "At least one parameter, chain and draw are required".to_string(),
+        ));
+    }
+
+    let mut ln_posterior = |x: &DVector<FloatValue>| -> RSCMResult<FloatValue> {
+        let ln_prior = prior.ln_pdf(x.as_slice())?;
+        if !ln_prior.is_finite() {
+            return Ok(FloatValue::NEG_INFINITY);
+        }
+        let parameters: ParameterSet = names.iter().cloned().zip(x.iter().copied()).collect();
+        let ln_posterior = ln_prior + ln_likelihood(&parameters)?;
+        Ok(match ln_posterior.is_nan() {
+            true => FloatValue::NEG_INFINITY,
+            false => ln_posterior,
+        })
+    };
+
+    // Covariance of the prior, estimated from random draws
+    let mut prior_covariance = RunningCovariance::new(d);
+    prior
+        .sample(1000, &mut RandomSampler::new(options.seed))?
+        .into_iter()
+        .for_each(|draw| prior_covariance.push(&DVector::from_vec(draw)));
+    let prior_covariance = prior_covariance.covariance();
+    let regularisation = DMatrix::from_diagonal(&(prior_covariance.diagonal() * 1e-10));
+    let initial_proposal = (&prior_covariance * options.initial_scale.powi(2))
+        .cholesky()
+        .map(|c| c.l())
+        .ok_or_else(|| RSCMError::Error("Covariance of the prior is singular".to_string()))?;
+    let scale = 2.38_f64.powi(2) / d as FloatValue;
+
+    let mut chains = Chains::new(names.clone());
+    let mut acceptance_rates = vec![];
+    for chain in 0..options.chains {
+        let mut rng = ModelRng::seed_from_u64(options.seed.wrapping_add(1 + chain as u64));
+
+        let mut starts = RandomSampler::new(rng.gen());
+        let mut start = None;
+        for _ in 0..options.max_initial_attempts {
+            let x = DVector::from_vec(prior.sample(1, &mut starts)?.remove(0));
+            let value = ln_posterior(&x)?;
+            if value.is_finite() {
+                start = Some((x, value));
+                break;
+            }
+        }
+        let (mut x, mut current) = start.ok_or_else(|| {
+            RSCMError::Error(format!(
+                "No valid starting point for chain {} after {} attempts",
+                chain, options.max_initial_attempts
+            ))
+        })?;
+
+        let mut history = RunningCovariance::new(d);
+        let mut draws = vec![];
+        let mut accepted = 0;
+        for draw in 0..options.draws {
+            let proposal = match draw >= options.adaptation_start && history.n > d as FloatValue {
+                true => (history.covariance() * scale + &regularisation)
+                    .cholesky()
+                    .map_or(initial_proposal.clone(), |c| c.l()),
+                false => initial_proposal.clone(),
+            };
+            let z = DVector::from_fn(d, |_, _| {
+                normal_ppf(rng.gen_range(FloatValue::EPSILON..1.0))
+            });
+            let candidate = &x + proposal * z;
+            let value = ln_posterior(&candidate)?;
+
+            if value - current >= rng.gen::<FloatValue>().ln() {
+                x = candidate;
+                current = value;
+                accepted += 1;
+            }
+            history.push(&x);
+            draws.push(x.iter().copied().collect());
+        }
+        chains.add_chain(draws)?;
+        acceptance_rates.push(accepted as FloatValue / options.draws as FloatValue);
+    }
+
+    Ok(SamplerResult {
+        chains,
+        acceptance_rates,
+    })
+}
+
+/// Sample the posterior distribution of the parameters of a model
+///
+/// `build` creates a model builder for a parameter set and `cost` is the negative
+/// log-likelihood of the output of a run, e.g. [`crate::optimise::GaussianLikelihood`].
+/// Parameter sets for which the model fails to run have zero likelihood.
+/// See [`adaptive_metropolis`] for details of the sampler.
+pub fn sample_posterior<F, C>(
+    prior: &JointDistribution,
+    build: F,
+    cost: &C,
+    options: &AdaptiveMetropolisOptions,
+) -> RSCMResult<SamplerResult>
+where
+    F: Fn(&ParameterSet) -> ModelBuilder,
+    C: CostFunction + ?Sized,
+{
+    adaptive_metropolis(
+        prior,
+        |parameters| Ok(-run_cost(&build(parameters), cost)?),
+        options,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calibration::Target;
+    use crate::distributions::Distribution;
+    use crate::example_components::{build_test_model, TestComponent, TestComponentParameters};
+    use crate::optimise::GaussianLikelihood;
+    use crate::sampling::Sampler;
+    use crate::timeseries::{TimeAxis, Timeseries};
+    use crate::timeseries_collection::VariableType;
+    use is_close::is_close;
+    use numpy::array;
+    use numpy::ndarray::Array;
+    use std::sync::Arc;
+
+    fn chains() -> Chains {
+        let mut chains = Chains::new(vec!["p".to_string(), "sigma".to_string()]);
+        chains
+            .add_chain((0..10).map(|i| vec![0.1 * i as FloatValue, 1.0]).collect())
+            .unwrap()
+            .add_chain((0..10).map(|i| vec![-0.1 * i as FloatValue, 1.0]).collect())
+            .unwrap();
+        chains
+    }
+
+    #[test]
+    fn thinning() {
+        let draws = chains().thin(5, 2).unwrap();
+
+        assert_eq!(
+            draws.iter().map(|d| (d.chain, d.draw)).collect::<Vec<_>>(),
+            [(0, 5), (0, 7), (0, 9), (1, 5), (1, 7), (1, 9)]
+        );
+        assert_eq!(draws[1].get("p").unwrap(), 0.1 * 7.0);
+        assert!(draws[1].get("q").is_err());
+
+        assert!(chains().thin(0, 0).is_err());
+        assert!(chains().add_chain(vec![vec![1.0]]).is_err());
+        assert_eq!(chains().parameter("sigma").unwrap(), vec![vec![1.0; 10]; 2]);
+    }
+
+    fn random_chains(offsets: &[FloatValue], autocorrelation: FloatValue) -> Chains {
+        let mut sampler = RandomSampler::new(42);
+        let mut chains = Chains::new(vec!["x".to_string()]);
+        for offset in offsets {
+            let mut value = 0.0;
+            let draws = sampler
+                .sample(1000, 1)
+                .unwrap()
+                .iter()
+                .map(|point| {
+                    value = autocorrelation * value + point[0] - 0.5;
+                    vec![value + offset]
+                })
+                .collect();
+            chains.add_chain(draws).unwrap();
+        }
+        chains
+    }
+
+    #[test]
+    fn diagnostics_of_independent_draws() {
+        let diagnostics = random_chains(&[0.0; 4], 0.0).diagnostics().unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].name, "x");
+        assert!(diagnostics[0].mean.abs() < 0.05);
+        assert!(is_close!(
+            diagnostics[0].std,
+            (1.0 / 12.0 as FloatValue).sqrt(),
+            rel_tol = 0.05
+        ));
+        assert!(diagnostics[0].r_hat < 1.01);
+        assert!(diagnostics[0].ess > 3000.0);
+    }
+
+    #[test]
+    fn diagnostics_of_poorly_mixed_chains() {
+        let autocorrelated = random_chains(&[0.0; 4], 0.95).diagnostics().unwrap();
+        assert!(autocorrelated[0].ess < 400.0);
+
+        let separated = random_chains(&[0.0, 0.0, 0.0, 2.0], 0.0)
+            .diagnostics()
+            .unwrap();
+        assert!(separated[0].r_hat > 1.5);
+    }
+
+    #[test]
+    fn diagnostics_require_draws() {
+        let mut chains = Chains::new(vec!["x".to_string()]);
+        assert!(chains.diagnostics().is_err());
+
+        chains
+            .add_chain(vec![vec![1.0]; 10])
+            .unwrap()
+            .add_chain(vec![vec![1.0]; 8])
+            .unwrap();
+        assert!(chains.diagnostics().is_err());
+    }
+
+    #[test]
+    fn arviz_export() {
+        let exported: serde_json::Value =
+            serde_json::from_str(&chains().to_arviz_json().unwrap()).unwrap();
+
+        assert_eq!(
+            exported["posterior"]["sigma"],
+            json!(vec![vec![1.0; 10]; 2])
+        );
+        assert_eq!(exported["posterior"]["p"][1][2], json!(-0.2));
+    }
+
+    #[test]
+    fn serialisation() {
+        let chains = chains();
+        let serialised = toml::to_string(&chains).unwrap();
+        assert_eq!(toml::from_str::<Chains>(&serialised).unwrap(), chains);
+    }
+
+    #[test]
+    fn predictive_runs() {
+        let mut scenario = TimeseriesCollection::new();
+        scenario
+            .add_timeseries(
+                "Emissions|CO2".to_string(),
+                Timeseries::from_values(array![10.0, 20.0], array![2000.0, 2100.0]),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+
+        let predictive = posterior_predictive(
+            &chains(),
+            8,
+            1,
+            &scenario,
+            |draw| {
+                let mut builder = ModelBuilder::new();
+                builder
+                    .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+                    .with_component(Arc::new(TestComponent::from_parameters(
+                        TestComponentParameters {
+                            p: draw.get("p").unwrap(),
+                        },
+                    )));
+                builder
+            },
+            &FailurePolicy::AbortAll,
+        )
+        .unwrap();
+
+        assert_eq!(predictive.draws.len(), 4);
+        assert_eq!(predictive.ensemble.results.len(), 4);
+
+        let collection = predictive.collection("ssp245").unwrap();
+        assert_eq!(collection.runs().len(), 4);
+        let run = RunKey::new("chain1-draw9", "ssp245");
+        assert!(collection
+            .get_by_name_and_run("Concentrations|CO2", &run)
+            .is_some());
+    }
+
+    #[test]
+    fn normal_posterior() {
+        // A standard normal prior and a single observation of 1 with unit variance
+        // give a normal posterior with a mean of 0.5 and a variance of 0.5
+        let mut prior = JointDistribution::new();
+        prior.with_parameter(
+            "x",
+            Distribution::Normal {
+                mean: 0.0,
+                std: 1.0,
+            }
+            .into(),
+        );
+        let options = AdaptiveMetropolisOptions {
+            draws: 5000,
+            seed: 3,
+            ..Default::default()
+        };
+        let result =
+            adaptive_metropolis(&prior, |p| Ok(-0.5 * (p["x"] - 1.0).powi(2)), &options).unwrap();
+
+        assert_eq!(result.chains.n_chains(), 4);
+        assert!(result
+            .acceptance_rates
+            .iter()
+            .all(|rate| (0.2..0.8).contains(rate)));
+
+        let draws: Vec<FloatValue> = result
+            .chains
+            .thin(1000, 1)
+            .unwrap()
+            .iter()
+            .map(|d| d.get("x").unwrap())
+            .collect();
+        let mean = mean(&draws);
+        let variance = draws.iter().map(|x| (x - mean).powi(2)).sum::<FloatValue>()
+            / (draws.len() - 1) as FloatValue;
+        assert!(is_close!(mean, 0.5, abs_tol = 0.05));
+        assert!(is_close!(variance, 0.5, abs_tol = 0.05));
+
+        let diagnostics = result.chains.diagnostics().unwrap();
+        assert!(diagnostics[0].r_hat < 1.05);
+
+        // The same seed gives the same chains
+        let repeated =
+            adaptive_metropolis(&prior, |p| Ok(-0.5 * (p["x"] - 1.0).powi(2)), &options).unwrap();
+        assert_eq!(repeated, result);
+    }
+
+    #[test]
+    fn model_posterior() {
+        let mut model = build_test_model(&ParameterSet::from([("p".to_string(), 0.5)])).build();
+        model.run();
+        let observations = model
+            .timeseries()
+            .get_timeseries_by_name("Concentrations|CO2")
+            .unwrap()
+            .clone();
+        let likelihood = GaussianLikelihood {
+            target: Target::new("Concentrations|CO2", observations, 2021.0, 2024.0),
+            sigma: 0.5,
+        };
+
+        let mut prior = JointDistribution::new();
+        prior.with_parameter("p", Distribution::Uniform { min: 0.0, max: 2.0 }.into());
+        let result = sample_posterior(
+            &prior,
+            build_test_model,
+            &likelihood,
+            &AdaptiveMetropolisOptions {
+                chains: 2,
+                draws: 500,
+                adaptation_start: 100,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let draws: Vec<FloatValue> = result
+            .chains
+            .thin(100, 1)
+            .unwrap()
+            .iter()
+            .map(|d| d.get("p").unwrap())
+            .collect();
+        assert!(draws.iter().all(|p| (0.0..=2.0).contains(p)));
+        assert!(is_close!(mean(&draws), 0.5, abs_tol = 0.05));
+    }
+
+    #[test]
+    fn no_valid_start() {
+        let mut prior = JointDistribution::new();
+        prior.with_parameter("x", Distribution::Uniform { min: 0.0, max: 1.0 }.into());
+        let result = adaptive_metropolis(
+            &prior,
+            |_| Ok(FloatValue::NEG_INFINITY),
+            &AdaptiveMetropolisOptions {
+                max_initial_attempts: 3,
+                ..Default::default()
+            },
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! Metadata describing how the results of a run were produced
+//!
+//! Each [`crate::model::Model`] carries [`RunMetadata`] which is serialised with the model
+//! and stored with its results in a [`crate::run_database::RunDatabase`],
+//! so results can be traced back to the scenario, parameters and version of rscm
+//! which produced them.
+use crate::calibration::ParameterSet;
+use crate::timeseries::FloatValue;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Scenario, parameters, versions and timing of a run
+///
+/// Metadata deserialised from models saved before metadata was recorded is empty.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunMetadata {
+    /// Name of the scenario which was run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<String>,
+    /// Values of the parameters used to build the model
+    #[serde(skip_serializing_if = "ParameterSet::is_empty")]
+    pub parameters: ParameterSet,
+    /// Version of each crate used to build the model, e.g. "rscm-core"
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub versions: BTreeMap<String, String>,
+    /// Hash of the git commit rscm was built from
+    ///
+    /// This is only known when rscm is built from a git repository.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_hash: Option<String>,
+    /// Time at which the model started running
+    ///
+    /// unit: seconds since the Unix epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started: Option<FloatValue>,
+    /// Wall-clock time spent running the model
+    ///
+    /// unit: s
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wall_time: Option<FloatValue>,
+}
+
+impl RunMetadata {
+    /// Metadata of a new run, recording the version of rscm-core
+    pub fn new() -> Self {
+        Self {
+            versions: BTreeMap::from([(
+                env!("CARGO_PKG_NAME").to_string(),
+                env!("CARGO_PKG_VERSION").to_string(),
+            )]),
+            git_hash: option_env!("RSCM_GIT_HASH").map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    /// True if nothing is known about the run
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Record the timing of a call which ran the model
+    ///
+    /// The start time of the first call is kept and the durations of every call are summed.
+    pub(crate) fn record_run(&mut self, started: SystemTime, elapsed: Duration) {
+        if self.started.is_none() {
+            self.started = started
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|since_epoch| since_epoch.as_secs_f64());
+        }
+        self.wall_time = Some(self.wall_time.unwrap_or(0.0) + elapsed.as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new() {
+        let metadata = RunMetadata::new();
+        assert_eq!(
+            metadata.versions.get("rscm-core").map(String::as_str),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+        assert!(metadata.started.is_none());
+        assert!(metadata.wall_time.is_none());
+
+        // Models saved before metadata was recorded have no versions
+        let empty: RunMetadata = toml::from_str("").unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(toml::to_string(&empty).unwrap(), "");
+    }
+
+    #[test]
+    fn record_run() {
+        let mut metadata = RunMetadata::new();
+        let started = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        metadata.record_run(started, Duration::from_millis(1500));
+        metadata.record_run(
+            started + Duration::from_secs(60),
+            Duration::from_millis(500),
+        );
+
+        assert_eq!(metadata.started, Some(1_700_000_000.0));
+        assert_eq!(metadata.wall_time, Some(2.0));
+    }
+}
@@ -0,0 +1,315 @@
+//! Comparison of model output against reference datasets
+//!
+//! Model output is compared against a reference (e.g. observations) at the time points of
+//! the reference that fall within a window.
+//! The model is interpolated onto these time points.
+//! Any time points where either the model or the reference are NaN are ignored.
+use crate::errors::{RSCMError, RSCMResult};
+use crate::sparse_timeseries::SparseTimeseries;
+use crate::timeseries::{FloatValue, Time, Timeseries};
+use crate::timeseries_collection::TimeseriesCollection;
+use pyo3::pyclass;
+use serde::{Deserialize, Serialize};
+use std::iter::zip;
+
+/// Skill scores of a model relative to a reference over a window
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Skill {
+    /// Number of time points that were compared
+    pub n: usize,
+    /// Root-mean-square error
+    pub rmse: FloatValue,
+    /// Mean of the model minus the reference
+    pub bias: FloatValue,
+    /// Pearson correlation coefficient
+    ///
+    /// NaN if either the model or the reference is constant over the window
+    pub correlation: FloatValue,
+    /// Linear trend of the model minus that of the reference
+    ///
+    /// unit: units of the timeseries per unit of time
+    pub trend_difference: FloatValue,
+}
+
+/// Skill of a single variable over a window
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreCardEntry {
+    pub variable: String,
+    pub start: Time,
+    pub end: Time,
+    pub skill: Skill,
+}
+
+/// Skill scores for a set of variables and windows
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreCard {
+    pub entries: Vec<ScoreCardEntry>,
+}
+
+impl ScoreCard {
+    /// Get the skill of a variable over a window
+    pub fn get(&self, variable: &str, start: Time, end: Time) -> Option<&Skill> {
+        self.entries
+            .iter()
+            .find(|e| e.variable == variable && e.start == start && e.end == end)
+            .map(|e| &e.skill)
+    }
+}
+
+/// Ordinary least-squares slope of `y` against `x`
+fn trend(x: &[FloatValue], y: &[FloatValue]) -> FloatValue {
+    let n = x.len() as FloatValue;
+    let x_mean = x.iter().sum::<FloatValue>() / n;
+    let y_mean = y.iter().sum::<FloatValue>() / n;
+
+    let covariance: FloatValue = x
+        .iter()
+        .zip(y)
+        .map(|(x, y)| (x - x_mean) * (y - y_mean))
+        .sum();
+    let variance: FloatValue = x.iter().map(|x| (x - x_mean).powi(2)).sum();
+
+    covariance / variance
+}
+
+/// Calculate the skill of a model relative to a reference over a window
+///
+/// Both ends of the window are inclusive.
+/// Returns an error if the units differ or if there are no valid time points within the window.
+pub fn skill(
+    model: &Timeseries<FloatValue>,
+    reference: &Timeseries<FloatValue>,
+    start: Time,
+    end: Time,
+) -> RSCMResult<Skill> {
+    if model.units() != reference.units() {
+        return Err(RSCMError::WrongUnits(
+            reference.units().to_string(),
+            model.units().to_string(),
+        ));
+    }
+
+    let time_axis = reference.time_axis();
+    let indices = time_axis.window_indices(start, end).unwrap_or(0..0);
+    let points = indices.map(|index| (time_axis.at(index).unwrap(), reference.at(index).unwrap()));
+
+    skill_at_points(model, points, start, end)
+}
+
+/// Calculate the skill of a model relative to observations at arbitrary times
+///
+/// The model is compared at each observation time within the window,
+/// with both ends of the window inclusive.
+/// Returns an error if the units differ or if there are no valid time points within the window.
+pub fn skill_at_observations(
+    model: &Timeseries<FloatValue>,
+    observations: &SparseTimeseries,
+    start: Time,
+    end: Time,
+) -> RSCMResult<Skill> {
+    if model.units() != observations.units() {
+        return Err(RSCMError::WrongUnits(
+            observations.units().to_string(),
+            model.units().to_string(),
+        ));
+    }
+
+    let points = zip(observations.times(), observations.values())
+        .map(|(time, value)| (*time, *value))
+        .filter(|(time, _)| (start..=end).contains(time));
+
+    skill_at_points(model, points, start, end)
+}
+
+/// Skill of a model relative to reference values at a set of time points
+fn skill_at_points(
+    model: &Timeseries<FloatValue>,
+    points: impl Iterator<Item = (Time, FloatValue)>,
+    start: Time,
+    end: Time,
+) -> RSCMResult<Skill> {
+    let mut times = vec![];
+    let mut model_values = vec![];
+    let mut reference_values = vec![];
+    for (time, reference_value) in points {
+        let model_value = model.at_time(time)?;
+
+        if !(reference_value.is_nan() || model_value.is_nan()) {
+            times.push(time);
+            model_values.push(model_value);
+            reference_values.push(reference_value);
+        }
+    }
+
+    let n = times.len();
+    if n == 0 {
+        return Err(RSCMError::Error(format!(
+            "No valid values to compare within the window [{}, {}]",
+            start, end
+        )));
+    }
+
+    let differences: Vec<FloatValue> = model_values
+        .iter()
+        .zip(reference_values.iter())
+        .map(|(m, r)| m - r)
+        .collect();
+    let bias = differences.iter().sum::<FloatValue>() / n as FloatValue;
+    let rmse = (differences.iter().map(|d| d.powi(2)).sum::<FloatValue>() / n as FloatValue).sqrt();
+
+    // The correlation is the slope of the standardised values
+    let correlation = {
+        let standardise = |values: &[FloatValue]| {
+            let mean = values.iter().sum::<FloatValue>() / n as FloatValue;
+            let std = (values
+                .iter()
+                .map(|v| (v - mean).powi(2))
+                .sum::<FloatValue>()
+                / n as FloatValue)
+                .sqrt();
+            values.iter().map(|v| (v - mean) / std).collect::<Vec<_>>()
+        };
+        trend(&standardise(&reference_values), &standardise(&model_values))
+    };
+
+    Ok(Skill {
+        n,
+        rmse,
+        bias,
+        correlation,
+        trend_difference: trend(&times, &model_values) - trend(&times, &reference_values),
+    })
+}
+
+/// Calculate the skill of a model for each reference variable and window
+///
+/// Each timeseries in `references` is compared against the timeseries with the same name
+/// in `model`.
+/// Returns an error if a reference variable is not present in `model`.
+pub fn score_card(
+    model: &TimeseriesCollection,
+    references: &TimeseriesCollection,
+    windows: &[(Time, Time)],
+) -> RSCMResult<ScoreCard> {
+    let mut entries = vec![];
+
+    for reference in references.iter() {
+        let timeseries = model
+            .get_timeseries_by_name(&reference.name)
+            .ok_or_else(|| RSCMError::TimeseriesNotFound(format!("'{}'", reference.name)))?;
+
+        for (start, end) in windows {
+            entries.push(ScoreCardEntry {
+                variable: reference.name.clone(),
+                start: *start,
+                end: *end,
+                skill: skill(timeseries, &reference.timeseries, *start, *end)?,
+            });
+        }
+    }
+
+    Ok(ScoreCard { entries })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolate::strategies::{InterpolationStrategy, LinearSplineStrategy};
+    use crate::timeseries_collection::VariableType;
+    use is_close::is_close;
+    use numpy::array;
+
+    fn reference() -> Timeseries<FloatValue> {
+        Timeseries::from_values(
+            array![0.0, 1.0, 2.0, FloatValue::NAN, 4.0],
+            array![2000.0, 2001.0, 2002.0, 2003.0, 2004.0],
+        )
+    }
+
+    #[test]
+    fn skill_scores() {
+        // Twice the trend and an offset of 1
+        let model = Timeseries::from_values(
+            array![1.0, 3.0, 5.0, 7.0, 9.0],
+            array![2000.0, 2001.0, 2002.0, 2003.0, 2004.0],
+        );
+
+        let result = skill(&model, &reference(), 2000.0, 2004.0).unwrap();
+        assert_eq!(result.n, 4);
+        assert!(is_close!(result.bias, (1.0 + 2.0 + 3.0 + 5.0) / 4.0));
+        assert!(is_close!(
+            result.rmse,
+            ((1.0 + 4.0 + 9.0 + 25.0) / 4.0_f64).sqrt()
+        ));
+        assert!(is_close!(result.correlation, 1.0));
+        assert!(is_close!(result.trend_difference, 1.0));
+
+        let result = skill(&model, &reference(), 2001.0, 2002.0).unwrap();
+        assert_eq!(result.n, 2);
+        assert!(is_close!(result.bias, 2.5));
+
+        assert!(skill(&model, &reference(), 2010.0, 2020.0).is_err());
+    }
+
+    #[test]
+    fn skill_observations() {
+        let model = Timeseries::from_values(
+            array![1.0, 3.0, 5.0, 7.0, 9.0],
+            array![2000.0, 2001.0, 2002.0, 2003.0, 2004.0],
+        );
+        let observations =
+            SparseTimeseries::new(array![2000.25, 2003.0, 2010.0], array![1.5, 6.0, 0.0], "")
+                .unwrap();
+
+        let result = skill_at_observations(&model, &observations, 2000.0, 2004.0).unwrap();
+        assert_eq!(result.n, 2);
+        assert!(is_close!(result.bias, 0.5));
+        assert!(is_close!(result.correlation, 1.0));
+
+        assert!(skill_at_observations(&model, &observations, 2011.0, 2020.0).is_err());
+    }
+
+    #[test]
+    fn skill_wrong_units() {
+        let model = Timeseries::new(
+            array![1.0, 3.0, 5.0, 7.0, 9.0],
+            reference().time_axis(),
+            "K".to_string(),
+            InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+        );
+
+        assert!(matches!(
+            skill(&model, &reference(), 2000.0, 2004.0),
+            Err(RSCMError::WrongUnits(_, _))
+        ));
+    }
+
+    #[test]
+    fn score_collection() {
+        let mut model = TimeseriesCollection::new();
+        model
+            .add_timeseries(
+                "Surface Temperature".to_string(),
+                reference(),
+                VariableType::Endogenous,
+            )
+            .unwrap();
+        let mut references = TimeseriesCollection::new();
+        references
+            .add_timeseries(
+                "Surface Temperature".to_string(),
+                reference(),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+
+        let card = score_card(&model, &references, &[(2000.0, 2004.0), (2000.0, 2001.0)]).unwrap();
+        assert_eq!(card.entries.len(), 2);
+        let result = card.get("Surface Temperature", 2000.0, 2001.0).unwrap();
+        assert_eq!(result.n, 2);
+        assert_eq!(result.rmse, 0.0);
+        assert_eq!(result.bias, 0.0);
+    }
+}
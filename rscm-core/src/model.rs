@@ -1,3 +1,4 @@
+use crate::calibration::ParameterSet;
 /// A model consists of a series of coupled components which are solved together.
 /// The model orchastrates the passing of state between different components.
 /// Each component is solved for a given time step in an order determined by their
@@ -10,29 +11,52 @@
 /// The required variables are identified when building the model.
 /// If a required exogenous variable isn't provided, then the build step will fail.
 use crate::component::{
-    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+    Component, InputState, ModelRng, OutputState, RequirementDefinition, RequirementType,
+    RngStream, State, TimestepContext,
 };
-use crate::errors::RSCMResult;
-use crate::interpolate::strategies::{InterpolationStrategy, LinearSplineStrategy};
-use crate::timeseries::{FloatValue, Time, TimeAxis, Timeseries};
-use crate::timeseries_collection::{TimeseriesCollection, VariableType};
+use crate::conservation::{MassBalanceCheck, MassBalanceReport};
+use crate::cost::{CostEstimate, CostHint};
+use crate::diagnostics::ModelDiagnostics;
+use crate::dual::{Dual, DualState};
+use crate::errors::{BuildError, BuildProblem, RSCMError, RSCMResult};
+use crate::events::{emit, ModelEvent, ModelObserver};
+use crate::html;
+use crate::interpolate::strategies::{
+    ExtrapolationPolicy, InterpolationStrategy, LinearSplineStrategy, PreviousStrategy,
+};
+use crate::labelled::LabelledTimeseries;
+use crate::metadata::RunMetadata;
+use crate::output::OutputSink;
+use crate::remap::RemappedComponent;
+use crate::submodel::SubModel;
+use crate::timeseries::{DisaggregationStrategy, FloatValue, Time, TimeAxis, Timeseries};
+use crate::timeseries_collection::{matches_pattern, TimeseriesCollection, VariableType};
+use crate::units;
 use numpy::ndarray::Array;
-use petgraph::dot::{Config, Dot};
 use petgraph::graph::NodeIndex;
-use petgraph::visit::{Bfs, IntoNeighbors, IntoNodeIdentifiers, Visitable};
+use petgraph::visit::{EdgeRef, IntoNeighbors, IntoNodeIdentifiers, Visitable};
 use petgraph::Graph;
+use pyo3::pyclass;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::ops::Index;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 type C = Arc<dyn Component>;
-type CGraph = Graph<C, RequirementDefinition>;
+pub(crate) type CGraph = Graph<C, RequirementDefinition>;
 
 #[derive(Debug)]
 struct VariableDefinition {
     name: String,
     unit: String,
+    default: Option<FloatValue>,
+    disaggregation: DisaggregationStrategy,
 }
 
 impl VariableDefinition {
@@ -40,6 +64,8 @@ impl VariableDefinition {
         Self {
             name: definition.name.clone(),
             unit: definition.unit.clone(),
+            default: definition.default,
+            disaggregation: definition.disaggregation,
         }
     }
 }
@@ -66,6 +92,304 @@ impl Component for NullComponent {
     }
 }
 
+/// Switches to enable or disable named groups of components
+///
+/// This allows a single configuration to describe a family of model variants,
+/// for example, when performing ablation studies.
+/// Groups which aren't listed are enabled.
+///
+/// Toggles can be read from a TOML configuration file:
+///
+/// ```rust
+/// use rscm_core::model::ComponentGroupToggles;
+///
+/// let toggles = ComponentGroupToggles::from_toml(
+///     r#"
+///     [groups]
+///     aerosols = false
+///     carbon-cycle = true
+///     "#,
+/// )
+/// .unwrap();
+/// assert!(!toggles.is_enabled("aerosols"));
+/// assert!(toggles.is_enabled("carbon-cycle"));
+/// assert!(toggles.is_enabled("ocean"));
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ComponentGroupToggles {
+    #[serde(default)]
+    groups: HashMap<String, bool>,
+}
+
+impl ComponentGroupToggles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a set of toggles from a TOML string
+    pub fn from_toml(config: &str) -> RSCMResult<Self> {
+        toml::from_str(config).map_err(|e| RSCMError::Error(format!("Invalid toggles: {}", e)))
+    }
+
+    /// Enable or disable a group
+    pub fn set(&mut self, group: &str, enabled: bool) -> &mut Self {
+        self.groups.insert(group.to_string(), enabled);
+        self
+    }
+
+    /// Check if a group is enabled
+    pub fn is_enabled(&self, group: &str) -> bool {
+        *self.groups.get(group).unwrap_or(&true)
+    }
+}
+
+/// Options for detecting numerical instability in components
+///
+/// After each component is solved, its outputs are compared against their values
+/// at the start of the step.
+/// An output is unstable if it isn't finite or if it changes by more than
+/// `absolute_tolerance + max_relative_change * |previous value|`.
+///
+/// Unstable steps are retried with the internal solver step halved
+/// (see [`TimestepContext::solver_step_size`]) up to `max_refinements` times
+/// before the step fails.
+/// Components without an internal solver produce the same result when retried.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StabilityOptions {
+    pub max_relative_change: FloatValue,
+    pub absolute_tolerance: FloatValue,
+    pub max_refinements: u32,
+}
+
+impl StabilityOptions {
+    fn is_stable(&self, previous: FloatValue, value: FloatValue) -> bool {
+        if !value.is_finite() {
+            return false;
+        }
+        // There is nothing to compare against for the first value of a variable
+        if !previous.is_finite() {
+            return true;
+        }
+        (value - previous).abs()
+            <= self.absolute_tolerance + self.max_relative_change * previous.abs()
+    }
+}
+
+/// How NaN values produced by components are handled
+///
+/// The same policy applies to values outside the bounds declared by the component
+/// which produces them (see [`RequirementDefinition::with_bounds`]).
+#[pyclass]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NanPolicy {
+    /// NaN values are stored like any other value
+    #[default]
+    Allow,
+    /// A warning is recorded (see [`Model::warnings`]) and the values are stored
+    Warn,
+    /// The component fails to solve for the step
+    Error,
+}
+
+/// Category of a [`ModelWarning`]
+///
+/// These allow automated pipelines to treat specific classes of warning as failures.
+#[pyclass]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WarningKind {
+    /// Exogenous data required by a component wasn't provided
+    MissingExogenousData,
+    /// Exogenous data has units which can't be converted to the units required by a component
+    ///
+    /// The data is used unchanged.
+    IncompatibleUnits,
+    /// A component produced NaN values when using [`NanPolicy::Warn`]
+    NanValues,
+    /// A component produced values outside of their declared bounds when using
+    /// [`NanPolicy::Warn`]
+    OutOfBounds,
+    /// A component failed to solve a step
+    ComponentFailed,
+    /// A run was stopped before reaching the end of the time axis
+    RunAborted,
+    /// Exogenous data has time steps which don't align with the model's time steps
+    ///
+    /// Some model time steps span multiple steps of the data so the values can't be
+    /// converted exactly.
+    LossyResampling,
+    /// A conserved quantity didn't balance after a step
+    ///
+    /// See [`ModelBuilder::with_mass_balance_check`]
+    MassBalanceViolation,
+    /// Values couldn't be written to an output sink
+    ///
+    /// See [`Model::add_output_sink`]
+    OutputFailed,
+}
+
+/// A problem found while building or running a model which didn't stop the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelWarning {
+    pub kind: WarningKind,
+    pub message: String,
+    /// Index of the time step which was being solved when the warning was recorded
+    ///
+    /// `None` for warnings recorded while building the model or updating its inputs.
+    #[serde(default)]
+    pub time_index: Option<usize>,
+}
+
+impl ModelWarning {
+    fn new(kind: WarningKind, message: String) -> Self {
+        log::warn!("{}", message);
+        Self {
+            kind,
+            message,
+            time_index: None,
+        }
+    }
+
+    /// Create a warning for a problem found while solving the step at `time_index`
+    fn at_step(kind: WarningKind, message: String, time_index: usize) -> Self {
+        Self {
+            time_index: Some(time_index),
+            ..Self::new(kind, message)
+        }
+    }
+}
+
+/// Options which apply to a whole model
+///
+/// These are set on the builder using [`ModelBuilder::with_options`] and are available to
+/// components via [`TimestepContext::options`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelOptions {
+    /// How NaN values and values outside of their bounds produced by components are handled
+    pub nan_policy: NanPolicy,
+    /// Extrapolation policy of the timeseries created by the model
+    ///
+    /// This applies to the timeseries which are solved by components
+    /// and to the constant timeseries created from the defaults of optional inputs.
+    /// Exogenous timeseries keep their own policy.
+    pub extrapolation: ExtrapolationPolicy,
+    /// Default step size for solvers internal to components
+    ///
+    /// unit: yr
+    pub solver_step: Time,
+    /// Maximum number of threads used when running independent models, e.g. for attribution
+    pub threads: usize,
+    /// Solve components which don't depend on each other concurrently using up to `threads` threads
+    ///
+    /// See [`ModelBuilder::with_parallel_components`]
+    pub parallel_components: bool,
+    /// Panic when building a model with missing exogenous data instead of recording a warning
+    pub strict: bool,
+    /// Options for detecting numerical instability
+    pub stability: Option<StabilityOptions>,
+    /// Limits on the resources used by a run
+    pub limits: Option<RunLimits>,
+    /// Unit contexts which may be used when converting values between components
+    ///
+    /// See [`crate::units`]
+    pub unit_contexts: Vec<String>,
+    /// Parameter whose sensitivity is propagated through the model using dual numbers
+    ///
+    /// See [`ModelBuilder::with_sensitivity`]
+    pub sensitivity: Option<String>,
+    /// Seed of the random number streams of the components
+    ///
+    /// See [`ModelBuilder::with_rng_seed`]
+    pub rng_seed: Option<u64>,
+    /// Index of the model in an ensemble, used to give each member different random numbers
+    ///
+    /// See [`ModelBuilder::with_ensemble_member`]
+    pub ensemble_member: Option<u64>,
+}
+
+pub(crate) static DEFAULT_MODEL_OPTIONS: ModelOptions = ModelOptions {
+    nan_policy: NanPolicy::Allow,
+    extrapolation: ExtrapolationPolicy::Extrapolate,
+    solver_step: 0.1,
+    threads: 1,
+    parallel_components: false,
+    strict: false,
+    stability: None,
+    limits: None,
+    unit_contexts: Vec::new(),
+    sensitivity: None,
+    rng_seed: None,
+    ensemble_member: None,
+};
+
+impl Default for ModelOptions {
+    fn default() -> Self {
+        DEFAULT_MODEL_OPTIONS.clone()
+    }
+}
+
+impl ModelOptions {
+    fn is_default(&self) -> bool {
+        *self == DEFAULT_MODEL_OPTIONS
+    }
+}
+
+/// Record of a step which required refinement to be solved stably
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepRefinement {
+    pub time_index: usize,
+    /// Name of the component's type
+    pub component: String,
+    /// Number of times the step was refined
+    pub refinements: u32,
+}
+
+/// Limits on the resources used by a run
+///
+/// Runs which exceed a limit are aborted with [`RSCMError::LimitExceeded`].
+/// This avoids pathological parameter sets stalling automated workflows such as calibration.
+/// Both limits apply to a single call to [`Model::run`] or [`Model::try_run`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunLimits {
+    /// Maximum wall-clock time
+    pub max_wall_time: Option<Duration>,
+    /// Maximum number of time steps
+    pub max_steps: Option<usize>,
+}
+
+impl RunLimits {
+    /// Check if a run that started at `started` and has taken `steps` steps can take another step
+    fn check(&self, started: Instant, steps: usize) -> RSCMResult<()> {
+        if let Some(max_steps) = self.max_steps {
+            if steps >= max_steps {
+                return Err(RSCMError::LimitExceeded(format!(
+                    "maximum of {} steps reached",
+                    max_steps
+                )));
+            }
+        }
+        if let Some(max_wall_time) = self.max_wall_time {
+            let elapsed = started.elapsed();
+            if elapsed >= max_wall_time {
+                return Err(RSCMError::LimitExceeded(format!(
+                    "wall time of {:?} reached the maximum of {:?}",
+                    elapsed, max_wall_time
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A component registered with a [`ModelBuilder`]
+#[derive(Clone)]
+struct RegisteredComponent {
+    component: C,
+    /// Name assigned by the user, otherwise the name of the component's type is used
+    name: Option<String>,
+    group: Option<String>,
+}
+
 /// Build a new model from a set of components
 ///
 /// The builder generates a graph that defines the inter-component dependencies
@@ -74,26 +398,51 @@ impl Component for NullComponent {
 ///
 /// # Examples
 /// TODO: figure out how to share example components throughout the docs
+#[derive(Clone)]
 pub struct ModelBuilder {
-    components: Vec<C>,
+    components: Vec<RegisteredComponent>,
+    group_toggles: ComponentGroupToggles,
     exogenous_variables: TimeseriesCollection,
     initial_values: InputState,
+    options: ModelOptions,
+    mass_balance_checks: Vec<MassBalanceCheck>,
+    /// Patterns of the variables to store, or `None` to store every variable
+    output_variables: Option<Vec<String>>,
+    observers: Vec<Arc<dyn ModelObserver>>,
+    metadata: RunMetadata,
     pub time_axis: Arc<TimeAxis>,
 }
 
 /// Checks if the new definition is valid
 ///
-/// If any definitions share a name then the units must be equivalent
+/// If any definitions share a name then the units must be the same
+/// or convertible using the unit registry and `unit_contexts`.
 ///
-/// Panics if the parameter definition is inconsistent with any existing definitions.
+/// Returns a problem if the definition is inconsistent with an existing definition.
 fn verify_definition(
     definitions: &mut HashMap<String, VariableDefinition>,
     definition: &RequirementDefinition,
-) {
-    let existing = definitions.get(&definition.name);
+    unit_contexts: &[String],
+) -> Result<(), BuildProblem> {
+    let existing = definitions.get_mut(&definition.name);
     match existing {
         Some(existing) => {
-            assert_eq!(existing.unit, definition.unit);
+            if !units::is_same_unit(&existing.unit, &definition.unit) {
+                if let Err(err) =
+                    units::conversion_factor(&definition.unit, &existing.unit, unit_contexts)
+                {
+                    return Err(BuildProblem::InconsistentUnits {
+                        variable: definition.name.clone(),
+                        message: err.to_string(),
+                    });
+                }
+            }
+            if existing.default.is_none() {
+                existing.default = definition.default;
+            }
+            if existing.disaggregation == DisaggregationStrategy::default() {
+                existing.disaggregation = definition.disaggregation;
+            }
         }
         None => {
             definitions.insert(
@@ -102,6 +451,49 @@ fn verify_definition(
             );
         }
     }
+    Ok(())
+}
+
+/// Convert exogenous data onto the model's time axis
+///
+/// By default the data are interpolated using the timeseries' interpolation strategy,
+/// so data can be supplied on any time axis, e.g. decadal values.
+/// A warning is recorded if the conversion loses information.
+/// Returns [`BuildProblem::InvalidExogenousData`] if the data can't be interpolated onto the
+/// time axis, e.g. if the data don't cover the time axis and extrapolation isn't allowed.
+fn resample_exogenous(
+    name: &str,
+    timeseries: Timeseries<FloatValue>,
+    time_axis: Arc<TimeAxis>,
+    strategy: DisaggregationStrategy,
+    warnings: &mut Vec<ModelWarning>,
+) -> Result<Timeseries<FloatValue>, BuildProblem> {
+    // Data on a time axis with different units is converted onto the model's time unit
+    let timeseries = match timeseries.time_axis().unit() == time_axis.unit() {
+        true => timeseries,
+        false => timeseries.to_time_unit(time_axis.unit()),
+    };
+    if !time_axis.is_aligned_with(&timeseries.time_axis()) {
+        warnings.push(ModelWarning::new(
+            WarningKind::LossyResampling,
+            format!(
+                "Time steps of the exogenous data for {} don't align with the model's time steps",
+                name
+            ),
+        ));
+    }
+    match strategy {
+        // Fail with a useful message if the data doesn't cover the model's time axis
+        DisaggregationStrategy::Interpolate => {
+            timeseries.try_interpolate_into(time_axis).map_err(|err| {
+                BuildProblem::InvalidExogenousData {
+                    variable: name.to_string(),
+                    message: err.to_string(),
+                }
+            })
+        }
+        _ => Ok(timeseries.disaggregate_into(time_axis, strategy)),
+    }
 }
 
 /// Check that a component graph is valid
@@ -128,11 +520,85 @@ where
     .is_err()
 }
 
+/// Order in which the nodes of a component graph are solved
+///
+/// Every node is solved after the nodes which it depends upon.
+/// Independent nodes are solved in the order they were added to the graph,
+/// so the order is deterministic.
+/// Self-referential edges are ignored.
+/// Panics if the graph contains any other cycles (see [`is_valid_graph`]).
+fn execution_order(graph: &CGraph) -> Vec<NodeIndex> {
+    let mut in_degree: Vec<usize> = vec![0; graph.node_count()];
+    graph
+        .edge_indices()
+        .filter_map(|ex| graph.edge_endpoints(ex))
+        .filter(|(source, target)| source != target)
+        .for_each(|(_, target)| in_degree[target.index()] += 1);
+
+    let mut ready: BinaryHeap<Reverse<NodeIndex>> = graph
+        .node_indices()
+        .filter(|nx| in_degree[nx.index()] == 0)
+        .map(Reverse)
+        .collect();
+    let mut order = Vec::with_capacity(graph.node_count());
+    while let Some(Reverse(nx)) = ready.pop() {
+        order.push(nx);
+        graph
+            .edges(nx)
+            .map(|edge| edge.target())
+            .filter(|target| *target != nx)
+            .for_each(|target| {
+                in_degree[target.index()] -= 1;
+                if in_degree[target.index()] == 0 {
+                    ready.push(Reverse(target));
+                }
+            });
+    }
+    assert_eq!(
+        order.len(),
+        graph.node_count(),
+        "Component graph contains a cycle"
+    );
+    order
+}
+
+/// Group the execution order into levels of components which can be solved concurrently
+///
+/// Components are added to the current level in execution order until a component
+/// requires a variable solved by a component already in the level.
+/// Components in a level therefore only read values solved by earlier levels
+/// (or earlier time steps), so solving the components in a level concurrently and then storing
+/// their solutions in order gives the same result as solving them one after another.
+fn execution_levels(graph: &CGraph, order: &[NodeIndex]) -> Vec<Vec<NodeIndex>> {
+    let mut levels: Vec<Vec<NodeIndex>> = vec![];
+    let mut solved: HashSet<String> = HashSet::new();
+    order.iter().for_each(|nx| {
+        let component = &graph[*nx];
+        let requires_level = component
+            .input_names()
+            .iter()
+            .any(|name| solved.contains(name));
+        if levels.is_empty() || requires_level {
+            levels.push(vec![]);
+            solved.clear();
+        }
+        levels.last_mut().unwrap().push(*nx);
+        solved.extend(component.output_names());
+    });
+    levels
+}
+
 impl ModelBuilder {
     pub fn new() -> Self {
         Self {
             components: vec![],
+            group_toggles: ComponentGroupToggles::new(),
             initial_values: InputState::empty(),
+            options: ModelOptions::default(),
+            mass_balance_checks: vec![],
+            output_variables: None,
+            observers: vec![],
+            metadata: RunMetadata::new(),
             exogenous_variables: TimeseriesCollection::new(),
             time_axis: Arc::new(TimeAxis::from_values(Array::range(2000.0, 2100.0, 1.0))),
         }
@@ -140,19 +606,272 @@ impl ModelBuilder {
 
     /// Register a component with the builder
     pub fn with_component(&mut self, component: Arc<dyn Component + Send + Sync>) -> &mut Self {
-        self.components.push(component);
+        self.components.push(RegisteredComponent {
+            component,
+            name: None,
+            group: None,
+        });
+        self
+    }
+
+    /// Register a component with a name
+    ///
+    /// The name is used instead of the name of the component's type in errors, warnings,
+    /// graphs and when looking up the component in a model,
+    /// so that multiple instances of the same type of component can be distinguished.
+    /// Building the model panics if two components are given the same name.
+    pub fn with_named_component(
+        &mut self,
+        name: &str,
+        component: Arc<dyn Component + Send + Sync>,
+    ) -> &mut Self {
+        self.components.push(RegisteredComponent {
+            component,
+            name: Some(name.to_string()),
+            group: None,
+        });
+        self
+    }
+
+    /// Register a component with the builder only if `enabled` is true
+    pub fn with_component_if(
+        &mut self,
+        enabled: bool,
+        component: Arc<dyn Component + Send + Sync>,
+    ) -> &mut Self {
+        if enabled {
+            self.with_component(component);
+        }
+        self
+    }
+
+    /// Check that a conserved quantity balances after each step
+    ///
+    /// Violations are recorded in [`Model::mass_balance`].
+    /// Building the model panics if the check refers to a variable which isn't in the model
+    /// or has units which can't be converted.
+    pub fn with_mass_balance_check(&mut self, check: MassBalanceCheck) -> &mut Self {
+        self.mass_balance_checks.push(check);
+        self
+    }
+
+    /// Detect numerical instability and retry unstable steps with a smaller solver step
+    ///
+    /// See [`StabilityOptions`]
+    pub fn with_stability_options(&mut self, options: StabilityOptions) -> &mut Self {
+        self.options.stability = Some(options);
         self
     }
 
+    /// Set the options which apply to the whole model
+    ///
+    /// This replaces any existing options, including those set by
+    /// [`ModelBuilder::with_stability_options`] and [`ModelBuilder::with_run_limits`].
+    pub fn with_options(&mut self, options: ModelOptions) -> &mut Self {
+        self.options = options;
+        self
+    }
+
+    pub fn options(&self) -> &ModelOptions {
+        &self.options
+    }
+
+    /// Abort runs which exceed a wall time or step budget
+    ///
+    /// See [`RunLimits`]
+    pub fn with_run_limits(&mut self, limits: RunLimits) -> &mut Self {
+        self.options.limits = Some(limits);
+        self
+    }
+
+    /// Calculate the sensitivity of every variable to a component parameter
+    ///
+    /// The derivatives are propagated alongside the run using [`Component::solve_dual`]
+    /// and are available from [`Model::sensitivity`].
+    /// Components which don't implement [`Component::solve_dual`] have NaN sensitivities,
+    /// which propagate to any components that depend upon them.
+    pub fn with_sensitivity(&mut self, parameter: &str) -> &mut Self {
+        self.options.sensitivity = Some(parameter.to_string());
+        self
+    }
+
+    /// Solve components which don't depend on each other concurrently
+    ///
+    /// Within each time step, the components are grouped into levels of components which only
+    /// read values solved by earlier levels, e.g. the ERFs of different gases.
+    /// The components in a level are solved on a pool of `threads` threads,
+    /// which is created once for the model, and their solutions are stored in the model in execution order,
+    /// so the results are identical to solving the components one after another.
+    pub fn with_parallel_components(&mut self, threads: usize) -> &mut Self {
+        self.options.threads = threads;
+        self.options.parallel_components = true;
+        self
+    }
+
+    /// Seed the random number streams of the components
+    ///
+    /// Each component draws from a stream derived from the seed, the ensemble member
+    /// (see [`ModelBuilder::with_ensemble_member`]) and the name of the component
+    /// (see [`RngStream::for_component`]),
+    /// so stochastic components are reproducible across runs
+    /// and adding or removing components doesn't change the random numbers of the others.
+    ///
+    /// Without a seed or ensemble member, the streams are numbered by the position
+    /// of each component in the model.
+    pub fn with_rng_seed(&mut self, seed: u64) -> &mut Self {
+        self.options.rng_seed = Some(seed);
+        self
+    }
+
+    /// Set the index of the model in an ensemble
+    ///
+    /// Each member of an ensemble with the same seed (see [`ModelBuilder::with_rng_seed`])
+    /// draws different random numbers. [`crate::ensemble::run_ensemble`] sets the index of
+    /// each member.
+    pub fn with_ensemble_member(&mut self, member: u64) -> &mut Self {
+        self.options.ensemble_member = Some(member);
+        self
+    }
+
+    /// Set the name of the scenario recorded in the model's [`RunMetadata`]
+    pub fn with_scenario(&mut self, scenario: &str) -> &mut Self {
+        self.metadata.scenario = Some(scenario.to_string());
+        self
+    }
+
+    /// Record the values of the parameters used to build the model in its [`RunMetadata`]
+    ///
+    /// The parameters are only recorded, they aren't applied to the components.
+    pub fn with_parameters(&mut self, parameters: ParameterSet) -> &mut Self {
+        self.metadata.parameters.extend(parameters);
+        self
+    }
+
+    /// Record the version of a crate or package used to build the model
+    ///
+    /// The version of rscm-core is always recorded.
+    pub fn with_version(&mut self, name: &str, version: &str) -> &mut Self {
+        self.metadata
+            .versions
+            .insert(name.to_string(), version.to_string());
+        self
+    }
+
+    /// Only store the variables which match one of a set of glob patterns
+    ///
+    /// Variables which aren't requested are still solved, but their values are discarded
+    /// rather than stored in the model's [`TimeseriesCollection`],
+    /// which reduces the memory used by large ensembles.
+    /// Exogenous data and any variables required by a component are always stored.
+    /// See [`matches_pattern`] for the supported patterns, e.g. "Effective Radiative Forcing|*".
+    pub fn with_output_variables(&mut self, patterns: &[&str]) -> &mut Self {
+        self.output_variables = Some(patterns.iter().map(|p| p.to_string()).collect());
+        self
+    }
+
+    /// Register an observer which receives the events of the model
+    ///
+    /// The observer receives [`ModelEvent::BuildStarted`] when the model is built,
+    /// followed by the events of the built model (see [`Model::add_observer`]).
+    pub fn with_observer(&mut self, observer: Arc<dyn ModelObserver>) -> &mut Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Register a component as part of a named group
+    ///
+    /// The component is only included in the model if the group is enabled when the model is built.
+    /// See [`ModelBuilder::with_group_enabled`] and [`ModelBuilder::with_group_toggles`].
+    pub fn with_component_in_group(
+        &mut self,
+        group: &str,
+        component: Arc<dyn Component + Send + Sync>,
+    ) -> &mut Self {
+        self.components.push(RegisteredComponent {
+            component,
+            name: None,
+            group: Some(group.to_string()),
+        });
+        self
+    }
+
+    /// Register a component with all of its outputs prefixed with `prefix|`
+    ///
+    /// This allows a generic component to be used multiple times in a model,
+    /// e.g. a carbon box for each reservoir.
+    /// The component is named `prefix` (see [`ModelBuilder::with_named_component`]).
+    /// Inputs are not prefixed, use a [`RemappedComponent`] directly to also rename inputs.
+    pub fn with_component_namespaced(
+        &mut self,
+        prefix: &str,
+        component: Arc<dyn Component + Send + Sync>,
+    ) -> &mut Self {
+        let mut namespaced = RemappedComponent::new(component);
+        namespaced.with_namespace(prefix);
+        self.with_named_component(prefix, Arc::new(namespaced))
+    }
+
+    /// Register a named component as part of a named group
+    ///
+    /// See [`ModelBuilder::with_named_component`] and [`ModelBuilder::with_component_in_group`].
+    pub fn with_named_component_in_group(
+        &mut self,
+        group: &str,
+        name: &str,
+        component: Arc<dyn Component + Send + Sync>,
+    ) -> &mut Self {
+        self.components.push(RegisteredComponent {
+            component,
+            name: Some(name.to_string()),
+            group: Some(group.to_string()),
+        });
+        self
+    }
+
+    /// Enable or disable a named group of components
+    ///
+    /// Groups are enabled by default.
+    pub fn with_group_enabled(&mut self, group: &str, enabled: bool) -> &mut Self {
+        self.group_toggles.set(group, enabled);
+        self
+    }
+
+    /// Apply a set of group toggles
+    ///
+    /// Overrides the state of any groups that have previously been set.
+    pub fn with_group_toggles(&mut self, toggles: ComponentGroupToggles) -> &mut Self {
+        toggles.groups.into_iter().for_each(|(group, enabled)| {
+            self.group_toggles.set(&group, enabled);
+        });
+        self
+    }
+
+    /// Whether a variable solved by the model should be stored
+    fn is_output_variable(&self, name: &str) -> bool {
+        match &self.output_variables {
+            Some(patterns) => patterns.iter().any(|p| matches_pattern(p, name)),
+            None => true,
+        }
+    }
+
+    /// Components that will be included in the model
+    fn enabled_components(&self) -> impl Iterator<Item = &RegisteredComponent> {
+        self.components.iter().filter(|c| match &c.group {
+            Some(group) => self.group_toggles.is_enabled(group),
+            None => true,
+        })
+    }
+
     /// Supply exogenous data to be used by the model
     ///
     /// Any unneeded timeseries will be ignored.
+    /// Replaces any previously supplied exogenous data with the same name.
     pub fn with_exogenous_variable(
         &mut self,
         name: &str,
         timeseries: Timeseries<FloatValue>,
     ) -> &mut Self {
-        self.exogenous_variables.add_timeseries(
+        self.exogenous_variables.upsert_timeseries(
             name.to_string(),
             timeseries,
             VariableType::Exogenous,
@@ -160,13 +879,26 @@ impl ModelBuilder {
         self
     }
 
+    /// Supply exogenous data with a value for each of a set of labels
+    ///
+    /// Each label is added as a separate timeseries named using
+    /// [`crate::component::labelled_name`].
+    pub fn with_labelled_exogenous_variable(
+        &mut self,
+        name: &str,
+        timeseries: LabelledTimeseries,
+    ) -> &mut Self {
+        timeseries.add_to_collection(name, &mut self.exogenous_variables, VariableType::Exogenous);
+        self
+    }
+
     /// Supply exogenous data to be used by the model
     ///
     /// Any unneeded timeseries will be ignored.
+    /// Replaces any previously supplied exogenous data with the same name.
     pub fn with_exogenous_collection(&mut self, collection: TimeseriesCollection) -> &mut Self {
         collection.into_iter().for_each(|x| {
-            self.exogenous_variables
-                .add_timeseries(x.name, x.timeseries, x.variable_type)
+            self.exogenous_variables.upsert_item(x);
         });
         self
     }
@@ -194,32 +926,116 @@ impl ModelBuilder {
 
     /// Builds the component graph for the registered components and creates a concrete model
     ///
-    /// Panics if the required data to build a model is not available.
+    /// Missing exogenous data, state variables without an initial value and exogenous data
+    /// with incompatible units are recorded as warnings on the model,
+    /// or panic with a description of the problem if the model is strict.
+    /// Panics if components use inconsistent units for a variable or if exogenous data can't
+    /// be interpolated onto the model's time axis.
+    /// Use [`ModelBuilder::try_build`] to check for all of these problems at once.
     pub fn build(&self) -> Model {
+        let (model, problems) = self.assemble();
+        problems.iter().for_each(|problem| match problem {
+            BuildProblem::InconsistentUnits { .. }
+            | BuildProblem::InvalidExogenousData { .. }
+            | BuildProblem::DuplicateComponentName(_)
+            | BuildProblem::CyclicGraph
+            | BuildProblem::InvalidMassBalanceCheck { .. } => panic!("{}", problem),
+            _ if self.options.strict => panic!("{}", problem),
+            _ => {}
+        });
+        model.expect("Models with a cyclic component graph can't be built")
+    }
+
+    /// Builds a model, returning an error if there are any problems with the model's inputs
+    ///
+    /// The error lists all the missing exogenous variables, state variables without an initial
+    /// value and variables with units that can't be converted, rather than continuing with
+    /// NaN values.
+    /// Problems which [`ModelBuilder::build`] panics on, such as duplicate component names
+    /// or invalid mass balance checks, are included in the same error.
+    pub fn try_build(&self) -> Result<Model, BuildError> {
+        let (model, problems) = self.assemble();
+        match (model, problems.is_empty()) {
+            (Some(model), true) => Ok(model),
+            _ => Err(BuildError { problems }),
+        }
+    }
+
+    /// Create a model from the registered components, collecting any problems with its inputs
+    ///
+    /// No model is created if the component graph contains a cycle.
+    fn assemble(&self) -> (Option<Model>, Vec<BuildProblem>) {
+        emit(
+            &self.observers,
+            ModelEvent::BuildStarted {
+                components: self
+                    .enabled_components()
+                    .map(|registered| {
+                        registered
+                            .name
+                            .clone()
+                            .unwrap_or_else(|| registered.component.typetag_name().to_string())
+                    })
+                    .collect(),
+            },
+        );
+
         // todo: refactor once this is more stable
         let mut graph: CGraph = Graph::new();
         let mut endrogoneous: HashMap<String, NodeIndex> = HashMap::new();
         let mut exogenous: Vec<String> = vec![];
+        let mut required: HashSet<String> = HashSet::new();
         let mut definitions: HashMap<String, VariableDefinition> = HashMap::new();
+        let mut warnings = vec![];
+        let mut problems = vec![];
         let initial_node = graph.add_node(Arc::new(NullComponent {}));
+        let mut component_names = vec![graph[initial_node].typetag_name().to_string()];
+
+        let mut assigned_names: Vec<&String> = vec![];
+        self.enabled_components()
+            .filter_map(|c| c.name.as_ref())
+            .for_each(|name| {
+                if !assigned_names.contains(&name) {
+                    assigned_names.push(name);
+                } else if !problems.contains(&BuildProblem::DuplicateComponentName(name.clone())) {
+                    problems.push(BuildProblem::DuplicateComponentName(name.clone()));
+                }
+            });
 
-        self.components.iter().for_each(|component| {
+        self.enabled_components().for_each(|registered| {
+            let component = &registered.component;
             let node = graph.add_node(component.clone());
+            component_names.push(
+                registered
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| component.typetag_name().to_string()),
+            );
             let mut has_dependencies = false;
 
             let requires = component.inputs();
             let provides = component.outputs();
 
             requires.iter().for_each(|requirement| {
-                verify_definition(&mut definitions, requirement);
+                if let Err(problem) =
+                    verify_definition(&mut definitions, requirement, &self.options.unit_contexts)
+                {
+                    problems.push(problem);
+                }
+                required.insert(requirement.name.clone());
 
-                if exogenous.contains(&requirement.name) {
-                    // Link to the node that provides the requirement
-                    graph.add_edge(endrogoneous[&requirement.name], node, requirement.clone());
-                    has_dependencies = true;
-                } else {
-                    // Add a new variable that must be defined outside of the model
-                    exogenous.push(requirement.name.clone())
+                match endrogoneous.get(&requirement.name) {
+                    Some(provider) => {
+                        // Link to the node that provides the requirement
+                        graph.add_edge(*provider, node, requirement.clone());
+                        has_dependencies = true;
+                    }
+                    // Add a new variable that must be defined outside of the model.
+                    // Components may share an input that is defined outside of the model
+                    None if !exogenous.contains(&requirement.name) => {
+                        exogenous.push(requirement.name.clone())
+                    }
+                    None => {}
                 }
             });
 
@@ -236,7 +1052,11 @@ impl ModelBuilder {
             }
 
             provides.iter().for_each(|requirement| {
-                verify_definition(&mut definitions, requirement);
+                if let Err(problem) =
+                    verify_definition(&mut definitions, requirement, &self.options.unit_contexts)
+                {
+                    problems.push(problem);
+                }
 
                 let val = endrogoneous.get(&requirement.name);
 
@@ -253,7 +1073,26 @@ impl ModelBuilder {
         });
 
         // Check that the component graph doesn't contain any loops
-        assert!(!is_valid_graph(&graph));
+        let is_cyclic = is_valid_graph(&graph);
+        if is_cyclic {
+            problems.push(BuildProblem::CyclicGraph);
+        }
+
+        // Initial values calculated by the components which solve each state variable
+        let mut initialised = InputState::empty();
+        if let Some(t0) = self.time_axis.at(0) {
+            self.enabled_components().for_each(|registered| {
+                let outputs = registered.component.output_names();
+                let (values, names) = registered
+                    .component
+                    .initialise(t0)
+                    .into_iter()
+                    .filter(|(name, _)| outputs.contains(name))
+                    .map(|(name, value)| (value, name))
+                    .unzip();
+                initialised.merge(InputState::from_vectors(values, names));
+            });
+        }
 
         // Create the timeseries collection using the information from the components
         let mut collection = TimeseriesCollection::new();
@@ -262,56 +1101,178 @@ impl ModelBuilder {
 
             if exogenous.contains(&name) {
                 // Exogenous variable is expected to be supplied
-                if self.initial_values.has(&name) {
+                // State variables (solved by a component) fall back to the value from the
+                // component's `initialise` and then their default as an initial value
+                let initial_value = match self.initial_values.has(&name) {
+                    true => Some(*self.initial_values.get(&name)),
+                    false if endrogoneous.contains_key(&name) && initialised.has(&name) => {
+                        Some(*initialised.get(&name))
+                    }
+                    false if endrogoneous.contains_key(&name) => definition.default,
+                    false => None,
+                };
+
+                if let Some(initial_value) = initial_value {
                     // An initial value was provided
                     let mut ts = Timeseries::new_empty(
                         self.time_axis.clone(),
                         definition.unit,
-                        InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+                        InterpolationStrategy::from(LinearSplineStrategy::with_extrapolation(
+                            self.options.extrapolation.clone(),
+                        )),
                     );
-                    ts.set(0, *self.initial_values.get(&name));
+                    ts.set(0, initial_value);
 
                     // Note that timeseries that are initialised are defined as Endogenous
                     // all but the first time point come from the model.
                     // This could potentially be defined as a different VariableType if needed.
-                    collection.add_timeseries(name, ts, VariableType::Endogenous)
+                    collection
+                        .add_timeseries(name, ts, VariableType::Endogenous)
+                        .expect("Variable names are unique");
                 } else {
                     // Check if the timeseries is available in the provided exogenous variables
                     // then interpolate to the right timebase
                     let timeseries = self.exogenous_variables.get_timeseries_by_name(&name);
 
                     match timeseries {
-                        Some(timeseries) => collection.add_timeseries(
-                            name,
-                            timeseries
-                                .to_owned()
-                                .interpolate_into(self.time_axis.clone()),
-                            VariableType::Exogenous,
-                        ),
-                        None => println!("No exogenous data for {}", definition.name),
+                        Some(timeseries) => {
+                            // Data without units or in units which can't be converted
+                            // are used unchanged
+                            let converted = match timeseries.units().is_empty() {
+                                true => Ok(timeseries.to_owned()),
+                                false => timeseries.convert_unit_with_contexts(
+                                    &definition.unit,
+                                    &self.options.unit_contexts,
+                                ),
+                            };
+                            let timeseries = converted.unwrap_or_else(|err| {
+                                let problem = BuildProblem::IncompatibleUnits {
+                                    variable: name.clone(),
+                                    message: err.to_string(),
+                                };
+                                warnings.push(ModelWarning::new(
+                                    WarningKind::IncompatibleUnits,
+                                    problem.to_string(),
+                                ));
+                                problems.push(problem);
+                                timeseries.to_owned()
+                            });
+                            match resample_exogenous(
+                                &name,
+                                timeseries,
+                                self.time_axis.clone(),
+                                definition.disaggregation,
+                                &mut warnings,
+                            ) {
+                                Ok(timeseries) => collection
+                                    .add_timeseries(name, timeseries, VariableType::Exogenous)
+                                    .expect("Variable names are unique"),
+                                Err(problem) => problems.push(problem),
+                            }
+                        }
+                        None => match definition.default {
+                            // Materialise the default of an optional input as a constant timeseries
+                            Some(default) => collection
+                                .add_timeseries(
+                                    name,
+                                    Timeseries::new(
+                                        Array::from_elem(self.time_axis.len(), default),
+                                        self.time_axis.clone(),
+                                        definition.unit,
+                                        InterpolationStrategy::from(
+                                            PreviousStrategy::with_extrapolation(
+                                                self.options.extrapolation.clone(),
+                                            ),
+                                        ),
+                                    ),
+                                    VariableType::Exogenous,
+                                )
+                                .expect("Variable names are unique"),
+                            None => {
+                                warnings.push(ModelWarning::new(
+                                    WarningKind::MissingExogenousData,
+                                    format!("No exogenous data for {}", definition.name),
+                                ));
+                                problems.push(match endrogoneous.contains_key(&name) {
+                                    true => BuildProblem::Uninitialised(name),
+                                    false => BuildProblem::MissingExogenousData(name),
+                                });
+                            }
+                        },
                     }
                 }
-            } else {
+            } else if required.contains(&name) || self.is_output_variable(&name) {
                 // Create a placeholder for data that will be generated by the model
-                collection.add_timeseries(
-                    definition.name,
-                    Timeseries::new_empty(
-                        self.time_axis.clone(),
-                        definition.unit,
-                        InterpolationStrategy::from(LinearSplineStrategy::new(true)),
-                    ),
-                    VariableType::Endogenous,
-                )
+                collection
+                    .add_timeseries(
+                        definition.name,
+                        Timeseries::new_empty(
+                            self.time_axis.clone(),
+                            definition.unit,
+                            InterpolationStrategy::from(LinearSplineStrategy::with_extrapolation(
+                                self.options.extrapolation.clone(),
+                            )),
+                        ),
+                        VariableType::Endogenous,
+                    )
+                    .expect("Variable names are unique");
             }
         }
 
-        // Add the components to the graph
-        Model::new(graph, initial_node, collection, self.time_axis.clone())
-    }
-}
+        // Components which use different units to the model's timeseries
+        let mut unit_conversions = vec![];
+        graph.node_indices().for_each(|node| {
+            graph[node].variables().iter().for_each(|definition| {
+                let factor = collection
+                    .get_timeseries_by_name(&definition.name)
+                    .filter(|ts| !units::is_same_unit(ts.units(), &definition.unit))
+                    .and_then(|ts| {
+                        units::conversion_factor(
+                            ts.units(),
+                            &definition.unit,
+                            &self.options.unit_contexts,
+                        )
+                        .ok()
+                    });
+                if let Some(factor) = factor {
+                    unit_conversions.push(UnitConversion {
+                        node: node.index(),
+                        variable: definition.name.clone(),
+                        factor,
+                    });
+                }
+            })
+        });
 
-impl Default for ModelBuilder {
-    fn default() -> Self {
+        self.mass_balance_checks.iter().for_each(|check| {
+            if let Err(err) = check.conversion_factors(&collection, &self.options.unit_contexts) {
+                problems.push(BuildProblem::InvalidMassBalanceCheck {
+                    check: check.name.clone(),
+                    message: err.to_string(),
+                });
+            }
+        });
+
+        if is_cyclic {
+            return (None, problems);
+        }
+
+        // Add the components to the graph
+        let mut model = Model::new(graph, initial_node, collection, self.time_axis.clone());
+        model.options = self.options.clone();
+        model.mass_balance_checks = self.mass_balance_checks.clone();
+        model.unit_conversions = unit_conversions;
+        model.component_names = component_names;
+        model.seed_rng_streams();
+        model.warnings = warnings;
+        model.observers = self.observers.clone();
+        model.metadata = self.metadata.clone();
+        (Some(model), problems)
+    }
+}
+
+impl Default for ModelBuilder {
+    fn default() -> Self {
         Self::new()
     }
 }
@@ -339,6 +1300,19 @@ pub struct Model {
     components: CGraph,
     /// The base node of the graph from where to begin traversing.
     initial_node: NodeIndex,
+    /// Order in which the nodes of the graph are solved each step
+    ///
+    /// This is calculated from the graph when the model is created or first stepped.
+    #[serde(skip)]
+    execution_order: Vec<NodeIndex>,
+    /// Levels of components in the execution order which don't depend on each other
+    #[serde(skip)]
+    execution_levels: Vec<Vec<NodeIndex>>,
+    /// Name of each node in the component graph
+    ///
+    /// Models serialised without names use the names of the components' types.
+    #[serde(default)]
+    component_names: Vec<String>,
     /// The model state
     ///
     /// Variable names within the model are unique and these variable names are used by
@@ -346,6 +1320,288 @@ pub struct Model {
     collection: TimeseriesCollection,
     time_axis: Arc<TimeAxis>,
     time_index: usize,
+    /// Random number stream for each node in the component graph
+    ///
+    /// Models serialised without streams are given the default streams when stepped.
+    #[serde(default)]
+    rng_streams: Vec<RngStream>,
+    /// Reusable buffer for temporary calculations within components
+    #[serde(skip)]
+    scratch: Vec<FloatValue>,
+    /// Threads used to solve independent components concurrently
+    ///
+    /// The pool is created when the model is first stepped with
+    /// [`ModelOptions::parallel_components`] and reused for every step.
+    #[serde(skip)]
+    thread_pool: Option<ThreadPool>,
+    /// Options which apply to the whole model
+    #[serde(default, skip_serializing_if = "ModelOptions::is_default")]
+    options: ModelOptions,
+    /// Steps which were refined to remain stable
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    refinements: Vec<StepRefinement>,
+    /// Variables which are converted when passed to or from components
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    unit_conversions: Vec<UnitConversion>,
+    /// Derivative of each endogenous variable with respect to [`ModelOptions::sensitivity`]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    tangents: HashMap<String, Vec<FloatValue>>,
+    /// Conserved quantities which are checked after each step
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mass_balance_checks: Vec<MassBalanceCheck>,
+    /// Result of the mass balance checks for the steps which have been run
+    #[serde(skip)]
+    mass_balance: MassBalanceReport,
+    /// Warnings recorded while building and running the model
+    ///
+    /// These describe a particular run so aren't serialised.
+    #[serde(skip)]
+    warnings: Vec<ModelWarning>,
+    /// Sinks which receive the values of each completed step
+    #[serde(skip)]
+    output_sinks: Vec<Box<dyn OutputSink>>,
+    /// Observers which receive the events of the model
+    #[serde(skip)]
+    observers: Vec<Arc<dyn ModelObserver>>,
+    /// Scenario, parameters, versions and timing of the run
+    #[serde(default, skip_serializing_if = "RunMetadata::is_empty")]
+    metadata: RunMetadata,
+}
+
+/// Conversion of a variable between the units of the model's timeseries and a component
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct UnitConversion {
+    /// Index of the component's node in the component graph
+    node: usize,
+    variable: String,
+    /// Factor which converts from the units of the timeseries to the units of the component
+    factor: FloatValue,
+}
+
+/// The components of a built model along with everything derived from them
+///
+/// Used to solve the same components on new timeseries without rebuilding the
+/// component graph, e.g. each step of a [`crate::submodel::SubModel`].
+#[derive(Debug, Clone)]
+pub(crate) struct ModelTemplate {
+    components: CGraph,
+    initial_node: NodeIndex,
+    execution_order: Vec<NodeIndex>,
+    execution_levels: Vec<Vec<NodeIndex>>,
+    component_names: Vec<String>,
+    options: ModelOptions,
+    unit_conversions: Vec<UnitConversion>,
+    mass_balance_checks: Vec<MassBalanceCheck>,
+    /// Timeseries of the model the template was created from
+    collection: TimeseriesCollection,
+}
+
+impl ModelTemplate {
+    /// Timeseries of the model the template was created from
+    pub(crate) fn timeseries(&self) -> &TimeseriesCollection {
+        &self.collection
+    }
+
+    /// Create a model which solves the components on `time_axis`
+    ///
+    /// `collection` must contain the same variables in the same units as
+    /// [`ModelTemplate::timeseries`].
+    /// The random number streams are seeded from `rng_seed` as if it was the seed of the
+    /// original model.
+    pub(crate) fn instantiate(
+        &self,
+        collection: TimeseriesCollection,
+        time_axis: Arc<TimeAxis>,
+        rng_seed: Option<u64>,
+    ) -> Model {
+        let mut model = Model {
+            components: self.components.clone(),
+            initial_node: self.initial_node,
+            execution_order: self.execution_order.clone(),
+            execution_levels: self.execution_levels.clone(),
+            component_names: self.component_names.clone(),
+            collection,
+            time_axis,
+            time_index: 0,
+            rng_streams: vec![],
+            scratch: vec![],
+            thread_pool: None,
+            options: ModelOptions {
+                rng_seed,
+                ..self.options.clone()
+            },
+            refinements: vec![],
+            unit_conversions: self.unit_conversions.clone(),
+            tangents: HashMap::new(),
+            mass_balance_checks: self.mass_balance_checks.clone(),
+            mass_balance: MassBalanceReport::default(),
+            warnings: vec![],
+            output_sinks: vec![],
+            observers: vec![],
+            metadata: RunMetadata::new(),
+        };
+        model.init_rng_streams();
+        model.seed_rng_streams();
+        model
+    }
+}
+
+/// Convert the values in `state` which have different units in the component at `nx`
+///
+/// If `to_component`, values are converted from the units of the model's timeseries into the
+/// component's units, otherwise the reverse.
+/// Only the converted values are returned.
+fn convert_units(
+    unit_conversions: &[UnitConversion],
+    nx: NodeIndex,
+    state: &InputState,
+    to_component: bool,
+) -> InputState {
+    let (values, names) = unit_conversions
+        .iter()
+        .filter(|conversion| conversion.node == nx.index() && state.has(&conversion.variable))
+        .map(|conversion| {
+            let value = *state.get(&conversion.variable);
+            let value = match to_component {
+                true => value * conversion.factor,
+                false => value / conversion.factor,
+            };
+            (value, conversion.variable.clone())
+        })
+        .unzip();
+    InputState::from_vectors(values, names)
+}
+
+/// Result of solving a component for a time step, before it is stored in the model
+struct ComponentSolution {
+    nx: NodeIndex,
+    /// Input state in the units of the component
+    input_state: InputState,
+    /// Number of times the step was refined
+    refinement: u32,
+    result: RSCMResult<OutputState>,
+}
+
+/// The parts of a model which are read while solving components for a time step
+///
+/// Solving a component only reads the model's state
+/// so components can be solved concurrently using a shared `StepSolver`.
+/// Each component has its own random number stream.
+struct StepSolver<'a> {
+    components: &'a CGraph,
+    collection: &'a TimeseriesCollection,
+    time_axis: &'a TimeAxis,
+    time_index: usize,
+    options: &'a ModelOptions,
+    unit_conversions: &'a [UnitConversion],
+    component_names: &'a [String],
+}
+
+impl StepSolver<'_> {
+    /// Solve the component at `nx` for the current time step
+    ///
+    /// If the model has stability options, the step is refined until the solution is stable.
+    fn solve(
+        &self,
+        nx: NodeIndex,
+        rng: &mut ModelRng,
+        scratch: &mut Vec<FloatValue>,
+    ) -> ComponentSolution {
+        let component = self.components.index(nx);
+        let t_current = self.time_axis.at(self.time_index).unwrap();
+        let mut input_state = component.extract_state(self.collection, t_current);
+        input_state.merge(convert_units(self.unit_conversions, nx, &input_state, true));
+
+        let mut refinement = 0;
+        let result = loop {
+            let mut context = TimestepContext::new(self.time_index, self.time_axis, rng, scratch)
+                .with_refinement(refinement)
+                .with_options(self.options);
+
+            let result = component.solve_with_context(&mut context, &input_state);
+
+            match (&self.options.stability, &result) {
+                (Some(options), Ok(output_state)) if !self.is_stable(options, output_state) => {
+                    if refinement < options.max_refinements {
+                        refinement += 1;
+                        continue;
+                    }
+                    break Err(RSCMError::Error(format!(
+                        "{} is numerically unstable at {} after {} refinements",
+                        self.component_names
+                            .get(nx.index())
+                            .map(String::as_str)
+                            .unwrap_or_else(|| component.typetag_name()),
+                        t_current,
+                        refinement
+                    )));
+                }
+                _ => break result,
+            }
+        };
+
+        ComponentSolution {
+            nx,
+            input_state,
+            refinement,
+            result,
+        }
+    }
+
+    /// Solve a level of independent components on the threads of `pool`
+    ///
+    /// The solutions are returned in the same order as `level`.
+    fn solve_concurrently(
+        &self,
+        level: &[NodeIndex],
+        rng_streams: &mut [RngStream],
+        pool: &ThreadPool,
+    ) -> Vec<ComponentSolution> {
+        let mut streams: Vec<(NodeIndex, &mut RngStream)> = rng_streams
+            .iter_mut()
+            .enumerate()
+            .map(|(i, stream)| (NodeIndex::new(i), stream))
+            .filter(|(nx, _)| level.contains(nx))
+            .collect();
+        streams.sort_by_key(|(nx, _)| level.iter().position(|other| other == nx));
+
+        pool.install(|| {
+            streams
+                .par_iter_mut()
+                .map_init(Vec::new, |scratch, (nx, stream)| {
+                    self.solve(*nx, stream.rng(), scratch)
+                })
+                .collect()
+        })
+    }
+
+    /// Check if the values solved by a component are stable
+    fn is_stable(&self, options: &StabilityOptions, output_state: &OutputState) -> bool {
+        output_state.iter().all(|(name, value)| {
+            let previous = self
+                .collection
+                .get_timeseries_by_name(name)
+                .and_then(|ts| ts.at(self.time_index))
+                .unwrap_or(FloatValue::NAN);
+            options.is_stable(previous, *value)
+        })
+    }
+}
+
+/// Seed used for the random number streams of a new model
+const DEFAULT_SEED: u64 = 0;
+
+/// Version of the format written by [`Model::save_state`]
+///
+/// This is incremented whenever a change to the model would prevent saved states
+/// from being restored.
+pub const STATE_FORMAT_VERSION: u32 = 1;
+
+/// A saved model along with the version of the format
+#[derive(Serialize)]
+struct SavedState<'a> {
+    format_version: u32,
+    model: &'a Model,
 }
 
 impl Model {
@@ -355,156 +1611,2825 @@ impl Model {
         collection: TimeseriesCollection,
         time_axis: Arc<TimeAxis>,
     ) -> Self {
-        Self {
+        let mut model = Self {
             components,
             initial_node,
+            execution_order: vec![],
+            execution_levels: vec![],
+            component_names: vec![],
             collection,
             time_axis,
             time_index: 0,
+            rng_streams: vec![],
+            scratch: vec![],
+            thread_pool: None,
+            options: ModelOptions::default(),
+            refinements: vec![],
+            unit_conversions: vec![],
+            tangents: HashMap::new(),
+            mass_balance_checks: vec![],
+            mass_balance: MassBalanceReport::default(),
+            warnings: vec![],
+            output_sinks: vec![],
+            observers: vec![],
+            metadata: RunMetadata::new(),
+        };
+        model.init_rng_streams();
+        model.execution_order = execution_order(&model.components);
+        model.execution_levels = execution_levels(&model.components, &model.execution_order);
+        model
+    }
+
+    /// Name of the component at a node in the component graph
+    ///
+    /// This is the name assigned when the component was registered with the [`ModelBuilder`],
+    /// otherwise the name of the component's type.
+    pub fn component_name(&self, nx: NodeIndex) -> &str {
+        self.component_names
+            .get(nx.index())
+            .map(String::as_str)
+            .unwrap_or_else(|| self.components[nx].typetag_name())
+    }
+
+    /// Names of the components in the order they were registered
+    ///
+    /// Excludes the initial null component.
+    pub fn component_names(&self) -> Vec<&str> {
+        self.components
+            .node_indices()
+            .filter(|nx| *nx != self.initial_node)
+            .map(|nx| self.component_name(nx))
+            .collect()
+    }
+
+    /// Name and component of each component in the order they were registered
+    ///
+    /// Excludes the initial null component.
+    pub fn components(&self) -> Vec<(&str, Arc<dyn Component>)> {
+        self.components
+            .node_indices()
+            .filter(|nx| *nx != self.initial_node)
+            .map(|nx| (self.component_name(nx), self.components[nx].clone()))
+            .collect()
+    }
+
+    /// Create a component which solves the components of this model
+    ///
+    /// This allows the model to be embedded within a larger model (see [`SubModel`]).
+    pub fn to_component(&self) -> SubModel {
+        SubModel::from_model(self)
+    }
+
+    /// Names of the components in the order they are solved each step
+    ///
+    /// Components are solved after any components which provide their inputs.
+    /// Useful for debugging the coupling between components.
+    /// Excludes the initial null component.
+    pub fn execution_order(&self) -> Vec<&str> {
+        let order = match self.execution_order.len() == self.components.node_count() {
+            true => self.execution_order.clone(),
+            false => execution_order(&self.components),
+        };
+        order
+            .into_iter()
+            .filter(|nx| *nx != self.initial_node)
+            .map(|nx| self.component_name(nx))
+            .collect()
+    }
+
+    /// Estimate the runtime and memory use of the remainder of the run
+    ///
+    /// The estimate uses the number of remaining time steps,
+    /// the [`Component::cost_hint`] of each component and the number of variables.
+    /// Use [`CostEstimate::ensemble`] to scale the estimate to an ensemble of runs.
+    pub fn estimate_cost(&self) -> CostEstimate {
+        let hints: Vec<CostHint> = self
+            .components
+            .node_indices()
+            .filter(|nx| *nx != self.initial_node)
+            .map(|nx| self.components[nx].cost_hint())
+            .collect();
+        let values = self.collection.iter().count() * self.time_axis.len();
+        CostEstimate::new(self.time_axis.len() - 1 - self.time_index, &hints, values)
+    }
+
+    /// Gets the time value at the current step
+    pub fn current_time(&self) -> Time {
+        self.time_axis.at(self.time_index).unwrap()
+    }
+    pub fn current_time_bounds(&self) -> (Time, Time) {
+        self.time_axis.at_bounds(self.time_index).unwrap()
+    }
+
+    /// The parts of the model which are read while solving components for the current step
+    fn step_solver(&self) -> StepSolver<'_> {
+        StepSolver {
+            components: &self.components,
+            collection: &self.collection,
+            time_axis: &self.time_axis,
+            time_index: self.time_index,
+            options: &self.options,
+            unit_conversions: &self.unit_conversions,
+            component_names: &self.component_names,
+        }
+    }
+
+    /// Solve a single component for the current timestep
+    ///
+    /// The updated state from the component is then pushed into the model's timeseries collection
+    /// to be later used by other components.
+    /// The output state defines the values at the next time index as it represents the state
+    /// at the start of the next timestep.
+    fn step_model_component(
+        &mut self,
+        nx: NodeIndex,
+        rng_stream: &mut RngStream,
+        scratch: &mut Vec<FloatValue>,
+    ) -> RSCMResult<()> {
+        let solution = self.step_solver().solve(nx, rng_stream.rng(), scratch);
+        self.store_solution(solution)
+    }
+
+    /// Store the solution of a component in the model's timeseries collection
+    ///
+    /// Any refinements and warnings of the component are recorded, while failures are returned.
+    fn store_solution(&mut self, solution: ComponentSolution) -> RSCMResult<()> {
+        let ComponentSolution {
+            nx,
+            input_state,
+            refinement,
+            result,
+        } = solution;
+        let name = self.component_name(nx).to_string();
+
+        if refinement > 0 && result.is_ok() {
+            self.refinements.push(StepRefinement {
+                time_index: self.time_index,
+                component: name.clone(),
+                refinements: refinement,
+            });
+        }
+
+        let result = result
+            .and_then(|output_state| self.apply_nan_policy(nx, output_state))
+            .and_then(|output_state| self.apply_bounds_policy(nx, output_state));
+
+        match result {
+            Ok(mut output_state) => {
+                if let Some(parameter) = self.options.sensitivity.clone() {
+                    self.step_tangents(nx, &parameter, &input_state, &output_state);
+                }
+                output_state.merge(self.convert_units(nx, &output_state, false));
+                output_state.iter().for_each(|(key, value)| {
+                    // Variables which weren't requested using `with_output_variables` are discarded
+                    if let Some(ts) = self.collection.get_timeseries_by_name_mut(key) {
+                        // The next time index is used as this output state represents the value
+                        // of a variable at the end of the current time step.
+                        // This is the same as the start of the next timestep.
+                        ts.set(self.time_index + 1, *value)
+                    }
+                });
+                Ok(())
+            }
+            Err(err) => {
+                emit(
+                    &self.observers,
+                    ModelEvent::ComponentFailed {
+                        component: name.clone(),
+                        time_index: self.time_index,
+                        time: self.current_time(),
+                        message: err.to_string(),
+                    },
+                );
+                Err(RSCMError::ComponentFailed {
+                    component: name,
+                    time_index: self.time_index,
+                    time: self.current_time(),
+                    source: Box::new(err),
+                })
+            }
+        }
+    }
+
+    /// Propagate the derivatives of a component's outputs with respect to `parameter`
+    ///
+    /// `input_state` and `output_state` are in the units of the component.
+    /// Endogenous inputs use the derivative at the same index as the value in `input_state`,
+    /// while exogenous inputs don't depend on the parameter.
+    fn step_tangents(
+        &mut self,
+        nx: NodeIndex,
+        parameter: &str,
+        input_state: &InputState,
+        output_state: &OutputState,
+    ) {
+        let component = self.components.index(nx).clone();
+        let factor = |name: &str| {
+            self.unit_conversions
+                .iter()
+                .find(|c| c.node == nx.index() && c.variable == name)
+                .map_or(1.0, |c| c.factor)
+        };
+
+        let dual_inputs: DualState = input_state
+            .iter()
+            .map(|(name, value)| {
+                let derivative = match self.collection.get_by_name(name) {
+                    Some(item) if item.variable_type == VariableType::Endogenous => {
+                        let index = (*item.timeseries.latest()).max(0) as usize;
+                        self.tangents
+                            .get(name)
+                            .map_or(0.0, |tangents| tangents[index])
+                    }
+                    _ => 0.0,
+                };
+                (name.clone(), Dual::new(*value, derivative * factor(name)))
+            })
+            .collect();
+
+        let (t_current, t_next) = self.current_time_bounds();
+        let dual_outputs = component
+            .solve_dual(t_current, t_next, &dual_inputs, parameter)
+            .and_then(|result| result.ok());
+
+        let n = self.time_axis.len();
+        for (name, _) in output_state.iter() {
+            let derivative = dual_outputs
+                .as_ref()
+                .and_then(|outputs| outputs.get(name))
+                .map_or(FloatValue::NAN, |dual| dual.derivative / factor(name));
+            self.tangents
+                .entry(name.clone())
+                .or_insert_with(|| vec![0.0; n])[self.time_index + 1] = derivative;
+        }
+    }
+
+    /// Derivative of a variable with respect to the parameter set using
+    /// [`ModelBuilder::with_sensitivity`]
+    ///
+    /// The derivative is in the units of the variable per unit of the parameter.
+    /// Exogenous variables and the initial values of endogenous variables don't depend on
+    /// the parameter so have a derivative of zero.
+    /// Returns `None` if sensitivities weren't requested or if `name` isn't a variable
+    /// in the model.
+    pub fn sensitivity(&self, name: &str) -> Option<Timeseries<FloatValue>> {
+        self.options.sensitivity.as_ref()?;
+        let timeseries = self.collection.get_timeseries_by_name(name)?;
+        let values = self
+            .tangents
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| vec![0.0; self.time_axis.len()]);
+        Some(Timeseries::new(
+            Array::from_vec(values),
+            self.time_axis.clone(),
+            timeseries.units().to_string(),
+            InterpolationStrategy::from(PreviousStrategy::new(true)),
+        ))
+    }
+
+    /// Step the model forward a step by solving each component for the current time step.
+    ///
+    /// Components are solved in the topological order of the component graph,
+    /// which is calculated once and cached along with the levels of that order
+    /// (see [`execution_order`] and [`execution_levels`]),
+    /// so each component is solved after the components which it depends upon.
+    /// The components in a level may be solved concurrently if
+    /// [`ModelOptions::parallel_components`] is set.
+    ///
+    /// All components are solved even if a component fails.
+    /// The errors of the failed components are returned in execution order.
+    fn step_model(&mut self) -> Vec<RSCMError> {
+        self.init_rng_streams();
+
+        // Models which have been deserialised don't have an execution order yet
+        if self.execution_order.len() != self.components.node_count() {
+            self.execution_order = execution_order(&self.components);
+            self.execution_levels = execution_levels(&self.components, &self.execution_order);
+        }
+
+        // The streams and buffer are taken so that components can be solved while
+        // the rest of the model is borrowed
+        let mut rng_streams = std::mem::take(&mut self.rng_streams);
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let threads = match self.options.parallel_components {
+            true => self.options.threads.max(1),
+            false => 1,
+        };
+        // The pool is only rebuilt if the number of threads has been changed
+        let pool_threads = self
+            .thread_pool
+            .as_ref()
+            .map(ThreadPool::current_num_threads);
+        if threads > 1 && pool_threads != Some(threads) {
+            self.thread_pool = Some(
+                ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .expect("Failed to create the thread pool"),
+            );
+        }
+
+        let mut errors = vec![];
+        for level in self.execution_levels.clone() {
+            let pool = self.thread_pool.as_ref().filter(|_| threads > 1);
+            let results: Vec<RSCMResult<()>> = match pool.filter(|_| level.len() > 1) {
+                Some(pool) => {
+                    let solutions =
+                        self.step_solver()
+                            .solve_concurrently(&level, &mut rng_streams, pool);
+                    // Solutions are stored in execution order
+                    solutions
+                        .into_iter()
+                        .map(|solution| self.store_solution(solution))
+                        .collect()
+                }
+                None => level
+                    .iter()
+                    .map(|nx| {
+                        self.step_model_component(*nx, &mut rng_streams[nx.index()], &mut scratch)
+                    })
+                    .collect(),
+            };
+            errors.extend(results.into_iter().filter_map(Result::err));
+        }
+
+        self.rng_streams = rng_streams;
+        self.scratch = scratch;
+        errors
+    }
+
+    /// Convert the values in `state` which have different units in the component at `nx`
+    ///
+    /// If `to_component`, values are converted from the units of the model's timeseries into the
+    /// component's units, otherwise the reverse.
+    /// Only the converted values are returned.
+    fn convert_units(&self, nx: NodeIndex, state: &InputState, to_component: bool) -> InputState {
+        convert_units(&self.unit_conversions, nx, state, to_component)
+    }
+
+    /// Check the values solved by a component for NaNs according to the model's [`NanPolicy`]
+    fn apply_nan_policy(
+        &mut self,
+        nx: NodeIndex,
+        output_state: OutputState,
+    ) -> RSCMResult<OutputState> {
+        if self.options.nan_policy == NanPolicy::Allow {
+            return Ok(output_state);
+        }
+
+        let mut nans: Vec<&String> = output_state
+            .iter()
+            .filter(|(_, value)| value.is_nan())
+            .map(|(name, _)| name)
+            .collect();
+        if nans.is_empty() {
+            return Ok(output_state);
+        }
+        nans.sort();
+
+        let message = format!(
+            "{} produced NaN values at {} for {:?}",
+            self.component_name(nx),
+            self.current_time(),
+            nans
+        );
+        match self.options.nan_policy {
+            NanPolicy::Error => Err(RSCMError::Error(message)),
+            _ => {
+                self.warnings.push(ModelWarning::at_step(
+                    WarningKind::NanValues,
+                    message,
+                    self.time_index,
+                ));
+                Ok(output_state)
+            }
+        }
+    }
+
+    /// Check the values solved by a component against the bounds declared by the component
+    ///
+    /// Violations are handled according to the model's [`NanPolicy`].
+    fn apply_bounds_policy(
+        &mut self,
+        nx: NodeIndex,
+        output_state: OutputState,
+    ) -> RSCMResult<OutputState> {
+        if self.options.nan_policy == NanPolicy::Allow {
+            return Ok(output_state);
+        }
+
+        let mut violations: Vec<String> = self.components[nx]
+            .outputs()
+            .iter()
+            .filter(|definition| definition.min.is_some() || definition.max.is_some())
+            .filter_map(|definition| {
+                let value = output_state.try_get(&definition.name).ok()?;
+                (!definition.is_within_bounds(*value)).then(|| {
+                    format!(
+                        "{}={} (bounds [{}, {}])",
+                        definition.name,
+                        value,
+                        definition.min.map_or("-inf".to_string(), |v| v.to_string()),
+                        definition.max.map_or("inf".to_string(), |v| v.to_string())
+                    )
+                })
+            })
+            .collect();
+        if violations.is_empty() {
+            return Ok(output_state);
+        }
+        violations.sort();
+
+        let message = format!(
+            "{} produced values outside of their bounds at {}: {}",
+            self.component_name(nx),
+            self.current_time(),
+            violations.join(", ")
+        );
+        match self.options.nan_policy {
+            NanPolicy::Error => Err(RSCMError::Error(message)),
+            _ => {
+                self.warnings.push(ModelWarning::at_step(
+                    WarningKind::OutOfBounds,
+                    message,
+                    self.time_index,
+                ));
+                Ok(output_state)
+            }
+        }
+    }
+
+    /// Create the default random number streams for any nodes without a stream
+    fn init_rng_streams(&mut self) {
+        for i in self.rng_streams.len()..self.components.node_count() {
+            self.rng_streams
+                .push(RngStream::new(DEFAULT_SEED, i as u64));
+        }
+    }
+
+    /// Derive the random number streams from [`ModelOptions::rng_seed`] and
+    /// [`ModelOptions::ensemble_member`]
+    ///
+    /// Models without either keep their existing streams.
+    fn seed_rng_streams(&mut self) {
+        if self.options.rng_seed.is_none() && self.options.ensemble_member.is_none() {
+            return;
+        }
+        self.rng_streams = self
+            .components
+            .node_indices()
+            .map(|nx| {
+                RngStream::for_component(
+                    self.options.rng_seed.unwrap_or(DEFAULT_SEED),
+                    self.options.ensemble_member.unwrap_or(0),
+                    self.component_name(nx),
+                )
+            })
+            .collect();
+    }
+
+    /// Random number streams for each node in the component graph
+    ///
+    /// These can be saved and later restored using [`Model::set_rng_streams`]
+    /// to replay the same random numbers from a checkpoint.
+    pub fn rng_streams(&self) -> &[RngStream] {
+        &self.rng_streams
+    }
+
+    /// Replace the random number streams
+    ///
+    /// There must be a stream for each node in the component graph.
+    pub fn set_rng_streams(&mut self, streams: Vec<RngStream>) -> RSCMResult<()> {
+        if streams.len() != self.components.node_count() {
+            return Err(RSCMError::Error(format!(
+                "Expected {} RNG streams, got {}",
+                self.components.node_count(),
+                streams.len()
+            )));
+        }
+        self.rng_streams = streams;
+        Ok(())
+    }
+
+    /// Reseed the random number stream of a component
+    ///
+    /// `component_name` is the name assigned to the component when it was registered,
+    /// otherwise the name of the component's type.
+    /// An error is returned if the model contains no components with this name,
+    /// or if it contains more than one as they would be ambiguous.
+    pub fn reseed_rng_stream(&mut self, component_name: &str, seed: u64) -> RSCMResult<()> {
+        self.init_rng_streams();
+
+        let mut matches = self
+            .components
+            .node_indices()
+            .filter(|nx| self.component_name(*nx) == component_name);
+        let nx = matches.next().ok_or_else(|| {
+            RSCMError::Error(format!("No component named '{}' in model", component_name))
+        })?;
+        if matches.next().is_some() {
+            return Err(RSCMError::Error(format!(
+                "Multiple components named '{}' in model",
+                component_name
+            )));
+        }
+
+        self.rng_streams[nx.index()].reseed(seed);
+        Ok(())
+    }
+
+    /// Reseed the random number streams of all components
+    ///
+    /// Useful for branching an ensemble member from a checkpoint with fresh noise.
+    pub fn reseed_rng_streams(&mut self, seed: u64) {
+        self.init_rng_streams();
+        self.rng_streams
+            .iter_mut()
+            .for_each(|stream| stream.reseed(seed));
+    }
+
+    /// Save the state of the model so that the run can be resumed later
+    ///
+    /// The state can be saved at any time index and includes the components,
+    /// the values solved so far and the random number streams,
+    /// so a restored model produces identical results to an uninterrupted run.
+    /// Warnings aren't included as they describe the run before the state was saved.
+    pub fn save_state(&self) -> RSCMResult<String> {
+        toml::to_string(&SavedState {
+            format_version: STATE_FORMAT_VERSION,
+            model: self,
+        })
+        .map_err(|e| RSCMError::Error(format!("Could not save model state: {}", e)))
+    }
+
+    /// Restore a model from a state written by [`Model::save_state`]
+    ///
+    /// Returns an error if the state was written using a different format version.
+    pub fn load_state(state: &str) -> RSCMResult<Self> {
+        let mut table = toml::from_str::<toml::Table>(state)
+            .map_err(|e| RSCMError::Error(format!("Invalid model state: {}", e)))?;
+        let version = table.get("format_version").and_then(|v| v.as_integer());
+        if version != Some(STATE_FORMAT_VERSION as i64) {
+            return Err(RSCMError::Error(format!(
+                "Unsupported model state format version {:?}, expected {}",
+                version, STATE_FORMAT_VERSION
+            )));
+        }
+
+        table
+            .remove("model")
+            .ok_or_else(|| RSCMError::Error("Model state doesn't contain a model".to_string()))?
+            .try_into()
+            .map_err(|e| RSCMError::Error(format!("Invalid model state: {}", e)))
+    }
+
+    /// Options which apply to the whole model
+    pub fn options(&self) -> &ModelOptions {
+        &self.options
+    }
+
+    /// Scenario, parameters, versions and timing of the run
+    pub fn metadata(&self) -> &RunMetadata {
+        &self.metadata
+    }
+
+    /// Steps which required refinement to be solved stably
+    pub fn refinements(&self) -> &[StepRefinement] {
+        &self.refinements
+    }
+
+    /// Steps the model forward one time step
+    ///
+    /// This solves the current time step and then updates the index.
+    /// Components which fail to solve the step leave the values they solve as NaN
+    /// and are recorded as [`WarningKind::ComponentFailed`] warnings,
+    /// while values which can't be written to an output sink are recorded as
+    /// [`WarningKind::OutputFailed`] warnings.
+    /// Use [`Model::try_step`] to handle these failures as errors.
+    ///
+    /// Panics if the model has reached the end of its time axis.
+    pub fn step(&mut self) {
+        self.check_not_finished()
+            .unwrap_or_else(|err| panic!("{}", err));
+        let failures = self.solve_step();
+        self.record_failures(&failures);
+    }
+
+    /// Steps the model forward one time step, returning an error if any component failed
+    ///
+    /// The time index is updated even if a component fails.
+    /// Failures are also recorded as warnings in the same way as [`Model::step`],
+    /// but only the first failure is returned.
+    /// Returns an error without solving anything if the model has reached the end of its
+    /// time axis.
+    pub fn try_step(&mut self) -> RSCMResult<()> {
+        self.check_not_finished()?;
+        let failures = self.solve_step();
+        self.record_failures(&failures);
+        failures
+            .into_iter()
+            .next()
+            .map_or(Ok(()), |(_, err)| Err(err))
+    }
+
+    fn check_not_finished(&self) -> RSCMResult<()> {
+        match self.finished() {
+            true => Err(RSCMError::Error(format!(
+                "The model has reached the end of its time axis at {}",
+                self.current_time()
+            ))),
+            false => Ok(()),
+        }
+    }
+
+    /// Record the failures of the previous step as warnings
+    fn record_failures(&mut self, failures: &[(WarningKind, RSCMError)]) {
+        for (kind, err) in failures {
+            let message = match kind {
+                WarningKind::OutputFailed => {
+                    format!("Output at {} failed: {}", self.current_time(), err)
+                }
+                _ => err.to_string(),
+            };
+            self.warnings
+                .push(ModelWarning::at_step(*kind, message, self.time_index - 1));
+        }
+    }
+
+    /// Solve the current time step and then update the index
+    ///
+    /// Returns the failures of the components and the output sinks.
+    fn solve_step(&mut self) -> Vec<(WarningKind, RSCMError)> {
+        let mut failures: Vec<(WarningKind, RSCMError)> = self
+            .step_model()
+            .into_iter()
+            .map(|err| (WarningKind::ComponentFailed, err))
+            .collect();
+        self.check_mass_balance(self.time_index + 1);
+
+        self.time_index += 1;
+        if let Err(err) = self.write_outputs() {
+            failures.push((WarningKind::OutputFailed, err));
+        }
+
+        emit(
+            &self.observers,
+            ModelEvent::StepCompleted {
+                time_index: self.time_index,
+                time: self.current_time(),
+            },
+        );
+        if self.finished() {
+            emit(
+                &self.observers,
+                ModelEvent::RunFinished {
+                    time: self.current_time(),
+                },
+            );
         }
+        failures
+    }
+
+    /// Register an observer which receives the events of the model
+    ///
+    /// [`ModelEvent::StepCompleted`] is emitted after each step is solved,
+    /// [`ModelEvent::ComponentFailed`] when a component fails to solve a step
+    /// and [`ModelEvent::RunFinished`] once the end of the time axis is reached.
+    /// Observers aren't serialised with the model.
+    pub fn add_observer(&mut self, observer: Arc<dyn ModelObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Stream the values of the endogenous variables as each step is completed
+    ///
+    /// The values at the current time are written immediately,
+    /// then the values of each step are written once the step has been solved.
+    /// The sink is finished once the model reaches the end of its time axis.
+    /// Sinks aren't serialised with the model.
+    pub fn add_output_sink(&mut self, sink: Box<dyn OutputSink>) -> RSCMResult<()> {
+        self.output_sinks.push(sink);
+        let index = self.output_sinks.len() - 1;
+        self.write_output(index)
+    }
+
+    /// Write the current values to each output sink
+    fn write_outputs(&mut self) -> RSCMResult<()> {
+        (0..self.output_sinks.len()).try_for_each(|index| self.write_output(index))
+    }
+
+    fn write_output(&mut self, index: usize) -> RSCMResult<()> {
+        let values: Vec<(&str, FloatValue)> = self
+            .collection
+            .iter()
+            .filter(|item| item.variable_type == VariableType::Endogenous)
+            .map(|item| {
+                let value = item
+                    .timeseries
+                    .at(self.time_index)
+                    .unwrap_or(FloatValue::NAN);
+                (item.name.as_str(), value)
+            })
+            .collect();
+        let sink = &mut self.output_sinks[index];
+        sink.write_step(self.time_axis.at(self.time_index).unwrap(), &values)?;
+        match self.time_index == self.time_axis.len() - 1 {
+            true => sink.finish(),
+            false => Ok(()),
+        }
+    }
+
+    /// Evaluate the mass balance checks at a time index
+    ///
+    /// A warning is recorded for each violation.
+    fn check_mass_balance(&mut self, time_index: usize) {
+        for check in self.mass_balance_checks.iter() {
+            self.mass_balance.checked += 1;
+            let violation = check
+                .evaluate(&self.collection, time_index, &self.options.unit_contexts)
+                .ok()
+                .flatten();
+            if let Some(violation) = violation {
+                self.warnings.push(ModelWarning::at_step(
+                    WarningKind::MassBalanceViolation,
+                    format!(
+                        "{} doesn't balance at {}: residual of {} exceeds the tolerance of {}",
+                        violation.check, violation.time, violation.residual, violation.tolerance
+                    ),
+                    time_index - 1,
+                ));
+                self.mass_balance.violations.push(violation);
+            }
+        }
+    }
+
+    /// Violations of the mass balance checks for the steps which have been run
+    ///
+    /// See [`ModelBuilder::with_mass_balance_check`]
+    pub fn mass_balance(&self) -> &MassBalanceReport {
+        &self.mass_balance
+    }
+
+    /// Steps the model until the end of the time axis
+    ///
+    /// The run stops early if any [`RunLimits`] are exceeded.
+    pub fn run(&mut self) {
+        if let Err(err) = self.run_within_limits() {
+            self.warnings.push(ModelWarning::at_step(
+                WarningKind::RunAborted,
+                format!("Run aborted at {}: {}", self.current_time(), err),
+                self.time_index,
+            ));
+        }
+    }
+
+    /// Warnings recorded while building and running the model
+    ///
+    /// Warnings are also emitted using the `log` crate as they are recorded.
+    pub fn warnings(&self) -> &[ModelWarning] {
+        &self.warnings
+    }
+
+    /// Steps the model until the end of the time axis, returning an error if a limit is exceeded
+    ///
+    /// Unlike [`Model::try_run`], failures of individual components don't stop the run.
+    pub fn run_within_limits(&mut self) -> RSCMResult<()> {
+        self.timed(|model, started| {
+            let mut steps = 0;
+            while model.time_index < model.time_axis.len() - 1 {
+                model.check_limits(started, steps)?;
+                model.step();
+                steps += 1;
+            }
+            Ok(())
+        })
+    }
+
+    /// Steps the model until the end of the time axis, stopping at the first failed step
+    ///
+    /// Exceeding any [`RunLimits`] returns [`RSCMError::LimitExceeded`].
+    pub fn try_run(&mut self) -> RSCMResult<()> {
+        self.timed(|model, started| {
+            let mut steps = 0;
+            while model.time_index < model.time_axis.len() - 1 {
+                model.check_limits(started, steps)?;
+                model.try_step()?;
+                steps += 1;
+            }
+            Ok(())
+        })
+    }
+
+    /// Run the model, recording the timing of the run in its metadata
+    fn timed(&mut self, run: impl FnOnce(&mut Self, Instant) -> RSCMResult<()>) -> RSCMResult<()> {
+        let start_time = SystemTime::now();
+        let started = Instant::now();
+        let result = run(self, started);
+        self.metadata.record_run(start_time, started.elapsed());
+        result
+    }
+
+    fn check_limits(&self, started: Instant, steps: usize) -> RSCMResult<()> {
+        match &self.options.limits {
+            Some(limits) => limits.check(started, steps),
+            None => Ok(()),
+        }
+    }
+
+    /// Create a diagram the represents the component graph in the DOT format
+    ///
+    /// Nodes are labelled with the names of the components.
+    /// Useful for debugging
+    pub fn as_dot(&self) -> String {
+        self.diagnostics().as_dot()
+    }
+
+    /// Descriptions of the component graph, e.g. as a Mermaid flowchart or JSON
+    pub fn diagnostics(&self) -> ModelDiagnostics<'_> {
+        ModelDiagnostics::new(self)
+    }
+
+    /// The component graph and the node from which it is traversed
+    pub(crate) fn graph(&self) -> (&CGraph, NodeIndex) {
+        (&self.components, self.initial_node)
+    }
+
+    /// Returns true if the model has no more time steps to process
+    pub fn finished(&self) -> bool {
+        self.time_index == self.time_axis.len() - 1
+    }
+
+    pub fn timeseries(&self) -> &TimeseriesCollection {
+        &self.collection
+    }
+
+    /// Keep the parts of the model which don't change as it's run
+    ///
+    /// The model's warnings, outputs and observers are discarded.
+    pub(crate) fn into_template(self) -> ModelTemplate {
+        ModelTemplate {
+            components: self.components,
+            initial_node: self.initial_node,
+            execution_order: self.execution_order,
+            execution_levels: self.execution_levels,
+            component_names: self.component_names,
+            options: self.options,
+            unit_conversions: self.unit_conversions,
+            mass_balance_checks: self.mass_balance_checks,
+            collection: self.collection,
+        }
+    }
+
+    /// A single line description of the model
+    ///
+    /// This includes the current time, the range of the time axis
+    /// and the number of components, variables and warnings.
+    pub fn summary(&self) -> String {
+        format!(
+            "current time: {}, {}, components: {}, variables: {}, warnings: {}",
+            self.current_time(),
+            self.time_axis,
+            // Excludes the initial null component
+            self.components.node_count() - 1,
+            self.collection.iter().count(),
+            self.warnings.len()
+        )
+    }
+
+    /// HTML description of the model and the state of each variable
+    pub fn to_html(&self) -> String {
+        format!(
+            "<div><strong>Model</strong> {}{}</div>",
+            html::escape(&self.summary()),
+            self.collection.to_html()
+        )
+    }
+
+    /// Hash of the exogenous inputs used by each step
+    ///
+    /// A step uses the values at the start and end of the step
+    /// so the hash for step `i` covers the exogenous values at time indices `i` and `i + 1`.
+    fn exogenous_hashes(&self) -> Vec<u64> {
+        let exogenous: Vec<_> = self
+            .collection
+            .iter()
+            .filter(|item| item.variable_type == VariableType::Exogenous)
+            .collect();
+
+        (0..self.time_axis.len() - 1)
+            .map(|step| {
+                let mut hasher = DefaultHasher::new();
+                exogenous.iter().for_each(|item| {
+                    item.name.hash(&mut hasher);
+                    let values = item.timeseries.values();
+                    [values[step], values[step + 1]]
+                        .iter()
+                        .for_each(|value| value.to_bits().hash(&mut hasher));
+                });
+                hasher.finish()
+            })
+            .collect()
+    }
+
+    /// Convert exogenous data into `units` on `time_axis`
+    ///
+    /// See [`resample_exogenous`].
+    fn resample_exogenous(
+        &mut self,
+        name: &str,
+        timeseries: Timeseries<FloatValue>,
+        units: &str,
+        time_axis: Arc<TimeAxis>,
+    ) -> RSCMResult<Timeseries<FloatValue>> {
+        let timeseries = match timeseries.units().is_empty() {
+            true => timeseries,
+            false => timeseries.convert_unit_with_contexts(units, &self.options.unit_contexts)?,
+        };
+        let strategy = self
+            .components
+            .node_weights()
+            .flat_map(|component| component.variables())
+            .find(|definition| definition.name == name)
+            .map(|definition| definition.disaggregation)
+            .unwrap_or_default();
+        resample_exogenous(name, timeseries, time_axis, strategy, &mut self.warnings)
+            .map_err(|problem| RSCMError::Error(problem.to_string()))
+    }
+
+    /// Replace exogenous timeseries, only recomputing the steps which are affected
+    ///
+    /// Each timeseries in `collection` must replace an existing exogenous variable.
+    /// The data are converted to the model's units and interpolated onto the model's time axis
+    /// in the same way as when the model is built.
+    ///
+    /// The exogenous inputs of each step are compared with the inputs of the existing data.
+    /// The model is rewound to the first step where the inputs differ
+    /// and the endogenous values after this step are cleared,
+    /// so that calling [`Model::run`] only recomputes the period with modified inputs.
+    /// Results before this step are reused unchanged.
+    /// The model isn't rewound if the modified inputs are after the current time.
+    ///
+    /// Random number streams aren't rewound so components which draw random numbers
+    /// won't reproduce the values of a run which was recomputed from the start.
+    /// Output sinks aren't rewound either, so any recomputed steps are written to them again.
+    ///
+    /// If any of the timeseries can't be converted an error is returned
+    /// and none of the timeseries are replaced.
+    ///
+    /// Returns the time index that the model will resume from.
+    pub fn update_exogenous(&mut self, collection: TimeseriesCollection) -> RSCMResult<usize> {
+        let previous_hashes = self.exogenous_hashes();
+
+        // Every item is converted before any are applied so that an error leaves the model unchanged
+        let n_warnings = self.warnings.len();
+        let updates = collection
+            .into_iter()
+            .map(|item| {
+                let existing = self.collection.get_by_name(&item.name).ok_or_else(|| {
+                    RSCMError::Error(format!("No exogenous variable named '{}'", item.name))
+                })?;
+                if existing.variable_type != VariableType::Exogenous {
+                    return Err(RSCMError::Error(format!(
+                        "'{}' is solved by the model and can't be updated",
+                        item.name
+                    )));
+                }
+                let units = existing.timeseries.units().to_string();
+                let timeseries = self.resample_exogenous(
+                    &item.name,
+                    item.timeseries,
+                    &units,
+                    self.time_axis.clone(),
+                )?;
+                Ok((item.name, timeseries))
+            })
+            .collect::<RSCMResult<Vec<_>>>();
+        let updates = match updates {
+            Ok(updates) => updates,
+            Err(err) => {
+                self.warnings.truncate(n_warnings);
+                return Err(err);
+            }
+        };
+        for (name, timeseries) in updates {
+            self.collection
+                .upsert_timeseries(name, timeseries, VariableType::Exogenous);
+        }
+
+        let first_modified = std::iter::zip(previous_hashes, self.exogenous_hashes())
+            .position(|(previous, current)| previous != current);
+        if let Some(step) = first_modified.filter(|step| *step < self.time_index) {
+            self.rewind(step);
+        }
+        Ok(self.time_index)
+    }
+
+    /// Replace a component without rebuilding the model
+    ///
+    /// This allows the parameters of a component to be modified between runs,
+    /// e.g. in a calibration loop, while reusing the component graph and the timeseries.
+    /// `component_name` is the name assigned to the component when it was registered,
+    /// otherwise the name of the component's type.
+    ///
+    /// The new component must have the same variables as the component it replaces,
+    /// with the same units and requirement types, so that the existing coupling
+    /// between components remains valid.
+    /// Components which were registered with renamed variables,
+    /// e.g. using [`ModelBuilder::with_component_namespaced`], must be replaced by a
+    /// [`RemappedComponent`] with the same renaming.
+    /// Initial values and the exogenous data of the model aren't modified,
+    /// so any changes to the default values of the component's inputs are ignored.
+    ///
+    /// The model is rewound to the start of the run and the endogenous values after the
+    /// initial values are cleared, so that calling [`Model::run`] recomputes the whole run.
+    /// Random number streams aren't rewound (see [`Model::reseed_rng_streams`])
+    /// and output sinks receive the recomputed steps again.
+    pub fn replace_component(
+        &mut self,
+        component_name: &str,
+        component: Arc<dyn Component + Send + Sync>,
+    ) -> RSCMResult<()> {
+        let mut matches = self
+            .components
+            .node_indices()
+            .filter(|nx| *nx != self.initial_node)
+            .filter(|nx| self.component_name(*nx) == component_name);
+        let nx = matches.next().ok_or_else(|| {
+            RSCMError::Error(format!("No component named '{}' in model", component_name))
+        })?;
+        if matches.next().is_some() {
+            return Err(RSCMError::Error(format!(
+                "Multiple components named '{}' in model",
+                component_name
+            )));
+        }
+
+        let signature = |component: &dyn Component| {
+            let mut variables: Vec<_> = component
+                .variables()
+                .into_iter()
+                .map(|d| (d.name, d.unit, d.requirement_type))
+                .collect();
+            variables.sort_by(|a, b| a.0.cmp(&b.0));
+            variables
+        };
+        let existing = signature(self.components[nx].as_ref());
+        let replacement = signature(component.as_ref());
+        if existing != replacement {
+            let describe = |variables: &[(String, String, RequirementType)]| {
+                variables
+                    .iter()
+                    .map(|(name, unit, requirement_type)| {
+                        format!("{} [{}] ({:?})", name, unit, requirement_type)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            return Err(RSCMError::Error(format!(
+                "Component '{}' can't be replaced as the variables don't match. Expected: {}. Found: {}",
+                component_name,
+                describe(&existing),
+                describe(&replacement)
+            )));
+        }
+
+        self.components[nx] = component;
+        self.rewind(0);
+        Ok(())
+    }
+
+    /// Rewind the model to a time index, clearing the endogenous values after it
+    ///
+    /// The refinements, sensitivities, mass balance violations and warnings recorded by the
+    /// cleared steps are also removed so they aren't reported twice once the steps are re-solved.
+    /// Output sinks aren't rewound, so they receive the re-solved steps again.
+    fn rewind(&mut self, step: usize) {
+        let endogenous: Vec<String> = self
+            .collection
+            .iter()
+            .filter(|item| item.variable_type == VariableType::Endogenous)
+            .map(|item| item.name.clone())
+            .collect();
+        for name in endogenous {
+            let ts = self.collection.get_timeseries_by_name_mut(&name).unwrap();
+            ts.truncate_after(step);
+        }
+        self.tangents
+            .values_mut()
+            .for_each(|tangents| tangents[step + 1..].fill(0.0));
+
+        let step_time = self.time_axis.at(step).unwrap();
+        let cleared_steps = self.time_index.saturating_sub(step);
+        self.mass_balance.checked = self
+            .mass_balance
+            .checked
+            .saturating_sub(cleared_steps * self.mass_balance_checks.len());
+        self.mass_balance
+            .violations
+            .retain(|violation| violation.time <= step_time);
+        self.warnings.retain(|warning| match warning.time_index {
+            Some(time_index) => time_index < step,
+            None => true,
+        });
+        self.refinements
+            .retain(|refinement| refinement.time_index < step);
+        self.time_index = step;
+    }
+
+    /// Continue the run on a different time axis
+    ///
+    /// This allows a model to be spun up cheaply using long time steps,
+    /// e.g. from a checkpoint written by [`Model::save_state`] on an annual time axis,
+    /// and then continued with shorter time steps, e.g. monthly.
+    /// The current time must be one of the values of `time_axis`.
+    ///
+    /// The endogenous values up to the current time are interpolated onto `time_axis`
+    /// using each timeseries' interpolation strategy, so the state at the current time
+    /// is carried over exactly.
+    /// Values before the start of the original time axis are NaN.
+    ///
+    /// Exogenous variables in `exogenous` replace the existing data,
+    /// in the same way as [`Model::update_exogenous`].
+    /// The data for other exogenous variables are resampled from the original time axis,
+    /// so any variability within the original time steps is lost.
+    ///
+    /// Models which propagate sensitivities can't be regridded.
+    pub fn regrid(
+        &mut self,
+        time_axis: TimeAxis,
+        exogenous: TimeseriesCollection,
+    ) -> RSCMResult<()> {
+        if self.options.sensitivity.is_some() {
+            return Err(RSCMError::Error(
+                "Models which propagate sensitivities can't be regridded".to_string(),
+            ));
+        }
+        if time_axis.unit() != self.time_axis.unit() {
+            return Err(RSCMError::Error(format!(
+                "Expected a time axis in {}, got {}",
+                self.time_axis.unit().symbol(),
+                time_axis.unit().symbol()
+            )));
+        }
+        let current_time = self.current_time();
+        let time_index = time_axis.get_index(current_time).ok_or_else(|| {
+            RSCMError::Error(format!(
+                "The new time axis doesn't contain the current time ({})",
+                current_time
+            ))
+        })?;
+        if let Some(item) = exogenous.iter().find(|item| {
+            self.collection
+                .get_by_name(&item.name)
+                .map(|existing| existing.variable_type)
+                != Some(VariableType::Exogenous)
+        }) {
+            return Err(RSCMError::Error(format!(
+                "No exogenous variable named '{}'",
+                item.name
+            )));
+        }
+        let time_axis = Arc::new(time_axis);
+
+        let mut collection = TimeseriesCollection::new();
+        for item in self.collection.clone() {
+            let units = item.timeseries.units().to_string();
+            let timeseries = match (
+                item.variable_type,
+                exogenous.get_timeseries_by_name(&item.name),
+            ) {
+                (VariableType::Exogenous, Some(replacement)) => self.resample_exogenous(
+                    &item.name,
+                    replacement.clone(),
+                    &units,
+                    time_axis.clone(),
+                )?,
+                (VariableType::Exogenous, None) => {
+                    self.resample_exogenous(&item.name, item.timeseries, &units, time_axis.clone())?
+                }
+                (VariableType::Endogenous, _) => {
+                    self.regrid_history(&item.timeseries, time_axis.clone(), time_index)
+                }
+            };
+            collection.add_timeseries(item.name, timeseries, item.variable_type)?;
+        }
+        // Refinements are kept where the step starts at the same time on the new time axis
+        let refinements = std::mem::take(&mut self.refinements);
+        self.refinements = refinements
+            .into_iter()
+            .filter_map(|refinement| {
+                let time = self.time_axis.at(refinement.time_index)?;
+                Some(StepRefinement {
+                    time_index: time_axis.get_index(time)?,
+                    ..refinement
+                })
+            })
+            .collect();
+
+        self.collection = collection;
+        self.time_axis = time_axis;
+        self.time_index = time_index;
+        Ok(())
+    }
+
+    /// Interpolate the values of an endogenous timeseries up to the current time
+    /// onto a new time axis
+    ///
+    /// `time_index` is the index of the current time on the new time axis.
+    fn regrid_history(
+        &self,
+        timeseries: &Timeseries<FloatValue>,
+        time_axis: Arc<TimeAxis>,
+        time_index: usize,
+    ) -> Timeseries<FloatValue> {
+        let mut regridded = Timeseries::new_empty(
+            time_axis.clone(),
+            timeseries.units().to_string(),
+            timeseries.interpolation_strategy().clone(),
+        );
+        let start = *self.time_axis.first();
+        time_axis
+            .values()
+            .iter()
+            .take(time_index + 1)
+            .enumerate()
+            .filter(|(_, time)| **time >= start)
+            .for_each(|(i, time)| {
+                // Values on the original time axis are used directly as the interpolation
+                // at the current time would use the unsolved values of the next step
+                let value = match self.time_axis.get_index(*time) {
+                    Some(index) => timeseries.at(index),
+                    None => timeseries.at_time(*time).ok(),
+                };
+                regridded.set(i, value.unwrap_or(FloatValue::NAN));
+            });
+        regridded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventRecorder;
+    use crate::example_components::{
+        TestCarbonPoolsComponent, TestComponent, TestComponentParameters, TestCumulativeComponent,
+        TestDecayComponent, TestEquilibriumComponent, TestNoiseComponent,
+        TestOptionalInputComponent, TestStiffComponent, TestSumComponent, TestUnitsComponent,
+    };
+    use is_close::is_close;
+    use numpy::array;
+    use numpy::ndarray::Array;
+    use std::iter::zip;
+    use std::sync::Mutex;
+
+    fn get_emissions() -> Timeseries<FloatValue> {
+        Timeseries::new(
+            array![0.0, 10.0],
+            Arc::new(TimeAxis::from_bounds(array![1800.0, 1850.0, 2100.0])),
+            "GtC / yr".to_string(),
+            InterpolationStrategy::from(PreviousStrategy::new(true)),
+        )
+    }
+
+    #[test]
+    fn step() {
+        let time_axis = TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0));
+        let mut model = ModelBuilder::new()
+            .with_time_axis(time_axis)
+            .with_component(Arc::new(TestComponent::from_parameters(
+                TestComponentParameters { p: 0.5 },
+            )))
+            .with_exogenous_variable("Emissions|CO2", get_emissions())
+            .build();
+
+        assert_eq!(model.time_index, 0);
+        model.step();
+        model.step();
+        assert_eq!(model.time_index, 2);
+        assert_eq!(model.current_time(), 2022.0);
+        model.run();
+        assert_eq!(model.time_index, 4);
+        assert!(model.finished());
+        assert!(model.try_step().is_err());
+        assert_eq!(
+            model.summary(),
+            "current time: 2024, len: 5, range: [2020.0, 2025.0), components: 1, variables: 2, warnings: 1"
+        );
+        assert!(model.to_html().contains("<td>Concentrations|CO2</td>"));
+
+        let concentrations = model
+            .collection
+            .get_timeseries_by_name("Concentrations|CO2")
+            .unwrap();
+
+        println!("{:?}", concentrations.values());
+
+        // The first value for an endogenous timeseries without a y0 value is NaN.
+        // This is because the values in the timeseries represents the state at the start
+        // of a time step.
+        // Since the values from t-1 aren't known we can't solve for y0
+        assert!(concentrations.at(0).unwrap().is_nan());
+        let mut iter = concentrations.values().into_iter();
+        iter.next(); // Skip the first value
+        assert!(iter.all(|x| !x.is_nan()));
+    }
+
+    #[test]
+    fn sensitivity() {
+        let build = |p: FloatValue, tau: FloatValue| {
+            let mut builder = ModelBuilder::new();
+            builder
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2030.0, 1.0)))
+                .with_component(Arc::new(TestComponent::from_parameters(
+                    TestComponentParameters { p },
+                )))
+                .with_component(Arc::new(TestDecayComponent { tau }))
+                .with_exogenous_variable("Emissions|CO2", get_emissions())
+                .with_initial_values(InputState::from_vectors(
+                    vec![0.0],
+                    vec!["Concentrations|CO2".to_string()],
+                ));
+            builder
+        };
+        let stock = |p: FloatValue, tau: FloatValue| {
+            let mut model = build(p, tau).build();
+            model.run();
+            model
+                .timeseries()
+                .get_timeseries_by_name("Stock")
+                .unwrap()
+                .values()
+                .to_owned()
+        };
+
+        let mut model = build(0.5, 4.0).build();
+        model.run();
+        assert!(model.sensitivity("Stock").is_none());
+
+        for (parameter, expected) in [
+            (
+                "tau",
+                (stock(0.5, 4.0 + 1e-5) - stock(0.5, 4.0 - 1e-5)) / 2e-5,
+            ),
+            (
+                "p",
+                (stock(0.5 + 1e-5, 4.0) - stock(0.5 - 1e-5, 4.0)) / 2e-5,
+            ),
+        ] {
+            let mut model = build(0.5, 4.0).with_sensitivity(parameter).build();
+            model.run();
+
+            let sensitivity = model.sensitivity("Stock").unwrap();
+            assert_eq!(sensitivity.values()[0], 0.0);
+            zip(sensitivity.values(), expected.iter())
+                .for_each(|(a, b)| assert!(is_close!(*a, *b, rel_tol = 1e-6, abs_tol = 1e-8)));
+        }
+
+        let mut model = build(0.5, 4.0).with_sensitivity("p").build();
+        model.run();
+        let concentration = model.sensitivity("Concentrations|CO2").unwrap();
+        assert_eq!(
+            concentration.values().slice(numpy::ndarray::s![1..]),
+            Array::from_elem(9, 1.3)
+        );
+        assert!(model
+            .sensitivity("Emissions|CO2")
+            .unwrap()
+            .values()
+            .iter()
+            .all(|v| *v == 0.0));
+        assert!(model.sensitivity("Unknown").is_none());
+
+        // Components which don't support dual numbers have unknown sensitivities
+        let mut model = build(0.5, 4.0)
+            .with_component(Arc::new(TestNoiseComponent {
+                name: "Noise".to_string(),
+            }))
+            .with_sensitivity("p")
+            .build();
+        model.run();
+        assert!(model.sensitivity("Noise").unwrap().values()[1].is_nan());
+    }
+
+    #[test]
+    fn decadal_exogenous_data() {
+        let build = |emissions: Timeseries<FloatValue>| {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2030.0, 1.0)))
+                .with_component(Arc::new(TestComponent::from_parameters(
+                    TestComponentParameters { p: 0.5 },
+                )))
+                .with_exogenous_variable("Emissions|CO2", emissions)
+                .build()
+        };
+        let emissions = |strategy: InterpolationStrategy| {
+            let mut emissions = Timeseries::from_values(
+                array![0.0, 10.0, 20.0, 30.0],
+                array![2010.0, 2020.0, 2030.0, 2040.0],
+            );
+            emissions.with_interpolation_strategy(strategy);
+            emissions
+        };
+        let values = |model: &Model| {
+            model
+                .timeseries()
+                .get_timeseries_by_name("Emissions|CO2")
+                .unwrap()
+                .values()
+                .to_vec()
+        };
+
+        let model = build(emissions(InterpolationStrategy::from(
+            LinearSplineStrategy::new(false),
+        )));
+        assert_eq!(values(&model)[..3], [10.0, 11.0, 12.0]);
+
+        let model = build(emissions(InterpolationStrategy::from(
+            PreviousStrategy::new(false),
+        )));
+        assert_eq!(values(&model)[..3], [10.0, 10.0, 10.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Exogenous data for Emissions|CO2 can't be interpolated")]
+    fn exogenous_data_not_covering_time_axis() {
+        let mut emissions = Timeseries::from_values(array![0.0, 10.0], array![2020.0, 2025.0]);
+        emissions.with_interpolation_strategy(InterpolationStrategy::from(
+            LinearSplineStrategy::new(false),
+        ));
+        ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2030.0, 1.0)))
+            .with_component(Arc::new(TestComponent::from_parameters(
+                TestComponentParameters { p: 0.5 },
+            )))
+            .with_exogenous_variable("Emissions|CO2", emissions)
+            .build();
+    }
+
+    #[test]
+    fn update_exogenous() {
+        let build = |emissions: Timeseries<FloatValue>| {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2030.0, 1.0)))
+                .with_component(Arc::new(TestComponent::from_parameters(
+                    TestComponentParameters { p: 0.5 },
+                )))
+                .with_exogenous_variable("Emissions|CO2", emissions)
+                .build()
+        };
+        let modified = Timeseries::from_values(
+            array![10.0, 10.0, 20.0, 20.0],
+            array![2000.0, 2024.0, 2025.0, 2100.0],
+        );
+
+        let mut model = build(get_emissions());
+        model.run();
+        let original = model.timeseries().clone();
+
+        let mut collection = TimeseriesCollection::new();
+        collection
+            .add_timeseries(
+                "Emissions|CO2".to_string(),
+                modified.clone(),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+        // The step from 2024 to 2025 is the first which uses the modified data
+        assert_eq!(model.update_exogenous(collection).unwrap(), 4);
+        assert_eq!(model.current_time(), 2024.0);
+
+        let concentrations = |model: &Model| {
+            model
+                .timeseries()
+                .get_timeseries_by_name("Concentrations|CO2")
+                .unwrap()
+                .values()
+                .to_vec()
+        };
+        let partial = concentrations(&model);
+        assert_eq!(
+            partial[1..5],
+            original
+                .get_timeseries_by_name("Concentrations|CO2")
+                .unwrap()
+                .values()
+                .to_vec()[1..5]
+        );
+        assert!(partial[5..].iter().all(|v| v.is_nan()));
+
+        model.run();
+        let mut expected = build(modified);
+        expected.run();
+        assert_eq!(concentrations(&model)[1..], concentrations(&expected)[1..]);
+
+        // Only exogenous variables can be updated
+        let mut collection = TimeseriesCollection::new();
+        collection
+            .add_timeseries(
+                "Concentrations|CO2".to_string(),
+                get_emissions(),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+        assert!(model.update_exogenous(collection).is_err());
+
+        // Data which don't cover the time axis are an error
+        let mut short = Timeseries::from_values(array![10.0, 10.0], array![2000.0, 2025.0]);
+        short.with_interpolation_strategy(InterpolationStrategy::from(LinearSplineStrategy::new(
+            false,
+        )));
+        let mut collection = TimeseriesCollection::new();
+        collection
+            .add_timeseries("Emissions|CO2".to_string(), short, VariableType::Exogenous)
+            .unwrap();
+        let err = model.update_exogenous(collection).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Exogenous data for Emissions|CO2 can't be interpolated"));
+    }
+
+    #[test]
+    fn update_exogenous_is_atomic() {
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2030.0, 1.0)))
+            .with_component(Arc::new(TestComponent::from_parameters(
+                TestComponentParameters { p: 0.5 },
+            )))
+            .with_exogenous_variable("Emissions|CO2", get_emissions())
+            .build();
+        model.run();
+        let original = model.timeseries().clone();
+        let n_warnings = model.warnings().len();
+
+        // The first item is valid but the second isn't
+        let mut collection = TimeseriesCollection::new();
+        collection
+            .add_timeseries(
+                "Emissions|CO2".to_string(),
+                Timeseries::from_values(array![20.0, 20.0], array![2000.0, 2100.0]),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+        collection
+            .add_timeseries(
+                "Emissions|CH4".to_string(),
+                get_emissions(),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+        let err = model.update_exogenous(collection).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No exogenous variable named 'Emissions|CH4'"
+        );
+
+        let values = |collection: &TimeseriesCollection, name: &str| {
+            collection
+                .get_timeseries_by_name(name)
+                .unwrap()
+                .values()
+                .to_vec()
+        };
+        assert_eq!(model.current_time(), 2029.0);
+        assert_eq!(
+            values(model.timeseries(), "Emissions|CO2"),
+            values(&original, "Emissions|CO2")
+        );
+        assert_eq!(
+            values(model.timeseries(), "Concentrations|CO2")[1..],
+            values(&original, "Concentrations|CO2")[1..]
+        );
+        assert_eq!(model.warnings().len(), n_warnings);
+    }
+
+    #[test]
+    fn update_exogenous_with_state() {
+        let build = |emissions: Timeseries<FloatValue>| {
+            // Never balances so a violation is recorded for every step
+            let mut check = MassBalanceCheck::new("Stock");
+            check.with_source("Stock");
+
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2030.0, 1.0)))
+                .with_component(Arc::new(TestComponent::from_parameters(
+                    TestComponentParameters { p: 0.5 },
+                )))
+                .with_component(Arc::new(TestDecayComponent { tau: 5.0 }))
+                .with_exogenous_variable("Emissions|CO2", emissions)
+                .with_mass_balance_check(check)
+                .build()
+        };
+        let modified = Timeseries::from_values(
+            array![10.0, 10.0, 20.0, 20.0],
+            array![2000.0, 2024.0, 2025.0, 2100.0],
+        );
+
+        let mut model = build(get_emissions());
+        model.run();
+
+        let mut collection = TimeseriesCollection::new();
+        collection
+            .add_timeseries(
+                "Emissions|CO2".to_string(),
+                modified.clone(),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+        assert_eq!(model.update_exogenous(collection).unwrap(), 4);
+        let stock = model.timeseries().get_timeseries_by_name("Stock").unwrap();
+        assert_eq!(*stock.latest(), 4);
+        assert_eq!(model.mass_balance().violations.len(), 4);
+
+        model.run();
+        let mut expected = build(modified);
+        expected.run();
+
+        let stock = |model: &Model| {
+            model
+                .timeseries()
+                .get_timeseries_by_name("Stock")
+                .unwrap()
+                .values()
+                .to_vec()
+        };
+        assert!(stock(&model).iter().all(|v| !v.is_nan()));
+        assert_eq!(stock(&model), stock(&expected));
+
+        // Records of the re-solved steps aren't duplicated
+        let violations = |model: &Model| {
+            model
+                .warnings()
+                .iter()
+                .filter(|w| w.kind == WarningKind::MassBalanceViolation)
+                .count()
+        };
+        assert_eq!(model.mass_balance(), expected.mass_balance());
+        assert_eq!(violations(&model), 9);
+        assert_eq!(violations(&expected), 9);
+    }
+
+    #[test]
+    fn replace_component() {
+        let build = |p: FloatValue| {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2030.0, 1.0)))
+                .with_named_component(
+                    "carbon",
+                    Arc::new(TestComponent::from_parameters(TestComponentParameters {
+                        p,
+                    })),
+                )
+                .with_exogenous_variable("Emissions|CO2", get_emissions())
+                .build()
+        };
+        let concentrations = |model: &Model| {
+            model
+                .timeseries()
+                .get_timeseries_by_name("Concentrations|CO2")
+                .unwrap()
+                .values()
+                .to_vec()
+        };
+
+        let mut model = build(0.5);
+        model.run();
+        model
+            .replace_component(
+                "carbon",
+                Arc::new(TestComponent::from_parameters(TestComponentParameters {
+                    p: 1.0,
+                })),
+            )
+            .unwrap();
+        assert_eq!(model.current_time(), 2020.0);
+        assert!(concentrations(&model)[1..].iter().all(|v| v.is_nan()));
+
+        model.run();
+        let mut expected = build(1.0);
+        expected.run();
+        assert_eq!(concentrations(&model)[1..], concentrations(&expected)[1..]);
+
+        // The replacement must be coupled to the same variables
+        let err = model
+            .replace_component("carbon", Arc::new(TestUnitsComponent {}))
+            .unwrap_err();
+        assert!(err.to_string().contains("variables don't match"));
+        assert!(model
+            .replace_component(
+                "TestComponent",
+                Arc::new(TestComponent::from_parameters(TestComponentParameters {
+                    p: 1.0
+                })),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn replace_coupled_component() {
+        let build = |tau: FloatValue| {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2030.0, 1.0)))
+                .with_component(Arc::new(TestComponent::from_parameters(
+                    TestComponentParameters { p: 0.5 },
+                )))
+                .with_named_component("decay", Arc::new(TestDecayComponent { tau }))
+                .with_exogenous_variable("Emissions|CO2", get_emissions())
+                .build()
+        };
+        let stock = |model: &Model| {
+            model
+                .timeseries()
+                .get_timeseries_by_name("Stock")
+                .unwrap()
+                .values()
+                .to_vec()
+        };
+
+        let mut model = build(5.0);
+        model.run();
+        model
+            .replace_component("decay", Arc::new(TestDecayComponent { tau: 10.0 }))
+            .unwrap();
+        model.run();
+
+        // The stock reads the solved concentrations and its own state
+        let mut expected = build(10.0);
+        expected.run();
+        assert!(stock(&model).iter().all(|v| !v.is_nan()));
+        assert_eq!(stock(&model), stock(&expected));
+    }
+
+    #[test]
+    fn disaggregation() {
+        let build = |values| {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2000.0, 2002.0, 0.25)))
+                .with_component(Arc::new(TestCumulativeComponent {}))
+                .with_exogenous_variable(
+                    "Emissions|CO2",
+                    Timeseries::new(
+                        values,
+                        Arc::new(TimeAxis::from_values(array![2000.0, 2001.0, 2002.0])),
+                        "GtC".to_string(),
+                        InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+                    ),
+                )
+                .build()
+        };
+
+        // Annual totals are split between the quarters rather than interpolated
+        let mut model = build(array![4.0, 8.0, 8.0]);
+        assert!(model.warnings().is_empty());
+        let emissions = model
+            .timeseries()
+            .get_timeseries_by_name("Emissions|CO2")
+            .unwrap();
+        assert_eq!(emissions.at(3).unwrap(), 1.0);
+        assert_eq!(emissions.at(4).unwrap(), 2.0);
+
+        model.run();
+        let cumulative = model
+            .timeseries()
+            .get_timeseries_by_name("Cumulative Emissions|CO2")
+            .unwrap();
+        assert_eq!(cumulative.at_time(2001.0).unwrap(), 4.0);
+
+        // Data with steps that don't align with the model
+        let model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2000.0, 2002.0, 1.0)))
+            .with_component(Arc::new(TestCumulativeComponent {}))
+            .with_exogenous_variable(
+                "Emissions|CO2",
+                Timeseries::from_values(array![1.0, 1.0, 1.0], array![2000.0, 2000.5, 2001.0]),
+            )
+            .build();
+        assert_eq!(
+            model.warnings()[0].message,
+            "Time steps of the exogenous data for Emissions|CO2 don't align with the model's time steps"
+        );
+    }
+
+    #[test]
+    fn optional_input_default() {
+        let time_axis = TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0));
+        let mut model = ModelBuilder::new()
+            .with_time_axis(time_axis)
+            .with_component(Arc::new(TestOptionalInputComponent {}))
+            .build();
+        model.run();
+
+        let item = model
+            .timeseries()
+            .get_by_name("Effective Radiative Forcing|Aerosols")
+            .unwrap();
+        assert_eq!(item.variable_type, VariableType::Exogenous);
+        assert_eq!(item.timeseries.units(), "W/m^2");
+        assert!(item.timeseries.values().iter().all(|v| *v == 0.0));
+
+        let total = model
+            .timeseries()
+            .get_timeseries_by_name("Effective Radiative Forcing|Total")
+            .unwrap();
+        assert_eq!(total.at_time(2024.0).unwrap(), 0.0);
+
+        // Exogenous data takes precedence over the default
+        let model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_component(Arc::new(TestOptionalInputComponent {}))
+            .with_exogenous_variable(
+                "Effective Radiative Forcing|Aerosols",
+                Timeseries::from_values(array![-0.5, -0.5], array![2000.0, 2100.0]),
+            )
+            .build();
+        let aerosols = model
+            .timeseries()
+            .get_timeseries_by_name("Effective Radiative Forcing|Aerosols")
+            .unwrap();
+        assert_eq!(aerosols.at_time(2022.0).unwrap(), -0.5);
+    }
+
+    #[test]
+    fn initialise() {
+        let build = |initial_values: InputState| {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+                .with_component(Arc::new(TestEquilibriumComponent { equilibrium: 2.0 }))
+                .with_initial_values(initial_values)
+                .build()
+        };
+        let level = |model: &Model| {
+            model
+                .timeseries()
+                .get_timeseries_by_name("Level")
+                .unwrap()
+                .values()
+                .to_vec()
+        };
+
+        // The component starts in equilibrium
+        let mut model = build(InputState::empty());
+        model.run();
+        assert_eq!(level(&model), vec![2.0; 5]);
+
+        // Initial values provided by the user take precedence
+        let mut model = build(InputState::from_vectors(
+            vec![0.0],
+            vec!["Level".to_string()],
+        ));
+        model.run();
+        assert_eq!(level(&model)[..3], [0.0, 1.0, 1.5]);
+    }
+
+    #[test]
+    fn model_options() {
+        let build = |options: ModelOptions| {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+                .with_component(Arc::new(TestOptionalInputComponent {}))
+                .with_exogenous_variable(
+                    "Effective Radiative Forcing|Aerosols",
+                    Timeseries::from_values(
+                        array![-0.5, FloatValue::NAN, -0.5],
+                        array![2020.0, 2022.0, 2025.0],
+                    ),
+                )
+                .with_options(options)
+                .build()
+        };
+
+        // The NaN is interpolated onto 2021. NaNs are stored by default
+        let mut model = build(ModelOptions::default());
+        assert!(model.try_run().is_ok());
+
+        let mut model = build(ModelOptions {
+            nan_policy: NanPolicy::Error,
+            ..Default::default()
+        });
+        match model.try_run() {
+            Err(RSCMError::ComponentFailed {
+                component,
+                time,
+                source,
+                ..
+            }) => {
+                assert_eq!(component, "TestOptionalInputComponent");
+                assert_eq!(time, 2021.0);
+                assert_eq!(
+                    source.to_string(),
+                    "TestOptionalInputComponent produced NaN values at 2021 for [\"Effective Radiative Forcing|Total\"]"
+                );
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+
+        // Failures are recorded as warnings when they aren't returned
+        let mut model = build(ModelOptions {
+            nan_policy: NanPolicy::Error,
+            ..Default::default()
+        });
+        model.run();
+        assert!(model.finished());
+        let failures: Vec<&ModelWarning> = model
+            .warnings()
+            .iter()
+            .filter(|w| w.kind == WarningKind::ComponentFailed)
+            .collect();
+        assert!(!failures.is_empty());
+        assert_eq!(failures[0].time_index, Some(1));
+        assert!(failures[0]
+            .message
+            .starts_with("TestOptionalInputComponent failed to solve at 2021"));
+
+        // The extrapolation policy applies to the timeseries solved by the model
+        let model = build(ModelOptions {
+            extrapolation: ExtrapolationPolicy::Error,
+            ..Default::default()
+        });
+        let total = model
+            .timeseries()
+            .get_timeseries_by_name("Effective Radiative Forcing|Total")
+            .unwrap();
+        assert!(matches!(
+            total.at_time(2030.0),
+            Err(RSCMError::ExtrapolationNotAllowed(..))
+        ));
+    }
+
+    #[test]
+    fn output_bounds() {
+        let build = |p: FloatValue, nan_policy: NanPolicy| {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0)))
+                .with_component(Arc::new(TestComponent::from_parameters(
+                    TestComponentParameters { p },
+                )))
+                .with_options(ModelOptions {
+                    nan_policy,
+                    ..Default::default()
+                })
+                .build()
+        };
+
+        // Values outside of the bounds are stored by default
+        let mut model = build(-1.0, NanPolicy::Allow);
+        assert!(model.try_run().is_ok());
+
+        let mut model = build(0.5, NanPolicy::Error);
+        assert!(model.try_run().is_ok());
+
+        let mut model = build(-1.0, NanPolicy::Warn);
+        model.run();
+        let warnings: Vec<&ModelWarning> = model
+            .warnings()
+            .iter()
+            .filter(|w| w.kind == WarningKind::OutOfBounds)
+            .collect();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[0].message,
+            "TestComponent produced values outside of their bounds at 2020: Concentrations|CO2=-1.3 (bounds [0, inf])"
+        );
+
+        let mut model = build(-1.0, NanPolicy::Error);
+        match model.try_run() {
+            Err(RSCMError::ComponentFailed {
+                component, time, ..
+            }) => {
+                assert_eq!(component, "TestComponent");
+                assert_eq!(time, 2020.0);
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn events() {
+        let recorder = EventRecorder::new();
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2024.0, 1.0)))
+            .with_named_component("forcing", Arc::new(TestOptionalInputComponent {}))
+            .with_exogenous_variable(
+                "Effective Radiative Forcing|Aerosols",
+                Timeseries::from_values(
+                    array![-0.5, FloatValue::NAN, -0.5],
+                    array![2020.0, 2022.0, 2025.0],
+                ),
+            )
+            .with_options(ModelOptions {
+                nan_policy: NanPolicy::Error,
+                ..Default::default()
+            })
+            .with_observer(Arc::new(recorder.clone()))
+            .build();
+        assert_eq!(
+            recorder.events(),
+            vec![ModelEvent::BuildStarted {
+                components: vec!["forcing".to_string()]
+            }]
+        );
+
+        model.run();
+        let events = recorder.events();
+        assert_eq!(
+            events[1..3],
+            [
+                ModelEvent::StepCompleted {
+                    time_index: 1,
+                    time: 2021.0
+                },
+                ModelEvent::ComponentFailed {
+                    component: "forcing".to_string(),
+                    time_index: 1,
+                    time: 2021.0,
+                    message: "forcing produced NaN values at 2021 for [\"Effective Radiative Forcing|Total\"]"
+                        .to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, ModelEvent::StepCompleted { .. }))
+                .count(),
+            3
+        );
+        assert_eq!(
+            events.last(),
+            Some(&ModelEvent::RunFinished { time: 2023.0 })
+        );
+
+        // Observers can also be added to a model which has been built
+        let recorder = EventRecorder::new();
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2022.0, 1.0)))
+            .with_component(Arc::new(TestComponent::from_parameters(
+                TestComponentParameters { p: 0.5 },
+            )))
+            .with_exogenous_variable("Emissions|CO2", get_emissions())
+            .build();
+        model.add_observer(Arc::new(recorder.clone()));
+        model.run();
+        assert_eq!(recorder.events().len(), 2);
+    }
+
+    #[test]
+    fn unit_conversion() {
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0)))
+            .with_component(Arc::new(TestOptionalInputComponent {}))
+            .with_component(Arc::new(TestUnitsComponent {}))
+            .with_initial_values(InputState::from_vectors(
+                vec![0.0],
+                vec!["Effective Radiative Forcing|Total".to_string()],
+            ))
+            .with_exogenous_variable(
+                "Effective Radiative Forcing|Aerosols",
+                Timeseries::new(
+                    array![-500.0, -500.0, -500.0],
+                    Arc::new(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0))),
+                    "mW / m^2".to_string(),
+                    InterpolationStrategy::from(PreviousStrategy::new(true)),
+                ),
+            )
+            .build();
+        model.run();
+
+        let get = |name: &str| model.timeseries().get_timeseries_by_name(name).unwrap();
+
+        // Exogenous data is converted to the units of the component
+        let aerosols = get("Effective Radiative Forcing|Aerosols");
+        assert_eq!(aerosols.units(), "W/m^2");
+        assert_eq!(aerosols.at(0).unwrap(), -0.5);
+        assert_eq!(
+            get("Effective Radiative Forcing|Total").at(1).unwrap(),
+            -0.5
+        );
+        // Values are converted when passed between components
+        assert_eq!(
+            get("Effective Radiative Forcing|Doubled").at(2).unwrap(),
+            -1000.0
+        );
+    }
+
+    #[test]
+    fn mass_balance() {
+        let build = |leak: FloatValue| {
+            let mut check = MassBalanceCheck::new("Carbon");
+            check
+                .with_source("Cumulative Emissions|CO2")
+                .with_sink("Carbon|Atmosphere")
+                .with_sink("Carbon|Ocean");
+
+            let mut model = ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2024.0, 1.0)))
+                .with_component(Arc::new(TestCarbonPoolsComponent { leak }))
+                .with_exogenous_variable(
+                    "Emissions|CO2",
+                    Timeseries::new(
+                        array![10.0, 10.0, 10.0, 10.0],
+                        Arc::new(TimeAxis::from_values(Array::range(2020.0, 2024.0, 1.0))),
+                        "GtC".to_string(),
+                        InterpolationStrategy::from(PreviousStrategy::new(true)),
+                    ),
+                )
+                .with_mass_balance_check(check)
+                .build();
+            model.run();
+            model
+        };
+
+        let model = build(0.0);
+        assert_eq!(model.mass_balance().checked, 3);
+        assert!(model.mass_balance().is_balanced());
+
+        let model = build(0.1);
+        let report = model.mass_balance();
+        assert_eq!(report.violations.len(), 3);
+        assert_eq!(report.violations[0].time, 2021.0);
+        assert!(is_close!(report.violations[0].residual, 1.0));
+        assert!(is_close!(report.violations[2].residual, 3.0));
+        assert!(model
+            .warnings()
+            .iter()
+            .any(|w| w.kind == WarningKind::MassBalanceViolation));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid mass balance check Carbon")]
+    fn mass_balance_unknown_variable() {
+        let mut check = MassBalanceCheck::new("Carbon");
+        check.with_source("Emissions|CO2").with_sink("Carbon|Land");
+
+        ModelBuilder::new()
+            .with_component(Arc::new(TestCumulativeComponent {}))
+            .with_mass_balance_check(check)
+            .build();
+    }
+
+    #[test]
+    fn exogenous_unit_conversion_with_context() {
+        let build = |unit_contexts: Vec<String>| {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0)))
+                .with_component(Arc::new(TestComponent::from_parameters(
+                    TestComponentParameters { p: 0.5 },
+                )))
+                .with_exogenous_variable(
+                    "Emissions|CO2",
+                    Timeseries::new(
+                        array![1000.0, 2000.0, 3000.0],
+                        Arc::new(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0))),
+                        "MtC".to_string(),
+                        InterpolationStrategy::from(PreviousStrategy::new(true)),
+                    ),
+                )
+                .with_options(ModelOptions {
+                    unit_contexts,
+                    ..Default::default()
+                })
+                .build()
+        };
+
+        // Exogenous data is converted using the enabled contexts
+        let model = build(vec!["CO2".to_string()]);
+        let emissions = model
+            .timeseries()
+            .get_timeseries_by_name("Emissions|CO2")
+            .unwrap();
+        assert_eq!(emissions.units(), "GtCO2");
+        assert!(is_close!(emissions.at(1).unwrap(), 2.0 * 44.009 / 12.011));
+
+        // Otherwise the data are used unchanged with a warning
+        let model = build(vec![]);
+        assert_eq!(
+            model
+                .timeseries()
+                .get_timeseries_by_name("Emissions|CO2")
+                .unwrap()
+                .units(),
+            "MtC"
+        );
+        assert!(model
+            .warnings()
+            .iter()
+            .any(|w| w.kind == WarningKind::IncompatibleUnits));
+    }
+
+    #[test]
+    #[should_panic(expected = "No exogenous data for Emissions|CO2")]
+    fn strict_model_options() {
+        ModelBuilder::new()
+            .with_component(Arc::new(TestComponent::from_parameters(
+                TestComponentParameters { p: 0.5 },
+            )))
+            .with_options(ModelOptions {
+                strict: true,
+                ..Default::default()
+            })
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "No initial value for Y")]
+    fn strict_model_options_uninitialised() {
+        ModelBuilder::new()
+            .with_component(Arc::new(TestSumComponent {
+                inputs: vec!["Y".to_string()],
+                output: "Y".to_string(),
+            }))
+            .with_options(ModelOptions {
+                strict: true,
+                ..Default::default()
+            })
+            .build();
+    }
+
+    #[test]
+    fn try_build() {
+        let sum = |inputs: &[&str], output: &str| {
+            Arc::new(TestSumComponent {
+                inputs: inputs.iter().map(|name| name.to_string()).collect(),
+                output: output.to_string(),
+            })
+        };
+        let mut builder = ModelBuilder::new();
+        builder
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_component(Arc::new(TestComponent::from_parameters(
+                TestComponentParameters { p: 0.5 },
+            )))
+            .with_component(sum(&["Z"], "W"))
+            .with_component(sum(&["Y"], "Y"));
+
+        let problems = builder.try_build().unwrap_err().problems;
+        assert_eq!(problems.len(), 3);
+        assert!(problems.contains(&BuildProblem::MissingExogenousData(
+            "Emissions|CO2".to_string()
+        )));
+        assert!(problems.contains(&BuildProblem::MissingExogenousData("Z".to_string())));
+        assert!(problems.contains(&BuildProblem::Uninitialised("Y".to_string())));
+        // The non-strict model can still be built with warnings
+        assert_eq!(builder.build().warnings().len(), 3);
+
+        // Units which can't be converted
+        builder
+            .with_exogenous_variable(
+                "Emissions|CO2",
+                Timeseries::new(
+                    array![1.0, 1.0],
+                    Arc::new(TimeAxis::from_values(array![2000.0, 2100.0])),
+                    "K".to_string(),
+                    InterpolationStrategy::from(PreviousStrategy::new(true)),
+                ),
+            )
+            .with_exogenous_variable(
+                "Z",
+                Timeseries::from_values(array![1.0, 1.0], array![2000.0, 2100.0]),
+            )
+            .with_initial_values(InputState::from_vectors(vec![0.0], vec!["Y".to_string()]))
+            .with_component(Arc::new(TestEquilibriumComponent { equilibrium: 1.0 }))
+            .with_component(sum(&["Level"], "V"));
+        let err = builder.try_build().unwrap_err();
+        assert_eq!(err.problems.len(), 2);
+        assert!(matches!(
+            &err.problems[0],
+            BuildProblem::InconsistentUnits { variable, .. } if variable == "Level"
+        ));
+        assert!(matches!(
+            &err.problems[1],
+            BuildProblem::IncompatibleUnits { variable, .. } if variable == "Emissions|CO2"
+        ));
+        assert!(err
+            .to_string()
+            .starts_with("Model can't be built: Inconsistent units for Level"));
+    }
+
+    #[test]
+    fn try_build_duplicate_component_names() {
+        let component = || {
+            Arc::new(TestComponent::from_parameters(TestComponentParameters {
+                p: 0.5,
+            }))
+        };
+        let err = ModelBuilder::new()
+            .with_named_component("ocean", component())
+            .with_named_component("ocean", component())
+            .with_named_component("ocean", component())
+            .with_exogenous_variable(
+                "Emissions|CO2",
+                Timeseries::from_values(array![10.0, 10.0], array![2000.0, 2100.0]),
+            )
+            .try_build()
+            .unwrap_err();
+        assert_eq!(
+            err.problems,
+            vec![BuildProblem::DuplicateComponentName("ocean".to_string())]
+        );
+    }
+
+    #[test]
+    fn try_build_invalid_mass_balance_check() {
+        let mut check = MassBalanceCheck::new("Carbon");
+        check.with_source("Emissions|CO2").with_sink("Carbon|Land");
+
+        let err = ModelBuilder::new()
+            .with_component(Arc::new(TestCumulativeComponent {}))
+            .with_mass_balance_check(check)
+            .try_build()
+            .unwrap_err();
+        assert!(matches!(
+            err.problems.last(),
+            Some(BuildProblem::InvalidMassBalanceCheck { check, .. }) if check == "Carbon"
+        ));
+    }
+
+    #[test]
+    fn cyclic_graph() {
+        // The builder only links a component to components registered before it,
+        // so a cycle can only be created by modifying the graph directly
+        let sum = |input: &str, output: &str| -> Arc<dyn Component + Send + Sync> {
+            Arc::new(TestSumComponent {
+                inputs: vec![input.to_string()],
+                output: output.to_string(),
+            })
+        };
+        let link = || RequirementDefinition::new("", "", RequirementType::EmptyLink);
+        let mut graph: CGraph = Graph::new();
+        let a = graph.add_node(sum("X", "Y"));
+        let b = graph.add_node(sum("Y", "X"));
+        graph.add_edge(a, a, link());
+        assert!(!is_valid_graph(&graph));
+
+        graph.add_edge(a, b, link());
+        graph.add_edge(b, a, link());
+        assert!(is_valid_graph(&graph));
     }
 
-    /// Gets the time value at the current step
-    pub fn current_time(&self) -> Time {
-        self.time_axis.at(self.time_index).unwrap()
+    #[test]
+    #[should_panic(expected = "Inconsistent units for Level")]
+    fn inconsistent_units() {
+        ModelBuilder::new()
+            .with_component(Arc::new(TestEquilibriumComponent { equilibrium: 1.0 }))
+            .with_component(Arc::new(TestSumComponent {
+                inputs: vec!["Level".to_string()],
+                output: "V".to_string(),
+            }))
+            .build();
     }
-    pub fn current_time_bounds(&self) -> (Time, Time) {
-        self.time_axis.at_bounds(self.time_index).unwrap()
+
+    #[test]
+    fn solver_step_option() {
+        // The stiff component is stable with a smaller solver step
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0)))
+            .with_component(Arc::new(TestStiffComponent { k: 30.0 }))
+            .with_options(ModelOptions {
+                solver_step: 0.01,
+                ..Default::default()
+            })
+            .with_stability_options(StabilityOptions {
+                max_relative_change: 2.0,
+                absolute_tolerance: 0.0,
+                max_refinements: 0,
+            })
+            .build();
+
+        model.try_run().unwrap();
+        assert!(model.refinements().is_empty());
+
+        // The solver step isn't available without a context
+        let err = TestStiffComponent { k: 30.0 }
+            .solve(2020.0, 2021.0, &InputState::empty())
+            .unwrap_err();
+        assert!(err.to_string().contains("TimestepContext"));
     }
 
-    /// Solve a single component for the current timestep
-    ///
-    /// The updated state from the component is then pushed into the model's timeseries collection
-    /// to be later used by other components.
-    /// The output state defines the values at the next time index as it represents the state
-    /// at the start of the next timestep.
-    fn step_model_component(&mut self, component: C) {
-        let input_state = component.extract_state(&self.collection, self.current_time());
+    #[test]
+    fn stability_refinement() {
+        let build = |k: FloatValue, max_refinements: u32| {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0)))
+                .with_component(Arc::new(TestStiffComponent { k }))
+                .with_stability_options(StabilityOptions {
+                    max_relative_change: 2.0,
+                    absolute_tolerance: 0.0,
+                    max_refinements,
+                })
+                .build()
+        };
 
-        let (start, end) = self.current_time_bounds();
+        // Stable without refinement
+        let mut model = build(5.0, 2);
+        model.run();
+        assert!(model.refinements().is_empty());
 
-        let result = component.solve(start, end, &input_state);
+        // Requires a single refinement each step
+        let mut model = build(30.0, 2);
+        model.run();
+        assert_eq!(
+            model.refinements(),
+            [
+                StepRefinement {
+                    time_index: 0,
+                    component: "TestStiffComponent".to_string(),
+                    refinements: 1
+                },
+                StepRefinement {
+                    time_index: 1,
+                    component: "TestStiffComponent".to_string(),
+                    refinements: 1
+                }
+            ]
+        );
+        let state = model.timeseries().get_timeseries_by_name("State").unwrap();
+        assert!(state.at(2).unwrap().abs() < 1e-6);
 
-        match result {
-            Ok(output_state) => output_state.iter().for_each(|(key, value)| {
-                let ts = self.collection.get_timeseries_by_name_mut(key).unwrap();
-                // The next time index is used as this output state represents the value of a
-                // variable at the end of the current time step.
-                // This is the same as the start of the next timestep.
-                ts.set(self.time_index + 1, *value)
-            }),
-            Err(err) => {
-                println!("Solving failed: {}", err)
-            }
-        }
+        // Fails without enough refinements so the unstable values are not stored
+        let mut model = build(30.0, 0);
+        model.run();
+        let state = model.timeseries().get_timeseries_by_name("State").unwrap();
+        assert!(state.at(1).unwrap().is_nan());
     }
 
-    /// Step the model forward a step by solving each component for the current time step.
-    ///
-    /// A breadth-first search across the component graph starting at the initial node
-    /// will solve the components in a way that ensures any models with dependencies are solved
-    /// after the dependent component is first solved.
-    fn step_model(&mut self) {
-        let mut bfs = Bfs::new(&self.components, self.initial_node);
-        while let Some(nx) = bfs.next(&self.components) {
-            let c = self.components.index(nx);
-            self.step_model_component(c.clone())
-        }
-    }
+    #[test]
+    fn run_limits() {
+        let build = |limits: RunLimits| {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+                .with_component(Arc::new(TestStiffComponent { k: 1.0 }))
+                .with_run_limits(limits)
+                .build()
+        };
 
-    /// Steps the model forward one time step
-    ///
-    /// This solves the current time step and then updates the index.
-    pub fn step(&mut self) {
-        assert!(self.time_index < self.time_axis.len() - 1);
-        self.step_model();
+        let mut model = build(RunLimits {
+            max_steps: Some(2),
+            ..Default::default()
+        });
+        let res = model.try_run();
+        assert!(matches!(res, Err(RSCMError::LimitExceeded(_))));
+        assert_eq!(model.current_time(), 2022.0);
 
-        self.time_index += 1;
+        let mut model = build(RunLimits {
+            max_wall_time: Some(Duration::ZERO),
+            ..Default::default()
+        });
+        assert!(matches!(
+            model.run_within_limits(),
+            Err(RSCMError::LimitExceeded(_))
+        ));
+
+        // Generous limits have no effect
+        let mut model = build(RunLimits {
+            max_wall_time: Some(Duration::from_secs(60)),
+            max_steps: Some(4),
+        });
+        model.try_run().unwrap();
+        assert!(model.finished());
     }
 
-    /// Steps the model until the end of the time axis
-    pub fn run(&mut self) {
-        while self.time_index < self.time_axis.len() - 1 {
-            self.step();
-        }
+    #[test]
+    fn warnings() {
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_component(Arc::new(TestOptionalInputComponent {}))
+            .with_component(Arc::new(TestComponent::from_parameters(
+                TestComponentParameters { p: 0.5 },
+            )))
+            .with_exogenous_variable(
+                "Effective Radiative Forcing|Aerosols",
+                Timeseries::from_values(
+                    array![-0.5, FloatValue::NAN, -0.5],
+                    array![2020.0, 2022.0, 2025.0],
+                ),
+            )
+            .with_options(ModelOptions {
+                nan_policy: NanPolicy::Warn,
+                ..Default::default()
+            })
+            .with_run_limits(RunLimits {
+                max_steps: Some(2),
+                ..Default::default()
+            })
+            .build();
+
+        let kinds = |model: &Model| -> Vec<WarningKind> {
+            model.warnings().iter().map(|w| w.kind).collect()
+        };
+        assert_eq!(kinds(&model), vec![WarningKind::MissingExogenousData]);
+        assert_eq!(
+            model.warnings()[0].message,
+            "No exogenous data for Emissions|CO2"
+        );
+
+        model.run();
+        assert_eq!(
+            kinds(&model),
+            vec![
+                WarningKind::MissingExogenousData,
+                WarningKind::NanValues,
+                WarningKind::RunAborted
+            ]
+        );
     }
 
-    /// Create a diagram the represents the component graph
-    ///
-    /// Useful for debugging
-    pub fn as_dot(&self) -> Dot<&CGraph> {
-        Dot::with_attr_getters(
-            &self.components,
-            &[Config::NodeNoLabel, Config::EdgeNoLabel],
-            &|_, er| format!("label = {:?}", er.weight().name),
-            &|_, (_, component)| format!("label = \"{:?}\"", component),
-        )
+    #[test]
+    fn rng_streams() {
+        let build = || {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+                .with_component(Arc::new(TestNoiseComponent {
+                    name: "Noise|A".to_string(),
+                }))
+                .with_component(Arc::new(TestNoiseComponent {
+                    name: "Noise|B".to_string(),
+                }))
+                .build()
+        };
+        let values = |model: &Model, name: &str| {
+            model
+                .timeseries()
+                .get_timeseries_by_name(name)
+                .unwrap()
+                .values()
+                .to_vec()
+        };
+
+        // Each component has an independent stream
+        let mut model = build();
+        model.step();
+        let a = values(&model, "Noise|A");
+        let b = values(&model, "Noise|B");
+        assert_ne!(a[1], b[1]);
+
+        // A restored checkpoint continues with the same random numbers
+        let checkpoint = toml::to_string(&model).unwrap();
+        let streams = model.rng_streams().to_vec();
+        model.run();
+        let mut restored = toml::from_str::<Model>(&checkpoint).unwrap();
+        restored.run();
+        assert_eq!(
+            values(&restored, "Noise|A")[4],
+            values(&model, "Noise|A")[4]
+        );
+
+        // Streams can be restored independently of the model state
+        let mut replayed = build();
+        replayed.step();
+        replayed.set_rng_streams(streams.clone()).unwrap();
+        replayed.run();
+        assert_eq!(
+            values(&replayed, "Noise|B")[4],
+            values(&model, "Noise|B")[4]
+        );
+        assert!(replayed.set_rng_streams(streams[..1].to_vec()).is_err());
+
+        // Reseeding a stream branches the noise
+        let mut branched = toml::from_str::<Model>(&checkpoint).unwrap();
+        branched.reseed_rng_streams(1);
+        branched.run();
+        assert_ne!(
+            values(&branched, "Noise|A")[4],
+            values(&model, "Noise|A")[4]
+        );
+
+        // Components of the same type are ambiguous
+        assert!(branched.reseed_rng_stream("TestNoiseComponent", 2).is_err());
+        assert!(branched.reseed_rng_stream("TestComponent", 2).is_err());
     }
 
-    /// Returns true if the model has no more time steps to process
-    pub fn finished(&self) -> bool {
-        self.time_index == self.time_axis.len() - 1
+    #[test]
+    fn rng_seed() {
+        let build = |seed: Option<u64>, member: Option<u64>, extra: bool| {
+            let mut builder = ModelBuilder::new();
+            builder
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+                .with_component_if(
+                    extra,
+                    Arc::new(TestNoiseComponent {
+                        name: "Noise|Extra".to_string(),
+                    }),
+                )
+                .with_named_component(
+                    "noise",
+                    Arc::new(TestNoiseComponent {
+                        name: "Noise".to_string(),
+                    }),
+                );
+            if let Some(seed) = seed {
+                builder.with_rng_seed(seed);
+            }
+            if let Some(member) = member {
+                builder.with_ensemble_member(member);
+            }
+            let mut model = builder.build();
+            model.run();
+            // The first value is NaN as outputs are solved at the end of each step
+            model
+                .timeseries()
+                .get_timeseries_by_name("Noise")
+                .unwrap()
+                .values()
+                .to_vec()[1..]
+                .to_vec()
+        };
+
+        // Reproducible for a given seed and member
+        let seeded = build(Some(42), Some(0), false);
+        assert_eq!(seeded, build(Some(42), Some(0), false));
+        assert_ne!(seeded, build(Some(43), Some(0), false));
+        assert_ne!(seeded, build(Some(42), Some(1), false));
+
+        // Streams are derived from the component name rather than its position
+        assert_eq!(seeded, build(Some(42), Some(0), true));
+        assert_ne!(build(None, None, false), build(None, None, true));
+
+        // The first member is used if no member is given
+        assert_eq!(seeded, build(Some(42), None, false));
     }
 
-    pub fn timeseries(&self) -> &TimeseriesCollection {
-        &self.collection
+    #[test]
+    fn metadata() {
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_component(Arc::new(TestNoiseComponent {
+                name: "Noise".to_string(),
+            }))
+            .with_scenario("ssp245")
+            .with_parameters(ParameterSet::from([("k".to_string(), 2.0)]))
+            .with_version("rscm-components", "0.2.0")
+            .build();
+        assert!(model.metadata().wall_time.is_none());
+        model.run();
+
+        let metadata = model.metadata();
+        assert_eq!(metadata.scenario.as_deref(), Some("ssp245"));
+        assert_eq!(metadata.parameters.get("k"), Some(&2.0));
+        assert_eq!(
+            metadata.versions.keys().collect::<Vec<_>>(),
+            ["rscm-components", "rscm-core"]
+        );
+        assert!(metadata.started.unwrap() > 0.0);
+        assert!(metadata.wall_time.unwrap() >= 0.0);
+
+        // The metadata is serialised with the model
+        let restored = toml::from_str::<Model>(&toml::to_string(&model).unwrap()).unwrap();
+        assert_eq!(restored.metadata(), metadata);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::example_components::{TestComponent, TestComponentParameters};
-    use crate::interpolate::strategies::PreviousStrategy;
-    use is_close::is_close;
-    use numpy::array;
-    use numpy::ndarray::Array;
-    use std::iter::zip;
+    #[test]
+    fn save_and_load_state() {
+        let build = || {
+            ModelBuilder::new()
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2030.0, 1.0)))
+                .with_component(Arc::new(TestNoiseComponent {
+                    name: "Noise".to_string(),
+                }))
+                .with_component(Arc::new(TestStiffComponent { k: 2.0 }))
+                .build()
+        };
+        let mut uninterrupted = build();
+        uninterrupted.run();
 
-    fn get_emissions() -> Timeseries<FloatValue> {
-        Timeseries::new(
-            array![0.0, 10.0],
-            Arc::new(TimeAxis::from_bounds(array![1800.0, 1850.0, 2100.0])),
-            "GtC / yr".to_string(),
-            InterpolationStrategy::from(PreviousStrategy::new(true)),
-        )
+        for time_index in [0, 4, 9] {
+            let mut model = build();
+            (0..time_index).for_each(|_| model.step());
+            let state = model.save_state().unwrap();
+
+            let mut restored = Model::load_state(&state).unwrap();
+            assert_eq!(restored.current_time(), model.current_time());
+            restored.run();
+            for name in ["Noise", "State"] {
+                let expected = uninterrupted
+                    .timeseries()
+                    .get_timeseries_by_name(name)
+                    .unwrap();
+                let actual = restored.timeseries().get_timeseries_by_name(name).unwrap();
+                zip(expected.values(), actual.values())
+                    .for_each(|(a, b)| assert_eq!(a.to_bits(), b.to_bits()));
+            }
+        }
+
+        let state = build().save_state().unwrap();
+        let unsupported = state.replace(
+            &format!("format_version = {}", STATE_FORMAT_VERSION),
+            "format_version = 0",
+        );
+        assert!(Model::load_state(&unsupported).is_err());
+        assert!(Model::load_state("model = 1").is_err());
     }
 
     #[test]
-    fn step() {
-        let time_axis = TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0));
+    fn regrid() {
+        let concentrations = |value: FloatValue| {
+            Timeseries::from_values(array![value, value], array![2000.0, 2100.0])
+        };
         let mut model = ModelBuilder::new()
-            .with_time_axis(time_axis)
-            .with_component(Arc::new(TestComponent::from_parameters(
-                TestComponentParameters { p: 0.5 },
-            )))
-            .with_exogenous_variable("Emissions|CO2", get_emissions())
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2031.0, 1.0)))
+            .with_component(Arc::new(TestDecayComponent { tau: 10.0 }))
+            .with_exogenous_variable("Concentrations|CO2", concentrations(1.0))
             .build();
+        (0..5).for_each(|_| model.step());
+        let annual = model
+            .timeseries()
+            .get_timeseries_by_name("Stock")
+            .unwrap()
+            .clone();
+
+        // Spin up on an annual time axis and continue monthly from a checkpoint
+        let mut model = Model::load_state(&model.save_state().unwrap()).unwrap();
+        let monthly = TimeAxis::from_values(Array::linspace(2020.0, 2030.0, 121));
+        let mut exogenous = TimeseriesCollection::new();
+        exogenous
+            .add_timeseries(
+                "Concentrations|CO2".to_string(),
+                concentrations(2.0),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+        model.regrid(monthly, exogenous).unwrap();
+        assert_eq!(model.current_time(), 2025.0);
+
+        let stock = model.timeseries().get_timeseries_by_name("Stock").unwrap();
+        assert_eq!(stock.len(), 121);
+        assert_eq!(stock.at(24), annual.at(2));
+        assert_eq!(stock.at(60), annual.at(5));
+        let midpoint = stock.at(30).unwrap();
+        assert!(midpoint > annual.at(2).unwrap() && midpoint < annual.at(3).unwrap());
+        assert!(stock.at(61).unwrap().is_nan());
 
-        assert_eq!(model.time_index, 0);
-        model.step();
-        model.step();
-        assert_eq!(model.time_index, 2);
-        assert_eq!(model.current_time(), 2022.0);
         model.run();
-        assert_eq!(model.time_index, 4);
-        assert!(model.finished());
+        let stock = model.timeseries().get_timeseries_by_name("Stock").unwrap();
+        // The monthly steps continue from the state at the checkpoint
+        let dt: FloatValue = 1.0 / 12.0;
+        let expected = annual.at(5).unwrap() * (-dt / 10.0).exp() + 2.0 * dt;
+        assert!((stock.at(61).unwrap() - expected).abs() < 1e-10);
+        assert!(stock.values().iter().all(|v| v.is_finite()));
 
-        let concentrations = model
-            .collection
-            .get_timeseries_by_name("Concentrations|CO2")
+        // The current time must be on the new time axis
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2031.0, 1.0)))
+            .with_component(Arc::new(TestDecayComponent { tau: 10.0 }))
+            .with_exogenous_variable("Concentrations|CO2", concentrations(1.0))
+            .build();
+        model.step();
+        let offset = TimeAxis::from_values(Array::range(2020.5, 2030.0, 1.0));
+        assert!(model.regrid(offset, TimeseriesCollection::new()).is_err());
+
+        let mut unknown = TimeseriesCollection::new();
+        unknown
+            .add_timeseries(
+                "Emissions|CO2".to_string(),
+                concentrations(1.0),
+                VariableType::Exogenous,
+            )
             .unwrap();
+        let monthly = TimeAxis::from_values(Array::linspace(2020.0, 2030.0, 121));
+        assert!(model.regrid(monthly, unknown).is_err());
+    }
 
-        println!("{:?}", concentrations.values());
+    #[test]
+    fn component_groups() {
+        let build = |builder: &mut ModelBuilder| {
+            builder
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+                .with_exogenous_variable("Emissions|CO2", get_emissions())
+                .build()
+        };
+        let component = || {
+            Arc::new(TestComponent::from_parameters(TestComponentParameters {
+                p: 0.5,
+            }))
+        };
 
-        // The first value for an endogenous timeseries without a y0 value is NaN.
-        // This is because the values in the timeseries represents the state at the start
-        // of a time step.
-        // Since the values from t-1 aren't known we can't solve for y0
-        assert!(concentrations.at(0).unwrap().is_nan());
-        let mut iter = concentrations.values().into_iter();
-        iter.next(); // Skip the first value
-        assert!(iter.all(|x| !x.is_nan()));
+        let model = build(ModelBuilder::new().with_component_if(false, component()));
+        assert_eq!(model.components.node_count(), 1);
+
+        let model = build(ModelBuilder::new().with_component_if(true, component()));
+        assert_eq!(model.components.node_count(), 2);
+
+        let model = build(ModelBuilder::new().with_component_in_group("carbon-cycle", component()));
+        assert_eq!(model.components.node_count(), 2);
+
+        let model = build(
+            ModelBuilder::new()
+                .with_component_in_group("carbon-cycle", component())
+                .with_group_toggles(
+                    ComponentGroupToggles::from_toml("[groups]\ncarbon-cycle = false").unwrap(),
+                ),
+        );
+        assert_eq!(model.components.node_count(), 1);
+        assert!(model
+            .timeseries()
+            .get_by_name("Concentrations|CO2")
+            .is_none());
+
+        let model = build(
+            ModelBuilder::new()
+                .with_component_in_group("carbon-cycle", component())
+                .with_group_enabled("carbon-cycle", false)
+                .with_group_enabled("carbon-cycle", true),
+        );
+        assert_eq!(model.components.node_count(), 2);
     }
 
     #[test]
@@ -520,15 +4445,294 @@ mod tests {
 
         let exp = r#"digraph {
     0 [ label = "NullComponent"]
-    1 [ label = "TestComponent { parameters: TestComponentParameters { p: 0.5 } }"]
+    1 [ label = "TestComponent"]
     0 -> 1 [ label = ""]
 }
 "#;
 
-        let res = format!("{:?}", model.as_dot());
+        let res = model.as_dot();
         assert_eq!(res, exp);
     }
 
+    #[test]
+    fn named_components() {
+        let component = |p| {
+            Arc::new(TestComponent::from_parameters(TestComponentParameters {
+                p,
+            }))
+        };
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_named_component("fast", component(0.5))
+            .with_named_component("slow", component(0.1))
+            .with_exogenous_variable("Emissions|CO2", get_emissions())
+            .build();
+
+        assert_eq!(model.component_names(), vec!["fast", "slow"]);
+        assert!(model.as_dot().contains("label = \"slow\""));
+        assert!(model.reseed_rng_stream("slow", 1).is_ok());
+        assert!(model.reseed_rng_stream("TestComponent", 1).is_err());
+
+        // Names survive serialisation
+        let serialised = toml::to_string(&model).unwrap();
+        let model: Model = toml::from_str(&serialised).unwrap();
+        assert_eq!(model.component_names(), vec!["fast", "slow"]);
+    }
+
+    #[test]
+    fn namespaced_components() {
+        let component = |p| {
+            Arc::new(TestComponent::from_parameters(TestComponentParameters {
+                p,
+            }))
+        };
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_component_namespaced("Land", component(0.5))
+            .with_component_namespaced("Ocean", component(0.1))
+            .with_exogenous_variable("Emissions|CO2", get_emissions())
+            .build();
+        model.run();
+
+        assert_eq!(model.component_names(), vec!["Land", "Ocean"]);
+        let collection = model.timeseries();
+        assert!(collection
+            .get_timeseries_by_name("Land|Concentrations|CO2")
+            .is_some());
+        assert!(collection
+            .get_timeseries_by_name("Ocean|Concentrations|CO2")
+            .is_some());
+        assert!(collection
+            .get_timeseries_by_name("Concentrations|CO2")
+            .is_none());
+    }
+
+    #[test]
+    fn execution_order() {
+        let sum = |inputs: &[&str], output: &str| {
+            Arc::new(TestSumComponent {
+                inputs: inputs.iter().map(|name| name.to_string()).collect(),
+                output: output.to_string(),
+            })
+        };
+        // "d" is one edge from "a", but must wait for "c" which is three edges away
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_named_component("a", sum(&[], "X"))
+            .with_named_component("b", sum(&["X"], "Y"))
+            .with_named_component("c", sum(&["Y"], "W"))
+            .with_named_component("d", sum(&["X", "W"], "Z"))
+            .build();
+
+        assert_eq!(model.execution_order(), vec!["a", "b", "c", "d"]);
+
+        model.step();
+        let z = model.timeseries().get_timeseries_by_name("Z").unwrap();
+        assert_eq!(z.at(1), Some(1.0 + 3.0 + 1.0));
+    }
+
+    #[test]
+    fn parallel_components() {
+        let sum = |inputs: &[&str], output: &str| {
+            Arc::new(TestSumComponent {
+                inputs: inputs.iter().map(|name| name.to_string()).collect(),
+                output: output.to_string(),
+            })
+        };
+        // "b" and "c" only depend on "a" so can be solved together
+        let build = |parallel: bool| {
+            let mut builder = ModelBuilder::new();
+            builder
+                .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+                .with_named_component("a", sum(&[], "X"))
+                .with_named_component("b", sum(&["X"], "Y"))
+                .with_named_component("c", sum(&["X"], "W"))
+                .with_named_component("d", sum(&["Y", "W"], "Z"))
+                .with_component(Arc::new(TestNoiseComponent {
+                    name: "Noise|A".to_string(),
+                }))
+                .with_component(Arc::new(TestNoiseComponent {
+                    name: "Noise|B".to_string(),
+                }));
+            if parallel {
+                builder.with_parallel_components(4);
+            }
+            builder.build()
+        };
+
+        let model = build(true);
+        let levels: Vec<Vec<&str>> = model
+            .execution_levels
+            .iter()
+            .map(|level| {
+                level
+                    .iter()
+                    .filter(|nx| **nx != model.initial_node)
+                    .map(|nx| model.component_name(*nx))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(levels.len(), 3);
+        assert!(levels[0].contains(&"a"));
+        assert!(levels[1].contains(&"b") && levels[1].contains(&"c"));
+        assert!(levels[2].contains(&"d"));
+
+        // Solving components concurrently gives identical results
+        let mut sequential = build(false);
+        sequential.run();
+        let mut parallel = build(true);
+        parallel.run();
+        ["X", "Y", "W", "Z", "Noise|A", "Noise|B"]
+            .iter()
+            .for_each(|name| {
+                let values = |model: &Model| {
+                    model
+                        .timeseries()
+                        .get_timeseries_by_name(name)
+                        .unwrap()
+                        .values()
+                        .to_vec()
+                };
+                assert_eq!(values(&parallel)[1..], values(&sequential)[1..], "{}", name);
+            });
+
+        // A single pool is created for the model
+        assert!(sequential.thread_pool.is_none());
+        let pool = parallel.thread_pool.as_ref().unwrap();
+        assert_eq!(pool.current_num_threads(), 4);
+    }
+
+    #[test]
+    fn output_sink() {
+        #[derive(Debug, Clone, Default)]
+        struct Recorded {
+            rows: Vec<(Time, String, FloatValue)>,
+            finished: bool,
+        }
+
+        /// Records the values written and whether the sink was finished
+        #[derive(Debug, Clone, Default)]
+        struct RecordingSink(Arc<Mutex<Recorded>>);
+
+        impl OutputSink for RecordingSink {
+            fn write_step(&mut self, time: Time, values: &[(&str, FloatValue)]) -> RSCMResult<()> {
+                let mut state = self.0.lock().unwrap();
+                values
+                    .iter()
+                    .for_each(|(name, value)| state.rows.push((time, name.to_string(), *value)));
+                Ok(())
+            }
+
+            fn finish(&mut self) -> RSCMResult<()> {
+                self.0.lock().unwrap().finished = true;
+                Ok(())
+            }
+        }
+
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0)))
+            .with_component(Arc::new(TestComponent::from_parameters(
+                TestComponentParameters { p: 0.5 },
+            )))
+            .with_exogenous_variable("Emissions|CO2", get_emissions())
+            .build();
+        let sink = RecordingSink::default();
+        model.add_output_sink(Box::new(sink.clone())).unwrap();
+
+        model.step();
+        assert!(!sink.0.lock().unwrap().finished);
+        model.run();
+
+        let Recorded { rows, finished } = sink.0.lock().unwrap().clone();
+        assert!(finished);
+        // Only the endogenous concentrations are written, including the initial NaN
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].2.is_nan());
+        assert_eq!(rows[1].0, 2021.0);
+        assert_eq!(rows[1].1, "Concentrations|CO2");
+        assert_eq!(
+            Some(rows[2].2),
+            model
+                .timeseries()
+                .get_timeseries_by_name("Concentrations|CO2")
+                .unwrap()
+                .at(2)
+        );
+    }
+
+    #[test]
+    fn output_variables() {
+        let sum = |inputs: &[&str], output: &str| {
+            Arc::new(TestSumComponent {
+                inputs: inputs.iter().map(|name| name.to_string()).collect(),
+                output: output.to_string(),
+            })
+        };
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_named_component("a", sum(&[], "Forcing|CO2"))
+            .with_named_component("b", sum(&[], "Forcing|CH4"))
+            .with_named_component("c", sum(&["Forcing|CO2"], "Total"))
+            .with_named_component("d", sum(&[], "Other"))
+            .with_output_variables(&["Total"])
+            .build();
+        model.run();
+
+        let collection = model.timeseries();
+        // Required by "c" so is still stored
+        assert!(collection.get_timeseries_by_name("Forcing|CO2").is_some());
+        assert!(collection.get_timeseries_by_name("Forcing|CH4").is_none());
+        assert!(collection.get_timeseries_by_name("Other").is_none());
+        let total = collection.get_timeseries_by_name("Total").unwrap();
+        assert_eq!(total.at(4), Some(2.0));
+
+        let model = ModelBuilder::new()
+            .with_named_component("a", sum(&[], "Forcing|CO2"))
+            .with_named_component("b", sum(&[], "Forcing|CH4"))
+            .with_named_component("d", sum(&[], "Other"))
+            .with_output_variables(&["Forcing|*"])
+            .build();
+        assert!(model
+            .timeseries()
+            .get_timeseries_by_name("Forcing|CH4")
+            .is_some());
+        assert!(model.timeseries().get_timeseries_by_name("Other").is_none());
+    }
+
+    #[test]
+    fn estimate_cost() {
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_component(Arc::new(TestComponent::from_parameters(
+                TestComponentParameters { p: 0.5 },
+            )))
+            .with_exogenous_variable("Emissions|CO2", get_emissions())
+            .build();
+
+        let estimate = model.estimate_cost();
+        assert_eq!(estimate.steps, 4);
+        assert_eq!(estimate.component_solves, 4);
+        // Emissions and concentrations
+        assert_eq!(estimate.bytes, 2 * 5 * 8);
+
+        model.step();
+        assert_eq!(model.estimate_cost().steps, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Multiple components named 'ocean'")]
+    fn duplicate_component_names() {
+        let component = || {
+            Arc::new(TestComponent::from_parameters(TestComponentParameters {
+                p: 0.5,
+            }))
+        };
+        ModelBuilder::new()
+            .with_named_component("ocean", component())
+            .with_named_component("ocean", component())
+            .build();
+    }
+
     #[test]
     fn serialise_and_deserialise_model() {
         let mut model = ModelBuilder::new()
@@ -538,6 +4742,8 @@ mod tests {
             )))
             .with_exogenous_variable("Emissions|CO2", get_emissions())
             .build();
+        // The metadata depends on the version of rscm so is checked separately
+        model.metadata = RunMetadata::default();
 
         model.step();
 
@@ -546,6 +4752,7 @@ mod tests {
         let serialised = toml::to_string(&model).unwrap();
 
         let expected = r#"initial_node = 0
+component_names = ["NullComponent", "TestComponent"]
 time_index = 1
 
 [components]
@@ -604,6 +4811,16 @@ data = [2020.0, 2021.0, 2022.0, 2023.0, 2024.0, 2025.0]
 v = 1
 dim = [6]
 data = [2020.0, 2021.0, 2022.0, 2023.0, 2024.0, 2025.0]
+
+[[rng_streams]]
+seed = "ecf273f981b5cd4587f0467306ad6cadd0d0a3e33317e767f29bea72d78a7dfe"
+stream = 0
+word_pos = "0"
+
+[[rng_streams]]
+seed = "ecf273f981b5cd4587f0467306ad6cadd0d0a3e33317e767f29bea72d78a7dfe"
+stream = 1
+word_pos = "0"
 "#;
 
         assert_eq!(serialised, expected);
@@ -0,0 +1,395 @@
+//! Fitting parameters to observations by minimising a cost
+//!
+//! A [`CostFunction`] measures the misfit between the output of a run and observations.
+//! It is implemented for [`Target`], which compares a variable against an observed timeseries,
+//! for slices of targets, which sum their weighted costs, and for closures.
+//! [`GaussianLikelihood`] is the negative log-likelihood of a target,
+//! for use with [`crate::mcmc::sample_posterior`].
+//!
+//! [`calibrate`] finds the parameter set which minimises a cost function using the
+//! Nelder-Mead simplex method (Nelder and Mead, 1965).
+//! The method doesn't need gradients so it is robust to costs which aren't smooth,
+//! for example due to thresholds in the model, but needs more runs than
+//! [`crate::gradient::minimise`] when the cost is smooth.
+//! [`nelder_mead`] minimises an arbitrary function of a parameter set.
+//!
+//! Parameter sets for which the model fails to run are assigned an infinite cost.
+use crate::calibration::{CalibrationParameter, ParameterSet, Target};
+use crate::errors::{RSCMError, RSCMResult};
+use crate::gradient::OptimisationResult;
+use crate::metrics::skill;
+use crate::model::ModelBuilder;
+use crate::timeseries::FloatValue;
+use crate::timeseries_collection::TimeseriesCollection;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Misfit between the output of a run and observations
+///
+/// Lower values are better.
+pub trait CostFunction {
+    fn cost(&self, output: &TimeseriesCollection) -> RSCMResult<FloatValue>;
+}
+
+impl CostFunction for Target {
+    /// Root-mean-square error scaled by the weight of the target
+    fn cost(&self, output: &TimeseriesCollection) -> RSCMResult<FloatValue> {
+        Ok(self.weight * self.objective(output)?)
+    }
+}
+
+impl CostFunction for [Target] {
+    /// Sum of the weighted costs of each target
+    fn cost(&self, output: &TimeseriesCollection) -> RSCMResult<FloatValue> {
+        self.iter().map(|target| target.cost(output)).sum()
+    }
+}
+
+impl<F> CostFunction for F
+where
+    F: Fn(&TimeseriesCollection) -> RSCMResult<FloatValue>,
+{
+    fn cost(&self, output: &TimeseriesCollection) -> RSCMResult<FloatValue> {
+        self(output)
+    }
+}
+
+/// Negative log-likelihood of a target, assuming independent Gaussian errors
+///
+/// The constant normalisation term is omitted, so the cost is
+/// `0.5 * n * (rmse / sigma)^2` where `n` is the number of compared time points.
+/// The weight of the target is ignored.
+#[derive(Debug, Clone)]
+pub struct GaussianLikelihood {
+    pub target: Target,
+    /// Standard deviation of the errors
+    pub sigma: FloatValue,
+}
+
+impl CostFunction for GaussianLikelihood {
+    fn cost(&self, output: &TimeseriesCollection) -> RSCMResult<FloatValue> {
+        let target = &self.target;
+        let timeseries = output
+            .get_timeseries_by_name(&target.variable)
+            .ok_or_else(|| RSCMError::TimeseriesNotFound(format!("'{}'", target.variable)))?;
+        let skill = skill(timeseries, &target.reference, target.start, target.end)?;
+        Ok(0.5 * skill.n as FloatValue * (skill.rmse / self.sigma).powi(2))
+    }
+}
+
+/// Options for the Nelder-Mead simplex method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NelderMeadOptions {
+    pub max_iterations: usize,
+    /// Size of the initial simplex relative to the range of each parameter
+    pub initial_step: FloatValue,
+    /// Stop once the costs at the vertices of the simplex differ by less than this value
+    /// and the simplex is smaller than `parameter_tolerance`
+    pub tolerance: FloatValue,
+    /// Largest distance between the vertices of the simplex, relative to the range of each
+    /// parameter, at which the search can stop
+    pub parameter_tolerance: FloatValue,
+}
+
+impl Default for NelderMeadOptions {
+    fn default() -> Self {
+        Self {
+            max_iterations: 500,
+            initial_step: 0.1,
+            tolerance: 1e-8,
+            parameter_tolerance: 1e-6,
+        }
+    }
+}
+
+/// A vertex of the simplex in the normalised parameter space
+struct Vertex {
+    x: Vec<FloatValue>,
+    value: FloatValue,
+}
+
+/// Minimise a function of a parameter set using the Nelder-Mead simplex method
+///
+/// Each parameter is constrained to lie within its `[min, max]` range
+/// by assigning an infinite value to trial points outside of the bounds.
+/// Parameters which aren't in `bounds` are held fixed at their `initial` values.
+/// NaN values of `f` are treated as infinite.
+pub fn nelder_mead<F>(
+    initial: &ParameterSet,
+    bounds: &[CalibrationParameter],
+    mut f: F,
+    options: &NelderMeadOptions,
+) -> RSCMResult<OptimisationResult>
+where
+    F: FnMut(&ParameterSet) -> RSCMResult<FloatValue>,
+{
+    if bounds.is_empty() {
+        return Err(RSCMError::Error(
+            "At least one parameter is required".to_string(),
+        ));
+    }
+    if let Some(p) = bounds
+        .iter()
+        .find(|p| p.min.partial_cmp(&p.max) != Some(Ordering::Less))
+    {
+        return Err(RSCMError::Error(format!(
+            "Invalid range [{}, {}] for parameter '{}'",
+            p.min, p.max, p.name
+        )));
+    }
+
+    // The search is performed in the unit hypercube so that each parameter has a similar scale
+    let to_parameters = |x: &[FloatValue]| {
+        let mut parameters = initial.clone();
+        bounds.iter().zip(x).for_each(|(p, x)| {
+            parameters.insert(p.name.clone(), p.min + x.clamp(0.0, 1.0) * (p.max - p.min));
+        });
+        parameters
+    };
+    let mut evaluate = |x: Vec<FloatValue>| -> RSCMResult<Vertex> {
+        // Points outside of the bounds are rejected rather than projected onto the bounds,
+        // which would collapse the simplex
+        let value = match x.iter().all(|x| (0.0..=1.0).contains(x)) {
+            true => f(&to_parameters(&x))?,
+            false => FloatValue::INFINITY,
+        };
+        Ok(Vertex {
+            x,
+            value: if value.is_nan() {
+                FloatValue::INFINITY
+            } else {
+                value
+            },
+        })
+    };
+
+    let start = bounds
+        .iter()
+        .map(|p| {
+            initial
+                .get(&p.name)
+                .map(|value| ((value - p.min) / (p.max - p.min)).clamp(0.0, 1.0))
+                .ok_or_else(|| RSCMError::Error(format!("Unknown parameter '{}'", p.name)))
+        })
+        .collect::<RSCMResult<Vec<_>>>()?;
+
+    let mut simplex = vec![evaluate(start.clone())?];
+    for i in 0..bounds.len() {
+        let mut x = start.clone();
+        // Step away from the upper bound so the simplex isn't degenerate
+        x[i] += match x[i] + options.initial_step <= 1.0 {
+            true => options.initial_step,
+            false => -options.initial_step,
+        };
+        simplex.push(evaluate(x)?);
+    }
+
+    let along = |from: &[FloatValue], to: &[FloatValue], t: FloatValue| -> Vec<FloatValue> {
+        from.iter().zip(to).map(|(a, b)| a + t * (b - a)).collect()
+    };
+
+    let n = bounds.len();
+    for iteration in 0..options.max_iterations {
+        simplex.sort_by(|a, b| a.value.partial_cmp(&b.value).unwrap_or(Ordering::Equal));
+
+        let spread = simplex[n].value - simplex[0].value;
+        let size = simplex[1..]
+            .iter()
+            .flat_map(|v| v.x.iter().zip(&simplex[0].x).map(|(a, b)| (a - b).abs()))
+            .fold(0.0, FloatValue::max);
+        if (spread <= options.tolerance || simplex[0].value == simplex[n].value)
+            && size <= options.parameter_tolerance
+        {
+            return Ok(OptimisationResult {
+                parameters: to_parameters(&simplex[0].x),
+                value: simplex[0].value,
+                iterations: iteration,
+                converged: true,
+            });
+        }
+
+        let centroid: Vec<FloatValue> = (0..n)
+            .map(|i| simplex[..n].iter().map(|v| v.x[i]).sum::<FloatValue>() / n as FloatValue)
+            .collect();
+        let worst = &simplex[n];
+
+        let reflected = evaluate(along(&centroid, &worst.x, -1.0))?;
+        if reflected.value < simplex[0].value {
+            let expanded = evaluate(along(&centroid, &worst.x, -2.0))?;
+            simplex[n] = match expanded.value < reflected.value {
+                true => expanded,
+                false => reflected,
+            };
+            continue;
+        }
+        if reflected.value < simplex[n - 1].value {
+            simplex[n] = reflected;
+            continue;
+        }
+
+        let contracted = match reflected.value < worst.value {
+            true => evaluate(along(&centroid, &reflected.x, 0.5))?,
+            false => evaluate(along(&centroid, &worst.x, 0.5))?,
+        };
+        if contracted.value < reflected.value.min(worst.value) {
+            simplex[n] = contracted;
+            continue;
+        }
+
+        // Shrink towards the best vertex
+        let best = simplex[0].x.clone();
+        for vertex in simplex.iter_mut().skip(1) {
+            *vertex = evaluate(along(&best, &vertex.x, 0.5))?;
+        }
+    }
+
+    simplex.sort_by(|a, b| a.value.partial_cmp(&b.value).unwrap_or(Ordering::Equal));
+    Ok(OptimisationResult {
+        parameters: to_parameters(&simplex[0].x),
+        value: simplex[0].value,
+        iterations: options.max_iterations,
+        converged: false,
+    })
+}
+
+/// Cost of running a model
+///
+/// Returns an infinite cost if the run fails.
+pub fn run_cost<C>(builder: &ModelBuilder, cost: &C) -> RSCMResult<FloatValue>
+where
+    C: CostFunction + ?Sized,
+{
+    let mut model = builder.build();
+    match model.try_run() {
+        Ok(()) => cost.cost(model.timeseries()),
+        Err(_) => Ok(FloatValue::INFINITY),
+    }
+}
+
+/// Find the parameters which minimise a cost function using the Nelder-Mead simplex method
+///
+/// `build` creates a model builder for a parameter set.
+/// See [`nelder_mead`] for how the bounds are applied.
+pub fn calibrate<F, C>(
+    initial: &ParameterSet,
+    bounds: &[CalibrationParameter],
+    build: F,
+    cost: &C,
+    options: &NelderMeadOptions,
+) -> RSCMResult<OptimisationResult>
+where
+    F: Fn(&ParameterSet) -> ModelBuilder,
+    C: CostFunction + ?Sized,
+{
+    nelder_mead(
+        initial,
+        bounds,
+        |parameters| run_cost(&build(parameters), cost),
+        options,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_components::build_test_model as build;
+    use is_close::is_close;
+
+    fn bound(name: &str, min: FloatValue, max: FloatValue) -> CalibrationParameter {
+        CalibrationParameter {
+            name: name.to_string(),
+            min,
+            max,
+        }
+    }
+
+    #[test]
+    fn rosenbrock() {
+        let initial = ParameterSet::from([
+            ("x".to_string(), -1.0),
+            ("y".to_string(), 1.0),
+            ("fixed".to_string(), 3.0),
+        ]);
+        let result = nelder_mead(
+            &initial,
+            &[bound("x", -2.0, 2.0), bound("y", -1.0, 3.0)],
+            |p| Ok((1.0 - p["x"]).powi(2) + 100.0 * (p["y"] - p["x"].powi(2)).powi(2)),
+            &NelderMeadOptions {
+                max_iterations: 2000,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(result.converged);
+        assert!(is_close!(result.parameters["x"], 1.0, abs_tol = 1e-4));
+        assert!(is_close!(result.parameters["y"], 1.0, abs_tol = 1e-4));
+        assert_eq!(result.parameters["fixed"], 3.0);
+    }
+
+    #[test]
+    fn bounded() {
+        // The unconstrained minimum is outside of the bounds
+        let result = nelder_mead(
+            &ParameterSet::from([("x".to_string(), 0.5)]),
+            &[bound("x", 0.0, 1.0)],
+            |p| Ok((p["x"] - 2.0).powi(2)),
+            &NelderMeadOptions::default(),
+        )
+        .unwrap();
+        assert!(is_close!(result.parameters["x"], 1.0));
+
+        let invalid = nelder_mead(
+            &ParameterSet::new(),
+            &[bound("x", 0.0, 1.0)],
+            |_| Ok(0.0),
+            &NelderMeadOptions::default(),
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn calibrate_target() {
+        // Observations generated with p = 0.5
+        let model = build(&ParameterSet::from([("p".to_string(), 0.5)]));
+        let mut model = model.build();
+        model.run();
+        let observations = model
+            .timeseries()
+            .get_timeseries_by_name("Concentrations|CO2")
+            .unwrap()
+            .clone();
+        let targets = vec![Target::new(
+            "Concentrations|CO2",
+            observations,
+            2021.0,
+            2024.0,
+        )];
+
+        let result = calibrate(
+            &ParameterSet::from([("p".to_string(), 2.0)]),
+            &[bound("p", 0.0, 5.0)],
+            build,
+            targets.as_slice(),
+            &NelderMeadOptions::default(),
+        )
+        .unwrap();
+        assert!(result.converged);
+        assert!(is_close!(result.parameters["p"], 0.5, abs_tol = 1e-4));
+
+        // Closures can be used as cost functions
+        let cost = |output: &TimeseriesCollection| -> RSCMResult<FloatValue> {
+            let ts = output.get_timeseries_by_name("Concentrations|CO2").unwrap();
+            Ok((ts.at(2).unwrap() - 0.65).powi(2))
+        };
+        let result = calibrate(
+            &ParameterSet::from([("p".to_string(), 2.0)]),
+            &[bound("p", 0.0, 5.0)],
+            build,
+            &cost,
+            &NelderMeadOptions::default(),
+        )
+        .unwrap();
+        assert!(is_close!(result.parameters["p"], 0.5, abs_tol = 1e-4));
+    }
+}
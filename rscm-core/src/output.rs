@@ -0,0 +1,281 @@
+//! Streaming of results during a run
+//!
+//! By default the results of a run are only available from the model's
+//! [`TimeseriesCollection`](crate::timeseries_collection::TimeseriesCollection) once it has run.
+//! An [`OutputSink`] added using [`crate::model::Model::add_output_sink`] receives the values
+//! of the endogenous variables as each time step is completed,
+//! so the results of each member of a large ensemble can be written to disk
+//! as it runs and the model dropped, rather than holding every member in memory.
+//!
+//! [`CsvSink`] writes the values in a long format with a row per variable and time.
+//! [`ParquetSink`] writes the same columns as a Parquet file and requires the `parquet` feature.
+use crate::errors::{RSCMError, RSCMResult};
+use crate::timeseries::{FloatValue, Time};
+use std::fmt::Debug;
+use std::io::Write;
+
+/// Receives the values of a model's variables as each time step is completed
+pub trait OutputSink: Debug + Send {
+    /// Write the values of the variables at `time`
+    ///
+    /// `values` contains the name and value of each endogenous variable.
+    fn write_step(&mut self, time: Time, values: &[(&str, FloatValue)]) -> RSCMResult<()>;
+
+    /// Called once the model has reached the end of its time axis
+    fn finish(&mut self) -> RSCMResult<()> {
+        Ok(())
+    }
+}
+
+/// Writes values as CSV with `time,variable,value` columns
+#[derive(Debug)]
+pub struct CsvSink<W: Write + Debug + Send> {
+    writer: W,
+    header_written: bool,
+}
+
+impl<W: Write + Debug + Send> CsvSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            header_written: false,
+        }
+    }
+
+    /// The underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_rows(&mut self, time: Time, values: &[(&str, FloatValue)]) -> std::io::Result<()> {
+        if !self.header_written {
+            writeln!(self.writer, "time,variable,value")?;
+            self.header_written = true;
+        }
+        values.iter().try_for_each(|(name, value)| {
+            // Quote names which contain commas or quotes
+            match name.contains(',') || name.contains('"') {
+                true => writeln!(
+                    self.writer,
+                    "{},\"{}\",{}",
+                    time,
+                    name.replace('"', "\"\""),
+                    value
+                ),
+                false => writeln!(self.writer, "{},{},{}", time, name, value),
+            }
+        })
+    }
+}
+
+impl<W: Write + Debug + Send> OutputSink for CsvSink<W> {
+    fn write_step(&mut self, time: Time, values: &[(&str, FloatValue)]) -> RSCMResult<()> {
+        self.write_rows(time, values)
+            .map_err(|e| RSCMError::Error(format!("Could not write output: {}", e)))
+    }
+
+    fn finish(&mut self) -> RSCMResult<()> {
+        self.writer
+            .flush()
+            .map_err(|e| RSCMError::Error(format!("Could not write output: {}", e)))
+    }
+}
+
+/// Writes values as a Parquet file with `time`, `variable` and `value` columns
+///
+/// Rows are buffered and written as a row group once `batch_size` rows have been collected.
+/// The file isn't valid until [`OutputSink::finish`] or [`ParquetSink::into_inner`] has been called.
+#[cfg(feature = "parquet")]
+#[derive(Debug)]
+pub struct ParquetSink<W: Write + Debug + Send> {
+    writer: Option<parquet::arrow::ArrowWriter<W>>,
+    inner: Option<W>,
+    batch_size: usize,
+    times: Vec<Time>,
+    variables: Vec<String>,
+    values: Vec<FloatValue>,
+}
+
+#[cfg(feature = "parquet")]
+impl<W: Write + Debug + Send> ParquetSink<W> {
+    pub fn new(writer: W) -> RSCMResult<Self> {
+        Self::with_batch_size(writer, 8192)
+    }
+
+    pub fn with_batch_size(writer: W, batch_size: usize) -> RSCMResult<Self> {
+        let writer = parquet::arrow::ArrowWriter::try_new(writer, Self::schema(), None)
+            .map_err(|e| RSCMError::Error(format!("Could not write output: {}", e)))?;
+        Ok(Self {
+            writer: Some(writer),
+            inner: None,
+            batch_size: batch_size.max(1),
+            times: Vec::new(),
+            variables: Vec::new(),
+            values: Vec::new(),
+        })
+    }
+
+    /// Finish the file and return the underlying writer
+    pub fn into_inner(mut self) -> RSCMResult<W> {
+        self.finish()?;
+        Ok(self.inner.take().expect("finished sinks have a writer"))
+    }
+
+    fn schema() -> std::sync::Arc<arrow_schema::Schema> {
+        use arrow_schema::{DataType, Field, Schema};
+
+        std::sync::Arc::new(Schema::new(vec![
+            Field::new("time", DataType::Float64, false),
+            Field::new("variable", DataType::Utf8, false),
+            Field::new("value", DataType::Float64, false),
+        ]))
+    }
+
+    fn write_batch(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray};
+        use std::sync::Arc;
+
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or("the sink has already been finished")?;
+        if self.times.is_empty() {
+            return Ok(());
+        }
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Float64Array::from(std::mem::take(&mut self.times))),
+            Arc::new(StringArray::from(std::mem::take(&mut self.variables))),
+            Arc::new(Float64Array::from(std::mem::take(&mut self.values))),
+        ];
+        writer.write(&RecordBatch::try_new(Self::schema(), columns)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl<W: Write + Debug + Send> OutputSink for ParquetSink<W> {
+    fn write_step(&mut self, time: Time, values: &[(&str, FloatValue)]) -> RSCMResult<()> {
+        if self.writer.is_none() {
+            return Err(RSCMError::Error(
+                "Could not write output: the sink has already been finished".to_string(),
+            ));
+        }
+        values.iter().for_each(|(name, value)| {
+            self.times.push(time);
+            self.variables.push(name.to_string());
+            self.values.push(*value);
+        });
+        if self.times.len() >= self.batch_size {
+            self.write_batch()
+                .map_err(|e| RSCMError::Error(format!("Could not write output: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> RSCMResult<()> {
+        if self.writer.is_none() {
+            return Ok(());
+        }
+        self.write_batch()
+            .and_then(|_| {
+                let mut inner = self.writer.take().unwrap().into_inner()?;
+                inner.flush()?;
+                self.inner = Some(inner);
+                Ok(())
+            })
+            .map_err(|e| RSCMError::Error(format!("Could not write output: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv() {
+        let mut sink = CsvSink::new(Vec::new());
+        sink.write_step(2020.0, &[("Surface Temperature", 1.5)])
+            .unwrap();
+        sink.write_step(2021.0, &[("Emissions|CO2, fossil", 10.0)])
+            .unwrap();
+        sink.finish().unwrap();
+
+        let csv = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!(
+            csv,
+            "time,variable,value\n\
+             2020,Surface Temperature,1.5\n\
+             2021,\"Emissions|CO2, fossil\",10\n"
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet() {
+        use arrow_array::{Float64Array, StringArray};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReader;
+
+        let path = std::env::temp_dir().join(format!("rscm-output-{}.parquet", std::process::id()));
+        let file = std::fs::File::create(&path).unwrap();
+        let mut sink = ParquetSink::with_batch_size(file, 3).unwrap();
+        for time in [2020.0, 2021.0] {
+            sink.write_step(
+                time,
+                &[
+                    ("Surface Temperature", time - 2019.0),
+                    ("Emissions|CO2", 10.0),
+                ],
+            )
+            .unwrap();
+        }
+        sink.finish().unwrap();
+        assert!(sink.write_step(2022.0, &[("Emissions|CO2", 10.0)]).is_err());
+        sink.into_inner().unwrap();
+
+        let reader =
+            ParquetRecordBatchReader::try_new(std::fs::File::open(&path).unwrap(), 1024).unwrap();
+        let (mut times, mut variables, mut values) =
+            (Vec::<f64>::new(), Vec::new(), Vec::<f64>::new());
+        for batch in reader {
+            let batch = batch.unwrap();
+            let column = |name: &str| batch.column_by_name(name).unwrap().clone();
+            let time = column("time");
+            let variable = column("variable");
+            let value = column("value");
+            times.extend(
+                time.as_any()
+                    .downcast_ref::<Float64Array>()
+                    .unwrap()
+                    .values(),
+            );
+            variables.extend(
+                variable
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .unwrap()
+                    .iter()
+                    .map(|v| v.unwrap().to_string()),
+            );
+            values.extend(
+                value
+                    .as_any()
+                    .downcast_ref::<Float64Array>()
+                    .unwrap()
+                    .values(),
+            );
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(times, vec![2020.0, 2020.0, 2021.0, 2021.0]);
+        assert_eq!(
+            variables,
+            vec![
+                "Surface Temperature",
+                "Emissions|CO2",
+                "Surface Temperature",
+                "Emissions|CO2"
+            ]
+        );
+        assert_eq!(values, vec![1.0, 10.0, 2.0, 10.0]);
+    }
+}
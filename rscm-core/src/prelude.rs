@@ -0,0 +1,24 @@
+//! Commonly used types for building components and models
+//!
+//! The items re-exported here are the supported public API of `rscm-core`.
+//! Downstream crates implementing components should import from the prelude
+//! rather than the individual modules, which may be reorganised between releases:
+//!
+//! ```rust
+//! use rscm_core::prelude::*;
+//! ```
+//!
+//! The names re-exported by the prelude are checked against the list in
+//! `prelude-exports.txt` by the `prelude` test.
+//! The signatures of the items aren't checked.
+pub use crate::component::{
+    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+    TimestepContext,
+};
+pub use crate::errors::{RSCMError, RSCMResult};
+pub use crate::interpolate::strategies::{
+    InterpolationStrategy, LinearSplineStrategy, NextStrategy, PreviousStrategy,
+};
+pub use crate::model::{Model, ModelBuilder};
+pub use crate::timeseries::{FloatValue, Time, TimeAxis, Timeseries};
+pub use crate::timeseries_collection::{TimeseriesCollection, VariableType};
@@ -0,0 +1,159 @@
+//! Recover information from previous model runs
+//!
+//! A serialised model (see `Model.to_toml`) records the parameters of every component.
+//! These can be used to construct identical components,
+//! for example, to continue with exactly the same configuration as a previous run.
+use crate::errors::{RSCMError, RSCMResult};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+/// Extract the parameters of a component from a serialised model
+///
+/// `component_name` is the name assigned to the component when it was registered
+/// with the model, otherwise the name of the component's type, e.g. "CO2ERF".
+/// An error is returned if the model contains no components with this name,
+/// or if it contains more than one as they would be ambiguous.
+pub fn component_parameters_from_toml(
+    serialised_model: &str,
+    component_name: &str,
+) -> RSCMResult<toml::Value> {
+    let model: toml::Value = toml::from_str(serialised_model)
+        .map_err(|e| RSCMError::Error(format!("Invalid serialised model: {}", e)))?;
+
+    let nodes = model
+        .get("components")
+        .and_then(|c| c.get("nodes"))
+        .and_then(|n| n.as_array())
+        .ok_or_else(|| RSCMError::Error("Serialised model has no components".to_string()))?;
+
+    // Models serialised before components were named are identified by their type
+    let names = model.get("component_names").and_then(|n| n.as_array());
+    let mut matches = nodes.iter().enumerate().filter_map(|(i, node)| {
+        let name = match names {
+            Some(names) => names.get(i),
+            None => node.get("type"),
+        };
+        (name.and_then(|n| n.as_str()) == Some(component_name)).then_some(node)
+    });
+
+    let node = matches.next().ok_or_else(|| {
+        RSCMError::Error(format!("No component named '{}' in run", component_name))
+    })?;
+    if matches.next().is_some() {
+        return Err(RSCMError::Error(format!(
+            "Multiple components named '{}' in run",
+            component_name
+        )));
+    }
+
+    // Remapped components wrap the component which has the parameters
+    let mut node = node;
+    while let Some(component) = node.get("component") {
+        node = component;
+    }
+
+    node.get("parameters").cloned().ok_or_else(|| {
+        RSCMError::Error(format!(
+            "Component '{}' has no parameters in run",
+            component_name
+        ))
+    })
+}
+
+/// Read the parameters of a component from a serialised model on disk
+pub fn component_parameters_from_run<P: DeserializeOwned>(
+    path: impl AsRef<Path>,
+    component_name: &str,
+) -> RSCMResult<P> {
+    let path = path.as_ref();
+    let serialised_model = std::fs::read_to_string(path)
+        .map_err(|e| RSCMError::Error(format!("Could not read run {}: {}", path.display(), e)))?;
+
+    component_parameters_from_toml(&serialised_model, component_name)?
+        .try_into()
+        .map_err(|e| {
+            RSCMError::Error(format!(
+                "Invalid parameters for component '{}': {}",
+                component_name, e
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_components::{TestComponent, TestComponentParameters};
+    use crate::model::ModelBuilder;
+    use crate::timeseries::{TimeAxis, Timeseries};
+    use numpy::array;
+    use numpy::ndarray::Array;
+    use std::sync::Arc;
+
+    fn serialised_model() -> String {
+        let model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_component(Arc::new(TestComponent::from_parameters(
+                TestComponentParameters { p: 0.5 },
+            )))
+            .with_exogenous_variable(
+                "Emissions|CO2",
+                Timeseries::from_values(array![0.0, 10.0], array![2020.0, 2025.0]),
+            )
+            .build();
+
+        toml::to_string(&model).unwrap()
+    }
+
+    #[test]
+    fn parameters_from_run() {
+        let path = std::env::temp_dir().join("rscm-provenance-test.toml");
+        std::fs::write(&path, serialised_model()).unwrap();
+
+        let parameters: TestComponentParameters =
+            component_parameters_from_run(&path, "TestComponent").unwrap();
+        assert_eq!(parameters.p, 0.5);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn named_components() {
+        let model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_named_component(
+                "fast",
+                Arc::new(TestComponent::from_parameters(TestComponentParameters {
+                    p: 0.5,
+                })),
+            )
+            .with_named_component(
+                "slow",
+                Arc::new(TestComponent::from_parameters(TestComponentParameters {
+                    p: 0.1,
+                })),
+            )
+            .with_exogenous_variable(
+                "Emissions|CO2",
+                Timeseries::from_values(array![0.0, 10.0], array![2020.0, 2025.0]),
+            )
+            .build();
+        let serialised = toml::to_string(&model).unwrap();
+
+        let parameters: TestComponentParameters =
+            component_parameters_from_toml(&serialised, "slow")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert_eq!(parameters.p, 0.1);
+        assert!(component_parameters_from_toml(&serialised, "TestComponent").is_err());
+    }
+
+    #[test]
+    fn missing_component() {
+        let res = component_parameters_from_toml(&serialised_model(), "CO2ERF");
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "No component named 'CO2ERF' in run"
+        );
+    }
+}
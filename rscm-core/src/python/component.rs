@@ -1,7 +1,7 @@
 /// Macros for exposing a component to Python and using python-defined modules in rust
 use crate::component::{Component, InputState, OutputState};
 use crate::errors::RSCMResult;
-use crate::timeseries::{FloatValue, Time};
+use crate::timeseries::{DisaggregationStrategy, FloatValue, Time};
 use pyo3::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -29,14 +29,24 @@ macro_rules! impl_component {
                 let output_state = self.0.solve(t_current, t_next, &state)?;
                 Ok(output_state.to_hashmap())
             }
+
+            pub fn initialise(&self, t0: Time) -> HashMap<String, FloatValue> {
+                self.0.initialise(t0).to_hashmap()
+            }
         }
     };
 }
 
 /// Create a component builder that can be used by python to instantiate components created Rust.
+///
+/// Any doc comments before the builder name are used as the docstring of the python class.
+/// The builder can be created from keyword arguments (`Builder(a=1.0)`),
+/// from a dictionary of parameters (`Builder.from_parameters({"a": 1.0})`)
+/// or from a previous run (`Builder.from_run("model.toml", "Component")`).
 #[macro_export]
 macro_rules! create_component_builder {
-    ($builder_name:ident, $rust_component:ty, $component_parameters:ty) => {
+    ($(#[$meta:meta])* $builder_name:ident, $rust_component:ty, $component_parameters:ty) => {
+        $(#[$meta])*
         #[pyclass]
         pub struct $builder_name {
             parameters: $component_parameters,
@@ -44,6 +54,17 @@ macro_rules! create_component_builder {
 
         #[pymethods]
         impl $builder_name {
+            #[new]
+            #[pyo3(signature = (**parameters))]
+            pub fn new(
+                py: Python<'_>,
+                parameters: Option<Bound<pyo3::types::PyDict>>,
+            ) -> PyResult<Self> {
+                let parameters =
+                    parameters.unwrap_or_else(|| pyo3::types::PyDict::new_bound(py));
+                Self::from_parameters(parameters.into_any())
+            }
+
             #[staticmethod]
             pub fn from_parameters(parameters: Bound<PyAny>) -> PyResult<Self> {
                 use pyo3::exceptions::PyValueError;
@@ -55,6 +76,16 @@ macro_rules! create_component_builder {
                     Err(e) => Err(PyValueError::new_err(format!("{}", e))),
                 }
             }
+            /// Create a builder using the parameters from a previous run
+            ///
+            /// `path` is a model that was serialised using `Model.to_toml`
+            #[staticmethod]
+            pub fn from_run(path: std::path::PathBuf, component_name: &str) -> PyResult<Self> {
+                let parameters = $crate::provenance::component_parameters_from_run::<
+                    $component_parameters,
+                >(path, component_name)?;
+                Ok(Self { parameters })
+            }
             pub fn build(&self) -> PyRustComponent {
                 PyRustComponent(std::sync::Arc::new(<$rust_component>::from_parameters(
                     self.parameters.clone(),
@@ -67,11 +98,36 @@ macro_rules! create_component_builder {
 #[pymethods]
 impl RequirementDefinition {
     #[new]
-    pub fn new_python(name: String, unit: String, requirement_type: RequirementType) -> Self {
+    #[pyo3(signature = (
+        name,
+        unit,
+        requirement_type,
+        default=None,
+        disaggregation=DisaggregationStrategy::Interpolate,
+        labels=vec![],
+        min=None,
+        max=None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_python(
+        name: String,
+        unit: String,
+        requirement_type: RequirementType,
+        default: Option<FloatValue>,
+        disaggregation: DisaggregationStrategy,
+        labels: Vec<String>,
+        min: Option<FloatValue>,
+        max: Option<FloatValue>,
+    ) -> Self {
         Self {
             name,
             unit,
             requirement_type,
+            default,
+            disaggregation,
+            labels,
+            min,
+            max,
         }
     }
 }
@@ -107,6 +163,20 @@ impl Component for PythonComponent {
         })
     }
 
+    fn initialise(&self, t0: Time) -> OutputState {
+        Python::with_gil(|py| {
+            let component = self.component.bind(py);
+            // Python components don't need to implement `initialise`
+            match component.hasattr("initialise").unwrap() {
+                true => {
+                    let py_result = component.call_method1("initialise", (t0,)).unwrap();
+                    OutputState::from_hashmap(py_result.extract().unwrap())
+                }
+                false => OutputState::empty(),
+            }
+        })
+    }
+
     fn solve(
         &self,
         t_current: Time,
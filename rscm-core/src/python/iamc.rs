@@ -0,0 +1,17 @@
+use crate::iamc;
+use crate::python::timeseries_collection::PyTimeseriesCollection;
+use pyo3::prelude::*;
+use std::path::PathBuf;
+
+/// Read the timeseries of a scenario from a CSV file in the IAMC format
+#[pyfunction]
+#[pyo3(signature = (path, scenario=None, region=None))]
+pub fn load_scenario(
+    path: PathBuf,
+    scenario: Option<&str>,
+    region: Option<&str>,
+) -> PyResult<PyTimeseriesCollection> {
+    Ok(PyTimeseriesCollection(iamc::load_scenario(
+        path, scenario, region,
+    )?))
+}
@@ -0,0 +1,15 @@
+use crate::metrics::{score_card as calculate_score_card, ScoreCardEntry};
+use crate::python::timeseries_collection::PyTimeseriesCollection;
+use crate::timeseries::Time;
+use pyo3::prelude::*;
+
+/// Calculate the skill of a model for each reference variable and window
+#[pyfunction]
+pub fn score_card(
+    model: Bound<PyTimeseriesCollection>,
+    references: Bound<PyTimeseriesCollection>,
+    windows: Vec<(Time, Time)>,
+) -> PyResult<Vec<ScoreCardEntry>> {
+    let card = calculate_score_card(&model.borrow().0, &references.borrow().0, &windows)?;
+    Ok(card.entries)
+}
@@ -34,7 +34,7 @@
 /// let mut collection = TimeseriesCollection::new();
 /// let timeseries = Timeseries::from_values(array![1.0, 2.0, 3.0], array![1.0, 2.0, 3.0]);
 ///
-/// collection.add_timeseries("Surface Temperature".to_string(), timeseries, VariableType::Exogenous);
+/// collection.add_timeseries("Surface Temperature".to_string(), timeseries, VariableType::Exogenous).unwrap();
 /// // We can't access timeseries anymore because the collection has taken ownership
 /// // The line below generates a compiler error if uncommented
 /// // timeseries.at(0)
@@ -80,36 +80,128 @@
 /// This tradeoff might be worth it if we need to expose a lot of functionality to Python,
 /// and it would also reduce the amount of boilerplate code.
 use crate::errors::RSCMError;
-use pyo3::exceptions::PyRuntimeError;
+use pyo3::exceptions::{PyIndexError, PyKeyError, PyRuntimeError, PyTimeoutError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::{pymodule, Bound, PyResult};
 
 mod component;
 mod example_component;
+mod iamc;
+mod metrics;
 mod model;
+mod optimise;
+mod sampling;
 pub mod timeseries;
-mod timeseries_collection;
+pub mod timeseries_collection;
+mod units;
 
 pub use component::PyRustComponent;
 
 #[pymodule]
 pub fn core(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<timeseries::PyTimeAxis>()?;
+    m.add_class::<crate::calendar::Calendar>()?;
     m.add_class::<timeseries::PyTimeseries>()?;
     m.add_class::<timeseries::PyInterpolationStrategy>()?;
+    m.add_class::<timeseries::PyExtrapolationPolicy>()?;
+    m.add_class::<crate::timeseries::DisaggregationStrategy>()?;
+    m.add_class::<crate::timeseries::RegridMethod>()?;
     m.add_class::<timeseries_collection::PyTimeseriesCollection>()?;
     m.add_class::<timeseries_collection::VariableType>()?;
+    m.add_class::<crate::archive::Precision>()?;
+    m.add_class::<crate::archive::Compression>()?;
     m.add_class::<component::PyPythonComponent>()?;
     m.add_class::<component::RequirementDefinition>()?;
     m.add_class::<component::RequirementType>()?;
+    m.add_class::<crate::model::NanPolicy>()?;
+    m.add_class::<crate::model::WarningKind>()?;
+    m.add("RSCMWarning", m.py().get_type_bound::<model::RSCMWarning>())?;
+    m.add(
+        "MissingExogenousDataWarning",
+        m.py()
+            .get_type_bound::<model::MissingExogenousDataWarning>(),
+    )?;
+    m.add(
+        "IncompatibleUnitsWarning",
+        m.py().get_type_bound::<model::IncompatibleUnitsWarning>(),
+    )?;
+    m.add(
+        "NanValuesWarning",
+        m.py().get_type_bound::<model::NanValuesWarning>(),
+    )?;
+    m.add(
+        "ComponentFailedWarning",
+        m.py().get_type_bound::<model::ComponentFailedWarning>(),
+    )?;
+    m.add(
+        "RunAbortedWarning",
+        m.py().get_type_bound::<model::RunAbortedWarning>(),
+    )?;
+    m.add(
+        "LossyResamplingWarning",
+        m.py().get_type_bound::<model::LossyResamplingWarning>(),
+    )?;
+    m.add(
+        "ComponentError",
+        m.py().get_type_bound::<model::ComponentError>(),
+    )?;
+    m.add("BuildError", m.py().get_type_bound::<model::BuildError>())?;
+    m.add(
+        "MassBalanceViolationWarning",
+        m.py()
+            .get_type_bound::<model::MassBalanceViolationWarning>(),
+    )?;
+    m.add(
+        "OutOfBoundsWarning",
+        m.py().get_type_bound::<model::OutOfBoundsWarning>(),
+    )?;
+    m.add(
+        "OutputFailedWarning",
+        m.py().get_type_bound::<model::OutputFailedWarning>(),
+    )?;
     m.add_class::<model::PyModelBuilder>()?;
     m.add_class::<model::PyModel>()?;
     m.add_class::<example_component::TestComponentBuilder>()?;
+    m.add_class::<crate::metrics::Skill>()?;
+    m.add_class::<crate::metrics::ScoreCardEntry>()?;
+    m.add_class::<crate::sampling::SamplingMethod>()?;
+    m.add_class::<crate::gradient::OptimisationResult>()?;
+    m.add_class::<crate::cost::CostEstimate>()?;
+    m.add_function(wrap_pyfunction!(metrics::score_card, m)?)?;
+    m.add_function(wrap_pyfunction!(optimise::calibrate, m)?)?;
+    m.add_function(wrap_pyfunction!(iamc::load_scenario, m)?)?;
+    m.add_function(wrap_pyfunction!(sampling::sample, m)?)?;
+    m.add_function(wrap_pyfunction!(units::register_unit, m)?)?;
+    m.add_function(wrap_pyfunction!(units::register_context, m)?)?;
+    m.add_function(wrap_pyfunction!(units::conversion_factor, m)?)?;
     Ok(())
 }
 
 impl From<RSCMError> for PyErr {
     fn from(e: RSCMError) -> PyErr {
-        PyRuntimeError::new_err(e.to_string())
+        match e {
+            RSCMError::LimitExceeded(_) => PyTimeoutError::new_err(e.to_string()),
+            RSCMError::NonMonotonicTime(..) | RSCMError::TimeseriesAlreadyExists(_) => {
+                PyValueError::new_err(e.to_string())
+            }
+            RSCMError::IndexOutOfBounds(..) => PyIndexError::new_err(e.to_string()),
+            RSCMError::TimeseriesNotFound(_) => PyKeyError::new_err(e.to_string()),
+            RSCMError::ComponentFailed {
+                ref component,
+                time_index,
+                time,
+                ..
+            } => {
+                let err = model::ComponentError::new_err(e.to_string());
+                Python::with_gil(|py| {
+                    let value = err.value_bound(py);
+                    value.setattr("component", component)?;
+                    value.setattr("time_index", time_index)?;
+                    value.setattr("time", time)
+                })
+                .map_or_else(|set_err| set_err, |_| err)
+            }
+            _ => PyRuntimeError::new_err(e.to_string()),
+        }
     }
 }
@@ -1,18 +1,174 @@
-use crate::component::InputState;
-use crate::model::{Model, ModelBuilder};
+use crate::attribution::{run_attribution, ForcingGroup};
+use crate::component::{Component, InputState, RngStream};
+use crate::cost::CostEstimate;
+use crate::events::{ModelEvent, ModelObserver};
+use crate::model::{
+    ComponentGroupToggles, Model, ModelBuilder, ModelOptions, ModelWarning, NanPolicy, RunLimits,
+    StabilityOptions, WarningKind, STATE_FORMAT_VERSION,
+};
 use crate::python::component::PyPythonComponent;
-use crate::python::timeseries::{PyTimeAxis, PyTimeseries};
+use crate::python::timeseries::PyExtrapolationPolicy;
+use crate::python::timeseries::{exogenous_timeseries, PyTimeAxis, PyTimeseries};
 use crate::python::timeseries_collection::PyTimeseriesCollection;
 use crate::python::PyRustComponent;
+use crate::remap::RemappedComponent;
 use crate::timeseries::{FloatValue, Time};
-use pyo3::exceptions::PyValueError;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyRuntimeError, PyUserWarning, PyValueError};
 use pyo3::prelude::*;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Serialised random number streams of a model
+#[derive(Serialize, Deserialize)]
+struct RngStreams {
+    streams: Vec<RngStream>,
+}
+
+create_exception!(
+    core,
+    ComponentError,
+    PyRuntimeError,
+    "A component failed to solve a step"
+);
+create_exception!(
+    core,
+    BuildError,
+    PyValueError,
+    "A model couldn't be built because of problems with its inputs"
+);
+create_exception!(
+    core,
+    RSCMWarning,
+    PyUserWarning,
+    "Base class of model warnings"
+);
+create_exception!(
+    core,
+    MissingExogenousDataWarning,
+    RSCMWarning,
+    "Exogenous data required by a component wasn't provided"
+);
+create_exception!(
+    core,
+    IncompatibleUnitsWarning,
+    RSCMWarning,
+    "Exogenous data has units which can't be converted"
+);
+create_exception!(
+    core,
+    NanValuesWarning,
+    RSCMWarning,
+    "A component produced NaN values"
+);
+create_exception!(
+    core,
+    ComponentFailedWarning,
+    RSCMWarning,
+    "A component failed to solve a step"
+);
+create_exception!(
+    core,
+    RunAbortedWarning,
+    RSCMWarning,
+    "A run was stopped before reaching the end of the time axis"
+);
+create_exception!(
+    core,
+    LossyResamplingWarning,
+    RSCMWarning,
+    "Exogenous data has time steps which don't align with the model's time steps"
+);
+create_exception!(
+    core,
+    MassBalanceViolationWarning,
+    RSCMWarning,
+    "A conserved quantity didn't balance after a step"
+);
+create_exception!(
+    core,
+    OutOfBoundsWarning,
+    RSCMWarning,
+    "A component produced values outside of their declared bounds"
+);
+create_exception!(
+    core,
+    OutputFailedWarning,
+    RSCMWarning,
+    "Values couldn't be written to an output sink"
+);
+
+/// Emit model warnings using Python's `warnings` module
+///
+/// If a warning filter turns a warning into an error, the error is returned.
+fn emit_warnings(py: Python, warnings: &[ModelWarning]) -> PyResult<()> {
+    warnings.iter().try_for_each(|warning| {
+        let category = match warning.kind {
+            WarningKind::MissingExogenousData => py.get_type_bound::<MissingExogenousDataWarning>(),
+            WarningKind::IncompatibleUnits => py.get_type_bound::<IncompatibleUnitsWarning>(),
+            WarningKind::NanValues => py.get_type_bound::<NanValuesWarning>(),
+            WarningKind::OutOfBounds => py.get_type_bound::<OutOfBoundsWarning>(),
+            WarningKind::ComponentFailed => py.get_type_bound::<ComponentFailedWarning>(),
+            WarningKind::RunAborted => py.get_type_bound::<RunAbortedWarning>(),
+            WarningKind::LossyResampling => py.get_type_bound::<LossyResamplingWarning>(),
+            WarningKind::MassBalanceViolation => py.get_type_bound::<MassBalanceViolationWarning>(),
+            WarningKind::OutputFailed => py.get_type_bound::<OutputFailedWarning>(),
+        };
+        PyErr::warn_bound(py, category.as_any(), &warning.message, 1)
+    })
+}
+
+/// Rename the variables of a component if `variables` or `namespace` is provided
+fn remap(
+    component: Arc<dyn Component>,
+    variables: Option<BTreeMap<String, String>>,
+    namespace: Option<&str>,
+) -> Arc<dyn Component> {
+    match (&variables, namespace) {
+        (None, None) => component,
+        _ => {
+            let mut remapped = RemappedComponent::new(component);
+            if let Some(namespace) = namespace {
+                remapped.with_namespace(namespace);
+            }
+            variables.iter().flatten().for_each(|(from, to)| {
+                remapped.with_variable(from, to);
+            });
+            Arc::new(remapped)
+        }
+    }
+}
 
 #[pyclass]
 #[pyo3(name = "ModelBuilder")]
 pub struct PyModelBuilder(pub ModelBuilder);
 
+impl PyModelBuilder {
+    /// Register a component, renaming its variables if `variables` or `namespace` is provided
+    ///
+    /// Namespaced components are named after the namespace unless a `name` is provided.
+    fn register(
+        &mut self,
+        component: Arc<dyn Component>,
+        group: Option<&str>,
+        name: Option<&str>,
+        variables: Option<BTreeMap<String, String>>,
+        namespace: Option<&str>,
+    ) {
+        let component = remap(component, variables, namespace);
+        match (group, name.or(namespace)) {
+            (Some(group), Some(name)) => {
+                self.0.with_named_component_in_group(group, name, component)
+            }
+            (Some(group), None) => self.0.with_component_in_group(group, component),
+            (None, Some(name)) => self.0.with_named_component(name, component),
+            (None, None) => self.0.with_component(component),
+        };
+    }
+}
+
 #[pymethods]
 impl PyModelBuilder {
     #[new]
@@ -21,21 +177,222 @@ impl PyModelBuilder {
     }
 
     /// Add a component that is defined in rust
+    #[pyo3(signature = (component, group=None, name=None, variables=None, namespace=None))]
     fn with_rust_component<'py>(
         mut self_: PyRefMut<'py, Self>,
         component: Bound<'py, PyRustComponent>,
+        group: Option<&str>,
+        name: Option<&str>,
+        variables: Option<BTreeMap<String, String>>,
+        namespace: Option<&str>,
     ) -> PyResult<PyRefMut<'py, Self>> {
-        self_.0.with_component(component.borrow().0.clone());
+        let component = component.borrow().0.clone();
+        self_.register(component, group, name, variables, namespace);
         Ok(self_)
     }
 
     /// Pass a component that is defined in python (UserDerivedComponent)
+    #[pyo3(signature = (component, group=None, name=None, variables=None, namespace=None))]
     fn with_py_component<'py>(
         mut self_: PyRefMut<'py, Self>,
         component: Bound<'py, PyPythonComponent>,
+        group: Option<&str>,
+        name: Option<&str>,
+        variables: Option<BTreeMap<String, String>>,
+        namespace: Option<&str>,
     ) -> PyResult<PyRefMut<'py, Self>> {
         let user_derived_component = component.borrow().0.clone();
-        self_.0.with_component(user_derived_component);
+        self_.register(user_derived_component, group, name, variables, namespace);
+        Ok(self_)
+    }
+
+    /// Run the model with all forcings and then with each forcing group zeroed out
+    ///
+    /// Returns the target variable from the run with all forcings and the
+    /// contribution from each forcing group.
+    fn run_attribution(
+        &self,
+        target: &str,
+        groups: HashMap<String, Vec<String>>,
+    ) -> PyResult<(PyTimeseries, HashMap<String, PyTimeseries>)> {
+        let groups: Vec<ForcingGroup> = groups
+            .into_iter()
+            .map(|(name, variables)| ForcingGroup { name, variables })
+            .collect();
+        let result = run_attribution(&self.0, target, &groups)?;
+
+        Ok((
+            PyTimeseries(result.total),
+            result
+                .contributions
+                .into_iter()
+                .map(|(name, ts)| (name, PyTimeseries(ts)))
+                .collect(),
+        ))
+    }
+
+    /// Detect numerical instability and retry unstable steps with a smaller solver step
+    #[pyo3(signature = (max_relative_change, absolute_tolerance=0.0, max_refinements=4))]
+    fn with_stability_options<'py>(
+        mut self_: PyRefMut<'py, Self>,
+        max_relative_change: FloatValue,
+        absolute_tolerance: FloatValue,
+        max_refinements: u32,
+    ) -> PyRefMut<'py, Self> {
+        self_.0.with_stability_options(StabilityOptions {
+            max_relative_change,
+            absolute_tolerance,
+            max_refinements,
+        });
+        self_
+    }
+
+    /// Abort runs which exceed a wall time (in seconds) or number of steps
+    #[pyo3(signature = (max_wall_time=None, max_steps=None))]
+    fn with_run_limits<'py>(
+        mut self_: PyRefMut<'py, Self>,
+        max_wall_time: Option<f64>,
+        max_steps: Option<usize>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let max_wall_time = max_wall_time
+            .map(Duration::try_from_secs_f64)
+            .transpose()
+            .map_err(|e| PyValueError::new_err(format!("Invalid max_wall_time: {}", e)))?;
+        self_.0.with_run_limits(RunLimits {
+            max_wall_time,
+            max_steps,
+        });
+        Ok(self_)
+    }
+
+    /// Call `callback` with each event of the model
+    fn with_observer<'py>(
+        mut self_: PyRefMut<'py, Self>,
+        callback: PyObject,
+    ) -> PyRefMut<'py, Self> {
+        self_.0.with_observer(Arc::new(PyObserver(callback)));
+        self_
+    }
+
+    /// Only store the variables which match one of a set of glob patterns
+    fn with_output_variables<'py>(
+        mut self_: PyRefMut<'py, Self>,
+        patterns: Vec<String>,
+    ) -> PyRefMut<'py, Self> {
+        let patterns: Vec<&str> = patterns.iter().map(|p| p.as_str()).collect();
+        self_.0.with_output_variables(&patterns);
+        self_
+    }
+
+    /// Calculate the sensitivity of every variable to a component parameter
+    fn with_sensitivity<'py>(
+        mut self_: PyRefMut<'py, Self>,
+        parameter: &str,
+    ) -> PyRefMut<'py, Self> {
+        self_.0.with_sensitivity(parameter);
+        self_
+    }
+
+    /// Seed the random number streams of the components
+    fn with_rng_seed<'py>(mut self_: PyRefMut<'py, Self>, seed: u64) -> PyRefMut<'py, Self> {
+        self_.0.with_rng_seed(seed);
+        self_
+    }
+
+    /// Set the index of the model in an ensemble, which gives each member different random numbers
+    fn with_ensemble_member<'py>(
+        mut self_: PyRefMut<'py, Self>,
+        member: u64,
+    ) -> PyRefMut<'py, Self> {
+        self_.0.with_ensemble_member(member);
+        self_
+    }
+
+    /// Set the name of the scenario recorded in the model's metadata
+    fn with_scenario<'py>(mut self_: PyRefMut<'py, Self>, scenario: &str) -> PyRefMut<'py, Self> {
+        self_.0.with_scenario(scenario);
+        self_
+    }
+
+    /// Record the values of the parameters used to build the model in its metadata
+    fn with_parameters<'py>(
+        mut self_: PyRefMut<'py, Self>,
+        parameters: BTreeMap<String, FloatValue>,
+    ) -> PyRefMut<'py, Self> {
+        self_.0.with_parameters(parameters);
+        self_
+    }
+
+    /// Record the version of a package used to build the model in its metadata
+    fn with_version<'py>(
+        mut self_: PyRefMut<'py, Self>,
+        name: &str,
+        version: &str,
+    ) -> PyRefMut<'py, Self> {
+        self_.0.with_version(name, version);
+        self_
+    }
+
+    /// Set the options which apply to the whole model
+    ///
+    /// Any stability options, run limits, sensitivities and random number seeds
+    /// which have already been set are kept.
+    #[pyo3(signature = (
+        nan_policy=NanPolicy::Allow,
+        extrapolation=None,
+        solver_step=0.1,
+        threads=1,
+        strict=false,
+        unit_contexts=vec![],
+        parallel_components=false
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn with_options<'py>(
+        mut self_: PyRefMut<'py, Self>,
+        nan_policy: NanPolicy,
+        extrapolation: Option<PyExtrapolationPolicy>,
+        solver_step: Time,
+        threads: usize,
+        strict: bool,
+        unit_contexts: Vec<String>,
+        parallel_components: bool,
+    ) -> PyRefMut<'py, Self> {
+        let existing = self_.0.options().clone();
+        self_.0.with_options(ModelOptions {
+            nan_policy,
+            extrapolation: extrapolation.map_or(ModelOptions::default().extrapolation, |e| e.0),
+            solver_step,
+            threads,
+            parallel_components,
+            strict,
+            stability: existing.stability,
+            limits: existing.limits,
+            unit_contexts,
+            sensitivity: existing.sensitivity,
+            rng_seed: existing.rng_seed,
+            ensemble_member: existing.ensemble_member,
+        });
+        self_
+    }
+
+    /// Enable or disable a named group of components
+    fn with_group_enabled<'py>(
+        mut self_: PyRefMut<'py, Self>,
+        group: &str,
+        enabled: bool,
+    ) -> PyRefMut<'py, Self> {
+        self_.0.with_group_enabled(group, enabled);
+        self_
+    }
+
+    /// Enable or disable groups of components using a TOML configuration
+    fn with_group_toggles_toml<'py>(
+        mut self_: PyRefMut<'py, Self>,
+        config: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let toggles = ComponentGroupToggles::from_toml(config)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        self_.0.with_group_toggles(toggles);
         Ok(self_)
     }
 
@@ -58,15 +415,20 @@ impl PyModelBuilder {
         self_
     }
 
+    /// Add exogenous data for a variable
+    ///
+    /// See [`exogenous_timeseries`] for the types of data which can be provided.
+    #[pyo3(signature = (name, values, times=None, unit=None))]
     fn with_exogenous_variable<'py>(
         mut self_: PyRefMut<'py, Self>,
         name: &str,
-        timeseries: Bound<'py, PyTimeseries>,
-    ) -> PyRefMut<'py, Self> {
-        self_
-            .0
-            .with_exogenous_variable(name, timeseries.borrow().0.clone());
-        self_
+        values: Bound<'py, PyAny>,
+        times: Option<Bound<'py, PyAny>>,
+        unit: Option<&str>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let timeseries = exogenous_timeseries(name, &values, times.as_ref(), unit)?;
+        self_.0.with_exogenous_variable(name, timeseries);
+        Ok(self_)
     }
 
     fn with_exogenous_collection<'py>(
@@ -79,8 +441,26 @@ impl PyModelBuilder {
         self_
     }
 
-    fn build(&self) -> PyResult<PyModel> {
-        Ok(PyModel(self.0.build()))
+    /// Build the model
+    ///
+    /// Any warnings from building the model are emitted using the `warnings` module.
+    fn build(&self, py: Python) -> PyResult<PyModel> {
+        let model = self.0.build();
+        emit_warnings(py, model.warnings())?;
+        Ok(PyModel(model))
+    }
+
+    /// Build the model, raising a `BuildError` listing any problems with its inputs
+    fn try_build(&self, py: Python) -> PyResult<PyModel> {
+        match self.0.try_build() {
+            Ok(model) => Ok(PyModel(model)),
+            Err(e) => {
+                let problems: Vec<String> = e.problems.iter().map(|p| p.to_string()).collect();
+                let err = BuildError::new_err(e.to_string());
+                err.value_bound(py).setattr("problems", problems)?;
+                Err(err)
+            }
+        }
     }
 }
 
@@ -92,6 +472,19 @@ pub struct PyModel(pub Model);
 impl PyModel {
     // Not exposing initialiser deliberately
 
+    fn __repr__(&self) -> String {
+        format!("<Model {}>", self.0.summary())
+    }
+
+    /// A single line description of the model
+    fn summary(&self) -> String {
+        self.0.summary()
+    }
+
+    fn _repr_html_(&self) -> String {
+        self.0.to_html()
+    }
+
     fn current_time(&self) -> Time {
         self.0.current_time()
     }
@@ -100,16 +493,92 @@ impl PyModel {
         self.0.current_time_bounds()
     }
 
-    fn step(mut self_: PyRefMut<Self>) {
-        self_.0.step()
+    fn step(mut self_: PyRefMut<Self>, py: Python) -> PyResult<()> {
+        let existing = self_.0.warnings().len();
+        self_.0.step();
+        emit_warnings(py, &self_.0.warnings()[existing..])
+    }
+    /// Raises a `TimeoutError` if the run exceeds any limits
+    fn run(mut self_: PyRefMut<Self>, py: Python) -> PyResult<()> {
+        let existing = self_.0.warnings().len();
+        let result = self_.0.run_within_limits();
+        emit_warnings(py, &self_.0.warnings()[existing..])?;
+        Ok(result?)
     }
-    fn run(mut self_: PyRefMut<Self>) {
-        self_.0.run()
+
+    /// Raises a `ComponentError` if any component fails to solve the step
+    fn try_step(mut self_: PyRefMut<Self>, py: Python) -> PyResult<()> {
+        let existing = self_.0.warnings().len();
+        let result = self_.0.try_step();
+        emit_warnings(py, &self_.0.warnings()[existing..])?;
+        Ok(result?)
+    }
+
+    /// Raises a `ComponentError` at the first failed step
+    /// or a `TimeoutError` if the run exceeds any limits
+    fn try_run(mut self_: PyRefMut<Self>, py: Python) -> PyResult<()> {
+        let existing = self_.0.warnings().len();
+        let result = self_.0.try_run();
+        emit_warnings(py, &self_.0.warnings()[existing..])?;
+        Ok(result?)
+    }
+
+    /// Warnings recorded while building and running the model
+    ///
+    /// Each item is a tuple of (kind, message)
+    fn warnings(&self) -> Vec<(WarningKind, String)> {
+        self.0
+            .warnings()
+            .iter()
+            .map(|w| (w.kind, w.message.clone()))
+            .collect()
+    }
+
+    /// Create a component which solves the components of this model
+    ///
+    /// The component can be registered with another model using `with_rust_component`.
+    fn to_component(&self) -> PyRustComponent {
+        PyRustComponent(Arc::new(self.0.to_component()))
     }
 
     fn as_dot(&self) -> String {
-        let dot = self.0.as_dot();
-        format!("{:?}", dot)
+        self.0.as_dot()
+    }
+
+    /// Mermaid flowchart of the components and the variables passed between them
+    fn as_mermaid(&self) -> String {
+        self.0.diagnostics().as_mermaid()
+    }
+
+    /// JSON description of the components and the variables passed between them
+    fn as_json(&self) -> String {
+        self.0.diagnostics().as_json()
+    }
+
+    /// Estimate the runtime and memory use of the remainder of the run
+    ///
+    /// The estimate is scaled to an ensemble of `members` runs shared between `threads`.
+    #[pyo3(signature = (members=1, threads=1))]
+    fn estimate_cost(&self, members: usize, threads: usize) -> CostEstimate {
+        self.0.estimate_cost().ensemble(members, threads)
+    }
+
+    /// Names of the components in the order they are solved each step
+    fn execution_order(&self) -> Vec<String> {
+        self.0
+            .execution_order()
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Names of the components in the model
+    fn component_names(&self) -> Vec<String> {
+        self.0
+            .component_names()
+            .into_iter()
+            .map(String::from)
+            .collect()
     }
 
     fn finished(&self) -> bool {
@@ -120,6 +589,118 @@ impl PyModel {
         PyTimeseriesCollection(self.0.timeseries().clone())
     }
 
+    /// Convert the state of the model to a pandas DataFrame
+    fn to_pandas(&self, py: Python) -> PyResult<PyObject> {
+        PyTimeseriesCollection::to_pandas(Bound::new(py, self.timeseries())?)
+    }
+
+    /// Replace exogenous timeseries, rewinding to the first step with modified inputs
+    ///
+    /// Returns the time index that the model will resume from.
+    fn update_exogenous(&mut self, collection: Bound<PyTimeseriesCollection>) -> PyResult<usize> {
+        Ok(self.0.update_exogenous(collection.borrow().0.clone())?)
+    }
+
+    /// Replace a component without rebuilding the model, rewinding to the start of the run
+    ///
+    /// `component` is either a rust or a python component.
+    /// `variables` and `namespace` rename the variables of the new component in the same
+    /// way as when registering a component, and must match the original registration.
+    /// See [`Model::replace_component`]
+    #[pyo3(signature = (component_name, component, variables=None, namespace=None))]
+    fn replace_component(
+        &mut self,
+        component_name: &str,
+        component: Bound<PyAny>,
+        variables: Option<BTreeMap<String, String>>,
+        namespace: Option<&str>,
+    ) -> PyResult<()> {
+        let component: Arc<dyn Component> =
+            if let Ok(component) = component.downcast::<PyRustComponent>() {
+                component.borrow().0.clone()
+            } else if let Ok(component) = component.downcast::<PyPythonComponent>() {
+                component.borrow().0.clone()
+            } else {
+                return Err(PyValueError::new_err("Expected a rust or python component"));
+            };
+        Ok(self
+            .0
+            .replace_component(component_name, remap(component, variables, namespace))?)
+    }
+
+    /// Continue the run on a different time axis, e.g. with shorter time steps
+    ///
+    /// See [`Model::regrid`]
+    #[pyo3(signature = (time_axis, exogenous=None))]
+    fn regrid(
+        &mut self,
+        time_axis: Bound<PyTimeAxis>,
+        exogenous: Option<Bound<PyTimeseriesCollection>>,
+    ) -> PyResult<()> {
+        let exogenous = exogenous
+            .map(|collection| collection.borrow().0.clone())
+            .unwrap_or_default();
+        Ok(self
+            .0
+            .regrid(time_axis.borrow().0.as_ref().clone(), exogenous)?)
+    }
+
+    /// Derivative of a variable with respect to the parameter set using `with_sensitivity`
+    fn sensitivity(&self, name: &str) -> Option<PyTimeseries> {
+        self.0.sensitivity(name).map(PyTimeseries)
+    }
+
+    /// Scenario, parameters, versions and timing of the run as a dictionary
+    fn metadata(&self, py: Python) -> PyResult<PyObject> {
+        Ok(pythonize::pythonize(py, self.0.metadata())?)
+    }
+
+    /// Steps which required refinement to be solved stably
+    ///
+    /// Each item is a tuple of (time index, component name, number of refinements)
+    fn refinements(&self) -> Vec<(usize, String, u32)> {
+        self.0
+            .refinements()
+            .iter()
+            .map(|r| (r.time_index, r.component.clone(), r.refinements))
+            .collect()
+    }
+
+    /// Serialise the random number streams of each component as TOML
+    ///
+    /// The streams can be restored using `restore_rng_streams`,
+    /// independently of the rest of the model's state.
+    fn save_rng_streams(&self) -> PyResult<String> {
+        let streams = RngStreams {
+            streams: self.0.rng_streams().to_vec(),
+        };
+        toml::to_string(&streams).map_err(|e| PyValueError::new_err(format!("{}", e)))
+    }
+
+    /// Restore random number streams saved using `save_rng_streams`
+    fn restore_rng_streams(&mut self, state: &str) -> PyResult<()> {
+        let streams = toml::from_str::<RngStreams>(state)
+            .map_err(|e| PyValueError::new_err(format!("{}", e)))?;
+        Ok(self.0.set_rng_streams(streams.streams)?)
+    }
+
+    /// Call `callback` with each subsequent event of the model
+    fn add_observer(&mut self, callback: PyObject) {
+        self.0.add_observer(Arc::new(PyObserver(callback)));
+    }
+
+    /// Reseed the random number stream of a component
+    ///
+    /// If `component_name` is None then the streams of all components are reseeded.
+    #[pyo3(signature = (seed, component_name=None))]
+    fn reseed_rng_streams(&mut self, seed: u64, component_name: Option<&str>) -> PyResult<()> {
+        match component_name {
+            Some(name) => self.0.reseed_rng_stream(name, seed)?,
+            None => self.0.reseed_rng_streams(seed),
+        }
+        Ok(())
+    }
+
     /// Generate a JSON representation of the model
     ///
     /// This includes the components, their internal state and the model's
@@ -132,6 +713,24 @@ impl PyModel {
         }
     }
 
+    /// Version of the format written by `save_state`
+    #[classattr]
+    #[allow(non_snake_case)]
+    fn STATE_FORMAT_VERSION() -> u32 {
+        STATE_FORMAT_VERSION
+    }
+
+    /// Save the state of the model so that the run can be resumed later
+    fn save_state(&self) -> PyResult<String> {
+        Ok(self.0.save_state()?)
+    }
+
+    /// Restore a model from a state written by `save_state`
+    #[staticmethod]
+    fn load_state(state: &str) -> PyResult<Self> {
+        Ok(PyModel(Model::load_state(state)?))
+    }
+
     /// Initialise a model from a TOML representation
     #[staticmethod]
     fn from_toml(string: String) -> PyResult<Self> {
@@ -142,3 +741,24 @@ impl PyModel {
         }
     }
 }
+
+/// Passes the events of a model to a Python callable as dictionaries
+///
+/// The `kind` key contains the name of the event.
+/// Exceptions raised by the callable are logged and otherwise ignored
+/// so that an observer can't interrupt a run.
+#[derive(Debug)]
+struct PyObserver(PyObject);
+
+impl ModelObserver for PyObserver {
+    fn notify(&self, event: &ModelEvent) {
+        Python::with_gil(|py| {
+            let result = pythonize::pythonize(py, event)
+                .map_err(PyErr::from)
+                .and_then(|event| self.0.call1(py, (event,)));
+            if let Err(err) = result {
+                log::warn!("Observer failed to handle {:?}: {}", event, err);
+            }
+        });
+    }
+}
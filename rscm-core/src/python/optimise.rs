@@ -0,0 +1,82 @@
+use crate::calibration::{CalibrationParameter, ParameterSet};
+use crate::errors::RSCMError;
+use crate::gradient::OptimisationResult;
+use crate::optimise::{nelder_mead, NelderMeadOptions};
+use crate::python::model::PyModelBuilder;
+use crate::python::timeseries_collection::PyTimeseriesCollection;
+use crate::timeseries::FloatValue;
+use pyo3::prelude::*;
+use std::collections::BTreeMap;
+
+/// Find the parameters which minimise a cost using the Nelder-Mead simplex method
+///
+/// `build` is called with a dictionary of parameters and returns a `ModelBuilder`.
+/// `cost` is called with the output of each successful run.
+/// Errors raised by either callable stop the search and are re-raised.
+#[pyfunction]
+#[pyo3(signature = (
+    initial,
+    bounds,
+    build,
+    cost,
+    max_iterations=500,
+    initial_step=0.1,
+    tolerance=1e-8,
+    parameter_tolerance=1e-6
+))]
+#[allow(clippy::too_many_arguments)]
+pub fn calibrate(
+    py: Python,
+    initial: ParameterSet,
+    bounds: BTreeMap<String, (FloatValue, FloatValue)>,
+    build: Bound<PyAny>,
+    cost: Bound<PyAny>,
+    max_iterations: usize,
+    initial_step: FloatValue,
+    tolerance: FloatValue,
+    parameter_tolerance: FloatValue,
+) -> PyResult<OptimisationResult> {
+    let bounds: Vec<CalibrationParameter> = bounds
+        .into_iter()
+        .map(|(name, (min, max))| CalibrationParameter { name, min, max })
+        .collect();
+    let options = NelderMeadOptions {
+        max_iterations,
+        initial_step,
+        tolerance,
+        parameter_tolerance,
+    };
+
+    // Python errors are kept so that they can be re-raised with their original type
+    let mut py_err: Option<PyErr> = None;
+    let evaluate = |parameters: &ParameterSet| -> PyResult<FloatValue> {
+        let builder = build
+            .call1((parameters.clone().into_py(py),))?
+            .extract::<PyRef<PyModelBuilder>>()?
+            .0
+            .clone();
+        let mut model = builder.build();
+        if model.try_run().is_err() {
+            return Ok(FloatValue::INFINITY);
+        }
+        cost.call1((PyTimeseriesCollection(model.timeseries().clone()),))?
+            .extract()
+    };
+
+    let result = nelder_mead(
+        &initial,
+        &bounds,
+        |parameters| {
+            evaluate(parameters).map_err(|err| {
+                let message = err.to_string();
+                py_err = Some(err);
+                RSCMError::Error(message)
+            })
+        },
+        &options,
+    );
+    match (result, py_err) {
+        (_, Some(err)) => Err(err),
+        (result, None) => Ok(result?),
+    }
+}
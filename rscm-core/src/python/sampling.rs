@@ -0,0 +1,20 @@
+use crate::sampling::SamplingMethod;
+use crate::timeseries::FloatValue;
+use numpy::PyArray2;
+use pyo3::prelude::*;
+
+/// Sample `n` points in the unit hypercube
+///
+/// Returns an array with shape `(n, dimensions)`.
+#[pyfunction]
+#[pyo3(signature = (method, n, dimensions, seed=0))]
+pub fn sample(
+    py: Python,
+    method: SamplingMethod,
+    n: usize,
+    dimensions: usize,
+    seed: u64,
+) -> PyResult<Bound<PyArray2<FloatValue>>> {
+    let points = method.sampler(seed).sample(n, dimensions)?;
+    Ok(PyArray2::from_vec2_bound(py, &points)?)
+}
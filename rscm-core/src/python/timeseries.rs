@@ -1,11 +1,17 @@
+use crate::calendar::{Calendar, Date};
 use crate::errors::RSCMResult;
 use crate::interpolate::strategies::{
-    InterpolationStrategy, LinearSplineStrategy, NextStrategy, PreviousStrategy,
+    ExtrapolationPolicy, InterpolationStrategy, LinearSplineStrategy, NextStrategy,
+    PreviousStrategy,
 };
-use crate::timeseries::{FloatValue, Time, TimeAxis, Timeseries};
-use numpy::{PyArray1, PyArrayMethods, ToPyArray};
-use pyo3::exceptions::PyValueError;
+use crate::timeseries::{
+    DisaggregationStrategy, FloatValue, RegridMethod, Time, TimeAxis, Timeseries,
+};
+use numpy::ndarray::Array1;
+use numpy::{PyArray1, PyArrayMethods, PyReadonlyArray1, ToPyArray};
+use pyo3::exceptions::{PyIndexError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PySlice};
 use std::sync::Arc;
 
 #[pyclass]
@@ -17,14 +23,39 @@ impl PyTimeAxis {
     fn __repr__(&self) -> String {
         format!("{:?}", self.0)
     }
+    /// Time axis starting at each value
+    ///
+    /// Dates, e.g. a pandas DatetimeIndex or numpy datetime64 array,
+    /// are converted into decimal years.
+    #[staticmethod]
+    fn from_values(values: Bound<PyAny>) -> PyResult<Self> {
+        Ok(Self(Arc::new(TimeAxis::try_from_values(decimal_years(
+            &values,
+        )?)?)))
+    }
+
+    #[staticmethod]
+    fn from_bounds(bounds: Bound<PyArray1<Time>>) -> RSCMResult<Self> {
+        Ok(Self(Arc::new(TimeAxis::try_from_bounds(
+            bounds.to_owned_array(),
+        )?)))
+    }
+
+    #[staticmethod]
+    fn piecewise(periods: Vec<(Time, Time, Time)>) -> RSCMResult<Self> {
+        Ok(Self(Arc::new(TimeAxis::try_piecewise(&periods)?)))
+    }
+
     #[staticmethod]
-    fn from_values(values: Bound<PyArray1<Time>>) -> Self {
-        Self(Arc::new(TimeAxis::from_values(values.to_owned_array())))
+    #[pyo3(signature = (start_year, end_year, calendar=Calendar::ProlepticGregorian))]
+    fn monthly(start_year: i32, end_year: i32, calendar: Calendar) -> Self {
+        Self(Arc::new(TimeAxis::monthly(start_year, end_year, calendar)))
     }
 
     #[staticmethod]
-    fn from_bounds(bounds: Bound<PyArray1<Time>>) -> Self {
-        Self(Arc::new(TimeAxis::from_bounds(bounds.to_owned_array())))
+    #[pyo3(signature = (start_year, end_year, calendar=Calendar::ProlepticGregorian))]
+    fn daily(start_year: i32, end_year: i32, calendar: Calendar) -> Self {
+        Self(Arc::new(TimeAxis::daily(start_year, end_year, calendar)))
     }
 
     fn values<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<Time>> {
@@ -46,6 +77,12 @@ impl PyTimeAxis {
     fn at_bounds(&self, index: usize) -> Option<(Time, Time)> {
         self.0.at_bounds(index)
     }
+
+    /// ISO 8601 labels for the start of each time step
+    #[pyo3(signature = (calendar=Calendar::ProlepticGregorian, include_day=false))]
+    fn labels(&self, calendar: Calendar, include_day: bool) -> Vec<String> {
+        self.0.labels(calendar, include_day)
+    }
 }
 
 #[derive(Clone)]
@@ -71,6 +108,45 @@ impl From<PyInterpolationStrategy> for InterpolationStrategy {
     }
 }
 
+/// Policy for determining values outside the time domain of a timeseries
+#[derive(Clone)]
+#[pyclass(frozen)]
+#[pyo3(name = "ExtrapolationPolicy")]
+pub struct PyExtrapolationPolicy(pub ExtrapolationPolicy);
+
+#[pymethods]
+impl PyExtrapolationPolicy {
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+
+    #[staticmethod]
+    fn error() -> Self {
+        Self(ExtrapolationPolicy::Error)
+    }
+
+    #[staticmethod]
+    fn extrapolate() -> Self {
+        Self(ExtrapolationPolicy::Extrapolate)
+    }
+
+    #[staticmethod]
+    fn hold_nearest() -> Self {
+        Self(ExtrapolationPolicy::HoldNearest)
+    }
+
+    #[staticmethod]
+    fn clamp_to_range(min: FloatValue, max: FloatValue) -> PyResult<Self> {
+        if min > max {
+            return Err(PyValueError::new_err(format!(
+                "min must not be greater than max ({} > {})",
+                min, max
+            )));
+        }
+        Ok(Self(ExtrapolationPolicy::ClampToRange(min, max)))
+    }
+}
+
 #[pyclass]
 #[pyo3(name = "Timeseries")]
 pub struct PyTimeseries(pub Timeseries<FloatValue>);
@@ -112,16 +188,79 @@ impl PyTimeseries {
         if values.len() != time.len() {
             Err(PyValueError::new_err("Lengths do not match"))
         } else {
-            Ok(PyTimeseries(Timeseries::from_values(values, time)))
+            Ok(PyTimeseries(Timeseries::new(
+                values,
+                Arc::new(TimeAxis::try_from_values(time)?),
+                "".to_string(),
+                InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+            )))
         }
     }
 
+    #[staticmethod]
+    fn from_csv(csv: &str) -> RSCMResult<Self> {
+        Ok(Self(Timeseries::from_csv(csv)?))
+    }
+
+    fn to_csv(&self) -> String {
+        self.0.to_csv()
+    }
+
     fn __repr__(&self) -> String {
-        format!("<Timeseries len={}>", self.0.len())
+        format!("<Timeseries {}>", self.0.summary())
+    }
+
+    /// A single line description of the timeseries
+    fn summary(&self) -> String {
+        self.0.summary()
+    }
+
+    fn set(&mut self, time_index: usize, value: FloatValue) -> RSCMResult<()> {
+        self.0.try_set(time_index, value)
+    }
+
+    /// Set a single value using an index or a contiguous range of values using a slice
+    ///
+    /// A slice can be assigned either an array of the same length or a single value.
+    fn __setitem__(&mut self, key: Bound<PyAny>, value: Bound<PyAny>) -> PyResult<()> {
+        let len = self.0.len() as isize;
+
+        if let Ok(slice) = key.downcast::<PySlice>() {
+            let indices = slice.indices(len as std::os::raw::c_long)?;
+            if indices.step != 1 {
+                return Err(PyValueError::new_err(
+                    "Only contiguous slices can be assigned",
+                ));
+            }
+            let values = match value.extract::<FloatValue>() {
+                Ok(value) => Array1::from_elem(indices.slicelength as usize, value),
+                Err(_) => value
+                    .extract::<PyReadonlyArray1<FloatValue>>()?
+                    .to_owned_array(),
+            };
+            if values.len() != indices.slicelength as usize {
+                return Err(PyValueError::new_err(format!(
+                    "Cannot assign {} values to a slice of length {}",
+                    values.len(),
+                    indices.slicelength
+                )));
+            }
+            return Ok(self.0.set_range(indices.start as usize, values.view())?);
+        }
+
+        let index = key.extract::<isize>()?;
+        let index = if index < 0 { index + len } else { index };
+        if !(0..len).contains(&index) {
+            return Err(PyIndexError::new_err("Timeseries index out of range"));
+        }
+        Ok(self.0.try_set(index as usize, value.extract()?)?)
     }
 
-    fn set(&mut self, time_index: usize, value: FloatValue) {
-        self.0.set(time_index, value)
+    /// Copy the values of another timeseries with the same time axis
+    ///
+    /// The values are converted into the units of this timeseries.
+    fn assign_from(&mut self, other: &Self) -> RSCMResult<()> {
+        self.0.assign_from(&other.0)
     }
 
     fn values<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<FloatValue>> {
@@ -148,8 +287,16 @@ impl PyTimeseries {
     }
 
     // TODO: Figure out how to return a mutable ref to self to enable chaining
-    fn with_interpolation_strategy(&mut self, interpolation_strategy: PyInterpolationStrategy) {
-        let interpolation_strategy: InterpolationStrategy = interpolation_strategy.into();
+    #[pyo3(signature = (interpolation_strategy, extrapolation=None))]
+    fn with_interpolation_strategy(
+        &mut self,
+        interpolation_strategy: PyInterpolationStrategy,
+        extrapolation: Option<PyExtrapolationPolicy>,
+    ) {
+        let mut interpolation_strategy: InterpolationStrategy = interpolation_strategy.into();
+        if let Some(extrapolation) = extrapolation {
+            interpolation_strategy = interpolation_strategy.with_extrapolation(extrapolation.0);
+        }
 
         self.0.with_interpolation_strategy(interpolation_strategy);
     }
@@ -165,6 +312,41 @@ impl PyTimeseries {
     fn at_time(&self, time: Time) -> RSCMResult<FloatValue> {
         self.0.at_time(time)
     }
+
+    fn slice(&self, start: Time, end: Time) -> RSCMResult<Self> {
+        Ok(Self(self.0.slice(start, end)?))
+    }
+
+    #[pyo3(signature = (unit, contexts=vec![]))]
+    fn convert_unit(&self, unit: &str, contexts: Vec<String>) -> RSCMResult<Self> {
+        Ok(Self(self.0.convert_unit_with_contexts(unit, &contexts)?))
+    }
+
+    #[pyo3(signature = (time_axis, strategy=DisaggregationStrategy::Interpolate))]
+    fn disaggregate_into(
+        &self,
+        time_axis: Bound<PyTimeAxis>,
+        strategy: DisaggregationStrategy,
+    ) -> Self {
+        Self(
+            self.0
+                .clone()
+                .disaggregate_into(time_axis.borrow().0.clone(), strategy),
+        )
+    }
+
+    #[pyo3(signature = (time_axis, method=RegridMethod::Interpolate))]
+    fn regrid(&self, time_axis: Bound<PyTimeAxis>, method: RegridMethod) -> RSCMResult<Self> {
+        Ok(Self(self.0.regrid(time_axis.borrow().0.clone(), method)?))
+    }
+
+    fn integrate(&self) -> Self {
+        Self(self.0.integrate())
+    }
+
+    fn differentiate(&self) -> RSCMResult<Self> {
+        Ok(Self(self.0.differentiate()?))
+    }
 }
 
 impl From<PyTimeseries> for Timeseries<FloatValue> {
@@ -172,3 +354,168 @@ impl From<PyTimeseries> for Timeseries<FloatValue> {
         value.0
     }
 }
+
+/// Convert the data for a single exogenous variable into a timeseries
+///
+/// `values` is either a Timeseries, an array of values at `times`, a pandas Series or
+/// DataFrame with a time index or an scmdata run containing a single timeseries.
+/// `unit` is the units of an array or pandas object and must be provided for these,
+/// while a Timeseries or scmdata run is converted into `unit`.
+pub(crate) fn exogenous_timeseries(
+    name: &str,
+    values: &Bound<PyAny>,
+    times: Option<&Bound<PyAny>>,
+    unit: Option<&str>,
+) -> PyResult<Timeseries<FloatValue>> {
+    let require_unit = || {
+        unit.ok_or_else(|| {
+            PyTypeError::new_err(format!(
+                "unit must be provided for exogenous data of type {}",
+                values.get_type()
+            ))
+        })
+    };
+
+    if let Ok(timeseries) = values.downcast::<PyTimeseries>() {
+        if times.is_some() {
+            return Err(PyTypeError::new_err(
+                "times can't be provided with a Timeseries",
+            ));
+        }
+        let timeseries = timeseries.borrow().0.clone();
+        return match unit {
+            Some(unit) => Ok(timeseries.convert_unit(unit)?),
+            None => Ok(timeseries),
+        };
+    }
+
+    if let Some(times) = times {
+        return timeseries_from_arrays(times, values, require_unit()?);
+    }
+
+    if values.hasattr("get_unique_meta")? {
+        // scmdata run
+        let count: usize = values.getattr("shape")?.get_item(0)?.extract()?;
+        if count != 1 {
+            return Err(PyValueError::new_err(format!(
+                "Expected a single timeseries for {}, got {}",
+                name, count
+            )));
+        }
+        let kwargs = [("no_duplicates", true)].into_py_dict_bound(values.py());
+        let run_unit: String = values
+            .call_method("get_unique_meta", ("unit",), Some(&kwargs))?
+            .extract()?;
+        let dates = values
+            .py()
+            .import_bound("pandas")?
+            .getattr("DatetimeIndex")?
+            .call1((values.getattr("time_points")?.getattr("values")?,))?;
+        let timeseries =
+            timeseries_from_arrays(&dates, &values.getattr("values")?.get_item(0)?, &run_unit)?;
+        return match unit {
+            Some(unit) => Ok(timeseries.convert_unit(unit)?),
+            None => Ok(timeseries),
+        };
+    }
+
+    let values = match values.hasattr("columns")? {
+        // pandas DataFrame with a time index
+        true => {
+            let columns: Vec<String> = values.getattr("columns")?.extract()?;
+            match columns.len() {
+                _ if columns.iter().any(|column| column == name) => values.get_item(name)?,
+                1 => values.get_item(&columns[0])?,
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "Expected a column named {} or a single column, got {:?}",
+                        name, columns
+                    )))
+                }
+            }
+        }
+        false => values.clone(),
+    };
+
+    if values.hasattr("index")? && values.hasattr("to_numpy")? {
+        // pandas Series with a time index
+        return timeseries_from_arrays(
+            &values.getattr("index")?,
+            &values.call_method0("to_numpy")?,
+            require_unit()?,
+        );
+    }
+
+    Err(PyTypeError::new_err(format!(
+        "times must be provided for exogenous data of type {}",
+        values.get_type()
+    )))
+}
+
+/// Create a linearly interpolated timeseries from arrays of times and values
+fn timeseries_from_arrays(
+    times: &Bound<PyAny>,
+    values: &Bound<PyAny>,
+    unit: &str,
+) -> PyResult<Timeseries<FloatValue>> {
+    let values = float_array(values)?;
+    let times = decimal_years(times)?;
+    if values.len() != times.len() {
+        return Err(PyValueError::new_err("Lengths do not match"));
+    }
+    Ok(Timeseries::new(
+        values,
+        Arc::new(TimeAxis::try_from_values(times)?),
+        unit.to_string(),
+        InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+    ))
+}
+
+/// Times as decimal years
+///
+/// Dates, e.g. a pandas DatetimeIndex, are converted using the proleptic Gregorian calendar.
+fn decimal_years(times: &Bound<PyAny>) -> PyResult<Array1<Time>> {
+    // numpy datetime64 values, e.g. the time coordinate of an xarray Dataset
+    let is_datetime64 = times.hasattr("dtype")?
+        && times
+            .getattr("dtype")?
+            .getattr("kind")?
+            .extract::<String>()?
+            == "M";
+    if is_datetime64 {
+        let dates = times
+            .py()
+            .import_bound("pandas")?
+            .getattr("DatetimeIndex")?
+            .call1((times,))?;
+        return decimal_years(&dates);
+    }
+    if !times.hasattr("dayofyear")? {
+        return float_array(times);
+    }
+    let years = float_array(&times.getattr("year")?)?;
+    let months = float_array(&times.getattr("month")?)?;
+    let days = float_array(&times.getattr("day")?)?;
+    Ok(years
+        .iter()
+        .zip(months.iter())
+        .zip(days.iter())
+        .map(|((year, month), day)| {
+            Date::new(*year as i32, *month as u32, *day as u32)
+                .to_decimal_year(Calendar::ProlepticGregorian)
+        })
+        .collect())
+}
+
+/// Convert an array-like object, e.g. a list or pandas Index, into an array of floats
+fn float_array(values: &Bound<PyAny>) -> PyResult<Array1<FloatValue>> {
+    let kwargs = [("dtype", "float64")].into_py_dict_bound(values.py());
+    let array =
+        values
+            .py()
+            .import_bound("numpy")?
+            .call_method("asarray", (values,), Some(&kwargs))?;
+    Ok(array
+        .extract::<PyReadonlyArray1<FloatValue>>()?
+        .to_owned_array())
+}
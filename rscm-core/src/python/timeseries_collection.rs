@@ -1,7 +1,11 @@
+use crate::archive::{load_collection, save_collection, Compression, Precision, WriteOptions};
+use crate::errors::RSCMResult;
 use crate::python::timeseries::PyTimeseries;
-use crate::timeseries_collection::TimeseriesCollection;
 pub use crate::timeseries_collection::VariableType;
+use crate::timeseries_collection::{RunKey, TimeseriesCollection, TimeseriesItem};
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::collections::BTreeMap;
 
 #[pyclass]
 #[pyo3(name = "TimeseriesCollection")]
@@ -19,32 +23,187 @@ impl PyTimeseriesCollection {
         format!("<TimeseriesCollection names={:?}>", names)
     }
 
+    /// Description of the collection with a line per timeseries
+    fn summary(&self) -> String {
+        self.0.summary()
+    }
+
+    fn _repr_html_(&self) -> String {
+        self.0.to_html()
+    }
+
+    /// Convert to a pandas DataFrame with a row per timeseries
+    ///
+    /// Requires pandas to be installed.
+    pub fn to_pandas(self_: Bound<'_, Self>) -> PyResult<PyObject> {
+        let to_pandas = self_
+            .py()
+            .import_bound("rscm.runner")?
+            .getattr("_to_pandas")?;
+        Ok(to_pandas.call1((self_,))?.unbind())
+    }
+
+    /// Add a timeseries to the collection
+    ///
+    /// Raises a `ValueError` if the timeseries already exists, unless `overwrite` is true.
+    #[pyo3(signature = (name, timeseries, variable_type, run=None, overwrite=false))]
     pub fn add_timeseries(
         &mut self,
         name: String,
         timeseries: Bound<PyTimeseries>,
         variable_type: VariableType,
-    ) {
-        let timeseries = timeseries.borrow().0.clone();
-        self.0.add_timeseries(name, timeseries, variable_type);
+        run: Option<(String, String)>,
+        overwrite: bool,
+    ) -> PyResult<()> {
+        let item = TimeseriesItem {
+            timeseries: timeseries.borrow().0.clone(),
+            name,
+            variable_type,
+            run: run.map(|(run_id, scenario)| RunKey::new(&run_id, &scenario)),
+        };
+
+        match overwrite {
+            true => {
+                self.0.upsert_item(item);
+                Ok(())
+            }
+            false => Ok(self.0.add_item(item)?),
+        }
     }
 
-    pub fn get_timeseries_by_name(&self, name: &str) -> Option<PyTimeseries> {
-        match self.0.get_timeseries_by_name(name) {
-            // We must clone the result because we cannot return references to rust owned data
-            Some(ts) => Option::from(PyTimeseries(ts.clone())),
-            None => Option::None,
+    #[pyo3(signature = (name, run=None))]
+    pub fn get_timeseries_by_name(
+        &self,
+        name: &str,
+        run: Option<(String, String)>,
+    ) -> Option<PyTimeseries> {
+        let item = match run {
+            Some((run_id, scenario)) => self
+                .0
+                .get_by_name_and_run(name, &RunKey::new(&run_id, &scenario)),
+            None => self.0.get_by_name(name),
+        };
+        // We must clone the result because we cannot return references to rust owned data
+        item.map(|item| PyTimeseries(item.timeseries.clone()))
+    }
+
+    /// Create a copy of the collection where the timeseries belong to a run
+    pub fn with_run(&self, run_id: &str, scenario: &str) -> Self {
+        Self(self.0.clone().with_run(RunKey::new(run_id, scenario)))
+    }
+
+    /// Copy the timeseries from another collection into this collection
+    ///
+    /// Raises a `ValueError` if a timeseries already exists, unless `overwrite` is true.
+    #[pyo3(signature = (other, overwrite=false))]
+    pub fn merge(&mut self, other: Bound<PyTimeseriesCollection>, overwrite: bool) -> PyResult<()> {
+        let other = other.borrow().0.clone();
+        match overwrite {
+            true => {
+                other.into_iter().for_each(|item| {
+                    self.0.upsert_item(item);
+                });
+                Ok(())
+            }
+            false => {
+                self.0.merge(other)?;
+                Ok(())
+            }
         }
     }
 
+    /// Get the unique (run_id, scenario) pairs in the collection
+    pub fn runs(&self) -> Vec<(String, String)> {
+        self.0
+            .runs()
+            .into_iter()
+            .map(|run| (run.run_id, run.scenario))
+            .collect()
+    }
+
     pub fn names(&self) -> Vec<String> {
         self.0.iter().map(|x| x.name.clone()).collect()
     }
 
+    /// Create a new collection with only the timeseries whose names match `pattern`
+    pub fn filter(&self, pattern: &str) -> Self {
+        Self(self.0.filter(pattern))
+    }
+
+    /// Remove the timeseries with a given name for every run
+    pub fn remove(&mut self, name: &str) -> RSCMResult<()> {
+        self.0.remove(name).map(|_| ())
+    }
+
+    /// Rename the timeseries with a given name for every run
+    pub fn rename(&mut self, name: &str, new_name: &str) -> RSCMResult<()> {
+        self.0.rename(name, new_name)
+    }
+
+    /// Get the (name, units, variable_type, run) of each timeseries
+    #[allow(clippy::type_complexity)]
+    pub fn metadata(&self) -> Vec<(String, String, VariableType, Option<(String, String)>)> {
+        self.0
+            .metadata()
+            .into_iter()
+            .map(|meta| {
+                (
+                    meta.name,
+                    meta.units,
+                    meta.variable_type,
+                    meta.run.map(|run| (run.run_id, run.scenario)),
+                )
+            })
+            .collect()
+    }
+
     pub fn timeseries(&self) -> Vec<PyTimeseries> {
         self.0
             .iter()
             .map(|x| PyTimeseries(x.timeseries.clone()))
             .collect()
     }
+
+    /// Convert variables into preferred units
+    #[pyo3(signature = (units, contexts=vec![]))]
+    pub fn convert_units(
+        &self,
+        units: BTreeMap<String, String>,
+        contexts: Vec<String>,
+    ) -> RSCMResult<Self> {
+        Ok(Self(self.0.convert_units(&units, &contexts)?))
+    }
+
+    /// Write the collection as an archive
+    #[pyo3(signature = (
+        precision=Precision::Float64,
+        units=BTreeMap::new(),
+        contexts=vec![],
+        compression=Compression::Uncompressed,
+    ))]
+    pub fn save<'py>(
+        &self,
+        py: Python<'py>,
+        precision: Precision,
+        units: BTreeMap<String, String>,
+        contexts: Vec<String>,
+        compression: Compression,
+    ) -> RSCMResult<Bound<'py, PyBytes>> {
+        let archive = save_collection(
+            &self.0,
+            &WriteOptions {
+                precision,
+                compression,
+                units,
+                unit_contexts: contexts,
+            },
+        )?;
+        Ok(PyBytes::new_bound(py, &archive))
+    }
+
+    /// Read a collection written by `save`
+    #[staticmethod]
+    pub fn load(archive: &[u8]) -> RSCMResult<Self> {
+        Ok(Self(load_collection(archive)?))
+    }
 }
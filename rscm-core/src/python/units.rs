@@ -0,0 +1,22 @@
+use crate::timeseries::FloatValue;
+use crate::units;
+use pyo3::prelude::*;
+
+/// Register a unit in the global unit registry
+#[pyfunction]
+pub fn register_unit(unit: &str, dimension: &str, scale: FloatValue) {
+    units::register_unit(unit, dimension, scale)
+}
+
+/// Register a conversion between two dimensions in the global unit registry
+#[pyfunction]
+pub fn register_context(context: &str, from_: &str, to: &str, factor: FloatValue) {
+    units::register_context(context, from_, to, factor)
+}
+
+/// Factor to convert between two units using the global unit registry
+#[pyfunction]
+#[pyo3(signature = (from_, to, contexts=vec![]))]
+pub fn conversion_factor(from_: &str, to: &str, contexts: Vec<String>) -> PyResult<FloatValue> {
+    Ok(units::conversion_factor(from_, to, &contexts)?)
+}
@@ -0,0 +1,260 @@
+//! Rename the variables of a component
+//!
+//! The variables solved by a model must be unique,
+//! so two instances of the same type of component can't be added to a model directly.
+//! Wrapping a component in a [`RemappedComponent`] maps the variables used by the component
+//! onto different variables in the model, for example,
+//! a carbon box component can be used for both the land and ocean by mapping its
+//! "Carbon Uptake" output onto "Carbon Uptake|Land" and "Carbon Uptake|Ocean".
+//!
+//! [`RemappedComponent::with_namespace`] prefixes all of the outputs of a component,
+//! e.g. "Land|Carbon Uptake", so that generic components can be reused for different reservoirs
+//! without listing each of their variables.
+use crate::component::{
+    Component, InputState, OutputState, RequirementDefinition, TimestepContext,
+};
+use crate::cost::CostHint;
+use crate::dual::DualState;
+use crate::errors::RSCMResult;
+use crate::timeseries::Time;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// A component with some of its variables renamed
+///
+/// Variables which aren't mapped keep their names.
+/// The state passed to the wrapped component uses its own variable names,
+/// so the component doesn't need to know that it has been remapped.
+///
+/// The input state is extracted using the default [`Component::extract_state`],
+/// so any custom extraction by the wrapped component is not used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemappedComponent {
+    component: Arc<dyn Component>,
+    /// Name of the variable in the component to the name in the model
+    variables: BTreeMap<String, String>,
+}
+
+impl RemappedComponent {
+    pub fn new(component: Arc<dyn Component>) -> Self {
+        Self {
+            component,
+            variables: BTreeMap::new(),
+        }
+    }
+
+    /// Map a variable of the component onto a different variable in the model
+    pub fn with_variable(&mut self, component_name: &str, model_name: &str) -> &mut Self {
+        self.variables
+            .insert(component_name.to_string(), model_name.to_string());
+        self
+    }
+
+    /// Prefix the names of all of the component's outputs with `prefix|`
+    ///
+    /// Inputs are unchanged, unless they are also outputs,
+    /// so that the component can still use variables solved by other components.
+    /// Inputs can be mapped separately using [`RemappedComponent::with_variable`].
+    pub fn with_namespace(&mut self, prefix: &str) -> &mut Self {
+        self.component.output_names().iter().for_each(|name| {
+            self.variables
+                .insert(name.clone(), format!("{}|{}", prefix, name));
+        });
+        self
+    }
+
+    /// The wrapped component
+    pub fn component(&self) -> &Arc<dyn Component> {
+        &self.component
+    }
+
+    /// Name of a variable in the model
+    fn to_model(&self, name: &str) -> String {
+        self.variables
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// Name of a variable in the component
+    fn to_component(&self, name: &str) -> String {
+        self.variables
+            .iter()
+            .find(|(_, model_name)| *model_name == name)
+            .map(|(component_name, _)| component_name.clone())
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    fn rename(&self, state: &InputState, rename: impl Fn(&str) -> String) -> InputState {
+        let (values, names) = state
+            .iter()
+            .map(|(name, value)| (*value, rename(name)))
+            .unzip();
+        InputState::from_vectors(values, names)
+    }
+}
+
+#[typetag::serde]
+impl Component for RemappedComponent {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        // Labelled variables are renamed per label
+        self.component
+            .variables()
+            .into_iter()
+            .map(|definition| RequirementDefinition {
+                name: self.to_model(&definition.name),
+                ..definition
+            })
+            .collect()
+    }
+
+    fn initialise(&self, t0: Time) -> OutputState {
+        self.rename(&self.component.initialise(t0), |name| self.to_model(name))
+    }
+
+    fn solve(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let input_state = self.rename(input_state, |name| self.to_component(name));
+        let output_state = self.component.solve(t_current, t_next, &input_state)?;
+        Ok(self.rename(&output_state, |name| self.to_model(name)))
+    }
+
+    fn solve_with_context(
+        &self,
+        context: &mut TimestepContext,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let input_state = self.rename(input_state, |name| self.to_component(name));
+        let output_state = self.component.solve_with_context(context, &input_state)?;
+        Ok(self.rename(&output_state, |name| self.to_model(name)))
+    }
+
+    fn cost_hint(&self) -> CostHint {
+        self.component.cost_hint()
+    }
+
+    fn solve_dual(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &DualState,
+        parameter: &str,
+    ) -> Option<RSCMResult<DualState>> {
+        let input_state = input_state
+            .iter()
+            .map(|(name, value)| (self.to_component(name), *value))
+            .collect();
+        let output_state = self
+            .component
+            .solve_dual(t_current, t_next, &input_state, parameter)?;
+        Some(output_state.map(|state| {
+            state
+                .into_iter()
+                .map(|(name, value)| (self.to_model(&name), value))
+                .collect()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::State;
+    use crate::example_components::{
+        TestComponent, TestComponentParameters, TestEquilibriumComponent,
+    };
+    use crate::model::ModelBuilder;
+    use crate::provenance::component_parameters_from_toml;
+    use crate::timeseries::{TimeAxis, Timeseries};
+    use numpy::array;
+    use numpy::ndarray::Array;
+
+    fn component(p: f64) -> Arc<dyn Component> {
+        Arc::new(TestComponent::from_parameters(TestComponentParameters {
+            p,
+        }))
+    }
+
+    #[test]
+    fn solve() {
+        let mut remapped = RemappedComponent::new(component(0.5));
+        remapped.with_variable("Concentrations|CO2", "Concentrations|CO2|Land");
+
+        assert_eq!(remapped.input_names(), vec!["Emissions|CO2"]);
+        assert_eq!(remapped.output_names(), vec!["Concentrations|CO2|Land"]);
+
+        let input_state = InputState::from_vectors(vec![2.0], remapped.input_names());
+        let output_state = remapped.solve(2020.0, 2021.0, &input_state).unwrap();
+        assert_eq!(*output_state.get("Concentrations|CO2|Land"), 1.0);
+    }
+
+    #[test]
+    fn namespace() {
+        let mut remapped = RemappedComponent::new(component(0.5));
+        remapped
+            .with_namespace("Land")
+            .with_variable("Emissions|CO2", "Land|Emissions|CO2");
+
+        assert_eq!(remapped.input_names(), vec!["Land|Emissions|CO2"]);
+        assert_eq!(remapped.output_names(), vec!["Land|Concentrations|CO2"]);
+    }
+
+    #[test]
+    fn initialise() {
+        let mut remapped =
+            RemappedComponent::new(Arc::new(TestEquilibriumComponent { equilibrium: 2.0 }));
+        remapped.with_namespace("Ocean");
+        assert_eq!(*remapped.initialise(2020.0).get("Ocean|Level"), 2.0);
+
+        // The initial value calculated by the component is used by the model
+        let model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_component(Arc::new(remapped))
+            .build();
+        let level = model
+            .timeseries()
+            .get_timeseries_by_name("Ocean|Level")
+            .unwrap();
+        assert_eq!(level.at(0), Some(2.0));
+    }
+
+    #[test]
+    fn multiple_instances() {
+        let mut land = RemappedComponent::new(component(0.5));
+        land.with_variable("Concentrations|CO2", "Concentrations|CO2|Land");
+        let mut ocean = RemappedComponent::new(component(0.1));
+        ocean
+            .with_variable("Emissions|CO2", "Emissions|CO2|Ocean")
+            .with_variable("Concentrations|CO2", "Concentrations|CO2|Ocean");
+
+        let emissions = Timeseries::from_values(array![10.0, 10.0], array![2020.0, 2025.0]);
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_named_component("land", Arc::new(land))
+            .with_named_component("ocean", Arc::new(ocean))
+            .with_exogenous_variable("Emissions|CO2", emissions.clone())
+            .with_exogenous_variable("Emissions|CO2|Ocean", emissions)
+            .build();
+        model.run();
+
+        let value = |name: &str| {
+            model
+                .timeseries()
+                .get_timeseries_by_name(name)
+                .unwrap()
+                .at(1)
+                .unwrap()
+        };
+        assert_eq!(value("Concentrations|CO2|Land"), 10.0 * 0.5);
+        assert_eq!(value("Concentrations|CO2|Ocean"), 10.0 * 0.1);
+
+        let serialised = toml::to_string(&model).unwrap();
+        let parameters = component_parameters_from_toml(&serialised, "ocean").unwrap();
+        assert_eq!(parameters.get("p").unwrap().as_float(), Some(0.1));
+    }
+}
@@ -0,0 +1,575 @@
+//! Accumulating the results of many runs in a single file
+//!
+//! A [`RunStore`] is an append-only store of [`RunRecord`]s.
+//! Each record holds the timeseries, parameters and provenance of a run.
+//! [`RunQuery`] selects records by scenario, variable and parameter range.
+//!
+//! [`RunDatabase`] appends records to a TOML file as array of tables entries
+//! so the file is always a valid TOML document and existing records are never rewritten.
+//! Every record is read to answer a query.
+//! With the `sqlite` feature, [`SqliteRunDatabase`] stores the records in an SQLite database
+//! which is indexed by scenario, variable and parameter,
+//! so only the matching runs and the requested variables are read.
+use crate::calibration::ParameterSet;
+use crate::errors::{RSCMError, RSCMResult};
+use crate::metadata::RunMetadata;
+use crate::model::Model;
+use crate::timeseries::FloatValue;
+use crate::timeseries_collection::{RunKey, TimeseriesCollection};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The results of a run along with how it was produced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub run: RunKey,
+    /// Parameters used to build the model
+    #[serde(default)]
+    pub parameters: ParameterSet,
+    /// Scenario, versions and timing of the run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<RunMetadata>,
+    /// The serialised model
+    ///
+    /// See [`crate::provenance`] for recovering the parameters of components.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<String>,
+    pub timeseries: TimeseriesCollection,
+}
+
+impl RunRecord {
+    /// Record a model after it has been run
+    ///
+    /// The model is serialised as the provenance of the run
+    /// and its metadata is stored with the record.
+    pub fn from_model(run: RunKey, parameters: ParameterSet, model: &Model) -> RSCMResult<Self> {
+        let provenance = toml::to_string(model)
+            .map_err(|e| RSCMError::Error(format!("Could not serialise model: {}", e)))?;
+
+        Ok(Self {
+            run,
+            parameters,
+            metadata: Some(model.metadata().clone()),
+            provenance: Some(provenance),
+            timeseries: model.timeseries().clone(),
+        })
+    }
+}
+
+/// Criteria for selecting runs from a [`RunDatabase`]
+///
+/// An empty query matches every run.
+#[derive(Debug, Clone, Default)]
+pub struct RunQuery {
+    scenario: Option<String>,
+    variables: Vec<String>,
+    parameter_ranges: Vec<(String, FloatValue, FloatValue)>,
+}
+
+impl RunQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only select runs of a scenario
+    pub fn with_scenario(&mut self, scenario: &str) -> &mut Self {
+        self.scenario = Some(scenario.to_string());
+        self
+    }
+
+    /// Only select runs which include a variable
+    ///
+    /// The timeseries of selected runs are limited to the requested variables.
+    pub fn with_variable(&mut self, variable: &str) -> &mut Self {
+        self.variables.push(variable.to_string());
+        self
+    }
+
+    /// Only select runs where a parameter is within `[min, max]`
+    ///
+    /// Runs without the parameter aren't selected.
+    pub fn with_parameter_range(
+        &mut self,
+        name: &str,
+        min: FloatValue,
+        max: FloatValue,
+    ) -> &mut Self {
+        self.parameter_ranges.push((name.to_string(), min, max));
+        self
+    }
+
+    fn matches(&self, record: &RunRecord) -> bool {
+        let scenario = self
+            .scenario
+            .as_ref()
+            .is_none_or(|scenario| &record.run.scenario == scenario);
+        let variables = self
+            .variables
+            .iter()
+            .all(|variable| record.timeseries.get_by_name(variable).is_some());
+        let parameters = self.parameter_ranges.iter().all(|(name, min, max)| {
+            record
+                .parameters
+                .get(name)
+                .is_some_and(|value| (min..=max).contains(&value))
+        });
+
+        scenario && variables && parameters
+    }
+
+    fn select(&self, record: RunRecord) -> RunRecord {
+        if self.variables.is_empty() {
+            return record;
+        }
+        let mut timeseries = TimeseriesCollection::new();
+        record
+            .timeseries
+            .into_iter()
+            .filter(|item| self.variables.contains(&item.name))
+            .for_each(|item| {
+                timeseries.upsert_item(item);
+            });
+
+        RunRecord {
+            timeseries,
+            ..record
+        }
+    }
+}
+
+/// An append-only store of runs
+///
+/// Records are returned in the order that they were appended.
+/// Stores don't check that run keys are unique.
+pub trait RunStore {
+    /// Append a run to the end of the store
+    fn append(&self, record: &RunRecord) -> RSCMResult<()>;
+
+    /// Runs which match a query
+    fn query(&self, query: &RunQuery) -> RSCMResult<Vec<RunRecord>>;
+
+    /// Every run in the store
+    fn runs(&self) -> RSCMResult<Vec<RunRecord>> {
+        self.query(&RunQuery::new())
+    }
+
+    /// Timeseries of the runs which match a query
+    ///
+    /// Each timeseries belongs to the run that it was recorded in.
+    fn collection(&self, query: &RunQuery) -> RSCMResult<TimeseriesCollection> {
+        let mut collection = TimeseriesCollection::new();
+        for record in self.query(query)? {
+            collection.merge(record.timeseries.with_run(record.run))?;
+        }
+        Ok(collection)
+    }
+}
+
+/// File containing every record written by [`RunDatabase::append`]
+#[derive(Serialize, Deserialize)]
+struct Records {
+    #[serde(default)]
+    runs: Vec<RunRecord>,
+}
+
+/// An append-only store of runs in a single TOML file
+#[derive(Debug, Clone)]
+pub struct RunDatabase {
+    path: PathBuf,
+}
+
+impl RunDatabase {
+    /// Open a database, which is created when the first run is appended
+    pub fn open(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every record in the file
+    fn read(&self) -> RSCMResult<Vec<RunRecord>> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+        let serialised = std::fs::read_to_string(&self.path).map_err(|e| {
+            RSCMError::Error(format!("Could not read {}: {}", self.path.display(), e))
+        })?;
+
+        toml::from_str::<Records>(&serialised)
+            .map(|records| records.runs)
+            .map_err(|e| {
+                RSCMError::Error(format!("Invalid database {}: {}", self.path.display(), e))
+            })
+    }
+}
+
+impl RunStore for RunDatabase {
+    fn append(&self, record: &RunRecord) -> RSCMResult<()> {
+        let serialised = toml::to_string(&Records {
+            runs: vec![record.clone()],
+        })
+        .map_err(|e| RSCMError::Error(format!("Could not serialise run: {}", e)))?;
+
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(format!("\n{}", serialised).as_bytes()))
+            .map_err(|e| {
+                RSCMError::Error(format!("Could not write {}: {}", self.path.display(), e))
+            })
+    }
+
+    fn query(&self, query: &RunQuery) -> RSCMResult<Vec<RunRecord>> {
+        Ok(self
+            .read()?
+            .into_iter()
+            .filter(|record| query.matches(record))
+            .map(|record| query.select(record))
+            .collect())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite_error(err: rusqlite::Error) -> RSCMError {
+    RSCMError::Error(format!("SQLite error: {}", err))
+}
+
+#[cfg(feature = "sqlite")]
+fn to_toml<T: Serialize>(value: &T) -> RSCMResult<String> {
+    toml::to_string(value).map_err(|e| RSCMError::Error(format!("Could not serialise run: {}", e)))
+}
+
+#[cfg(feature = "sqlite")]
+fn from_toml<T: serde::de::DeserializeOwned>(value: &str) -> RSCMResult<T> {
+    toml::from_str(value).map_err(|e| RSCMError::Error(format!("Invalid run in database: {}", e)))
+}
+
+/// An append-only store of runs in an SQLite database
+///
+/// Each run is a row of the `runs` table, with its parameters in the `parameters` table
+/// and each of its timeseries serialised as TOML in the `timeseries` table.
+/// Queries are evaluated by SQLite so only the matching runs are read.
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct SqliteRunDatabase {
+    connection: rusqlite::Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteRunDatabase {
+    const SCHEMA: &'static str = "
+        CREATE TABLE IF NOT EXISTS runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id TEXT NOT NULL,
+            scenario TEXT NOT NULL,
+            metadata TEXT,
+            provenance TEXT
+        );
+        CREATE INDEX IF NOT EXISTS runs_scenario ON runs (scenario);
+        CREATE TABLE IF NOT EXISTS parameters (
+            run INTEGER NOT NULL REFERENCES runs (id),
+            name TEXT NOT NULL,
+            value REAL NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS parameters_name ON parameters (name, value);
+        CREATE TABLE IF NOT EXISTS timeseries (
+            run INTEGER NOT NULL REFERENCES runs (id),
+            name TEXT NOT NULL,
+            item TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS timeseries_run ON timeseries (run, name);
+    ";
+
+    /// Open a database, creating it if it doesn't exist
+    pub fn open(path: impl AsRef<Path>) -> RSCMResult<Self> {
+        Self::from_connection(rusqlite::Connection::open(path).map_err(sqlite_error)?)
+    }
+
+    /// Open a temporary database which is only kept in memory
+    pub fn open_in_memory() -> RSCMResult<Self> {
+        Self::from_connection(rusqlite::Connection::open_in_memory().map_err(sqlite_error)?)
+    }
+
+    fn from_connection(connection: rusqlite::Connection) -> RSCMResult<Self> {
+        connection
+            .execute_batch(Self::SCHEMA)
+            .map_err(sqlite_error)?;
+        Ok(Self { connection })
+    }
+
+    /// Read a run, only including the timeseries of `variables` if any are given
+    fn read(&self, id: i64, run: RunKey, variables: &[String]) -> RSCMResult<RunRecord> {
+        let (metadata, provenance): (Option<String>, Option<String>) = self
+            .connection
+            .query_row(
+                "SELECT metadata, provenance FROM runs WHERE id = ?1",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(sqlite_error)?;
+
+        let mut statement = self
+            .connection
+            .prepare_cached("SELECT name, value FROM parameters WHERE run = ?1")
+            .map_err(sqlite_error)?;
+        let parameters = statement
+            .query_map([id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .and_then(|rows| rows.collect::<Result<ParameterSet, _>>())
+            .map_err(sqlite_error)?;
+
+        let mut statement = self
+            .connection
+            .prepare_cached("SELECT name, item FROM timeseries WHERE run = ?1 ORDER BY rowid")
+            .map_err(sqlite_error)?;
+        let items = statement
+            .query_map([id], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))
+            .and_then(|rows| rows.collect::<Result<Vec<(String, String)>, _>>())
+            .map_err(sqlite_error)?;
+        let mut timeseries = TimeseriesCollection::new();
+        for (name, item) in items {
+            if variables.is_empty() || variables.contains(&name) {
+                timeseries.upsert_item(from_toml(&item)?);
+            }
+        }
+
+        Ok(RunRecord {
+            run,
+            parameters,
+            metadata: metadata.as_deref().map(from_toml).transpose()?,
+            provenance,
+            timeseries,
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl RunStore for SqliteRunDatabase {
+    fn append(&self, record: &RunRecord) -> RSCMResult<()> {
+        let metadata = record.metadata.as_ref().map(to_toml).transpose()?;
+        let items = record
+            .timeseries
+            .iter()
+            .map(|item| Ok((item.name.as_str(), to_toml(item)?)))
+            .collect::<RSCMResult<Vec<_>>>()?;
+
+        // The run is only visible once all of its rows have been written
+        let transaction = self
+            .connection
+            .unchecked_transaction()
+            .map_err(sqlite_error)?;
+        transaction
+            .execute(
+                "INSERT INTO runs (run_id, scenario, metadata, provenance) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![
+                    record.run.run_id,
+                    record.run.scenario,
+                    metadata,
+                    record.provenance
+                ],
+            )
+            .map_err(sqlite_error)?;
+        let id = transaction.last_insert_rowid();
+        for (name, value) in &record.parameters {
+            transaction
+                .execute(
+                    "INSERT INTO parameters (run, name, value) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![id, name, value],
+                )
+                .map_err(sqlite_error)?;
+        }
+        for (name, item) in items {
+            transaction
+                .execute(
+                    "INSERT INTO timeseries (run, name, item) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![id, name, item],
+                )
+                .map_err(sqlite_error)?;
+        }
+        transaction.commit().map_err(sqlite_error)
+    }
+
+    fn query(&self, query: &RunQuery) -> RSCMResult<Vec<RunRecord>> {
+        use rusqlite::types::Value;
+
+        let mut conditions = vec![];
+        let mut values: Vec<Value> = vec![];
+        if let Some(scenario) = &query.scenario {
+            values.push(Value::Text(scenario.clone()));
+            conditions.push(format!("scenario = ?{}", values.len()));
+        }
+        for variable in &query.variables {
+            values.push(Value::Text(variable.clone()));
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM timeseries WHERE run = runs.id AND name = ?{})",
+                values.len()
+            ));
+        }
+        for (name, min, max) in &query.parameter_ranges {
+            values.extend([
+                Value::Text(name.clone()),
+                Value::Real(*min),
+                Value::Real(*max),
+            ]);
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM parameters WHERE run = runs.id AND name = ?{} \
+                 AND value BETWEEN ?{} AND ?{})",
+                values.len() - 2,
+                values.len() - 1,
+                values.len()
+            ));
+        }
+        let filter = match conditions.is_empty() {
+            true => String::new(),
+            false => format!("WHERE {}", conditions.join(" AND ")),
+        };
+
+        let mut statement = self
+            .connection
+            .prepare(&format!(
+                "SELECT id, run_id, scenario FROM runs {} ORDER BY id",
+                filter
+            ))
+            .map_err(sqlite_error)?;
+        let runs = statement
+            .query_map(rusqlite::params_from_iter(values), |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    RunKey::new(&row.get::<_, String>(1)?, &row.get::<_, String>(2)?),
+                ))
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(sqlite_error)?;
+
+        runs.into_iter()
+            .map(|(id, run)| self.read(id, run, &query.variables))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_components::{TestComponent, TestComponentParameters};
+    use crate::model::ModelBuilder;
+    use crate::provenance::component_parameters_from_toml;
+    use crate::timeseries::{TimeAxis, Timeseries};
+    use numpy::array;
+    use numpy::ndarray::Array;
+    use std::sync::Arc;
+
+    fn record(run_id: &str, scenario: &str, p: FloatValue) -> RunRecord {
+        let mut model = ModelBuilder::new()
+            .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2025.0, 1.0)))
+            .with_component(Arc::new(TestComponent::from_parameters(
+                TestComponentParameters { p },
+            )))
+            .with_exogenous_variable(
+                "Emissions|CO2",
+                Timeseries::from_values(array![0.0, 10.0], array![2020.0, 2025.0]),
+            )
+            .with_scenario(scenario)
+            .build();
+        model.run();
+
+        RunRecord::from_model(
+            RunKey::new(run_id, scenario),
+            ParameterSet::from([("p".to_string(), p)]),
+            &model,
+        )
+        .unwrap()
+    }
+
+    /// Append runs to an empty store and check that they can be queried
+    fn append_and_query(database: &impl RunStore) {
+        assert!(database.runs().unwrap().is_empty());
+
+        database.append(&record("1", "ssp119", 0.5)).unwrap();
+        database.append(&record("2", "ssp585", 0.5)).unwrap();
+        database.append(&record("3", "ssp585", 2.0)).unwrap();
+
+        let runs = database.runs().unwrap();
+        let ids: Vec<_> = runs.iter().map(|r| r.run.run_id.as_str()).collect();
+        assert_eq!(ids, ["1", "2", "3"]);
+
+        let parameters: TestComponentParameters =
+            component_parameters_from_toml(runs[2].provenance.as_ref().unwrap(), "TestComponent")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert_eq!(parameters.p, 2.0);
+        let metadata = runs[2].metadata.as_ref().unwrap();
+        assert_eq!(metadata.scenario.as_deref(), Some("ssp585"));
+        assert!(metadata.wall_time.is_some());
+
+        let mut query = RunQuery::new();
+        query
+            .with_scenario("ssp585")
+            .with_parameter_range("p", 1.0, 3.0);
+        let selected = database.query(&query).unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].run, RunKey::new("3", "ssp585"));
+
+        let mut query = RunQuery::new();
+        query.with_variable("Concentrations|CO2");
+        let collection = database.collection(&query).unwrap();
+        assert_eq!(collection.runs().len(), 3);
+        assert!(collection.get_by_name("Emissions|CO2").is_none());
+        let concentrations = collection
+            .get_by_name_and_run("Concentrations|CO2", &RunKey::new("3", "ssp585"))
+            .unwrap();
+        assert_eq!(concentrations.timeseries.at(1).unwrap(), 1.3 * 2.0);
+
+        let mut query = RunQuery::new();
+        query.with_variable("Surface Temperature");
+        assert!(database.query(&query).unwrap().is_empty());
+    }
+
+    #[test]
+    fn toml_database() {
+        let path = std::env::temp_dir().join("rscm-run-database-test.toml");
+        let _ = std::fs::remove_file(&path);
+        append_and_query(&RunDatabase::open(&path));
+
+        // Records are read back from the file
+        assert_eq!(RunDatabase::open(&path).runs().unwrap().len(), 3);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_database() {
+        append_and_query(&SqliteRunDatabase::open_in_memory().unwrap());
+
+        let path = std::env::temp_dir().join("rscm-run-database-test.sqlite");
+        let _ = std::fs::remove_file(&path);
+        SqliteRunDatabase::open(&path)
+            .unwrap()
+            .append(&record("1", "ssp119", 0.5))
+            .unwrap();
+        let runs = SqliteRunDatabase::open(&path).unwrap().runs().unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(
+            runs[0].parameters,
+            ParameterSet::from([("p".to_string(), 0.5)])
+        );
+        let concentrations = |record: &RunRecord| {
+            record
+                .timeseries
+                .get_timeseries_by_name("Concentrations|CO2")
+                .unwrap()
+                .values()
+                .to_vec()
+        };
+        assert_eq!(
+            concentrations(&runs[0])[1..],
+            concentrations(&record("1", "ssp119", 0.5))[1..]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
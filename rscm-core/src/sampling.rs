@@ -0,0 +1,458 @@
+//! Sampling of parameter spaces
+//!
+//! A [`Sampler`] generates points in the unit hypercube `[0, 1)^d`
+//! which are scaled onto the ranges of each parameter using [`scale_to_ranges`].
+//! Quasi-random samplers cover the space more evenly than independent random samples
+//! so fewer ensemble members are needed to explore the same space.
+//!
+//! All samplers are seedable so that the same seed always produces the same points.
+//! Successive calls to [`Sampler::sample`] continue the sequence of quasi-random samplers.
+use crate::component::ModelRng;
+use crate::errors::{RSCMError, RSCMResult};
+use crate::timeseries::FloatValue;
+use pyo3::pyclass;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Generates points in the unit hypercube
+pub trait Sampler {
+    /// Generate `n` points with `dimensions` coordinates in `[0, 1)`
+    fn sample(&mut self, n: usize, dimensions: usize) -> RSCMResult<Vec<Vec<FloatValue>>>;
+}
+
+/// Scale a point in the unit hypercube onto a `(min, max)` range for each dimension
+pub fn scale_to_ranges(
+    point: &[FloatValue],
+    ranges: &[(FloatValue, FloatValue)],
+) -> Vec<FloatValue> {
+    assert_eq!(point.len(), ranges.len());
+    point
+        .iter()
+        .zip(ranges)
+        .map(|(x, (min, max))| min + x * (max - min))
+        .collect()
+}
+
+/// Independent uniform random samples
+#[derive(Debug, Clone)]
+pub struct RandomSampler {
+    rng: ModelRng,
+}
+
+impl RandomSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: ModelRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampler for RandomSampler {
+    fn sample(&mut self, n: usize, dimensions: usize) -> RSCMResult<Vec<Vec<FloatValue>>> {
+        Ok((0..n)
+            .map(|_| (0..dimensions).map(|_| self.rng.gen()).collect())
+            .collect())
+    }
+}
+
+/// Latin hypercube sampling
+///
+/// Each dimension is divided into `n` equal strata and each stratum contains exactly one point.
+/// Points are placed randomly within their strata.
+/// Each call to [`Sampler::sample`] generates an independent design.
+#[derive(Debug, Clone)]
+pub struct LatinHypercubeSampler {
+    rng: ModelRng,
+}
+
+impl LatinHypercubeSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: ModelRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampler for LatinHypercubeSampler {
+    fn sample(&mut self, n: usize, dimensions: usize) -> RSCMResult<Vec<Vec<FloatValue>>> {
+        let mut points = vec![vec![0.0; dimensions]; n];
+        for d in 0..dimensions {
+            let mut strata: Vec<usize> = (0..n).collect();
+            strata.shuffle(&mut self.rng);
+            for (point, stratum) in points.iter_mut().zip(strata) {
+                let offset: FloatValue = self.rng.gen();
+                point[d] = (stratum as FloatValue + offset) / n as FloatValue;
+            }
+        }
+        Ok(points)
+    }
+}
+
+/// Number of bits used for the Sobol sequence
+const SOBOL_BITS: usize = 32;
+
+/// Primitive polynomials and initial direction numbers for dimensions 2 and above
+///
+/// Each entry is `(degree, coefficients, initial direction numbers)`
+/// from the `new-joe-kuo-6.21201` set of Joe and Kuo (2008).
+const SOBOL_DIRECTIONS: [(u32, u32, &[u32]); 20] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+    (6, 19, &[1, 1, 1, 15, 7, 5]),
+    (6, 22, &[1, 3, 1, 15, 13, 25]),
+    (6, 25, &[1, 1, 5, 5, 19, 61]),
+    (7, 1, &[1, 3, 7, 11, 23, 15, 103]),
+    (7, 4, &[1, 3, 7, 13, 13, 15, 69]),
+];
+
+/// Direction numbers of a dimension of the Sobol sequence
+fn sobol_direction_numbers(dimension: usize) -> [u32; SOBOL_BITS] {
+    let mut v = [0u32; SOBOL_BITS];
+    if dimension == 0 {
+        (0..SOBOL_BITS).for_each(|i| v[i] = 1 << (SOBOL_BITS - 1 - i));
+        return v;
+    }
+
+    let (s, a, m) = SOBOL_DIRECTIONS[dimension - 1];
+    let s = s as usize;
+    for i in 0..SOBOL_BITS {
+        v[i] = match i < s {
+            true => m[i] << (SOBOL_BITS - 1 - i),
+            false => {
+                let mut value = v[i - s] ^ (v[i - s] >> s);
+                for k in 1..s {
+                    value ^= ((a >> (s - 1 - k)) & 1) * v[i - k];
+                }
+                value
+            }
+        };
+    }
+    v
+}
+
+/// Sobol low-discrepancy sequence
+///
+/// Points are generated in Gray code order starting from the origin.
+/// Up to 21 dimensions are supported.
+///
+/// If seeded, each dimension is scrambled using a random digital shift
+/// which preserves the uniformity of the sequence while avoiding points on the boundaries.
+#[derive(Debug, Clone)]
+pub struct SobolSampler {
+    index: u32,
+    state: Vec<u32>,
+    shifts: Option<ModelRng>,
+    shift: Vec<u32>,
+}
+
+impl SobolSampler {
+    pub const MAX_DIMENSIONS: usize = SOBOL_DIRECTIONS.len() + 1;
+
+    /// Unscrambled sequence
+    pub fn new() -> Self {
+        Self {
+            index: 0,
+            state: vec![],
+            shifts: None,
+            shift: vec![],
+        }
+    }
+
+    /// Sequence scrambled using a random digital shift
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            shifts: Some(ModelRng::seed_from_u64(seed)),
+            ..Self::new()
+        }
+    }
+}
+
+impl Default for SobolSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn sample(&mut self, n: usize, dimensions: usize) -> RSCMResult<Vec<Vec<FloatValue>>> {
+        if dimensions > Self::MAX_DIMENSIONS {
+            return Err(RSCMError::Error(format!(
+                "Sobol sequences support at most {} dimensions, got {}",
+                Self::MAX_DIMENSIONS,
+                dimensions
+            )));
+        }
+        if self.index > 0 && dimensions != self.state.len() {
+            return Err(RSCMError::Error(format!(
+                "Expected {} dimensions to continue the sequence, got {}",
+                self.state.len(),
+                dimensions
+            )));
+        }
+        if self.index == 0 {
+            self.state = vec![0; dimensions];
+            self.shift = match &mut self.shifts {
+                Some(rng) => (0..dimensions).map(|_| rng.gen()).collect(),
+                None => vec![0; dimensions],
+            };
+        }
+        let directions: Vec<_> = (0..dimensions).map(sobol_direction_numbers).collect();
+
+        let scale = (1u64 << SOBOL_BITS) as FloatValue;
+        let mut points = Vec::with_capacity(n);
+        for _ in 0..n {
+            points.push(
+                self.state
+                    .iter()
+                    .zip(&self.shift)
+                    .map(|(x, shift)| (x ^ shift) as FloatValue / scale)
+                    .collect(),
+            );
+
+            // The next point differs from the current point in the direction given by the
+            // position of the lowest zero bit of the index
+            let bit = self.index.trailing_ones() as usize;
+            if bit >= SOBOL_BITS {
+                return Err(RSCMError::Error("Sobol sequence is exhausted".to_string()));
+            }
+            self.state
+                .iter_mut()
+                .zip(&directions)
+                .for_each(|(x, v)| *x ^= v[bit]);
+            self.index += 1;
+        }
+        Ok(points)
+    }
+}
+
+/// The first `n` prime numbers
+fn primes(n: usize) -> Vec<u64> {
+    let mut primes: Vec<u64> = Vec::with_capacity(n);
+    let mut candidate = 2;
+    while primes.len() < n {
+        if primes.iter().all(|p| candidate % p != 0) {
+            primes.push(candidate);
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+/// Radical inverse of `index` in `base`
+fn radical_inverse(mut index: u64, base: u64) -> FloatValue {
+    let mut result = 0.0;
+    let mut fraction = 1.0 / base as FloatValue;
+    while index > 0 {
+        result += (index % base) as FloatValue * fraction;
+        index /= base;
+        fraction /= base as FloatValue;
+    }
+    result
+}
+
+/// Halton low-discrepancy sequence
+///
+/// Each dimension uses the radical inverse in successive prime bases.
+/// The sequence starts from the index 1 to avoid the origin.
+/// Halton sequences degrade in high dimensions so [`SobolSampler`] is preferred beyond
+/// ~10 dimensions.
+///
+/// If seeded, each dimension is randomly shifted modulo 1 (a Cranley-Patterson rotation).
+#[derive(Debug, Clone)]
+pub struct HaltonSampler {
+    index: u64,
+    shifts: Option<ModelRng>,
+    shift: Vec<FloatValue>,
+}
+
+impl HaltonSampler {
+    /// Unshifted sequence
+    pub fn new() -> Self {
+        Self {
+            index: 1,
+            shifts: None,
+            shift: vec![],
+        }
+    }
+
+    /// Sequence with a random shift in each dimension
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            shifts: Some(ModelRng::seed_from_u64(seed)),
+            ..Self::new()
+        }
+    }
+}
+
+impl Default for HaltonSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn sample(&mut self, n: usize, dimensions: usize) -> RSCMResult<Vec<Vec<FloatValue>>> {
+        if let Some(rng) = &mut self.shifts {
+            while self.shift.len() < dimensions {
+                self.shift.push(rng.gen());
+            }
+        }
+        let bases = primes(dimensions);
+
+        let points = (self.index..self.index + n as u64)
+            .map(|index| {
+                bases
+                    .iter()
+                    .enumerate()
+                    .map(|(d, base)| {
+                        let value =
+                            radical_inverse(index, *base) + self.shift.get(d).unwrap_or(&0.0);
+                        value.fract()
+                    })
+                    .collect()
+            })
+            .collect();
+        self.index += n as u64;
+        Ok(points)
+    }
+}
+
+/// Method used to sample a parameter space
+///
+/// This allows the sampler to be selected in configuration files.
+#[pyclass]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SamplingMethod {
+    /// See [`RandomSampler`]
+    Random,
+    /// See [`LatinHypercubeSampler`]
+    #[default]
+    LatinHypercube,
+    /// See [`SobolSampler`]
+    Sobol,
+    /// See [`HaltonSampler`]
+    Halton,
+}
+
+impl SamplingMethod {
+    /// Create a sampler using `seed`
+    ///
+    /// The quasi-random sequences are scrambled using the seed.
+    pub fn sampler(&self, seed: u64) -> Box<dyn Sampler> {
+        match self {
+            Self::Random => Box::new(RandomSampler::new(seed)),
+            Self::LatinHypercube => Box::new(LatinHypercubeSampler::new(seed)),
+            Self::Sobol => Box::new(SobolSampler::with_seed(seed)),
+            Self::Halton => Box::new(HaltonSampler::with_seed(seed)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check that each of `n` equal intervals of each dimension contains a single point
+    fn is_stratified(points: &[Vec<FloatValue>]) -> bool {
+        let n = points.len();
+        (0..points[0].len()).all(|d| {
+            let mut strata: Vec<usize> = points
+                .iter()
+                .map(|p| (p[d] * n as FloatValue).floor() as usize)
+                .collect();
+            strata.sort();
+            strata == (0..n).collect::<Vec<_>>()
+        })
+    }
+
+    #[test]
+    fn latin_hypercube() {
+        let points = LatinHypercubeSampler::new(0).sample(50, 4).unwrap();
+        assert_eq!(points.len(), 50);
+        assert!(is_stratified(&points));
+
+        assert_eq!(points, LatinHypercubeSampler::new(0).sample(50, 4).unwrap());
+        assert_ne!(points, LatinHypercubeSampler::new(1).sample(50, 4).unwrap());
+    }
+
+    #[test]
+    fn sobol() {
+        let mut sampler = SobolSampler::new();
+        let points = sampler.sample(4, 2).unwrap();
+        assert_eq!(
+            points,
+            vec![
+                vec![0.0, 0.0],
+                vec![0.5, 0.5],
+                vec![0.75, 0.25],
+                vec![0.25, 0.75],
+            ]
+        );
+        // The sequence continues from the previous call
+        assert_eq!(sampler.sample(1, 2).unwrap(), vec![vec![0.375, 0.375]]);
+        assert!(sampler.sample(1, 3).is_err());
+
+        let points = SobolSampler::new()
+            .sample(64, SobolSampler::MAX_DIMENSIONS)
+            .unwrap();
+        assert!(is_stratified(&points));
+        // Scrambling preserves the stratification
+        assert!(is_stratified(
+            &SobolSampler::with_seed(3).sample(64, 5).unwrap()
+        ));
+
+        assert!(SobolSampler::new()
+            .sample(1, SobolSampler::MAX_DIMENSIONS + 1)
+            .is_err());
+    }
+
+    #[test]
+    fn halton() {
+        let points = HaltonSampler::new().sample(3, 2).unwrap();
+        assert_eq!(
+            points,
+            vec![
+                vec![0.5, 1.0 / 3.0],
+                vec![0.25, 2.0 / 3.0],
+                vec![0.75, 1.0 / 9.0]
+            ]
+        );
+
+        let shifted = HaltonSampler::with_seed(0).sample(100, 3).unwrap();
+        assert!(shifted.iter().flatten().all(|x| (0.0..1.0).contains(x)));
+        assert_eq!(shifted, HaltonSampler::with_seed(0).sample(100, 3).unwrap());
+    }
+
+    #[test]
+    fn sampling_method() {
+        let points = SamplingMethod::Random.sampler(0).sample(10, 2).unwrap();
+        let scaled: Vec<_> = points
+            .iter()
+            .map(|p| scale_to_ranges(p, &[(1.0, 2.0), (-10.0, 0.0)]))
+            .collect();
+        assert!(scaled
+            .iter()
+            .all(|p| (1.0..2.0).contains(&p[0]) && (-10.0..0.0).contains(&p[1])));
+
+        let method: SamplingMethod = toml::from_str::<toml::Table>("method = \"Sobol\"").unwrap()
+            ["method"]
+            .clone()
+            .try_into()
+            .unwrap();
+        assert_eq!(method, SamplingMethod::Sobol);
+    }
+}
@@ -0,0 +1,250 @@
+//! Random generation of emissions scenarios
+//!
+//! A [`ScenarioFamily`] describes a family of plausible future emissions trajectories.
+//! Emissions grow at a constant rate until a peak year,
+//! after which they decline exponentially towards a floor (which may be negative).
+//! The peak year, decline rate and floor of each scenario are sampled uniformly from
+//! the ranges in the family.
+//!
+//! Sampled [`ScenarioParameters`] can be used as (part of) the parameters of an ensemble member
+//! (see [`crate::ensemble::run_ensemble`]) so that uncertainty in future policy is explored
+//! alongside parametric uncertainty.
+use crate::component::ModelRng;
+use crate::errors::{RSCMError, RSCMResult};
+use crate::interpolate::strategies::{InterpolationStrategy, LinearSplineStrategy};
+use crate::sampling::{scale_to_ranges, Sampler};
+use crate::timeseries::{FloatValue, Time, TimeAxis, Timeseries};
+use crate::timeseries_collection::{TimeseriesCollection, VariableType};
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Parameters of a single scenario
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioParameters {
+    /// Year in which emissions peak
+    pub peak_year: Time,
+    /// Rate at which emissions decline towards the floor after the peak
+    ///
+    /// unit: 1 / yr
+    pub decline_rate: FloatValue,
+    /// Long-term level of emissions
+    ///
+    /// unit: units of the variable
+    pub floor: FloatValue,
+}
+
+/// A parameterised family of emissions trajectories
+///
+/// Each range is a `(min, max)` pair which parameters are sampled uniformly from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioFamily {
+    /// Name of the generated variable, e.g. "Emissions|CO2"
+    pub variable: String,
+    pub unit: String,
+    /// Emissions at the start of the time axis
+    pub start_value: FloatValue,
+    /// Fractional growth in emissions per year before the peak
+    ///
+    /// unit: 1 / yr
+    pub growth_rate: FloatValue,
+    pub peak_year: (Time, Time),
+    pub decline_rate: (FloatValue, FloatValue),
+    pub floor: (FloatValue, FloatValue),
+}
+
+impl ScenarioFamily {
+    fn validate(&self) -> RSCMResult<()> {
+        [
+            ("peak_year", self.peak_year),
+            ("decline_rate", self.decline_rate),
+            ("floor", self.floor),
+        ]
+        .iter()
+        .try_for_each(|(name, (min, max))| match min <= max {
+            true => Ok(()),
+            false => Err(RSCMError::Error(format!(
+                "Invalid range for {}: min ({}) is greater than max ({})",
+                name, min, max
+            ))),
+        })
+    }
+
+    /// Sample the parameters of a single scenario
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> ScenarioParameters {
+        let mut uniform = |(min, max): (FloatValue, FloatValue)| match min < max {
+            true => rng.gen_range(min..=max),
+            false => min,
+        };
+
+        ScenarioParameters {
+            peak_year: uniform(self.peak_year),
+            decline_rate: uniform(self.decline_rate),
+            floor: uniform(self.floor),
+        }
+    }
+
+    /// Sample the parameters of `n` scenarios
+    ///
+    /// The same `seed` always produces the same scenarios.
+    pub fn sample_n(&self, n: usize, seed: u64) -> RSCMResult<Vec<ScenarioParameters>> {
+        self.validate()?;
+
+        let mut rng = ModelRng::seed_from_u64(seed);
+        Ok((0..n).map(|_| self.sample(&mut rng)).collect())
+    }
+
+    /// Sample the parameters of `n` scenarios using a [`Sampler`]
+    ///
+    /// Quasi-random samplers cover the ranges more evenly than [`ScenarioFamily::sample_n`].
+    pub fn sample_with(
+        &self,
+        n: usize,
+        sampler: &mut dyn Sampler,
+    ) -> RSCMResult<Vec<ScenarioParameters>> {
+        self.validate()?;
+
+        let ranges = [self.peak_year, self.decline_rate, self.floor];
+        Ok(sampler
+            .sample(n, ranges.len())?
+            .iter()
+            .map(|point| {
+                let values = scale_to_ranges(point, &ranges);
+                ScenarioParameters {
+                    peak_year: values[0],
+                    decline_rate: values[1],
+                    floor: values[2],
+                }
+            })
+            .collect())
+    }
+
+    /// Emissions of a scenario at a point in time
+    pub fn value(&self, parameters: &ScenarioParameters, start: Time, time: Time) -> FloatValue {
+        let growth = |t: Time| self.start_value * (1.0 + self.growth_rate).powf(t - start);
+
+        if time <= parameters.peak_year {
+            growth(time)
+        } else {
+            let peak = growth(parameters.peak_year.max(start));
+            parameters.floor
+                + (peak - parameters.floor)
+                    * (-parameters.decline_rate * (time - parameters.peak_year)).exp()
+        }
+    }
+
+    /// Generate the trajectory of a scenario on a time axis
+    ///
+    /// The collection contains a single exogenous timeseries which can be passed to
+    /// a model builder.
+    pub fn generate(
+        &self,
+        parameters: &ScenarioParameters,
+        time_axis: Arc<TimeAxis>,
+    ) -> TimeseriesCollection {
+        let start = *time_axis.first();
+        let values = time_axis
+            .values()
+            .mapv(|time| self.value(parameters, start, time));
+
+        let mut collection = TimeseriesCollection::new();
+        collection
+            .add_timeseries(
+                self.variable.clone(),
+                Timeseries::new(
+                    values,
+                    time_axis,
+                    self.unit.clone(),
+                    InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+                ),
+                VariableType::Exogenous,
+            )
+            .expect("Collection is empty");
+        collection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampling::LatinHypercubeSampler;
+    use is_close::is_close;
+    use numpy::ndarray::Array;
+
+    fn family() -> ScenarioFamily {
+        ScenarioFamily {
+            variable: "Emissions|CO2".to_string(),
+            unit: "GtC / yr".to_string(),
+            start_value: 10.0,
+            growth_rate: 0.01,
+            peak_year: (2030.0, 2050.0),
+            decline_rate: (0.02, 0.1),
+            floor: (-2.0, 1.0),
+        }
+    }
+
+    #[test]
+    fn sample_within_ranges() {
+        let samples = family().sample_n(100, 0).unwrap();
+
+        assert_eq!(samples.len(), 100);
+        samples.iter().for_each(|s| {
+            assert!((2030.0..=2050.0).contains(&s.peak_year));
+            assert!((0.02..=0.1).contains(&s.decline_rate));
+            assert!((-2.0..=1.0).contains(&s.floor));
+        });
+        // Samples are reproducible
+        assert_eq!(samples, family().sample_n(100, 0).unwrap());
+        assert_ne!(samples, family().sample_n(100, 1).unwrap());
+    }
+
+    #[test]
+    fn sample_with_sampler() {
+        let samples = family()
+            .sample_with(10, &mut LatinHypercubeSampler::new(0))
+            .unwrap();
+
+        // Each tenth of the range of peak years contains a single scenario
+        let mut deciles: Vec<_> = samples
+            .iter()
+            .map(|s| ((s.peak_year - 2030.0) / 2.0).floor() as usize)
+            .collect();
+        deciles.sort();
+        assert_eq!(deciles, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn invalid_range() {
+        let mut family = family();
+        family.floor = (1.0, -2.0);
+
+        assert!(family.sample_n(1, 0).is_err());
+    }
+
+    #[test]
+    fn trajectory() {
+        let family = family();
+        let parameters = ScenarioParameters {
+            peak_year: 2030.0,
+            decline_rate: 0.05,
+            floor: -1.0,
+        };
+        let time_axis = Arc::new(TimeAxis::from_values(Array::range(2020.0, 2201.0, 1.0)));
+
+        let collection = family.generate(&parameters, time_axis);
+        let emissions = collection.get_timeseries_by_name("Emissions|CO2").unwrap();
+
+        let peak = 10.0 * 1.01_f64.powi(10);
+        assert_eq!(emissions.at_time(2020.0).unwrap(), 10.0);
+        assert!(is_close!(emissions.at_time(2030.0).unwrap(), peak));
+        assert!(is_close!(
+            emissions.at_time(2050.0).unwrap(),
+            -1.0 + (peak + 1.0) * (-1.0_f64).exp()
+        ));
+        assert!(is_close!(
+            emissions.at_time(2200.0).unwrap(),
+            -1.0,
+            abs_tol = 1e-2
+        ));
+    }
+}
@@ -0,0 +1,253 @@
+//! Timeseries of observations at arbitrary times
+//!
+//! Observations and constraints are often only available at irregular times
+//! (e.g. a handful of survey years) and don't represent values over a time step.
+//! A [`SparseTimeseries`] stores values at arbitrary, strictly increasing times
+//! without the bounds or latest value that describe model output.
+//! Values between the observations are interpolated using an [`InterpolationStrategy`].
+use crate::errors::{RSCMError, RSCMResult};
+use crate::interpolate::strategies::{
+    Interp1DStrategy, InterpolationStrategy, LinearSplineStrategy, SegmentCache,
+};
+use crate::timeseries::{FloatValue, Time, TimeAxis, Timeseries};
+use numpy::ndarray::{concatenate, s, Array1, ArrayView1, Axis};
+use serde::{Deserialize, Serialize};
+use std::iter::zip;
+use std::sync::Arc;
+
+/// Values at arbitrary points in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SparseTimeseries {
+    units: String,
+    times: Array1<Time>,
+    values: Array1<FloatValue>,
+    interpolation_strategy: InterpolationStrategy,
+    #[serde(skip)]
+    segment_cache: SegmentCache,
+}
+
+impl SparseTimeseries {
+    /// Create a timeseries from values at arbitrary times
+    ///
+    /// Time points with NaN values are treated as missing observations and are dropped.
+    /// Values are linearly interpolated between the observations and
+    /// extrapolation outside of the observed times is an error.
+    ///
+    /// Returns an error if the lengths differ, the times aren't strictly increasing
+    /// or there are fewer than two valid observations.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use rscm_core::sparse_timeseries::SparseTimeseries;
+    ///
+    /// let observations = SparseTimeseries::new(
+    ///     array![1850.0, 1901.5, 1960.0, 2023.0],
+    ///     array![0.0, 0.2, f64::NAN, 1.2],
+    ///     "K",
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(observations.len(), 3);
+    /// assert_eq!(observations.at_time(1962.25).unwrap(), 0.7);
+    /// assert!(observations.at_time(1800.0).is_err());
+    /// ```
+    pub fn new(times: Array1<Time>, values: Array1<FloatValue>, units: &str) -> RSCMResult<Self> {
+        if times.len() != values.len() {
+            return Err(RSCMError::Error(format!(
+                "Number of times ({}) doesn't match the number of values ({})",
+                times.len(),
+                values.len()
+            )));
+        }
+        if times.windows(2).into_iter().any(|w| w[0] >= w[1]) {
+            return Err(RSCMError::Error(
+                "Time values must be strictly increasing".to_string(),
+            ));
+        }
+
+        let (times, values): (Vec<Time>, Vec<FloatValue>) = zip(times, values)
+            .filter(|(_, value)| !value.is_nan())
+            .unzip();
+        if times.len() < 2 {
+            return Err(RSCMError::Error(format!(
+                "At least 2 valid observations are required, found {}",
+                times.len()
+            )));
+        }
+
+        Ok(Self {
+            units: units.to_string(),
+            times: Array1::from_vec(times),
+            values: Array1::from_vec(values),
+            interpolation_strategy: InterpolationStrategy::from(LinearSplineStrategy::new(false)),
+            segment_cache: SegmentCache::new(),
+        })
+    }
+
+    /// Replace the interpolation strategy
+    pub fn with_interpolation_strategy(
+        &mut self,
+        interpolation_strategy: InterpolationStrategy,
+    ) -> &mut Self {
+        self.interpolation_strategy = interpolation_strategy;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn times(&self) -> ArrayView1<'_, Time> {
+        self.times.view()
+    }
+
+    pub fn values(&self) -> ArrayView1<'_, FloatValue> {
+        self.values.view()
+    }
+
+    pub fn units(&self) -> &str {
+        &self.units
+    }
+
+    /// Interpolate a value at a given time using the interpolation strategy
+    pub fn at_time(&self, time: Time) -> RSCMResult<FloatValue> {
+        // The strategies expect bounds so the last observation is closed with a zero-width step
+        let bounds = concatenate![Axis(0), self.times, self.times.slice(s![-1..])];
+        self.interpolation_strategy.interpolate_cached(
+            &bounds.view(),
+            &self.values.view(),
+            time,
+            &self.segment_cache,
+        )
+    }
+
+    /// Observations within a window
+    ///
+    /// Both ends of the window are inclusive.
+    /// Returns `None` if fewer than two observations are within the window.
+    pub fn window(&self, start: Time, end: Time) -> Option<Self> {
+        let (times, values): (Vec<Time>, Vec<FloatValue>) = zip(&self.times, &self.values)
+            .filter(|(t, _)| (start..=end).contains(*t))
+            .unzip();
+
+        let mut window = Self::new(
+            Array1::from_vec(times),
+            Array1::from_vec(values),
+            &self.units,
+        )
+        .ok()?;
+        window.with_interpolation_strategy(self.interpolation_strategy.clone());
+        Some(window)
+    }
+
+    /// Interpolate onto a time axis, e.g. to use the observations as exogenous data
+    ///
+    /// Time points where the interpolation fails, such as those outside of the observed
+    /// times when extrapolation isn't allowed, are NaN.
+    pub fn interpolate_into(&self, time_axis: Arc<TimeAxis>) -> Timeseries<FloatValue> {
+        let values = time_axis
+            .values()
+            .mapv(|t| self.at_time(t).unwrap_or(FloatValue::NAN));
+
+        Timeseries::new(
+            values,
+            time_axis,
+            self.units.clone(),
+            self.interpolation_strategy.clone(),
+        )
+    }
+
+    /// Values of a model output at each observation time
+    ///
+    /// Returns an error if the units differ or the model can't be interpolated
+    /// at an observation time.
+    pub fn sample(&self, model: &Timeseries<FloatValue>) -> RSCMResult<Array1<FloatValue>> {
+        if model.units() != self.units {
+            return Err(RSCMError::WrongUnits(
+                self.units.clone(),
+                model.units().to_string(),
+            ));
+        }
+        self.times.iter().map(|t| model.at_time(*t)).collect()
+    }
+}
+
+impl From<&Timeseries<FloatValue>> for SparseTimeseries {
+    /// Observations at the start of each time step of a timeseries
+    ///
+    /// Panics if the timeseries has fewer than two non-NaN values.
+    fn from(timeseries: &Timeseries<FloatValue>) -> Self {
+        Self::new(
+            timeseries.time_axis().values().to_owned(),
+            timeseries.values().to_owned(),
+            timeseries.units(),
+        )
+        .expect("Timeseries doesn't contain enough valid values")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpolate::strategies::PreviousStrategy;
+    use numpy::array;
+    use numpy::ndarray::Array;
+
+    fn observations() -> SparseTimeseries {
+        SparseTimeseries::new(array![2000.0, 2003.5, 2010.0], array![1.0, 2.0, 4.0], "K").unwrap()
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(SparseTimeseries::new(array![2000.0], array![1.0, 2.0], "K").is_err());
+        assert!(SparseTimeseries::new(array![2001.0, 2000.0], array![1.0, 2.0], "K").is_err());
+        assert!(
+            SparseTimeseries::new(array![2000.0, 2001.0], array![1.0, FloatValue::NAN], "K")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn interpolate_into() {
+        let mut observations = observations();
+        let time_axis = Arc::new(TimeAxis::from_values(Array::range(1999.0, 2012.0, 1.0)));
+
+        let timeseries = observations.interpolate_into(time_axis.clone());
+        assert_eq!(timeseries.units(), "K");
+        assert!(timeseries.at(0).unwrap().is_nan());
+        assert_eq!(timeseries.at(1).unwrap(), 1.0);
+        assert_eq!(timeseries.at(3).unwrap(), 1.0 + 2.0 / 3.5);
+        assert!(timeseries.at(12).unwrap().is_nan());
+
+        observations.with_interpolation_strategy(PreviousStrategy::new(false).into());
+        let timeseries = observations.interpolate_into(time_axis);
+        assert_eq!(timeseries.at(5).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn window_and_sample() {
+        let observations = observations();
+        assert_eq!(
+            observations.window(2001.0, 2010.0).unwrap().times(),
+            array![2003.5, 2010.0]
+        );
+        assert!(observations.window(2001.0, 2005.0).is_none());
+
+        let model = Timeseries::new(
+            Array::range(0.0, 20.0, 1.0),
+            Arc::new(TimeAxis::from_values(Array::range(2000.0, 2020.0, 1.0))),
+            "K".to_string(),
+            LinearSplineStrategy::new(true).into(),
+        );
+        assert_eq!(observations.sample(&model).unwrap(), array![0.0, 3.5, 10.0]);
+
+        let model = model.convert_unit("mK").unwrap();
+        assert!(observations.sample(&model).is_err());
+    }
+}
@@ -0,0 +1,554 @@
+//! Models embedded as a single component of a larger model
+//!
+//! A [`SubModel`] wraps the components of a built [`Model`] so that they can be registered
+//! with another [`ModelBuilder`] as a single component,
+//! for example, a calibrated emissions to concentrations model can be reused
+//! as the carbon cycle of a larger model.
+//!
+//! The requirements of the sub-model are derived from the model it was created from:
+//!
+//! * exogenous variables without any data become inputs
+//! * variables which are carried between time steps (e.g. `InputAndOutput` state)
+//!   become `InputAndOutput` state, with the model's initial values as defaults.
+//!   Initial values calculated by the components using [`Component::initialise`]
+//!   take precedence over these defaults
+//! * all other variables solved by the components become outputs
+//!
+//! The state of the sub-model is held by the model it is embedded in,
+//! so embedded sub-models can be rewound, serialised and replaced like any other component.
+use crate::component::{
+    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+    TimestepContext,
+};
+use crate::errors::{RSCMError, RSCMResult};
+use crate::interpolate::strategies::{InterpolationStrategy, PreviousStrategy};
+use crate::model::{Model, ModelBuilder, ModelOptions, ModelTemplate};
+use crate::timeseries::{FloatValue, Time, TimeAxis, Timeseries};
+use crate::timeseries_collection::{TimeseriesCollection, VariableType};
+use numpy::array;
+use numpy::ndarray::Array;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+
+/// The components of a model solved as a single component
+///
+/// Each time step is solved by a model containing the components on a time axis
+/// with the single step, using the inputs and state supplied by the outer model.
+/// The component graph of this model is only built once,
+/// when the sub-model is created or first solved after being deserialised.
+/// The inputs are held constant over the step.
+/// Exogenous data supplied to the original model are kept,
+/// so the original model should have been built on a time axis which covers
+/// the time axis of the outer model.
+///
+/// When solved by a model, stochastic components in the sub-model are seeded from the
+/// random number stream of the sub-model each step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubModel {
+    /// Name and component of each component in the order they were registered
+    components: Vec<(String, Arc<dyn Component>)>,
+    /// Exogenous data supplied to the original model
+    exogenous: TimeseriesCollection,
+    options: ModelOptions,
+    definitions: Vec<RequirementDefinition>,
+    /// The components with their graph and unit conversions, used to solve each step
+    #[serde(skip)]
+    template: OnceLock<ModelTemplate>,
+}
+
+impl SubModel {
+    /// Create a component from the components of a built model
+    ///
+    /// The model doesn't need to have been run.
+    pub fn from_model(model: &Model) -> Self {
+        let components: Vec<(String, Arc<dyn Component>)> = model
+            .components()
+            .into_iter()
+            .map(|(name, component)| (name.to_string(), component))
+            .collect();
+        let collection = model.timeseries();
+
+        let mut exogenous = TimeseriesCollection::new();
+        collection
+            .iter()
+            .filter(|item| item.variable_type == VariableType::Exogenous)
+            .for_each(|item| {
+                exogenous.upsert_item(item.clone());
+            });
+
+        let sub_model = Self {
+            definitions: derive_definitions(&components, collection),
+            components,
+            exogenous,
+            options: model.options().clone(),
+            template: OnceLock::new(),
+        };
+        // Any problems building the template are returned when the sub-model is solved
+        if let Ok(template) = sub_model.build_template() {
+            sub_model.template.get_or_init(|| template);
+        }
+        sub_model
+    }
+
+    /// Names of the components in the order they were registered
+    pub fn component_names(&self) -> Vec<&str> {
+        self.components
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Build the model used to solve each step
+    ///
+    /// The values of the model's timeseries are placeholders which are replaced each step.
+    fn build_template(&self) -> RSCMResult<ModelTemplate> {
+        let time_axis = Arc::new(step_time_axis(0.0, 1.0));
+        let placeholder = |unit: &str| {
+            Timeseries::new(
+                Array::from_elem(time_axis.len(), FloatValue::NAN),
+                time_axis.clone(),
+                unit.to_string(),
+                InterpolationStrategy::from(PreviousStrategy::new(true)),
+            )
+        };
+
+        let mut builder = ModelBuilder::new();
+        self.components.iter().for_each(|(name, component)| {
+            match name == component.typetag_name() {
+                true => builder.with_component(component.clone()),
+                false => builder.with_named_component(name, component.clone()),
+            };
+        });
+        self.exogenous.iter().for_each(|item| {
+            builder.with_exogenous_variable(&item.name, placeholder(item.timeseries.units()));
+        });
+        self.definitions
+            .iter()
+            .for_each(|definition| match definition.requirement_type {
+                RequirementType::Input => {
+                    builder
+                        .with_exogenous_variable(&definition.name, placeholder(&definition.unit));
+                }
+                RequirementType::InputAndOutput => {
+                    builder.with_initial_values(InputState::from_vectors(
+                        vec![FloatValue::NAN],
+                        vec![definition.name.clone()],
+                    ));
+                }
+                _ => {}
+            });
+
+        builder
+            .with_options(self.options.clone())
+            .with_time_axis(time_axis.as_ref().clone())
+            .try_build()
+            .map(Model::into_template)
+            .map_err(|err| RSCMError::Error(format!("Sub-model can't be built: {}", err)))
+    }
+
+    /// The model used to solve each step, building it if needed
+    fn template(&self) -> RSCMResult<&ModelTemplate> {
+        if let Some(template) = self.template.get() {
+            return Ok(template);
+        }
+        let template = self.build_template()?;
+        Ok(self.template.get_or_init(|| template))
+    }
+
+    /// Timeseries on the step's time axis using the inputs and state supplied by the outer model
+    fn step_timeseries(
+        &self,
+        template: &ModelTemplate,
+        time_axis: Arc<TimeAxis>,
+        input_state: &InputState,
+    ) -> RSCMResult<TimeseriesCollection> {
+        let mut collection = TimeseriesCollection::new();
+        for item in template.timeseries().iter() {
+            let units = item.timeseries.units().to_string();
+            let strategy = item.timeseries.interpolation_strategy().clone();
+            let timeseries = match self.exogenous.get_timeseries_by_name(&item.name) {
+                Some(exogenous) => {
+                    let exogenous = match exogenous.time_axis().unit() == time_axis.unit() {
+                        true => exogenous.clone(),
+                        false => exogenous.to_time_unit(time_axis.unit()),
+                    };
+                    exogenous.try_interpolate_into(time_axis.clone())?
+                }
+                // Inputs are held constant over the step
+                None if item.variable_type == VariableType::Exogenous => Timeseries::new(
+                    Array::from_elem(time_axis.len(), *input_state.get(&item.name)),
+                    time_axis.clone(),
+                    units,
+                    strategy,
+                ),
+                None => {
+                    let mut timeseries = Timeseries::new_empty(time_axis.clone(), units, strategy);
+                    if input_state.has(&item.name) {
+                        timeseries.set(0, *input_state.get(&item.name));
+                    }
+                    timeseries
+                }
+            };
+            collection.add_timeseries(item.name.clone(), timeseries, item.variable_type)?;
+        }
+        Ok(collection)
+    }
+
+    fn solve_step(
+        &self,
+        time_axis: TimeAxis,
+        input_state: &InputState,
+        rng_seed: Option<u64>,
+    ) -> RSCMResult<OutputState> {
+        let template = self.template()?;
+        let time_axis = Arc::new(time_axis);
+        let collection = self.step_timeseries(template, time_axis.clone(), input_state)?;
+        let mut model = template.instantiate(collection, time_axis, rng_seed);
+        model.try_step()?;
+
+        let (values, names) = self
+            .output_names()
+            .into_iter()
+            .map(|name| {
+                model
+                    .timeseries()
+                    .get_timeseries_by_name(&name)
+                    .and_then(|timeseries| timeseries.at(1))
+                    .map(|value| (value, name.clone()))
+                    .ok_or_else(|| RSCMError::Error(format!("Sub-model didn't solve '{}'", name)))
+            })
+            .collect::<RSCMResult<Vec<(FloatValue, String)>>>()?
+            .into_iter()
+            .unzip();
+        Ok(OutputState::from_vectors(values, names))
+    }
+}
+
+/// Time axis used to solve a single time step
+///
+/// A model solves the steps between the values of its time axis,
+/// so the time axis contains both the start and end of the step.
+fn step_time_axis(t_current: Time, t_next: Time) -> TimeAxis {
+    TimeAxis::from_values(array![t_current, t_next])
+}
+
+/// Requirements of the components of a model as a single component
+///
+/// A variable solved by a component is state if it is required by the same or
+/// an earlier component, as it is then read before it is solved each step.
+fn derive_definitions(
+    components: &[(String, Arc<dyn Component>)],
+    collection: &TimeseriesCollection,
+) -> Vec<RequirementDefinition> {
+    let mut definitions: Vec<RequirementDefinition> = vec![];
+    let mut solved: Vec<String> = vec![];
+    let mut state: Vec<String> = vec![];
+
+    components.iter().for_each(|(_, component)| {
+        component.inputs().into_iter().for_each(|input| {
+            if !solved.contains(&input.name) && !state.contains(&input.name) {
+                state.push(input.name.clone());
+            }
+            if !definitions.iter().any(|d| d.name == input.name) {
+                definitions.push(input);
+            }
+        });
+        component.outputs().into_iter().for_each(|output| {
+            solved.push(output.name.clone());
+            definitions.retain(|d| d.name != output.name);
+            definitions.push(output);
+        });
+    });
+
+    definitions
+        .into_iter()
+        .filter_map(|definition| {
+            let is_solved = solved.contains(&definition.name);
+            let is_state = state.contains(&definition.name);
+            let timeseries = collection.get_timeseries_by_name(&definition.name);
+            let requirement_type = match (is_solved, is_state) {
+                (true, true) => RequirementType::InputAndOutput,
+                (true, false) => RequirementType::Output,
+                // Inputs with exogenous data are supplied by the sub-model
+                (false, _) if timeseries.is_some() => return None,
+                (false, _) => RequirementType::Input,
+            };
+            // Initial values of the state are used as defaults
+            let default = match requirement_type {
+                RequirementType::InputAndOutput => timeseries
+                    .and_then(|timeseries| timeseries.at(0))
+                    .filter(|value| !value.is_nan())
+                    .or(definition.default),
+                _ => definition.default,
+            };
+            Some(RequirementDefinition {
+                requirement_type,
+                default,
+                ..definition
+            })
+        })
+        .collect()
+}
+
+#[typetag::serde]
+impl Component for SubModel {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        self.definitions.clone()
+    }
+
+    fn initialise(&self, t0: Time) -> OutputState {
+        let state_names: Vec<&String> = self
+            .definitions
+            .iter()
+            .filter(|definition| definition.requirement_type == RequirementType::InputAndOutput)
+            .map(|definition| &definition.name)
+            .collect();
+        let mut initialised = OutputState::empty();
+        self.components.iter().for_each(|(_, component)| {
+            let (values, names) = component
+                .initialise(t0)
+                .into_iter()
+                .filter(|(name, _)| state_names.contains(&name))
+                .map(|(name, value)| (value, name))
+                .unzip();
+            initialised.merge(OutputState::from_vectors(values, names));
+        });
+        initialised
+    }
+
+    fn solve(
+        &self,
+        t_current: Time,
+        t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        self.solve_step(step_time_axis(t_current, t_next), input_state, None)
+    }
+
+    fn solve_with_context(
+        &self,
+        context: &mut TimestepContext,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let time_axis =
+            step_time_axis(context.t_current(), context.t_next()).with_unit(context.time_unit());
+        let seed = context.rng().gen();
+        self.solve_step(time_axis, input_state, Some(seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_components::{
+        TestComponent, TestComponentParameters, TestDecayComponent, TestEquilibriumComponent,
+        TestNoiseComponent,
+    };
+    use crate::interpolate::strategies::LinearSplineStrategy;
+
+    fn components() -> Vec<Arc<dyn Component>> {
+        vec![
+            Arc::new(TestComponent::from_parameters(TestComponentParameters {
+                p: 0.5,
+            })),
+            Arc::new(TestDecayComponent { tau: 10.0 }),
+        ]
+    }
+
+    fn emissions() -> Timeseries<FloatValue> {
+        Timeseries::from_values(array![0.0, 10.0], array![2020.0, 2030.0])
+    }
+
+    fn time_axis() -> TimeAxis {
+        TimeAxis::from_values(Array::range(2020.0, 2030.0, 1.0))
+    }
+
+    fn values(model: &Model, name: &str) -> Vec<FloatValue> {
+        model
+            .timeseries()
+            .get_timeseries_by_name(name)
+            .unwrap()
+            .values()
+            .to_vec()
+    }
+
+    #[test]
+    fn definitions() {
+        let mut builder = ModelBuilder::new();
+        components().into_iter().for_each(|component| {
+            builder.with_component(component);
+        });
+        let sub_model = builder.with_time_axis(time_axis()).build().to_component();
+
+        let mut definitions = sub_model.definitions();
+        definitions.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(
+            definitions
+                .iter()
+                .map(|d| (d.name.as_str(), d.requirement_type.clone(), d.default))
+                .collect::<Vec<_>>(),
+            [
+                ("Concentrations|CO2", RequirementType::Output, None),
+                ("Emissions|CO2", RequirementType::Input, None),
+                ("Stock", RequirementType::InputAndOutput, Some(0.0)),
+            ]
+        );
+        assert_eq!(
+            sub_model.component_names(),
+            ["TestComponent", "TestDecayComponent"]
+        );
+
+        // Inputs with exogenous data are solved within the sub-model
+        let sub_model = builder
+            .with_exogenous_variable("Emissions|CO2", emissions())
+            .with_initial_values(InputState::from_vectors(
+                vec![2.0],
+                vec!["Stock".to_string()],
+            ))
+            .build()
+            .to_component();
+        assert_eq!(sub_model.input_names(), ["Stock"]);
+        assert_eq!(
+            sub_model
+                .definitions()
+                .iter()
+                .find(|d| d.name == "Stock")
+                .unwrap()
+                .default,
+            Some(2.0)
+        );
+    }
+
+    #[test]
+    fn matches_flat_model() {
+        // Concentrations are solved within the sub-model or supplied as an input
+        let configurations: [(Vec<Arc<dyn Component>>, &str); 2] = [
+            (components(), "Emissions|CO2"),
+            (
+                vec![Arc::new(TestDecayComponent { tau: 10.0 })],
+                "Concentrations|CO2",
+            ),
+        ];
+
+        for (components, input) in configurations {
+            let mut flat = ModelBuilder::new();
+            components.into_iter().for_each(|component| {
+                flat.with_component(component);
+            });
+            let sub_model = flat.with_time_axis(time_axis()).build().to_component();
+            assert_eq!(sub_model.input_names(), [input, "Stock"]);
+            let mut flat = flat.with_exogenous_variable(input, emissions()).build();
+            flat.run();
+
+            let mut nested = ModelBuilder::new()
+                .with_named_component("carbon cycle", Arc::new(sub_model))
+                .with_time_axis(time_axis())
+                .with_exogenous_variable(input, emissions())
+                .build();
+            nested.run();
+            assert!(nested.warnings().is_empty(), "{:?}", nested.warnings());
+
+            for name in ["Concentrations|CO2", "Stock"] {
+                // The first value of outputs is NaN
+                assert_eq!(values(&nested, name)[1..], values(&flat, name)[1..]);
+            }
+            assert!(values(&nested, "Stock")[9] > 0.0);
+
+            // The sub-model is serialised with the model
+            let restored = toml::from_str::<Model>(&toml::to_string(&nested).unwrap()).unwrap();
+            assert_eq!(restored.component_names(), ["carbon cycle"]);
+        }
+    }
+
+    #[test]
+    fn initialise() {
+        let sub_model = ModelBuilder::new()
+            .with_component(Arc::new(TestEquilibriumComponent { equilibrium: 2.0 }))
+            .with_initial_values(InputState::from_vectors(
+                vec![1.0],
+                vec!["Level".to_string()],
+            ))
+            .build()
+            .to_component();
+
+        // The value calculated by the component takes precedence over the default
+        assert_eq!(sub_model.definitions()[0].default, Some(1.0));
+        assert_eq!(*sub_model.initialise(2020.0).get("Level"), 2.0);
+        let model = ModelBuilder::new()
+            .with_component(Arc::new(sub_model))
+            .with_time_axis(time_axis())
+            .build();
+        assert_eq!(values(&model, "Level")[0], 2.0);
+    }
+
+    #[test]
+    fn random_numbers() {
+        let sub_model = ModelBuilder::new()
+            .with_component(Arc::new(TestNoiseComponent {
+                name: "Noise".to_string(),
+            }))
+            .build()
+            .to_component();
+        let mut model = ModelBuilder::new()
+            .with_component(Arc::new(sub_model))
+            .with_time_axis(time_axis())
+            .build();
+        model.run();
+
+        // Each step draws different random numbers
+        let noise = values(&model, "Noise");
+        assert_ne!(noise[1], noise[2]);
+    }
+
+    #[test]
+    fn deserialised() {
+        let sub_model = ModelBuilder::new()
+            .with_component(Arc::new(TestDecayComponent { tau: 10.0 }))
+            .build()
+            .to_component();
+        let restored: Arc<dyn Component> =
+            serde_json::from_str(&serde_json::to_string(&sub_model as &dyn Component).unwrap())
+                .unwrap();
+
+        // The model used to solve each step is rebuilt when the sub-model is first solved
+        let [expected, solved] =
+            [Arc::new(sub_model) as Arc<dyn Component>, restored].map(|component| {
+                let mut model = ModelBuilder::new()
+                    .with_component(component)
+                    .with_time_axis(time_axis())
+                    .with_exogenous_variable("Concentrations|CO2", emissions())
+                    .build();
+                model.run();
+                values(&model, "Stock")
+            });
+        assert_eq!(solved[1..], expected[1..]);
+    }
+
+    #[test]
+    fn solve_errors() {
+        let mut concentrations =
+            Timeseries::from_values(array![0.0, 10.0, 20.0], array![2010.0, 2030.0, 2035.0]);
+        concentrations.with_interpolation_strategy(InterpolationStrategy::from(
+            LinearSplineStrategy::new(false),
+        ));
+        let sub_model = ModelBuilder::new()
+            .with_component(Arc::new(TestDecayComponent { tau: 10.0 }))
+            .with_time_axis(time_axis())
+            .with_exogenous_variable("Concentrations|CO2", concentrations)
+            .build()
+            .to_component();
+
+        // The exogenous data of the sub-model don't cover the outer model's time axis
+        let mut model = ModelBuilder::new()
+            .with_component(Arc::new(sub_model))
+            .with_time_axis(TimeAxis::from_values(Array::range(2040.0, 2045.0, 1.0)))
+            .build();
+        let err = model.try_step().unwrap_err();
+        assert!(
+            err.to_string().contains("Extrapolation is not allowed"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Utilities for testing components in isolation
+//!
+//! [`ComponentTester`] runs a single component over a time axis using synthetic inputs.
+//! This avoids having to build a full model or manually construct collections and states.
+//!
+//! ```rust
+//! use numpy::array;
+//! use rscm_core::component::{
+//!     Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
+//! };
+//! use rscm_core::errors::RSCMResult;
+//! use rscm_core::testing::ComponentTester;
+//! use rscm_core::timeseries::{Time, TimeAxis};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, Serialize, Deserialize)]
+//! struct Doubler {}
+//!
+//! #[typetag::serde]
+//! impl Component for Doubler {
+//!     fn definitions(&self) -> Vec<RequirementDefinition> {
+//!         vec![
+//!             RequirementDefinition::new("input", "K", RequirementType::Input),
+//!             RequirementDefinition::new("output", "K", RequirementType::Output),
+//!         ]
+//!     }
+//!
+//!     fn solve(&self, _t_current: Time, _t_next: Time, input_state: &InputState) -> RSCMResult<OutputState> {
+//!         Ok(OutputState::from_vectors(vec![input_state.get("input") * 2.0], self.output_names()))
+//!     }
+//! }
+//!
+//! let outputs = ComponentTester::new(
+//!     Doubler {},
+//!     TimeAxis::from_values(array![2020.0, 2021.0, 2022.0]),
+//! )
+//! .with_input("input", array![1.0, 2.0, 3.0])
+//! .run()
+//! .unwrap();
+//!
+//! let output = outputs.get_timeseries_by_name("output").unwrap();
+//! assert_eq!(output.at(2), Some(4.0));
+//! ```
+use crate::component::{Component, ModelRng, RequirementType, TimestepContext};
+use crate::errors::{RSCMError, RSCMResult};
+use crate::interpolate::strategies::{InterpolationStrategy, PreviousStrategy};
+use crate::timeseries::{FloatValue, TimeAxis, Timeseries};
+use crate::timeseries_collection::{TimeseriesCollection, VariableType};
+use numpy::ndarray::Array1;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Run a single component over a time axis using synthetic inputs
+///
+/// Inputs are provided as arrays with a value for each point on the time axis.
+/// Optional inputs which aren't provided use their default value.
+/// State variables (inputs which are also outputs) require an initial value
+/// unless they define a default.
+pub struct ComponentTester<T: Component> {
+    component: T,
+    time_axis: Arc<TimeAxis>,
+    inputs: HashMap<String, Array1<FloatValue>>,
+    initial_values: HashMap<String, FloatValue>,
+    seed: u64,
+}
+
+impl<T: Component> ComponentTester<T> {
+    pub fn new(component: T, time_axis: TimeAxis) -> Self {
+        Self {
+            component,
+            time_axis: Arc::new(time_axis),
+            inputs: HashMap::new(),
+            initial_values: HashMap::new(),
+            seed: 0,
+        }
+    }
+
+    /// Provide the values of an input for each point on the time axis
+    pub fn with_input(mut self, name: &str, values: Array1<FloatValue>) -> Self {
+        self.inputs.insert(name.to_string(), values);
+        self
+    }
+
+    /// Provide the initial value of a state variable
+    pub fn with_initial_value(mut self, name: &str, value: FloatValue) -> Self {
+        self.initial_values.insert(name.to_string(), value);
+        self
+    }
+
+    /// Seed for the random number generator passed to the component
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Create the collection containing the inputs and placeholders for the outputs
+    fn build_collection(&self) -> RSCMResult<TimeseriesCollection> {
+        let mut collection = TimeseriesCollection::new();
+        let strategy = || InterpolationStrategy::from(PreviousStrategy::new(true));
+
+        for definition in self.component.variables() {
+            let name = definition.name.as_str();
+            match definition.requirement_type {
+                RequirementType::Input => {
+                    let values = match (self.inputs.get(name), definition.default) {
+                        (Some(values), _) => {
+                            if values.len() != self.time_axis.len() {
+                                return Err(RSCMError::Error(format!(
+                                    "Input '{}' has {} values but the time axis has {}",
+                                    name,
+                                    values.len(),
+                                    self.time_axis.len()
+                                )));
+                            }
+                            values.clone()
+                        }
+                        (None, Some(default)) => Array1::from_elem(self.time_axis.len(), default),
+                        (None, None) => {
+                            return Err(RSCMError::Error(format!(
+                                "No values provided for input '{}'",
+                                name
+                            )))
+                        }
+                    };
+                    let ts = Timeseries::new(
+                        values,
+                        self.time_axis.clone(),
+                        definition.unit,
+                        strategy(),
+                    );
+                    collection.add_timeseries(name.to_string(), ts, VariableType::Exogenous)?;
+                }
+                RequirementType::InputAndOutput | RequirementType::Output => {
+                    let mut ts =
+                        Timeseries::new_empty(self.time_axis.clone(), definition.unit, strategy());
+
+                    if definition.requirement_type == RequirementType::InputAndOutput {
+                        let initial_value = self
+                            .initial_values
+                            .get(name)
+                            .copied()
+                            .or(definition.default)
+                            .ok_or_else(|| {
+                                RSCMError::Error(format!(
+                                    "No initial value provided for state variable '{}'",
+                                    name
+                                ))
+                            })?;
+                        ts.set(0, initial_value);
+                    }
+                    collection.add_timeseries(name.to_string(), ts, VariableType::Endogenous)?;
+                }
+                RequirementType::EmptyLink => {}
+            }
+        }
+
+        Ok(collection)
+    }
+
+    /// Solve the component for each step of the time axis
+    ///
+    /// Returns a collection containing the inputs and the solved outputs.
+    /// Any error from the component is returned immediately.
+    pub fn run(&self) -> RSCMResult<TimeseriesCollection> {
+        let mut collection = self.build_collection()?;
+        let mut rng = ModelRng::seed_from_u64(self.seed);
+        let mut scratch = vec![];
+
+        for time_index in 0..self.time_axis.len() - 1 {
+            let t_current = self.time_axis.at(time_index).unwrap();
+            let input_state = self.component.extract_state(&collection, t_current);
+
+            let mut context =
+                TimestepContext::new(time_index, &self.time_axis, &mut rng, &mut scratch);
+            let output_state = self
+                .component
+                .solve_with_context(&mut context, &input_state)?;
+
+            output_state.into_iter().for_each(|(name, value)| {
+                collection
+                    .get_timeseries_by_name_mut(&name)
+                    .unwrap()
+                    .set(time_index + 1, value)
+            });
+        }
+
+        Ok(collection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_components::TestOptionalInputComponent;
+    use numpy::array;
+
+    #[test]
+    fn run() {
+        let outputs = ComponentTester::new(
+            TestOptionalInputComponent {},
+            TimeAxis::from_values(array![2020.0, 2021.0, 2022.0]),
+        )
+        .with_input(
+            "Effective Radiative Forcing|Aerosols",
+            array![-0.5, -1.0, -2.0],
+        )
+        .run()
+        .unwrap();
+
+        let total = outputs
+            .get_timeseries_by_name("Effective Radiative Forcing|Total")
+            .unwrap();
+        assert!(total.at(0).unwrap().is_nan());
+        assert_eq!(total.at(1), Some(-0.5));
+        assert_eq!(total.at(2), Some(-1.0));
+    }
+
+    #[test]
+    fn optional_input() {
+        let outputs = ComponentTester::new(
+            TestOptionalInputComponent {},
+            TimeAxis::from_values(array![2020.0, 2021.0, 2022.0]),
+        )
+        .run()
+        .unwrap();
+
+        let total = outputs
+            .get_timeseries_by_name("Effective Radiative Forcing|Total")
+            .unwrap();
+        assert_eq!(total.at(2), Some(0.0));
+    }
+
+    #[test]
+    fn invalid_input() {
+        let tester = ComponentTester::new(
+            TestOptionalInputComponent {},
+            TimeAxis::from_values(array![2020.0, 2021.0, 2022.0]),
+        )
+        .with_input("Effective Radiative Forcing|Aerosols", array![-0.5, -1.0]);
+
+        assert_eq!(
+            tester.run().err().unwrap().to_string(),
+            "Input 'Effective Radiative Forcing|Aerosols' has 2 values but the time axis has 3"
+        );
+    }
+}
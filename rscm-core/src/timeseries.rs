@@ -1,12 +1,21 @@
-use crate::errors::RSCMResult;
-use crate::interpolate::strategies::{InterpolationStrategy, LinearSplineStrategy};
+use crate::errors::{RSCMError, RSCMResult};
+use crate::interpolate::strategies::{
+    ExtrapolationPolicy, Interp1DStrategy, InterpolationStrategy, LinearSplineStrategy,
+    PreviousStrategy, SegmentCache,
+};
 use crate::interpolate::Interp1d;
+use crate::units::{self, Unit};
+use is_close::is_close;
 use nalgebra::max;
 use num::{Float, ToPrimitive};
 use numpy::ndarray::prelude::*;
 use numpy::ndarray::{Array, Array1, ViewRepr};
+use pyo3::pyclass;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Display, Formatter};
 use std::iter::zip;
+use std::ops::Range;
 use std::sync::Arc;
 
 /// The type of float used in time calculations
@@ -19,16 +28,90 @@ pub type Time = f64;
 /// This is a placeholder to make it easier to be able to use a generic representation of value.
 pub type FloatValue = f64;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Default absolute tolerance used when comparing time values
+///
+/// This is well below the resolution of any sensible time step (~30 seconds in decimal years).
+pub const DEFAULT_TIME_TOLERANCE: Time = 1e-6;
+
+/// Unit of the values of a [`TimeAxis`]
+///
+/// Rates used by components are always expressed per year (e.g. "GtC / yr"),
+/// whatever the unit of the time axis.
+/// Solvers convert the length of each time step into years
+/// (see [`crate::ivp::IVPBuilder::with_time_unit`]),
+/// and exogenous data is converted onto the unit of the model's time axis.
+/// The conversions use the definitions in the unit registry (see [`crate::units`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeUnit {
+    /// Decimal years, e.g. 2000.5
+    #[default]
+    Year,
+    /// Seconds
+    Second,
+}
+
+impl TimeUnit {
+    /// Symbol of the unit in the unit registry
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            TimeUnit::Year => "yr",
+            TimeUnit::Second => "s",
+        }
+    }
+
+    /// Length of one unit in years
+    pub fn in_years(&self) -> Time {
+        units::conversion_factor::<&str>(self.symbol(), TimeUnit::Year.symbol(), &[])
+            .expect("Time units are registered")
+    }
+
+    fn is_year(&self) -> bool {
+        *self == TimeUnit::Year
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TimeAxis {
     bounds: Array1<Time>,
+    #[serde(default, skip_serializing_if = "TimeUnit::is_year")]
+    unit: TimeUnit,
 }
 
-fn check_monotonic_increasing(arr: &Array1<Time>) -> bool {
+/// Summarises the time axis
+///
+/// The alternate form (`{:#?}`) includes all of the bounds.
+impl Debug for TimeAxis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match f.alternate() {
+            true => f
+                .debug_struct("TimeAxis")
+                .field("bounds", &self.bounds.to_vec())
+                .finish(),
+            false => write!(f, "TimeAxis {{ {} }}", self),
+        }
+    }
+}
+
+impl Display for TimeAxis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "len: {}, range: [{:?}, {:?})",
+            self.len(),
+            self.first(),
+            self.bounds[self.bounds.len() - 1]
+        )
+    }
+}
+
+fn check_monotonic_increasing(arr: &Array1<Time>) -> RSCMResult<()> {
     let mut zipped_arr = zip(arr.slice(s![0..arr.len() - 1]), arr.slice(s![1..]));
 
     // Check that [i + 1] > [i]
-    zipped_arr.all(|(&a, &b)| b > a)
+    match zipped_arr.find(|(&a, &b)| b.partial_cmp(&a) != Some(Ordering::Greater)) {
+        Some((&a, &b)) => Err(RSCMError::NonMonotonicTime(a, b)),
+        None => Ok(()),
+    }
 }
 
 /// Axis for a time series
@@ -42,17 +125,57 @@ fn check_monotonic_increasing(arr: &Array1<Time>) -> bool {
 ///
 /// Generally, decimal year values are used throughout
 impl TimeAxis {
-    fn new(bounds: Array1<Time>) -> Self {
-        let is_monotonic = check_monotonic_increasing(&bounds);
-        assert!(is_monotonic);
+    fn new(bounds: Array1<Time>) -> RSCMResult<Self> {
+        if bounds.len() < 2 {
+            return Err(RSCMError::Error(format!(
+                "A time axis requires at least 2 bounds, got {}",
+                bounds.len()
+            )));
+        }
+        check_monotonic_increasing(&bounds)?;
+
+        Ok(Self {
+            bounds,
+            unit: TimeUnit::default(),
+        })
+    }
+
+    /// Set the unit of the time values
+    ///
+    /// The values are unchanged, see [`TimeAxis::to_unit`] to convert them.
+    pub fn with_unit(mut self, unit: TimeUnit) -> Self {
+        self.unit = unit;
+        self
+    }
 
-        Self { bounds }
+    /// Unit of the time values, years by default
+    pub fn unit(&self) -> TimeUnit {
+        self.unit
+    }
+
+    /// Convert the time values into a different unit
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use rscm_core::timeseries::{TimeAxis, TimeUnit};
+    /// let ta = TimeAxis::from_values(array![0.0, 1.0]).to_unit(TimeUnit::Second);
+    /// assert!((ta.at(1).unwrap() - 365.25 * 86400.0).abs() < 1e-6);
+    /// ```
+    pub fn to_unit(&self, unit: TimeUnit) -> Self {
+        let factor = self.unit.in_years() / unit.in_years();
+        Self::new(&self.bounds * factor)
+            .expect("Scaling preserves the order of the bounds")
+            .with_unit(unit)
     }
 
     /// Initialise using values
     ///
     /// Assumes that the size of the last time step is equal to the size of the previous time step
     ///
+    /// Panics if the values aren't valid, see [`TimeAxis::try_from_values`].
+    ///
     /// # Example
     ///
     /// ```rust
@@ -63,7 +186,34 @@ impl TimeAxis {
     /// assert_eq!(ta.at_bounds(2).unwrap(), expected);
     /// ```
     pub fn from_values(values: Array1<Time>) -> Self {
-        assert!(values.len() >= 2);
+        Self::try_from_values(values).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Initialise using values
+    ///
+    /// Returns an error if there are fewer than 2 values
+    /// or the values aren't strictly increasing.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use rscm_core::errors::RSCMError;
+    /// use rscm_core::timeseries::TimeAxis;
+    /// assert!(TimeAxis::try_from_values(array![1.0, 2.0]).is_ok());
+    /// assert!(matches!(
+    ///     TimeAxis::try_from_values(array![1.0, 3.0, 2.0]),
+    ///     Err(RSCMError::NonMonotonicTime(3.0, 2.0))
+    /// ));
+    /// assert!(TimeAxis::try_from_values(array![1.0]).is_err());
+    /// ```
+    pub fn try_from_values(values: Array1<Time>) -> RSCMResult<Self> {
+        if values.len() < 2 {
+            return Err(RSCMError::Error(format!(
+                "A time axis requires at least 2 values, got {}",
+                values.len()
+            )));
+        }
         let step = values[values.len() - 1] - values[values.len() - 2];
 
         let mut bounds = Array::zeros(values.len() + 1);
@@ -77,6 +227,8 @@ impl TimeAxis {
 
     /// Initialise using bounds
     ///
+    /// Panics if the bounds aren't valid, see [`TimeAxis::try_from_bounds`].
+    ///
     /// # Example
     ///
     /// ```rust
@@ -86,16 +238,93 @@ impl TimeAxis {
     /// assert_eq!(ta.len(), 3);
     /// ```
     pub fn from_bounds(bounds: Array1<Time>) -> Self {
-        assert!(bounds.len() > 1);
+        Self::try_from_bounds(bounds).unwrap_or_else(|err| panic!("{}", err))
+    }
 
+    /// Initialise using bounds
+    ///
+    /// Returns an error if there are fewer than 2 bounds
+    /// or the bounds aren't strictly increasing.
+    pub fn try_from_bounds(bounds: Array1<Time>) -> RSCMResult<Self> {
         Self::new(bounds)
     }
 
-    pub fn values(&self) -> ArrayView1<Time> {
+    /// Initialise using a series of contiguous periods, each with its own time step
+    ///
+    /// Each period is given as `(start, end, step)`.
+    /// This allows a model to be spun up using long time steps
+    /// before switching to shorter time steps for the period of interest.
+    ///
+    /// Panics if the periods aren't valid, see [`TimeAxis::try_piecewise`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rscm_core::timeseries::TimeAxis;
+    /// let ta = TimeAxis::piecewise(&[(1750.0, 1850.0, 10.0), (1850.0, 2100.0, 1.0)]);
+    /// assert_eq!(ta.len(), 10 + 250);
+    /// assert_eq!(ta.at_bounds(9).unwrap(), (1840.0, 1850.0));
+    /// assert_eq!(ta.at_bounds(10).unwrap(), (1850.0, 1851.0));
+    /// ```
+    pub fn piecewise(periods: &[(Time, Time, Time)]) -> Self {
+        Self::try_piecewise(periods).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Initialise using a series of contiguous periods, each with its own time step
+    ///
+    /// Returns an error if there are no periods, the periods aren't contiguous,
+    /// a period doesn't have a positive length and step
+    /// or a period isn't a whole number of steps.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rscm_core::timeseries::TimeAxis;
+    /// assert!(TimeAxis::try_piecewise(&[(1750.0, 1850.0, 10.0)]).is_ok());
+    /// assert!(TimeAxis::try_piecewise(&[(1750.0, 1855.0, 10.0)]).is_err());
+    /// assert!(TimeAxis::try_piecewise(&[]).is_err());
+    /// ```
+    pub fn try_piecewise(periods: &[(Time, Time, Time)]) -> RSCMResult<Self> {
+        let Some(&(first, _, _)) = periods.first() else {
+            return Err(RSCMError::Error("Expected at least one period".to_string()));
+        };
+
+        let mut bounds = vec![first];
+        for &(start, end, step) in periods {
+            if *bounds.last().unwrap() != start {
+                return Err(RSCMError::Error(format!(
+                    "Periods must be contiguous, got a period starting at {} after {}",
+                    start,
+                    bounds.last().unwrap()
+                )));
+            }
+            if (end <= start) || (step <= 0.0) {
+                return Err(RSCMError::Error(format!(
+                    "Periods must have a positive length and step, got {}-{} with a step of {}",
+                    start, end, step
+                )));
+            }
+            let n_steps = ((end - start) / step).round();
+            if (n_steps < 1.0) || ((start + n_steps * step - end).abs() > 1e-9 * step) {
+                return Err(RSCMError::Error(format!(
+                    "Period {}-{} isn't a whole number of steps of {}",
+                    start, end, step
+                )));
+            }
+
+            bounds.extend((1..n_steps as usize).map(|i| start + i as Time * step));
+            // Avoid accumulating rounding errors at the end of each period
+            bounds.push(end);
+        }
+
+        Self::try_from_bounds(Array1::from_vec(bounds))
+    }
+
+    pub fn values(&self) -> ArrayView1<'_, Time> {
         self.bounds.slice(s![0..self.len()])
     }
 
-    pub fn bounds(&self) -> ArrayView1<Time> {
+    pub fn bounds(&self) -> ArrayView1<'_, Time> {
         self.bounds.view()
     }
 
@@ -155,17 +384,55 @@ impl TimeAxis {
         }
     }
 
-    pub fn get_index(&self, time: Time) -> usize {
-        self.bounds
-            .as_slice()
-            .unwrap()
-            // Have to use binary_search_by as
-            .binary_search_by(|v| v.partial_cmp(&time).expect("Couldn't compare values"))
-            .unwrap()
+    /// Get the index of the time value which matches `time`
+    ///
+    /// Decimal-year arithmetic rarely reproduces bit-identical values so the comparison
+    /// is performed with an absolute tolerance of [`DEFAULT_TIME_TOLERANCE`].
+    /// See [`TimeAxis::get_index_with_tolerance`] to use a different tolerance.
+    ///
+    /// Returns `None` if no time value is close to `time`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use rscm_core::timeseries::TimeAxis;
+    /// let ta = TimeAxis::from_values(array![2000.0, 2000.1, 2000.2]);
+    /// assert_eq!(ta.get_index(2000.0 + 0.1 + 0.1), Some(2));
+    /// assert_eq!(ta.get_index(2000.15), None);
+    /// ```
+    pub fn get_index(&self, time: Time) -> Option<usize> {
+        self.get_index_with_tolerance(time, DEFAULT_TIME_TOLERANCE)
+    }
+
+    /// Get the index of the time value which is within `tolerance` of `time`
+    ///
+    /// If multiple values are within the tolerance, the index of the closest value is returned.
+    pub fn get_index_with_tolerance(&self, time: Time, tolerance: Time) -> Option<usize> {
+        let values = self.values();
+        let values = values.as_slice().unwrap();
+
+        // Index of the first value that is not less than the target
+        let idx = values.partition_point(|v| *v < time);
+
+        [idx.checked_sub(1), Some(idx)]
+            .into_iter()
+            .flatten()
+            .filter(|&i| i < values.len())
+            .filter(|&i| is_close!(values[i], time, rel_tol = 0.0, abs_tol = tolerance))
+            .min_by(|&a, &b| {
+                let dist_a = (values[a] - time).abs();
+                let dist_b = (values[b] - time).abs();
+                dist_a
+                    .partial_cmp(&dist_b)
+                    .expect("Couldn't compare values")
+            })
     }
 
     /// Check if the axis contains a given value
     ///
+    /// Values are compared using an absolute tolerance of [`DEFAULT_TIME_TOLERANCE`].
+    ///
     /// # Example
     ///
     /// ```rust
@@ -173,29 +440,147 @@ impl TimeAxis {
     /// use rscm_core::timeseries::TimeAxis;
     /// let ta = TimeAxis::from_values(array![1.0, 2.0, 3.0]);
     /// assert!(ta.contains(1.0));
+    /// assert!(ta.contains(1.0 + 1e-9));
     /// assert!(!ta.contains(27.0));
     /// ```
     pub fn contains(&self, value: Time) -> bool {
-        let mut found = false;
+        self.get_index(value).is_some()
+    }
 
-        for v in self.values().iter() {
-            if value == *v {
-                found = true;
-                break;
-            }
+    /// Check if the axis contains a value within `tolerance` of `value`
+    pub fn contains_with_tolerance(&self, value: Time, tolerance: Time) -> bool {
+        self.get_index_with_tolerance(value, tolerance).is_some()
+    }
+
+    /// Get the range of indices of the time values that fall within `[start, end]`
+    ///
+    /// Both ends of the window are inclusive (within [`DEFAULT_TIME_TOLERANCE`]).
+    /// Returns `None` if no values fall within the window.
+    pub fn window_indices(&self, start: Time, end: Time) -> Option<Range<usize>> {
+        let values = self.values();
+        let values = values.as_slice().unwrap();
+
+        let first = values.partition_point(|v| *v < start - DEFAULT_TIME_TOLERANCE);
+        let last = values.partition_point(|v| *v <= end + DEFAULT_TIME_TOLERANCE);
+
+        match first < last {
+            true => Some(first..last),
+            false => None,
         }
-        found
     }
+
+    /// Get the index of the time step which contains `time`
+    ///
+    /// Times before the first step or after the last step use the first or last step respectively.
+    pub fn step_index(&self, time: Time) -> usize {
+        let values = self.values();
+        let values = values.as_slice().unwrap();
+
+        values
+            .partition_point(|v| *v <= time + DEFAULT_TIME_TOLERANCE)
+            .clamp(1, values.len())
+            - 1
+    }
+
+    /// Check if each bound of `other` is also a bound of this axis or lies outside of it
+    ///
+    /// If not, a step of this axis contains parts of multiple steps of `other`
+    /// so converting values from `other` onto this axis loses information.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use numpy::ndarray::Array;
+    /// use rscm_core::timeseries::TimeAxis;
+    /// let annual = TimeAxis::from_values(Array::range(2000.0, 2010.0, 1.0));
+    /// let monthly = TimeAxis::from_values(Array::range(2000.0, 2010.0, 1.0 / 12.0));
+    /// assert!(monthly.is_aligned_with(&annual));
+    /// assert!(!annual.is_aligned_with(&monthly));
+    /// ```
+    pub fn is_aligned_with(&self, other: &TimeAxis) -> bool {
+        other.bounds().iter().all(|bound| {
+            let index = self.step_index(*bound);
+            let (start, end) = self.at_bounds(index).unwrap();
+            *bound <= start + DEFAULT_TIME_TOLERANCE || *bound >= end - DEFAULT_TIME_TOLERANCE
+        })
+    }
+
+    /// Create a new time axis containing a subset of the time steps
+    ///
+    /// The bounds of the selected time steps are preserved,
+    /// including the upper bound of the last selected step.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use rscm_core::timeseries::TimeAxis;
+    /// let ta = TimeAxis::from_values(array![1.0, 2.0, 3.0, 4.0]);
+    /// let sliced = ta.slice(1..3);
+    /// assert_eq!(sliced.bounds(), array![2.0, 3.0, 4.0]);
+    /// ```
+    pub fn slice(&self, indices: Range<usize>) -> Self {
+        assert!(!indices.is_empty() && indices.end <= self.len());
+
+        Self::new(
+            self.bounds
+                .slice(s![indices.start..indices.end + 1])
+                .to_owned(),
+        )
+        .expect("A subset of the bounds is valid")
+        .with_unit(self.unit)
+    }
+}
+
+/// How values are converted onto a time axis with shorter time steps
+///
+/// This is used when a variable is required at a finer resolution than it is provided,
+/// for example, monthly values of annual data.
+#[pyclass]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisaggregationStrategy {
+    /// Interpolate using the timeseries' interpolation strategy
+    #[default]
+    Interpolate,
+    /// Repeat the value of the step which contains the start of each new step
+    ///
+    /// Appropriate for rates and other intensive quantities.
+    Repeat,
+    /// Split the value of each step between the new steps in proportion to their length
+    ///
+    /// Appropriate for quantities which are totals over a time step.
+    Split,
+}
+
+/// How values are converted onto a new time axis by [`Timeseries::regrid`]
+#[pyclass]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RegridMethod {
+    /// Interpolate the value at the start of each new step
+    /// using the timeseries' interpolation strategy
+    ///
+    /// Appropriate for state variables such as concentrations or temperatures.
+    #[default]
+    Interpolate,
+    /// Average the values over each new step, treating them as constant over each step
+    ///
+    /// The integral over time is preserved so this is appropriate for fluxes,
+    /// e.g. emissions, which are redistributed without creating or destroying mass.
+    Conservative,
 }
 
 /// A contiguous set of values
 ///
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// The [`Debug`] and [`Display`] representations summarise the values
+/// (see [`Timeseries::summary`]).
+/// The alternate debug form (`{:#?}`) includes all of the values.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Timeseries<T>
 where
     T: Float,
 {
-    units: String,
+    units: Unit,
     values: Array1<T>,
     // Using a reference counted time axis to avoid having to maintain multiple clones of the
     // time axis.
@@ -203,6 +588,9 @@ where
     /// Latest value specified
     latest: isize,
     interpolation_strategy: InterpolationStrategy,
+    /// Segment used by the previous call to `at_time`
+    #[serde(skip)]
+    segment_cache: SegmentCache,
 }
 
 impl<T> Timeseries<T>
@@ -225,11 +613,12 @@ where
             .unwrap();
 
         Self {
-            units,
+            units: Unit::from(units),
             values,
             time_axis,
             latest,
             interpolation_strategy,
+            segment_cache: SegmentCache::new(),
         }
     }
 
@@ -263,13 +652,111 @@ where
     }
 
     /// Set a value at time_index
+    ///
+    /// Panics if `time_index` is out of bounds, see [`Timeseries::try_set`].
     pub fn set(&mut self, time_index: usize, value: T) {
-        assert!(time_index < self.len());
+        self.try_set(time_index, value)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Set a value at time_index
+    ///
+    /// Returns an error if `time_index` is out of bounds.
+    pub fn try_set(&mut self, time_index: usize, value: T) -> RSCMResult<()> {
+        if time_index >= self.len() {
+            return Err(RSCMError::IndexOutOfBounds(time_index, self.len()));
+        }
         self.values[time_index] = value;
 
         if !value.is_nan() {
             self.latest = max(self.latest, time_index.to_isize().unwrap())
         }
+        Ok(())
+    }
+
+    /// Set consecutive values starting at `start_index`
+    ///
+    /// This is equivalent to calling [`Timeseries::set`] for each value,
+    /// but is more efficient when writing a history.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use numpy::ndarray::Array;
+    /// use rscm_core::timeseries::Timeseries;
+    ///
+    /// let mut timeseries = Timeseries::from_values(Array::from_elem(4, f64::NAN), Array::range(2000.0, 2004.0, 1.0));
+    /// timeseries.set_range(1, array![1.0, 2.0].view()).unwrap();
+    ///
+    /// assert_eq!(timeseries.at(2), Some(2.0));
+    /// assert_eq!(*timeseries.latest(), 2);
+    /// assert!(timeseries.set_range(3, array![3.0, 4.0].view()).is_err());
+    /// ```
+    pub fn set_range(&mut self, start_index: usize, values: ArrayView1<T>) -> RSCMResult<()> {
+        let end_index = start_index + values.len();
+        if end_index > self.len() {
+            return Err(RSCMError::Error(format!(
+                "Cannot set {} values starting at index {} of a timeseries with {} values",
+                values.len(),
+                start_index,
+                self.len()
+            )));
+        }
+        self.values
+            .slice_mut(s![start_index..end_index])
+            .assign(&values);
+
+        if let Some(index) = values.iter().rposition(|v| !v.is_nan()) {
+            self.latest = max(self.latest, (start_index + index).to_isize().unwrap())
+        }
+        Ok(())
+    }
+
+    /// Clear the values after `time_index`
+    ///
+    /// The cleared values are set to NaN and the latest valid timestep is moved back
+    /// to `time_index` (if it was later), unlike setting NaN values using [`Timeseries::set`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use rscm_core::timeseries::Timeseries;
+    ///
+    /// let mut timeseries: Timeseries<f64> = Timeseries::from_values(array![1.0, 2.0, 3.0, 4.0], array![2000.0, 2001.0, 2002.0, 2003.0]);
+    /// timeseries.truncate_after(1);
+    ///
+    /// assert!(timeseries.at(2).unwrap().is_nan());
+    /// assert_eq!(*timeseries.latest(), 1);
+    /// assert_eq!(timeseries.latest_value(), Some(2.0));
+    /// ```
+    pub fn truncate_after(&mut self, time_index: usize) {
+        if time_index + 1 < self.len() {
+            self.values.slice_mut(s![time_index + 1..]).fill(T::nan());
+        }
+        self.latest = self.latest.min(time_index.to_isize().unwrap());
+    }
+
+    /// Copy the values of another timeseries with the same time axis
+    ///
+    /// The values are converted into the units of this timeseries.
+    /// The interpolation strategy of this timeseries is unchanged.
+    pub fn assign_from(&mut self, other: &Timeseries<T>) -> RSCMResult<()> {
+        let same_axis = Arc::ptr_eq(&self.time_axis, &other.time_axis)
+            || (self.time_axis.unit() == other.time_axis.unit()
+                && self.time_axis.bounds() == other.time_axis.bounds());
+        if !same_axis {
+            return Err(RSCMError::Error(format!(
+                "Time axes differ: {:?} and {:?}",
+                self.time_axis, other.time_axis
+            )));
+        }
+
+        match self.units == other.units {
+            true => self.set_range(0, other.values()),
+            false => self.set_range(0, other.convert_unit(self.units.as_str())?.values()),
+        }
     }
 
     /// Get the index of the latest valid timestep
@@ -313,10 +800,15 @@ where
     ///
     /// This method interpolates using the current interpolation strategy to determine
     /// the value at `time`.
+    /// The segment containing `time` is remembered so that querying increasing times
+    /// (as occurs during a model run) avoids searching the entire time axis.
     pub fn at_time(&self, time: Time) -> RSCMResult<T> {
-        let interp = self.interpolator();
-
-        interp.interpolate(time)
+        self.interpolation_strategy.interpolate_cached(
+            &self.time_axis.values(),
+            &self.values.view(),
+            time,
+            &self.segment_cache,
+        )
     }
 
     /// Get the value of the timeseries at a given time index
@@ -347,7 +839,8 @@ where
     /// The interpolation strategy will determine how the interpolation is performed or if
     /// extrapolation is allowed.
     ///
-    /// This currently panics if the interpolation fails for any reason.
+    /// This panics if the interpolation fails for any reason,
+    /// see [`Timeseries::try_interpolate_into`].
     ///
     /// # Examples
     ///
@@ -365,46 +858,859 @@ where
     /// assert_eq!(new_timeseries.at(1).unwrap(), 1.5);
     /// ```
     pub fn interpolate_into(self, new_time_axis: Arc<TimeAxis>) -> Self {
-        let mut values = Array1::zeros(new_time_axis.len());
-        let interp = self.interpolator();
+        self.try_interpolate_into(new_time_axis).unwrap()
+    }
 
-        zip(new_time_axis.values().iter(), values.iter_mut()).for_each(|(t, value)| {
-            *value = interp.interpolate(*t).unwrap();
-        });
+    /// Interpolate a timeseries onto a new time axis
+    ///
+    /// An error is returned if any of the values can't be interpolated,
+    /// for example, if the new time axis extends beyond the timeseries and the
+    /// interpolation strategy doesn't allow extrapolation.
+    pub fn try_interpolate_into(self, new_time_axis: Arc<TimeAxis>) -> RSCMResult<Self> {
+        let values = {
+            let interp = self.interpolator();
+            new_time_axis
+                .values()
+                .iter()
+                .map(|t| interp.interpolate(*t))
+                .collect::<RSCMResult<Array1<T>>>()?
+        };
+
+        Ok(Self::new(
+            values,
+            new_time_axis,
+            self.units.into(),
+            self.interpolation_strategy,
+        ))
+    }
+
+    /// Convert a timeseries onto a time axis with shorter time steps
+    ///
+    /// Unlike [`Timeseries::interpolate_into`], `strategy` defines how the values of each step
+    /// are distributed between the new steps.
+    /// Steps of the new time axis which aren't contained within a single step of the
+    /// timeseries can't be converted exactly (see [`TimeAxis::is_aligned_with`]).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use numpy::array;
+    /// use rscm_core::timeseries::{DisaggregationStrategy, TimeAxis, Timeseries};
+    ///
+    /// let annual = Timeseries::from_values(array![12.0, 24.0], array![2000.0, 2001.0]);
+    /// let quarterly = Arc::new(TimeAxis::from_values(array![2000.0, 2000.25, 2000.5, 2000.75, 2001.0]));
+    ///
+    /// let repeated = annual.clone().disaggregate_into(quarterly.clone(), DisaggregationStrategy::Repeat);
+    /// assert_eq!(repeated.values(), array![12.0, 12.0, 12.0, 12.0, 24.0]);
+    ///
+    /// let split = annual.disaggregate_into(quarterly, DisaggregationStrategy::Split);
+    /// assert_eq!(split.values(), array![3.0, 3.0, 3.0, 3.0, 6.0]);
+    /// ```
+    pub fn disaggregate_into(
+        self,
+        new_time_axis: Arc<TimeAxis>,
+        strategy: DisaggregationStrategy,
+    ) -> Self {
+        if strategy == DisaggregationStrategy::Interpolate {
+            return self.interpolate_into(new_time_axis);
+        }
+
+        let values = (0..new_time_axis.len())
+            .map(|i| {
+                let (start, end) = new_time_axis.at_bounds(i).unwrap();
+                let index = self.time_axis.step_index(start);
+                let value = self.values[index];
+                match strategy {
+                    DisaggregationStrategy::Split => {
+                        let (step_start, step_end) = self.time_axis.at_bounds(index).unwrap();
+                        let fraction = (end - start) / (step_end - step_start);
+                        value * <T as From<Time>>::from(fraction)
+                    }
+                    _ => value,
+                }
+            })
+            .collect();
 
         Self::new(
             values,
             new_time_axis,
-            self.units,
+            self.units.into(),
             self.interpolation_strategy,
         )
     }
 
-    pub fn values(&self) -> ArrayView1<T> {
+    /// Convert a timeseries onto a new time axis
+    ///
+    /// Unlike [`Timeseries::disaggregate_into`], the new time axis can have longer or shorter
+    /// time steps than the timeseries, or steps which don't align with those of the timeseries.
+    /// See [`RegridMethod`] for how the new values are calculated.
+    ///
+    /// A time axis with a different unit is converted into the unit of the new time axis first.
+    /// Conservative regridding returns an error if the new time axis extends beyond the
+    /// bounds of the timeseries.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use numpy::array;
+    /// use numpy::ndarray::Array;
+    /// use rscm_core::timeseries::{RegridMethod, TimeAxis, Timeseries};
+    ///
+    /// let annual = Timeseries::from_values(array![10.0, 12.0], array![2000.0, 2001.0]);
+    ///
+    /// let quarterly = Arc::new(TimeAxis::from_values(Array::range(2000.0, 2002.0, 0.25)));
+    /// let regridded = annual.regrid(quarterly, RegridMethod::Conservative).unwrap();
+    /// assert_eq!(regridded.values(), array![10.0, 10.0, 10.0, 10.0, 12.0, 12.0, 12.0, 12.0]);
+    ///
+    /// let biennial = Arc::new(TimeAxis::from_bounds(array![2000.0, 2002.0]));
+    /// let regridded = annual.regrid(biennial, RegridMethod::Conservative).unwrap();
+    /// assert_eq!(regridded.values(), array![11.0]);
+    /// ```
+    pub fn regrid(&self, new_time_axis: Arc<TimeAxis>, method: RegridMethod) -> RSCMResult<Self> {
+        let timeseries = match self.time_axis.unit() == new_time_axis.unit() {
+            true => self.clone(),
+            false => self.to_time_unit(new_time_axis.unit()),
+        };
+        match method {
+            RegridMethod::Interpolate => timeseries.try_interpolate_into(new_time_axis),
+            RegridMethod::Conservative => timeseries.regrid_conservative(new_time_axis),
+        }
+    }
+
+    fn regrid_conservative(self, new_time_axis: Arc<TimeAxis>) -> RSCMResult<Self> {
+        let bounds = self.time_axis.bounds();
+        let (first, last) = (bounds[0], bounds[bounds.len() - 1]);
+        let new_bounds = new_time_axis.bounds();
+        let (new_first, new_last) = (new_bounds[0], new_bounds[new_bounds.len() - 1]);
+        if new_first < first - DEFAULT_TIME_TOLERANCE || new_last > last + DEFAULT_TIME_TOLERANCE {
+            return Err(RSCMError::Error(format!(
+                "Can't conservatively regrid a timeseries covering [{}, {}) onto [{}, {})",
+                first, last, new_first, new_last
+            )));
+        }
+
+        let values = (0..new_time_axis.len())
+            .map(|i| {
+                let (start, end) = new_time_axis.at_bounds(i).unwrap();
+                let total = (self.time_axis.step_index(start)..self.len())
+                    .map(|index| (index, self.time_axis.at_bounds(index).unwrap()))
+                    .take_while(|(_, (step_start, _))| *step_start < end)
+                    .fold(T::zero(), |total, (index, (step_start, step_end))| {
+                        let overlap = end.min(step_end) - start.max(step_start);
+                        total + self.values[index] * <T as From<Time>>::from(overlap.max(0.0))
+                    });
+                total / <T as From<Time>>::from(end - start)
+            })
+            .collect();
+
+        Ok(Self::new(
+            values,
+            new_time_axis,
+            self.units.into(),
+            self.interpolation_strategy,
+        ))
+    }
+
+    pub fn values(&self) -> ArrayView1<'_, T> {
         self.values.view()
     }
 
     pub fn units(&self) -> &str {
+        self.units.as_str()
+    }
+
+    /// The parsed units of the timeseries
+    pub fn unit(&self) -> &Unit {
         &self.units
     }
 
+    /// Get the subset of the timeseries within a time window
+    ///
+    /// The resulting timeseries contains the values for the time steps that start within
+    /// `[start, end]` (inclusive) and has a correspondingly truncated time axis.
+    /// The values are copied, but only for the time steps within the window.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use numpy::ndarray::Array;
+    /// use rscm_core::timeseries::Timeseries;
+    ///
+    /// let timeseries = Timeseries::from_values(array![1.0, 2.0, 3.0, 4.0, 5.0], Array::range(2000.0, 2050.0, 10.0));
+    ///
+    /// let window = timeseries.slice(2010.0, 2030.0).unwrap();
+    /// assert_eq!(window.values(), array![2.0, 3.0, 4.0]);
+    /// assert_eq!(window.time_axis().bounds(), array![2010.0, 2020.0, 2030.0, 2040.0]);
+    ///
+    /// assert!(timeseries.slice(2060.0, 2070.0).is_err());
+    /// ```
+    pub fn slice(&self, start: Time, end: Time) -> RSCMResult<Self> {
+        if start > end {
+            return Err(RSCMError::Error(format!(
+                "Start of window ({}) is after the end ({})",
+                start, end
+            )));
+        }
+        let indices = self.time_axis.window_indices(start, end).ok_or_else(|| {
+            RSCMError::Error(format!(
+                "No values within the window [{}, {}]. Time axis covers [{}, {})",
+                start,
+                end,
+                self.time_axis.first(),
+                self.time_axis.last()
+            ))
+        })?;
+
+        Ok(Self::new(
+            self.values.slice(s![indices.clone()]).to_owned(),
+            Arc::new(self.time_axis.slice(indices)),
+            self.units.to_string(),
+            self.interpolation_strategy.clone(),
+        ))
+    }
+
     pub fn time_axis(&self) -> Arc<TimeAxis> {
         self.time_axis.clone()
     }
+
+    pub fn interpolation_strategy(&self) -> &InterpolationStrategy {
+        &self.interpolation_strategy
+    }
+
+    /// Convert the time axis into a different unit
+    ///
+    /// The values are unchanged.
+    pub fn to_time_unit(&self, unit: TimeUnit) -> Self {
+        Self::new(
+            self.values.clone(),
+            Arc::new(self.time_axis.to_unit(unit)),
+            self.units.to_string(),
+            self.interpolation_strategy.clone(),
+        )
+    }
+
+    /// Number of NaN values
+    pub fn nan_count(&self) -> usize {
+        self.values.iter().filter(|v| v.is_nan()).count()
+    }
+
+    /// A compact text plot of the values using block characters
+    ///
+    /// The values are averaged into at most `width` groups
+    /// which are scaled between the smallest and largest group.
+    /// Groups containing only NaN values are shown as a space.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use numpy::ndarray::Array;
+    /// use rscm_core::timeseries::Timeseries;
+    ///
+    /// let timeseries = Timeseries::from_values(
+    ///     array![0.0, 1.0, 2.0, 3.0, f64::NAN, 7.0],
+    ///     Array::range(2000.0, 2006.0, 1.0),
+    /// );
+    /// assert_eq!(timeseries.sparkline(10), "▁▂▃▄ █");
+    /// assert_eq!(timeseries.sparkline(3), "▁▃█");
+    /// ```
+    pub fn sparkline(&self, width: usize) -> String {
+        const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+        let width = width.clamp(1, self.len());
+        let groups: Vec<Option<T>> = (0..width)
+            .map(|i| {
+                let start = i * self.len() / width;
+                let end = (i + 1) * self.len() / width;
+                let valid: Vec<T> = self
+                    .values
+                    .slice(s![start..end])
+                    .iter()
+                    .filter(|v| !v.is_nan())
+                    .cloned()
+                    .collect();
+                match valid.is_empty() {
+                    true => None,
+                    false => Some(
+                        valid.iter().fold(T::zero(), |acc, v| acc + *v)
+                            / <T as num::NumCast>::from(valid.len()).unwrap(),
+                    ),
+                }
+            })
+            .collect();
+
+        let valid = groups.iter().flatten();
+        let min = valid.clone().fold(T::infinity(), |a, b| a.min(*b));
+        let max = valid.fold(T::neg_infinity(), |a, b| a.max(*b));
+        let top = <T as num::NumCast>::from(LEVELS.len() - 1).unwrap();
+
+        groups
+            .iter()
+            .map(|group| match group {
+                None => ' ',
+                Some(_) if max <= min => LEVELS[0],
+                Some(value) => {
+                    let level = ((*value - min) / (max - min) * top).round();
+                    LEVELS[level.to_usize().unwrap_or(0).min(LEVELS.len() - 1)]
+                }
+            })
+            .collect()
+    }
+
+    /// A single line description of the timeseries
+    ///
+    /// This includes the units, the range of the time axis,
+    /// the first and last values and the number of NaN values.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use numpy::ndarray::Array;
+    /// use rscm_core::timeseries::Timeseries;
+    ///
+    /// let timeseries = Timeseries::from_values(array![1.0, f64::NAN, 3.0], Array::range(2000.0, 2030.0, 10.0));
+    /// assert_eq!(
+    ///     timeseries.summary(),
+    ///     "units: \"\", len: 3, range: [2000.0, 2030.0), first: 1.0, last: 3.0, nan: 1"
+    /// );
+    /// ```
+    pub fn summary(&self) -> String
+    where
+        T: Debug,
+    {
+        format!(
+            "units: {:?}, {}, first: {:?}, last: {:?}, nan: {}",
+            self.units.as_str(),
+            self.time_axis,
+            self.values[0],
+            self.values[self.values.len() - 1],
+            self.nan_count()
+        )
+    }
+
+    /// Convert the values of the timeseries into different units
+    ///
+    /// Units are converted using the global unit registry (see [`crate::units`]).
+    /// Returns an error if the units aren't compatible.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use rscm_core::interpolate::strategies::{InterpolationStrategy, LinearSplineStrategy};
+    /// use rscm_core::timeseries::{TimeAxis, Timeseries};
+    /// use std::sync::Arc;
+    ///
+    /// let timeseries = Timeseries::new(
+    ///     array![1.0, 2.0],
+    ///     Arc::new(TimeAxis::from_values(array![2000.0, 2001.0])),
+    ///     "GtC / yr".to_string(),
+    ///     InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+    /// );
+    ///
+    /// let converted = timeseries.convert_unit("MtC / yr").unwrap();
+    /// assert_eq!(converted.values(), array![1000.0, 2000.0]);
+    /// assert_eq!(converted.units(), "MtC / yr");
+    ///
+    /// assert!(timeseries.convert_unit("GtCO2 / yr").is_err());
+    /// assert!(timeseries.convert_unit_with_contexts("GtCO2 / yr", &["CO2"]).is_ok());
+    /// ```
+    pub fn convert_unit(&self, unit: &str) -> RSCMResult<Self> {
+        self.convert_unit_with_contexts::<&str>(unit, &[])
+    }
+
+    /// Convert the values of the timeseries into different units using conversion contexts
+    ///
+    /// `contexts` enable conversions between different dimensions,
+    /// e.g. "CO2" enables conversions between GtC and GtCO2.
+    pub fn convert_unit_with_contexts<S: AsRef<str>>(
+        &self,
+        unit: &str,
+        contexts: &[S],
+    ) -> RSCMResult<Self> {
+        let factor: T =
+            crate::units::conversion_factor(self.units.as_str(), unit, contexts)?.into();
+
+        Ok(Self::new(
+            self.values.mapv(|v| v * factor),
+            self.time_axis.clone(),
+            unit.to_string(),
+            self.interpolation_strategy.clone(),
+        ))
+    }
+
+    /// Length of each time step in years
+    fn step_lengths(&self) -> Array1<T> {
+        let in_years = self.time_axis.unit().in_years();
+        (0..self.len())
+            .map(|i| {
+                let (start, end) = self.time_axis.at_bounds(i).unwrap();
+                <T as From<Time>>::from((end - start) * in_years)
+            })
+            .collect()
+    }
+
+    /// Cumulative integral of the timeseries over time
+    ///
+    /// Each value of the result is the integral from the start of the time axis
+    /// to the start of that time step, so the first value is always zero.
+    /// The integral of each step is exact for the interpolation strategy of the timeseries,
+    /// i.e. a linearly interpolated timeseries is integrated using the trapezoidal rule
+    /// and a timeseries using previous-value interpolation is treated as constant over each step.
+    ///
+    /// Rates are expressed per year (see [`TimeUnit`]) so the units of the result
+    /// are multiplied by "yr", e.g. "GtC / yr" becomes "GtC".
+    /// The result is linearly interpolated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use rscm_core::interpolate::strategies::{InterpolationStrategy, PreviousStrategy};
+    /// use rscm_core::timeseries::{TimeAxis, Timeseries};
+    /// use std::sync::Arc;
+    ///
+    /// let emissions = Timeseries::new(
+    ///     array![10.0, 11.0, 12.0],
+    ///     Arc::new(TimeAxis::from_values(array![2000.0, 2001.0, 2003.0])),
+    ///     "GtC / yr".to_string(),
+    ///     InterpolationStrategy::from(PreviousStrategy::new(true)),
+    /// );
+    ///
+    /// let cumulative = emissions.integrate();
+    /// assert_eq!(cumulative.values(), array![0.0, 10.0, 32.0]);
+    /// assert_eq!(cumulative.units(), "GtC");
+    /// ```
+    pub fn integrate(&self) -> Self {
+        // The midpoints are all within the time axis, but the linear strategy
+        // treats the final value as a bound so extrapolation must be allowed
+        let interp = Interp1d::new(
+            self.time_axis.values(),
+            self.values.view(),
+            self.interpolation_strategy
+                .clone()
+                .with_extrapolation(ExtrapolationPolicy::Extrapolate),
+        );
+        let step_lengths = self.step_lengths();
+
+        let mut total = T::zero();
+        let mut values = Array1::zeros(self.len());
+        for i in 1..self.len() {
+            let (start, end) = self.time_axis.at_bounds(i - 1).unwrap();
+            // The midpoint rule is exact for both linear and piecewise-constant interpolation
+            let midpoint = interp.interpolate(0.5 * (start + end)).unwrap();
+            total = total + midpoint * step_lengths[i - 1];
+            values[i] = total;
+        }
+
+        Self::new(
+            values,
+            self.time_axis.clone(),
+            self.units.multiplied_by(&Unit::from("yr"), 1).into(),
+            LinearSplineStrategy::new(
+                self.interpolation_strategy
+                    .extrapolation()
+                    .allows_extrapolation(),
+            )
+            .into(),
+        )
+    }
+
+    /// Rate of change of the timeseries over each time step
+    ///
+    /// Each value of the result is the change in the interpolated timeseries between the start
+    /// and end of a time step divided by the length of the step.
+    /// The end of the final time step is calculated using the interpolation strategy,
+    /// so an error is returned if the strategy doesn't allow extrapolation.
+    ///
+    /// The result uses previous-value interpolation as the rate is constant over each step,
+    /// which makes this the inverse of [`Timeseries::integrate`] for piecewise-constant rates.
+    /// Rates are expressed per year (see [`TimeUnit`]) so the units of the result
+    /// are divided by "yr", e.g. "GtC" becomes "GtC / yr".
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use rscm_core::timeseries::Timeseries;
+    ///
+    /// let timeseries = Timeseries::from_values(array![0.0, 10.0, 30.0], array![2000.0, 2001.0, 2003.0]);
+    ///
+    /// let rate = timeseries.differentiate().unwrap();
+    /// assert_eq!(rate.values(), array![10.0, 10.0, 10.0]);
+    /// assert_eq!(rate.units(), "1 / yr");
+    /// ```
+    pub fn differentiate(&self) -> RSCMResult<Self> {
+        let step_lengths = self.step_lengths();
+        let last = self.interpolator().interpolate(*self.time_axis.last())?;
+
+        let ends = self
+            .values
+            .slice(s![1..])
+            .iter()
+            .cloned()
+            .chain([last])
+            .collect::<Array1<T>>();
+        let values = (&ends - &self.values) / &step_lengths;
+
+        Ok(Self::new(
+            values,
+            self.time_axis.clone(),
+            self.units.multiplied_by(&Unit::from("yr"), -1).into(),
+            PreviousStrategy::new(
+                self.interpolation_strategy
+                    .extrapolation()
+                    .allows_extrapolation(),
+            )
+            .into(),
+        ))
+    }
+}
+
+impl Timeseries<FloatValue> {
+    /// Write the timeseries as CSV
+    ///
+    /// The units, interpolation strategy and end of the final time step are written as
+    /// `#` comment lines before a `time,value` header so that [`Timeseries::from_csv`]
+    /// reproduces the timeseries exactly.
+    /// Missing values are written as `NaN`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use rscm_core::timeseries::Timeseries;
+    ///
+    /// let timeseries = Timeseries::from_values(array![1.0, 2.5], array![2000.0, 2001.0]);
+    /// assert_eq!(
+    ///     timeseries.to_csv(),
+    ///     "# units: \n# interpolation: Linear\n# end: 2002\ntime,value\n2000,1\n2001,2.5\n"
+    /// );
+    /// ```
+    pub fn to_csv(&self) -> String {
+        let interpolation = match serde_json::to_value(&self.interpolation_strategy) {
+            Ok(serde_json::Value::String(name)) => name,
+            Ok(value) => value.to_string(),
+            Err(_) => unreachable!("Interpolation strategies are always serialisable"),
+        };
+
+        let mut csv = format!(
+            "# units: {}\n# interpolation: {}\n# end: {}\ntime,value\n",
+            self.units,
+            interpolation,
+            self.time_axis.last()
+        );
+        zip(self.time_axis.values(), self.values.iter()).for_each(|(time, value)| {
+            csv.push_str(&format!("{},{}\n", time, value));
+        });
+        csv
+    }
+
+    /// Read a timeseries from CSV
+    ///
+    /// The CSV contains a `time,value` header followed by one row per time step.
+    /// Header lines starting with `#` are optional and have the form `# key: value`:
+    ///
+    /// * `units`: units of the values. Defaults to no units
+    /// * `interpolation`: name of the interpolation strategy (`Linear`, `Next` or `Previous`)
+    ///   or the JSON representation of a strategy with a different extrapolation policy.
+    ///   Defaults to `Linear`
+    /// * `end`: the end of the final time step.
+    ///   If missing, the final time step is assumed to be the same length as the previous one
+    ///
+    /// Empty and `NaN` values are read as missing values.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use numpy::array;
+    /// use rscm_core::timeseries::Timeseries;
+    ///
+    /// let csv = "# units: GtC / yr\ntime,value\n2000,10.0\n2010,\n2020,12.5\n";
+    /// let timeseries = Timeseries::from_csv(csv).unwrap();
+    ///
+    /// assert_eq!(timeseries.units(), "GtC / yr");
+    /// assert_eq!(timeseries.time_axis().bounds(), array![2000.0, 2010.0, 2020.0, 2030.0]);
+    /// assert!(timeseries.at(1).unwrap().is_nan());
+    /// ```
+    pub fn from_csv(csv: &str) -> RSCMResult<Self> {
+        let mut units = "".to_string();
+        let mut interpolation_strategy =
+            InterpolationStrategy::from(LinearSplineStrategy::new(true));
+        let mut end: Option<Time> = None;
+        let mut header_seen = false;
+        let mut times = Vec::new();
+        let mut values = Vec::new();
+
+        let parse_float = |text: &str, line_number: usize| -> RSCMResult<FloatValue> {
+            text.parse::<FloatValue>().map_err(|_| {
+                RSCMError::Error(format!(
+                    "Could not parse {:?} as a number on line {}",
+                    text, line_number
+                ))
+            })
+        };
+
+        for (index, line) in csv.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix('#') {
+                let Some((key, value)) = comment.split_once(':') else {
+                    continue;
+                };
+                let value = value.trim();
+                match key.trim() {
+                    "units" => units = value.to_string(),
+                    "interpolation" => {
+                        let json = match value.starts_with('{') {
+                            true => value.to_string(),
+                            false => format!("{:?}", value),
+                        };
+                        interpolation_strategy = serde_json::from_str(&json).map_err(|e| {
+                            RSCMError::Error(format!(
+                                "Invalid interpolation strategy on line {}: {}",
+                                line_number, e
+                            ))
+                        })?;
+                    }
+                    "end" => end = Some(parse_float(value, line_number)?),
+                    _ => {}
+                }
+                continue;
+            }
+
+            let columns: Vec<&str> = line.split(',').map(|c| c.trim()).collect();
+            if !header_seen {
+                if columns != ["time", "value"] {
+                    return Err(RSCMError::Error(format!(
+                        "Expected a \"time,value\" header on line {}, found {:?}",
+                        line_number, line
+                    )));
+                }
+                header_seen = true;
+                continue;
+            }
+            if columns.len() != 2 {
+                return Err(RSCMError::Error(format!(
+                    "Expected 2 columns on line {}, found {}",
+                    line_number,
+                    columns.len()
+                )));
+            }
+
+            times.push(parse_float(columns[0], line_number)?);
+            values.push(match columns[1] {
+                "" => FloatValue::NAN,
+                value => parse_float(value, line_number)?,
+            });
+        }
+
+        if times.len() < 2 {
+            return Err(RSCMError::Error(format!(
+                "At least 2 rows are required to define a timeseries, found {}",
+                times.len()
+            )));
+        }
+        if times.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(RSCMError::Error(
+                "Time values must be strictly increasing".to_string(),
+            ));
+        }
+
+        let time_axis = match end {
+            Some(end) => {
+                if end <= times[times.len() - 1] {
+                    return Err(RSCMError::Error(format!(
+                        "End of the time axis ({}) must be after the last time value ({})",
+                        end,
+                        times[times.len() - 1]
+                    )));
+                }
+                times.push(end);
+                TimeAxis::from_bounds(Array1::from_vec(times))
+            }
+            None => TimeAxis::from_values(Array1::from_vec(times)),
+        };
+
+        Ok(Self::new(
+            Array1::from_vec(values),
+            Arc::new(time_axis),
+            units,
+            interpolation_strategy,
+        ))
+    }
+}
+
+impl<T> Debug for Timeseries<T>
+where
+    T: Float + From<Time> + Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match f.alternate() {
+            true => f
+                .debug_struct("Timeseries")
+                .field("units", &self.units.as_str())
+                .field("values", &self.values.to_vec())
+                .field("time_axis", &self.time_axis)
+                .field("latest", &self.latest)
+                .field("interpolation_strategy", &self.interpolation_strategy)
+                .finish(),
+            false => write!(f, "Timeseries {{ {} }}", self.summary()),
+        }
+    }
+}
+
+impl<T> Display for Timeseries<T>
+where
+    T: Float + From<Time> + Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.summary())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::interpolate::strategies::{InterpolationStrategy, PreviousStrategy};
-    use is_close::is_close;
+    use crate::interpolate::strategies::InterpolationStrategy;
 
     #[test]
-    #[should_panic]
+    fn piecewise() {
+        let ta = TimeAxis::piecewise(&[
+            (1750.0, 1850.0, 10.0),
+            (1850.0, 1851.0, 0.25),
+            (1851.0, 1853.0, 1.0),
+        ]);
+        assert_eq!(
+            ta.bounds().to_vec(),
+            [
+                Array::range(1750.0, 1850.0, 10.0).to_vec(),
+                vec![1850.0, 1850.25, 1850.5, 1850.75, 1851.0, 1852.0, 1853.0],
+            ]
+            .concat()
+        );
+        assert_eq!(
+            TimeAxis::piecewise(&[(2000.0, 2001.0, 0.1)])
+                .bounds()
+                .to_vec(),
+            Array::linspace(2000.0, 2001.0, 11).to_vec()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Periods must be contiguous")]
+    fn piecewise_gap() {
+        TimeAxis::piecewise(&[(1750.0, 1850.0, 10.0), (1900.0, 2000.0, 1.0)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Period 1750-1855 isn't a whole number of steps of 10")]
+    fn piecewise_partial_step() {
+        TimeAxis::piecewise(&[(1750.0, 1855.0, 10.0), (1855.0, 2000.0, 1.0)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Time values must be strictly increasing, got 2020 followed by 1")]
     fn check_monotonic_values() {
         Timeseries::from_values(array![1.0, 2.0, 3.0], array![2020.0, 1.0, 2021.0,]);
     }
 
+    #[test]
+    fn set_out_of_bounds() {
+        let mut timeseries = Timeseries::from_values(array![1.0, 2.0], array![2020.0, 2021.0]);
+
+        timeseries.try_set(1, 3.0).unwrap();
+        assert_eq!(timeseries.at(1), Some(3.0));
+        assert!(matches!(
+            timeseries.try_set(2, 3.0),
+            Err(RSCMError::IndexOutOfBounds(2, 2))
+        ));
+    }
+
+    #[test]
+    fn get_index_with_float_error() {
+        let ta = TimeAxis::from_values(Array::range(2000.0, 2001.0, 0.1));
+
+        // Accumulating decimal steps doesn't reproduce the values exactly
+        let mut t = 2000.0;
+        for expected in 0..ta.len() {
+            assert_eq!(ta.get_index(t), Some(expected));
+            t += 0.1;
+        }
+
+        assert_eq!(ta.get_index(1999.0), None);
+        assert_eq!(ta.get_index(2000.05), None);
+        // The end bound isn't a value on the axis
+        assert_eq!(ta.get_index(2001.0), None);
+
+        assert_eq!(ta.get_index_with_tolerance(2000.06, 0.05), Some(1));
+        assert!(ta.contains_with_tolerance(2000.06, 0.05));
+        assert!(!ta.contains(2000.06));
+
+        // The tolerance doesn't scale with the magnitude of the times, e.g. times in seconds
+        let seconds = TimeAxis::from_values(array![6.3e10, 6.3e10 + 1.0]);
+        assert_eq!(seconds.get_index(6.3e10 + 1.0), Some(1));
+        assert_eq!(seconds.get_index(6.3e10 + 0.5), None);
+    }
+
+    #[test]
+    fn time_units() {
+        let ta = TimeAxis::from_values(array![2000.0, 2001.0]);
+        assert_eq!(ta.unit(), TimeUnit::Year);
+        // The default unit isn't serialised
+        assert_eq!(
+            serde_json::to_string(&ta).unwrap(),
+            r#"{"bounds":{"v":1,"dim":[3],"data":[2000.0,2001.0,2002.0]}}"#
+        );
+
+        let seconds = ta.to_unit(TimeUnit::Second);
+        assert_eq!(seconds.unit(), TimeUnit::Second);
+        assert!(is_close!(seconds.at(1).unwrap(), 2001.0 * 365.25 * 86400.0));
+        assert_eq!(seconds.slice(0..1).unit(), TimeUnit::Second);
+
+        let roundtrip: TimeAxis =
+            serde_json::from_str(&serde_json::to_string(&seconds).unwrap()).unwrap();
+        assert_eq!(roundtrip.unit(), TimeUnit::Second);
+        let years = roundtrip.to_unit(TimeUnit::Year);
+        assert!(is_close!(years.at(1).unwrap(), 2001.0));
+    }
+
+    #[test]
+    fn slice() {
+        let mut timeseries = Timeseries::from_values(
+            array![1.0, 2.0, 3.0, FloatValue::nan(), FloatValue::nan()],
+            Array::range(2000.0, 2005.0, 1.0),
+        );
+        timeseries
+            .with_interpolation_strategy(InterpolationStrategy::from(PreviousStrategy::new(false)));
+
+        let window = timeseries.slice(2000.5, 2003.0).unwrap();
+        assert_eq!(window.values().to_vec()[..2], [2.0, 3.0]);
+        assert_eq!(window.len(), 3);
+        assert_eq!(
+            window.time_axis().bounds(),
+            array![2001.0, 2002.0, 2003.0, 2004.0]
+        );
+        // Interpolation strategy is retained
+        assert_eq!(window.at_time(2001.5).unwrap(), 2.0);
+        assert!(window.at_time(2000.0).is_err());
+
+        // Float tolerant edges
+        let window = timeseries.slice(2000.0 + 1e-9, 2001.0 - 1e-9).unwrap();
+        assert_eq!(window.values(), array![1.0, 2.0]);
+
+        assert!(timeseries.slice(2003.0, 2001.0).is_err());
+        assert!(timeseries.slice(2010.0, 2020.0).is_err());
+    }
+
     #[test]
     fn get_value() {
         let mut result = Timeseries::from_values(
@@ -423,6 +1729,49 @@ mod tests {
         assert!(result.at_time(2026.0).is_err());
     }
 
+    #[test]
+    fn assign_from() {
+        let time_axis = Arc::new(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0)));
+        let new = |values: Array1<FloatValue>, units: &str| {
+            Timeseries::new(
+                values,
+                time_axis.clone(),
+                units.to_string(),
+                InterpolationStrategy::from(PreviousStrategy::new(true)),
+            )
+        };
+
+        let mut timeseries = Timeseries::new_empty(
+            time_axis.clone(),
+            "MtC / yr".to_string(),
+            InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+        );
+        timeseries
+            .assign_from(&new(array![1.0, 2.0, FloatValue::NAN], "GtC / yr"))
+            .unwrap();
+
+        assert_eq!(timeseries.values().slice(s![..2]), array![1000.0, 2000.0]);
+        assert!(timeseries.at(2).unwrap().is_nan());
+        assert_eq!(*timeseries.latest(), 1);
+        assert_eq!(timeseries.units(), "MtC / yr");
+        // The strategy isn't copied
+        assert_eq!(timeseries.at_time(2020.5).unwrap(), 1500.0);
+
+        // An equal time axis which isn't shared
+        let other =
+            Timeseries::from_values(array![1.0, 2.0, 3.0], Array::range(2020.0, 2023.0, 1.0));
+        let mut unitless =
+            Timeseries::from_values(array![0.0, 0.0, 0.0], Array::range(2020.0, 2023.0, 1.0));
+        unitless.assign_from(&other).unwrap();
+        assert_eq!(unitless.values(), array![1.0, 2.0, 3.0]);
+
+        let other = Timeseries::from_values(array![1.0, 2.0], array![2020.0, 2021.0]);
+        assert!(timeseries.assign_from(&other).is_err());
+        assert!(timeseries
+            .assign_from(&new(array![1.0, 2.0, 3.0], "K"))
+            .is_err());
+    }
+
     #[test]
     fn custom_interpolator() {
         let data = array![1.0, 1.5, 2.0];
@@ -442,6 +1791,75 @@ mod tests {
         assert_eq!(result, 2.0);
     }
 
+    #[test]
+    fn integrate_and_differentiate() {
+        let mut emissions = Timeseries::new(
+            array![10.0, 11.0, 12.0, 13.0],
+            Arc::new(TimeAxis::from_values(array![
+                2000.0, 2001.0, 2003.0, 2004.0
+            ])),
+            "GtC / yr".to_string(),
+            InterpolationStrategy::from(PreviousStrategy::new(true)),
+        );
+
+        let cumulative = emissions.integrate();
+        assert_eq!(cumulative.values(), array![0.0, 10.0, 32.0, 44.0]);
+        assert_eq!(cumulative.units(), "GtC");
+
+        // Piecewise-constant rates are recovered exactly
+        let rate = cumulative.differentiate().unwrap();
+        assert_eq!(rate.values(), array![10.0, 11.0, 12.0, 12.0]);
+        assert_eq!(rate.units(), "GtC / yr");
+        assert_eq!(rate.unit(), emissions.unit());
+
+        // Rates are per year whatever the unit of the time axis
+        let in_seconds = emissions.to_time_unit(TimeUnit::Second).integrate();
+        assert!(is_close!(in_seconds.values()[3], 44.0));
+
+        // Linear interpolation uses the trapezoidal rule
+        emissions.with_interpolation_strategy(LinearSplineStrategy::new(false).into());
+        assert_eq!(
+            emissions.integrate().values(),
+            array![0.0, 10.5, 33.5, 46.0]
+        );
+        assert!(emissions.differentiate().is_err());
+    }
+
+    #[test]
+    fn regrid() {
+        let emissions = Timeseries::from_values(
+            array![10.0, 20.0, 30.0, 40.0],
+            array![2000.0, 2001.0, 2002.0, 2003.0],
+        );
+
+        // Steps which don't align with the original steps
+        let uneven = Arc::new(TimeAxis::from_bounds(array![
+            2000.0, 2000.5, 2002.0, 2004.0
+        ]));
+        let regridded = emissions
+            .regrid(uneven.clone(), RegridMethod::Conservative)
+            .unwrap();
+        assert_eq!(regridded.values(), array![10.0, 50.0 / 3.0, 35.0]);
+
+        let total = |ts: &Timeseries<FloatValue>| -> FloatValue {
+            (0..ts.len())
+                .map(|i| {
+                    let (start, end) = ts.time_axis().at_bounds(i).unwrap();
+                    ts.values()[i] * (end - start)
+                })
+                .sum()
+        };
+        assert!(is_close!(total(&regridded), total(&emissions)));
+
+        let interpolated = emissions.regrid(uneven, RegridMethod::Interpolate).unwrap();
+        assert_eq!(interpolated.values(), array![10.0, 15.0, 30.0]);
+
+        let beyond = Arc::new(TimeAxis::from_bounds(array![2003.0, 2005.0]));
+        assert!(emissions
+            .regrid(beyond, RegridMethod::Conservative)
+            .is_err());
+    }
+
     #[test]
     fn serialise_and_deserialise_json() {
         let data = array![1.0, 1.5, 2.0];
@@ -509,4 +1927,42 @@ data = [2020.0, 2021.0, 2022.0, 2023.0]
         assert!(zip(timeseries.values(), deserialised.values())
             .all(|(x0, x1)| { is_close!(*x0, *x1) || (x0.is_nan() && x0.is_nan()) }))
     }
+
+    #[test]
+    fn csv_round_trip() {
+        let mut timeseries = Timeseries::new(
+            array![1.0, FloatValue::NAN, 3.25],
+            Arc::new(TimeAxis::from_bounds(array![
+                1850.0, 1900.0, 1950.0, 1955.0
+            ])),
+            "GtC / yr".to_string(),
+            InterpolationStrategy::from(LinearSplineStrategy::new(true)),
+        );
+        timeseries.with_interpolation_strategy(InterpolationStrategy::from(
+            PreviousStrategy::with_extrapolation(ExtrapolationPolicy::ClampToRange(0.0, 5.0)),
+        ));
+
+        let csv = timeseries.to_csv();
+        assert!(csv.contains("\"strategy\":\"Previous\""));
+        assert!(csv.contains("1900,NaN\n"));
+
+        let read = Timeseries::from_csv(&csv).unwrap();
+        assert_eq!(read.units(), "GtC / yr");
+        assert_eq!(read.time_axis().bounds(), timeseries.time_axis().bounds());
+        assert_eq!(read.latest(), timeseries.latest());
+        assert_eq!(read.to_csv(), csv);
+    }
+
+    #[test]
+    fn csv_invalid() {
+        assert!(Timeseries::from_csv("time,value\n2000,1.0\n").is_err());
+        assert!(Timeseries::from_csv("year,emissions\n2000,1.0\n2001,2.0\n").is_err());
+        assert!(Timeseries::from_csv("time,value\n2001,1.0\n2000,2.0\n").is_err());
+        assert!(Timeseries::from_csv("time,value\n2000,1.0\n2001,abc\n").is_err());
+        assert!(Timeseries::from_csv("# end: 2001\ntime,value\n2000,1.0\n2001,2.0\n").is_err());
+        assert!(
+            Timeseries::from_csv("# interpolation: Cubic\ntime,value\n2000,1.0\n2001,2.0\n")
+                .is_err()
+        );
+    }
 }
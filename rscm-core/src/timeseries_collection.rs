@@ -1,5 +1,9 @@
+use crate::errors::{RSCMError, RSCMResult};
+use crate::html;
 use crate::timeseries::{FloatValue, Timeseries};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
 
 #[derive(Copy, Clone, PartialOrd, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[pyo3::pyclass]
@@ -10,15 +14,51 @@ pub enum VariableType {
     Endogenous,
 }
 
+/// Identifies the run that produced a timeseries
+///
+/// This allows the results from multiple model runs to be stored in a single collection
+/// where the same variable name is present for each run.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct RunKey {
+    pub run_id: String,
+    pub scenario: String,
+}
+
+impl RunKey {
+    pub fn new(run_id: &str, scenario: &str) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            scenario: scenario.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimeseriesItem {
     pub timeseries: Timeseries<FloatValue>,
     pub name: String,
     pub variable_type: VariableType,
+    /// Run that the timeseries belongs to
+    ///
+    /// `None` for timeseries that aren't associated with a specific run,
+    /// e.g. the state of a single model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<RunKey>,
+}
+
+/// Description of a timeseries in a collection without its values
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeseriesMetadata {
+    pub name: String,
+    pub units: String,
+    pub variable_type: VariableType,
+    pub run: Option<RunKey>,
 }
 
 /// A collection of time series data.
 /// Allows for easy access to time series data by name across the whole model
+///
+/// Timeseries are uniquely identified by their name and optional [`RunKey`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimeseriesCollection {
     timeseries: Vec<TimeseriesItem>,
@@ -39,30 +79,257 @@ impl TimeseriesCollection {
 
     /// Add a new timeseries to the collection
     ///
-    /// Panics if a timeseries with the same name already exists in the collection
-    /// TODO: Revisit if this is the correct way of handling this type of error
+    /// Returns an error if a timeseries with the same name already exists in the collection.
+    /// See [`TimeseriesCollection::upsert_timeseries`] to overwrite existing timeseries.
     pub fn add_timeseries(
         &mut self,
         name: String,
         timeseries: Timeseries<FloatValue>,
         variable_type: VariableType,
-    ) {
-        if self.timeseries.iter().any(|x| x.name == name) {
-            panic!("timeseries {} already exists", name)
-        }
-        self.timeseries.push(TimeseriesItem {
+    ) -> RSCMResult<()> {
+        self.add_item(TimeseriesItem {
+            timeseries,
+            name,
+            variable_type,
+            run: None,
+        })
+    }
+
+    /// Add a new timeseries associated with a specific run to the collection
+    ///
+    /// Returns an error if a timeseries with the same name already exists for the run.
+    pub fn add_timeseries_for_run(
+        &mut self,
+        name: String,
+        timeseries: Timeseries<FloatValue>,
+        variable_type: VariableType,
+        run: RunKey,
+    ) -> RSCMResult<()> {
+        self.add_item(TimeseriesItem {
             timeseries,
             name,
             variable_type,
-        });
+            run: Some(run),
+        })
+    }
+
+    /// Add a timeseries to the collection, replacing any existing timeseries with the same name
+    ///
+    /// Returns the replaced item if one existed.
+    pub fn upsert_timeseries(
+        &mut self,
+        name: String,
+        timeseries: Timeseries<FloatValue>,
+        variable_type: VariableType,
+    ) -> Option<TimeseriesItem> {
+        self.upsert_item(TimeseriesItem {
+            timeseries,
+            name,
+            variable_type,
+            run: None,
+        })
+    }
+
+    /// Replace an existing timeseries
+    ///
+    /// Returns an error if the collection doesn't contain a timeseries with the same name,
+    /// otherwise the replaced item is returned.
+    pub fn replace_timeseries(
+        &mut self,
+        name: String,
+        timeseries: Timeseries<FloatValue>,
+        variable_type: VariableType,
+    ) -> RSCMResult<TimeseriesItem> {
+        match self.position(&name, &None) {
+            Some(idx) => Ok(std::mem::replace(
+                &mut self.timeseries[idx],
+                TimeseriesItem {
+                    timeseries,
+                    name,
+                    variable_type,
+                    run: None,
+                },
+            )),
+            None => Err(RSCMError::TimeseriesNotFound(describe_key(&name, &None))),
+        }
+    }
+
+    /// Add an item to the collection
+    ///
+    /// Returns an error if an item with the same name and run already exists.
+    pub fn add_item(&mut self, item: TimeseriesItem) -> RSCMResult<()> {
+        if self.position(&item.name, &item.run).is_some() {
+            return Err(RSCMError::TimeseriesAlreadyExists(describe_key(
+                &item.name, &item.run,
+            )));
+        }
+        self.insert_sorted(item);
+        Ok(())
+    }
+
+    /// Add an item to the collection, replacing any item with the same name and run
+    pub fn upsert_item(&mut self, item: TimeseriesItem) -> Option<TimeseriesItem> {
+        match self.position(&item.name, &item.run) {
+            Some(idx) => Some(std::mem::replace(&mut self.timeseries[idx], item)),
+            None => {
+                self.insert_sorted(item);
+                None
+            }
+        }
+    }
+
+    fn position(&self, name: &str, run: &Option<RunKey>) -> Option<usize> {
+        self.timeseries
+            .iter()
+            .position(|x| x.name == name && x.run == *run)
+    }
+
+    fn insert_sorted(&mut self, item: TimeseriesItem) {
+        self.timeseries.push(item);
         // Ensure the order of the serialised timeseries is stable
-        self.timeseries.sort_unstable_by_key(|x| x.name.clone());
+        self.timeseries
+            .sort_unstable_by(|a, b| (&a.name, &a.run).cmp(&(&b.name, &b.run)));
     }
 
+    /// Get a timeseries item by name
+    ///
+    /// If the collection contains the results from multiple runs, the item without a run
+    /// is returned if present, otherwise the item for the first run (by [`RunKey`] order).
+    /// See [`TimeseriesCollection::get_by_name_and_run`] to query a specific run.
     pub fn get_by_name(&self, name: &str) -> Option<&TimeseriesItem> {
         self.timeseries.iter().find(|x| x.name == name)
     }
 
+    /// Get a timeseries item by name for a specific run
+    pub fn get_by_name_and_run(&self, name: &str, run: &RunKey) -> Option<&TimeseriesItem> {
+        self.timeseries
+            .iter()
+            .find(|x| x.name == name && x.run.as_ref() == Some(run))
+    }
+
+    /// Associate all timeseries that don't already belong to a run with `run`
+    ///
+    /// This is typically used on the results of a model run before merging the results
+    /// from several runs into a single collection.
+    pub fn with_run(mut self, run: RunKey) -> Self {
+        self.timeseries
+            .iter_mut()
+            .filter(|x| x.run.is_none())
+            .for_each(|x| x.run = Some(run.clone()));
+        self
+    }
+
+    /// Add all the timeseries from another collection into this collection
+    ///
+    /// Returns an error if any timeseries has the same name and run as an existing timeseries.
+    /// In this case, the timeseries preceding the duplicate will have already been added.
+    pub fn merge(&mut self, other: TimeseriesCollection) -> RSCMResult<&mut Self> {
+        other.into_iter().try_for_each(|item| self.add_item(item))?;
+        Ok(self)
+    }
+
+    /// Get the unique runs within the collection
+    pub fn runs(&self) -> Vec<RunKey> {
+        let mut runs: Vec<RunKey> = self
+            .timeseries
+            .iter()
+            .filter_map(|x| x.run.clone())
+            .collect();
+        runs.sort_unstable();
+        runs.dedup();
+        runs
+    }
+
+    /// Create a new collection with only the timeseries for a given run
+    pub fn filter_run(&self, run: &RunKey) -> TimeseriesCollection {
+        Self {
+            timeseries: self
+                .timeseries
+                .iter()
+                .filter(|x| x.run.as_ref() == Some(run))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Create a new collection with only the timeseries whose names match `pattern`
+    ///
+    /// See [`matches_pattern`] for the supported patterns, e.g. "Emissions|*".
+    /// The timeseries for every run are included.
+    pub fn filter(&self, pattern: &str) -> TimeseriesCollection {
+        Self {
+            timeseries: self
+                .timeseries
+                .iter()
+                .filter(|x| matches_pattern(pattern, &x.name))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Remove the timeseries with a given name
+    ///
+    /// The timeseries for every run are removed and returned.
+    /// Returns an error if the collection doesn't contain a timeseries with the name.
+    pub fn remove(&mut self, name: &str) -> RSCMResult<Vec<TimeseriesItem>> {
+        let (removed, kept) = std::mem::take(&mut self.timeseries)
+            .into_iter()
+            .partition(|x| x.name == name);
+        self.timeseries = kept;
+
+        match removed.is_empty() {
+            true => Err(RSCMError::TimeseriesNotFound(describe_key(name, &None))),
+            false => Ok(removed),
+        }
+    }
+
+    /// Rename the timeseries with a given name
+    ///
+    /// The timeseries for every run are renamed.
+    /// Returns an error if the collection doesn't contain a timeseries named `name`
+    /// or if a timeseries named `new_name` already exists for any of the same runs.
+    /// The collection is unchanged if an error is returned.
+    pub fn rename(&mut self, name: &str, new_name: &str) -> RSCMResult<()> {
+        let runs: Vec<Option<RunKey>> = self
+            .timeseries
+            .iter()
+            .filter(|x| x.name == name)
+            .map(|x| x.run.clone())
+            .collect();
+        if runs.is_empty() {
+            return Err(RSCMError::TimeseriesNotFound(describe_key(name, &None)));
+        }
+        if let Some(run) = runs
+            .iter()
+            .find(|run| self.position(new_name, run).is_some())
+        {
+            return Err(RSCMError::TimeseriesAlreadyExists(describe_key(
+                new_name, run,
+            )));
+        }
+
+        self.timeseries
+            .iter_mut()
+            .filter(|x| x.name == name)
+            .for_each(|x| x.name = new_name.to_string());
+        self.timeseries
+            .sort_unstable_by(|a, b| (&a.name, &a.run).cmp(&(&b.name, &b.run)));
+        Ok(())
+    }
+
+    /// Describe each timeseries in the collection without its values
+    pub fn metadata(&self) -> Vec<TimeseriesMetadata> {
+        self.timeseries
+            .iter()
+            .map(|x| TimeseriesMetadata {
+                name: x.name.clone(),
+                units: x.timeseries.units().to_string(),
+                variable_type: x.variable_type,
+                run: x.run.clone(),
+            })
+            .collect()
+    }
+
     pub fn get_timeseries_by_name(&self, name: &str) -> Option<&Timeseries<FloatValue>> {
         self.get_by_name(name).map(|item| &item.timeseries)
     }
@@ -79,6 +346,156 @@ impl TimeseriesCollection {
     pub fn iter(&self) -> impl Iterator<Item = &TimeseriesItem> {
         self.timeseries.iter()
     }
+
+    /// Convert variables into preferred units
+    ///
+    /// `units` maps the names of variables to the units that they should be converted into.
+    /// The timeseries of a variable for every run are converted.
+    /// Variables which aren't in `units` are unchanged
+    /// and units for variables which aren't in the collection are ignored.
+    /// Returns an error if a variable can't be converted using the global unit registry
+    /// and `contexts`.
+    pub fn convert_units<S: AsRef<str>>(
+        &self,
+        units: &BTreeMap<String, String>,
+        contexts: &[S],
+    ) -> RSCMResult<TimeseriesCollection> {
+        let timeseries = self
+            .timeseries
+            .iter()
+            .map(|item| match units.get(&item.name) {
+                Some(unit) => Ok(TimeseriesItem {
+                    timeseries: item
+                        .timeseries
+                        .convert_unit_with_contexts(unit, contexts)
+                        .map_err(|e| {
+                            RSCMError::Error(format!("Could not convert {}: {}", item.name, e))
+                        })?,
+                    ..item.clone()
+                }),
+                None => Ok(item.clone()),
+            })
+            .collect::<RSCMResult<Vec<_>>>()?;
+        Ok(Self { timeseries })
+    }
+
+    /// Description of the collection with a line per timeseries
+    ///
+    /// See [`Timeseries::summary`] for the information included about each timeseries.
+    pub fn summary(&self) -> String {
+        self.to_string()
+    }
+
+    /// HTML table with a row per timeseries
+    ///
+    /// Each row includes the units, time range, first and last values,
+    /// the number of NaN values and a [`Timeseries::sparkline`] of the values.
+    pub fn to_html(&self) -> String {
+        let rows: Vec<Vec<String>> = self
+            .timeseries
+            .iter()
+            .map(|item| {
+                let timeseries = &item.timeseries;
+                let time_axis = timeseries.time_axis();
+                let values = timeseries.values();
+                vec![
+                    item.name.clone(),
+                    item.run
+                        .as_ref()
+                        .map(|run| format!("{} / {}", run.run_id, run.scenario))
+                        .unwrap_or_default(),
+                    format!("{:?}", item.variable_type),
+                    timeseries.units().to_string(),
+                    format!("[{}, {})", time_axis.first(), time_axis.last()),
+                    html::number(values[0]),
+                    html::number(values[values.len() - 1]),
+                    timeseries.nan_count().to_string(),
+                    timeseries.sparkline(20),
+                ]
+            })
+            .collect();
+
+        format!(
+            "<div><strong>TimeseriesCollection</strong> with {} timeseries{}</div>",
+            self.timeseries.len(),
+            html::table(
+                &["Name", "Run", "Type", "Units", "Range", "First", "Last", "NaN", "Values"],
+                &rows
+            )
+        )
+    }
+}
+
+impl Display for TimeseriesItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:?}: {}",
+            describe_key(&self.name, &self.run),
+            self.variable_type,
+            self.timeseries.summary()
+        )
+    }
+}
+
+/// Check if a variable name matches a glob pattern
+///
+/// `*` matches any sequence of characters, including the `|` separating namespaces,
+/// so "Effective Radiative Forcing|*" matches "Effective Radiative Forcing|CO2"
+/// and "Effective Radiative Forcing|Aerosols|Direct".
+/// `?` matches any single character.
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    // Position in the pattern and name after the most recent `*`
+    let mut backtrack: Option<(usize, usize)> = None;
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the `*` consume another character
+                Some((star_p, star_n)) => {
+                    backtrack = Some((star_p, star_n + 1));
+                    p = star_p;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+impl Display for TimeseriesCollection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TimeseriesCollection with {} timeseries",
+            self.timeseries.len()
+        )?;
+        self.timeseries
+            .iter()
+            .try_for_each(|item| write!(f, "\n  {}", item))
+    }
+}
+
+fn describe_key(name: &str, run: &Option<RunKey>) -> String {
+    match run {
+        Some(run) => format!(
+            "'{}' (run_id='{}', scenario='{}')",
+            name, run.run_id, run.scenario
+        ),
+        None => format!("'{}'", name),
+    }
 }
 
 impl IntoIterator for TimeseriesCollection {
@@ -93,43 +510,351 @@ impl IntoIterator for TimeseriesCollection {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::interpolate::strategies::LinearSplineStrategy;
+    use is_close::is_close;
     use numpy::array;
     use numpy::ndarray::Array;
 
+    fn get_timeseries() -> Timeseries<FloatValue> {
+        Timeseries::from_values(array![1.0, 2.0, 3.0], Array::range(2020.0, 2023.0, 1.0))
+    }
+
+    #[test]
+    fn convert_units() {
+        let mut collection = TimeseriesCollection::new();
+        collection
+            .add_timeseries(
+                "Emissions|CO2".to_string(),
+                Timeseries::new(
+                    array![1.0, 2.0, 3.0],
+                    get_timeseries().time_axis(),
+                    "GtC / yr".to_string(),
+                    LinearSplineStrategy::new(true).into(),
+                ),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+        collection
+            .add_timeseries(
+                "Surface Temperature".to_string(),
+                get_timeseries(),
+                VariableType::Endogenous,
+            )
+            .unwrap();
+
+        let units = BTreeMap::from([
+            ("Emissions|CO2".to_string(), "MtCO2 / yr".to_string()),
+            (
+                "Atmospheric Concentrations|CO2".to_string(),
+                "ppb".to_string(),
+            ),
+        ]);
+        let converted = collection.convert_units(&units, &["CO2"]).unwrap();
+        let emissions = converted.get_timeseries_by_name("Emissions|CO2").unwrap();
+        assert_eq!(emissions.units(), "MtCO2 / yr");
+        assert!(is_close!(emissions.at(1).unwrap(), 2e3 * 44.009 / 12.011));
+        assert_eq!(
+            converted
+                .get_timeseries_by_name("Surface Temperature")
+                .unwrap()
+                .at(0),
+            Some(1.0)
+        );
+
+        let no_context: [&str; 0] = [];
+        assert!(collection.convert_units(&units, &no_context).is_err());
+    }
+
+    #[test]
+    fn display() {
+        let mut collection = TimeseriesCollection::new();
+        collection
+            .add_timeseries(
+                "Surface Temperature".to_string(),
+                Timeseries::from_values(
+                    Array::range(0.0, 350.0, 1.0),
+                    Array::range(1750.0, 2100.0, 1.0),
+                ),
+                VariableType::Endogenous,
+            )
+            .unwrap();
+
+        assert_eq!(
+            collection.summary(),
+            "TimeseriesCollection with 1 timeseries\n  'Surface Temperature' Endogenous: units: \"\", len: 350, range: [1750.0, 2100.0), first: 0.0, last: 349.0, nan: 0"
+        );
+
+        let timeseries = collection
+            .get_timeseries_by_name("Surface Temperature")
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", timeseries),
+            format!("Timeseries {{ {} }}", timeseries.summary())
+        );
+        // The full values are only included in the alternate form
+        assert!(format!("{:#?}", timeseries).contains("349.0"));
+        assert!(!format!("{:?}", collection).contains("348.0"));
+    }
+
+    #[test]
+    fn html() {
+        let mut collection = TimeseriesCollection::new();
+        collection
+            .add_timeseries(
+                "Emissions|<CO2>".to_string(),
+                Timeseries::from_values(array![1.0, 2.0, 4.0], array![2000.0, 2001.0, 2002.0]),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+
+        let html = collection.to_html();
+        assert!(html.starts_with("<div><strong>TimeseriesCollection</strong> with 1 timeseries"));
+        assert!(html.contains(
+            "<td>Emissions|&lt;CO2&gt;</td><td></td><td>Exogenous</td><td></td>\
+             <td>[2000, 2003)</td><td>1.0000</td><td>4.0000</td><td>0</td><td>▁▃█</td>"
+        ));
+    }
+
     #[test]
     fn adding() {
         let mut collection = TimeseriesCollection::new();
 
-        let timeseries =
-            Timeseries::from_values(array![1.0, 2.0, 3.0], Array::range(2020.0, 2023.0, 1.0));
-        collection.add_timeseries(
-            "Surface Temperature".to_string(),
-            timeseries.clone(),
-            VariableType::Exogenous,
+        let timeseries = get_timeseries();
+        collection
+            .add_timeseries(
+                "Surface Temperature".to_string(),
+                timeseries.clone(),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+        collection
+            .add_timeseries(
+                "Emissions|CO2".to_string(),
+                timeseries.clone(),
+                VariableType::Endogenous,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn merging_runs() {
+        let mut run_1 = TimeseriesCollection::new();
+        run_1
+            .add_timeseries(
+                "Surface Temperature".to_string(),
+                get_timeseries(),
+                VariableType::Endogenous,
+            )
+            .unwrap();
+        let run_2 = run_1.clone();
+
+        let mut merged = TimeseriesCollection::new();
+        merged
+            .merge(run_1.with_run(RunKey::new("1", "ssp119")))
+            .unwrap()
+            .merge(run_2.with_run(RunKey::new("2", "ssp585")))
+            .unwrap();
+
+        assert_eq!(merged.iter().count(), 2);
+        assert_eq!(
+            merged.runs(),
+            vec![RunKey::new("1", "ssp119"), RunKey::new("2", "ssp585")]
         );
-        collection.add_timeseries(
-            "Emissions|CO2".to_string(),
-            timeseries.clone(),
-            VariableType::Endogenous,
+        assert!(merged
+            .get_by_name_and_run("Surface Temperature", &RunKey::new("2", "ssp585"))
+            .is_some());
+        assert!(merged
+            .get_by_name_and_run("Surface Temperature", &RunKey::new("3", "ssp585"))
+            .is_none());
+        assert_eq!(
+            merged
+                .filter_run(&RunKey::new("1", "ssp119"))
+                .iter()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn merging_same_run() {
+        let mut collection = TimeseriesCollection::new();
+        collection
+            .add_timeseries_for_run(
+                "test".to_string(),
+                get_timeseries(),
+                VariableType::Exogenous,
+                RunKey::new("1", "ssp119"),
+            )
+            .unwrap();
+        let other = collection.clone();
+
+        let err = collection.merge(other).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Timeseries 'test' (run_id='1', scenario='ssp119') already exists"
         );
     }
 
     #[test]
-    #[should_panic]
     fn adding_same_name() {
         let mut collection = TimeseriesCollection::new();
 
-        let timeseries =
-            Timeseries::from_values(array![1.0, 2.0, 3.0], Array::range(2020.0, 2023.0, 1.0));
-        collection.add_timeseries(
+        collection
+            .add_timeseries(
+                "test".to_string(),
+                get_timeseries(),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+        let res = collection.add_timeseries(
             "test".to_string(),
-            timeseries.clone(),
+            get_timeseries(),
+            VariableType::Endogenous,
+        );
+
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Timeseries 'test' already exists"
+        );
+        // The original is retained
+        assert_eq!(
+            collection.get_by_name("test").unwrap().variable_type,
+            VariableType::Exogenous
+        );
+    }
+
+    #[test]
+    fn upsert_and_replace() {
+        let mut collection = TimeseriesCollection::new();
+
+        let replaced = collection.upsert_timeseries(
+            "test".to_string(),
+            get_timeseries(),
             VariableType::Exogenous,
         );
-        collection.add_timeseries(
+        assert!(replaced.is_none());
+
+        let mut timeseries = get_timeseries();
+        timeseries.set(0, 10.0);
+        let replaced = collection.upsert_timeseries(
             "test".to_string(),
             timeseries.clone(),
             VariableType::Endogenous,
         );
+        assert_eq!(replaced.unwrap().variable_type, VariableType::Exogenous);
+        assert_eq!(collection.iter().count(), 1);
+        assert_eq!(
+            collection
+                .get_timeseries_by_name("test")
+                .unwrap()
+                .at(0)
+                .unwrap(),
+            10.0
+        );
+
+        let replaced = collection
+            .replace_timeseries(
+                "test".to_string(),
+                get_timeseries(),
+                VariableType::Exogenous,
+            )
+            .unwrap();
+        assert_eq!(replaced.variable_type, VariableType::Endogenous);
+
+        let res =
+            collection.replace_timeseries("other".to_string(), timeseries, VariableType::Exogenous);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Timeseries 'other' not found"
+        );
+    }
+
+    #[test]
+    fn querying() {
+        let mut collection = TimeseriesCollection::new();
+        ["Emissions|CO2", "Emissions|CH4", "Surface Temperature"]
+            .iter()
+            .for_each(|name| {
+                collection
+                    .add_timeseries(name.to_string(), get_timeseries(), VariableType::Exogenous)
+                    .unwrap();
+            });
+        collection
+            .add_timeseries_for_run(
+                "Emissions|CO2".to_string(),
+                get_timeseries(),
+                VariableType::Exogenous,
+                RunKey::new("1", "ssp119"),
+            )
+            .unwrap();
+
+        let names = |collection: &TimeseriesCollection| -> Vec<String> {
+            collection.iter().map(|x| x.name.clone()).collect()
+        };
+        assert_eq!(
+            names(&collection.filter("Emissions|*")),
+            ["Emissions|CH4", "Emissions|CO2", "Emissions|CO2"]
+        );
+        assert_eq!(names(&collection.filter("Emissions")), Vec::<String>::new());
+
+        // Renaming applies to every run
+        collection
+            .rename("Emissions|CO2", "Emissions|CO2|Fossil")
+            .unwrap();
+        assert_eq!(collection.filter("Emissions|CO2|*").iter().count(), 2);
+        assert_eq!(
+            collection
+                .rename("Emissions|CH4", "Surface Temperature")
+                .err()
+                .unwrap()
+                .to_string(),
+            "Timeseries 'Surface Temperature' already exists"
+        );
+        assert!(collection.rename("Emissions|CO2", "Other").is_err());
+
+        assert_eq!(collection.remove("Emissions|CO2|Fossil").unwrap().len(), 2);
+        assert!(collection.remove("Emissions|CO2|Fossil").is_err());
+
+        assert_eq!(
+            collection.metadata(),
+            vec![
+                TimeseriesMetadata {
+                    name: "Emissions|CH4".to_string(),
+                    units: "".to_string(),
+                    variable_type: VariableType::Exogenous,
+                    run: None,
+                },
+                TimeseriesMetadata {
+                    name: "Surface Temperature".to_string(),
+                    units: "".to_string(),
+                    variable_type: VariableType::Exogenous,
+                    run: None,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn patterns() {
+        assert!(matches_pattern(
+            "Surface Temperature",
+            "Surface Temperature"
+        ));
+        assert!(!matches_pattern(
+            "Surface Temperature",
+            "Surface Temperature|Land"
+        ));
+        assert!(matches_pattern(
+            "Effective Radiative Forcing|*",
+            "Effective Radiative Forcing|Aerosols|Direct"
+        ));
+        assert!(!matches_pattern(
+            "Effective Radiative Forcing|*",
+            "Effective Radiative Forcing"
+        ));
+        assert!(matches_pattern("*|CO2", "Emissions|CO2"));
+        assert!(matches_pattern("Emissions|CH?", "Emissions|CH4"));
+        assert!(!matches_pattern("*|CO2", "Emissions|CH4"));
+        assert!(matches_pattern("*", ""));
     }
 }
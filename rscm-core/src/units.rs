@@ -0,0 +1,682 @@
+//! Registry of units and the conversions between them
+//!
+//! Each unit belongs to a dimension (e.g. `[carbon]`) and has a scale relative to the base unit
+//! of that dimension.
+//! Units of the same dimension can always be converted between.
+//!
+//! Some conversions are only valid in a particular context.
+//! For example, a mass of carbon can be converted to a mass of CO2
+//! or a CO2 concentration, but only when the quantity refers to CO2.
+//! A context defines a conversion factor between the base units of two dimensions,
+//! and conversions between dimensions are only performed when the context is enabled.
+//!
+//! A global registry is populated with common climate units and the "CO2" context.
+//! Custom units and contexts can be registered at runtime using [`register_unit`]
+//! and [`register_context`].
+//! The global registry is used by [`crate::timeseries::Timeseries::convert_unit`]
+//! and when converting values passed between components with different units.
+//!
+//! Units are parsed into a [`Unit`], a product of symbols raised to integer powers,
+//! so "GtC / yr", "GtC/yr" and "GtC yr^-1" are equivalent.
+//! Units which haven't been registered are composed from the definitions of their symbols,
+//! e.g. "MtCO2 / month" can be used once "month" has been registered as a `[time]` unit.
+//! Contexts also apply to compound units, so the conversion from `[carbon]` to `[CO2]`
+//! is used for `[carbon] / [time]` as well.
+//! Only multiplicative conversions are supported.
+use crate::errors::{RSCMError, RSCMResult};
+use crate::timeseries::FloatValue;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::{OnceLock, RwLock};
+
+/// Molecular weight of CO2 divided by the atomic weight of carbon
+const CO2_PER_C: FloatValue = 44.009 / 12.011;
+/// Mass of carbon in the atmosphere per ppm of CO2
+///
+/// unit: GtC / ppm
+const GTC_PER_PPM: FloatValue = 2.124;
+/// Length of a Julian year
+///
+/// unit: day / yr
+const DAYS_PER_YEAR: FloatValue = 365.25;
+/// unit: s / day
+const SECONDS_PER_DAY: FloatValue = 86400.0;
+
+/// Symbols and the power that they are raised to
+type Terms = BTreeMap<String, i32>;
+
+/// A unit parsed into a product of symbols raised to integer powers
+///
+/// Symbols are separated by whitespace or `*`, `/` divides by the following symbol
+/// and `^` raises a symbol to an integer power.
+/// Two units are equal if they contain the same symbols and powers,
+/// regardless of how they were written.
+/// The original text is kept for display.
+///
+/// Text which can't be parsed is treated as a single symbol.
+///
+/// # Examples
+///
+/// ```rust
+/// use rscm_core::units::Unit;
+///
+/// assert_eq!(Unit::from("W / m^2"), Unit::from("W m^-2"));
+/// assert_eq!(Unit::from("W / m^2").to_string(), "W / m^2");
+/// assert_ne!(Unit::from("GtC / yr"), Unit::from("GtC"));
+/// assert!(Unit::parse("GtC /").is_err());
+/// assert!(Unit::from("").is_dimensionless());
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct Unit {
+    text: String,
+    terms: Terms,
+}
+
+impl Unit {
+    /// Parse a unit
+    ///
+    /// Returns an error if the unit has a missing symbol or an invalid power.
+    pub fn parse(text: &str) -> RSCMResult<Self> {
+        Ok(Self {
+            text: text.to_string(),
+            terms: parse_terms(text)?,
+        })
+    }
+
+    /// The unit as it was written
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Check if the unit doesn't contain any symbols
+    pub fn is_dimensionless(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Multiply the unit by `other` raised to `power`
+    ///
+    /// The result is written with the symbols that have positive powers
+    /// before those with negative powers.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rscm_core::units::Unit;
+    ///
+    /// let yr = Unit::from("yr");
+    /// assert_eq!(Unit::from("GtC / yr").multiplied_by(&yr, 1).to_string(), "GtC");
+    /// assert_eq!(Unit::from("GtC").multiplied_by(&yr, -1).to_string(), "GtC / yr");
+    /// assert_eq!(Unit::from("").multiplied_by(&yr, -1).to_string(), "1 / yr");
+    /// ```
+    pub fn multiplied_by(&self, other: &Unit, power: i32) -> Self {
+        let mut terms = self.terms.clone();
+        multiply(&mut terms, &other.terms, power);
+        Self {
+            text: written(&terms),
+            terms,
+        }
+    }
+
+    /// Canonical form of the unit used to look up definitions
+    fn canonical(&self) -> String {
+        canonical(&self.terms)
+    }
+}
+
+fn parse_terms(text: &str) -> RSCMResult<Terms> {
+    let invalid = |reason: &str| RSCMError::Error(format!("Invalid unit '{}': {}", text, reason));
+
+    // Split the text into symbols and operators, keeping powers attached to their symbol
+    let spaced = text
+        .replace('*', " * ")
+        .replace('/', " / ")
+        .replace(" ^", "^")
+        .replace("^ ", "^");
+    let mut terms = Terms::new();
+    let mut sign = 1;
+    let mut expects_symbol = true;
+    for token in spaced.split_whitespace() {
+        match token {
+            "*" | "/" if expects_symbol => return Err(invalid("missing symbol")),
+            "*" => expects_symbol = true,
+            // Dimensionless, e.g. "1 / yr"
+            "1" => expects_symbol = false,
+            "/" => {
+                sign = -1;
+                expects_symbol = true;
+            }
+            _ => {
+                let (symbol, power) = match token.split_once('^') {
+                    Some((symbol, power)) => (
+                        symbol,
+                        power
+                            .parse::<i32>()
+                            .map_err(|_| invalid(&format!("invalid power '{}'", power)))?,
+                    ),
+                    None => (token, 1),
+                };
+                if symbol.is_empty() {
+                    return Err(invalid("missing symbol"));
+                }
+                *terms.entry(symbol.to_string()).or_default() += sign * power;
+                sign = 1;
+                expects_symbol = false;
+            }
+        }
+    }
+    if expects_symbol && !spaced.trim().is_empty() {
+        return Err(invalid("missing symbol"));
+    }
+
+    terms.retain(|_, power| *power != 0);
+    Ok(terms)
+}
+
+fn canonical(terms: &Terms) -> String {
+    terms
+        .iter()
+        .map(|(symbol, power)| match power {
+            1 => symbol.clone(),
+            _ => format!("{}^{}", symbol, power),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Human readable form of a unit, e.g. "W / m^2"
+fn written(terms: &Terms) -> String {
+    let symbol = |(symbol, power): (&String, i32)| match power {
+        1 => symbol.clone(),
+        _ => format!("{}^{}", symbol, power),
+    };
+    let numerator: Vec<String> = terms
+        .iter()
+        .filter(|(_, power)| **power > 0)
+        .map(|(s, power)| symbol((s, *power)))
+        .collect();
+    let denominator: Vec<String> = terms
+        .iter()
+        .filter(|(_, power)| **power < 0)
+        .map(|(s, power)| format!("/ {}", symbol((s, -*power))))
+        .collect();
+
+    match (numerator.is_empty(), denominator.is_empty()) {
+        (true, true) => "".to_string(),
+        (true, false) => format!("1 {}", denominator.join(" ")),
+        _ => numerator
+            .into_iter()
+            .chain(denominator)
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+/// Multiply `terms` by `other` raised to `power`
+fn multiply(terms: &mut Terms, other: &Terms, power: i32) {
+    other.iter().for_each(|(symbol, p)| {
+        *terms.entry(symbol.clone()).or_default() += p * power;
+    });
+    terms.retain(|_, p| *p != 0);
+}
+
+impl From<&str> for Unit {
+    fn from(text: &str) -> Self {
+        Self::parse(text).unwrap_or_else(|_| Self {
+            text: text.to_string(),
+            terms: Terms::from([(text.split_whitespace().collect(), 1)]),
+        })
+    }
+}
+
+impl From<String> for Unit {
+    fn from(text: String) -> Self {
+        Self::from(text.as_str())
+    }
+}
+
+impl From<Unit> for String {
+    fn from(unit: Unit) -> Self {
+        unit.text
+    }
+}
+
+impl PartialEq for Unit {
+    fn eq(&self, other: &Self) -> bool {
+        self.terms == other.terms
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// A unit which has been registered
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitDefinition {
+    pub dimension: String,
+    /// Size of the unit relative to the base unit of the dimension
+    pub scale: FloatValue,
+}
+
+/// Conversion between the base units of two dimensions
+#[derive(Debug, Clone, PartialEq)]
+struct ContextConversion {
+    from: Terms,
+    to: Terms,
+    /// Quantity of `to` equivalent to one base unit of `from`
+    factor: FloatValue,
+}
+
+/// Normalise the name of a unit so that equivalent units share a name
+fn normalise(unit: &str) -> String {
+    Unit::from(unit).canonical()
+}
+
+/// Replace the dimensions `from` within `dimensions` with `to`
+///
+/// Returns `None` if `dimensions` doesn't contain `from`.
+fn substitute(dimensions: &Terms, from: &Terms, to: &Terms) -> Option<Terms> {
+    if from.is_empty()
+        || from
+            .iter()
+            .any(|(symbol, power)| dimensions.get(symbol) != Some(power))
+    {
+        return None;
+    }
+    let mut result = dimensions.clone();
+    multiply(&mut result, from, -1);
+    multiply(&mut result, to, 1);
+    Some(result)
+}
+
+/// A collection of units and contexts
+#[derive(Debug, Clone, Default)]
+pub struct UnitRegistry {
+    units: HashMap<String, UnitDefinition>,
+    contexts: HashMap<String, Vec<ContextConversion>>,
+}
+
+impl UnitRegistry {
+    /// Create a registry without any units
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry containing common climate units and the "CO2" context
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+
+        let masses = [
+            ("t", 1.0),
+            ("kt", 1e3),
+            ("Mt", 1e6),
+            ("Gt", 1e9),
+            ("Pg", 1e9),
+        ];
+        for (prefix, scale) in masses {
+            for (species, dimension) in [
+                ("C", "[carbon]"),
+                ("CO2", "[CO2]"),
+                ("CH4", "[CH4]"),
+                ("N2O", "[N2O]"),
+            ] {
+                registry
+                    .register_unit(&format!("{}{}", prefix, species), dimension, scale)
+                    .register_unit(
+                        &format!("{}{} / yr", prefix, species),
+                        &format!("{} / [time]", dimension),
+                        scale,
+                    );
+            }
+        }
+
+        registry
+            .register_unit("ppm", "[concentration]", 1.0)
+            .register_unit("ppb", "[concentration]", 1e-3)
+            .register_unit("ppt", "[concentration]", 1e-6)
+            .register_unit("ppm / yr", "[concentration] / [time]", 1.0)
+            .register_unit("ppb / yr", "[concentration] / [time]", 1e-3)
+            .register_unit("K", "[temperature]", 1.0)
+            .register_unit("delta_degC", "[temperature]", 1.0)
+            .register_unit("mK", "[temperature]", 1e-3)
+            .register_unit("W / m^2", "[power] / [area]", 1.0)
+            .register_unit("mW / m^2", "[power] / [area]", 1e-3)
+            .register_unit("yr", "[time]", 1.0)
+            .register_unit("day", "[time]", 1.0 / DAYS_PER_YEAR)
+            .register_unit("s", "[time]", 1.0 / (DAYS_PER_YEAR * SECONDS_PER_DAY));
+
+        registry
+            .register_context("CO2", "[carbon]", "[CO2]", CO2_PER_C)
+            .register_context("CO2", "[concentration]", "[carbon]", GTC_PER_PPM * 1e9);
+
+        registry
+    }
+
+    /// Register a unit
+    ///
+    /// `scale` is the size of the unit relative to the base unit of `dimension`.
+    /// Registering an existing unit replaces its definition.
+    pub fn register_unit(&mut self, unit: &str, dimension: &str, scale: FloatValue) -> &mut Self {
+        self.units.insert(
+            normalise(unit),
+            UnitDefinition {
+                dimension: dimension.to_string(),
+                scale,
+            },
+        );
+        self
+    }
+
+    /// Register a conversion between two dimensions which applies within `context`
+    ///
+    /// One base unit of `from` is equivalent to `factor` base units of `to`.
+    /// The reverse conversion is also available within the context.
+    pub fn register_context(
+        &mut self,
+        context: &str,
+        from: &str,
+        to: &str,
+        factor: FloatValue,
+    ) -> &mut Self {
+        self.contexts
+            .entry(context.to_string())
+            .or_default()
+            .push(ContextConversion {
+                from: Unit::from(from).terms,
+                to: Unit::from(to).terms,
+                factor,
+            });
+        self
+    }
+
+    /// Get the definition of a unit
+    pub fn get(&self, unit: &str) -> Option<&UnitDefinition> {
+        self.units.get(&normalise(unit))
+    }
+
+    /// Dimensions and scale of a unit
+    ///
+    /// Units which haven't been registered are composed from the definitions of their symbols.
+    fn resolve(&self, unit: &str) -> RSCMResult<(Terms, FloatValue)> {
+        let parsed = Unit::from(unit);
+        if let Some(definition) = self.units.get(&parsed.canonical()) {
+            return Ok((
+                Unit::from(definition.dimension.as_str()).terms,
+                definition.scale,
+            ));
+        }
+
+        let mut dimensions = Terms::new();
+        let mut scale = 1.0;
+        for (symbol, power) in parsed.terms.iter() {
+            let definition = self
+                .units
+                .get(symbol)
+                .ok_or_else(|| RSCMError::Error(format!("Unknown unit '{}'", unit)))?;
+            multiply(
+                &mut dimensions,
+                &Unit::from(definition.dimension.as_str()).terms,
+                *power,
+            );
+            scale *= definition.scale.powi(*power);
+        }
+        Ok((dimensions, scale))
+    }
+
+    /// Factor to convert between the base units of two dimensions using the enabled contexts
+    ///
+    /// Conversions may be chained, e.g. from `[concentration]` to `[carbon]` to `[CO2]`,
+    /// and apply within compound dimensions, e.g. from `[carbon] / [time]` to `[CO2] / [time]`.
+    fn dimension_factor<S: AsRef<str>>(
+        &self,
+        from: &Terms,
+        to: &Terms,
+        contexts: &[S],
+    ) -> RSCMResult<FloatValue> {
+        let mut edges: Vec<(&Terms, &Terms, FloatValue)> = vec![];
+        for context in contexts {
+            let conversions = self.contexts.get(context.as_ref()).ok_or_else(|| {
+                RSCMError::Error(format!("Unknown unit context '{}'", context.as_ref()))
+            })?;
+            conversions.iter().for_each(|c| {
+                edges.push((&c.from, &c.to, c.factor));
+                edges.push((&c.to, &c.from, 1.0 / c.factor));
+            });
+        }
+
+        let mut visited = HashSet::from([canonical(from)]);
+        let mut queue = VecDeque::from([(from.clone(), 1.0)]);
+        while let Some((dimensions, factor)) = queue.pop_front() {
+            if &dimensions == to {
+                return Ok(factor);
+            }
+            edges.iter().for_each(|(a, b, f)| {
+                if let Some(next) = substitute(&dimensions, a, b) {
+                    if visited.insert(canonical(&next)) {
+                        queue.push_back((next, factor * f));
+                    }
+                }
+            });
+        }
+
+        Err(RSCMError::Error(format!(
+            "Cannot convert from {} to {} without a suitable context",
+            canonical(from),
+            canonical(to)
+        )))
+    }
+
+    /// Factor which converts a value in `from` units to a value in `to` units
+    ///
+    /// `contexts` are the names of the contexts which may be used to convert between dimensions.
+    pub fn conversion_factor<S: AsRef<str>>(
+        &self,
+        from: &str,
+        to: &str,
+        contexts: &[S],
+    ) -> RSCMResult<FloatValue> {
+        if is_same_unit(from, to) {
+            return Ok(1.0);
+        }
+        let (from_dimensions, from_scale) = self.resolve(from)?;
+        let (to_dimensions, to_scale) = self.resolve(to)?;
+
+        let factor = match from_dimensions == to_dimensions {
+            true => 1.0,
+            false => self
+                .dimension_factor(&from_dimensions, &to_dimensions, contexts)
+                .map_err(|_| RSCMError::WrongUnits(to.to_string(), from.to_string()))?,
+        };
+        Ok(factor * from_scale / to_scale)
+    }
+
+    /// Check if a value in `from` units can be converted into `to` units
+    pub fn is_convertible<S: AsRef<str>>(&self, from: &str, to: &str, contexts: &[S]) -> bool {
+        self.conversion_factor(from, to, contexts).is_ok()
+    }
+}
+
+fn global_registry() -> &'static RwLock<UnitRegistry> {
+    static REGISTRY: OnceLock<RwLock<UnitRegistry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(UnitRegistry::with_defaults()))
+}
+
+/// Register a unit in the global registry
+///
+/// See [`UnitRegistry::register_unit`]
+pub fn register_unit(unit: &str, dimension: &str, scale: FloatValue) {
+    global_registry()
+        .write()
+        .unwrap()
+        .register_unit(unit, dimension, scale);
+}
+
+/// Register a conversion between two dimensions in the global registry
+///
+/// See [`UnitRegistry::register_context`]
+pub fn register_context(context: &str, from: &str, to: &str, factor: FloatValue) {
+    global_registry()
+        .write()
+        .unwrap()
+        .register_context(context, from, to, factor);
+}
+
+/// Factor to convert from `from` units to `to` units using the global registry
+///
+/// See [`UnitRegistry::conversion_factor`]
+pub fn conversion_factor<S: AsRef<str>>(
+    from: &str,
+    to: &str,
+    contexts: &[S],
+) -> RSCMResult<FloatValue> {
+    global_registry()
+        .read()
+        .unwrap()
+        .conversion_factor(from, to, contexts)
+}
+
+/// Check if units are the same once parsed
+pub fn is_same_unit(a: &str, b: &str) -> bool {
+    Unit::from(a) == Unit::from(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use is_close::is_close;
+
+    const NO_CONTEXT: [&str; 0] = [];
+
+    #[test]
+    fn same_dimension() {
+        let registry = UnitRegistry::with_defaults();
+
+        assert_eq!(
+            registry
+                .conversion_factor("GtC / yr", "MtC/yr", &NO_CONTEXT)
+                .unwrap(),
+            1000.0
+        );
+        assert_eq!(
+            registry
+                .conversion_factor("GtC", "PgC", &NO_CONTEXT)
+                .unwrap(),
+            1.0
+        );
+        assert!(registry
+            .conversion_factor("GtC", "ppm", &NO_CONTEXT)
+            .is_err());
+        assert!(registry
+            .conversion_factor("GtC", "unknown", &NO_CONTEXT)
+            .is_err());
+    }
+
+    #[test]
+    fn with_context() {
+        let registry = UnitRegistry::with_defaults();
+
+        assert!(is_close!(
+            registry
+                .conversion_factor("GtC", "GtCO2", &["CO2"])
+                .unwrap(),
+            44.009 / 12.011
+        ));
+        assert!(is_close!(
+            registry.conversion_factor("ppm", "GtC", &["CO2"]).unwrap(),
+            2.124
+        ));
+        // Chained conversions
+        assert!(is_close!(
+            registry
+                .conversion_factor("MtCO2", "ppb", &["CO2"])
+                .unwrap(),
+            1.0 / (44.009 / 12.011) / 2.124
+        ));
+        assert!(registry
+            .conversion_factor("GtC", "GtCO2", &["N2O"])
+            .is_err());
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Unit::parse("W / m^2").unwrap(),
+            Unit::parse("W*m^-2").unwrap()
+        );
+        assert_eq!(Unit::parse("m ^ 2 / m").unwrap(), Unit::parse("m").unwrap());
+        assert_eq!(
+            Unit::parse("GtC / yr / yr").unwrap(),
+            Unit::parse("GtC yr^-2").unwrap()
+        );
+        assert!(Unit::parse("kg m^-2 s^-1").is_ok());
+        assert_eq!(
+            Unit::parse("1 / yr").unwrap(),
+            Unit::parse("yr^-1").unwrap()
+        );
+        assert!(Unit::parse("yr /").is_err());
+        assert!(Unit::parse("* yr").is_err());
+        assert!(Unit::parse("m^two").is_err());
+
+        // Unparseable units are treated as a single symbol
+        assert_eq!(Unit::from("m^two").as_str(), "m^two");
+        assert_ne!(Unit::from("m^two"), Unit::from("m"));
+
+        let unit: Unit = serde_json::from_str("\"GtC / yr\"").unwrap();
+        assert_eq!(unit, Unit::from("GtC yr^-1"));
+        assert_eq!(serde_json::to_string(&unit).unwrap(), "\"GtC / yr\"");
+    }
+
+    #[test]
+    fn composed_units() {
+        let mut registry = UnitRegistry::with_defaults();
+        registry.register_unit("month", "[time]", 1.0 / 12.0);
+
+        assert!(is_close!(
+            registry
+                .conversion_factor("MtC / month", "GtC / yr", &NO_CONTEXT)
+                .unwrap(),
+            12e-3
+        ));
+        assert!(is_close!(
+            registry
+                .conversion_factor("GtC yr^-1", "MtC/yr", &NO_CONTEXT)
+                .unwrap(),
+            1000.0
+        ));
+        // Contexts apply within compound units
+        assert!(is_close!(
+            registry
+                .conversion_factor("GtC / month", "MtCO2 / yr", &["CO2"])
+                .unwrap(),
+            12e3 * 44.009 / 12.011
+        ));
+        assert!(registry
+            .conversion_factor("GtC / month", "GtC", &["CO2"])
+            .is_err());
+        assert!(registry
+            .conversion_factor("GtC / fortnight", "GtC / yr", &NO_CONTEXT)
+            .is_err());
+    }
+
+    #[test]
+    fn custom_units() {
+        let mut registry = UnitRegistry::new();
+        registry
+            .register_unit("GtN", "[nitrogen]", 1e9)
+            .register_unit("MtN2O", "[N2O]", 1e6)
+            .register_context("N2O", "[nitrogen]", "[N2O]", 44.013 / 28.014);
+
+        assert!(is_close!(
+            registry
+                .conversion_factor("GtN", "MtN2O", &["N2O"])
+                .unwrap(),
+            1e3 * 44.013 / 28.014
+        ));
+        assert!(!registry.is_convertible("GtN", "MtN2O", &NO_CONTEXT));
+    }
+}
@@ -0,0 +1,86 @@
+//! Checks that the names re-exported by the prelude don't change unintentionally
+//!
+//! Only the names of the items are compared, not their signatures.
+//! If an item is deliberately added to or removed from the prelude,
+//! update `prelude-exports.txt` to match.
+use numpy::ndarray::Array;
+use rscm_core::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Items exported by the prelude, sorted by name
+fn prelude_items() -> Vec<String> {
+    let source = include_str!("../src/prelude.rs");
+    let mut items: Vec<String> = source
+        .split(';')
+        .filter_map(|statement| statement.split("pub use ").nth(1))
+        .flat_map(|path| {
+            let path = path.split_whitespace().collect::<String>();
+            let names = match path.split_once('{') {
+                Some((_, names)) => names.trim_end_matches('}').to_string(),
+                None => path.rsplit("::").next().unwrap().to_string(),
+            };
+            names
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(|name| format!("rscm_core::prelude::{}", name))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    items.sort();
+    items
+}
+
+#[test]
+fn prelude_exports_match() {
+    let expected: Vec<&str> = include_str!("../prelude-exports.txt").lines().collect();
+    assert_eq!(
+        prelude_items(),
+        expected,
+        "The prelude has changed, update prelude-exports.txt if this is intended"
+    );
+}
+
+/// A component written using only the prelude
+#[derive(Debug, Serialize, Deserialize)]
+struct Doubler {}
+
+#[typetag::serde]
+impl Component for Doubler {
+    fn definitions(&self) -> Vec<RequirementDefinition> {
+        vec![
+            RequirementDefinition::new("Input", "", RequirementType::Input),
+            RequirementDefinition::new("Output", "", RequirementType::Output),
+        ]
+    }
+
+    fn solve(
+        &self,
+        _t_current: Time,
+        _t_next: Time,
+        input_state: &InputState,
+    ) -> RSCMResult<OutputState> {
+        let value: FloatValue = *input_state.get("Input") * 2.0;
+        Ok(OutputState::from_vectors(vec![value], self.output_names()))
+    }
+}
+
+#[test]
+fn prelude_is_sufficient_for_components() {
+    let input = Timeseries::new(
+        Array::from_elem(3, 1.5),
+        Arc::new(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0))),
+        "".to_string(),
+        InterpolationStrategy::from(PreviousStrategy::new(true)),
+    );
+    let mut model: Model = ModelBuilder::new()
+        .with_time_axis(TimeAxis::from_values(Array::range(2020.0, 2023.0, 1.0)))
+        .with_component(Arc::new(Doubler {}))
+        .with_exogenous_variable("Input", input)
+        .build();
+    model.run();
+
+    let collection: &TimeseriesCollection = model.timeseries();
+    let output = collection.get_timeseries_by_name("Output").unwrap();
+    assert_eq!(output.at(1), Some(3.0));
+}
@@ -1,2 +1,2 @@
 pub mod python;
-mod two_layer;
+pub mod two_layer;
@@ -1,15 +1,46 @@
-use crate::two_layer::{TwoLayerComponent, TwoLayerComponentParameters};
+use crate::two_layer::TwoLayerComponentParameters;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::wrap_pymodule;
-use rscm_components::python::components;
-use rscm_core::create_component_builder;
-use rscm_core::python::{core, PyRustComponent};
+use rscm_components::python::{components, TwoLayerComponentBuilder};
+use rscm_core::python::core;
+use rscm_core::python::timeseries::PyTimeseries;
+use rscm_core::python::timeseries_collection::PyTimeseriesCollection;
 
-create_component_builder!(
-    TwoLayerComponentBuilder,
-    TwoLayerComponent,
-    TwoLayerComponentParameters
-);
+/// Emulate the temperature response to a prescribed ERF pathway
+///
+/// See [`crate::two_layer::run_two_layer`]
+#[pyfunction]
+fn run_two_layer(
+    erf: Bound<PyTimeseries>,
+    parameters: Bound<PyAny>,
+) -> PyResult<PyTimeseriesCollection> {
+    let parameters = pythonize::depythonize_bound::<TwoLayerComponentParameters>(parameters)
+        .map_err(|e| PyValueError::new_err(format!("{}", e)))?;
+    let erf = erf.borrow().0.clone();
+
+    Ok(PyTimeseriesCollection(crate::two_layer::run_two_layer(
+        erf, parameters,
+    )))
+}
+
+/// Diagnose the ERF implied by a prescribed surface temperature
+///
+/// See [`crate::two_layer::diagnose_forcing`]
+#[pyfunction]
+fn diagnose_forcing(
+    temperature: Bound<PyTimeseries>,
+    parameters: Bound<PyAny>,
+) -> PyResult<PyTimeseriesCollection> {
+    let parameters = pythonize::depythonize_bound::<TwoLayerComponentParameters>(parameters)
+        .map_err(|e| PyValueError::new_err(format!("{}", e)))?;
+    let temperature = temperature.borrow().0.clone();
+
+    Ok(PyTimeseriesCollection(crate::two_layer::diagnose_forcing(
+        temperature,
+        parameters,
+    )?))
+}
 
 #[pymodule]
 #[pyo3(name = "_lib")]
@@ -17,7 +48,10 @@ fn rscm(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add("__version__", env!("CARGO_PKG_VERSION"))?;
     m.add_wrapped(wrap_pymodule!(core))?;
     m.add_wrapped(wrap_pymodule!(components))?;
+    // Also available from the top-level module for backwards compatibility
     m.add_class::<TwoLayerComponentBuilder>()?;
+    m.add_function(wrap_pyfunction!(run_two_layer, m)?)?;
+    m.add_function(wrap_pyfunction!(diagnose_forcing, m)?)?;
 
     set_path(m, "rscm._lib.core", "core")?;
     set_path(m, "rscm._lib.components", "components")?;
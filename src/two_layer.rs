@@ -1,152 +1,203 @@
-#![allow(dead_code)]
-
-use ode_solvers::*;
+//! The two-layer energy balance model
+//!
+//! The [`TwoLayerComponent`] is provided by `rscm-components` and is re-exported here
+//! alongside helpers for running it in isolation.
 use std::sync::Arc;
 
-use rscm_core::component::{
-    Component, InputState, OutputState, RequirementDefinition, RequirementType, State,
-};
-use rscm_core::errors::RSCMResult;
-use rscm_core::ivp::{IVPBuilder, IVP};
-use rscm_core::timeseries::{FloatValue, Time};
-use serde::{Deserialize, Serialize};
-
-// Define some types that are used by OdeSolvers
-type ModelState = Vector3<FloatValue>;
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct TwoLayerComponentParameters {
-    pub lambda0: FloatValue,
-    pub a: FloatValue,
-    pub efficacy: FloatValue,
-    pub eta: FloatValue,
-    pub heat_capacity_surface: FloatValue,
-    pub heat_capacity_deep: FloatValue,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TwoLayerComponent {
+use numpy::ndarray::Array1;
+use rscm_core::errors::{RSCMError, RSCMResult};
+use rscm_core::interpolate::strategies::{InterpolationStrategy, PreviousStrategy};
+use rscm_core::model::ModelBuilder;
+use rscm_core::timeseries::{FloatValue, Timeseries};
+use rscm_core::timeseries_collection::{TimeseriesCollection, VariableType};
+
+pub use rscm_components::{TwoLayerComponent, TwoLayerComponentParameters};
+
+/// Emulate the temperature response to a prescribed Effective Radiative Forcing (ERF) pathway
+///
+/// This is a convenience wrapper which builds and runs a model containing only a
+/// [`TwoLayerComponent`].
+/// The model is solved on the time axis of `erf`.
+///
+/// Returns the state of the model after the run,
+/// including the "Effective Radiative Forcing", "Surface Temperature",
+/// "Deep Ocean Temperature" and "Ocean Heat Content" timeseries.
+pub fn run_two_layer(
+    erf: Timeseries<FloatValue>,
     parameters: TwoLayerComponentParameters,
+) -> TimeseriesCollection {
+    let mut model = ModelBuilder::new()
+        .with_time_axis(erf.time_axis().as_ref().clone())
+        .with_component(Arc::new(TwoLayerComponent::from_parameters(parameters)))
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+        .build();
+    model.run();
+
+    model.timeseries().clone()
 }
 
-// Create the set of ODEs to represent the two layer model
-impl IVP<Time, ModelState> for TwoLayerComponent {
-    fn calculate_dy_dt(
-        &self,
-        _t: Time,
-        input_state: &InputState,
-        y: &ModelState,
-        dy_dt: &mut ModelState,
-    ) {
-        let temperature_surface = y[0];
-        let temperature_deep = y[1];
-        let erf = input_state.get("Effective Radiative Forcing");
-
-        let temperature_difference = temperature_surface - temperature_deep;
-
-        let lambda_eff = self.parameters.lambda0 - self.parameters.a * temperature_surface;
-        let heat_exchange_surface =
-            self.parameters.efficacy * self.parameters.eta * temperature_difference;
-        let dtemperature_surface_dt =
-            (erf - lambda_eff * temperature_surface - heat_exchange_surface)
-                / self.parameters.heat_capacity_surface;
-
-        let heat_exchange_deep = self.parameters.eta * temperature_difference;
-        let dtemperature_deep_dt = heat_exchange_deep / self.parameters.heat_capacity_deep;
-
-        dy_dt[0] = dtemperature_surface_dt;
-        dy_dt[1] = dtemperature_deep_dt;
-        dy_dt[2] = self.parameters.heat_capacity_surface * dtemperature_surface_dt
-            + self.parameters.heat_capacity_deep * dtemperature_deep_dt;
-    }
-}
-
-impl TwoLayerComponent {
-    pub fn from_parameters(parameters: TwoLayerComponentParameters) -> Self {
-        Self { parameters }
-    }
-}
-
-#[typetag::serde]
-impl Component for TwoLayerComponent {
-    fn definitions(&self) -> Vec<RequirementDefinition> {
-        vec![
-            RequirementDefinition::new(
-                "Effective Radiative Forcing",
-                "W/m^2",
-                RequirementType::Input,
-            ),
-            RequirementDefinition::new("Surface Temperature", "K", RequirementType::Output),
-        ]
+/// Diagnose the Effective Radiative Forcing (ERF) implied by a prescribed surface temperature
+///
+/// This inverts the two-layer equations.
+/// The deep ocean temperature is integrated from the prescribed surface temperature,
+/// after which the ERF over each step is the forcing required to produce the change
+/// in surface temperature over that step.
+/// A forward Euler discretisation is used so that
+/// stepping the two-layer equations with the diagnosed ERF reproduces `temperature`.
+/// Both layers are assumed to start with a temperature of zero.
+///
+/// The value of the ERF at index `n` is the forcing over the step from `n` to `n + 1`
+/// so the ERF timeseries uses previous-value interpolation and the final value is NaN.
+///
+/// Returns a collection containing the prescribed "Surface Temperature" and the diagnosed
+/// "Effective Radiative Forcing" and "Deep Ocean Temperature" timeseries.
+pub fn diagnose_forcing(
+    temperature: Timeseries<FloatValue>,
+    parameters: TwoLayerComponentParameters,
+) -> RSCMResult<TimeseriesCollection> {
+    let time_axis = temperature.time_axis();
+    let values = temperature.values();
+    if values.iter().any(|v| v.is_nan()) {
+        return Err(RSCMError::Error(
+            "Cannot diagnose forcing from a temperature timeseries containing NaN values"
+                .to_string(),
+        ));
     }
 
-    fn solve(
-        &self,
-        t_current: Time,
-        t_next: Time,
-        input_state: &InputState,
-    ) -> RSCMResult<OutputState> {
-        let erf = input_state.get("Effective Radiative Forcing");
-
-        let y0 = ModelState::new(0.0, 0.0, 0.0);
-
-        let solver = IVPBuilder::new(Arc::new(self.to_owned()), input_state.clone(), y0);
-        println!("Solving {:?} with state: {:?}", self, input_state);
+    let n = values.len();
+    let mut erf = Array1::from_elem(n, FloatValue::NAN);
+    let mut temperature_deep = Array1::zeros(n);
 
-        let mut solver = solver.to_rk4(t_current, t_next, 0.1);
-        let stats = solver.integrate().expect("Failed solving");
+    for i in 0..n.saturating_sub(1) {
+        let step = time_axis.at(i + 1).unwrap() - time_axis.at(i).unwrap();
+        let temperature_surface = values[i];
+        let temperature_difference = temperature_surface - temperature_deep[i];
 
-        let results = solver.results();
+        let lambda_eff = parameters.lambda0 - parameters.a * temperature_surface;
+        let dtemperature_surface_dt = (values[i + 1] - temperature_surface) / step;
 
-        println!("Stats {:?}", stats);
-        println!("Results {:?}", results);
-
-        // Create the solver
-
-        Ok(OutputState::from_vectors(
-            vec![erf * self.parameters.lambda0],
-            self.output_names(),
-        ))
+        erf[i] = parameters.heat_capacity_surface * dtemperature_surface_dt
+            + lambda_eff * temperature_surface
+            + parameters.efficacy * parameters.eta * temperature_difference;
+        temperature_deep[i + 1] = temperature_deep[i]
+            + step * parameters.eta * temperature_difference / parameters.heat_capacity_deep;
     }
+
+    let mut collection = TimeseriesCollection::new();
+    collection.add_timeseries(
+        "Effective Radiative Forcing".to_string(),
+        Timeseries::new(
+            erf,
+            time_axis.clone(),
+            "W/m^2".to_string(),
+            InterpolationStrategy::from(PreviousStrategy::new(true)),
+        ),
+        VariableType::Endogenous,
+    )?;
+    collection.add_timeseries(
+        "Deep Ocean Temperature".to_string(),
+        Timeseries::new(
+            temperature_deep,
+            time_axis,
+            temperature.units().to_string(),
+            InterpolationStrategy::from(PreviousStrategy::new(true)),
+        ),
+        VariableType::Endogenous,
+    )?;
+    collection.add_timeseries(
+        "Surface Temperature".to_string(),
+        temperature,
+        VariableType::Exogenous,
+    )?;
+
+    Ok(collection)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use numpy::array;
-    use rscm_core::timeseries::Timeseries;
-    use rscm_core::timeseries_collection::{TimeseriesCollection, VariableType};
 
     #[test]
-    fn it_works() {
-        // Solve the two layer component in isolation
-        let model = TwoLayerComponent::from_parameters(TwoLayerComponentParameters {
-            lambda0: 0.5,
-            a: 0.01,
-            efficacy: 0.5,
-            eta: 0.1,
-            heat_capacity_surface: 1.0,
-            heat_capacity_deep: 100.0,
-        });
+    fn run_from_erf() {
+        let erf = Timeseries::from_values(
+            array![1.0, 1.5, 2.0, 2.0],
+            array![1848.0, 1849.0, 1850.0, 1851.0],
+        );
 
-        let mut ts_collection = TimeseriesCollection::new();
-        ts_collection.add_timeseries(
-            "Effective Radiative Forcing".to_string(),
-            Timeseries::from_values(
-                array![1.0, 1.5, 2.0, 2.0],
-                array![1848.0, 1849.0, 1850.0, 1900.0],
-            ),
-            VariableType::Exogenous,
+        let results = run_two_layer(
+            erf,
+            TwoLayerComponentParameters {
+                lambda0: 0.5,
+                a: 0.01,
+                efficacy: 0.5,
+                eta: 0.1,
+                heat_capacity_surface: 1.0,
+                heat_capacity_deep: 100.0,
+            },
         );
 
-        let input_state = model.extract_state(&ts_collection, 1848.0);
-        println!("Input: {:?}", input_state);
+        let temperature = results
+            .get_timeseries_by_name("Surface Temperature")
+            .unwrap();
+        assert_eq!(temperature.len(), 4);
+        assert_eq!(temperature.at_time(1848.0).unwrap(), 0.0);
+        // The temperature carries on rising from the previous step
+        let values = temperature.values();
+        assert!(values.windows(2).into_iter().all(|w| w[1] > w[0]));
+        assert!(results
+            .get_timeseries_by_name("Ocean Heat Content")
+            .is_some());
+        assert!(results
+            .get_timeseries_by_name("Effective Radiative Forcing")
+            .is_some());
+    }
 
-        // Create the solver
-        let output_state = model.solve(1848.0, 1849.0, &input_state);
+    #[test]
+    fn diagnose_forcing_roundtrip() {
+        let parameters = TwoLayerComponentParameters {
+            lambda0: 1.2,
+            a: 0.05,
+            efficacy: 1.3,
+            eta: 0.7,
+            heat_capacity_surface: 8.0,
+            heat_capacity_deep: 100.0,
+        };
+        let erf = [1.0, 1.5, 2.0, 2.5, 3.0];
+
+        // Forward Euler integration of the two-layer equations
+        let step = 0.5;
+        let (mut temperature_surface, mut temperature_deep) = (0.0, 0.0);
+        let mut temperature = vec![temperature_surface];
+        erf.iter().for_each(|erf| {
+            let temperature_difference = temperature_surface - temperature_deep;
+            let lambda_eff = parameters.lambda0 - parameters.a * temperature_surface;
+            let dtemperature_surface_dt = (erf
+                - lambda_eff * temperature_surface
+                - parameters.efficacy * parameters.eta * temperature_difference)
+                / parameters.heat_capacity_surface;
+            temperature_deep +=
+                step * parameters.eta * temperature_difference / parameters.heat_capacity_deep;
+            temperature_surface += step * dtemperature_surface_dt;
+            temperature.push(temperature_surface);
+        });
 
-        println!("Output: {:?}", output_state);
-        let output_state = output_state.unwrap();
-        assert_eq!(*output_state.get("Surface Temperature"), 0.5);
+        let time = Array1::range(2000.0, 2003.0, step);
+        let results = diagnose_forcing(
+            Timeseries::from_values(Array1::from(temperature), time),
+            parameters,
+        )
+        .unwrap();
+
+        let diagnosed = results
+            .get_timeseries_by_name("Effective Radiative Forcing")
+            .unwrap();
+        erf.iter().enumerate().for_each(|(i, expected)| {
+            assert!((diagnosed.at(i).unwrap() - expected).abs() < 1e-10);
+        });
+        assert!(diagnosed.at(erf.len()).unwrap().is_nan());
+        assert!(results
+            .get_timeseries_by_name("Deep Ocean Temperature")
+            .is_some());
     }
 }
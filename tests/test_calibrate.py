@@ -0,0 +1,68 @@
+import numpy as np
+import pytest
+
+from rscm._lib import TwoLayerComponentBuilder
+from rscm.core import (
+    InterpolationStrategy,
+    ModelBuilder,
+    OptimisationResult,
+    TimeAxis,
+    Timeseries,
+    calibrate,
+)
+
+TIME_AXIS = TimeAxis.from_values(np.arange(1850.0, 1900.0, 1.0))
+
+
+def build(parameters):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=parameters["lambda0"],
+            a=0.0,
+            efficacy=1.0,
+            eta=0.7,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=7.3,
+        )
+    ).build()
+    erf = Timeseries(
+        np.linspace(0.0, 2.0, len(TIME_AXIS)),
+        TIME_AXIS,
+        "W / m^2",
+        InterpolationStrategy.Next,
+    )
+    return (
+        ModelBuilder()
+        .with_time_axis(TIME_AXIS)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+    )
+
+
+def temperature(output):
+    return output.get_timeseries_by_name("Surface Temperature").values()[1:]
+
+
+def test_calibrate():
+    model = build({"lambda0": 1.1}).build()
+    model.run()
+    observed = temperature(model.timeseries())
+
+    result = calibrate(
+        {"lambda0": 0.5},
+        {"lambda0": (0.1, 3.0)},
+        build,
+        lambda output: float(np.sum((temperature(output) - observed) ** 2)),
+    )
+
+    assert isinstance(result, OptimisationResult)
+    assert result.converged
+    assert result.parameters["lambda0"] == pytest.approx(1.1, abs=1e-3)
+
+
+def test_calibrate_error():
+    def cost(output):
+        raise ValueError("Invalid output")
+
+    with pytest.raises(ValueError, match="Invalid output"):
+        calibrate({"lambda0": 0.5}, {"lambda0": (0.1, 3.0)}, build, cost)
@@ -0,0 +1,74 @@
+import pytest
+
+from rscm.components import (
+    BlendedTemperatureBuilder,
+    CarbonCycleBuilder,
+    CH4ERFBuilder,
+    CO2ERFBuilder,
+    FaIRCarbonCycleBuilder,
+    GreensFunctionTemperatureBuilder,
+    InternalVariabilityBuilder,
+    N2OERFBuilder,
+    OceanCarbonCycleBuilder,
+    OceanSurfacePartialPressureBuilder,
+    PatternScalingBuilder,
+)
+
+PARAMETERS = {
+    BlendedTemperatureBuilder: dict(
+        land_fraction=0.29, sea_ice_fraction=0.04, sst_ratio=0.93, coverage=1.0
+    ),
+    CarbonCycleBuilder: dict(tau=20.3, conc_pi=280.0, alpha_temperature=0.0),
+    CH4ERFBuilder: dict(conc_pi=729.2),
+    CO2ERFBuilder: dict(erf_2xco2=4.0, conc_pi=280.0),
+    FaIRCarbonCycleBuilder: dict(
+        partition=[0.2173, 0.2240, 0.2824, 0.2763],
+        tau=[1.0e6, 394.4, 36.54, 4.304],
+        r0=35.0,
+        r_uptake=0.019,
+        r_temperature=4.165,
+        r_atmosphere=0.0,
+        iirf_max=97.0,
+        iirf_horizon=100.0,
+        conc_pi=278.0,
+    ),
+    GreensFunctionTemperatureBuilder: dict(q=[0.33, 0.41], d=[8.4, 409.5]),
+    InternalVariabilityBuilder: dict(variance=0.01, autocorrelation=0.5),
+    N2OERFBuilder: dict(conc_pi=270.1),
+    OceanCarbonCycleBuilder: dict(mixing_timescale=12.0),
+    OceanSurfacePartialPressureBuilder: dict(
+        ospp_preindustrial=278.0,
+        sensitivity_ospp_to_temperature=0.043,
+        sea_surface_temperature_preindustrial=17.9,
+        delta_ospp_offsets=[1.5568, 7.4706, 1.2748, 2.4491, 1.5468],
+        delta_ospp_coefficients=[-0.013993, -0.20207, -0.12015, -0.12639, -0.15326],
+    ),
+    PatternScalingBuilder: dict(regions=["Land", "Ocean"], patterns=[1.4, 0.85]),
+}
+
+
+@pytest.mark.parametrize("builder", PARAMETERS.keys())
+def test_builder(builder):
+    parameters = PARAMETERS[builder]
+
+    from_kwargs = builder(**parameters).build()
+    from_dict = builder.from_parameters(parameters).build()
+
+    assert [d.name for d in from_kwargs.definitions()] == [
+        d.name for d in from_dict.definitions()
+    ]
+    assert builder.__doc__.startswith("Builder for the")
+
+
+def test_builder_missing_parameter():
+    with pytest.raises(ValueError, match="missing field `conc_pi`"):
+        CO2ERFBuilder(erf_2xco2=4.0)
+
+
+def test_fair_carbon_cycle_defaults():
+    default = FaIRCarbonCycleBuilder().build()
+    overridden = FaIRCarbonCycleBuilder(r_temperature=0.0).build()
+
+    assert [d.name for d in default.definitions()] == [
+        d.name for d in overridden.definitions()
+    ]
@@ -13,6 +13,9 @@ class ExamplePythonComponent:
     ) -> dict[str, float]:
         return {"output": input_state.get("input") * 3}
 
+    def initialise(self, t0: float) -> dict[str, float]:
+        return {"output": 1.0}
+
 
 def test_component_definitions():
     component = TestComponentBuilder.from_parameters({"p": 12}).build()
@@ -44,3 +47,5 @@ def test_user_derived_create_and_solve():
 
     res = component.solve(0, 1, {"input": 35.0})
     assert res["output"] == 35.0 * 3.0
+
+    assert component.initialise(0) == {"output": 1.0}
@@ -0,0 +1,31 @@
+import pytest
+
+import rscm
+from rscm.core import TimeseriesCollection
+
+CSV = """\
+Model,Scenario,Region,Variable,Unit,2015,2020,2030
+MESSAGE,SSP2-4.5,World,Emissions|CO2,Mt CO2/yr,39152.7,40647.8,43476.1
+MESSAGE,SSP5-8.5,World,Emissions|CO2,Mt CO2/yr,39152.7,43712.3,55297.6
+"""
+
+
+def test_load_scenario(tmp_path):
+    path = tmp_path / "ssp.csv"
+    path.write_text(CSV)
+
+    collection = rscm.load_scenario(path, scenario="SSP2-4.5")
+
+    assert isinstance(collection, TimeseriesCollection)
+    assert collection.names() == ["Emissions|CO2"]
+    emissions = collection.get_timeseries_by_name("Emissions|CO2")
+    assert emissions.units == "Mt CO2/yr"
+    assert emissions.at_time(2020.0) == pytest.approx(40647.8)
+
+
+def test_load_scenario_ambiguous(tmp_path):
+    path = tmp_path / "ssp.csv"
+    path.write_text(CSV)
+
+    with pytest.raises(RuntimeError, match="select one"):
+        rscm.load_scenario(path)
@@ -0,0 +1,49 @@
+import numpy as np
+import pytest
+
+from rscm.core import (
+    InterpolationStrategy,
+    TimeAxis,
+    Timeseries,
+    TimeseriesCollection,
+    VariableType,
+    score_card,
+)
+
+
+def _collection(values):
+    collection = TimeseriesCollection()
+    collection.add_timeseries(
+        "Surface Temperature",
+        Timeseries(
+            np.asarray(values),
+            TimeAxis.from_values(np.arange(2000.0, 2005.0)),
+            "K",
+            InterpolationStrategy.Linear,
+        ),
+        VariableType.Exogenous,
+    )
+    return collection
+
+
+def test_score_card():
+    model = _collection([1.0, 2.0, 3.0, 4.0, 5.0])
+    references = _collection([0.0, 1.0, 2.0, 3.0, 4.0])
+
+    card = score_card(model, references, [(2000.0, 2004.0), (2000.0, 2001.0)])
+
+    assert len(card) == 2
+    assert card[0].variable == "Surface Temperature"
+    assert (card[1].start, card[1].end) == (2000.0, 2001.0)
+    assert card[0].skill.n == 5
+    assert card[0].skill.bias == pytest.approx(1.0)
+    assert card[0].skill.rmse == pytest.approx(1.0)
+    assert card[0].skill.correlation == pytest.approx(1.0)
+    assert card[0].skill.trend_difference == pytest.approx(0.0)
+
+
+def test_score_card_missing_window():
+    model = _collection([1.0, 2.0, 3.0, 4.0, 5.0])
+
+    with pytest.raises(RuntimeError, match="No valid values to compare"):
+        score_card(model, model, [(2010.0, 2020.0)])
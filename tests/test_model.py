@@ -1,9 +1,30 @@
+import json
+import warnings
+
 import numpy as np
 import numpy.testing as npt
+import pytest
 
 from rscm._lib import TwoLayerComponentBuilder
-from rscm._lib.core import InterpolationStrategy, Model, Timeseries
-from rscm.core import ModelBuilder
+from rscm._lib.core import (
+    InterpolationStrategy,
+    Model,
+    TimeAxis,
+    Timeseries,
+    TimeseriesCollection,
+    VariableType,
+)
+from rscm.components import InternalVariabilityBuilder
+from rscm.core import (
+    BuildError,
+    ComponentError,
+    ComponentFailedWarning,
+    ExtrapolationPolicy,
+    MissingExogenousDataWarning,
+    ModelBuilder,
+    NanPolicy,
+    WarningKind,
+)
 
 
 def test_model(time_axis):
@@ -13,8 +34,8 @@ def test_model(time_axis):
             a=0.0,
             efficacy=0.0,
             eta=0.0,
-            heat_capacity_deep=0.0,
-            heat_capacity_surface=0.0,
+            heat_capacity_deep=1.0,
+            heat_capacity_surface=1.0,
         )
     ).build()
 
@@ -39,6 +60,330 @@ def test_model(time_axis):
 
     model.run()
 
+    assert model.summary().startswith("current time: 1995")
+    assert repr(model) == f"<Model {model.summary()}>"
+    assert "<td>Surface Temperature</td>" in model._repr_html_()
+
+
+def test_model_to_pandas(time_axis):
+    pytest.importorskip("pandas")
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.5,
+            a=0.0,
+            efficacy=0.0,
+            eta=0.0,
+            heat_capacity_deep=1.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+    erf = Timeseries(
+        np.asarray([1.0] * len(time_axis)),
+        time_axis,
+        "W / m^2",
+        InterpolationStrategy.Next,
+    )
+    model = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+        .build()
+    )
+    model.step()
+
+    df = model.to_pandas()
+    assert list(df.index.names) == ["variable", "unit"]
+    npt.assert_allclose(df.columns, time_axis.values())
+    # The deep ocean is decoupled so the surface relaxes towards ERF / lambda0
+    assert df.loc[("Surface Temperature", "K")].iloc[1] == pytest.approx(
+        2.0 * (1.0 - np.exp(-0.5 * 5.0))
+    )
+    assert np.isnan(df.loc[("Surface Temperature", "K")].iloc[2])
+
+    assert model.timeseries().to_pandas().equals(df)
+
+
+def _exogenous_erf(time_axis, values, **kwargs):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.5,
+            a=0.0,
+            efficacy=0.0,
+            eta=0.0,
+            heat_capacity_deep=1.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+    model = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", values, **kwargs)
+        .build()
+    )
+    erf = model.timeseries().get_timeseries_by_name("Effective Radiative Forcing")
+    return erf.units, erf.values()
+
+
+def test_model_exogenous_array(time_axis):
+    times = np.arange(1850.0, 2000.0, 10.0)
+
+    units, values = _exogenous_erf(
+        time_axis, times - 1850.0, times=times, unit="W / m^2"
+    )
+    assert units == "W / m^2"
+    npt.assert_allclose(values, time_axis.values() - 1850.0)
+
+    with pytest.raises(TypeError, match="times must be provided"):
+        _exogenous_erf(time_axis, times)
+
+    with pytest.raises(TypeError, match="unit must be provided"):
+        _exogenous_erf(time_axis, times - 1850.0, times=times)
+
+
+def test_model_exogenous_pandas(time_axis):
+    pd = pytest.importorskip("pandas")
+    times = time_axis.values()
+
+    series = pd.Series(times - 1850.0, index=times)
+    units, values = _exogenous_erf(time_axis, series, unit="W / m^2")
+    assert units == "W / m^2"
+    npt.assert_allclose(values, times - 1850.0)
+
+    # Dates are converted into decimal years
+    dates = pd.to_datetime([f"{int(t)}-01-01" for t in times])
+    frame = pd.DataFrame(
+        {"Effective Radiative Forcing": times - 1850.0, "Other": 0.0}, index=dates
+    )
+    _, values = _exogenous_erf(time_axis, frame, unit="W / m^2")
+    npt.assert_allclose(values, times - 1850.0)
+
+    with pytest.raises(ValueError, match="a single column"):
+        _exogenous_erf(time_axis, frame.rename(columns=str.upper), unit="W / m^2")
+
+    with pytest.raises(TypeError, match="unit must be provided"):
+        _exogenous_erf(time_axis, series)
+
+
+def test_model_exogenous_scmdata(time_axis):
+    scmdata = pytest.importorskip("scmdata")
+    times = time_axis.values()
+
+    run = scmdata.ScmRun(
+        data=(times - 1850.0) * 1e3,
+        index=times.astype(int),
+        columns={
+            "model": "test",
+            "scenario": "test",
+            "region": "World",
+            "variable": "Effective Radiative Forcing",
+            "unit": "mW / m^2",
+        },
+    )
+    units, values = _exogenous_erf(time_axis, run, unit="W / m^2")
+    assert units == "W / m^2"
+    npt.assert_allclose(values, times - 1850.0)
+
+
+def test_model_named_components(time_axis):
+    parameters = dict(
+        lambda0=0.0,
+        a=0.0,
+        efficacy=0.0,
+        eta=0.0,
+        heat_capacity_deep=1.0,
+        heat_capacity_surface=1.0,
+    )
+    component = TwoLayerComponentBuilder.from_parameters(parameters).build()
+
+    model = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component, name="climate")
+        .with_exogenous_variable(
+            "Effective Radiative Forcing",
+            Timeseries(
+                np.asarray([1.0] * len(time_axis)),
+                time_axis,
+                "W / m^2",
+                InterpolationStrategy.Next,
+            ),
+        )
+        .build()
+    )
+
+    assert model.component_names() == ["climate"]
+    assert '"climate"' in model.as_dot()
+    assert model.as_mermaid().startswith('flowchart LR\n    n1["climate"]')
+    assert json.loads(model.as_json())["nodes"][0]["name"] == "climate"
+    model.reseed_rng_streams(1, "climate")
+
+
+def test_model_namespaced_components(time_axis):
+    parameters = dict(
+        lambda0=0.5,
+        a=0.0,
+        efficacy=1.0,
+        eta=0.0,
+        heat_capacity_deep=100.0,
+        heat_capacity_surface=1.0,
+    )
+    builder = ModelBuilder().with_time_axis(time_axis)
+    for namespace in ("Low", "High"):
+        component = TwoLayerComponentBuilder.from_parameters(parameters).build()
+        builder.with_rust_component(component, namespace=namespace)
+    model = builder.with_exogenous_variable(
+        "Effective Radiative Forcing",
+        Timeseries(
+            np.asarray([1.0] * len(time_axis)),
+            time_axis,
+            "W / m^2",
+            InterpolationStrategy.Next,
+        ),
+    ).build()
+
+    assert model.component_names() == ["Low", "High"]
+    assert model.execution_order() == ["Low", "High"]
+
+    estimate = model.estimate_cost(members=10, threads=2)
+    assert estimate.component_solves == 10 * 2 * (len(time_axis) - 1)
+    assert estimate.bytes > 0
+    model.run()
+    assert "High|Surface Temperature" in model.timeseries().names()
+
+
+def test_model_update_exogenous(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.3,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.1,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+
+    def erf(values):
+        return Timeseries(
+            np.asarray(values),
+            time_axis,
+            "W / m^2",
+            InterpolationStrategy.Previous,
+        )
+
+    n = len(time_axis)
+    model = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", erf([1.0] * n))
+        .build()
+    )
+    model.run()
+    original = (
+        model.timeseries().get_timeseries_by_name("Surface Temperature").values()
+    )
+
+    collection = TimeseriesCollection()
+    collection.add_timeseries(
+        "Effective Radiative Forcing",
+        erf([1.0] * (n - 5) + [2.0] * 5),
+        VariableType.Exogenous,
+    )
+    assert model.update_exogenous(collection) == n - 6
+    assert not model.finished()
+
+    model.run()
+    updated = model.timeseries().get_timeseries_by_name("Surface Temperature").values()
+    npt.assert_array_equal(updated[: n - 5], original[: n - 5])
+    assert updated[-1] > original[-1]
+
+
+def test_model_replace_component(time_axis):
+    def two_layer(lambda0):
+        return TwoLayerComponentBuilder.from_parameters(
+            dict(
+                lambda0=lambda0,
+                a=0.0,
+                efficacy=1.0,
+                eta=0.1,
+                heat_capacity_deep=100.0,
+                heat_capacity_surface=1.0,
+            )
+        ).build()
+
+    erf = Timeseries(
+        np.ones(len(time_axis)),
+        time_axis,
+        "W / m^2",
+        InterpolationStrategy.Previous,
+    )
+
+    def build(lambda0):
+        return (
+            ModelBuilder()
+            .with_time_axis(time_axis)
+            .with_rust_component(two_layer(lambda0), name="climate")
+            .with_exogenous_variable("Effective Radiative Forcing", erf)
+            .build()
+        )
+
+    model = build(0.3)
+    model.run()
+    model.replace_component("climate", two_layer(0.6))
+    assert not model.finished()
+    model.run()
+
+    expected = build(0.6)
+    expected.run()
+    npt.assert_array_equal(
+        model.timeseries().get_timeseries_by_name("Surface Temperature").values(),
+        expected.timeseries().get_timeseries_by_name("Surface Temperature").values(),
+    )
+
+    with pytest.raises(RuntimeError, match="No component named"):
+        model.replace_component("ocean", two_layer(0.6))
+
+
+def test_model_sensitivity(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.3,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.1,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+    erf = Timeseries(
+        np.asarray([1.0] * len(time_axis)),
+        time_axis,
+        "W / m^2",
+        InterpolationStrategy.Previous,
+    )
+
+    builder = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+    )
+    model = builder.build()
+    model.run()
+    assert model.sensitivity("Surface Temperature") is None
+
+    model = builder.with_sensitivity("lambda0").build()
+    model.run()
+    npt.assert_array_equal(
+        model.sensitivity("Effective Radiative Forcing").values(), 0.0
+    )
+    # The two-layer component doesn't support dual numbers
+    assert np.isnan(model.sensitivity("Surface Temperature").values()[1:]).all()
+    assert model.sensitivity("Unknown") is None
+
 
 def test_model_serialisation(time_axis):
     component = TwoLayerComponentBuilder.from_parameters(
@@ -47,8 +392,8 @@ def test_model_serialisation(time_axis):
             a=0.0,
             efficacy=0.0,
             eta=0.0,
-            heat_capacity_deep=0.0,
-            heat_capacity_surface=0.0,
+            heat_capacity_deep=1.0,
+            heat_capacity_surface=1.0,
         )
     ).build()
 
@@ -80,3 +425,530 @@ def test_model_serialisation(time_axis):
         new_model.timeseries().get_timeseries_by_name("Surface Temperature").values(),
         model.timeseries().get_timeseries_by_name("Surface Temperature").values(),
     )
+
+
+def test_model_save_state(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.3,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.1,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+    erf = Timeseries(
+        np.asarray([1.0] * len(time_axis)),
+        time_axis,
+        "W / m^2",
+        InterpolationStrategy.Previous,
+    )
+    builder = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+    )
+
+    model = builder.build()
+    model.step()
+    model.step()
+    state = model.save_state()
+    assert f"format_version = {Model.STATE_FORMAT_VERSION}" in state
+
+    restored = Model.load_state(state)
+    assert restored.current_time() == model.current_time()
+
+    model.run()
+    restored.run()
+    npt.assert_array_equal(
+        restored.timeseries().get_timeseries_by_name("Surface Temperature").values(),
+        model.timeseries().get_timeseries_by_name("Surface Temperature").values(),
+    )
+
+    with pytest.raises(RuntimeError, match="format version"):
+        Model.load_state(state.replace("format_version", "other_version"))
+
+
+def test_model_regrid():
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.3,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.1,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+    annual = TimeAxis.from_values(np.arange(1850.0, 1901.0))
+    erf = Timeseries(
+        np.asarray([1.0] * len(annual)),
+        annual,
+        "W / m^2",
+        InterpolationStrategy.Previous,
+    )
+    model = (
+        ModelBuilder()
+        .with_time_axis(annual)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+        .build()
+    )
+    for _ in range(30):
+        model.step()
+    spun_up = model.timeseries().get_timeseries_by_name("Surface Temperature")
+
+    # Continue a spun-up checkpoint with monthly steps
+    restored = Model.load_state(model.save_state())
+    restored.regrid(TimeAxis.from_values(np.linspace(1850.0, 1900.0, 601)))
+    assert restored.current_time() == pytest.approx(1880.0)
+    temperature = restored.timeseries().get_timeseries_by_name("Surface Temperature")
+    assert temperature.values()[360] == spun_up.values()[30]
+
+    restored.run()
+    temperature = restored.timeseries().get_timeseries_by_name("Surface Temperature")
+    assert np.isfinite(temperature.values()).all()
+
+    with pytest.raises(RuntimeError, match="doesn't contain the current time"):
+        model.regrid(TimeAxis.from_values(np.arange(1850.5, 1900.0)))
+
+
+def test_model_observer(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.3,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.1,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+    erf = Timeseries(
+        np.asarray([1.0] * len(time_axis)),
+        time_axis,
+        "W / m^2",
+        InterpolationStrategy.Previous,
+    )
+    events = []
+    model = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+        .with_observer(events.append)
+        .build()
+    )
+    assert events == [{"kind": "BuildStarted", "components": ["TwoLayerComponent"]}]
+
+    model.step()
+    assert events[-1] == {"kind": "StepCompleted", "time_index": 1, "time": 1855.0}
+
+    model.run()
+    assert events[-1] == {"kind": "RunFinished", "time": time_axis.values()[-1]}
+    assert len(events) == 1 + (len(time_axis) - 1) + 1
+
+
+def test_model_attribution(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.5,
+            a=0.0,
+            efficacy=0.0,
+            eta=0.0,
+            heat_capacity_deep=1.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+
+    builder = ModelBuilder()
+    builder.with_time_axis(time_axis).with_rust_component(component)
+    erf = Timeseries(
+        np.asarray([1.0] * len(time_axis)),
+        time_axis,
+        "W / m^2",
+        InterpolationStrategy.Next,
+    )
+    builder.with_exogenous_variable("Effective Radiative Forcing", erf)
+
+    total, contributions = builder.run_attribution(
+        "Surface Temperature", {"total": ["Effective Radiative Forcing"]}
+    )
+
+    assert list(contributions) == ["total"]
+    npt.assert_allclose(contributions["total"].values()[1:], total.values()[1:])
+
+
+def test_component_from_run(time_axis, tmp_path):
+    parameters = dict(
+        lambda0=0.3,
+        a=0.0,
+        efficacy=1.0,
+        eta=0.1,
+        heat_capacity_deep=100.0,
+        heat_capacity_surface=1.0,
+    )
+    component = TwoLayerComponentBuilder.from_parameters(parameters).build()
+
+    erf = Timeseries(
+        np.asarray([1.0] * len(time_axis)),
+        time_axis,
+        "W / m^2",
+        InterpolationStrategy.Next,
+    )
+    model = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+        .build()
+    )
+    model.run()
+    (tmp_path / "model.toml").write_text(model.to_toml())
+
+    builder = TwoLayerComponentBuilder.from_run(
+        tmp_path / "model.toml", "TwoLayerComponent"
+    )
+    state = {
+        "Effective Radiative Forcing": 1.0,
+        "Surface Temperature": 0.0,
+        "Deep Ocean Temperature": 0.0,
+        "Ocean Heat Content": 0.0,
+    }
+    res = builder.build().solve(2000, 2010, state)
+    assert res == component.solve(2000, 2010, state)
+
+    with pytest.raises(RuntimeError, match="No component named 'CO2ERF' in run"):
+        TwoLayerComponentBuilder.from_run(tmp_path / "model.toml", "CO2ERF")
+
+
+def test_model_stability_options(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.3,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.1,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+    erf = Timeseries(
+        np.asarray([1.0] * len(time_axis)),
+        time_axis,
+        "W / m^2",
+        InterpolationStrategy.Next,
+    )
+
+    model = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+        .with_stability_options(max_relative_change=1.0, max_refinements=2)
+        .build()
+    )
+    model.run()
+
+    assert model.refinements() == []
+
+
+def test_model_run_limits(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.3,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.1,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+    erf = Timeseries(
+        np.asarray([1.0] * len(time_axis)),
+        time_axis,
+        "W / m^2",
+        InterpolationStrategy.Next,
+    )
+
+    model = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+        .with_run_limits(max_steps=1)
+        .build()
+    )
+
+    with pytest.raises(TimeoutError, match="maximum of 1 steps reached"):
+        model.run()
+    assert not model.finished()
+
+
+def test_model_rng_streams(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.3,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.1,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+    erf = Timeseries(
+        np.asarray([1.0] * len(time_axis)),
+        time_axis,
+        "W / m^2",
+        InterpolationStrategy.Next,
+    )
+    model = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+        .build()
+    )
+
+    streams = model.save_rng_streams()
+    assert "[[streams]]" in streams
+
+    model.reseed_rng_streams(1)
+    assert model.save_rng_streams() != streams
+    model.restore_rng_streams(streams)
+    assert model.save_rng_streams() == streams
+
+    model.reseed_rng_streams(2, "TwoLayerComponent")
+    with pytest.raises(RuntimeError, match="No component named 'CO2ERF'"):
+        model.reseed_rng_streams(2, "CO2ERF")
+
+
+def test_model_rng_seed(time_axis):
+    temperature = Timeseries(
+        np.ones(len(time_axis)),
+        time_axis,
+        "K",
+        InterpolationStrategy.Previous,
+    )
+
+    def run(seed, member):
+        component = InternalVariabilityBuilder(
+            variance=0.01, autocorrelation=0.5
+        ).build()
+        model = (
+            ModelBuilder()
+            .with_time_axis(time_axis)
+            .with_rust_component(component)
+            .with_exogenous_variable("Surface Temperature", temperature)
+            .with_rng_seed(seed)
+            .with_ensemble_member(member)
+            .build()
+        )
+        model.run()
+        return (
+            model.timeseries()
+            .get_timeseries_by_name("Surface Temperature|Internal Variability")
+            .values()[1:]
+        )
+
+    npt.assert_array_equal(run(1, 0), run(1, 0))
+    assert not np.array_equal(run(1, 0), run(1, 1))
+    assert not np.array_equal(run(1, 0), run(2, 0))
+
+
+def test_model_options(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.3,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.1,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+    erf = Timeseries(
+        np.asarray([1.0] * len(time_axis)),
+        time_axis,
+        "W / m^2",
+        InterpolationStrategy.Next,
+    )
+
+    builder = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component)
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+        .with_options(
+            nan_policy=NanPolicy.Warn,
+            extrapolation=ExtrapolationPolicy.error(),
+            threads=2,
+            parallel_components=True,
+        )
+    )
+
+    total, contributions = builder.run_attribution(
+        "Surface Temperature", {"a": ["Effective Radiative Forcing"], "b": []}
+    )
+    assert sorted(contributions) == ["a", "b"]
+
+    model = builder.build()
+    model.run()
+    assert model.finished()
+
+
+def test_model_warnings(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.3,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.1,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+    builder = ModelBuilder().with_time_axis(time_axis).with_rust_component(component)
+
+    with pytest.warns(
+        MissingExogenousDataWarning,
+        match="No exogenous data for Effective Radiative Forcing",
+    ):
+        model = builder.build()
+    assert model.warnings() == [
+        (
+            WarningKind.MissingExogenousData,
+            "No exogenous data for Effective Radiative Forcing",
+        )
+    ]
+
+    # Pipelines can fail on specific classes of warning
+    with warnings.catch_warnings():
+        warnings.simplefilter("error", MissingExogenousDataWarning)
+        with pytest.raises(MissingExogenousDataWarning):
+            builder.build()
+
+
+def test_model_try_build(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.3,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.1,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+    builder = ModelBuilder().with_time_axis(time_axis).with_rust_component(component)
+
+    with pytest.raises(BuildError, match="Model can't be built") as excinfo:
+        builder.try_build()
+    assert isinstance(excinfo.value, ValueError)
+    assert "No exogenous data for Effective Radiative Forcing" in excinfo.value.problems
+
+
+def test_model_try_run(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.3,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.1,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=1.0,
+        )
+    ).build()
+
+    # The missing forcing is NaN, which is an error with this policy
+    with pytest.warns(MissingExogenousDataWarning):
+        model = (
+            ModelBuilder()
+            .with_time_axis(time_axis)
+            .with_rust_component(component)
+            .with_options(nan_policy=NanPolicy.Error)
+            .build()
+        )
+
+    with pytest.warns(ComponentFailedWarning):
+        with pytest.raises(ComponentError, match="produced NaN values") as excinfo:
+            model.try_run()
+    assert isinstance(excinfo.value, RuntimeError)
+    assert excinfo.value.component == "TwoLayerComponent"
+    assert excinfo.value.time_index == 0
+    assert excinfo.value.time == 1850.0
+    assert not model.finished()
+
+
+def test_model_metadata(time_axis):
+    component = InternalVariabilityBuilder(variance=0.01, autocorrelation=0.5).build()
+    temperature = Timeseries(
+        np.ones(len(time_axis)),
+        time_axis,
+        "K",
+        InterpolationStrategy.Previous,
+    )
+    model = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(component)
+        .with_exogenous_variable("Surface Temperature", temperature)
+        .with_scenario("ssp245")
+        .with_parameters({"variance": 0.01})
+        .with_version("rscm", "1.0.0")
+        .build()
+    )
+    assert "wall_time" not in model.metadata()
+    model.run()
+
+    metadata = model.metadata()
+    assert metadata["scenario"] == "ssp245"
+    assert metadata["parameters"] == {"variance": 0.01}
+    assert metadata["versions"]["rscm"] == "1.0.0"
+    assert "rscm-core" in metadata["versions"]
+    assert metadata["wall_time"] >= 0
+
+    assert Model.from_toml(model.to_toml()).metadata() == metadata
+
+
+def test_model_to_component(time_axis):
+    component = TwoLayerComponentBuilder.from_parameters(
+        dict(
+            lambda0=0.5,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.5,
+            heat_capacity_deep=100.0,
+            heat_capacity_surface=8.0,
+        )
+    ).build()
+    erf = Timeseries(
+        np.linspace(0.0, 2.0, len(time_axis)),
+        time_axis,
+        "W / m^2",
+        InterpolationStrategy.Previous,
+    )
+
+    builder = ModelBuilder().with_time_axis(time_axis).with_rust_component(component)
+    # The forcing is an input of the sub-model
+    with pytest.warns(MissingExogenousDataWarning):
+        sub_model = builder.build().to_component()
+    flat = builder.with_exogenous_variable("Effective Radiative Forcing", erf).build()
+    flat.run()
+
+    nested = (
+        ModelBuilder()
+        .with_time_axis(time_axis)
+        .with_rust_component(sub_model, name="climate")
+        .with_exogenous_variable("Effective Radiative Forcing", erf)
+        .build()
+    )
+    nested.run()
+
+    assert nested.component_names() == ["climate"]
+    npt.assert_allclose(
+        nested.timeseries().get_timeseries_by_name("Surface Temperature").values()[1:],
+        flat.timeseries().get_timeseries_by_name("Surface Temperature").values()[1:],
+    )
@@ -0,0 +1,207 @@
+import json
+
+import numpy as np
+import numpy.testing as npt
+import pytest
+
+from rscm import run, run_ensemble
+from rscm.core import (
+    InterpolationStrategy,
+    TimeAxis,
+    Timeseries,
+    TimeseriesCollection,
+)
+
+CONFIG = {
+    "time_axis": {"start": 1850.0, "end": 1855.0},
+    "components": {
+        "temperature": {
+            "type": "TwoLayerComponent",
+            "parameters": dict(
+                lambda0=0.5,
+                a=0.01,
+                efficacy=0.5,
+                eta=0.1,
+                heat_capacity_surface=1.0,
+                heat_capacity_deep=100.0,
+            ),
+        }
+    },
+}
+
+HIGH_SENSITIVITY_VARIABLES = {
+    name: f"{name}|High"
+    for name in ["Surface Temperature", "Deep Ocean Temperature", "Ocean Heat Content"]
+}
+
+
+def erf():
+    return Timeseries(
+        np.asarray([1.0] * 6),
+        TimeAxis.from_values(np.arange(1850.0, 1856.0)),
+        "W / m^2",
+        InterpolationStrategy.Previous,
+    )
+
+
+def test_run_collection():
+    results = run(
+        CONFIG, exogenous={"Effective Radiative Forcing": erf()}, output="collection"
+    )
+
+    assert isinstance(results, TimeseriesCollection)
+    temperature = results.get_timeseries_by_name("Surface Temperature")
+    assert temperature.at_time(1851.0) > 0.0
+
+
+def test_run_pandas(tmp_path):
+    pd = pytest.importorskip("pandas")
+    exogenous = pd.DataFrame(
+        [[1.0] * 6],
+        index=pd.MultiIndex.from_tuples(
+            [("Effective Radiative Forcing", "W / m^2")], names=["variable", "unit"]
+        ),
+        columns=np.arange(1850.0, 1856.0),
+    )
+    config = tmp_path / "config.json"
+    config.write_text(json.dumps(CONFIG))
+
+    results = run(config, exogenous=exogenous)
+
+    assert results.index.names == ["variable", "unit"]
+    temperature = results.loc[("Surface Temperature", "K")]
+    npt.assert_allclose(temperature.index, np.arange(1850.0, 1856.0))
+    assert temperature[1851.0] > 0.0
+
+
+def test_run_xarray():
+    xr = pytest.importorskip("xarray")
+    exogenous = xr.Dataset(
+        {
+            "Effective Radiative Forcing": xr.DataArray(
+                [1.0] * 6,
+                coords={"time": np.arange(1850.0, 1856.0)},
+                dims=["time"],
+                attrs={"units": "W / m^2"},
+            )
+        }
+    )
+
+    results = run(CONFIG, exogenous=exogenous, output="xarray")
+
+    assert results["Surface Temperature"].attrs["units"] == "K"
+    assert float(results["Surface Temperature"].sel(time=1851.0)) > 0.0
+
+
+def test_run_units():
+    results = run(
+        CONFIG,
+        exogenous={"Effective Radiative Forcing": erf()},
+        output="collection",
+        units={"Surface Temperature": "mK"},
+    )
+
+    temperature = results.get_timeseries_by_name("Surface Temperature")
+    assert temperature.units == "mK"
+    assert temperature.at_time(1851.0) > 1.0
+
+    with pytest.raises(RuntimeError, match="Could not convert Surface Temperature"):
+        run(
+            CONFIG,
+            exogenous={"Effective Radiative Forcing": erf()},
+            units={"Surface Temperature": "GtC"},
+        )
+
+
+def test_run_unknown_component():
+    config = dict(CONFIG, components={"x": {"type": "Unknown"}})
+
+    with pytest.raises(ValueError, match="Unknown type 'Unknown' for component 'x'"):
+        run(config)
+
+
+def test_run_remapped_components():
+    temperature = CONFIG["components"]["temperature"]
+    config = dict(
+        CONFIG,
+        components={
+            "temperature": temperature,
+            "temperature-high-sensitivity": dict(
+                temperature,
+                parameters=dict(temperature["parameters"], lambda0=0.25),
+                variables=HIGH_SENSITIVITY_VARIABLES,
+            ),
+        },
+    )
+
+    results = run(
+        config, exogenous={"Effective Radiative Forcing": erf()}, output="collection"
+    )
+
+    default = results.get_timeseries_by_name("Surface Temperature")
+    high = results.get_timeseries_by_name("Surface Temperature|High")
+    # A weaker feedback gives more warming
+    assert high.at_time(1855.0) > default.at_time(1855.0)
+
+
+def test_run_output_variables():
+    temperature = CONFIG["components"]["temperature"]
+    config = dict(
+        CONFIG,
+        components={
+            "temperature": temperature,
+            "temperature-high-sensitivity": dict(
+                temperature,
+                parameters=dict(temperature["parameters"], lambda0=0.25),
+                variables=HIGH_SENSITIVITY_VARIABLES,
+            ),
+        },
+        output_variables=["Surface Temperature|*"],
+    )
+
+    results = run(
+        config, exogenous={"Effective Radiative Forcing": erf()}, output="collection"
+    )
+
+    assert results.get_timeseries_by_name("Surface Temperature") is None
+    assert results.get_timeseries_by_name("Surface Temperature|High") is not None
+
+
+def test_run_ensemble(tmp_path):
+    parameters = [
+        {"temperature.lambda0": 0.5},
+        {"temperature.lambda0": 0.25},
+    ]
+
+    results = run_ensemble(
+        CONFIG,
+        parameters,
+        exogenous={"Effective Radiative Forcing": erf()},
+        output="collection",
+    )
+
+    assert len(results) == 2
+    temperatures = [
+        r.get_timeseries_by_name("Surface Temperature").at_time(1855.0)
+        for r in results
+    ]
+    # A weaker feedback gives more warming
+    assert temperatures[1] > temperatures[0] > 0.0
+    # The configuration isn't modified
+    assert CONFIG["components"]["temperature"]["parameters"]["lambda0"] == 0.5
+
+    with pytest.raises(ValueError, match="doesn't match a component"):
+        run_ensemble(CONFIG, [{"ocean.lambda0": 1.0}])
+
+    pd = pytest.importorskip("pandas")
+    path = tmp_path / "parameters.csv"
+    pd.DataFrame(parameters).to_csv(path, index=False)
+    from_file = run_ensemble(
+        CONFIG,
+        path,
+        exogenous={"Effective Radiative Forcing": erf()},
+        output="collection",
+    )
+    assert from_file[1].get_timeseries_by_name("Surface Temperature").at_time(
+        1855.0
+    ) == pytest.approx(0.25)
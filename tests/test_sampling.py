@@ -0,0 +1,34 @@
+import numpy as np
+import numpy.testing as npt
+import pytest
+
+from rscm.core import SamplingMethod, sample
+
+
+@pytest.mark.parametrize(
+    "method",
+    [
+        SamplingMethod.Random,
+        SamplingMethod.LatinHypercube,
+        SamplingMethod.Sobol,
+        SamplingMethod.Halton,
+    ],
+)
+def test_sample(method):
+    points = sample(method, 16, 3, seed=2)
+
+    assert points.shape == (16, 3)
+    assert np.all((points >= 0.0) & (points < 1.0))
+    npt.assert_array_equal(points, sample(method, 16, 3, seed=2))
+
+
+def test_latin_hypercube_stratified():
+    points = sample(SamplingMethod.LatinHypercube, 10, 2)
+
+    for d in range(2):
+        npt.assert_array_equal(np.sort(np.floor(points[:, d] * 10)), np.arange(10))
+
+
+def test_sobol_dimensions():
+    with pytest.raises(RuntimeError, match="at most 21 dimensions"):
+        sample(SamplingMethod.Sobol, 4, 22)
@@ -2,7 +2,15 @@ import numpy as np
 import numpy.testing as npt
 import pytest
 
-from rscm.core import InterpolationStrategy, TimeAxis, Timeseries
+from rscm.core import (
+    Calendar,
+    DisaggregationStrategy,
+    ExtrapolationPolicy,
+    InterpolationStrategy,
+    RegridMethod,
+    TimeAxis,
+    Timeseries,
+)
 
 
 class TestTimeAxis:
@@ -13,9 +21,59 @@ class TestTimeAxis:
         npt.assert_allclose(axis.bounds(), [2000.0, 2020.0, 2040.0, 2060.0])
         assert len(axis) == 3
 
-        # This is in rust debug format, but ok for now
-        exp = "TimeAxis { bounds: [2000.0, 2020.0, 2040.0, 2060.0], shape=[4], strides=[1], layout=CFcf (0xf), const ndim=1 }"  # noqa: E501
-        assert repr(axis) == exp
+        assert repr(axis) == "TimeAxis { len: 3, range: [2000.0, 2060.0) }"
+
+    def test_time_axis_labels(self):
+        axis = TimeAxis.from_values(np.asarray([2000.0, 2000.5, 2001.0]))
+
+        assert axis.labels() == ["2000-01", "2000-07", "2001-01"]
+        assert axis.labels(Calendar.NoLeap, include_day=True) == [
+            "2000-01-01",
+            "2000-07-02",
+            "2001-01-01",
+        ]
+
+    def test_time_axis_from_dates(self):
+        pd = pytest.importorskip("pandas")
+        expected = [2000.0, 2000 + 182 / 366, 2001.0]
+
+        dates = pd.to_datetime(["2000-01-01", "2000-07-01", "2001-01-01"])
+        npt.assert_allclose(TimeAxis.from_values(dates).values(), expected)
+        npt.assert_allclose(
+            TimeAxis.from_values(dates.to_numpy()).values(), expected
+        )
+
+    def test_time_axis_piecewise(self):
+        axis = TimeAxis.piecewise([(1750.0, 1850.0, 10.0), (1850.0, 1853.0, 1.0)])
+
+        assert len(axis) == 13
+        npt.assert_allclose(axis.bounds()[9:], [1840.0, 1850.0, 1851.0, 1852.0, 1853.0])
+
+        with pytest.raises(RuntimeError, match="Periods must be contiguous"):
+            TimeAxis.piecewise([(1750.0, 1850.0, 10.0), (1900.0, 2000.0, 1.0)])
+        with pytest.raises(RuntimeError, match="isn't a whole number of steps"):
+            TimeAxis.piecewise([(1750.0, 1855.0, 10.0)])
+
+    def test_time_axis_invalid(self):
+        with pytest.raises(ValueError, match="got 2010 followed by 2005"):
+            TimeAxis.from_values(np.asarray([2000.0, 2010.0, 2005.0]))
+        with pytest.raises(ValueError, match="strictly increasing"):
+            TimeAxis.from_bounds(np.asarray([2000.0, 2000.0]))
+        with pytest.raises(RuntimeError, match="at least 2 values"):
+            TimeAxis.from_values(np.asarray([2000.0]))
+
+    def test_time_axis_monthly(self):
+        axis = TimeAxis.monthly(2000, 2001)
+
+        assert len(axis) == 24
+        assert axis.bounds()[0] == 2000.0
+        assert axis.bounds()[-1] == 2002.0
+        assert axis.labels()[:3] == ["2000-01", "2000-02", "2000-03"]
+        assert axis.labels()[-1] == "2001-12"
+
+    def test_time_axis_daily(self):
+        assert len(TimeAxis.daily(2024, 2024)) == 366
+        assert len(TimeAxis.daily(2024, 2024, Calendar.NoLeap)) == 365
 
     def test_time_axis_immutable(self, time_axis):
         values = time_axis.values()
@@ -61,6 +119,37 @@ class TestTimeseries:
         ts.set(0, 42.0)
         assert ts.values()[0] == 42.0
 
+    def test_setitem(self, timeseries):
+        timeseries[0] = 1.0
+        timeseries[-1] = 2.0
+        timeseries[1:4] = np.array([3.0, 4.0, 5.0])
+        timeseries[4:6] = 6.0
+
+        npt.assert_allclose(timeseries.values()[:6], [1.0, 3.0, 4.0, 5.0, 6.0, 6.0])
+        assert timeseries.values()[-1] == 2.0
+
+        with pytest.raises(IndexError):
+            timeseries[100] = 1.0
+        with pytest.raises(IndexError, match="Index 100 is out of bounds"):
+            timeseries.set(100, 1.0)
+        with pytest.raises(ValueError, match="Cannot assign 2 values"):
+            timeseries[0:3] = np.array([1.0, 2.0])
+        with pytest.raises(ValueError, match="Only contiguous slices"):
+            timeseries[::2] = 1.0
+
+    def test_assign_from(self, timeseries):
+        other = Timeseries(
+            values=np.zeros(len(timeseries)),
+            time_axis=timeseries.time_axis,
+            units="mK",
+            interpolation_strategy=InterpolationStrategy.Linear,
+        )
+        other.assign_from(timeseries)
+        npt.assert_allclose(other.values(), timeseries.values() * 1000.0)
+
+        with pytest.raises(RuntimeError, match="Time axes differ"):
+            other.assign_from(timeseries.slice(1900.0, 1950.0))
+
     def test_create_invalid(self, time_axis):
         values = np.arange(0.0, 10.0)
         assert len(values) != len(time_axis)
@@ -95,3 +184,97 @@ class TestTimeseries:
         assert timeseries.at_time(1850) == 1850.0
         assert timeseries.at_time(1850.5) == 1850.5
         assert timeseries.at_time(2100.0) == 2100.0
+
+    def test_at_time_extrapolation_policy(self, timeseries):
+        timeseries.with_interpolation_strategy(
+            InterpolationStrategy.Linear, ExtrapolationPolicy.hold_nearest()
+        )
+        assert timeseries.at_time(1849) == 1850.0
+        assert timeseries.at_time(2100.0) == 2000.0
+
+        timeseries.with_interpolation_strategy(
+            InterpolationStrategy.Linear, ExtrapolationPolicy.clamp_to_range(0.0, 2050.0)
+        )
+        assert timeseries.at_time(2100.0) == 2050.0
+
+        timeseries.with_interpolation_strategy(
+            InterpolationStrategy.Linear, ExtrapolationPolicy.error()
+        )
+        with pytest.raises(RuntimeError, match="Extrapolation is not allowed"):
+            timeseries.at_time(2100.0)
+
+    def test_slice(self, timeseries):
+        window = timeseries.slice(1900.0, 1950.0)
+
+        npt.assert_allclose(window.values(), np.arange(1900.0, 1951.0, 5))
+        npt.assert_allclose(window.time_axis.bounds(), np.arange(1900.0, 1956.0, 5))
+        assert window.units == timeseries.units
+
+        with pytest.raises(RuntimeError, match="No values within the window"):
+            timeseries.slice(2100.0, 2200.0)
+
+    def test_summary(self, timeseries):
+        exp = (
+            'units: "K", len: 31, range: [1850.0, 2005.0), '
+            "first: 1850.0, last: 2000.0, nan: 0"
+        )
+        assert timeseries.summary() == exp
+        assert repr(timeseries) == f"<Timeseries {exp}>"
+
+    def test_disaggregate_into(self, timeseries):
+        annual = TimeAxis.from_values(np.arange(1850.0, 1860.0))
+
+        repeated = timeseries.disaggregate_into(annual, DisaggregationStrategy.Repeat)
+        npt.assert_allclose(repeated.values(), [1850.0] * 5 + [1855.0] * 5)
+
+        split = timeseries.disaggregate_into(annual, DisaggregationStrategy.Split)
+        npt.assert_allclose(split.values(), [370.0] * 5 + [371.0] * 5)
+
+    def test_regrid(self, timeseries):
+        decadal = TimeAxis.from_values(np.arange(1850.0, 1900.0, 10.0))
+
+        interpolated = timeseries.regrid(decadal)
+        npt.assert_allclose(interpolated.values(), decadal.values())
+
+        conservative = timeseries.regrid(decadal, RegridMethod.Conservative)
+        npt.assert_allclose(conservative.values(), decadal.values() + 2.5)
+
+        with pytest.raises(RuntimeError):
+            timeseries.regrid(
+                TimeAxis.from_values(np.asarray([2000.0, 2010.0])),
+                RegridMethod.Conservative,
+            )
+
+    def test_integrate(self, timeseries):
+        cumulative = timeseries.integrate()
+
+        # The value at the end of each step is used with next-value interpolation
+        times = timeseries.time_axis.values()
+        npt.assert_allclose(
+            cumulative.values(),
+            (times**2 - 1850.0**2) / 2 + 2.5 * (times - 1850.0),
+        )
+        assert cumulative.units == "K yr"
+
+        rate = cumulative.differentiate()
+        npt.assert_allclose(rate.values()[:-1], times[:-1] + 5.0)
+        assert rate.units == "K"
+
+    def test_differentiate(self, timeseries):
+        rate = timeseries.differentiate()
+
+        # The final value is extrapolated as constant
+        npt.assert_allclose(rate.values(), [1.0] * 30 + [0.0])
+        assert rate.units == "K / yr"
+
+    def test_csv_round_trip(self, timeseries):
+        csv = timeseries.to_csv()
+        assert csv.startswith("# units: K\n")
+
+        read = Timeseries.from_csv(csv)
+        npt.assert_allclose(read.values(), timeseries.values())
+        npt.assert_allclose(read.time_axis.bounds(), timeseries.time_axis.bounds())
+        assert read.units == "K"
+
+        with pytest.raises(RuntimeError, match='Expected a "time,value" header'):
+            Timeseries.from_csv("year,value\n2000,1.0\n2001,2.0\n")
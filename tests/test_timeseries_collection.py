@@ -1,4 +1,6 @@
-from rscm.core import TimeseriesCollection, VariableType
+import pytest
+
+from rscm.core import Compression, Precision, TimeseriesCollection, VariableType
 
 
 class TestTimeseriesCollection:
@@ -9,6 +11,10 @@ class TestTimeseriesCollection:
 
         assert repr(collection) == '<TimeseriesCollection names=["Other", "Test"]>'
 
+        summary = collection.summary().splitlines()
+        assert summary[0] == "TimeseriesCollection with 2 timeseries"
+        assert summary[1].startswith("  'Other' Endogenous: units: \"K\", len: 31")
+
     def test_timeseries(self, timeseries):
         collection = TimeseriesCollection()
         collection.add_timeseries("Test", timeseries, VariableType.Exogenous)
@@ -33,3 +39,97 @@ class TestTimeseriesCollection:
         ts_from_collection.set(0, 3.0)
 
         assert collection.get_timeseries_by_name("Test").at(0) == 1850.0
+
+    def test_merge_runs(self, timeseries):
+        run = TimeseriesCollection()
+        run.add_timeseries("Test", timeseries, VariableType.Endogenous)
+
+        merged = TimeseriesCollection()
+        merged.merge(run.with_run("1", "ssp119"))
+        merged.merge(run.with_run("2", "ssp585"))
+
+        assert merged.runs() == [("1", "ssp119"), ("2", "ssp585")]
+        assert merged.names() == ["Test", "Test"]
+        assert merged.get_timeseries_by_name("Test", run=("2", "ssp585")) is not None
+        assert merged.get_timeseries_by_name("Test", run=("3", "ssp585")) is None
+
+    def test_query(self, timeseries):
+        collection = TimeseriesCollection()
+        collection.add_timeseries("Emissions|CO2", timeseries, VariableType.Exogenous)
+        collection.add_timeseries("Emissions|CH4", timeseries, VariableType.Exogenous)
+        collection.add_timeseries(
+            "Surface Temperature", timeseries, VariableType.Endogenous
+        )
+
+        assert collection.filter("Emissions|*").names() == [
+            "Emissions|CH4",
+            "Emissions|CO2",
+        ]
+
+        collection.rename("Emissions|CH4", "Emissions|CH4|Fossil")
+        collection.remove("Emissions|CO2")
+        assert collection.metadata() == [
+            ("Emissions|CH4|Fossil", "K", VariableType.Exogenous, None),
+            ("Surface Temperature", "K", VariableType.Endogenous, None),
+        ]
+
+        with pytest.raises(KeyError, match="not found"):
+            collection.remove("Emissions|CO2")
+        with pytest.raises(ValueError, match="already exists"):
+            collection.rename("Emissions|CH4|Fossil", "Surface Temperature")
+
+    def test_add_duplicate(self, timeseries):
+        collection = TimeseriesCollection()
+        collection.add_timeseries("Test", timeseries, VariableType.Exogenous)
+
+        with pytest.raises(ValueError, match="Timeseries 'Test' already exists"):
+            collection.add_timeseries("Test", timeseries, VariableType.Exogenous)
+
+        timeseries.set(0, 2.0)
+        collection.add_timeseries(
+            "Test", timeseries, VariableType.Exogenous, overwrite=True
+        )
+        assert collection.get_timeseries_by_name("Test").at(0) == 2.0
+
+    def test_save_and_load(self, timeseries):
+        collection = TimeseriesCollection()
+        collection.add_timeseries("Test", timeseries, VariableType.Exogenous)
+
+        archive = collection.save()
+        loaded = TimeseriesCollection.load(archive)
+        assert loaded.names() == ["Test"]
+        assert loaded.get_timeseries_by_name("Test").at(0) == 1850.0
+
+        reduced = collection.save(Precision.Float32)
+        assert b'precision = "Float32"' in reduced
+        assert TimeseriesCollection.load(reduced).names() == ["Test"]
+
+        compressed = collection.save(compression=Compression.Zstd)
+        assert len(compressed) < len(archive)
+        assert TimeseriesCollection.load(compressed).names() == ["Test"]
+
+        with pytest.raises(RuntimeError, match="Invalid archive"):
+            TimeseriesCollection.load(b"not an archive")
+
+    def test_preferred_units(self, timeseries):
+        collection = TimeseriesCollection()
+        collection.add_timeseries("Test", timeseries, VariableType.Exogenous)
+
+        converted = collection.convert_units({"Test": "mK"})
+        assert converted.get_timeseries_by_name("Test").units == "mK"
+        assert collection.get_timeseries_by_name("Test").units == "K"
+
+        loaded = TimeseriesCollection.load(collection.save(units={"Test": "mK"}))
+        assert loaded.get_timeseries_by_name("Test").at(0) == 1850.0 * 1000.0
+
+        with pytest.raises(RuntimeError, match="Could not convert Test"):
+            collection.save(units={"Test": "GtC"})
+
+    def test_repr_html(self, timeseries):
+        collection = TimeseriesCollection()
+        collection.add_timeseries("Test", timeseries, VariableType.Exogenous)
+
+        html = collection._repr_html_()
+        assert html.startswith("<div><strong>TimeseriesCollection</strong>")
+        assert "<td>Test</td>" in html
+        assert "<td>K</td>" in html
@@ -1,4 +1,7 @@
-from rscm import TwoLayerComponentBuilder
+import numpy as np
+
+from rscm import TwoLayerComponentBuilder, diagnose_forcing, run_two_layer
+from rscm.core import InterpolationStrategy, TimeAxis, Timeseries
 
 
 def test_create_component():
@@ -12,5 +15,71 @@ def test_create_component():
             heat_capacity_surface=1,
         )
     ).build()
-    res = component.solve(2000, 2010, {"Effective Radiative Forcing": 12})
+    res = component.solve(
+        2000,
+        2010,
+        {
+            "Effective Radiative Forcing": 12,
+            "Surface Temperature": 0.0,
+            "Deep Ocean Temperature": 0.0,
+            "Ocean Heat Content": 0.0,
+        },
+    )
     assert isinstance(res, dict)
+    assert sorted(res) == [
+        "Deep Ocean Temperature",
+        "Ocean Heat Content",
+        "Surface Temperature",
+    ]
+
+
+def test_run_two_layer():
+    erf = Timeseries(
+        np.asarray([1.0, 1.5, 2.0, 2.0]),
+        TimeAxis.from_values(np.asarray([1848.0, 1849.0, 1850.0, 1851.0])),
+        "W / m^2",
+        InterpolationStrategy.Previous,
+    )
+
+    results = run_two_layer(
+        erf,
+        dict(
+            lambda0=0.5,
+            a=0.01,
+            efficacy=0.5,
+            eta=0.1,
+            heat_capacity_surface=1.0,
+            heat_capacity_deep=100.0,
+        ),
+    )
+
+    temperature = results.get_timeseries_by_name("Surface Temperature").values()
+    assert temperature[0] == 0.0
+    # The state is carried between steps so the temperature keeps rising
+    assert (np.diff(temperature) > 0).all()
+    assert results.get_timeseries_by_name("Ocean Heat Content") is not None
+
+
+def test_diagnose_forcing():
+    temperature = Timeseries(
+        np.asarray([0.0, 0.1, 0.3, 0.4]),
+        TimeAxis.from_values(np.asarray([1848.0, 1849.0, 1850.0, 1851.0])),
+        "K",
+        InterpolationStrategy.Linear,
+    )
+
+    results = diagnose_forcing(
+        temperature,
+        dict(
+            lambda0=1.0,
+            a=0.0,
+            efficacy=1.0,
+            eta=0.5,
+            heat_capacity_surface=8.0,
+            heat_capacity_deep=100.0,
+        ),
+    )
+
+    erf = results.get_timeseries_by_name("Effective Radiative Forcing")
+    np.testing.assert_allclose(erf.values()[0], 0.8)
+    assert np.isnan(erf.values()[-1])